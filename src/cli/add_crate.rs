@@ -0,0 +1,43 @@
+use anyhow::{Context, Result};
+use std::env;
+use tracing::info;
+
+use crate::core::cargo;
+use crate::core::tree::EntryKind;
+use crate::core::workspace::{self, WorkspaceKind};
+use crate::git::commands;
+
+/// Resolve a crate of the Cargo workspace to the sparse checkout patterns
+/// `cargo build -p <name>` needs: the crate and its transitive path
+/// dependencies, plus the manifests and target roots cargo reads to load the
+/// other members. The manifests are read from git at HEAD, so they need not
+/// be checked out.
+pub fn crate_paths(name: &str) -> Result<Vec<String>> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let entries = commands::list_tree_structure(&current_dir, "HEAD")
+        .context("Failed to list repository tree")?;
+    let read_file = |path: &str| commands::show_file(&current_dir, "HEAD", path).ok();
+
+    let members: Vec<_> = workspace::detect_projects(&entries, read_file)
+        .into_iter()
+        .filter(|project| project.kind == WorkspaceKind::Cargo)
+        .collect();
+    let project = members
+        .iter()
+        .find(|project| project.matches_name(name))
+        .with_context(|| {
+            format!(
+                "Unknown crate '{}'. Run `git-partial projects` to list the workspace crates.",
+                name
+            )
+        })?;
+    info!("Resolving crate {} in {}", project.name, project.dir);
+
+    let member_dirs: Vec<String> = members.iter().map(|member| member.dir.clone()).collect();
+    let selection = cargo::select_crate(&project.dir, &member_dirs, read_file)?;
+    Ok(selection.patterns(|file| {
+        entries
+            .iter()
+            .any(|entry| entry.kind == EntryKind::Blob && entry.path == file)
+    }))
+}
@@ -0,0 +1,52 @@
+use anyhow::{Context, Result};
+use std::env;
+use tracing::info;
+
+use crate::core::tree::EntryKind;
+use crate::core::workspace::{self, WorkspaceKind};
+use crate::core::{gradle, maven};
+use crate::git::commands;
+
+/// Resolve a module of a Gradle or Maven build to the sparse checkout
+/// patterns building it needs: the module and the modules it depends on,
+/// transitively, plus the build files the tool reads to configure the
+/// others. The build files are read from git at HEAD, so they need not be
+/// checked out.
+pub fn module_paths(name: &str) -> Result<Vec<String>> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let entries = commands::list_tree_structure(&current_dir, "HEAD")
+        .context("Failed to list repository tree")?;
+    let read_file = |path: &str| commands::show_file(&current_dir, "HEAD", path).ok();
+
+    let projects = workspace::detect_projects(&entries, read_file);
+    let project = projects
+        .iter()
+        .filter(|project| matches!(project.kind, WorkspaceKind::Gradle | WorkspaceKind::Maven))
+        .find(|project| project.matches_name(name))
+        .with_context(|| {
+            format!(
+                "Unknown module '{}'. Run `git-partial projects` to list the build's modules.",
+                name
+            )
+        })?;
+    info!(
+        "Resolving {} module {} in {}",
+        project.kind, project.name, project.dir
+    );
+
+    let modules: Vec<_> = projects
+        .iter()
+        .filter(|module| module.kind == project.kind)
+        .cloned()
+        .collect();
+    let selection = if project.kind == WorkspaceKind::Gradle {
+        gradle::select_module(&project.dir, &modules, read_file)?
+    } else {
+        maven::select_module(&project.dir, &modules, read_file)?
+    };
+    Ok(selection.patterns(|file| {
+        entries
+            .iter()
+            .any(|entry| entry.kind == EntryKind::Blob && entry.path == file)
+    }))
+}
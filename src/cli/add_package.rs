@@ -0,0 +1,47 @@
+use anyhow::{Context, Result};
+use std::env;
+use tracing::info;
+
+use crate::core::npm;
+use crate::core::tree::EntryKind;
+use crate::core::workspace::{self, WorkspaceKind};
+use crate::git::commands;
+
+/// Resolve a package of the JavaScript workspace to the sparse checkout
+/// patterns installing and building it needs: the package and the workspace
+/// packages it depends on, transitively, plus the root configuration and
+/// lockfile and the other packages' manifests. The manifests are read from
+/// git at HEAD, so they need not be checked out.
+pub fn package_paths(name: &str) -> Result<Vec<String>> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let entries = commands::list_tree_structure(&current_dir, "HEAD")
+        .context("Failed to list repository tree")?;
+    let read_file = |path: &str| commands::show_file(&current_dir, "HEAD", path).ok();
+
+    let packages: Vec<_> = workspace::detect_projects(&entries, read_file)
+        .into_iter()
+        .filter(|project| {
+            matches!(
+                project.kind,
+                WorkspaceKind::Pnpm | WorkspaceKind::Npm | WorkspaceKind::Turborepo
+            )
+        })
+        .collect();
+    let project = packages
+        .iter()
+        .find(|project| project.matches_name(name))
+        .with_context(|| {
+            format!(
+                "Unknown package '{}'. Run `git-partial projects` to list the workspace packages.",
+                name
+            )
+        })?;
+    info!("Resolving package {} in {}", project.name, project.dir);
+
+    let selection = npm::select_package(&project.dir, &packages, read_file)?;
+    Ok(selection.patterns(|file| {
+        entries
+            .iter()
+            .any(|entry| entry.kind == EntryKind::Blob && entry.path == file)
+    }))
+}
@@ -1,13 +1,112 @@
 use anyhow::{Context, Result};
-use log::{debug, info};
+use std::collections::BTreeSet;
 use std::env;
+use std::fs;
+use std::path::Path;
+use tracing::{debug, info, warn};
 
+use crate::cli::{history, policy, root_mount, tree};
+use crate::core::companions::{CompanionRules, COMPANIONS_FILE};
+use crate::core::config;
+use crate::core::error::PartialError;
 use crate::core::metadata::RepositoryMetadata;
+use crate::core::path_selector;
+use crate::core::pending::PendingFetches;
+use crate::core::symlinks::{self, OutsideLink};
+use crate::core::tree::{self as core_tree, EntryKind, TreeEntry};
 use crate::git::commands;
 use crate::git::sparse;
+use crate::utils::{disk, prompt};
 
-/// Add new paths to the sparse checkout
-pub async fn add_new_paths(paths: &[String]) -> Result<()> {
+/// Git config key for the download size above which `add-paths` asks first.
+/// Accepts git's `k`, `m` and `g` suffixes.
+const CONFIRM_THRESHOLD_KEY: &str = "gitpartial.addConfirmThreshold";
+
+/// Download size above which `add-paths` asks first, unless configured
+const DEFAULT_CONFIRM_THRESHOLD: u64 = 1024 * 1024 * 1024;
+
+/// Git config key naming a local companion rules file, which takes
+/// precedence over the one committed to the repository
+const COMPANIONS_PATH_KEY: &str = "gitpartial.companions";
+
+/// Translate regular expressions over the paths at HEAD into the concrete
+/// sparse patterns selecting the files they match
+pub fn regex_paths(sources: &[String]) -> Result<Vec<String>> {
+    if sources.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let regexes = core_tree::compile_regexes(sources)?;
+    let entries = commands::list_tree_structure(&current_dir, "HEAD")
+        .context("Failed to list repository files")?;
+
+    let unmatched: Vec<String> = sources
+        .iter()
+        .zip(&regexes)
+        .filter(|(_, regex)| {
+            !entries
+                .iter()
+                .any(|entry| entry.kind == EntryKind::Blob && regex.is_match(&entry.path))
+        })
+        .map(|(source, _)| source.clone())
+        .collect();
+    if !unmatched.is_empty() {
+        return Err(PartialError::PatternMatchedNothing(unmatched).into());
+    }
+
+    let paths = core_tree::regex_selection(&entries, &regexes);
+    info!("Regexes selected {} patterns", paths.len());
+    Ok(paths)
+}
+
+/// The companions the repository's rules add with `paths` (see
+/// [`CompanionRules`]), resolved and limited to those that match files at
+/// HEAD and are not checked out yet. The rules come from the file named by
+/// `gitpartial.companions`, else `.gitpartial-companions.toml` at HEAD.
+pub fn companion_paths(paths: &[String]) -> Result<Vec<String>> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let content = match commands::config_value(&current_dir, COMPANIONS_PATH_KEY) {
+        Some(path) => fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read companion rules {}", path))?,
+        None => match commands::show_file(&current_dir, "HEAD", COMPANIONS_FILE) {
+            Ok(content) => content,
+            Err(_) => return Ok(Vec::new()),
+        },
+    };
+    let companions = CompanionRules::parse(&content)?.companions(paths);
+    if companions.is_empty() {
+        return Ok(companions);
+    }
+
+    let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+    let companions: Vec<String> = config::substitute_variables(&companions, &current_dir)?
+        .into_iter()
+        .filter(|companion| !metadata.checked_out_paths.contains(companion))
+        .collect();
+    let files = commands::list_tree_files(&current_dir, "HEAD")
+        .context("Failed to list repository files")?;
+    let unmatched = path_selector::unmatched_patterns(&companions, &files);
+    for companion in &unmatched {
+        debug!("Companion {} matches no files, skipping", companion);
+    }
+    Ok(companions
+        .into_iter()
+        .filter(|companion| !unmatched.contains(companion))
+        .collect())
+}
+
+/// Add new paths to the sparse checkout.
+/// Shows how much the new paths would download and asks for confirmation
+/// over the configured threshold; with `assume_yes` the estimate is only logged.
+/// Returns the messages for the user rather than printing them, so the
+/// dashboard can show them.
+pub async fn add_new_paths(
+    paths: &[String],
+    assume_yes: bool,
+    override_policy: bool,
+    force: bool,
+) -> Result<String> {
     info!("Adding new paths to sparse checkout");
     debug!("New paths: {:?}", paths);
 
@@ -18,41 +117,248 @@ pub async fn add_new_paths(paths: &[String]) -> Result<()> {
 
     // Check if repo is using sparse checkout (can be simplified by checking metadata)
     if !sparse::is_sparse_checkout()? {
-        anyhow::bail!(
-            "This repository is not using sparse checkout. Did you clone it with git-partial?"
-        );
+        return Err(PartialError::SparseCheckoutDisabled.into());
     }
 
+    // Refuse patterns that would not check out anything. The cached upstream
+    // tree answers most checks; only patterns it misses are looked up at HEAD.
+    let mut unmatched = match tree::upstream_tree(&current_dir) {
+        Ok((_, entries)) => {
+            let files: Vec<String> = entries
+                .into_iter()
+                .filter(|entry| entry.kind == EntryKind::Blob)
+                .map(|entry| entry.path)
+                .collect();
+            path_selector::unmatched_patterns(paths, &files)
+        }
+        Err(_) => paths.to_vec(),
+    };
+    let mut paths = paths.to_vec();
+    if !unmatched.is_empty() {
+        let tree_files = commands::list_tree_files(&current_dir, "HEAD")
+            .context("Failed to list repository files")?;
+        unmatched = path_selector::unmatched_patterns(&unmatched, &tree_files);
+        // Accented names may be stored in the other Unicode normal form
+        unmatched.retain(|pattern| {
+            let variant = path_selector::unicode_variants(pattern)
+                .into_iter()
+                .find(|variant| {
+                    path_selector::unmatched_patterns(std::slice::from_ref(variant), &tree_files)
+                        .is_empty()
+                });
+            match variant {
+                Some(variant) => {
+                    debug!("Using the {:?} form of {:?}", variant, pattern);
+                    for path in paths.iter_mut().filter(|path| *path == pattern) {
+                        *path = variant.clone();
+                    }
+                    false
+                }
+                None => true,
+            }
+        });
+    }
+    if !unmatched.is_empty() {
+        return Err(PartialError::PatternMatchedNothing(unmatched).into());
+    }
+    let paths = paths.as_slice();
+
     // Determine the full set of paths (existing + new)
     let mut final_paths = metadata.checked_out_paths.clone();
-    let mut added_new = false;
+    let mut new_paths = Vec::new();
     for path in paths {
         if final_paths.insert(path.clone()) {
-            added_new = true;
+            new_paths.push(path.clone());
         }
     }
+    if new_paths.is_empty() {
+        info!("No new paths to add. Sparse checkout and metadata remain unchanged.");
+        return Ok(String::new());
+    }
+    let final_paths_vec: Vec<String> = final_paths.iter().cloned().collect();
 
-    // Only update sparse checkout and metadata if new paths were actually added
-    if added_new {
-        let final_paths_vec: Vec<String> = final_paths.iter().cloned().collect();
+    policy::enforce(
+        &current_dir,
+        &metadata.remote_url,
+        &final_paths_vec,
+        override_policy,
+    )
+    .await?;
+    policy::check_limits(&current_dir, &metadata.remote_url, &final_paths_vec, force).await?;
 
-        // Set updated paths in sparse-checkout
-        commands::set_sparse_checkout(&current_dir, &final_paths_vec)
-            .context("Failed to update sparse checkout paths")?;
+    // Ask before large downloads
+    let mut output = String::new();
+    if !confirm_download(&current_dir, &metadata, paths, assume_yes, &mut output).await? {
+        output.push_str("Aborted.\n");
+        return Ok(output);
+    }
+
+    // Set updated paths in sparse-checkout. Without network the blobs
+    // cannot be fetched, so only the patterns are updated and the
+    // checkout is queued for `sync`
+    match commands::set_sparse_checkout(&current_dir, &final_paths_vec) {
+        Ok(()) => PendingFetches::clear(&current_dir)?,
+        Err(e) if commands::is_promisor_fetch_failure(&e) => {
+            warn!("Remote unreachable, queueing the checkout: {:#}", e);
+            commands::write_sparse_patterns(&current_dir, &final_paths_vec)?;
+            let mut pending = PendingFetches::load(&current_dir)?;
+            pending.push(&new_paths);
+            pending.save(&current_dir)?;
+            output.push_str(&format!(
+                "Offline: queued {} path(s). Run 'git-partial sync' when back online.\n",
+                new_paths.len()
+            ));
+        }
+        Err(e) => return Err(e.context("Failed to update sparse checkout paths")),
+    }
+
+    // Update metadata object
+    metadata.checked_out_paths = final_paths;
+    // Optionally update last commit if needed, though add-paths might not change it
+
+    // Save updated metadata
+    metadata
+        .save(&current_dir)
+        .context("Failed to save updated metadata")?;
+    history::record(&current_dir, &new_paths, &[]);
+    root_mount::refresh_links(&current_dir, &metadata);
 
-        // Update metadata object
-        metadata.checked_out_paths = final_paths;
-        // Optionally update last commit if needed, though add-paths might not change it
+    info!("Successfully added new paths and updated metadata");
+    Ok(output)
+}
 
-        // Save updated metadata
-        metadata
-            .save(&current_dir)
-            .context("Failed to save updated metadata")?;
+/// Look for checked out symlinks whose targets are outside the sparse paths.
+/// With `follow`, the targets are added, again for links among the added
+/// files; otherwise the links are reported.
+pub async fn check_symlinks(
+    follow: bool,
+    assume_yes: bool,
+    override_policy: bool,
+    force: bool,
+) -> Result<()> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let mut added = BTreeSet::new();
+    loop {
+        let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+        let patterns: Vec<String> = metadata.checked_out_paths.iter().cloned().collect();
+        let outside: Vec<OutsideLink> = links_outside(&current_dir, &patterns)?
+            .into_iter()
+            .filter(|link| !added.contains(&link.pattern))
+            .collect();
+        if outside.is_empty() {
+            return Ok(());
+        }
+        if !follow {
+            eprintln!("Warning: symlinks point outside the sparse paths:");
+            for link in &outside {
+                eprintln!("  {} -> {}", link.link, link.target);
+            }
+            eprintln!(
+                "  Add their targets with 'git-partial add-paths --follow-symlinks <path>...'."
+            );
+            return Ok(());
+        }
 
-        info!("Successfully added new paths and updated metadata");
+        let mut targets: Vec<String> = outside.into_iter().map(|link| link.pattern).collect();
+        targets.sort();
+        targets.dedup();
+        eprintln!("Adding symlink targets: {:?}", targets);
+        added.extend(targets.iter().cloned());
+        print!(
+            "{}",
+            add_new_paths(&targets, assume_yes, override_policy, force).await?
+        );
+    }
+}
+
+/// Checked out symlinks pointing at files `patterns` do not cover.
+/// Targets are read from the working tree, where git writes them as plain
+/// files when the file system has no symlinks.
+fn links_outside(
+    repo_path: &Path,
+    patterns: &[String],
+) -> Result<Vec<OutsideLink>> {
+    let links: Vec<(String, String)> = commands::list_symlinks(repo_path, "HEAD")
+        .context("Failed to list symlinks")?
+        .into_iter()
+        .filter_map(|link| {
+            let path = repo_path.join(&link);
+            let target = match fs::symlink_metadata(&path).ok()? {
+                meta if meta.file_type().is_symlink() => {
+                    fs::read_link(&path).ok()?.to_string_lossy().into_owned()
+                }
+                _ => fs::read_to_string(&path).ok()?,
+            };
+            Some((link, target))
+        })
+        .collect();
+    if links.is_empty() {
+        return Ok(Vec::new());
+    }
+    let entries = commands::list_tree_structure(repo_path, "HEAD")
+        .context("Failed to list repository files")?;
+    Ok(symlinks::outside_targets(&links, &entries, patterns))
+}
+
+/// Show the files and bytes the new paths would bring in and ask when it
+/// exceeds the threshold. Sizes come from the promisor remote since listing
+/// them locally would download the blobs; without it only files are counted.
+/// The estimate shown without asking is added to `output`.
+async fn confirm_download(
+    repo_path: &Path,
+    metadata: &RepositoryMetadata,
+    paths: &[String],
+    assume_yes: bool,
+    output: &mut String,
+) -> Result<bool> {
+    if metadata.filter_ignored {
+        // The server sent every object at clone time; nothing is downloaded
+        output.push_str(
+            "The server ignored the partial clone filter; the new paths are already local.\n",
+        );
+        return Ok(true);
+    }
+    let head = commands::get_head_commit(repo_path).context("Failed to get HEAD commit")?;
+    let remote_entries = tree::remote_tree_entries(&metadata.remote_url, &head).await;
+    let sizes_known = remote_entries.is_some();
+    let entries = match remote_entries {
+        Some(entries) => entries,
+        None => commands::list_tree_files(repo_path, "HEAD")
+            .context("Failed to list repository files")?
+            .into_iter()
+            .map(|path| TreeEntry {
+                path,
+                kind: EntryKind::Blob,
+                size: None,
+            })
+            .collect(),
+    };
+
+    let existing: Vec<String> = metadata.checked_out_paths.iter().cloned().collect();
+    let estimate = core_tree::estimate_added(&entries, &existing, paths);
+    let summary = if sizes_known {
+        format!(
+            "The new paths add {} file(s), about {} to download.",
+            estimate.files,
+            disk::format_size(estimate.bytes)
+        )
     } else {
-        info!("No new paths to add. Sparse checkout and metadata remain unchanged.");
+        format!(
+            "The new paths add {} file(s) (download size unknown).",
+            estimate.files
+        )
+    };
+    info!("{}", summary);
+    if assume_yes {
+        return Ok(true);
     }
 
-    Ok(())
+    let threshold =
+        commands::config_int(repo_path, CONFIRM_THRESHOLD_KEY).unwrap_or(DEFAULT_CONFIRM_THRESHOLD);
+    if estimate.bytes > threshold {
+        prompt::confirm(&summary, false)
+    } else {
+        output.push_str(&format!("{}\n", summary));
+        Ok(true)
+    }
 }
@@ -0,0 +1,117 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::path::Path;
+use std::process::Command;
+use tracing::{debug, info, warn};
+
+use crate::core::project_graph::{self, ProjectGraph};
+use crate::core::tree::EntryKind;
+use crate::core::workspace::{self, WorkspaceKind};
+use crate::git::commands;
+
+/// Resolve a project of an Nx or Turborepo workspace to sparse checkout
+/// patterns. With `with_deps`, the projects it depends on are included,
+/// transitively, along with the files the tool reads to build its project
+/// graph. The graph comes from `nx graph` when Nx is installed, and
+/// otherwise from the manifests at HEAD.
+pub fn project_graph_paths(
+    name: &str,
+    with_deps: bool,
+) -> Result<Vec<String>> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let entries = commands::list_tree_structure(&current_dir, "HEAD")
+        .context("Failed to list repository tree")?;
+    let read_file = |path: &str| commands::show_file(&current_dir, "HEAD", path).ok();
+
+    let kinds = workspace::detect_kinds(&entries);
+    let is_nx = kinds.contains(&WorkspaceKind::Nx);
+    if !is_nx && !kinds.contains(&WorkspaceKind::Turborepo) {
+        anyhow::bail!(
+            "No Nx or Turborepo workspace detected. Use `add-crate`, `add-package` or `add-module` for other workspaces."
+        );
+    }
+
+    let projects: Vec<_> = workspace::detect_projects(&entries, read_file)
+        .into_iter()
+        .filter(|project| {
+            matches!(
+                project.kind,
+                WorkspaceKind::Nx
+                    | WorkspaceKind::Turborepo
+                    | WorkspaceKind::Pnpm
+                    | WorkspaceKind::Npm
+            )
+        })
+        .collect();
+    let project = projects
+        .iter()
+        .find(|project| project.matches_name(name))
+        .with_context(|| {
+            format!(
+                "Unknown project '{}'. Run `git-partial projects` to list available projects.",
+                name
+            )
+        })?;
+    if !with_deps {
+        return Ok(vec![project.pattern()]);
+    }
+    info!("Resolving project {} in {}", project.name, project.dir);
+
+    let graph = match is_nx.then(|| query_nx_graph(&current_dir)).flatten() {
+        Some(graph) => graph,
+        None => {
+            info!("Resolving the project graph from manifests");
+            ProjectGraph::from_manifests(&projects, read_file)
+        }
+    };
+    let selection = project_graph::select_project(&project.dir, &graph, &projects);
+    Ok(selection.patterns(|file| {
+        entries
+            .iter()
+            .any(|entry| entry.kind == EntryKind::Blob && entry.path == file)
+    }))
+}
+
+/// Asks Nx for the project graph, which also knows the dependencies its
+/// plugins infer from source imports. Returns `None` if Nx is not
+/// installed or the command fails.
+fn query_nx_graph(repo_path: &Path) -> Option<ProjectGraph> {
+    let local = repo_path.join("node_modules/.bin/nx");
+    let program = if local.is_file() {
+        local.to_string_lossy().into_owned()
+    } else {
+        "nx".to_string()
+    };
+
+    info!("Running {} graph --file=stdout", program);
+    let output = match Command::new(&program)
+        .current_dir(repo_path)
+        .args(["graph", "--file=stdout"])
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) => {
+            debug!("{} is not available: {}", program, e);
+            return None;
+        }
+    };
+
+    if !output.status.success() {
+        warn!(
+            "nx graph failed, falling back to manifests: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return None;
+    }
+
+    match ProjectGraph::parse_nx_graph(&String::from_utf8_lossy(&output.stdout)) {
+        Ok(graph) => Some(graph),
+        Err(e) => {
+            warn!(
+                "Unreadable nx graph output, falling back to manifests: {:#}",
+                e
+            );
+            None
+        }
+    }
+}
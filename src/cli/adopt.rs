@@ -0,0 +1,123 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::path::Path;
+use tracing::{debug, info, warn};
+
+use crate::cli::history;
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::path_selector;
+use crate::core::registry;
+use crate::core::url_rewrite;
+use crate::git::commands;
+
+/// Start managing a repository that already uses partial clone and sparse
+/// checkout, such as one set up by `scalar clone`, without recloning it
+pub async fn adopt_repository() -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+
+    if RepositoryMetadata::exists(&current_dir) {
+        anyhow::bail!("This repository is already managed by git-partial");
+    }
+
+    let kind = detect_kind(&current_dir);
+    info!("Adopting {} repository", kind);
+
+    if commands::config_value(&current_dir, "core.sparseCheckout").as_deref() != Some("true") {
+        anyhow::bail!(
+            "This repository is not using sparse checkout. Use `git-partial clone` to create a partial clone instead."
+        );
+    }
+    let is_partial_clone =
+        commands::config_value(&current_dir, "remote.origin.promisor").as_deref() == Some("true")
+            || commands::config_value(&current_dir, "extensions.partialClone").is_some();
+    if !is_partial_clone {
+        anyhow::bail!(
+            "This repository is not a partial clone (no promisor remote configured). Use `git-partial import` to take over a plain sparse checkout."
+        );
+    }
+
+    let paths = current_patterns(&current_dir)?;
+    let metadata = take_over(&current_dir, &paths)?;
+
+    Ok(format!(
+        "Adopted {} repository with {} path(s) from {}\n",
+        kind,
+        metadata.checked_out_paths.len(),
+        metadata.remote_url
+    ))
+}
+
+/// Reads the current sparse patterns, converting cone mode directories
+fn current_patterns(repo_path: &Path) -> Result<Vec<String>> {
+    let listed =
+        commands::sparse_checkout_list(repo_path).context("Failed to list sparse checkout")?;
+
+    let cone =
+        commands::config_value(repo_path, "core.sparseCheckoutCone").as_deref() == Some("true");
+    if !cone {
+        return Ok(listed);
+    }
+
+    Ok(path_selector::cone_to_patterns(
+        &listed,
+        &root_files(repo_path)?,
+    ))
+}
+
+/// Lists the files at the repository root, which cone mode always includes
+pub fn root_files(repo_path: &Path) -> Result<Vec<String>> {
+    Ok(commands::list_tree_files(repo_path, "HEAD")
+        .context("Failed to list repository files")?
+        .into_iter()
+        .filter(|path| !path.contains('/'))
+        .collect())
+}
+
+/// Writes metadata for an existing sparse checkout so git-partial manages it
+pub fn take_over(
+    repo_path: &Path,
+    paths: &[String],
+) -> Result<RepositoryMetadata> {
+    debug!("Taking over paths: {:?}", paths);
+    if paths.is_empty() {
+        anyhow::bail!("The sparse checkout has no patterns to import");
+    }
+
+    let remote_url = commands::run_git_command_in_dir(repo_path, &["remote", "get-url", "origin"])
+        .context("Failed to read the URL of remote 'origin'")?;
+    // Record the repository as written before URL rewrites
+    let rules = commands::url_rewrites(Some(repo_path));
+    let mut metadata = RepositoryMetadata::new(url_rewrite::canonical(&remote_url, &rules));
+    metadata.add_paths(paths);
+    let head_commit = commands::get_head_commit(repo_path).context("Failed to get HEAD commit")?;
+    metadata.set_last_commit(&head_commit);
+    metadata
+        .save(repo_path)
+        .context("Failed to save metadata")?;
+    history::record(repo_path, paths, &[]);
+    if let Err(e) = registry::register(repo_path) {
+        warn!("Failed to register the repository: {:#}", e);
+    }
+    Ok(metadata)
+}
+
+/// Names the tool that most likely set up the repository
+fn detect_kind(repo_path: &Path) -> &'static str {
+    if commands::config_value(repo_path, "core.gvfs").is_some() {
+        return "GVFS";
+    }
+
+    // `scalar register` records enlistments in the global config
+    let toplevel = commands::run_git_command_in_dir(repo_path, &["rev-parse", "--show-toplevel"])
+        .unwrap_or_default();
+    let scalar_repos = commands::run_git_command_in_dir(
+        repo_path,
+        &["config", "--global", "--get-all", "scalar.repo"],
+    )
+    .unwrap_or_default();
+    if !toplevel.is_empty() && scalar_repos.lines().any(|repo| repo.trim() == toplevel) {
+        return "Scalar";
+    }
+
+    "partial clone"
+}
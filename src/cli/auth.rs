@@ -0,0 +1,69 @@
+use anyhow::{Context, Result};
+use std::env;
+
+use crate::core::metadata::RepositoryMetadata;
+use crate::remote::auth::{self, AuthHost};
+use crate::remote::RemoteUrl;
+
+/// Report which token git-partial would use for a repository URL, or for the
+/// clone's remote when none is given
+pub fn auth_status(url: Option<&str>) -> Result<String> {
+    let url = match url {
+        Some(url) => url.to_string(),
+        None => {
+            let current_dir = env::current_dir().context("Failed to get current directory")?;
+            RepositoryMetadata::load(&current_dir)
+                .context("Failed to load metadata")?
+                .remote_url
+        }
+    };
+    let host = RemoteUrl::parse(&url)
+        .map(|remote| remote.host)
+        .with_context(|| format!("'{}' is not a remote URL", url))?;
+    let Some(kind) = AuthHost::from_host(&host) else {
+        return Ok(format!(
+            "{}: no built-in authentication; git's credential helpers are used\n",
+            host
+        ));
+    };
+    Ok(match auth::find_token(kind, &host) {
+        Some(token) => format!("{}: token from {}\n", host, token.source),
+        None => {
+            let hint = match kind {
+                AuthHost::GitHub => {
+                    "set GITHUB_TOKEN, run 'gh auth login', or 'git-partial auth login'"
+                }
+                AuthHost::GitLab => "set GITLAB_TOKEN or run 'glab auth login'",
+                AuthHost::AzureDevOps => "set AZURE_DEVOPS_PAT to a personal access token",
+            };
+            format!("{}: no token found; {}\n", host, hint)
+        }
+    })
+}
+
+/// Log in to a GitHub host with the OAuth device flow and store the token in
+/// git's credential helper
+pub async fn login(
+    host: &str,
+    client_id: Option<&str>,
+) -> Result<()> {
+    if AuthHost::from_host(host) != Some(AuthHost::GitHub) {
+        anyhow::bail!(
+            "Device login is only supported for GitHub hosts; '{}' is not one",
+            host
+        );
+    }
+    let client_id = auth::github_client_id(client_id).context(
+        "No OAuth app to log in with; pass --client-id or set GIT_PARTIAL_GITHUB_CLIENT_ID",
+    )?;
+
+    let code = auth::request_device_code(host, &client_id).await?;
+    println!(
+        "Open {} and enter the code {}",
+        code.verification_uri, code.user_code
+    );
+    let token = auth::wait_for_token(host, &client_id, &code).await?;
+    auth::store_credential(host, &token)?;
+    println!("Logged in to {}.", host);
+    Ok(())
+}
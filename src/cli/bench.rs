@@ -0,0 +1,204 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Instant;
+use tracing::info;
+
+use crate::core::config::{self, GlobalConfig};
+use crate::utils::interrupt::RemoveOnInterrupt;
+
+/// What `bench` measures, and with which options
+#[derive(Debug, Clone, Default)]
+pub struct BenchOptions {
+    pub repo_url: String,
+    /// Paths the clone starts with
+    pub paths: Vec<String>,
+    /// Paths added after the clone; the add-paths step is skipped without any
+    pub add_paths: Vec<String>,
+    pub runs: usize,
+    /// Clone in cone mode, whatever the global config says
+    pub cone: bool,
+    /// Use a sparse index (`index.sparse`), which needs cone mode
+    pub sparse_index: bool,
+}
+
+/// Timings of one step over all runs
+#[derive(Debug, Serialize, PartialEq)]
+pub struct StepTimings {
+    pub name: &'static str,
+    pub runs_ms: Vec<u64>,
+    pub min_ms: u64,
+    pub median_ms: u64,
+    pub max_ms: u64,
+}
+
+impl StepTimings {
+    fn new(
+        name: &'static str,
+        runs_ms: Vec<u64>,
+    ) -> Self {
+        let mut sorted = runs_ms.clone();
+        sorted.sort_unstable();
+        StepTimings {
+            name,
+            min_ms: sorted.first().copied().unwrap_or(0),
+            median_ms: median(&sorted),
+            max_ms: sorted.last().copied().unwrap_or(0),
+            runs_ms,
+        }
+    }
+}
+
+/// Median of sorted values; the lower middle one for an even count
+fn median(sorted: &[u64]) -> u64 {
+    match sorted.len() {
+        0 => 0,
+        len => sorted[(len - 1) / 2],
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct BenchReport<'a> {
+    repo_url: &'a str,
+    paths: &'a [String],
+    add_paths: &'a [String],
+    cone: bool,
+    sparse_index: bool,
+    runs: usize,
+    steps: Vec<StepTimings>,
+}
+
+/// Measure clone, add-paths, smart-pull and status against a repository,
+/// each run in a fresh clone in a temporary directory. The steps run this
+/// executable, so they are timed as a user would see them.
+/// Returns the results as a JSON line.
+pub async fn bench(options: &BenchOptions) -> Result<String> {
+    if options.sparse_index && !options.cone {
+        anyhow::bail!("--sparse-index needs --cone: git only keeps a sparse index in cone mode");
+    }
+    let scratch = env::temp_dir().join(format!("git-partial-bench-{}", std::process::id()));
+    fs::create_dir_all(&scratch)
+        .with_context(|| format!("Failed to create {}", scratch.display()))?;
+    let _guard = RemoveOnInterrupt::new(&scratch);
+    let result = run_steps(options, &scratch);
+    fs::remove_dir_all(&scratch).ok();
+    result
+}
+
+fn run_steps(
+    options: &BenchOptions,
+    scratch: &Path,
+) -> Result<String> {
+    let exe = env::current_exe().context("Failed to locate the git-partial executable")?;
+
+    let command = |dir: &Path, args: &[&str]| {
+        let mut command = Command::new(&exe);
+        command
+            .args(args)
+            .current_dir(dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        if options.cone {
+            command.env("XDG_CONFIG_HOME", scratch.join("config"));
+        }
+        if options.sparse_index {
+            command
+                .env("GIT_CONFIG_COUNT", "1")
+                .env("GIT_CONFIG_KEY_0", "index.sparse")
+                .env("GIT_CONFIG_VALUE_0", "true");
+        }
+        command
+    };
+    if options.cone {
+        // A copy of the global config with cone mode on
+        let config = GlobalConfig {
+            cone: Some(true),
+            ..config::global().clone()
+        };
+        let path = scratch.join("config").join("git-partial");
+        fs::create_dir_all(&path)?;
+        fs::write(
+            path.join("config.toml"),
+            toml::to_string_pretty(&config).context("Failed to serialize config")?,
+        )?;
+    }
+
+    let mut steps: Vec<(&'static str, Vec<u64>)> = vec![
+        ("clone", Vec::new()),
+        ("add_paths", Vec::new()),
+        ("smart_pull", Vec::new()),
+        ("status", Vec::new()),
+    ];
+    for run in 0..options.runs {
+        let dest = scratch.join(format!("run-{}", run));
+        let dest_str = dest.to_string_lossy().into_owned();
+        let mut clone_args = vec![
+            "clone",
+            options.repo_url.as_str(),
+            dest_str.as_str(),
+            "--paths",
+        ];
+        clone_args.extend(options.paths.iter().map(String::as_str));
+        let mut add_args = vec!["add-paths", "--yes"];
+        add_args.extend(options.add_paths.iter().map(String::as_str));
+
+        let runs: [(&Path, Vec<&str>); 4] = [
+            (scratch, clone_args),
+            (&dest, add_args),
+            (&dest, vec!["smart-pull"]),
+            (&dest, vec!["status"]),
+        ];
+        for ((name, timings), (dir, args)) in steps.iter_mut().zip(runs) {
+            if *name == "add_paths" && options.add_paths.is_empty() {
+                continue;
+            }
+            info!("Run {}: {}", run + 1, name);
+            let started = Instant::now();
+            let output = command(dir, &args)
+                .output()
+                .context("Failed to run git-partial")?;
+            if !output.status.success() {
+                anyhow::bail!(
+                    "{} failed in run {}: {}",
+                    name,
+                    run + 1,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+            timings.push(started.elapsed().as_millis() as u64);
+        }
+        fs::remove_dir_all(&dest).ok();
+    }
+
+    let report = BenchReport {
+        repo_url: &options.repo_url,
+        paths: &options.paths,
+        add_paths: &options.add_paths,
+        cone: options.cone,
+        sparse_index: options.sparse_index,
+        runs: options.runs,
+        steps: steps
+            .into_iter()
+            .filter(|(_, timings)| !timings.is_empty())
+            .map(|(name, timings)| StepTimings::new(name, timings))
+            .collect(),
+    };
+    Ok(format!("{}\n", serde_json::to_string(&report)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_timings() {
+        let step = StepTimings::new("clone", vec![30, 10, 20, 40]);
+        assert_eq!((step.min_ms, step.median_ms, step.max_ms), (10, 20, 40));
+        assert_eq!(step.runs_ms, vec![30, 10, 20, 40]);
+        assert_eq!(StepTimings::new("status", vec![]).median_ms, 0);
+    }
+}
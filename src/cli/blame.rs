@@ -0,0 +1,33 @@
+use anyhow::{Context, Result};
+use std::env;
+use tracing::info;
+
+use crate::cli::large_files;
+use crate::git::commands;
+
+/// Run `git blame` on `file` with `options`, after downloading the versions
+/// of the file in the history of HEAD that are missing locally. Git would
+/// otherwise fetch them one object at a time while blaming; here they come
+/// in one batch. Files outside the sparse checkout are blamed at HEAD.
+pub async fn blame(
+    file: &str,
+    options: &[String],
+) -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let missing = large_files::skipped_blobs(&current_dir, &[file.to_string()])?;
+    if !missing.is_empty() {
+        info!("Fetching {} version(s) of {}", missing.len(), file);
+        let ids: Vec<String> = missing.into_iter().map(|blob| blob.id).collect();
+        commands::fetch_objects(&current_dir, &ids)
+            .with_context(|| format!("Failed to fetch the history of {}", file))?;
+    }
+
+    let mut args = vec!["blame"];
+    args.extend(options.iter().map(String::as_str));
+    if !current_dir.join(file).exists() {
+        args.push("HEAD");
+    }
+    args.extend(["--", file]);
+    commands::run_git_command_in_dir(&current_dir, &args)
+        .with_context(|| format!("Failed to blame {}", file))
+}
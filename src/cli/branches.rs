@@ -0,0 +1,171 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::path::Path;
+use tracing::{debug, info};
+
+use crate::core::branch_activity::{self, BranchActivity};
+use crate::core::history;
+use crate::core::metadata::RepositoryMetadata;
+use crate::git::commands;
+
+/// List the branches of `origin` whose commits since they diverged from
+/// `base` (by default the remote's default branch) change files in the
+/// sparse paths, most recently updated first. Only the remote-tracking refs
+/// and trees are read, so no blob is fetched; `fetch` updates the refs first.
+pub async fn branches(
+    base: Option<&str>,
+    fetch: bool,
+    show_files: bool,
+) -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+    if fetch {
+        commands::run_git_command_in_dir(&current_dir, &["fetch", "origin", "--prune", "--quiet"])
+            .context("Failed to fetch from origin")?;
+    }
+    let base = match base {
+        Some(base) => base.to_string(),
+        None => default_base(&current_dir)?,
+    };
+    let base_commit = commands::run_git_command_in_dir(
+        &current_dir,
+        &["rev-parse", "--verify", &format!("{}^{{commit}}", base)],
+    )
+    .with_context(|| format!("Unknown base branch '{}'", base))?;
+    info!("Comparing the branches of origin against {}", base);
+
+    let mut patterns: Vec<String> = metadata.checked_out_paths.iter().cloned().collect();
+    patterns.sort();
+    let format = format!("--format={}", branch_activity::REF_FORMAT);
+    let refs = commands::run_git_command_in_dir(
+        &current_dir,
+        &["for-each-ref", &format, "refs/remotes/origin/"],
+    )
+    .context("Failed to list the branches of origin")?;
+
+    let mut activity = Vec::new();
+    for branch in branch_activity::parse_refs(&refs) {
+        if branch.name == base {
+            continue;
+        }
+        match branch_changes(&current_dir, &base_commit, &branch.name, &patterns) {
+            Ok(Some((commits, files))) => activity.push(BranchActivity {
+                branch,
+                commits,
+                files,
+            }),
+            Ok(None) => {}
+            Err(e) => debug!("Skipping {}: {:#}", branch.name, e),
+        }
+    }
+    activity.sort_by(|a, b| {
+        b.branch
+            .timestamp
+            .cmp(&a.branch.timestamp)
+            .then(a.branch.name.cmp(&b.branch.name))
+    });
+
+    let mut output = format!(
+        "Branches changing the sparse paths since they left {}:\n",
+        base
+    );
+    if activity.is_empty() {
+        output.push_str("  (none)\n");
+    }
+    let width = activity
+        .iter()
+        .map(|branch| branch.branch.name.len())
+        .max()
+        .unwrap_or(0);
+    for branch in &activity {
+        output.push_str(&format!(
+            "  {:width$}  {} commit(s), {} file(s)  {}, {}\n",
+            branch.branch.name,
+            branch.commits,
+            branch.files.len(),
+            branch.branch.author,
+            history::format_timestamp(branch.branch.timestamp),
+            width = width
+        ));
+        if show_files {
+            for file in &branch.files {
+                output.push_str(&format!("      {}\n", file));
+            }
+        }
+    }
+    if metadata.fetch_branches.is_some() {
+        output.push_str(
+            "Note: the fetch refspec is narrowed, so only the fetched branches are compared; see 'git-partial narrow-fetch'.\n",
+        );
+    }
+    Ok(output)
+}
+
+/// The branch `origin/HEAD` points to, else `origin/main` or `origin/master`
+fn default_base(repo_path: &Path) -> Result<String> {
+    if let Ok(head) = commands::run_git_command_in_dir(
+        repo_path,
+        &[
+            "symbolic-ref",
+            "--quiet",
+            "--short",
+            "refs/remotes/origin/HEAD",
+        ],
+    ) {
+        return Ok(head);
+    }
+    ["origin/main", "origin/master"]
+        .into_iter()
+        .find(|name| {
+            commands::run_git_command_in_dir(
+                repo_path,
+                &[
+                    "rev-parse",
+                    "--verify",
+                    "--quiet",
+                    &format!("refs/remotes/{}", name),
+                ],
+            )
+            .is_ok()
+        })
+        .map(str::to_string)
+        .context("Cannot tell the default branch of origin; pass --base")
+}
+
+/// Commits on `branch` since its merge base with `base` and the files they
+/// change within `patterns`, or None when it changes none of them
+fn branch_changes(
+    repo_path: &Path,
+    base: &str,
+    branch: &str,
+    patterns: &[String],
+) -> Result<Option<(usize, Vec<String>)>> {
+    let merge_base = commands::run_git_command_in_dir(repo_path, &["merge-base", base, branch])?;
+    let output = commands::run_git_command_in_dir(
+        repo_path,
+        &[
+            "diff",
+            "-z",
+            "--name-only",
+            "--no-renames",
+            &merge_base,
+            branch,
+        ],
+    )?;
+    let changed = commands::nul_separated(&output);
+    let files = branch_activity::relevant_files(&changed, patterns);
+    if files.is_empty() {
+        return Ok(None);
+    }
+    let commits = commands::run_git_command_in_dir(
+        repo_path,
+        &[
+            "rev-list",
+            "--count",
+            &format!("{}..{}", merge_base, branch),
+        ],
+    )?
+    .parse()
+    .context("Failed to count commits")?;
+    Ok(Some((commits, files)))
+}
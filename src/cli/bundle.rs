@@ -0,0 +1,236 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tracing::info;
+
+use crate::core::bundle::BundleHeader;
+use crate::core::config;
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::path_selector;
+use crate::git::commands;
+use crate::utils::disk;
+
+/// Bundle headers larger than this are not read; the pack follows them
+const MAX_HEADER_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Returns the absolute path and header of `url` when it names a bundle file
+pub fn read_bundle(url: &str) -> Option<(PathBuf, BundleHeader)> {
+    let path = Path::new(url);
+    if !path.is_file() {
+        return None;
+    }
+    let mut data = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(MAX_HEADER_BYTES)
+        .read_to_end(&mut data)
+        .ok()?;
+    let header = BundleHeader::parse(&data)?;
+    Some((fs::canonicalize(path).ok()?, header))
+}
+
+/// Pack the given branches (by default the current one) into a bundle for a
+/// machine without access to the remote. Commits and trees are included
+/// whole, blobs only for `paths` (by default the sparse paths), so the
+/// bundle can be cloned with the same paths but no others.
+pub async fn create_bundle(
+    file: &str,
+    paths: &[String],
+    branches: &[String],
+) -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let paths = if paths.is_empty() {
+        let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+        metadata.checked_out_paths.iter().cloned().collect()
+    } else {
+        paths.to_vec()
+    };
+
+    let branches = if branches.is_empty() {
+        vec![
+            commands::run_git_command_in_dir(&current_dir, &["symbolic-ref", "--short", "HEAD"])
+                .context("Failed to get current branch")?,
+        ]
+    } else {
+        branches.to_vec()
+    };
+    let mut refs = Vec::new();
+    for branch in &branches {
+        let commit = commands::run_git_command_in_dir(
+            &current_dir,
+            &[
+                "rev-parse",
+                "--verify",
+                &format!("refs/heads/{}^{{commit}}", branch),
+            ],
+        )
+        .with_context(|| format!("Unknown branch '{}'", branch))?;
+        refs.push((commit, format!("refs/heads/{}", branch)));
+    }
+
+    // rev-list reads sparse filters from a blob, written like a sparse checkout file
+    let mut patterns = filter_patterns(&paths).join("\n");
+    patterns.push('\n');
+    let patterns_blob = write_blob(&current_dir, &patterns)?;
+    let header = BundleHeader {
+        filter: Some(format!("sparse:oid={}", patterns_blob)),
+        prerequisites: Vec::new(),
+        refs,
+    };
+    info!("Bundling {:?} with paths {:?}", branches, paths);
+
+    let mut output =
+        File::create(file).with_context(|| format!("Failed to create bundle {}", file))?;
+    output.write_all(header.to_string().as_bytes())?;
+    let tips: Vec<&str> = header.refs.iter().map(|(id, _)| id.as_str()).collect();
+    let result = write_pack(&current_dir, &tips, &patterns_blob, output);
+    if result.is_err() {
+        fs::remove_file(file).ok();
+    }
+    result.with_context(|| format!("Failed to write bundle {}", file))?;
+
+    let size = fs::metadata(file).map(|m| m.len()).unwrap_or(0);
+    Ok(format!(
+        "Created {} ({}) with {} branch(es) and {} path(s).\n",
+        file,
+        disk::format_size(size),
+        branches.len(),
+        paths.len()
+    ))
+}
+
+/// Set up `dest_path` from a bundle: the objects are unpacked, every branch
+/// becomes a remote-tracking branch of `origin` (the bundle file), and the
+/// first is checked out once the sparse paths are set. A filtered bundle
+/// lacks blobs, so it is registered as a promisor remote like a partial clone.
+pub fn clone_from_bundle(
+    bundle_path: &Path,
+    header: &BundleHeader,
+    dest_path: &Path,
+) -> Result<()> {
+    if !header.prerequisites.is_empty() {
+        anyhow::bail!(
+            "The bundle needs {} commit(s) it does not contain; only complete bundles can be cloned",
+            header.prerequisites.len()
+        );
+    }
+    let branches = header.branches();
+    let (branch, commit) = *branches
+        .first()
+        .context("The bundle does not contain any branch")?;
+    let bundle_url = bundle_path.to_string_lossy();
+    info!("Unbundling {} into {}", bundle_url, dest_path.display());
+
+    let git = |args: &[&str]| commands::run_git_command_in_dir(dest_path, args);
+    git(&["init", "--quiet", "--initial-branch", branch])?;
+    git(&["bundle", "unbundle", &bundle_url]).context("Failed to unpack bundle")?;
+    git(&["remote", "add", "origin", &bundle_url])?;
+    for (name, id) in &branches {
+        git(&["update-ref", &format!("refs/remotes/origin/{}", name), id])?;
+    }
+    git(&["update-ref", &format!("refs/heads/{}", branch), commit])?;
+    git(&["config", &format!("branch.{}.remote", branch), "origin"])?;
+    git(&[
+        "config",
+        &format!("branch.{}.merge", branch),
+        &format!("refs/heads/{}", branch),
+    ])?;
+
+    if let Some(filter) = &header.filter {
+        // Mark the unpacked objects as promised, so the missing blobs are expected
+        let pack_dir = dest_path.join(git(&["rev-parse", "--git-path", "objects/pack"])?);
+        for entry in fs::read_dir(&pack_dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "pack") {
+                fs::write(path.with_extension("promisor"), "")?;
+            }
+        }
+        git(&["config", "core.repositoryformatversion", "1"])?;
+        git(&["config", "extensions.partialClone", "origin"])?;
+        git(&["config", "remote.origin.promisor", "true"])?;
+        git(&["config", "remote.origin.partialclonefilter", filter])?;
+    }
+    Ok(())
+}
+
+/// Sparse checkout lines for `paths` in the mode clones use, so a clone of
+/// the bundle finds every blob its checkout needs
+fn filter_patterns(paths: &[String]) -> Vec<String> {
+    match config::global().cone {
+        Some(true) => match path_selector::patterns_to_cone(paths) {
+            Some(dirs) => path_selector::cone_file(&dirs),
+            None => commands::sparse_patterns(paths),
+        },
+        _ => commands::sparse_patterns(paths),
+    }
+}
+
+/// Store `content` as a blob and return its id
+fn write_blob(
+    repo_path: &Path,
+    content: &str,
+) -> Result<String> {
+    let mut child = Command::new("git")
+        .args(["hash-object", "-w", "--stdin"])
+        .current_dir(repo_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to execute git command")?;
+    child
+        .stdin
+        .take()
+        .context("Failed to open git stdin")?
+        .write_all(content.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git command failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Append a pack of everything reachable from `tips` to `output`, leaving out
+/// blobs outside the patterns and any blob a partial clone does not have
+fn write_pack(
+    repo_path: &Path,
+    tips: &[&str],
+    patterns_blob: &str,
+    output: File,
+) -> Result<()> {
+    let filter = format!("--filter=sparse:oid={}", patterns_blob);
+    let mut child = Command::new("git")
+        .args([
+            "pack-objects",
+            "--stdout",
+            "--revs",
+            "--quiet",
+            &filter,
+            "--missing=allow-promisor",
+        ])
+        .current_dir(repo_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::from(output))
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to execute git command")?;
+    let mut stdin = child.stdin.take().context("Failed to open git stdin")?;
+    for tip in tips {
+        writeln!(stdin, "{}", tip)?;
+    }
+    drop(stdin);
+    let result = child.wait_with_output()?;
+    if !result.status.success() {
+        anyhow::bail!(
+            "Git command failed: {}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use tracing::info;
+
+use crate::cli::materialize;
+use crate::core::path_selector;
+use crate::git::cat_file::{BatchMode, CatFile};
+use crate::git::commands;
+
+/// Stream the files at `rev` matching the paths or patterns read from
+/// stdin, one per line, to stdout. Each file is written as a
+/// `<id> <size> <path>` header line, its content and a newline, in the
+/// manner of `git cat-file --batch`; a line that matches nothing is
+/// answered with `<line> missing`. The missing contents are fetched from
+/// the promisor remote in one batch up front, and the sparse checkout is
+/// left alone, so this also works in a `--bare-partial` clone.
+pub async fn cat_tree(rev: &str) -> Result<()> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let specs: Vec<String> = io::stdin()
+        .lock()
+        .lines()
+        .map(|line| line.map(|line| line.trim().to_string()))
+        .filter(|line| !matches!(line, Ok(line) if line.is_empty()))
+        .collect::<Result<_, _>>()
+        .context("Failed to read paths from stdin")?;
+
+    let mut stdout = io::BufWriter::new(io::stdout().lock());
+    stream_files(&current_dir, rev, &specs, &mut stdout)?;
+    stdout.flush().context("Failed to write to stdout")
+}
+
+fn stream_files<W: Write>(
+    repo_path: &Path,
+    rev: &str,
+    specs: &[String],
+    output: &mut W,
+) -> Result<()> {
+    let files = commands::list_blob_ids(repo_path, rev)
+        .with_context(|| format!("Failed to list the files at {}", rev))?;
+
+    let missing = materialize::missing_at(repo_path, rev, specs)?;
+    if !missing.is_empty() {
+        let ids: Vec<String> = missing
+            .into_iter()
+            .map(|blob| blob.id)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        info!("Fetching {} blob(s) at {}", ids.len(), rev);
+        commands::fetch_objects(repo_path, &ids).context("Failed to fetch the missing blobs")?;
+    }
+
+    // Each file once, in the order of the first line that matches it
+    let mut written = HashSet::new();
+    let mut cat_file = CatFile::spawn(repo_path, BatchMode::Contents)?;
+    for spec in specs {
+        let pattern = path_selector::compile_sparse(spec);
+        let matching: Vec<&(String, String)> = files
+            .iter()
+            .filter(|(_, path)| path_selector::pattern_covers(&pattern, path))
+            .collect();
+        if matching.is_empty() {
+            writeln!(output, "{} missing", spec)?;
+            continue;
+        }
+        for (id, path) in matching {
+            if !written.insert(path.as_str()) {
+                continue;
+            }
+            let (info, content) = cat_file
+                .query(id)?
+                .with_context(|| format!("Failed to read {} ({})", path, id))?;
+            writeln!(output, "{} {} {}", info.id, info.size, path)?;
+            output.write_all(&content)?;
+            writeln!(output)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use anyhow::{Context, Result};
+use std::env;
+use tracing::info;
+
+use crate::core::metadata::RepositoryMetadata;
+use crate::remote::Provider;
+
+/// List the files changed by a pull request (merge request on GitLab)
+pub async fn show_changed_files(
+    number: u64,
+    pre_clone: Option<&str>,
+) -> Result<String> {
+    let repo_url = match pre_clone {
+        Some(url) => url.to_string(),
+        None => {
+            let current_dir = env::current_dir().context("Failed to get current directory")?;
+            RepositoryMetadata::load(&current_dir)
+                .context("Failed to load metadata")?
+                .remote_url
+        }
+    };
+
+    let provider = Provider::from_url(&repo_url).with_context(|| {
+        format!(
+            "Pull request lookup is not supported for '{}' (GitHub, GitLab and Bitbucket only)",
+            repo_url
+        )
+    })?;
+
+    info!("Looking up files changed by #{} in {}", number, repo_url);
+    let files = provider.changed_files(number).await?;
+
+    let mut output = String::new();
+    for file in files {
+        output.push_str(&format!("{}\n", file));
+    }
+    Ok(output)
+}
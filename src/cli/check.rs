@@ -0,0 +1,246 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::fmt;
+use std::path::Path;
+use tracing::{debug, info};
+
+use crate::core::journal::Journal;
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::path_selector;
+use crate::core::pull_state::PullInProgress;
+use crate::git::backend::{CommandBackend, GitBackend};
+use crate::git::commands;
+use crate::git::upstream;
+
+/// Paths listed per problem before the rest are only counted
+const MAX_LISTED_PATHS: usize = 10;
+
+/// A partial clone invariant that `check` found broken
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+    /// The directory is not a git-partial repository
+    NotPartial,
+
+    /// The metadata disagrees with the repository
+    Inconsistent(String),
+
+    /// Staged paths the sparse paths do not cover
+    StagedOutside(Vec<String>),
+
+    /// HEAD is more than `max` commits behind its upstream
+    Behind {
+        upstream: String,
+        commits: usize,
+        max: usize,
+    },
+
+    /// Staged paths under paths pinned at a commit
+    StagedPinned(Vec<String>),
+}
+
+impl Problem {
+    /// The documented exit code: 5 outside a git-partial repository, as for
+    /// `status --check`, 6 inconsistent metadata, 7 staged paths outside the
+    /// sparse paths, 8 too far behind, 9 staged paths pinned at a commit
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Problem::NotPartial => 5,
+            Problem::Inconsistent(_) => 6,
+            Problem::StagedOutside(_) => 7,
+            Problem::Behind { .. } => 8,
+            Problem::StagedPinned(_) => 9,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            Problem::NotPartial => write!(f, "not a git-partial repository"),
+            Problem::Inconsistent(reason) => write!(f, "inconsistent metadata: {}", reason),
+            Problem::StagedOutside(paths) => write_paths(
+                f,
+                &format!(
+                    "{} staged path(s) fall outside the sparse paths:",
+                    paths.len()
+                ),
+                paths,
+            ),
+            Problem::StagedPinned(paths) => write_paths(
+                f,
+                &format!(
+                    "{} staged path(s) are pinned at a commit; committing them would move the branch to the pinned versions:",
+                    paths.len()
+                ),
+                paths,
+            ),
+            Problem::Behind {
+                upstream,
+                commits,
+                max,
+            } => write!(
+                f,
+                "{} commit(s) behind {} (at most {} allowed); run 'git-partial smart-pull'",
+                commits, upstream, max
+            ),
+        }
+    }
+}
+
+/// Writes `heading` and the first paths, one per line
+fn write_paths(
+    f: &mut fmt::Formatter<'_>,
+    heading: &str,
+    paths: &[String],
+) -> fmt::Result {
+    write!(f, "{}", heading)?;
+    for path in paths.iter().take(MAX_LISTED_PATHS) {
+        write!(f, "\n  {}", path)?;
+    }
+    if paths.len() > MAX_LISTED_PATHS {
+        write!(f, "\n  ... and {} more", paths.len() - MAX_LISTED_PATHS)?;
+    }
+    Ok(())
+}
+
+/// The exit code for the problems found, that of the first, or 0
+pub fn exit_code(problems: &[Problem]) -> i32 {
+    problems.first().map_or(0, Problem::exit_code)
+}
+
+/// Validate the partial clone invariants quickly enough for a pre-commit
+/// hook: the metadata matches the repository, no staged path falls outside
+/// the sparse paths or under a path pinned at a commit, and HEAD is at most `max_behind` commits behind its
+/// upstream as last fetched (fetched first with `fetch`). Returns the
+/// problems found, most fundamental first.
+pub async fn check(
+    fetch: bool,
+    max_behind: usize,
+) -> Result<Vec<Problem>> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let Ok(metadata) = RepositoryMetadata::load(&current_dir) else {
+        return Ok(vec![Problem::NotPartial]);
+    };
+
+    let mut problems: Vec<Problem> = inconsistencies(&current_dir, &metadata)?
+        .into_iter()
+        .map(Problem::Inconsistent)
+        .collect();
+
+    let staged = commands::staged_paths(&current_dir).context("Failed to list staged paths")?;
+    let outside = path_selector::outside_patterns(&staged, &metadata.checked_out_paths);
+    if !outside.is_empty() {
+        problems.push(Problem::StagedOutside(outside));
+    }
+    let pinned = path_selector::covered_paths(&staged, metadata.pinned_revisions.keys());
+    if !pinned.is_empty() {
+        problems.push(Problem::StagedPinned(pinned));
+    }
+
+    if let Some((upstream, commits)) = behind(&current_dir, fetch)? {
+        info!("{} commit(s) behind {}", commits, upstream);
+        if commits > max_behind {
+            problems.push(Problem::Behind {
+                upstream,
+                commits,
+                max: max_behind,
+            });
+        }
+    }
+    Ok(problems)
+}
+
+/// Ways the metadata disagrees with the repository: an interrupted
+/// operation or pull, sparse checkout turned off, or sparse patterns
+/// changed behind git-partial's back
+fn inconsistencies(
+    repo_path: &Path,
+    metadata: &RepositoryMetadata,
+) -> Result<Vec<String>> {
+    let mut reasons = Vec::new();
+    if let Some(journal) = Journal::load(repo_path)? {
+        reasons.push(format!(
+            "an interrupted {} is incomplete; run 'git-partial recover'",
+            journal.operation
+        ));
+    }
+    if PullInProgress::load(repo_path)?.is_some() {
+        reasons.push(
+            "a smart-pull stopped at conflicts; run 'git-partial smart-pull --continue' or '--abort'"
+                .to_string(),
+        );
+    }
+    if metadata.bare {
+        return Ok(reasons);
+    }
+
+    let paths: Vec<String> = metadata.checked_out_paths.iter().cloned().collect();
+    match commands::applied_sparse_patterns(repo_path) {
+        None => reasons.push("sparse checkout is not enabled".to_string()),
+        Some((cone, mut applied)) => {
+            let (wanted_cone, mut wanted) = commands::wanted_sparse_patterns(&paths);
+            applied.sort();
+            wanted.sort();
+            if (cone, applied) != (wanted_cone, wanted) {
+                reasons.push(
+                    "the sparse-checkout patterns differ from the sparse paths; run 'git-partial sync-state' to record them"
+                        .to_string(),
+                );
+            }
+        }
+    }
+    Ok(reasons)
+}
+
+/// The upstream of the current branch and how many commits HEAD is behind
+/// it, or None on a detached HEAD or without a remote-tracking branch
+fn behind(
+    repo_path: &Path,
+    fetch: bool,
+) -> Result<Option<(String, usize)>> {
+    let backend = CommandBackend::new(repo_path);
+    let Ok(branch) = backend.current_branch() else {
+        debug!("Detached HEAD; not comparing with an upstream");
+        return Ok(None);
+    };
+    let upstream = upstream::resolve(&backend, &branch, None)?;
+    if fetch {
+        upstream::fetch(&backend, &upstream).context("Failed to fetch changes")?;
+    }
+    let rev = upstream.rev();
+    if backend.rev_parse(&rev).is_err() {
+        debug!("{} is not fetched; not comparing with it", rev);
+        return Ok(None);
+    }
+    let (_, behind) = backend.ahead_behind("HEAD", &rev)?;
+    Ok(Some((rev, behind)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(exit_code(&[]), 0);
+        let behind = Problem::Behind {
+            upstream: "origin/main".to_string(),
+            commits: 120,
+            max: 100,
+        };
+        assert_eq!(
+            behind.to_string(),
+            "120 commit(s) behind origin/main (at most 100 allowed); run 'git-partial smart-pull'"
+        );
+        let staged = Problem::StagedOutside(vec!["docs/a.md".to_string()]);
+        assert_eq!(
+            staged.to_string(),
+            "1 staged path(s) fall outside the sparse paths:\n  docs/a.md"
+        );
+        assert_eq!(exit_code(&[staged, behind]), 7);
+        assert_eq!(exit_code(&[Problem::NotPartial]), 5);
+    }
+}
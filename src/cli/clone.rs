@@ -1,27 +1,248 @@
 use anyhow::{Context, Result};
-use log::{debug, info};
+use serde_json::json;
 use std::fs;
 use std::path::Path;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
 
+use crate::cli::policy::{self, Overrides};
+use crate::cli::{bundle, history, what_changed};
+use crate::core::config;
+use crate::core::journal::{Journal, JournalState, Step};
+use crate::core::lfs;
 use crate::core::metadata::RepositoryMetadata;
-use crate::git::commands;
+use crate::core::registry;
+use crate::core::url_rewrite;
+use crate::git::{commands, maintenance};
+use crate::remote::capabilities;
+use crate::utils::disk;
+use crate::utils::interrupt::RemoveOnInterrupt;
 
-/// Clone a repository with specified paths
+/// Remote that serves the blobs a `--via` mirror does not have
+pub const DIRECT_REMOTE: &str = "direct";
+
+/// Time spent in each phase of a clone
+struct CloneTimings {
+    clone: Duration,
+    checkout: Duration,
+}
+
+/// Clone a repository with specified paths, fetching through `via` when given
 pub async fn clone_repository(
     repo_url: &str,
     destination: &str,
     paths: &[String],
+    via: Option<&str>,
+    rewrite: Option<&str>,
+    overrides: Overrides,
+) -> Result<()> {
+    clone_with_args(repo_url, destination, paths, &[], via, rewrite, overrides).await?;
+    // Left out of CI clones: they are shallow and short-lived
+    if let Err(e) = maintenance::write_commit_graph(Path::new(destination)) {
+        warn!("Failed to write commit-graph: {:#}", e);
+    }
+    if let Err(e) = registry::register(Path::new(destination)) {
+        warn!("Failed to register the clone: {:#}", e);
+    }
+    Ok(())
+}
+
+/// Clone a repository for an ephemeral CI runner: shallow, single branch and
+/// without tags, optionally borrowing objects from a runner-local cache
+/// (by default the global config's `cache_dir`).
+/// Returns a single-line JSON report of timings and sizes.
+pub async fn clone_for_ci(
+    repo_url: &str,
+    destination: &str,
+    paths: &[String],
+    cache_dir: Option<&str>,
+    via: Option<&str>,
+    rewrite: Option<&str>,
+    overrides: Overrides,
+) -> Result<String> {
+    let cache_dir = cache_dir.or(config::global().cache_dir.as_deref());
+    let mut args = vec![
+        "--depth=1".to_string(),
+        "--single-branch".to_string(),
+        "--no-tags".to_string(),
+    ];
+    if let Some(cache_dir) = cache_dir {
+        // Falls back to a plain clone when the cache does not exist yet
+        args.push(format!("--reference-if-able={}", cache_dir));
+    }
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+
+    let started = Instant::now();
+    let timings =
+        clone_with_args(repo_url, destination, paths, &args, via, rewrite, overrides).await?;
+    let total = started.elapsed();
+
+    let dest_path = Path::new(destination);
+    let git_dir_bytes = disk::dir_size(&dest_path.join(".git"));
+    let checkout_bytes = disk::dir_size(dest_path).saturating_sub(git_dir_bytes);
+
+    let report = json!({
+        "clone_ms": timings.clone.as_millis() as u64,
+        "checkout_ms": timings.checkout.as_millis() as u64,
+        "total_ms": total.as_millis() as u64,
+        "git_dir_bytes": git_dir_bytes,
+        "checkout_bytes": checkout_bytes,
+        "cache_used": cache_dir.is_some_and(|dir| Path::new(dir).exists()),
+    });
+    Ok(format!("{}\n", report))
+}
+
+/// Clone a repository without a working tree, for services that only read
+/// the selected paths with `tree` and `show`. The bare repository is
+/// blobless; contents are fetched on demand. `git fetch` updates the local
+/// branches directly, as there is no checkout to merge into.
+pub async fn clone_bare(
+    repo_url: &str,
+    destination: &str,
+    paths: &[String],
+    override_policy: bool,
 ) -> Result<()> {
+    info!(
+        "Starting bare partial clone from {} to {}",
+        repo_url, destination
+    );
+    let rules = commands::url_rewrites(None);
+    let remote_url = url_rewrite::canonical(repo_url, &rules);
+    capabilities::ensure_partial_clone(&url_rewrite::apply(repo_url, &rules))?;
+
+    let dest_path = Path::new(destination);
+    let existed = dest_path.exists();
+    if existed && fs::read_dir(dest_path)?.next().is_some() {
+        anyhow::bail!(
+            "Destination directory '{}' exists and is not empty.",
+            destination
+        );
+    }
+    let _incomplete = if existed {
+        RemoveOnInterrupt::contents(dest_path)
+    } else {
+        RemoveOnInterrupt::new(dest_path)
+    };
+
+    let filter = format!("--filter={}", config::clone_filter());
+    if let Err(e) =
+        commands::run_git_command(&["clone", "--bare", &filter, "--", repo_url, destination])
+            .with_context(|| format!("Failed to perform bare clone into {}", destination))
+    {
+        remove_incomplete(dest_path, existed);
+        return Err(e);
+    }
+    commands::run_git_command_in_dir(
+        dest_path,
+        &[
+            "config",
+            "remote.origin.fetch",
+            "+refs/heads/*:refs/heads/*",
+        ],
+    )?;
+    if let Err(e) = policy::enforce(dest_path, &remote_url, paths, override_policy).await {
+        remove_incomplete(dest_path, existed);
+        return Err(e);
+    }
+
+    let mut metadata = RepositoryMetadata::new(remote_url);
+    metadata.bare = true;
+    metadata.filter = Some(config::clone_filter().to_string());
+    metadata.filter_ignored = filter_ignored(dest_path, &metadata.remote_url, false);
+    metadata.add_paths(paths);
+    let head_commit = commands::get_head_commit(dest_path).context("Failed to get HEAD commit")?;
+    metadata.set_last_commit(&head_commit);
+    metadata
+        .save(dest_path)
+        .context("Failed to save metadata")?;
+    history::record(dest_path, paths, &[]);
+    what_changed::record(dest_path, &head_commit);
+    if let Err(e) = maintenance::write_commit_graph(dest_path) {
+        warn!("Failed to write commit-graph: {:#}", e);
+    }
+    if let Err(e) = registry::register(dest_path) {
+        warn!("Failed to register the clone: {:#}", e);
+    }
+
+    info!("Bare partial clone completed in {}", destination);
+    Ok(())
+}
+
+/// Register the canonical repository of a fork as the `upstream` remote.
+/// Pushes keep going to `origin`, the fork.
+pub fn add_upstream_remote(
+    destination: &str,
+    upstream_url: &str,
+) -> Result<()> {
+    info!("Adding upstream remote {}", upstream_url);
+    let dest_path = Path::new(destination);
+
+    commands::run_git_command_in_dir(dest_path, &["remote", "add", "upstream", upstream_url])
+        .context("Failed to add upstream remote")?;
+    // Fetch from upstream blobless too, like the original clone
+    commands::run_git_command_in_dir(dest_path, &["config", "remote.upstream.promisor", "true"])?;
+    commands::run_git_command_in_dir(
+        dest_path,
+        &[
+            "config",
+            "remote.upstream.partialclonefilter",
+            config::clone_filter(),
+        ],
+    )?;
+    commands::run_git_command_in_dir(dest_path, &["config", "remote.pushDefault", "origin"])?;
+    commands::run_git_command_in_dir(dest_path, &["fetch", "upstream", "--quiet"])
+        .context("Failed to fetch upstream remote")?;
+
+    let mut metadata = RepositoryMetadata::load(dest_path).context("Failed to load metadata")?;
+    metadata.upstream_url = Some(upstream_url.to_string());
+    metadata
+        .save(dest_path)
+        .context("Failed to save metadata")?;
+    Ok(())
+}
+
+async fn clone_with_args(
+    repo_url: &str,
+    destination: &str,
+    paths: &[String],
+    clone_args: &[&str],
+    via: Option<&str>,
+    rewrite: Option<&str>,
+    overrides: Overrides,
+) -> Result<CloneTimings> {
     info!(
         "Starting partial clone from {} to {}",
         repo_url, destination
     );
     debug!("Paths to include: {:?}", paths);
 
+    // A bundle file is unpacked instead of cloned; it is the remote from then on
+    let bundle = bundle::read_bundle(repo_url);
+    let bundle_url = bundle
+        .as_ref()
+        .map(|(path, _)| path.to_string_lossy().into_owned());
+    let repo_url = bundle_url.as_deref().unwrap_or(repo_url);
+    if bundle.is_some() && (via.is_some() || rewrite.is_some()) {
+        anyhow::bail!("--via and --rewrite cannot be used when cloning from a bundle");
+    }
+    // Metadata records the repository as written before URL rewrites, so
+    // fetching from a mirror or CDN does not change which repository it is
+    let rules = commands::url_rewrites(None);
+    let remote_url = url_rewrite::canonical(repo_url, &rules);
+    let fetch_url = match rewrite {
+        Some(rewrite) => rewrite.to_string(),
+        None => url_rewrite::apply(repo_url, &rules),
+    };
+    if bundle.is_none() {
+        // Refuse servers that would silently send every blob
+        capabilities::ensure_partial_clone(via.unwrap_or(&fetch_url))?;
+    }
+
     let dest_path = Path::new(destination);
+    let existed = dest_path.exists();
 
     // Check if destination exists and is not empty
-    if dest_path.exists() {
+    if existed {
         if fs::read_dir(dest_path)?.next().is_none() {
             // Directory exists but is empty, proceed
         } else {
@@ -35,18 +256,85 @@ pub async fn clone_repository(
         fs::create_dir_all(dest_path)
             .with_context(|| format!("Failed to create destination directory: {}", destination))?;
     }
+    // An interrupted clone leaves an unusable repository behind. Remove the
+    // directory if we created it; an existing empty one is only emptied.
+    let _incomplete = if existed {
+        RemoveOnInterrupt::contents(dest_path)
+    } else {
+        RemoveOnInterrupt::new(dest_path)
+    };
+
+    // Scope LFS downloads before the initial checkout; this is
+    // reconciled with the actual attributes once the sparse paths are set
+    let fetch_include = format!("--config=lfs.fetchinclude={}", lfs::fetch_include(paths));
+    let mut clone_args = clone_args.to_vec();
+    clone_args.push(&fetch_include);
+    let rewrite_config =
+        rewrite.map(|rewrite| format!("--config=url.{}.insteadOf={}", rewrite, repo_url));
+    if let Some(rewrite_config) = &rewrite_config {
+        clone_args.push(rewrite_config);
+    }
 
     // Perform sparse clone into the destination directory
-    commands::clone_sparse(repo_url, destination)
-        .with_context(|| format!("Failed to perform sparse clone into {}", destination))?;
+    let started = Instant::now();
+    let cloned = match &bundle {
+        Some((bundle_path, header)) => bundle::clone_from_bundle(bundle_path, header, dest_path)
+            .with_context(|| format!("Failed to clone bundle into {}", destination)),
+        None => commands::clone_sparse(via.unwrap_or(repo_url), destination, &clone_args)
+            .with_context(|| format!("Failed to perform sparse clone into {}", destination)),
+    };
+    if let Err(e) = cloned {
+        remove_incomplete(dest_path, existed);
+        return Err(e);
+    }
+    let clone = started.elapsed();
+    if via.is_some() {
+        route_through_mirror(dest_path, repo_url)?;
+    }
 
-    // Set sparse-checkout paths within the cloned repository
-    commands::set_sparse_checkout(dest_path, paths)
-        .context("Failed to set sparse checkout paths")?;
+    // Check the paths before checking them out. The destination was empty,
+    // so a rejected clone is removed entirely.
+    if let Err(e) = policy::enforce(dest_path, &remote_url, paths, overrides.policy).await {
+        fs::remove_dir_all(dest_path).ok();
+        return Err(e);
+    }
+    if let Err(e) = policy::check_limits(dest_path, &remote_url, paths, overrides.limits).await {
+        fs::remove_dir_all(dest_path).ok();
+        return Err(e);
+    }
 
-    // Create and save metadata
-    let mut metadata = RepositoryMetadata::new(repo_url.to_string());
+    // Create the metadata up front, so the journal can complete the clone
+    let mut metadata = RepositoryMetadata::new(remote_url);
+    metadata.mirror_url = via.map(String::from);
+    metadata.filter = match &bundle {
+        Some((_, header)) => header.filter.clone(),
+        None => Some(config::clone_filter().to_string()),
+    };
+    metadata.filter_ignored =
+        bundle.is_none() && filter_ignored(dest_path, &metadata.remote_url, true);
     metadata.add_paths(paths);
+    let mut journal = Journal::begin(
+        dest_path,
+        "clone",
+        None,
+        JournalState {
+            branch: None,
+            metadata: metadata.clone(),
+        },
+    )?;
+
+    // Set sparse-checkout paths within the cloned repository
+    // A checkout failing halfway, e.g. on a path too long for the file
+    // system, leaves a working tree nothing can repair
+    let started = Instant::now();
+    if let Err(e) = commands::set_sparse_checkout(dest_path, paths)
+        .context("Failed to set sparse checkout paths")
+    {
+        remove_incomplete(dest_path, existed);
+        return Err(e);
+    }
+    let checkout = started.elapsed();
+    journal.step(dest_path, Step::SparseCheckout)?;
 
     // Get the current HEAD commit and set it in metadata
     let head_commit = commands::get_head_commit(dest_path).context("Failed to get HEAD commit")?;
@@ -55,7 +343,85 @@ pub async fn clone_repository(
     metadata
         .save(dest_path)
         .context("Failed to save metadata")?;
+    history::record(dest_path, paths, &[]);
+    what_changed::record(dest_path, &head_commit);
+    journal.step(dest_path, Step::Metadata)?;
+    Journal::finish(dest_path)?;
 
     info!("Partial clone completed in {}", destination);
+    Ok(CloneTimings { clone, checkout })
+}
+
+/// Check whether the server behind a fresh clone ignored the partial clone
+/// filter, which some do without saying so, and warn. A clone made with
+/// `--sparse` has only fetched the blobs at the top level of HEAD.
+fn filter_ignored(
+    dest_path: &Path,
+    remote_url: &str,
+    checked_out: bool,
+) -> bool {
+    let filter = config::clone_filter();
+    let counts = commands::count_local_blobs(dest_path).and_then(|local| {
+        let top_level = if checked_out {
+            commands::run_git_command_in_dir(dest_path, &["ls-tree", "HEAD"])?
+                .lines()
+                .filter(|line| line.split_whitespace().nth(1) == Some("blob"))
+                .count()
+        } else {
+            0
+        };
+        Ok((local, top_level))
+    });
+    let (local, top_level) = match counts {
+        Ok(counts) => counts,
+        Err(e) => {
+            warn!("Failed to check the partial clone filter: {:#}", e);
+            return false;
+        }
+    };
+    if !capabilities::filter_ignored(filter, local, top_level) {
+        return false;
+    }
+    eprintln!(
+        "Warning: {} ignored the partial clone filter ({}) and sent every object: {} file versions are local where {} were expected. The clone works, but takes the space of a full clone.",
+        remote_url, filter, local, top_level
+    );
+    true
+}
+
+/// Remove what a failed clone left in `dest_path`: the directory itself
+/// if the clone created it, otherwise only its contents
+fn remove_incomplete(
+    dest_path: &Path,
+    existed: bool,
+) {
+    if !existed {
+        fs::remove_dir_all(dest_path).ok();
+        return;
+    }
+    for entry in fs::read_dir(dest_path).into_iter().flatten().flatten() {
+        if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+            fs::remove_dir_all(entry.path()).ok();
+        } else {
+            fs::remove_file(entry.path()).ok();
+        }
+    }
+}
+
+/// Configure a clone made from a mirror: history keeps coming from the mirror
+/// (`origin`), pushes go to the repository itself, and blobs the blobless
+/// mirror cannot serve are fetched directly through a second promisor remote.
+fn route_through_mirror(
+    dest_path: &Path,
+    repo_url: &str,
+) -> Result<()> {
+    info!("Routing pushes and missing blobs to {}", repo_url);
+    commands::run_git_command_in_dir(dest_path, &["config", "remote.origin.pushurl", repo_url])?;
+    commands::run_git_command_in_dir(dest_path, &["remote", "add", DIRECT_REMOTE, repo_url])
+        .context("Failed to add direct remote")?;
+    let promisor = format!("remote.{}.promisor", DIRECT_REMOTE);
+    let filter = format!("remote.{}.partialclonefilter", DIRECT_REMOTE);
+    commands::run_git_command_in_dir(dest_path, &["config", &promisor, "true"])?;
+    commands::run_git_command_in_dir(dest_path, &["config", &filter, config::clone_filter()])?;
     Ok(())
 }
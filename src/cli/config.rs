@@ -0,0 +1,85 @@
+use anyhow::{Context, Result};
+use tracing::info;
+
+use crate::core::config::GlobalConfig;
+
+/// Print every value set in the global config file
+pub fn list_global() -> Result<String> {
+    let config = GlobalConfig::load()?;
+    toml::to_string(&config).context("Failed to serialize config")
+}
+
+/// Print one value of the global config file
+pub fn get_global(key: &str) -> Result<String> {
+    let table = to_table(&GlobalConfig::load()?)?;
+    match table.get(key) {
+        Some(toml::Value::String(value)) => Ok(format!("{}\n", value)),
+        Some(value) => Ok(format!("{}\n", value)),
+        None => anyhow::bail!("'{}' is not set in the global config", key),
+    }
+}
+
+/// Set a value in the global config file. The value is read as a TOML
+/// boolean or number when it looks like one, and as a string otherwise.
+pub fn set_global(
+    key: &str,
+    value: &str,
+) -> Result<String> {
+    let mut table = to_table(&GlobalConfig::load()?)?;
+    table.insert(key.to_string(), parse_value(value));
+    let config: GlobalConfig = toml::Value::Table(table)
+        .try_into()
+        .with_context(|| format!("Invalid setting {} = {}", key, value))?;
+
+    let path = config.save()?;
+    info!("Set {} in {}", key, path.display());
+    Ok(format!("Set {} = {} in {}\n", key, value, path.display()))
+}
+
+/// Remove a value from the global config file
+pub fn unset_global(key: &str) -> Result<String> {
+    let mut table = to_table(&GlobalConfig::load()?)?;
+    if table.remove(key).is_none() {
+        anyhow::bail!("'{}' is not set in the global config", key);
+    }
+    let config: GlobalConfig = toml::Value::Table(table)
+        .try_into()
+        .context("Failed to update config")?;
+
+    let path = config.save()?;
+    Ok(format!("Removed {} from {}\n", key, path.display()))
+}
+
+fn to_table(config: &GlobalConfig) -> Result<toml::Table> {
+    match toml::Value::try_from(config).context("Failed to serialize config")? {
+        toml::Value::Table(table) => Ok(table),
+        _ => unreachable!("the global config serializes to a table"),
+    }
+}
+
+fn parse_value(value: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", value))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .filter(|parsed| parsed.is_bool() || parsed.is_integer())
+        .unwrap_or_else(|| toml::Value::String(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_value("true"), toml::Value::Boolean(true));
+        assert_eq!(parse_value("8"), toml::Value::Integer(8));
+        assert_eq!(
+            parse_value("blob:limit=1m"),
+            toml::Value::String("blob:limit=1m".to_string())
+        );
+        assert_eq!(
+            parse_value("\"quoted\""),
+            toml::Value::String("\"quoted\"".to_string())
+        );
+    }
+}
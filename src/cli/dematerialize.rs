@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::env;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+use crate::core::history;
+use crate::core::large_files;
+use crate::core::metadata::RepositoryMetadata;
+use crate::git::commands;
+use crate::git::packs;
+use crate::utils::disk;
+
+/// Delete the local blobs of every version of the files matching `patterns`
+/// to free disk space. Commits, trees and the sparse patterns are kept, and
+/// git fetches a dropped blob again from the remote when it is needed.
+pub async fn dematerialize(patterns: &[String]) -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let blobs = history_blobs(&current_dir, patterns)?;
+    let Some((dropped, freed)) = drop_blobs(&current_dir, &blobs)? else {
+        return Ok(format!(
+            "No local blobs of {} to drop.\n",
+            patterns.join(", ")
+        ));
+    };
+    Ok(format!(
+        "Dropped {} blob(s) of {}, freeing {}. Git fetches them again when needed.\n",
+        dropped,
+        patterns.join(", "),
+        disk::format_size(freed)
+    ))
+}
+
+/// Drop the local blobs of the paths removed from the checkout longer than
+/// `retention` ago, unless the current sparse paths use them too. Returns
+/// what was pruned, or `None` when nothing was.
+pub fn prune_removed(
+    repo_path: &Path,
+    retention: Duration,
+) -> Result<Option<String>> {
+    let metadata = RepositoryMetadata::load(repo_path).context("Failed to load metadata")?;
+    let current: Vec<String> = metadata.checked_out_paths.iter().cloned().collect();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let expired = history::expired_removals(
+        &history::load(repo_path)?,
+        &current,
+        now,
+        retention.as_secs(),
+    );
+    if expired.is_empty() {
+        return Ok(None);
+    }
+
+    let mut blobs = history_blobs(repo_path, &expired)?;
+    if !current.is_empty() {
+        let kept = history_blobs(repo_path, &current)?;
+        blobs.retain(|id| !kept.contains(id));
+    }
+    let Some((dropped, freed)) = drop_blobs(repo_path, &blobs)? else {
+        return Ok(None);
+    };
+    Ok(Some(format!(
+        "Pruned {} blob(s) of removed paths {}, freeing {}.\n",
+        dropped,
+        expired.join(", "),
+        disk::format_size(freed)
+    )))
+}
+
+/// Rewrite the promisor packs without `blobs`. Returns how many were
+/// dropped and the bytes freed, or `None` when no pack held any.
+fn drop_blobs(
+    repo_path: &Path,
+    blobs: &HashSet<String>,
+) -> Result<Option<(usize, u64)>> {
+    // Only objects in promisor packs came from the remote; local commits
+    // may hold the only copy of theirs
+    let mut rewritten = Vec::new();
+    let mut kept = Vec::new();
+    let mut dropped = 0;
+    for pack in packs::promisor_packs(repo_path)? {
+        let (removed, objects): (Vec<String>, Vec<String>) = packs::pack_objects(repo_path, &pack)?
+            .into_iter()
+            .partition(|id| blobs.contains(id));
+        if !removed.is_empty() {
+            dropped += removed.len();
+            kept.extend(objects);
+            rewritten.push(pack);
+        }
+    }
+    if dropped == 0 {
+        return Ok(None);
+    }
+
+    let before: u64 = rewritten.iter().map(|pack| pack.size()).sum();
+    info!(
+        "Dropping {} blob(s) from {} pack(s)",
+        dropped,
+        rewritten.len()
+    );
+    let written = packs::replace(repo_path, &rewritten, &kept)
+        .context("Failed to rewrite the packs without the blobs")?;
+    Ok(Some((dropped, before.saturating_sub(written.size()))))
+}
+
+/// Ids of the blobs of every version of the files matching `patterns`, in
+/// the history of all refs
+fn history_blobs(
+    repo_path: &Path,
+    patterns: &[String],
+) -> Result<HashSet<String>> {
+    let pathspecs: Vec<String> = patterns
+        .iter()
+        .map(|pattern| format!(":(glob){}", pattern.trim_start_matches('/')))
+        .collect();
+    let mut args = vec![
+        "log",
+        "--raw",
+        "--no-abbrev",
+        "--no-renames",
+        "--format=",
+        "--all",
+        "--",
+    ];
+    args.extend(pathspecs.iter().map(String::as_str));
+    let history = commands::run_git_command_in_dir(repo_path, &args)
+        .context("Failed to read the history of the paths")?;
+    Ok(large_files::parse_raw_log(&history)
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect())
+}
@@ -0,0 +1,70 @@
+use anyhow::Result;
+
+use crate::cli::tree;
+use crate::core::tree as core_tree;
+use crate::utils::disk;
+
+/// Report the files and blob size under `path` at HEAD (or `reference`),
+/// by directory down to `depth` levels, largest first, so expensive paths
+/// can be spotted before adding them. Sizes come from the remote, as for
+/// `add-paths`, since listing them locally would download the blobs; with
+/// `pre_clone` they come from the remote's API without a clone.
+pub async fn disk_usage(
+    path: Option<&str>,
+    depth: usize,
+    pre_clone: Option<&str>,
+    reference: Option<&str>,
+) -> Result<String> {
+    let (entries, sizes_known) = tree::load_tree_entries(pre_clone, reference).await?;
+
+    let root = path.unwrap_or("");
+    let usage = core_tree::disk_usage(&entries, root, depth.max(1))?;
+    let sizes: Vec<String> = usage
+        .iter()
+        .map(|row| {
+            if sizes_known {
+                disk::format_size(row.bytes)
+            } else {
+                "?".to_string()
+            }
+        })
+        .collect();
+    let size_width = sizes.iter().map(String::len).max().unwrap_or(0);
+    let files_width = usage
+        .iter()
+        .map(|row| row.files.to_string().len())
+        .max()
+        .unwrap_or(0);
+
+    let mut output = String::new();
+    for (row, size) in usage.iter().zip(&sizes) {
+        output.push_str(&format!(
+            "{:>size_width$}  {:>files_width$} file(s)  {}\n",
+            size,
+            row.files,
+            row.path,
+            size_width = size_width,
+            files_width = files_width
+        ));
+    }
+    let files: usize = usage.iter().map(|row| row.files).sum();
+    let bytes: u64 = usage.iter().map(|row| row.bytes).sum();
+    let location = match root.trim_matches('/') {
+        "" => "the repository".to_string(),
+        root => root.to_string(),
+    };
+    if sizes_known {
+        output.push_str(&format!(
+            "Total: {} in {} file(s) under {}\n",
+            disk::format_size(bytes),
+            files,
+            location
+        ));
+    } else {
+        output.push_str(&format!(
+            "Total: {} file(s) under {}. Sizes are unavailable from the remote.\n",
+            files, location
+        ));
+    }
+    Ok(output)
+}
@@ -0,0 +1,36 @@
+use anyhow::Result;
+use tracing::debug;
+
+use crate::cli::tree;
+use crate::core::tree as core_tree;
+use crate::utils::disk;
+
+/// Estimate how many files and bytes the given paths, and the files
+/// matching the given regexes, would check out. The size is unknown when
+/// the remote cannot be asked for it.
+pub async fn estimate_paths(
+    paths: &[String],
+    regexes: &[String],
+    pre_clone: Option<&str>,
+    reference: Option<&str>,
+) -> Result<String> {
+    debug!("Estimating paths: {:?}", paths);
+    let regexes = core_tree::compile_regexes(regexes)?;
+    let (entries, sizes_known) = tree::load_tree_entries(pre_clone, reference).await?;
+    let mut paths = paths.to_vec();
+    paths.extend(core_tree::regex_selection(&entries, &regexes));
+    let estimate = core_tree::estimate(&entries, &paths);
+
+    if !sizes_known {
+        return Ok(format!(
+            "Files: {}\nSize: unknown (blob sizes are unavailable from the remote)\n",
+            estimate.files
+        ));
+    }
+    Ok(format!(
+        "Files: {}\nSize: {} ({} bytes)\n",
+        estimate.files,
+        disk::format_size(estimate.bytes),
+        estimate.bytes
+    ))
+}
@@ -0,0 +1,74 @@
+use anyhow::{Context, Result};
+use std::env;
+use tracing::info;
+
+use crate::core::metadata::RepositoryMetadata;
+use crate::git::commands;
+use crate::utils::shell;
+
+/// What `export` prints
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    /// A shell script reproducing the clone with stock git commands
+    Script,
+
+    /// The sparse checkout patterns
+    Patterns,
+}
+
+/// Export the current partial clone for users of plain git, either as a
+/// shell script reproducing it or as the sparse checkout patterns
+pub async fn export(format: ExportFormat) -> Result<String> {
+    info!("Exporting partial clone configuration");
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+
+    let mut paths: Vec<String> = metadata.checked_out_paths.iter().cloned().collect();
+    paths.sort();
+    let patterns = commands::sparse_patterns(&paths);
+
+    if format == ExportFormat::Patterns {
+        // Suitable for `.git/info/sparse-checkout` or `git sparse-checkout set --no-cone --stdin`
+        let mut output = patterns.join("\n");
+        output.push('\n');
+        return Ok(output);
+    }
+
+    let branch = commands::run_git_command_in_dir(&current_dir, &["branch", "--show-current"])
+        .unwrap_or_default();
+    let directory = current_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "repo".to_string());
+
+    let mut clone = vec![
+        "git clone --filter=blob:none --sparse".to_string(),
+        shell::quote(&metadata.remote_url),
+    ];
+    if !branch.is_empty() {
+        clone.insert(1, format!("--branch {}", shell::quote(&branch)));
+    }
+
+    let mut output = String::new();
+    output.push_str("#!/bin/sh\n");
+    output.push_str("# Reproduces a git-partial checkout with stock git (2.27 or later)\n");
+    output.push_str("set -e\n\n");
+    output.push_str(&format!(
+        "dir=\"${{1:-{}}}\"\n",
+        directory.replace(['"', '$', '`', '\\'], "_")
+    ));
+    output.push_str(&format!("{} \"$dir\"\n", clone.join(" ")));
+    output.push_str("cd \"$dir\"\n");
+    output.push_str("git sparse-checkout set --no-cone --");
+    for pattern in &patterns {
+        output.push_str(&format!(" {}", shell::quote(pattern)));
+    }
+    output.push('\n');
+    if let Some(commit) = &metadata.last_commit {
+        output.push_str(&format!(
+            "# Last synced commit: {}\n# git checkout --detach {}\n",
+            commit, commit
+        ));
+    }
+    Ok(output)
+}
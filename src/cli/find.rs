@@ -0,0 +1,128 @@
+use anyhow::{Context, Result};
+use std::env;
+use tracing::info;
+
+use crate::cli::{add_paths, tree};
+use crate::core::fuzzy;
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::path_selector;
+use crate::core::tree::{EntryKind, TreeEntry};
+use crate::git::commands;
+use crate::utils::prompt;
+
+/// Fuzzy-search file and directory names in the tree of `reference`, by
+/// default the upstream of the current branch. With `add`, offer to add
+/// the directory containing the best match to the sparse paths.
+pub async fn find(
+    query: &str,
+    reference: Option<&str>,
+    limit: usize,
+    add: bool,
+    assume_yes: bool,
+) -> Result<()> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+
+    let (rev, entries) = match reference {
+        Some(reference) => (
+            reference.to_string(),
+            commands::list_tree_structure(&current_dir, reference)
+                .with_context(|| format!("Failed to list the tree of {}", reference))?,
+        ),
+        None => tree::upstream_tree(&current_dir)?,
+    };
+    info!(
+        "Searching {} entries of {} for '{}'",
+        entries.len(),
+        rev,
+        query
+    );
+
+    let candidates = entries.iter().map(|entry| entry.path.as_str());
+    let matches: Vec<&TreeEntry> = fuzzy::rank(query, candidates)
+        .into_iter()
+        .take(limit)
+        .filter_map(|path| entries.iter().find(|entry| entry.path == path))
+        .collect();
+    if matches.is_empty() {
+        println!("No paths in {} match '{}'.", rev, query);
+        return Ok(());
+    }
+
+    let patterns: Vec<_> = metadata
+        .checked_out_paths
+        .iter()
+        .map(|raw| path_selector::compile_sparse(raw))
+        .collect();
+    for entry in &matches {
+        let suffix = if entry.kind == EntryKind::Tree {
+            "/"
+        } else {
+            ""
+        };
+        let checked_out = patterns
+            .iter()
+            .any(|pattern| path_selector::pattern_covers(pattern, &entry.path));
+        let marker = if checked_out { "  (checked out)" } else { "" };
+        println!("{}{}{}", entry.path, suffix, marker);
+    }
+
+    if add {
+        let pattern = containing_directory_pattern(matches[0]);
+        let summary = format!("Adding '{}' would check out {}.", pattern, matches[0].path);
+        if prompt::confirm(&summary, assume_yes)? {
+            print!(
+                "{}",
+                add_paths::add_new_paths(&[pattern], assume_yes, false, false).await?
+            );
+        } else {
+            println!("Not added.");
+        }
+    }
+    Ok(())
+}
+
+/// The pattern adding a match: a directory whole, or the directory
+/// containing a file. Files at the root are added by themselves.
+fn containing_directory_pattern(entry: &TreeEntry) -> String {
+    let dir = match entry.kind {
+        EntryKind::Tree => Some(entry.path.as_str()),
+        EntryKind::Blob => entry.path.rsplit_once('/').map(|(dir, _)| dir),
+    };
+    match dir {
+        Some(dir) => format!("{}/**", dir),
+        None => entry.path.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        path: &str,
+        kind: EntryKind,
+    ) -> TreeEntry {
+        TreeEntry {
+            path: path.to_string(),
+            kind,
+            size: None,
+        }
+    }
+
+    #[test]
+    fn test_containing_directory_pattern() {
+        assert_eq!(
+            containing_directory_pattern(&entry("src/app/main.rs", EntryKind::Blob)),
+            "src/app/**"
+        );
+        assert_eq!(
+            containing_directory_pattern(&entry("docs", EntryKind::Tree)),
+            "docs/**"
+        );
+        assert_eq!(
+            containing_directory_pattern(&entry("README.md", EntryKind::Blob)),
+            "README.md"
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+use crate::core::history::{self, HistoryEntry};
+use crate::core::metadata::RepositoryMetadata;
+use crate::git::commands;
+
+/// Record a change to the sparse paths made by the running command.
+/// History is informational, so failures are logged instead of failing the command.
+pub fn record(
+    repo_path: &Path,
+    added: &[String],
+    removed: &[String],
+) {
+    if added.is_empty() && removed.is_empty() {
+        return;
+    }
+
+    let mut added = added.to_vec();
+    let mut removed = removed.to_vec();
+    added.sort();
+    removed.sort();
+    let entry = HistoryEntry {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default(),
+        command: env::args().skip(1).collect::<Vec<_>>().join(" "),
+        added,
+        removed,
+        commit: commands::get_head_commit(repo_path).ok(),
+    };
+    if let Err(e) = history::append(repo_path, &entry) {
+        warn!("Failed to record history: {:#}", e);
+    }
+}
+
+/// Show the changes to the sparse paths, newest first
+pub async fn show_history(
+    path: Option<&str>,
+    command: Option<&str>,
+    limit: Option<usize>,
+) -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+
+    let entries: Vec<HistoryEntry> = history::load(&current_dir)?
+        .into_iter()
+        .rev()
+        .filter(|entry| path.is_none_or(|path| entry.touches(path)))
+        .filter(|entry| command.is_none_or(|command| entry.subcommand() == command))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+    if entries.is_empty() {
+        return Ok("No matching history.\n".to_string());
+    }
+
+    let mut output = String::new();
+    for entry in entries {
+        let commit = entry.commit.as_deref().unwrap_or("-------");
+        output.push_str(&format!(
+            "{}  {}  {}\n",
+            history::format_timestamp(entry.timestamp),
+            &commit[..commit.len().min(7)],
+            entry.command
+        ));
+        for pattern in &entry.added {
+            output.push_str(&format!("  + {}\n", pattern));
+        }
+        for pattern in &entry.removed {
+            output.push_str(&format!("  - {}\n", pattern));
+        }
+    }
+    Ok(output)
+}
@@ -0,0 +1,207 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use crate::cli::{history, policy};
+use crate::core::error::PartialError;
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::path_selector;
+use crate::git::commands;
+
+/// Marks hooks written by git-partial so they can be replaced safely
+const HOOK_MARKER: &str = "git-partial commit guard";
+
+/// Maximum number of paths listed in a warning
+const MAX_LISTED_PATHS: usize = 20;
+
+/// Marks the hooks that keep the metadata in sync with plain git commands
+const SYNC_HOOK_MARKER: &str = "git-partial state sync";
+
+/// Hooks run after plain git moves HEAD or changes the checkout
+const SYNC_HOOKS: &[&str] = &["post-checkout", "post-merge"];
+
+/// Install a pre-commit hook that checks staged paths against the sparse
+/// patterns, and with `sync` the hooks that keep the metadata up to date
+pub async fn install_hooks(
+    block: bool,
+    force: bool,
+    sync: bool,
+) -> Result<()> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+
+    // Only partial clones have sparse patterns to guard
+    RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+
+    let hooks_dir =
+        commands::hooks_dir(&current_dir).context("Failed to locate hooks directory")?;
+    fs::create_dir_all(&hooks_dir)
+        .with_context(|| format!("Failed to create directory: {:?}", hooks_dir))?;
+
+    // Call this binary by absolute path so the hook works without git-partial on PATH
+    let exe = env::current_exe().context("Failed to locate the git-partial executable")?;
+    let command = format!(
+        "\"{}\" check-staged{}",
+        exe.display(),
+        if block { " --block" } else { "" }
+    );
+    let hook_path = hooks_dir.join("pre-commit");
+    write_hook(&hook_path, HOOK_MARKER, &command, force)?;
+    println!(
+        "Installed pre-commit hook at {} ({} mode)",
+        hook_path.display(),
+        if block { "block" } else { "warn" }
+    );
+
+    if sync {
+        let command = format!("\"{}\" sync-state", exe.display());
+        for name in SYNC_HOOKS {
+            let hook_path = hooks_dir.join(name);
+            write_hook(&hook_path, SYNC_HOOK_MARKER, &command, force)?;
+            println!("Installed {} hook at {}", name, hook_path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Write a hook running `command`, refusing to replace a hook that was not
+/// written by git-partial unless `force` is given
+fn write_hook(
+    hook_path: &Path,
+    marker: &str,
+    command: &str,
+    force: bool,
+) -> Result<()> {
+    if hook_path.exists() && !force {
+        let existing = fs::read_to_string(hook_path).unwrap_or_default();
+        if !existing.contains(marker) {
+            anyhow::bail!(
+                "A {} hook already exists at {:?}. Re-run with --force to replace it.",
+                hook_path.file_name().unwrap_or_default().to_string_lossy(),
+                hook_path
+            );
+        }
+    }
+
+    let script = format!(
+        "#!/bin/sh\n# {} (installed by `git-partial install-hooks`)\nexec {}\n",
+        marker, command
+    );
+    fs::write(hook_path, script)
+        .with_context(|| format!("Failed to write hook: {:?}", hook_path))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(hook_path, fs::Permissions::from_mode(0o755))
+            .with_context(|| format!("Failed to make hook executable: {:?}", hook_path))?;
+    }
+    Ok(())
+}
+
+/// Bring the metadata up to date after plain git commands (run by the
+/// post-checkout and post-merge hooks): record where HEAD is now, and take
+/// over sparse patterns changed with `git sparse-checkout`. Nothing is done
+/// when the hook was triggered by git-partial itself.
+pub async fn sync_state() -> Result<()> {
+    if env::var_os(commands::INTERNAL_ENV).is_some() {
+        return Ok(());
+    }
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let mut metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+    let mut changes = Vec::new();
+
+    let head = commands::get_head_commit(&current_dir).context("Failed to get HEAD commit")?;
+    if metadata.last_commit.as_deref() != Some(head.as_str()) {
+        metadata.set_last_commit(&head);
+        changes.push(format!("HEAD is now {}", &head[..7.min(head.len())]));
+    }
+
+    let current: Vec<String> = metadata.checked_out_paths.iter().cloned().collect();
+    if let Some((cone, applied)) = commands::applied_sparse_patterns(&current_dir) {
+        let (wanted_cone, mut wanted) = commands::wanted_sparse_patterns(&current);
+        let mut sorted = applied.clone();
+        sorted.sort();
+        wanted.sort();
+        if (cone, sorted) != (wanted_cone, wanted) {
+            let paths: HashSet<String> = applied
+                .iter()
+                .map(|pattern| pattern.trim_start_matches('/').to_string())
+                .collect();
+            let added: Vec<String> = paths
+                .difference(&metadata.checked_out_paths)
+                .cloned()
+                .collect();
+            let removed: Vec<String> = metadata
+                .checked_out_paths
+                .difference(&paths)
+                .cloned()
+                .collect();
+            info!(
+                "Sparse patterns changed outside git-partial: +{:?} -{:?}",
+                added, removed
+            );
+            history::record(&current_dir, &added, &removed);
+            // Git already applied the patterns, so a violation can only be reported
+            let patterns: Vec<String> = paths.iter().cloned().collect();
+            if let Err(e) =
+                policy::enforce(&current_dir, &metadata.remote_url, &patterns, false).await
+            {
+                eprintln!("git-partial: warning: {}", e);
+            }
+            metadata.checked_out_paths = paths;
+            changes.push("sparse paths taken from the sparse checkout".to_string());
+        }
+    }
+
+    if changes.is_empty() {
+        return Ok(());
+    }
+    metadata
+        .save(&current_dir)
+        .context("Failed to save metadata")?;
+    eprintln!("git-partial: updated metadata ({})", changes.join(", "));
+    Ok(())
+}
+
+/// Check staged paths against the sparse patterns.
+/// Warns about paths outside the checkout, or fails in block mode.
+/// Always fails for paths pinned at a commit.
+pub async fn check_staged(block: bool) -> Result<()> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+
+    let staged = commands::staged_paths(&current_dir).context("Failed to list staged paths")?;
+    let pinned = path_selector::covered_paths(&staged, metadata.pinned_revisions.keys());
+    if !pinned.is_empty() {
+        return Err(PartialError::StagedPinned(pinned).into());
+    }
+    let outside = path_selector::outside_patterns(&staged, &metadata.checked_out_paths);
+    info!(
+        "{} of {} staged path(s) outside the sparse checkout",
+        outside.len(),
+        staged.len()
+    );
+
+    if outside.is_empty() {
+        return Ok(());
+    }
+    if block {
+        return Err(PartialError::StagedOutsideSparse(outside).into());
+    }
+
+    eprintln!(
+        "warning: {} staged path(s) fall outside the sparse checkout:",
+        outside.len()
+    );
+    for path in outside.iter().take(MAX_LISTED_PATHS) {
+        eprintln!("  {}", path);
+    }
+    if outside.len() > MAX_LISTED_PATHS {
+        eprintln!("  ... and {} more", outside.len() - MAX_LISTED_PATHS);
+    }
+    eprintln!("These are often accidental deletions caused by sparse checkout state.");
+    Ok(())
+}
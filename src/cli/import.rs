@@ -0,0 +1,54 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::fs;
+use tracing::info;
+
+use crate::cli::adopt;
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::path_selector;
+use crate::git::commands;
+
+/// Take over a sparse checkout configured with plain git by converting
+/// `.git/info/sparse-checkout` into git-partial metadata
+pub async fn import_repository() -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+
+    if RepositoryMetadata::exists(&current_dir) {
+        anyhow::bail!("This repository is already managed by git-partial");
+    }
+    if commands::config_value(&current_dir, "core.sparseCheckout").as_deref() != Some("true") {
+        anyhow::bail!("This repository is not using sparse checkout");
+    }
+
+    // Resolves to the per-worktree file in linked worktrees
+    let sparse_file = current_dir.join(
+        commands::run_git_command_in_dir(
+            &current_dir,
+            &["rev-parse", "--git-path", "info/sparse-checkout"],
+        )
+        .context("Failed to locate the sparse checkout file")?,
+    );
+    let content = fs::read_to_string(&sparse_file)
+        .with_context(|| format!("Failed to read {:?}", sparse_file))?;
+
+    let cone =
+        commands::config_value(&current_dir, "core.sparseCheckoutCone").as_deref() == Some("true");
+    info!(
+        "Importing {} sparse checkout from {:?}",
+        if cone { "cone mode" } else { "non-cone" },
+        sparse_file
+    );
+    let paths = if cone {
+        let dirs = path_selector::parse_cone_file(&content);
+        path_selector::cone_to_patterns(&dirs, &adopt::root_files(&current_dir)?)
+    } else {
+        path_selector::parse_pattern_file(&content)
+    };
+
+    let metadata = adopt::take_over(&current_dir, &paths)?;
+    Ok(format!(
+        "Imported {} path(s) from {}\n",
+        metadata.checked_out_paths.len(),
+        sparse_file.display()
+    ))
+}
@@ -0,0 +1,125 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::path::Path;
+use tracing::info;
+
+use crate::core::config;
+use crate::core::large_files::{self, SkippedBlob};
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::path_selector;
+use crate::git::commands;
+
+/// List the versions of files under the sparse paths that the clone's filter
+/// (e.g. `blob:limit=1m`) left on the remote, or fetch those of `fetch`.
+pub async fn large_files(fetch: &[String]) -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+    let patterns: Vec<String> = metadata.checked_out_paths.iter().cloned().collect();
+    if metadata.filter_ignored {
+        return Ok(format!(
+            "The server ignored the partial clone filter ({}); every version of every file is local.\n",
+            metadata.filter.as_deref().unwrap_or(config::DEFAULT_FILTER)
+        ));
+    }
+    let skipped = skipped_blobs(&current_dir, &patterns)?;
+
+    if !fetch.is_empty() {
+        return fetch_blobs(&current_dir, &skipped, fetch);
+    }
+
+    let filter = metadata
+        .filter
+        .or_else(|| commands::config_value(&current_dir, "remote.origin.partialclonefilter"))
+        .unwrap_or_else(|| config::DEFAULT_FILTER.to_string());
+    if skipped.is_empty() {
+        return Ok(format!(
+            "Every version of the files under the sparse paths is local (filter {}).\n",
+            filter
+        ));
+    }
+    let mut output = match config::blob_limit(&filter) {
+        Some(limit) => format!(
+            "Versions over {} under the sparse paths left on the remote (filter {}):\n",
+            crate::utils::disk::format_size(limit),
+            filter
+        ),
+        None => format!(
+            "Versions under the sparse paths left on the remote (filter {}):\n",
+            filter
+        ),
+    };
+    let width = skipped
+        .iter()
+        .map(|blob| blob.path.len())
+        .max()
+        .unwrap_or(0);
+    for blob in &skipped {
+        output.push_str(&format!(
+            "  {:width$}  {}\n",
+            blob.path,
+            &blob.id[..7.min(blob.id.len())],
+            width = width
+        ));
+    }
+    output.push_str("Fetch them with 'git-partial large-files --fetch <path>...'.\n");
+    Ok(output)
+}
+
+/// Versions of the files matching `patterns` in the history of HEAD that
+/// are missing locally, newest first. Listing them fetches nothing.
+pub fn skipped_blobs(
+    repo_path: &Path,
+    patterns: &[String],
+) -> Result<Vec<SkippedBlob>> {
+    if patterns.is_empty() {
+        return Ok(Vec::new());
+    }
+    let pathspecs: Vec<String> = patterns
+        .iter()
+        .map(|pattern| format!(":(glob){}", pattern.trim_start_matches('/')))
+        .collect();
+    let mut args = vec![
+        "log",
+        "--raw",
+        "--no-abbrev",
+        "--no-renames",
+        "--format=",
+        "HEAD",
+        "--",
+    ];
+    args.extend(pathspecs.iter().map(String::as_str));
+    let history = commands::run_git_command_in_dir(repo_path, &args)
+        .context("Failed to read the history of the sparse paths")?;
+    let missing = commands::run_git_command_in_dir(
+        repo_path,
+        &["rev-list", "--objects", "--missing=print", "HEAD"],
+    )
+    .context("Failed to list missing objects")?;
+    Ok(large_files::skipped_blobs(
+        &large_files::parse_raw_log(&history),
+        &missing,
+    ))
+}
+
+/// Download the skipped versions of the files matching `paths`. Git fetches
+/// a missing blob from the promisor remote when it is looked up.
+fn fetch_blobs(
+    repo_path: &Path,
+    skipped: &[SkippedBlob],
+    paths: &[String],
+) -> Result<String> {
+    let selected: Vec<&SkippedBlob> = skipped
+        .iter()
+        .filter(|blob| {
+            paths.iter().any(|path| {
+                path_selector::pattern_covers(&path_selector::compile_sparse(path), &blob.path)
+            })
+        })
+        .collect();
+    for blob in &selected {
+        info!("Fetching {} ({})", blob.path, blob.id);
+        commands::object_info(repo_path, &blob.id)?
+            .with_context(|| format!("Failed to fetch {} ({})", blob.path, blob.id))?;
+    }
+    Ok(format!("Fetched {} version(s).\n", selected.len()))
+}
@@ -0,0 +1,51 @@
+use anyhow::{Context, Result};
+use std::env;
+use tracing::info;
+
+use crate::core::lfs;
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::path_selector;
+use crate::git::{commands, lfs as git_lfs};
+
+/// Report which LFS objects are fetched and which are skipped by the sparse patterns
+pub async fn show_lfs_status() -> Result<String> {
+    info!("Collecting LFS status");
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+
+    let lfs_patterns = git_lfs::tracked_patterns(&current_dir);
+    if lfs_patterns.is_empty() {
+        return Ok("This repository does not use Git LFS.\n".to_string());
+    }
+
+    let sparse_patterns: Vec<_> = metadata
+        .checked_out_paths
+        .iter()
+        .map(|raw| path_selector::compile_sparse(raw))
+        .collect();
+    let files = commands::list_tree_files(&current_dir, "HEAD")
+        .context("Failed to list repository files")?;
+
+    let (fetched, skipped): (Vec<String>, Vec<String>) = files
+        .into_iter()
+        .filter(|path| lfs::is_tracked(&lfs_patterns, path))
+        .partition(|path| {
+            sparse_patterns
+                .iter()
+                .any(|pattern| path_selector::pattern_covers(pattern, path))
+        });
+
+    let fetch_include =
+        commands::run_git_command_in_dir(&current_dir, &["config", "lfs.fetchinclude"])
+            .unwrap_or_else(|_| "(not set)".to_string());
+
+    let mut output = String::new();
+    output.push_str(&format!("LFS patterns: {}\n", lfs_patterns.join(", ")));
+    output.push_str(&format!("lfs.fetchinclude: {}\n", fetch_include));
+    output.push_str(&format!("Fetched: {} object(s)\n", fetched.len()));
+    output.push_str(&format!("Skipped: {} object(s)\n", skipped.len()));
+    for path in &skipped {
+        output.push_str(&format!("  {}\n", path));
+    }
+    Ok(output)
+}
@@ -0,0 +1,108 @@
+use anyhow::{Context, Result};
+use std::env;
+use tracing::{info, warn};
+
+use crate::core::error::PartialError;
+use crate::core::metadata::RepositoryMetadata;
+use crate::git::commands;
+use crate::git::pins;
+
+/// List the sparse paths, marking pinned ones
+pub async fn list_paths() -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+
+    let mut paths: Vec<&String> = metadata.checked_out_paths.iter().collect();
+    paths.sort();
+
+    let mut output = String::new();
+    for path in paths {
+        if let Some(commit) = metadata.pinned_revisions.get(path) {
+            output.push_str(&format!(
+                "{} (pinned at {})\n",
+                path,
+                &commit[..commit.len().min(7)]
+            ));
+        } else if metadata.pinned_paths.contains(path) {
+            output.push_str(&format!("{} (pinned)\n", path));
+        } else {
+            output.push_str(&format!("{}\n", path));
+        }
+    }
+    Ok(output)
+}
+
+/// Mark or unmark paths as pinned so they cannot be dropped without `--force`
+pub async fn set_pinned(
+    paths: &[String],
+    pinned: bool,
+) -> Result<()> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let mut metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+
+    for path in paths {
+        if !metadata.checked_out_paths.contains(path) {
+            warn!("Path '{}' is not in the sparse checkout, skipping", path);
+            continue;
+        }
+        if pinned {
+            metadata.pinned_paths.insert(path.clone());
+        } else {
+            if let Some(commit) = metadata.pinned_revisions.remove(path) {
+                pins::release(&current_dir, path, &commit)?;
+            }
+            metadata.pinned_paths.remove(path);
+        }
+    }
+
+    metadata
+        .save(&current_dir)
+        .context("Failed to save updated metadata")?;
+    info!("Pinned paths: {:?}", metadata.pinned_paths);
+    Ok(())
+}
+
+/// Pin paths and check them out as of `revision`, while the rest of the
+/// checkout follows the branch. `smart-pull` leaves them at that commit until
+/// they are unpinned or pinned elsewhere.
+pub async fn pin_at(
+    paths: &[String],
+    revision: &str,
+) -> Result<()> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let mut metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+    let commit = commands::run_git_command_in_dir(
+        &current_dir,
+        &["rev-parse", "--verify", &format!("{}^{{commit}}", revision)],
+    )
+    .with_context(|| format!("Unknown commit: {}", revision))?;
+
+    for path in paths {
+        if !metadata.checked_out_paths.contains(path) {
+            warn!("Path '{}' is not in the sparse checkout, skipping", path);
+            continue;
+        }
+        // Move from an earlier pin without losing edits made since
+        if let Some(previous) = metadata.pinned_revisions.get(path) {
+            if pins::has_local_changes(&current_dir, path, previous)? {
+                return Err(PartialError::PinnedPathChanged {
+                    path: path.clone(),
+                    commit: previous.clone(),
+                }
+                .into());
+            }
+            pins::release(&current_dir, path, previous)?;
+        }
+        pins::apply(&current_dir, path, &commit)?;
+        metadata.pinned_paths.insert(path.clone());
+        metadata
+            .pinned_revisions
+            .insert(path.clone(), commit.clone());
+    }
+
+    metadata
+        .save(&current_dir)
+        .context("Failed to save updated metadata")?;
+    info!("Pinned revisions: {:?}", metadata.pinned_revisions);
+    Ok(())
+}
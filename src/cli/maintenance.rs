@@ -0,0 +1,38 @@
+use anyhow::{Context, Result};
+use std::env;
+use tracing::info;
+
+use crate::cli::dematerialize;
+use crate::core::config;
+use crate::git::maintenance;
+
+/// Keep the repository's history fast to query: run the maintenance tasks
+/// now, or register the repository for background maintenance by git.
+/// Running the tasks also prunes the objects of removed paths once
+/// `gitpartial.pruneAfter` has passed.
+pub async fn maintain(
+    register: bool,
+    unregister: bool,
+) -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    if register {
+        maintenance::register(&current_dir).context("Failed to register for maintenance")?;
+        return Ok(
+            "Registered for background maintenance. Run 'git maintenance start' once on \
+                   this machine to schedule it.\n"
+                .to_string(),
+        );
+    }
+    if unregister {
+        maintenance::unregister(&current_dir).context("Failed to unregister from maintenance")?;
+        return Ok("Unregistered from background maintenance.\n".to_string());
+    }
+
+    info!("Running maintenance tasks {:?}", maintenance::TASKS);
+    maintenance::run(&current_dir).context("Failed to run maintenance")?;
+    let mut output = format!("Ran {}.\n", maintenance::TASKS.join(", "));
+    if let Some(retention) = config::prune_after(&current_dir)? {
+        output.extend(dematerialize::prune_removed(&current_dir, retention)?);
+    }
+    Ok(output)
+}
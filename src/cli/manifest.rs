@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use crate::cli::policy::Overrides;
+use crate::cli::{add_paths, clone, sync};
+use crate::core::config;
+use crate::core::lock::RepoLock;
+use crate::core::manifest::{Manifest, ManifestRepository, WORKSPACE_FILE};
+use crate::core::path_selector;
+use crate::remote::auth;
+
+/// Clone every repository of the manifest at `manifest_path` into its own
+/// directory of `workspace`, with its own paths. The manifest is copied to
+/// the workspace root first, so `sync` there can finish an interrupted
+/// clone. Returns the directories of the clones.
+pub async fn clone_manifest(
+    manifest_path: &str,
+    workspace: &str,
+    overrides: Overrides,
+) -> Result<Vec<PathBuf>> {
+    let content = fs::read_to_string(manifest_path)
+        .with_context(|| format!("Failed to read manifest {}", manifest_path))?;
+    let manifest =
+        Manifest::parse(&content).with_context(|| format!("Failed to parse {}", manifest_path))?;
+
+    let workspace = Path::new(workspace);
+    if workspace.join(WORKSPACE_FILE).exists() {
+        anyhow::bail!(
+            "'{}' is already a workspace; run 'git-partial sync' there to update it.",
+            workspace.display()
+        );
+    }
+    fs::create_dir_all(workspace)
+        .with_context(|| format!("Failed to create {}", workspace.display()))?;
+    fs::write(workspace.join(WORKSPACE_FILE), content)
+        .context("Failed to write the workspace manifest")?;
+
+    let mut dirs = Vec::new();
+    for repository in &manifest.repositories {
+        dirs.push(clone_into(workspace, repository, overrides).await?);
+    }
+    Ok(dirs)
+}
+
+async fn clone_into(
+    workspace: &Path,
+    repository: &ManifestRepository,
+    overrides: Overrides,
+) -> Result<PathBuf> {
+    let dir = workspace.join(repository.dir());
+    let paths = resolve_paths(repository, workspace)?;
+    println!(
+        "Cloning repository: {} to {} with paths: {:?}",
+        repository.url,
+        dir.display(),
+        paths
+    );
+    auth::apply_to_git(&repository.url);
+    clone::clone_repository(
+        &repository.url,
+        &dir.to_string_lossy(),
+        &paths,
+        None,
+        None,
+        overrides,
+    )
+    .await
+    .with_context(|| format!("Failed to clone {}", repository.url))?;
+    Ok(dir)
+}
+
+/// Bring each repository of the workspace at `workspace` in line with its
+/// manifest: clone the missing ones, fetch the paths queued while offline
+/// and check out paths added to the manifest since. Paths dropped from the
+/// manifest stay checked out; `remove-paths` in the repository removes
+/// them. Every repository is visited even when one fails.
+pub async fn sync_workspace(
+    workspace: &Path,
+    manifest: &Manifest,
+) -> Result<()> {
+    let mut failed = Vec::new();
+    for repository in &manifest.repositories {
+        let dir = workspace.join(repository.dir());
+        let result = if dir.exists() {
+            println!("{}:", repository.dir());
+            sync_repository(&dir, repository).await
+        } else {
+            clone_into(workspace, repository, Overrides::default())
+                .await
+                .map(|_| ())
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to sync {}: {:#}", repository.dir(), e);
+            failed.push(repository.dir());
+        }
+    }
+    env::set_current_dir(workspace)?;
+    if !failed.is_empty() {
+        anyhow::bail!("Failed to sync {}", failed.join(", "));
+    }
+    Ok(())
+}
+
+async fn sync_repository(
+    dir: &Path,
+    repository: &ManifestRepository,
+) -> Result<()> {
+    let _lock = RepoLock::acquire(dir, true)?;
+    env::set_current_dir(dir).with_context(|| format!("Failed to enter {}", dir.display()))?;
+    info!("Syncing {}", dir.display());
+    auth::apply_to_git(&repository.url);
+    sync::sync().await?;
+    let paths = resolve_paths(repository, dir)?;
+    if !paths.is_empty() {
+        print!(
+            "{}",
+            add_paths::add_new_paths(&paths, true, false, false).await?
+        );
+    }
+    Ok(())
+}
+
+/// The repository's paths with their `${NAME}` variables substituted and
+/// their braces expanded, as for paths given on the command line
+fn resolve_paths(
+    repository: &ManifestRepository,
+    repo_path: &Path,
+) -> Result<Vec<String>> {
+    let paths = config::substitute_variables(&repository.paths, repo_path)?;
+    Ok(path_selector::expand_patterns(&paths))
+}
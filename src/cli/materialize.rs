@@ -0,0 +1,82 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::env;
+use std::path::Path;
+use tracing::info;
+
+use crate::cli::large_files;
+use crate::core::large_files::SkippedBlob;
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::path_selector;
+use crate::git::commands;
+
+/// Download every version of the files matching `patterns` (the sparse
+/// paths when empty), or only their version at HEAD, so history commands
+/// such as `git log -p` and `git blame` work offline for them
+pub async fn materialize(
+    patterns: &[String],
+    head_only: bool,
+) -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let patterns: Vec<String> = if patterns.is_empty() {
+        let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+        metadata.checked_out_paths.iter().cloned().collect()
+    } else {
+        patterns.to_vec()
+    };
+
+    let missing = if head_only {
+        missing_at(&current_dir, "HEAD", &patterns)?
+    } else {
+        large_files::skipped_blobs(&current_dir, &patterns)?
+    };
+    let scope = if head_only { "at HEAD" } else { "in history" };
+    if missing.is_empty() {
+        return Ok(format!(
+            "Every version {} of the matching files is already local.\n",
+            scope
+        ));
+    }
+
+    // A blob shared by several paths or commits is fetched once
+    let mut seen = HashSet::new();
+    let ids: Vec<String> = missing
+        .iter()
+        .filter(|blob| seen.insert(blob.id.as_str()))
+        .map(|blob| blob.id.clone())
+        .collect();
+    info!("Fetching {} blob(s) for {:?}", ids.len(), patterns);
+    commands::fetch_objects(&current_dir, &ids).context("Failed to fetch the missing blobs")?;
+    Ok(format!(
+        "Fetched {} blob(s) {} for {}.\n",
+        ids.len(),
+        scope,
+        patterns.join(", ")
+    ))
+}
+
+/// Files at `rev` matching `patterns` whose content is missing locally
+pub fn missing_at(
+    repo_path: &Path,
+    rev: &str,
+    patterns: &[String],
+) -> Result<Vec<SkippedBlob>> {
+    let compiled: Vec<_> = patterns
+        .iter()
+        .map(|pattern| path_selector::compile_sparse(pattern))
+        .collect();
+    let files: Vec<(String, String)> = commands::list_blob_ids(repo_path, rev)?
+        .into_iter()
+        .filter(|(_, path)| {
+            compiled
+                .iter()
+                .any(|pattern| path_selector::pattern_covers(pattern, path))
+        })
+        .collect();
+    let missing = commands::run_git_command_in_dir(
+        repo_path,
+        &["rev-list", "--objects", "--missing=print", "--no-walk", rev],
+    )
+    .context("Failed to list missing objects")?;
+    Ok(crate::core::large_files::skipped_blobs(&files, &missing))
+}
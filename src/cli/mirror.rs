@@ -0,0 +1,65 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::git::commands;
+
+/// Create a bare blobless mirror that partial clones can fetch through with `clone --via`.
+/// The mirror carries commits and trees; clones fetch blobs from the repository itself.
+pub async fn create_mirror(
+    repo_url: &str,
+    dir: &str,
+) -> Result<()> {
+    info!("Creating blobless mirror of {} in {}", repo_url, dir);
+    commands::run_git_command_in_dir(
+        Path::new("."),
+        &[
+            "clone",
+            "--mirror",
+            "--filter=blob:none",
+            "--",
+            repo_url,
+            dir,
+        ],
+    )
+    .with_context(|| format!("Failed to create mirror in {}", dir))?;
+
+    // Clones are blobless too and ask for objects by id
+    let dir_path = Path::new(dir);
+    commands::run_git_command_in_dir(dir_path, &["config", "uploadpack.allowFilter", "true"])?;
+    commands::run_git_command_in_dir(
+        dir_path,
+        &["config", "uploadpack.allowAnySHA1InWant", "true"],
+    )?;
+
+    println!(
+        "Mirror ready. Keep it fresh with 'git-partial mirror update {} --interval 300' or a cron job.",
+        dir
+    );
+    Ok(())
+}
+
+/// Fetch new history into a mirror, once or every `interval` seconds.
+/// When running periodically, failed updates are logged and retried.
+pub async fn update_mirror(
+    dir: &str,
+    interval: Option<u64>,
+) -> Result<()> {
+    loop {
+        info!("Updating mirror {}", dir);
+        let result =
+            commands::run_git_command_in_dir(dir, &["fetch", "--prune", "--quiet", "origin"])
+                .with_context(|| format!("Failed to update mirror {}", dir));
+        match result {
+            Ok(_) => println!("Updated mirror {}", dir),
+            Err(e) if interval.is_some() => warn!("{:#}", e),
+            Err(e) => return Err(e),
+        }
+
+        let Some(seconds) = interval else {
+            return Ok(());
+        };
+        tokio::time::sleep(Duration::from_secs(seconds)).await;
+    }
+}
@@ -1,4 +1,59 @@
+pub mod add_crate;
+pub mod add_module;
+pub mod add_package;
 pub mod add_paths;
+pub mod add_project;
+pub mod adopt;
+pub mod auth;
+pub mod bench;
+pub mod blame;
+pub mod branches;
+pub mod bundle;
+pub mod cat_tree;
+pub mod changed_files;
+pub mod check;
 pub mod clone;
+pub mod config;
+pub mod dematerialize;
+pub mod du;
+pub mod estimate;
+pub mod export;
+pub mod find;
+pub mod history;
+pub mod hooks;
+pub mod import;
+pub mod large_files;
+pub mod lfs_status;
+pub mod list_paths;
+pub mod maintenance;
+pub mod manifest;
+pub mod materialize;
+pub mod mirror;
+pub mod narrow_fetch;
+pub mod open;
+pub mod plugins;
+pub mod policy;
+pub mod profile;
+pub mod projects;
+pub mod pull_conflicts;
+pub mod recover;
+pub mod remove_paths;
+pub mod repair;
+pub mod replay;
+pub mod root_mount;
+pub mod savings;
+pub mod self_update;
+pub mod serve_cache;
+pub mod serve_http;
+pub mod show;
 pub mod smart_pull;
 pub mod status;
+pub mod sync;
+pub mod targets;
+pub mod top;
+pub mod tree;
+pub mod ui;
+pub mod watch;
+pub mod what_changed;
+pub mod why_missing;
+pub mod worktree;
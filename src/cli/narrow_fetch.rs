@@ -0,0 +1,201 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::core::fetch_refspec;
+use crate::core::metadata::RepositoryMetadata;
+use crate::git::commands;
+
+/// Git config key of `origin`'s fetch refspecs
+const FETCH_KEY: &str = "remote.origin.fetch";
+
+/// Narrow `origin`'s fetch refspec to the current branch and the requested
+/// ones, adding the branches of `add` and dropping those of `remove`, so a
+/// fetch no longer negotiates every ref of a large repository. The added
+/// branches are fetched right away, and the remote-tracking refs no longer
+/// fetched are deleted. `all` restores fetching every branch.
+pub async fn narrow_fetch(
+    add: &[String],
+    remove: &[String],
+    all: bool,
+) -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    narrow(&current_dir, add, remove, all)
+}
+
+/// `narrow-fetch` for the clone at `repo_path`, also run by `clone --narrow-fetch`
+pub fn narrow(
+    repo_path: &Path,
+    add: &[String],
+    remove: &[String],
+    all: bool,
+) -> Result<String> {
+    let mut metadata = RepositoryMetadata::load(repo_path).context("Failed to load metadata")?;
+    if metadata.bare {
+        anyhow::bail!(
+            "narrow-fetch does not apply to bare clones, which fetch into their branches"
+        );
+    }
+
+    if all {
+        set_refspecs(repo_path, &[fetch_refspec::WIDE.to_string()])?;
+        metadata.fetch_branches = None;
+        metadata
+            .save(repo_path)
+            .context("Failed to save metadata")?;
+        return Ok(
+            "Fetching every branch of origin again; the next fetch brings in the others.\n"
+                .to_string(),
+        );
+    }
+
+    let mut requested = metadata.fetch_branches.clone().unwrap_or_default();
+    requested.extend(add.iter().cloned());
+    requested.retain(|branch| !remove.contains(branch));
+    requested.sort();
+    requested.dedup();
+    let current = commands::current_branch(repo_path);
+    let branches = fetch_refspec::branches(current.as_deref(), &requested);
+    if branches.is_empty() {
+        anyhow::bail!("HEAD is detached; name the branches to fetch");
+    }
+
+    // Fetched before anything changes, so a misspelled branch changes nothing
+    if !add.is_empty() {
+        let refspecs: Vec<String> = add
+            .iter()
+            .map(|branch| fetch_refspec::branch_refspec(branch))
+            .collect();
+        let mut args = vec!["fetch", "--quiet", "origin"];
+        args.extend(refspecs.iter().map(String::as_str));
+        commands::run_git_command_in_dir(repo_path, &args)
+            .with_context(|| format!("Failed to fetch {}", add.join(", ")))?;
+    }
+
+    metadata.fetch_branches = Some(requested);
+    apply(repo_path, &metadata)?;
+    let pruned = prune_tracking_refs(repo_path, &branches)?;
+    metadata
+        .save(repo_path)
+        .context("Failed to save metadata")?;
+
+    let mut output = format!("Fetching only: {}\n", branches.join(", "));
+    if pruned > 0 {
+        output.push_str(&format!(
+            "Deleted {} remote-tracking ref(s) no longer fetched.\n",
+            pruned
+        ));
+    }
+    Ok(output)
+}
+
+/// Fetch `branch` ahead of switching to it in a clone with a narrowed
+/// refspec, where its remote-tracking ref may be missing. The refspec
+/// covers it from then on, which `git switch` needs to create the local
+/// branch from it. A failure is only logged; `git switch` reports a
+/// branch that does not exist.
+pub fn fetch_for_switch(
+    repo_path: &Path,
+    metadata: &RepositoryMetadata,
+    branch: &str,
+) {
+    let Some(requested) = &metadata.fetch_branches else {
+        return;
+    };
+    let local = commands::run_git_command_in_dir(
+        repo_path,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("refs/heads/{}", branch),
+        ],
+    );
+    if local.is_ok() {
+        return;
+    }
+    let refspec = fetch_refspec::branch_refspec(branch);
+    let mut requested = requested.clone();
+    requested.push(branch.to_string());
+    let current = commands::current_branch(repo_path);
+    let refspecs: Vec<String> = fetch_refspec::branches(current.as_deref(), &requested)
+        .iter()
+        .map(|branch| fetch_refspec::branch_refspec(branch))
+        .collect();
+    let fetched =
+        commands::run_git_command_in_dir(repo_path, &["fetch", "--quiet", "origin", &refspec])
+            .and_then(|_| set_refspecs(repo_path, &refspecs));
+    if let Err(e) = fetched {
+        warn!("Failed to fetch {}: {:#}", branch, e);
+    }
+}
+
+/// Rewrite the narrowed refspec for the branch now checked out, after a
+/// switch. A failure is only logged, as the switch itself is complete.
+pub fn refresh(
+    repo_path: &Path,
+    metadata: &RepositoryMetadata,
+) {
+    if let Err(e) = apply(repo_path, metadata) {
+        warn!("Failed to update the fetch refspec: {:#}", e);
+    }
+}
+
+/// Write the refspecs of the current and requested branches, if narrowed
+pub fn apply(
+    repo_path: &Path,
+    metadata: &RepositoryMetadata,
+) -> Result<()> {
+    let Some(requested) = &metadata.fetch_branches else {
+        return Ok(());
+    };
+    let current = commands::current_branch(repo_path);
+    let refspecs: Vec<String> = fetch_refspec::branches(current.as_deref(), requested)
+        .iter()
+        .map(|branch| fetch_refspec::branch_refspec(branch))
+        .collect();
+    if refspecs.is_empty() {
+        // A detached HEAD keeps fetching what it fetched before
+        return Ok(());
+    }
+    set_refspecs(repo_path, &refspecs)
+}
+
+fn set_refspecs(
+    repo_path: &Path,
+    refspecs: &[String],
+) -> Result<()> {
+    // Fails when there is nothing to unset
+    let _ = commands::run_git_command_in_dir(repo_path, &["config", "--unset-all", FETCH_KEY]);
+    for refspec in refspecs {
+        commands::run_git_command_in_dir(repo_path, &["config", "--add", FETCH_KEY, refspec])
+            .with_context(|| format!("Failed to set {}", FETCH_KEY))?;
+    }
+    info!("Fetch refspec set to {:?}", refspecs);
+    Ok(())
+}
+
+/// Delete the remote-tracking refs of branches no longer fetched, which
+/// would otherwise go stale; returns how many were deleted
+fn prune_tracking_refs(
+    repo_path: &Path,
+    branches: &[String],
+) -> Result<usize> {
+    let tracking: Vec<String> = commands::run_git_command_in_dir(
+        repo_path,
+        &[
+            "for-each-ref",
+            "--format=%(refname)",
+            "refs/remotes/origin/",
+        ],
+    )?
+    .lines()
+    .map(str::to_string)
+    .collect();
+    let stale = fetch_refspec::stale_refs(&tracking, branches);
+    if !stale.is_empty() {
+        commands::delete_refs(repo_path, &stale).context("Failed to delete stale refs")?;
+    }
+    Ok(stale.len())
+}
@@ -0,0 +1,115 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::Command;
+use tracing::info;
+
+use crate::cli::add_paths;
+use crate::cli::why_missing::{self, Diagnosis};
+use crate::core::config;
+use crate::core::journal::Journal;
+use crate::core::lock::RepoLock;
+use crate::core::metadata::RepositoryMetadata;
+use crate::git::commands;
+use crate::utils::prompt;
+
+/// Open the repository, or `path` in it, in the editor. A path that exists
+/// at HEAD but is outside the sparse paths is added first, after asking
+/// unless `assume_yes`.
+pub async fn open(
+    path: Option<&str>,
+    editor: Option<String>,
+    assume_yes: bool,
+) -> Result<()> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+    let editor = config::editor(editor, &current_dir)?;
+
+    let path = path
+        .map(|path| path.trim_start_matches("./").trim_end_matches('/'))
+        .filter(|path| !path.is_empty());
+    let target = match path {
+        Some(path) => {
+            if !current_dir.join(path).exists() {
+                check_out(&current_dir, &metadata, path, assume_yes).await?;
+            }
+            current_dir.join(path)
+        }
+        None => current_dir.clone(),
+    };
+
+    launch(&editor, &target, &current_dir)
+}
+
+/// Add the pattern that checks out `path`, which is missing from disk
+async fn check_out(
+    repo_path: &Path,
+    metadata: &RepositoryMetadata,
+    path: &str,
+    assume_yes: bool,
+) -> Result<()> {
+    let mut patterns: Vec<String> = metadata.checked_out_paths.iter().cloned().collect();
+    patterns.sort();
+    let entries =
+        commands::list_tree_structure(repo_path, "HEAD").context("Failed to list HEAD tree")?;
+
+    match why_missing::diagnose(path, &entries, &patterns) {
+        Diagnosis::Excluded { suggested_pattern } => {
+            let summary = format!(
+                "'{}' is not checked out. Adding the pattern '{}' would check it out.",
+                path, suggested_pattern
+            );
+            if !prompt::confirm(&summary, assume_yes)? {
+                anyhow::bail!(
+                    "Not opened. Run: git-partial add-paths '{}'",
+                    suggested_pattern
+                );
+            }
+            // Held only while adding, not while the editor runs
+            let _lock = RepoLock::acquire(repo_path, true)?;
+            Journal::ensure_none(repo_path)?;
+            let output = add_paths::add_new_paths(&[suggested_pattern], true, false, false).await?;
+            print!("{}", output);
+            Ok(())
+        }
+        Diagnosis::Covered { .. } => anyhow::bail!(
+            "'{}' is selected by the sparse paths but missing from disk; restore it with: git checkout HEAD -- {}",
+            path,
+            path
+        ),
+        Diagnosis::NotFound => anyhow::bail!(
+            "'{}' does not exist at HEAD; see 'git-partial why-missing {}'",
+            path,
+            path
+        ),
+    }
+}
+
+/// Run the editor command, split on whitespace, with `target` appended,
+/// and wait for it, as terminal editors need the terminal until they exit
+fn launch(
+    editor: &str,
+    target: &Path,
+    repo_path: &Path,
+) -> Result<()> {
+    let mut words = editor.split_whitespace();
+    let program = words.next().context("The editor command is empty")?;
+    info!("Opening {} with {}", target.display(), editor);
+    let status = Command::new(program)
+        .args(words)
+        .arg(target)
+        .current_dir(repo_path)
+        .status()
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => anyhow::anyhow!(
+                "Editor '{}' not found; set $EDITOR or 'git config gitpartial.editor'",
+                program
+            ),
+            _ => anyhow::Error::new(e).context(format!("Failed to run editor '{}'", program)),
+        })?;
+    if !status.success() {
+        anyhow::bail!("Editor '{}' exited with {}", editor, status);
+    }
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::env;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use tracing::info;
+
+use crate::core::metadata::RepositoryMetadata;
+use crate::utils::interrupt;
+
+/// Prefix of the executables run for unknown subcommands, like `git-<cmd>`
+const PLUGIN_PREFIX: &str = "git-partial-";
+
+/// Run `git-partial-<name>` from PATH with the remaining arguments and
+/// return its exit code.
+///
+/// Inside a git-partial repository the plugin gets `GIT_PARTIAL_ROOT` and
+/// `GIT_PARTIAL_METADATA`, and a JSON object with the repository root and
+/// metadata on stdin. `GIT_PARTIAL_BIN` lets it call back into git-partial.
+pub fn run_plugin(args: &[String]) -> Result<i32> {
+    let (name, plugin_args) = args.split_first().context("No subcommand given")?;
+    let program = find_plugin(name, env::var_os("PATH").as_deref()).with_context(|| {
+        format!(
+            "'{}' is not a git-partial command, and no {}{} was found on PATH",
+            name, PLUGIN_PREFIX, name
+        )
+    })?;
+    info!("Running plugin {}", program.display());
+
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let root = RepositoryMetadata::find_root(&current_dir);
+    let mut command = Command::new(&program);
+    command.args(plugin_args).stdin(Stdio::piped());
+    if let Ok(exe) = env::current_exe() {
+        command.env("GIT_PARTIAL_BIN", exe);
+    }
+    command.env("GIT_PARTIAL_VERSION", env!("CARGO_PKG_VERSION"));
+    let metadata = match &root {
+        Some(root) => {
+            command.env("GIT_PARTIAL_ROOT", root).env(
+                "GIT_PARTIAL_METADATA",
+                RepositoryMetadata::metadata_path(root),
+            );
+            Some(RepositoryMetadata::load(root).context("Failed to load metadata")?)
+        }
+        None => None,
+    };
+    let state = json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "root": root,
+        "metadata": metadata,
+    });
+
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program.display()))?;
+    let pid = child.id();
+    interrupt::track_child(pid);
+    if let Some(mut stdin) = child.stdin.take() {
+        // Written from a thread, and errors ignored, since the plugin may
+        // exit or stop reading without consuming its input
+        let state = state.to_string();
+        std::thread::spawn(move || stdin.write_all(state.as_bytes()).ok());
+    }
+    let status = child.wait();
+    interrupt::untrack_child(pid);
+    let status = status.with_context(|| format!("Failed to wait for {}", program.display()))?;
+
+    // A plugin killed by a signal has no exit code
+    Ok(status.code().unwrap_or(1))
+}
+
+/// Find the executable for plugin `name` in the directories of `path`
+fn find_plugin(
+    name: &str,
+    path: Option<&std::ffi::OsStr>,
+) -> Option<PathBuf> {
+    let file_name = format!("{}{}{}", PLUGIN_PREFIX, name, env::consts::EXE_SUFFIX);
+    env::split_paths(path?)
+        .map(|dir| dir.join(&file_name))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_find_plugin_in_path_order() {
+        let first = TempDir::new().unwrap();
+        let second = TempDir::new().unwrap();
+        let file_name = format!("git-partial-hello{}", env::consts::EXE_SUFFIX);
+        fs::write(second.path().join(&file_name), "").unwrap();
+
+        let path = env::join_paths([first.path(), second.path()]).unwrap();
+        assert_eq!(
+            find_plugin("hello", Some(&path)),
+            Some(second.path().join(&file_name))
+        );
+
+        fs::write(first.path().join(&file_name), "").unwrap();
+        assert_eq!(
+            find_plugin("hello", Some(&path)),
+            Some(first.path().join(&file_name))
+        );
+        assert_eq!(find_plugin("missing", Some(&path)), None);
+    }
+}
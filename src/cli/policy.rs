@@ -0,0 +1,133 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::cli::tree;
+use crate::core::config;
+use crate::core::error::PartialError;
+use crate::core::policy::{CheckoutLimits, Policy, POLICY_FILE};
+use crate::core::tree::{EntryKind, TreeEntry};
+use crate::git::commands;
+
+/// Git config key naming a policy file outside the repository, e.g. set in
+/// the system gitconfig by an administrator. Takes precedence over the
+/// policy committed in the repository.
+const POLICY_PATH_KEY: &str = "gitpartial.policy";
+
+/// Git config key that must be true for `--override-policy` to be honored
+const ALLOW_OVERRIDE_KEY: &str = "gitpartial.allowPolicyOverride";
+
+/// What a clone may proceed despite: `--override-policy` and `--force`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Overrides {
+    /// Violations of the organization's policy
+    pub policy: bool,
+
+    /// The user's checkout limits
+    pub limits: bool,
+}
+
+/// Check the sparse paths of a clone against the organization's policy.
+/// Violations fail the command unless `override_policy` is given and
+/// overrides are enabled in git config.
+pub async fn enforce(
+    repo_path: &Path,
+    remote_url: &str,
+    patterns: &[String],
+    override_policy: bool,
+) -> Result<()> {
+    let Some(policy) = load_policy(repo_path)? else {
+        return Ok(());
+    };
+    info!("Checking paths against policy: {:?}", policy);
+
+    let entries =
+        checkout_entries(repo_path, remote_url, policy.max_checkout_bytes.is_some()).await?;
+
+    let violations = policy.violations(&entries, patterns);
+    if violations.is_empty() {
+        return Ok(());
+    }
+    if !override_policy {
+        return Err(PartialError::PolicyViolations(violations).into());
+    }
+    if commands::config_value(repo_path, ALLOW_OVERRIDE_KEY).as_deref() != Some("true") {
+        anyhow::bail!(
+            "Policy overrides are disabled. An administrator can enable them with: git config --system {} true",
+            ALLOW_OVERRIDE_KEY
+        );
+    }
+    for violation in &violations {
+        eprintln!("warning: overriding policy: {}", violation);
+    }
+    Ok(())
+}
+
+/// Check the checkout of `patterns` against the user's limits,
+/// `max_checkout_size` and `max_files`. Exceeding one fails the command,
+/// unless `force` is given, which only warns.
+pub async fn check_limits(
+    repo_path: &Path,
+    remote_url: &str,
+    patterns: &[String],
+    force: bool,
+) -> Result<()> {
+    let limits = CheckoutLimits {
+        max_bytes: config::max_checkout_size(repo_path)?,
+        max_files: config::max_files(repo_path)?,
+    };
+    if limits == CheckoutLimits::default() {
+        return Ok(());
+    }
+    let entries = checkout_entries(repo_path, remote_url, limits.max_bytes.is_some()).await?;
+    let exceeded = limits.exceeded(&entries, patterns);
+    if exceeded.is_empty() {
+        return Ok(());
+    }
+    if !force {
+        return Err(PartialError::CheckoutTooLarge(exceeded).into());
+    }
+    for limit in &exceeded {
+        eprintln!("warning: forcing past checkout limit: {}", limit);
+    }
+    Ok(())
+}
+
+/// The files at HEAD, with sizes read from the remote so the blobs are not
+/// downloaded to measure them. Without an API for the remote the sizes are
+/// unknown, which is logged when `needs_sizes`.
+async fn checkout_entries(
+    repo_path: &Path,
+    remote_url: &str,
+    needs_sizes: bool,
+) -> Result<Vec<TreeEntry>> {
+    let head = commands::get_head_commit(repo_path).context("Failed to get HEAD commit")?;
+    if let Some(entries) = tree::remote_tree_entries(remote_url, &head).await {
+        return Ok(entries);
+    }
+    if needs_sizes {
+        warn!("Checkout size unknown, skipping the size limit");
+    }
+    Ok(commands::list_tree_files(repo_path, "HEAD")
+        .context("Failed to list repository files")?
+        .into_iter()
+        .map(|path| TreeEntry {
+            path,
+            kind: EntryKind::Blob,
+            size: None,
+        })
+        .collect())
+}
+
+/// Loads the policy from the configured path, or from the repository at HEAD
+fn load_policy(repo_path: &Path) -> Result<Option<Policy>> {
+    let content = match commands::config_value(repo_path, POLICY_PATH_KEY) {
+        Some(path) => std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read policy file {}", path))?,
+        None => match commands::show_file(repo_path, "HEAD", POLICY_FILE) {
+            Ok(content) => content,
+            Err(_) => return Ok(None),
+        },
+    };
+    Policy::parse(&content).map(Some)
+}
@@ -0,0 +1,185 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::env;
+use tracing::info;
+
+use crate::cli::{history, narrow_fetch, policy, root_mount};
+use crate::core::config;
+use crate::core::error::PartialError;
+use crate::core::journal::{Journal, JournalState, Step};
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::path_selector;
+use crate::core::profiles::{self, BranchProfile};
+use crate::git::commands;
+
+/// Define the paths to apply on branches matching a glob, replacing any previous definition
+pub async fn set_profile(
+    branch: &str,
+    paths: &[String],
+) -> Result<()> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let mut metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+
+    let profile = BranchProfile {
+        branch: branch.to_string(),
+        paths: paths.to_vec(),
+    };
+    match metadata
+        .branch_profiles
+        .iter_mut()
+        .find(|existing| existing.branch == branch)
+    {
+        Some(existing) => *existing = profile,
+        None => metadata.branch_profiles.push(profile),
+    }
+
+    metadata
+        .save(&current_dir)
+        .context("Failed to save updated metadata")?;
+    Ok(())
+}
+
+/// Remove the profile defined for a branch glob
+pub async fn remove_profile(branch: &str) -> Result<()> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let mut metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+
+    let before = metadata.branch_profiles.len();
+    metadata
+        .branch_profiles
+        .retain(|profile| profile.branch != branch);
+    if metadata.branch_profiles.len() == before {
+        anyhow::bail!("No profile defined for '{}'", branch);
+    }
+
+    metadata
+        .save(&current_dir)
+        .context("Failed to save updated metadata")?;
+    Ok(())
+}
+
+/// List the profiles in the order they are matched
+pub async fn list_profiles() -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+
+    if metadata.branch_profiles.is_empty() {
+        return Ok("No branch profiles defined.\n".to_string());
+    }
+
+    let mut output = String::new();
+    for profile in &metadata.branch_profiles {
+        output.push_str(&format!(
+            "{}: {}\n",
+            profile.branch,
+            profile.paths.join(" ")
+        ));
+    }
+    Ok(output)
+}
+
+/// Switch to a branch and apply the first matching profile.
+/// Without a matching profile the sparse paths are left unchanged.
+pub async fn switch_branch(
+    branch: &str,
+    override_policy: bool,
+    force: bool,
+) -> Result<()> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+
+    let mut profile = profiles::matching_profile(&metadata.branch_profiles, branch).cloned();
+    // Shared profiles may name `${TEAM}`-style variables, resolved on this machine
+    if let Some(profile) = &mut profile {
+        profile.paths = path_selector::expand_patterns(&config::substitute_variables(
+            &profile.paths,
+            &current_dir,
+        )?);
+    }
+
+    // Check pinned paths before touching the working tree
+    if let Some(profile) = &profile {
+        let kept: HashSet<&String> = profile.paths.iter().collect();
+        let mut dropped: Vec<String> = metadata
+            .pinned_paths
+            .iter()
+            .filter(|path| !kept.contains(path))
+            .cloned()
+            .collect();
+        dropped.sort();
+        if !dropped.is_empty() && !force {
+            return Err(PartialError::PinnedPaths(dropped).into());
+        }
+        policy::enforce(
+            &current_dir,
+            &metadata.remote_url,
+            &profile.paths,
+            override_policy,
+        )
+        .await?;
+    }
+
+    let previous = metadata.checked_out_paths.clone();
+    let mut updated = metadata.clone();
+    if let Some(profile) = &profile {
+        updated.checked_out_paths = profile.paths.iter().cloned().collect();
+        let checked_out = updated.checked_out_paths.clone();
+        updated
+            .pinned_paths
+            .retain(|path| checked_out.contains(path));
+    }
+    let mut journal = Journal::begin(
+        &current_dir,
+        "switch",
+        Some(JournalState {
+            branch: commands::current_branch(&current_dir),
+            metadata,
+        }),
+        JournalState {
+            branch: Some(branch.to_string()),
+            metadata: updated.clone(),
+        },
+    )?;
+    let mut metadata = updated;
+
+    narrow_fetch::fetch_for_switch(&current_dir, &metadata, branch);
+    // A refused switch changes nothing, so there is nothing to recover
+    if let Err(e) = commands::run_git_command_in_dir(&current_dir, &["switch", branch])
+        .with_context(|| format!("Failed to switch to branch {}", branch))
+    {
+        narrow_fetch::refresh(&current_dir, &metadata);
+        Journal::finish(&current_dir)?;
+        return Err(e);
+    }
+    journal.step(&current_dir, Step::Checkout)?;
+    narrow_fetch::refresh(&current_dir, &metadata);
+
+    if let Some(profile) = profile {
+        info!("Applying profile '{}'", profile.branch);
+        println!("Applying profile '{}': {:?}", profile.branch, profile.paths);
+        commands::set_sparse_checkout(&current_dir, &profile.paths)
+            .context("Failed to update sparse checkout paths")?;
+        journal.step(&current_dir, Step::SparseCheckout)?;
+    }
+
+    let head_commit =
+        commands::get_head_commit(&current_dir).context("Failed to get HEAD commit")?;
+    metadata.set_last_commit(&head_commit);
+    metadata
+        .save(&current_dir)
+        .context("Failed to save updated metadata")?;
+
+    let added: Vec<String> = metadata
+        .checked_out_paths
+        .difference(&previous)
+        .cloned()
+        .collect();
+    let removed: Vec<String> = previous
+        .difference(&metadata.checked_out_paths)
+        .cloned()
+        .collect();
+    history::record(&current_dir, &added, &removed);
+    root_mount::refresh_links(&current_dir, &metadata);
+    journal.step(&current_dir, Step::Metadata)?;
+    Journal::finish(&current_dir)
+}
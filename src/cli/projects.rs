@@ -0,0 +1,70 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::path::Path;
+use tracing::info;
+
+use crate::core::workspace::{self, Project};
+use crate::git::commands;
+
+/// List the logical projects of a monorepo workspace
+pub async fn list_projects() -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let projects = detect_projects(&current_dir)?;
+
+    if projects.is_empty() {
+        return Ok(
+            "No workspace projects detected (looked for pnpm, npm/yarn, Cargo, Nx, Turborepo, Gradle and Maven configuration).\n"
+                .to_string(),
+        );
+    }
+
+    let name_width = projects.iter().map(|p| p.name.len()).max().unwrap_or(0);
+    let mut output = String::new();
+    for project in &projects {
+        output.push_str(&format!(
+            "{:<width$}  {} ({})\n",
+            project.name,
+            project.dir,
+            project.kind,
+            width = name_width
+        ));
+    }
+    Ok(output)
+}
+
+/// Resolve project names to the sparse checkout patterns covering them
+pub fn project_paths(names: &[String]) -> Result<Vec<String>> {
+    if names.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let projects = detect_projects(&current_dir)?;
+
+    names
+        .iter()
+        .map(|name| {
+            projects
+                .iter()
+                .find(|project| project.matches_name(name))
+                .map(|project| project.pattern())
+                .with_context(|| {
+                    format!(
+                        "Unknown project '{}'. Run `git-partial projects` to list available projects.",
+                        name
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Detect projects from the workspace configuration at HEAD.
+/// Configuration files are read from git, so they need not be checked out.
+fn detect_projects(repo_path: &Path) -> Result<Vec<Project>> {
+    info!("Detecting workspace projects");
+    let entries = commands::list_tree_structure(repo_path, "HEAD")
+        .context("Failed to list repository tree")?;
+    Ok(workspace::detect_projects(&entries, |path| {
+        commands::show_file(repo_path, "HEAD", path).ok()
+    }))
+}
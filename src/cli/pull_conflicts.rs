@@ -0,0 +1,217 @@
+use anyhow::{Context, Result};
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::core::pull_state::{Integration, PullInProgress};
+use crate::git::commands;
+use crate::utils::prompt;
+
+/// How to go on from a pull stopped at conflicts
+const NEXT_STEPS: &str = "Resolve them and 'git add' them, then run 'git-partial smart-pull --continue', or 'git-partial smart-pull --abort' to undo the pull.\n";
+
+/// What resolving one round of conflicts led to
+enum Progress {
+    /// The rebase or merge is complete at this HEAD
+    Done(String),
+
+    /// Git stopped at conflicts again, e.g. at the next rebased commit
+    Conflicts,
+}
+
+/// Rebase or merge the current branch onto `upstream`, which it diverged
+/// from, and return the new HEAD. When git stops at conflicts, the pull is
+/// recorded for `smart-pull --continue` and `--abort`, and at a terminal
+/// the user is walked through them; `None` means it is still stopped. The
+/// conflict report is added to `output` rather than printed.
+pub fn integrate(
+    repo_path: &Path,
+    integration: Integration,
+    upstream: &str,
+    output: &mut String,
+) -> Result<Option<String>> {
+    let original_head = commands::get_head_commit(repo_path).context("Failed to get HEAD")?;
+    info!("Running git {} {}", integration.command(), upstream);
+    let args = match integration {
+        Integration::Rebase => vec!["rebase", upstream],
+        Integration::Merge => vec!["merge", "--no-edit", upstream],
+    };
+    if let Err(e) = commands::run_git_command_in_dir(repo_path, &args) {
+        if conflicted_files(repo_path)?.is_empty() {
+            return Err(e.context(format!(
+                "Failed to {} onto {}",
+                integration.command(),
+                upstream
+            )));
+        }
+        let state = PullInProgress {
+            integration,
+            upstream: upstream.to_string(),
+            original_head,
+        };
+        state.save(repo_path)?;
+        return assist(repo_path, &state, output);
+    }
+    commands::get_head_commit(repo_path)
+        .map(Some)
+        .context("Failed to get new HEAD commit after pull")
+}
+
+/// Carry on with the stopped pull after the conflicts were resolved and
+/// staged. Returns the new HEAD, or `None` when git stopped at conflicts
+/// again, which is reported in `output`.
+pub fn resume(
+    repo_path: &Path,
+    state: &PullInProgress,
+    output: &mut String,
+) -> Result<Option<String>> {
+    let conflicts = conflicted_files(repo_path)?;
+    if !conflicts.is_empty() {
+        anyhow::bail!(
+            "{} file(s) still have conflicts:\n{}\nResolve them and 'git add' them, or run 'git-partial smart-pull --abort'",
+            conflicts.len(),
+            list(&conflicts).trim_end()
+        );
+    }
+    match step(repo_path, state)? {
+        Progress::Done(head) => Ok(Some(head)),
+        Progress::Conflicts => assist(repo_path, state, output),
+    }
+}
+
+/// Stop the pull and return to the commit it started from
+pub fn abort(
+    repo_path: &Path,
+    state: &PullInProgress,
+) -> Result<()> {
+    if in_progress(repo_path, state.integration) {
+        commands::run_git_command_in_dir(repo_path, &[state.integration.command(), "--abort"])
+            .with_context(|| format!("Failed to abort the {}", state.integration.command()))?;
+    } else {
+        // Finished or abandoned by hand; put the branch back all the same
+        commands::run_git_command_in_dir(repo_path, &["reset", "--hard", &state.original_head])
+            .context("Failed to reset to the commit before the pull")?;
+    }
+    Ok(())
+}
+
+/// Files git could not merge, as `git diff --diff-filter=U` lists them
+pub fn conflicted_files(repo_path: &Path) -> Result<Vec<String>> {
+    let output = commands::run_git_command_in_dir(
+        repo_path,
+        &["diff", "-z", "--name-only", "--diff-filter=U"],
+    )
+    .context("Failed to list conflicted files")?;
+    Ok(commands::nul_separated(&output))
+}
+
+/// Run `git rebase --continue` or commit the merge, without opening an
+/// editor for the message
+fn step(
+    repo_path: &Path,
+    state: &PullInProgress,
+) -> Result<Progress> {
+    if in_progress(repo_path, state.integration) {
+        let args = match state.integration {
+            Integration::Rebase => vec!["-c", "core.editor=true", "rebase", "--continue"],
+            Integration::Merge => vec!["commit", "--no-edit"],
+        };
+        if let Err(e) = commands::run_git_command_in_dir(repo_path, &args) {
+            if conflicted_files(repo_path)?.is_empty() {
+                return Err(e.context(format!(
+                    "Failed to continue the {}",
+                    state.integration.command()
+                )));
+            }
+            return Ok(Progress::Conflicts);
+        }
+    }
+    commands::get_head_commit(repo_path)
+        .map(Progress::Done)
+        .context("Failed to get new HEAD commit after pull")
+}
+
+/// List the conflicts and, at a terminal, offer `git mergetool` and then
+/// to carry on, round after round. Returns the new HEAD when the pull was
+/// completed here; otherwise what is left to do is added to `output`.
+fn assist(
+    repo_path: &Path,
+    state: &PullInProgress,
+    output: &mut String,
+) -> Result<Option<String>> {
+    loop {
+        let conflicts = conflicted_files(repo_path)?;
+        let summary = format!(
+            "The {} onto {} stopped at conflicts in {} file(s):\n{}",
+            state.integration.command(),
+            state.upstream,
+            conflicts.len(),
+            list(&conflicts)
+        );
+        if !io::stdin().is_terminal() {
+            output.push_str(&summary);
+            output.push_str(NEXT_STEPS);
+            return Ok(None);
+        }
+        if !prompt::confirm(
+            &format!(
+                "{}The merge tool (git mergetool) opens for each file.",
+                summary
+            ),
+            false,
+        )? {
+            output.push_str(NEXT_STEPS);
+            return Ok(None);
+        }
+        let status = commands::git_command()
+            .arg("mergetool")
+            .current_dir(repo_path)
+            .status();
+        if let Err(e) = status {
+            warn!("Failed to run git mergetool: {:#}", e);
+        }
+
+        let remaining = conflicted_files(repo_path)?;
+        if !remaining.is_empty() {
+            output.push_str(&format!(
+                "{} file(s) still have conflicts:\n{}",
+                remaining.len(),
+                list(&remaining)
+            ));
+            output.push_str(NEXT_STEPS);
+            return Ok(None);
+        }
+        if !prompt::confirm(
+            &format!(
+                "All conflicts are resolved; the {} goes on.",
+                state.integration.command()
+            ),
+            false,
+        )? {
+            output.push_str(NEXT_STEPS);
+            return Ok(None);
+        }
+        if let Progress::Done(head) = step(repo_path, state)? {
+            return Ok(Some(head));
+        }
+    }
+}
+
+/// Whether git still has the rebase or merge under way
+fn in_progress(
+    repo_path: &Path,
+    integration: Integration,
+) -> bool {
+    let marker = match integration {
+        Integration::Rebase => ["rebase-merge", "rebase-apply"].as_slice(),
+        Integration::Merge => ["MERGE_HEAD"].as_slice(),
+    };
+    marker.iter().any(|name| {
+        commands::run_git_command_in_dir(repo_path, &["rev-parse", "--git-path", name])
+            .is_ok_and(|path| repo_path.join(path).exists())
+    })
+}
+
+fn list(files: &[String]) -> String {
+    files.iter().map(|file| format!("  {}\n", file)).collect()
+}
@@ -0,0 +1,90 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::path::Path;
+use tracing::info;
+
+use crate::cli::{history, root_mount};
+use crate::core::journal::{Journal, JournalState, Step};
+use crate::git::commands;
+
+/// Finish the operation an error or a crash left unfinished, as recorded in
+/// `.gitpartial/journal.json`, or undo it with `rollback`. Either way the
+/// branch, the sparse patterns and the metadata agree again afterwards.
+pub async fn recover(rollback: bool) -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let Some(journal) = Journal::load(&current_dir)? else {
+        return Ok("Nothing to recover.\n".to_string());
+    };
+
+    let done: Vec<&str> = journal.done.iter().map(Step::name).collect();
+    let mut output = format!(
+        "Found an interrupted {} (done: {}).\n",
+        journal.operation,
+        if done.is_empty() {
+            "nothing".to_string()
+        } else {
+            done.join(", ")
+        }
+    );
+    let metadata_written = journal.done.contains(&Step::Metadata);
+    let (from, to) = if rollback {
+        let before = journal.before.as_ref().with_context(|| {
+            format!(
+                "An interrupted {} cannot be rolled back; remove the directory and start over, or run 'git-partial recover' to finish it",
+                journal.operation
+            )
+        })?;
+        (Some(&journal.after), before)
+    } else {
+        (journal.before.as_ref(), &journal.after)
+    };
+
+    info!("Recovering {}: {:?}", journal.operation, journal.done);
+    apply(&current_dir, to)?;
+    // The history holds the operation once its metadata step is done
+    if rollback == metadata_written {
+        let from_paths = from
+            .map(|state| state.metadata.checked_out_paths.clone())
+            .unwrap_or_default();
+        let paths = &to.metadata.checked_out_paths;
+        let added: Vec<String> = paths.difference(&from_paths).cloned().collect();
+        let removed: Vec<String> = from_paths.difference(paths).cloned().collect();
+        history::record(&current_dir, &added, &removed);
+    }
+    Journal::finish(&current_dir)?;
+
+    if rollback {
+        output.push_str(&format!("Rolled back the {}.\n", journal.operation));
+    } else {
+        output.push_str(&format!("Completed the {}.\n", journal.operation));
+    }
+    Ok(output)
+}
+
+/// Brings the branch, the sparse patterns and the metadata to `state`.
+/// Each step is harmless to repeat.
+fn apply(
+    repo_path: &Path,
+    state: &JournalState,
+) -> Result<()> {
+    if let Some(branch) = &state.branch {
+        if commands::current_branch(repo_path).as_ref() != Some(branch) {
+            commands::run_git_command_in_dir(repo_path, &["switch", branch])
+                .with_context(|| format!("Failed to switch to branch {}", branch))?;
+        }
+    }
+
+    let mut paths: Vec<String> = state.metadata.checked_out_paths.iter().cloned().collect();
+    paths.sort();
+    commands::set_sparse_checkout(repo_path, &paths)
+        .context("Failed to update sparse checkout paths")?;
+
+    let mut metadata = state.metadata.clone();
+    let head_commit = commands::get_head_commit(repo_path).context("Failed to get HEAD commit")?;
+    metadata.set_last_commit(&head_commit);
+    metadata
+        .save(repo_path)
+        .context("Failed to save metadata")?;
+    root_mount::refresh_links(repo_path, &metadata);
+    Ok(())
+}
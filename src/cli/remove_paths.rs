@@ -0,0 +1,123 @@
+use anyhow::{Context, Result};
+use std::env;
+use tracing::{debug, info, warn};
+
+use crate::cli::{history, policy, root_mount};
+use crate::core::error::PartialError;
+use crate::core::journal::{Journal, JournalState, Step};
+use crate::core::metadata::RepositoryMetadata;
+use crate::git::commands;
+use crate::git::pins;
+use crate::git::sparse;
+use crate::utils::prompt;
+
+/// Remove paths from the sparse checkout. Returns the messages for the
+/// user rather than printing them, so the dashboard can show them.
+pub async fn remove_paths(
+    paths: &[String],
+    assume_yes: bool,
+    override_policy: bool,
+    force: bool,
+) -> Result<String> {
+    info!("Removing paths from sparse checkout");
+    debug!("Paths to remove: {:?}", paths);
+
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+
+    // Load existing metadata
+    let mut metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+
+    if !sparse::is_sparse_checkout()? {
+        return Err(PartialError::SparseCheckoutDisabled.into());
+    }
+
+    // Only paths that are actually checked out can be removed
+    let mut to_remove = Vec::new();
+    for path in paths {
+        if metadata.checked_out_paths.contains(path) {
+            if !to_remove.contains(path) {
+                to_remove.push(path.clone());
+            }
+        } else {
+            warn!("Path '{}' is not in the sparse checkout, skipping", path);
+        }
+    }
+
+    if to_remove.is_empty() {
+        info!("No matching paths to remove. Sparse checkout and metadata remain unchanged.");
+        return Ok(String::new());
+    }
+
+    let pinned = metadata.pinned(&to_remove);
+    if !pinned.is_empty() && !force {
+        return Err(PartialError::PinnedPaths(pinned.into_iter().cloned().collect()).into());
+    }
+
+    let mut final_paths = metadata.checked_out_paths.clone();
+    for path in &to_remove {
+        final_paths.remove(path);
+    }
+
+    if final_paths.is_empty() {
+        anyhow::bail!("Cannot remove every path. At least one path must remain checked out.");
+    }
+
+    // Removing a mandatory path breaks the organization's policy
+    let final_paths_vec: Vec<String> = final_paths.iter().cloned().collect();
+    policy::enforce(
+        &current_dir,
+        &metadata.remote_url,
+        &final_paths_vec,
+        override_policy,
+    )
+    .await?;
+
+    // Removing paths deletes files from the working tree, so ask first
+    let mut summary = String::from("The following paths will be removed from the checkout:\n");
+    for path in &to_remove {
+        summary.push_str(&format!("  - {}\n", path));
+    }
+    summary.push_str("Files matching these paths will be deleted from the working directory.");
+
+    if !prompt::confirm(&summary, assume_yes)? {
+        return Ok("Aborted.\n".to_string());
+    }
+
+    for path in &to_remove {
+        if let Some(commit) = metadata.pinned_revisions.remove(path) {
+            pins::release(&current_dir, path, &commit)?;
+        }
+    }
+    let mut updated = metadata.clone();
+    updated.checked_out_paths = final_paths;
+    for path in &to_remove {
+        updated.pinned_paths.remove(path);
+    }
+    let mut journal = Journal::begin(
+        &current_dir,
+        "remove-paths",
+        Some(JournalState {
+            branch: None,
+            metadata,
+        }),
+        JournalState {
+            branch: None,
+            metadata: updated.clone(),
+        },
+    )?;
+
+    commands::set_sparse_checkout(&current_dir, &final_paths_vec)
+        .context("Failed to update sparse checkout paths")?;
+    journal.step(&current_dir, Step::SparseCheckout)?;
+
+    updated
+        .save(&current_dir)
+        .context("Failed to save updated metadata")?;
+    history::record(&current_dir, &[], &to_remove);
+    root_mount::refresh_links(&current_dir, &updated);
+    journal.step(&current_dir, Step::Metadata)?;
+    Journal::finish(&current_dir)?;
+
+    info!("Successfully removed paths and updated metadata");
+    Ok(String::new())
+}
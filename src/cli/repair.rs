@@ -0,0 +1,120 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use crate::core::error::PartialError;
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::path_selector;
+use crate::git::commands;
+use crate::git::sparse;
+
+/// Restore the skip-worktree bit on tracked files that git reports as deleted
+/// only because they lie outside the sparse paths.
+/// Deletions inside the sparse paths are real local changes and are left alone.
+/// With `index`, every bit in the index is recomputed from the sparse
+/// checkout patterns instead.
+pub async fn repair(index: bool) -> Result<()> {
+    info!("Repairing skip-worktree state");
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+
+    if !sparse::is_sparse_checkout()? {
+        return Err(PartialError::SparseCheckoutDisabled.into());
+    }
+    if index {
+        return repair_index(&current_dir);
+    }
+
+    let deletions =
+        commands::unstaged_deletions(&current_dir).context("Failed to list deleted files")?;
+    let outside = path_selector::outside_patterns(&deletions, &metadata.checked_out_paths);
+    if outside.is_empty() {
+        println!("Nothing to repair.");
+        return Ok(());
+    }
+
+    commands::set_skip_worktree(&current_dir, &outside)
+        .context("Failed to restore skip-worktree")?;
+    for path in &outside {
+        println!("Repaired {}", path);
+    }
+    Ok(())
+}
+
+/// Set the skip-worktree bit of every tracked file the sparse checkout
+/// patterns leave out, and clear it on every file they select, checking
+/// out the files that were hidden. A file left out but changed locally
+/// keeps its bit cleared, so the change is not lost.
+fn repair_index(repo_path: &Path) -> Result<()> {
+    let (cone, patterns) =
+        commands::applied_sparse_patterns(repo_path).ok_or(PartialError::SparseCheckoutDisabled)?;
+    let changed: HashSet<String> = commands::unstaged_changes(repo_path)
+        .context("Failed to list local changes")?
+        .into_iter()
+        .collect();
+
+    let mut hidden = Vec::new();
+    let mut exposed = Vec::new();
+    let mut kept = Vec::new();
+    for (path, skipped) in commands::skip_worktree_entries(repo_path)? {
+        match (
+            path_selector::sparse_includes(cone, &patterns, &path),
+            skipped,
+        ) {
+            (true, true) => hidden.push(path),
+            (false, false) if changed.contains(&path) && repo_path.join(&path).exists() => {
+                kept.push(path)
+            }
+            (false, false) => exposed.push(path),
+            _ => {}
+        }
+    }
+    if hidden.is_empty() && exposed.is_empty() && kept.is_empty() {
+        println!("The index matches the sparse paths.");
+        return Ok(());
+    }
+
+    if !hidden.is_empty() {
+        commands::clear_skip_worktree(repo_path, &hidden)
+            .context("Failed to clear skip-worktree")?;
+        // A copy already in the working tree is kept as a local change
+        let missing: Vec<&str> = hidden
+            .iter()
+            .filter(|path| !repo_path.join(path).exists())
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            let mut args = vec!["checkout", "--"];
+            args.extend(missing);
+            commands::run_git_command_in_dir(repo_path, &args)
+                .context("Failed to check out the hidden files")?;
+        }
+    }
+    if !exposed.is_empty() {
+        commands::set_skip_worktree(repo_path, &exposed)
+            .context("Failed to restore skip-worktree")?;
+        for path in &exposed {
+            let file = repo_path.join(path);
+            if file.is_file() || file.is_symlink() {
+                fs::remove_file(&file).with_context(|| format!("Failed to remove {}", path))?;
+            }
+        }
+    }
+
+    for path in &hidden {
+        println!("Checked out {} (was hidden by skip-worktree)", path);
+    }
+    for path in &exposed {
+        println!("Marked {} skip-worktree (outside the sparse paths)", path);
+    }
+    for path in &kept {
+        println!(
+            "Kept {}: outside the sparse paths but changed locally; commit or discard it, then repair again",
+            path
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,180 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tracing::info;
+
+use crate::git::commands::INTERNAL_ENV;
+use crate::git::trace::{self, TraceEntry};
+
+/// Run the git commands of a `--trace-git` file again, in order, inside
+/// `scratch` (a new temporary directory by default) rather than where they
+/// first ran, and report those exiting differently than traced.
+///
+/// The directory of the first command stands for `scratch`: working
+/// directories and arguments under it are moved there. Commands that ran
+/// elsewhere are skipped.
+pub async fn replay(
+    trace_file: &str,
+    scratch: Option<&str>,
+) -> Result<String> {
+    let content = fs::read_to_string(trace_file)
+        .with_context(|| format!("Failed to read trace {}", trace_file))?;
+    let entries = trace::parse(&content)?;
+    let Some(first) = entries.first() else {
+        return Ok(format!("{} has no git commands.\n", trace_file));
+    };
+    let root = first.cwd.clone();
+
+    let scratch = match scratch {
+        Some(dir) => PathBuf::from(dir),
+        None => env::temp_dir().join(format!("git-partial-replay-{}", std::process::id())),
+    };
+    fs::create_dir_all(&scratch)
+        .with_context(|| format!("Failed to create directory: {:?}", scratch))?;
+    let scratch = fs::canonicalize(&scratch)
+        .with_context(|| format!("Failed to resolve {}", scratch.display()))?;
+
+    let mut output = format!(
+        "Replaying {} git command(s) from {} in {}\n",
+        entries.len(),
+        trace_file,
+        scratch.display()
+    );
+    let mut skipped = 0;
+    let mut differed = 0;
+    for (index, entry) in entries.iter().enumerate() {
+        output.push_str(&format!("{}. git {}: ", index + 1, entry.args.join(" ")));
+        let Some(cwd) = rebase(&entry.cwd, &root, &scratch) else {
+            output.push_str(&format!(
+                "skipped, ran in {} outside {}\n",
+                entry.cwd.display(),
+                root.display()
+            ));
+            skipped += 1;
+            continue;
+        };
+        let (exit_code, stderr) = run(entry, &cwd, &root, &scratch)?;
+        if exit_code == entry.exit_code {
+            output.push_str(&format!("{}\n", describe(exit_code)));
+            continue;
+        }
+        differed += 1;
+        output.push_str(&format!(
+            "{}, traced {}\n",
+            describe(exit_code),
+            describe(entry.exit_code)
+        ));
+        for line in stderr.lines().filter(|line| !line.trim().is_empty()) {
+            output.push_str(&format!("   {}\n", line));
+        }
+    }
+    output.push_str(&format!(
+        "Replayed {} command(s), skipped {}; {} exited differently than traced.\n",
+        entries.len() - skipped,
+        skipped,
+        differed
+    ));
+    Ok(output)
+}
+
+/// Moves `path` from under `root` to under `scratch`
+fn rebase(
+    path: &Path,
+    root: &Path,
+    scratch: &Path,
+) -> Option<PathBuf> {
+    path.strip_prefix(root)
+        .ok()
+        .map(|relative| scratch.join(relative))
+}
+
+/// Moves an argument naming a path under `root`, possibly as an option's
+/// value like `--git-dir=<path>`, to under `scratch`
+fn rebase_arg(
+    arg: &str,
+    root: &Path,
+    scratch: &Path,
+) -> String {
+    let (prefix, value) = match arg.split_once('=') {
+        Some((option, value)) if option.starts_with('-') => (format!("{}=", option), value),
+        _ => (String::new(), arg),
+    };
+    match rebase(Path::new(value), root, scratch) {
+        Some(path) if Path::new(value).is_absolute() => {
+            format!("{}{}", prefix, path.to_string_lossy())
+        }
+        _ => arg.to_string(),
+    }
+}
+
+/// Runs one traced command, returning its exit code and stderr
+fn run(
+    entry: &TraceEntry,
+    cwd: &Path,
+    root: &Path,
+    scratch: &Path,
+) -> Result<(Option<i32>, String)> {
+    let args: Vec<String> = entry
+        .args
+        .iter()
+        .map(|arg| rebase_arg(arg, root, scratch))
+        .collect();
+    info!("Replaying git {} in {}", args.join(" "), cwd.display());
+    // A directory the traced command created may be missing if an earlier one failed
+    let output = Command::new("git")
+        .env(INTERNAL_ENV, "1")
+        .args(&args)
+        .current_dir(if cwd.is_dir() { cwd } else { scratch })
+        .stdin(Stdio::null())
+        .output()
+        .context("Failed to execute git command")?;
+    Ok((
+        output.status.code(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    ))
+}
+
+fn describe(exit_code: Option<i32>) -> String {
+    match exit_code {
+        Some(code) => format!("exit {}", code),
+        None => "killed".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebase_arg() {
+        let root = Path::new("/work");
+        let scratch = Path::new("/tmp/replay");
+        assert_eq!(rebase_arg("/work/app", root, scratch), "/tmp/replay/app");
+        assert_eq!(
+            rebase_arg("--git-dir=/work/app/.git", root, scratch),
+            "--git-dir=/tmp/replay/app/.git"
+        );
+        assert_eq!(
+            rebase_arg("/elsewhere/repo", root, scratch),
+            "/elsewhere/repo"
+        );
+        assert_eq!(rebase_arg("status", root, scratch), "status");
+        assert_eq!(
+            rebase_arg("core.sshCommand=ssh -i /work/key", root, scratch),
+            "core.sshCommand=ssh -i /work/key"
+        );
+    }
+
+    #[test]
+    fn test_rebase() {
+        let root = Path::new("/work");
+        let scratch = Path::new("/tmp/replay");
+        assert_eq!(
+            rebase(Path::new("/work/app"), root, scratch),
+            Some(PathBuf::from("/tmp/replay/app"))
+        );
+        assert_eq!(rebase(Path::new("/other"), root, scratch), None);
+    }
+}
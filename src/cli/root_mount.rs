@@ -0,0 +1,168 @@
+use anyhow::{Context, Result};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::cli::clone;
+use crate::cli::policy::Overrides;
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::root_mount::{self, RootMount};
+
+/// Clone a repository so that the subdirectory `mount.root` is the top
+/// level of `destination`, for tooling that expects the project at the
+/// repository root. The clone itself goes to a hidden directory next to
+/// `destination`, which becomes a link to its root subdirectory; the other
+/// selected paths are linked under `mount.prefix`. Returns the directory
+/// of the clone.
+pub async fn clone_with_root(
+    repo_url: &str,
+    destination: &str,
+    paths: &[String],
+    mount: &RootMount,
+    via: Option<&str>,
+    rewrite: Option<&str>,
+    overrides: Overrides,
+) -> Result<PathBuf> {
+    let link = Path::new(destination);
+    if link.symlink_metadata().is_ok() {
+        anyhow::bail!(
+            "'{}' already exists; clone --root creates it as a link",
+            destination
+        );
+    }
+    let store = root_mount::store_dir(link);
+
+    let mut paths = paths.to_vec();
+    if !paths.contains(&mount.root_pattern()) {
+        paths.insert(0, mount.root_pattern());
+    }
+    clone::clone_repository(
+        repo_url,
+        &store.to_string_lossy(),
+        &paths,
+        via,
+        rewrite,
+        overrides,
+    )
+    .await?;
+
+    let mut metadata = RepositoryMetadata::load(&store).context("Failed to load metadata")?;
+    metadata.root_mount = Some(mount.clone());
+    metadata.save(&store).context("Failed to save metadata")?;
+    if !store.join(&mount.root).is_dir() {
+        anyhow::bail!(
+            "'{}' is not a directory in {}; the clone is in {}",
+            mount.root,
+            repo_url,
+            store.display()
+        );
+    }
+
+    // Relative, so the pair can be moved together
+    let target = Path::new(store.file_name().context("Invalid destination")?).join(&mount.root);
+    symlink(&target, link, true)
+        .with_context(|| format!("Failed to link {} to {}", destination, target.display()))?;
+    exclude(&store, &format!("/{}/", mount.prefix_dir()))?;
+    refresh(&store, &metadata)?;
+    info!("Mounted {} at {}", mount.root, destination);
+    Ok(store)
+}
+
+/// Recreate the links under the prefix of a `clone --root` clone for its
+/// current sparse paths, after paths are added or removed. A failure is
+/// only logged, as the checkout itself is complete.
+pub fn refresh_links(
+    repo_path: &Path,
+    metadata: &RepositoryMetadata,
+) {
+    if let Err(e) = refresh(repo_path, metadata) {
+        warn!("Failed to update the mounted paths: {:#}", e);
+    }
+}
+
+fn refresh(
+    repo_path: &Path,
+    metadata: &RepositoryMetadata,
+) -> Result<()> {
+    let Some(mount) = &metadata.root_mount else {
+        return Ok(());
+    };
+    let dir = repo_path.join(mount.prefix_dir());
+    if dir.is_dir() {
+        for entry in fs::read_dir(&dir).with_context(|| format!("Failed to read {:?}", dir))? {
+            let path = entry?.path();
+            if path.symlink_metadata()?.file_type().is_symlink() {
+                remove_link(&path).with_context(|| format!("Failed to remove {:?}", path))?;
+            }
+        }
+    }
+
+    let links = mount.links(&metadata.checked_out_paths);
+    if links.is_empty() {
+        // Only removed when nothing else was put there
+        let _ = fs::remove_dir(&dir);
+        return Ok(());
+    }
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    for (name, target) in links {
+        let is_dir = repo_path.join(&name).is_dir();
+        symlink(Path::new(&target), &dir.join(&name), is_dir)
+            .with_context(|| format!("Failed to link {}/{}", mount.prefix_dir(), name))?;
+    }
+    Ok(())
+}
+
+/// Keep the links out of `git status` with `.git/info/exclude`
+fn exclude(
+    repo_path: &Path,
+    pattern: &str,
+) -> Result<()> {
+    let path = repo_path.join(".git").join("info").join("exclude");
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    if existing.lines().any(|line| line == pattern) {
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {:?}", path))?;
+    let separator = if existing.is_empty() || existing.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+    writeln!(file, "{}{}", separator, pattern)
+        .with_context(|| format!("Failed to write {:?}", path))
+}
+
+#[cfg(unix)]
+fn symlink(
+    target: &Path,
+    link: &Path,
+    _is_dir: bool,
+) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(
+    target: &Path,
+    link: &Path,
+    is_dir: bool,
+) -> std::io::Result<()> {
+    if is_dir {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+/// Windows removes directory links like directories
+fn remove_link(path: &Path) -> std::io::Result<()> {
+    fs::remove_file(path).or_else(|_| fs::remove_dir(path))
+}
@@ -0,0 +1,80 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::path::Path;
+use tracing::info;
+
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::savings::{self, CloneStats};
+use crate::core::tree::EntryKind;
+use crate::git::commands;
+use crate::remote;
+use crate::utils::disk;
+
+/// Compare the size of this partial clone against a full clone of the remote
+pub async fn show_savings() -> Result<String> {
+    info!("Measuring partial clone savings");
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let mut metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+
+    let partial = partial_stats(&current_dir, metadata.bare)?;
+
+    // Measuring needs direct access to the remote object store,
+    // otherwise fall back to the figures from the last measurement
+    let (full, cached) = match remote::local_path(&metadata.remote_url) {
+        Some(remote_path) => {
+            let full = measure_full_clone(&remote_path)
+                .with_context(|| format!("Failed to measure {}", metadata.remote_url))?;
+            metadata.full_clone_stats = Some(full.clone());
+            metadata
+                .save(&current_dir)
+                .context("Failed to save metadata")?;
+            (full, false)
+        }
+        None => match metadata.full_clone_stats.clone() {
+            Some(full) => (full, true),
+            None => anyhow::bail!(
+                "Cannot measure a full clone of '{}': the remote is not reachable on this machine and no earlier measurement is cached.",
+                metadata.remote_url
+            ),
+        },
+    };
+
+    Ok(savings::format_savings(&partial, &full, cached))
+}
+
+/// Measure what the partial clone at `repo_path` uses. A bare clone is all
+/// object store.
+pub fn partial_stats(
+    repo_path: &Path,
+    bare: bool,
+) -> Result<CloneStats> {
+    let objects = commands::count_objects(repo_path).context("Failed to count objects")?;
+    if bare {
+        return Ok(CloneStats {
+            objects,
+            git_dir_bytes: disk::dir_size(repo_path),
+            checkout_bytes: 0,
+        });
+    }
+    let git_dir_bytes = disk::dir_size(&repo_path.join(".git"));
+    Ok(CloneStats {
+        objects,
+        git_dir_bytes,
+        checkout_bytes: disk::dir_size(repo_path).saturating_sub(git_dir_bytes),
+    })
+}
+
+/// Measure what a full clone of the repository at `remote_path` would use
+pub fn measure_full_clone(remote_path: &Path) -> Result<CloneStats> {
+    let (objects, git_dir_bytes) = commands::reachable_objects(remote_path)?;
+    let checkout_bytes = commands::list_tree_entries(remote_path, "HEAD")?
+        .iter()
+        .filter(|entry| entry.kind == EntryKind::Blob)
+        .filter_map(|entry| entry.size)
+        .sum();
+    Ok(CloneStats {
+        objects,
+        git_dir_bytes,
+        checkout_bytes,
+    })
+}
@@ -0,0 +1,308 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use std::env;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use crate::remote::github::{GitHubClient, Release};
+use crate::remote::{self, RemoteUrl};
+use crate::utils::interrupt::RemoveOnInterrupt;
+
+/// Repository whose GitHub releases publish the binaries
+const RELEASE_REPOSITORY: &str = "bufferings/git-partial";
+
+/// Base64 Ed25519 public key the release binaries are signed with, set by
+/// the release build. Builds without it cannot verify, so cannot update.
+const RELEASE_PUBLIC_KEY: Option<&str> = option_env!("GIT_PARTIAL_RELEASE_PUBLIC_KEY");
+
+/// Exit code of `self-update --check` when a newer release exists,
+/// matching `status --check` for a branch that is behind
+pub const EXIT_OUTDATED: i32 = 3;
+
+/// Result of comparing the running binary with the latest release
+#[derive(Debug, PartialEq)]
+pub enum UpdateCheck {
+    UpToDate { current: String },
+    Available { current: String, latest: String },
+}
+
+impl UpdateCheck {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            UpdateCheck::UpToDate { .. } => 0,
+            UpdateCheck::Available { .. } => EXIT_OUTDATED,
+        }
+    }
+}
+
+impl std::fmt::Display for UpdateCheck {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            UpdateCheck::UpToDate { current } => {
+                write!(f, "git-partial {} is up to date", current)
+            }
+            UpdateCheck::Available { current, latest } => {
+                write!(
+                    f,
+                    "git-partial {} is available (running {})",
+                    latest, current
+                )
+            }
+        }
+    }
+}
+
+/// Compare the running version with the latest release
+pub async fn check_for_update() -> Result<UpdateCheck> {
+    let release = latest_release().await?;
+    Ok(compare(env!("CARGO_PKG_VERSION"), &release.tag_name))
+}
+
+/// Download the latest release for this platform, verify its checksum and
+/// its signature against the embedded release key, and replace the running
+/// executable with it
+pub async fn self_update() -> Result<()> {
+    let release = latest_release().await?;
+    let check = compare(env!("CARGO_PKG_VERSION"), &release.tag_name);
+    if let UpdateCheck::UpToDate { .. } = check {
+        println!("{}", check);
+        return Ok(());
+    }
+    let public_key = RELEASE_PUBLIC_KEY.context(
+        "This build has no release signing key to verify updates with; download the release manually",
+    )?;
+
+    let name = asset_name();
+    let find_asset = |name: &str| {
+        release
+            .assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .map(|asset| asset.browser_download_url.as_str())
+    };
+    let binary_url = find_asset(&name).with_context(|| {
+        format!(
+            "Release {} has no binary for this platform ({})",
+            release.tag_name, name
+        )
+    })?;
+    let checksum_url = find_asset(&format!("{}.sha256", name)).with_context(|| {
+        format!(
+            "Release {} has no checksum for {}; refusing to install it",
+            release.tag_name, name
+        )
+    })?;
+    let signature_url = find_asset(&format!("{}.sig", name)).with_context(|| {
+        format!(
+            "Release {} has no signature for {}; refusing to install it",
+            release.tag_name, name
+        )
+    })?;
+
+    println!("Downloading {} {}...", name, release.tag_name);
+    let binary = remote::download(binary_url).await?;
+    let checksum = remote::download(checksum_url).await?;
+    let expected = parse_checksum(&String::from_utf8_lossy(&checksum))
+        .context("Failed to parse the release checksum")?;
+    let actual = sha256_hex(&binary);
+    if actual != expected {
+        anyhow::bail!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            name,
+            expected,
+            actual
+        );
+    }
+    let signature = remote::download(signature_url).await?;
+    verify_signature(public_key, &binary, &String::from_utf8_lossy(&signature))
+        .with_context(|| format!("Refusing to install {}", name))?;
+
+    let exe = env::current_exe().context("Failed to locate the running executable")?;
+    replace_executable(&exe, &binary)?;
+    println!(
+        "Updated {} to {}",
+        exe.display(),
+        release.tag_name.trim_start_matches('v')
+    );
+    Ok(())
+}
+
+async fn latest_release() -> Result<Release> {
+    let repository = RemoteUrl {
+        host: "github.com".to_string(),
+        path: RELEASE_REPOSITORY.to_string(),
+    };
+    let client = GitHubClient::from_remote(&repository).context("Invalid release repository")?;
+    info!("Checking the latest release of {}", RELEASE_REPOSITORY);
+    client
+        .latest_release()
+        .await
+        .context("Failed to check for a new release")
+}
+
+fn compare(
+    current: &str,
+    tag: &str,
+) -> UpdateCheck {
+    let latest = tag.trim_start_matches('v');
+    match (parse_version(current), parse_version(latest)) {
+        (Some(current_version), Some(latest_version)) if latest_version > current_version => {
+            UpdateCheck::Available {
+                current: current.to_string(),
+                latest: latest.to_string(),
+            }
+        }
+        _ => UpdateCheck::UpToDate {
+            current: current.to_string(),
+        },
+    }
+}
+
+/// Parses `major.minor.patch`, ignoring pre-release and build suffixes
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version.split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().unwrap_or(Some(0))?;
+    let patch = parts.next().unwrap_or(Some(0))?;
+    Some((major, minor, patch))
+}
+
+/// Name of the release asset built for this platform, e.g. `git-partial-x86_64-linux`
+fn asset_name() -> String {
+    format!(
+        "git-partial-{}-{}{}",
+        env::consts::ARCH,
+        env::consts::OS,
+        env::consts::EXE_SUFFIX
+    )
+}
+
+/// Reads the digest from a `sha256sum` line (`<hex>  <file>`) or a bare digest
+fn parse_checksum(content: &str) -> Option<String> {
+    let digest = content.split_whitespace().next()?.to_lowercase();
+    (digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit())).then_some(digest)
+}
+
+/// Checks a detached Ed25519 signature of `data`, given in base64 like the
+/// public key
+fn verify_signature(
+    public_key: &str,
+    data: &[u8],
+    signature: &str,
+) -> Result<()> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let public_key = engine
+        .decode(public_key.trim())
+        .context("Invalid release public key")?;
+    let signature = engine
+        .decode(signature.trim())
+        .context("Failed to parse the release signature")?;
+    ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, public_key)
+        .verify(data, &signature)
+        .map_err(|_| anyhow::anyhow!("The release signature does not match the binary"))
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA256, data)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Write the new binary next to the executable and move it into place, so
+/// a failed download never leaves a half-written executable behind
+fn replace_executable(
+    exe: &Path,
+    binary: &[u8],
+) -> Result<()> {
+    let staged = exe.with_extension("new");
+    let _incomplete = RemoveOnInterrupt::new(&staged);
+    fs::write(&staged, binary).with_context(|| format!("Failed to write {}", staged.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))
+            .with_context(|| format!("Failed to make {} executable", staged.display()))?;
+    }
+    // A running executable cannot be overwritten on Windows, but it can be renamed
+    #[cfg(windows)]
+    {
+        let old = exe.with_extension("old");
+        fs::remove_file(&old).ok();
+        fs::rename(exe, &old).with_context(|| format!("Failed to move {} aside", exe.display()))?;
+    }
+
+    fs::rename(&staged, exe).with_context(|| format!("Failed to replace {}", exe.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(
+            compare("0.1.0", "v0.2.0"),
+            UpdateCheck::Available {
+                current: "0.1.0".to_string(),
+                latest: "0.2.0".to_string()
+            }
+        );
+        assert_eq!(compare("0.10.0", "v0.9.1").exit_code(), 0);
+        assert_eq!(compare("0.1.0", "0.1.0").exit_code(), 0);
+        // Tags that are not versions never trigger an update
+        assert_eq!(compare("0.1.0", "nightly").exit_code(), 0);
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("1.2.3"), Some((1, 2, 3)));
+        assert_eq!(parse_version("1.2.3-rc.1"), Some((1, 2, 3)));
+        assert_eq!(parse_version("2"), Some((2, 0, 0)));
+        assert_eq!(parse_version("latest"), None);
+    }
+
+    #[test]
+    fn test_parse_checksum() {
+        let digest = "a".repeat(64);
+        assert_eq!(
+            parse_checksum(&format!("{}  git-partial-x86_64-linux\n", digest)),
+            Some(digest.clone())
+        );
+        assert_eq!(parse_checksum(&digest.to_uppercase()), Some(digest));
+        assert_eq!(parse_checksum("not a checksum"), None);
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        use ring::signature::{Ed25519KeyPair, KeyPair};
+
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new()).unwrap();
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let engine = base64::engine::general_purpose::STANDARD;
+        let public_key = engine.encode(key_pair.public_key().as_ref());
+        let signature = format!("{}\n", engine.encode(key_pair.sign(b"binary").as_ref()));
+
+        assert!(verify_signature(&public_key, b"binary", &signature).is_ok());
+        assert!(verify_signature(&public_key, b"tampered", &signature)
+            .unwrap_err()
+            .to_string()
+            .contains("does not match"));
+        assert!(verify_signature(&public_key, b"binary", "not base64!").is_err());
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
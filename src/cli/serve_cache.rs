@@ -0,0 +1,332 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::process::Command;
+use tokio::sync::Mutex;
+use tracing::{debug_span, field, info, info_span, warn, Instrument};
+
+use crate::core::config;
+use crate::core::metrics;
+use crate::git::commands;
+use crate::utils::http_server;
+
+/// Default port of the git protocol
+const DEFAULT_PORT: u16 = 9418;
+
+/// Cache directory unless given or set in the global config
+const DEFAULT_CACHE_DIR: &str = "git-partial-cache";
+
+/// Largest pkt-line the git protocol allows
+const MAX_PKT_LEN: usize = 65520;
+
+/// Settings shared by every connection
+struct CacheServer {
+    /// Base URL repositories are fetched from, e.g. `https://github.com`
+    origin: String,
+
+    /// Directory holding one mirror per repository
+    cache_dir: PathBuf,
+
+    /// How long a mirror is served before it is refreshed from origin
+    refresh_after: Duration,
+
+    /// When each mirror was last refreshed; also serializes refreshes
+    refreshed: Mutex<HashMap<PathBuf, Instant>>,
+}
+
+/// A `git://` request, as sent in the first pkt-line
+#[derive(Debug, PartialEq)]
+struct Request {
+    /// Requested service, e.g. `git-upload-pack`
+    service: String,
+
+    /// Repository path, e.g. `/org/repo.git`
+    path: String,
+
+    /// Protocol parameters for upload-pack, e.g. `version=2`
+    protocol: Option<String>,
+}
+
+/// Serve repositories over the git protocol from a local cache of origin.
+/// Each repository is fetched from origin on its first request and refreshed
+/// at most every `refresh_after` seconds; requests in between are served from
+/// the cache. Mirrors are complete so blobless clients can fetch any blob.
+/// With `metrics_listen`, Prometheus metrics of the cache are served there.
+pub async fn serve_cache(
+    listen: &str,
+    origin: &str,
+    cache_dir: Option<&str>,
+    refresh_after: u64,
+    metrics_listen: Option<&str>,
+) -> Result<()> {
+    let cache_dir = cache_dir
+        .or(config::global().cache_dir.as_deref())
+        .unwrap_or(DEFAULT_CACHE_DIR);
+    let address = listen_address(listen);
+    let listener = TcpListener::bind(&address)
+        .await
+        .with_context(|| format!("Failed to listen on {}", address))?;
+    println!(
+        "Serving {} from cache {} on git://{}",
+        origin,
+        cache_dir,
+        listener.local_addr()?
+    );
+
+    if let Some(listen) = metrics_listen {
+        http_server::serve_metrics(listen).await?;
+    }
+
+    let server = Arc::new(CacheServer {
+        origin: origin.trim_end_matches('/').to_string(),
+        cache_dir: PathBuf::from(cache_dir),
+        refresh_after: Duration::from_secs(refresh_after),
+        refreshed: Mutex::new(HashMap::new()),
+    });
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let server = Arc::clone(&server);
+        tokio::spawn(
+            async move {
+                if let Err(e) = handle_connection(&server, stream).await {
+                    warn!("Request from {} failed: {:#}", peer, e);
+                }
+            }
+            .instrument(info_span!("connection", %peer)),
+        );
+    }
+}
+
+async fn handle_connection(
+    server: &CacheServer,
+    mut stream: TcpStream,
+) -> Result<()> {
+    let request = match read_pkt_line(&mut stream)
+        .await?
+        .and_then(|line| parse_request(&line))
+    {
+        Some(request) => request,
+        None => return send_error(&mut stream, "invalid request").await,
+    };
+    if request.service != "git-upload-pack" {
+        return send_error(&mut stream, "only fetching and cloning are supported").await;
+    }
+    let Some(relative) = sanitize_path(&request.path) else {
+        return send_error(&mut stream, "invalid repository path").await;
+    };
+    info!("{} {}", request.service, request.path);
+
+    let mirror = server.cache_dir.join(&relative);
+    if let Err(e) = server.ensure_fresh(&relative, &mirror).await {
+        return send_error(&mut stream, &format!("{:#}", e)).await;
+    }
+
+    let span = debug_span!(
+        "git",
+        args = %format!("upload-pack --strict {}", mirror.display()),
+        duration_ms = field::Empty,
+        exit_code = field::Empty
+    );
+    let started = Instant::now();
+    let mut command = Command::new("git");
+    command
+        .args(["upload-pack", "--strict"])
+        .arg(&mirror)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped());
+    if let Some(protocol) = &request.protocol {
+        command.env("GIT_PROTOCOL", protocol);
+    }
+    let mut child = command.spawn().context("Failed to start upload-pack")?;
+
+    let mut stdin = child.stdin.take().context("upload-pack has no stdin")?;
+    let mut stdout = child.stdout.take().context("upload-pack has no stdout")?;
+    let (mut reader, mut writer) = stream.into_split();
+    let to_git = tokio::spawn(async move { tokio::io::copy(&mut reader, &mut stdin).await });
+    tokio::io::copy(&mut stdout, &mut writer).await?;
+    writer.shutdown().await.ok();
+    to_git.abort();
+    let status = child.wait().await?;
+
+    span.record("duration_ms", started.elapsed().as_millis() as u64);
+    if let Some(code) = status.code() {
+        span.record("exit_code", code);
+    }
+    Ok(())
+}
+
+impl CacheServer {
+    /// Mirror the repository on a cache miss, or refresh a stale mirror.
+    /// A stale mirror is still served when origin cannot be reached.
+    async fn ensure_fresh(
+        &self,
+        relative: &Path,
+        mirror: &Path,
+    ) -> Result<()> {
+        let mut refreshed = self.refreshed.lock().await;
+        let url = format!("{}/{}", self.origin, relative.to_string_lossy());
+
+        let started = Instant::now();
+        if !mirror.exists() {
+            info!("Cache miss, mirroring {}", url);
+            metrics::count_cache_request("miss");
+            let mirror = mirror.to_path_buf();
+            let origin_url = url.clone();
+            tokio::task::spawn_blocking(move || create_mirror(&origin_url, &mirror)).await??;
+        } else if refreshed
+            .get(relative)
+            .is_none_or(|at| at.elapsed() >= self.refresh_after)
+        {
+            info!("Refreshing {}", url);
+            let dir = mirror.to_path_buf();
+            let result = tokio::task::spawn_blocking(move || {
+                commands::run_git_command_in_dir(&dir, &["fetch", "--prune", "--quiet", "origin"])
+            })
+            .await?;
+            if let Err(e) = result {
+                warn!("Serving stale cache of {}: {:#}", url, e);
+                metrics::count_cache_request("stale");
+                return Ok(());
+            }
+            metrics::count_cache_request("refresh");
+        } else {
+            metrics::count_cache_request("hit");
+            return Ok(());
+        }
+
+        metrics::observe_fetch(&url, started.elapsed().as_secs_f64());
+        refreshed.insert(relative.to_path_buf(), Instant::now());
+        Ok(())
+    }
+}
+
+fn create_mirror(
+    url: &str,
+    mirror: &Path,
+) -> Result<()> {
+    if let Some(parent) = mirror.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {:?}", parent))?;
+    }
+    commands::run_git_command_in_dir(
+        Path::new("."),
+        &[
+            "clone",
+            "--mirror",
+            "--quiet",
+            "--",
+            url,
+            &mirror.to_string_lossy(),
+        ],
+    )
+    .with_context(|| format!("Failed to mirror {}", url))?;
+    commands::run_git_command_in_dir(mirror, &["config", "uploadpack.allowFilter", "true"])?;
+    commands::run_git_command_in_dir(mirror, &["config", "uploadpack.allowAnySHA1InWant", "true"])?;
+    Ok(())
+}
+
+/// Accepts `:9418`-style addresses as all interfaces
+fn listen_address(listen: &str) -> String {
+    match listen {
+        "" => format!("0.0.0.0:{}", DEFAULT_PORT),
+        _ if listen.starts_with(':') => format!("0.0.0.0{}", listen),
+        _ => listen.to_string(),
+    }
+}
+
+/// Parses `git-upload-pack /path\0host=example.com\0\0version=2\0`
+fn parse_request(line: &[u8]) -> Option<Request> {
+    let line = String::from_utf8_lossy(line);
+    let mut fields = line.split('\0');
+    let (service, path) = fields.next()?.trim_end().split_once(' ')?;
+
+    // Extra parameters follow the host after an empty field
+    let protocol: Vec<&str> = fields
+        .skip_while(|field| !field.is_empty())
+        .filter(|field| !field.is_empty())
+        .collect();
+    Some(Request {
+        service: service.to_string(),
+        path: path.to_string(),
+        protocol: (!protocol.is_empty()).then(|| protocol.join(":")),
+    })
+}
+
+/// Turns a request path into a path relative to the cache, refusing `..`
+fn sanitize_path(path: &str) -> Option<PathBuf> {
+    let relative = path.trim_start_matches('/');
+    let valid = !relative.is_empty()
+        && relative
+            .split('/')
+            .all(|component| !component.is_empty() && component != "." && component != "..");
+    valid.then(|| PathBuf::from(relative))
+}
+
+async fn read_pkt_line(stream: &mut TcpStream) -> Result<Option<Vec<u8>>> {
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await?;
+    let Some(len) = std::str::from_utf8(&header)
+        .ok()
+        .and_then(|hex| usize::from_str_radix(hex, 16).ok())
+        .filter(|len| (5..=MAX_PKT_LEN).contains(len))
+    else {
+        return Ok(None);
+    };
+    let mut line = vec![0u8; len - 4];
+    stream.read_exact(&mut line).await?;
+    Ok(Some(line))
+}
+
+async fn send_error(
+    stream: &mut TcpStream,
+    message: &str,
+) -> Result<()> {
+    let payload = format!("ERR {}\n", message);
+    stream
+        .write_all(format!("{:04x}{}", payload.len() + 4, payload).as_bytes())
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        assert_eq!(
+            parse_request(b"git-upload-pack /org/repo.git\0host=cache:9418\0\0version=2\0"),
+            Some(Request {
+                service: "git-upload-pack".to_string(),
+                path: "/org/repo.git".to_string(),
+                protocol: Some("version=2".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_request(b"git-upload-pack /repo\0host=cache\0").map(|r| r.protocol),
+            Some(None)
+        );
+        assert_eq!(parse_request(b"garbage"), None);
+    }
+
+    #[test]
+    fn test_sanitize_path() {
+        assert_eq!(
+            sanitize_path("/org/repo.git"),
+            Some(PathBuf::from("org/repo.git"))
+        );
+        assert_eq!(sanitize_path("/org/../../etc"), None);
+        assert_eq!(sanitize_path("/"), None);
+    }
+
+    #[test]
+    fn test_listen_address() {
+        assert_eq!(listen_address(":9418"), "0.0.0.0:9418");
+        assert_eq!(listen_address("127.0.0.1:1234"), "127.0.0.1:1234");
+    }
+}
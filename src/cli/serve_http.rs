@@ -0,0 +1,253 @@
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{info, info_span, warn, Instrument};
+
+use crate::cli::{savings, status};
+use crate::core::incoming;
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::registry;
+use crate::core::savings as clone_savings;
+use crate::git::backend::{CommandBackend, GitBackend};
+use crate::git::commands;
+use crate::git::upstream;
+use crate::utils::http_server;
+
+/// A repository served over HTTP, named after its directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServedRepository {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Names the repositories after their directory. Repeated names get a
+/// `-2`, `-3`... suffix in order, so a name stays stable while the list only grows.
+pub fn name_repositories(paths: &[PathBuf]) -> Vec<ServedRepository> {
+    let mut served: Vec<ServedRepository> = Vec::new();
+    for path in paths {
+        let base = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "repository".to_string());
+        let mut name = base.clone();
+        let mut suffix = 1;
+        while served.iter().any(|repo| repo.name == name) {
+            suffix += 1;
+            name = format!("{}-{}", base, suffix);
+        }
+        served.push(ServedRepository {
+            name,
+            path: path.clone(),
+        });
+    }
+    served
+}
+
+/// Serve read-only JSON about the registered clones, and `extra` ones, for
+/// dashboards. The registry is read again on every request, so new clones
+/// show up without a restart. Nothing is fetched: figures reflect the last
+/// fetch of each clone.
+pub async fn serve_http(
+    listen: &str,
+    extra: &[String],
+) -> Result<()> {
+    let mut extra_paths = Vec::new();
+    for path in extra {
+        extra_paths
+            .push(fs::canonicalize(path).with_context(|| format!("Failed to resolve {}", path))?);
+    }
+    let extra_paths = Arc::new(extra_paths);
+
+    let listener = TcpListener::bind(listen)
+        .await
+        .with_context(|| format!("Failed to listen on {}", listen))?;
+    println!(
+        "Serving repository status on http://{}",
+        listener.local_addr()?
+    );
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let extra_paths = Arc::clone(&extra_paths);
+        tokio::spawn(
+            async move {
+                if let Err(e) = handle_connection(stream, &extra_paths).await {
+                    warn!("Request from {} failed: {:#}", peer, e);
+                }
+            }
+            .instrument(info_span!("connection", %peer)),
+        );
+    }
+}
+
+/// The clones to serve: the registry's, then `extra`, skipping those whose
+/// metadata is gone
+fn repositories(extra: &[PathBuf]) -> Vec<ServedRepository> {
+    let mut paths = registry::load();
+    for path in extra {
+        if !paths.contains(path) {
+            paths.push(path.clone());
+        }
+    }
+    paths.retain(|path| RepositoryMetadata::exists(path));
+    name_repositories(&paths)
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    extra: &[PathBuf],
+) -> Result<()> {
+    let Some(request) = http_server::read_request(&mut stream).await? else {
+        return Ok(());
+    };
+    info!("{} {}", request.method, request.path);
+    if request.method != "GET" {
+        let body = json!({"error": "only GET is supported"});
+        return send_json(&mut stream, 405, &body).await;
+    }
+
+    let repositories = repositories(extra);
+    // Reading git state blocks; keep it off the runtime's workers
+    let (status, body) = tokio::task::spawn_blocking(move || respond(&request.path, &repositories))
+        .await
+        .context("Request handler panicked")?;
+    send_json(&mut stream, status, &body).await
+}
+
+/// Routes a request path to its JSON response and status code
+fn respond(
+    path: &str,
+    repositories: &[ServedRepository],
+) -> (u16, Value) {
+    let segments: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+    let (name, endpoint) = match segments.as_slice() {
+        ["repos"] => {
+            let list: Vec<Value> = repositories
+                .iter()
+                .map(|repo| {
+                    let metadata = RepositoryMetadata::load(&repo.path).ok();
+                    json!({
+                        "name": repo.name,
+                        "path": repo.path,
+                        "remote_url": metadata.as_ref().map(|m| m.remote_url.clone()),
+                        "bare": metadata.is_some_and(|m| m.bare),
+                    })
+                })
+                .collect();
+            return (200, Value::Array(list));
+        }
+        ["repos", name, endpoint] => (*name, *endpoint),
+        _ => return (404, json!({"error": format!("no such endpoint: {}", path)})),
+    };
+    let Some(repo) = repositories.iter().find(|repo| repo.name == name) else {
+        return (
+            404,
+            json!({"error": format!("no such repository: {}", name)}),
+        );
+    };
+    let result = match endpoint {
+        "status" => status_of(&repo.path),
+        "paths" => paths_of(&repo.path),
+        "savings" => savings_of(&repo.path),
+        "incoming" => incoming_of(&repo.path),
+        _ => return (404, json!({"error": format!("no such endpoint: {}", path)})),
+    };
+    match result {
+        Ok(body) => (200, body),
+        Err(e) => (500, json!({"error": format!("{:#}", e)})),
+    }
+}
+
+async fn send_json(
+    stream: &mut TcpStream,
+    status: u16,
+    body: &Value,
+) -> Result<()> {
+    let body = serde_json::to_string_pretty(body)?;
+    http_server::send(stream, status, "application/json", &body).await
+}
+
+/// The `status --format json` report, without fetching
+fn status_of(repo_path: &Path) -> Result<Value> {
+    let metadata = RepositoryMetadata::load(repo_path).context("Failed to load metadata")?;
+    let report = status::collect_status(repo_path, &metadata, None, false)?;
+    Ok(serde_json::to_value(report)?)
+}
+
+fn paths_of(repo_path: &Path) -> Result<Value> {
+    let metadata = RepositoryMetadata::load(repo_path).context("Failed to load metadata")?;
+    let mut paths: Vec<&String> = metadata.checked_out_paths.iter().collect();
+    paths.sort();
+    let mut pinned: Vec<&String> = metadata.pinned_paths.iter().collect();
+    pinned.sort();
+    Ok(json!({ "paths": paths, "pinned": pinned }))
+}
+
+/// Size of the clone against a full clone, measured when the remote is on
+/// this machine, otherwise as last measured by `savings`
+fn savings_of(repo_path: &Path) -> Result<Value> {
+    let metadata = RepositoryMetadata::load(repo_path).context("Failed to load metadata")?;
+    let partial = savings::partial_stats(repo_path, metadata.bare)?;
+    let (full, cached) = match crate::remote::local_path(&metadata.remote_url) {
+        Some(remote_path) => (Some(savings::measure_full_clone(&remote_path)?), false),
+        None => (metadata.full_clone_stats.clone(), true),
+    };
+    let saved_percent = full
+        .as_ref()
+        .map(|full| clone_savings::saved_percent(partial.total_bytes(), full.total_bytes()));
+    Ok(json!({
+        "partial": partial,
+        "full": full,
+        "cached": cached,
+        "saved_percent": saved_percent,
+    }))
+}
+
+/// Changes between HEAD and its upstream, as last fetched, grouped by sparse path
+fn incoming_of(repo_path: &Path) -> Result<Value> {
+    let metadata = RepositoryMetadata::load(repo_path).context("Failed to load metadata")?;
+    let backend = CommandBackend::new(repo_path);
+    let branch = backend
+        .current_branch()
+        .context("Failed to get current branch")?;
+    let upstream = upstream::resolve(&backend, &branch, None)?.rev();
+    let base = commands::run_git_command_in_dir(repo_path, &["merge-base", "HEAD", &upstream])
+        .with_context(|| format!("Failed to compare HEAD with {}", upstream))?;
+    let changes = commands::diff_name_status(repo_path, &base, &upstream)?;
+    let patterns: Vec<String> = metadata.checked_out_paths.iter().cloned().collect();
+    Ok(json!({
+        "upstream": upstream,
+        "commits": status::incoming_commits(repo_path, &upstream, &patterns),
+        "changes": incoming::summarize(&changes, &patterns, None),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name_repositories() {
+        let served = name_repositories(&[
+            PathBuf::from("/work/app"),
+            PathBuf::from("/other/app"),
+            PathBuf::from("/work/web"),
+        ]);
+        let names: Vec<&str> = served.iter().map(|repo| repo.name.as_str()).collect();
+        assert_eq!(names, vec!["app", "app-2", "web"]);
+    }
+
+    #[test]
+    fn test_respond_routes() {
+        let repositories = name_repositories(&[PathBuf::from("/nonexistent/app")]);
+        assert_eq!(respond("/repos", &repositories).0, 200);
+        assert_eq!(respond("/repos/web/status", &repositories).0, 404);
+        assert_eq!(respond("/repos/app/unknown", &repositories).0, 404);
+        assert_eq!(respond("/", &repositories).0, 404);
+        // The metadata cannot be read
+        assert_eq!(respond("/repos/app/paths", &repositories).0, 500);
+    }
+}
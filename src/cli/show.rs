@@ -0,0 +1,40 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::fs;
+use std::io::Write;
+use tracing::info;
+
+use crate::git::commands;
+
+/// Print a file at a revision without adding it to the sparse checkout.
+/// `spec` is `<ref>:<path>`, or just `<path>` for HEAD.
+pub async fn show_file(
+    spec: &str,
+    output: Option<&str>,
+) -> Result<()> {
+    let spec = if spec.contains(':') {
+        spec.to_string()
+    } else {
+        format!("HEAD:{}", spec)
+    };
+    info!("Showing {}", spec);
+
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let content = commands::read_blob(&current_dir, &spec)
+        .with_context(|| format!("Failed to read {}", spec))?;
+
+    match output {
+        Some(path) => {
+            fs::write(path, &content).with_context(|| format!("Failed to write {}", path))?;
+            eprintln!("Saved {} ({} bytes) to {}", spec, content.len(), path);
+        }
+        None => {
+            let mut stdout = std::io::stdout();
+            stdout
+                .write_all(&content)
+                .context("Failed to write to stdout")?;
+            stdout.flush().context("Failed to write to stdout")?;
+        }
+    }
+    Ok(())
+}
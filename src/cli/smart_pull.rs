@@ -1,50 +1,631 @@
 use anyhow::{Context, Result};
-use log::info;
 use std::env;
+use std::fmt;
+use std::path::Path;
+use tracing::info;
 
+use crate::cli::{history, policy, pull_conflicts, tree, what_changed};
+use crate::core::error::PartialError;
+use crate::core::incoming::{self, IncomingSummary, PatternMove};
 use crate::core::metadata::RepositoryMetadata;
+use crate::core::pull_state::{Integration, PullInProgress};
+use crate::git::backend::{CommandBackend, GitBackend};
 use crate::git::commands;
+use crate::git::pins;
 use crate::git::sparse;
+use crate::git::upstream;
+use crate::utils::prompt;
 
-/// Smart pull updates only the checked-out paths
-pub async fn perform_smart_pull() -> Result<()> {
+/// What `smart-pull --branch` did for a branch other than the current one
+#[derive(Debug, PartialEq)]
+pub enum BranchUpdate {
+    /// Only the remote-tracking ref was updated
+    Fetched { upstream: String },
+
+    /// The local branch already contained its upstream
+    UpToDate { branch: String },
+
+    /// The local branch was fast-forwarded
+    FastForwarded {
+        branch: String,
+        from: String,
+        to: String,
+    },
+}
+
+impl fmt::Display for BranchUpdate {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            BranchUpdate::Fetched { upstream } => write!(f, "Fetched {}", upstream),
+            BranchUpdate::UpToDate { branch } => write!(f, "{} is up to date", branch),
+            BranchUpdate::FastForwarded { branch, from, to } => {
+                write!(
+                    f,
+                    "Fast-forwarded {}: {}..{}",
+                    branch,
+                    short(from),
+                    short(to)
+                )
+            }
+        }
+    }
+}
+
+/// Smart pull updates only the checked-out paths.
+/// Pulls from the branch's configured upstream unless `upstream_override` is given,
+/// or from the same branch on `from_remote` (e.g. `upstream` for forks).
+///
+/// With `branch` naming another branch than the current one, only that
+/// branch's remote-tracking ref is fetched, and with `fast_forward` the
+/// local branch is fast-forwarded too; the checkout is left alone.
+///
+/// When upstream moved a sparse path's files outside the sparse paths, the
+/// pattern is rewritten to follow them with `follow_renames`, or after
+/// asking with `confirm`; otherwise the move is only reported.
+///
+/// When local commits diverged from the upstream, `integration` rebases or
+/// merges them instead of failing. A pull that stops at conflicts is
+/// finished with [`continue_pull`] or undone with [`abort_pull`].
+///
+/// Returns the messages for the user rather than printing them, so the
+/// dashboard can show them.
+pub async fn perform_smart_pull(
+    upstream_override: Option<&str>,
+    from_remote: Option<&str>,
+    branch: Option<&str>,
+    fast_forward_branch: bool,
+    confirm: bool,
+    follow_renames: bool,
+    integration: Option<Integration>,
+) -> Result<String> {
     info!("Starting smart pull");
 
-    // Check if repo is using sparse checkout
-    if !sparse::is_sparse_checkout()? {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    if PullInProgress::load(&current_dir)?.is_some() {
         anyhow::bail!(
-            "This repository is not using sparse checkout. Did you clone it with git-partial?"
+            "A smart-pull stopped at conflicts; finish it with 'git-partial smart-pull --continue' or undo it with '--abort'"
         );
     }
+    if RepositoryMetadata::load(&current_dir).is_ok_and(|metadata| metadata.bare) {
+        return pull_bare(&current_dir).await;
+    }
 
-    // Fetch latest changes
-    info!("Fetching latest changes");
-    commands::run_git_command(&["fetch", "origin"]).context("Failed to fetch changes")?;
+    // Check if repo is using sparse checkout
+    if !sparse::is_sparse_checkout()? {
+        return Err(PartialError::SparseCheckoutDisabled.into());
+    }
 
-    // Get current branch
-    let current_branch = commands::run_git_command(&["branch", "--show-current"])
-        .context("Failed to get current branch")?
-        .trim()
-        .to_string();
+    let backend = CommandBackend::new(&current_dir);
+    if let Some(branch) = branch {
+        let current_branch = backend
+            .current_branch()
+            .context("Failed to get current branch")?;
+        if branch != current_branch {
+            let update = pull_branch_with(
+                &backend,
+                branch,
+                upstream_override,
+                from_remote,
+                fast_forward_branch,
+            )?;
+            return Ok(format!("{}\n", update));
+        }
+    }
 
-    info!("Current branch: {}", current_branch);
+    let (current_branch, remote_ref) = fetch_upstream(&backend, upstream_override, from_remote)?;
+    let mut metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
 
-    // Perform a merge-based pull optimized for sparse checkout
-    commands::run_git_command(&["merge", "--ff-only", &format!("origin/{}", current_branch)])
-        .context("Failed to perform smart pull")?;
+    // Preview what the merge brings into the sparse paths; skipped when
+    // there is nothing to merge or the histories diverged
+    let incoming = backend.rev_parse("HEAD")? != backend.rev_parse(&remote_ref)?
+        && backend.is_ancestor("HEAD", &remote_ref)?;
+    let mut moves = Vec::new();
+    let mut output = String::new();
+    if incoming {
+        moves = detect_moves(&current_dir, &metadata, &remote_ref)?;
+        let summary = preview_incoming(&current_dir, &metadata, "HEAD", &remote_ref).await?;
+        // Nothing to confirm when the checkout does not change
+        if !confirm || summary.is_empty() {
+            output.push_str(&summary.to_string());
+        } else if !prompt::confirm(summary.to_string().trim_end(), false)? {
+            return Ok(format!(
+                "Not merged. The fetched changes stay in {}.\n",
+                remote_ref
+            ));
+        }
+    }
+
+    // Pinned paths sit out the merge at HEAD's version, then go back to
+    // their commit, so the merge neither trips over nor updates them
+    for (path, commit) in &metadata.pinned_revisions {
+        if pins::has_local_changes(&current_dir, path, commit)? {
+            return Err(PartialError::PinnedPathChanged {
+                path: path.clone(),
+                commit: commit.clone(),
+            }
+            .into());
+        }
+        pins::release(&current_dir, path, commit)?;
+    }
+    let merged = match integration {
+        Some(integration) if !backend.is_ancestor("HEAD", &remote_ref)? => {
+            pull_conflicts::integrate(&current_dir, integration, &remote_ref, &mut output)
+        }
+        _ => fast_forward(&backend, &current_branch, &remote_ref).map(Some),
+    };
+    // Stopped at conflicts: the pins go back once the pull is finished
+    if matches!(merged, Ok(None)) {
+        return Ok(output);
+    }
+    for (path, commit) in &metadata.pinned_revisions {
+        pins::apply(&current_dir, path, commit)?;
+    }
+    let Some(head_commit) = merged? else {
+        return Ok(output);
+    };
 
     // After successful pull, update the metadata
+    metadata.set_last_commit(&head_commit);
+    if !moves.is_empty() {
+        follow_moves(
+            &current_dir,
+            &mut metadata,
+            &moves,
+            confirm,
+            follow_renames,
+            &mut output,
+        )
+        .await?;
+    }
+
+    metadata
+        .save(&current_dir)
+        .context("Failed to save updated metadata after pull")?;
+    what_changed::record(&current_dir, &head_commit);
+
+    info!("Smart pull completed successfully and metadata updated");
+    Ok(output)
+}
+
+/// Finish a smart-pull that stopped at conflicts, once they are resolved
+/// and staged, and bring the metadata up to date. Returns the messages for
+/// the user, as [`perform_smart_pull`] does.
+pub async fn continue_pull() -> Result<String> {
     let current_dir = env::current_dir().context("Failed to get current directory")?;
-    let mut metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+    let state =
+        PullInProgress::load(&current_dir)?.context("No smart-pull is stopped at conflicts")?;
+    let mut output = String::new();
+    let Some(head_commit) = pull_conflicts::resume(&current_dir, &state, &mut output)? else {
+        return Ok(output);
+    };
 
-    let head_commit = commands::get_head_commit(&current_dir)
-        .context("Failed to get new HEAD commit after pull")?;
+    let mut metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+    for (path, commit) in &metadata.pinned_revisions {
+        pins::apply(&current_dir, path, commit)?;
+    }
     metadata.set_last_commit(&head_commit);
-
     metadata
         .save(&current_dir)
         .context("Failed to save updated metadata after pull")?;
+    what_changed::record(&current_dir, &head_commit);
+    PullInProgress::clear(&current_dir)?;
+    output.push_str(&format!(
+        "Finished the {} onto {} at {}\n",
+        state.integration.command(),
+        state.upstream,
+        short(&head_commit)
+    ));
+    Ok(output)
+}
+
+/// Undo a smart-pull that stopped at conflicts, returning to the commit it
+/// started from
+pub async fn abort_pull() -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let state =
+        PullInProgress::load(&current_dir)?.context("No smart-pull is stopped at conflicts")?;
+    pull_conflicts::abort(&current_dir, &state)?;
+
+    let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+    for (path, commit) in &metadata.pinned_revisions {
+        pins::apply(&current_dir, path, commit)?;
+    }
+    PullInProgress::clear(&current_dir)?;
+    Ok(format!(
+        "Aborted the {}; back at {}\n",
+        state.integration.command(),
+        short(&state.original_head)
+    ))
+}
+
+/// Fetch the upstream of the current branch and return the branch and the
+/// upstream revision to merge
+pub fn fetch_upstream<B: GitBackend>(
+    backend: &B,
+    upstream_override: Option<&str>,
+    from_remote: Option<&str>,
+) -> Result<(String, String)> {
+    // Get current branch
+    let current_branch = backend
+        .current_branch()
+        .context("Failed to get current branch")?;
+
+    info!("Current branch: {}", current_branch);
+
+    let from_override = from_remote.map(|remote| format!("{}/{}", remote, current_branch));
+    let upstream = upstream::resolve(
+        backend,
+        &current_branch,
+        upstream_override.or(from_override.as_deref()),
+    )?;
+
+    // Fetch latest changes
+    info!("Fetching latest changes from {}", upstream);
+    upstream::fetch(backend, &upstream).context("Failed to fetch changes")?;
+    Ok((current_branch, upstream.rev()))
+}
+
+/// Fast-forward the current branch to `remote_ref` and return the new HEAD.
+/// Fails with `Diverged` when local commits prevent the fast-forward.
+fn fast_forward<B: GitBackend>(
+    backend: &B,
+    current_branch: &str,
+    remote_ref: &str,
+) -> Result<String> {
+    // Perform a merge-based pull optimized for sparse checkout
+    if let Err(e) = backend.merge_ff_only(remote_ref) {
+        // A failed fast-forward usually means local and remote history diverged
+        if !backend.is_ancestor("HEAD", remote_ref).unwrap_or(false) {
+            return Err(PartialError::Diverged {
+                branch: current_branch.to_string(),
+                upstream: remote_ref.to_string(),
+            }
+            .into());
+        }
+        return Err(e.context("Failed to perform smart pull"));
+    }
+
+    backend
+        .rev_parse("HEAD")
+        .context("Failed to get new HEAD commit after pull")
+}
+
+/// Fetch the upstream of `branch`, which is not checked out, and with
+/// `fast_forward` move the local branch to it. Fails with `Diverged` when
+/// the local branch has commits the upstream lacks.
+fn pull_branch_with<B: GitBackend>(
+    backend: &B,
+    branch: &str,
+    upstream_override: Option<&str>,
+    from_remote: Option<&str>,
+    fast_forward: bool,
+) -> Result<BranchUpdate> {
+    let from_override = from_remote.map(|remote| format!("{}/{}", remote, branch));
+    let upstream = upstream::resolve(
+        backend,
+        branch,
+        upstream_override.or(from_override.as_deref()),
+    )?;
+
+    info!("Fetching {} for {}", upstream, branch);
+    upstream::fetch(backend, &upstream).context("Failed to fetch changes")?;
+    let remote_ref = upstream.rev();
+    if !fast_forward {
+        return Ok(BranchUpdate::Fetched {
+            upstream: remote_ref,
+        });
+    }
+
+    let old = backend
+        .rev_parse(branch)
+        .with_context(|| format!("Local branch '{}' does not exist", branch))?;
+    let new = backend
+        .rev_parse(&remote_ref)
+        .with_context(|| format!("Failed to resolve {}", remote_ref))?;
+    if old == new || backend.is_ancestor(&new, &old)? {
+        return Ok(BranchUpdate::UpToDate {
+            branch: branch.to_string(),
+        });
+    }
+    if !backend.is_ancestor(&old, &new)? {
+        return Err(PartialError::Diverged {
+            branch: branch.to_string(),
+            upstream: remote_ref,
+        }
+        .into());
+    }
+    backend
+        .update_branch(branch, &new, &old)
+        .with_context(|| format!("Failed to fast-forward {}", branch))?;
+    Ok(BranchUpdate::FastForwarded {
+        branch: branch.to_string(),
+        from: old,
+        to: new,
+    })
+}
+
+/// Update a bare clone: its branches follow the remote's directly, as there
+/// is no checkout to merge into. Returns what changed under the selected paths.
+async fn pull_bare(repo_path: &Path) -> Result<String> {
+    let backend = CommandBackend::new(repo_path);
+    let mut metadata = RepositoryMetadata::load(repo_path).context("Failed to load metadata")?;
+    let old = backend.rev_parse("HEAD")?;
+    info!("Fetching the branches of origin");
+    commands::run_git_command_in_dir(repo_path, &["fetch", "origin", "--prune", "--quiet"])
+        .context("Failed to fetch changes")?;
+    let new = backend.rev_parse("HEAD")?;
+    let output = if old != new {
+        preview_incoming(repo_path, &metadata, &old, &new)
+            .await?
+            .to_string()
+    } else {
+        String::new()
+    };
 
+    metadata.set_last_commit(&new);
+    metadata
+        .save(repo_path)
+        .context("Failed to save updated metadata after pull")?;
+    what_changed::record(repo_path, &new);
     info!("Smart pull completed successfully and metadata updated");
+    Ok(output)
+}
+
+/// Summarize the changes between `from` and `remote_ref` by sparse pattern.
+/// Sizes come from the promisor remote, as in `add-paths`.
+async fn preview_incoming(
+    repo_path: &Path,
+    metadata: &RepositoryMetadata,
+    from: &str,
+    remote_ref: &str,
+) -> Result<IncomingSummary> {
+    let changes = commands::diff_name_status(repo_path, from, remote_ref)
+        .context("Failed to list incoming changes")?;
+    let commit = CommandBackend::new(repo_path).rev_parse(remote_ref)?;
+    let entries = tree::remote_tree_entries(&metadata.remote_url, &commit).await;
+    // Paths pinned at a commit do not take the changes
+    let patterns: Vec<String> = metadata
+        .checked_out_paths
+        .iter()
+        .filter(|path| !metadata.pinned_revisions.contains_key(*path))
+        .cloned()
+        .collect();
+    Ok(incoming::summarize(&changes, &patterns, entries.as_deref()))
+}
+
+/// Finds the sparse paths whose files `remote_ref` moves outside the
+/// sparse paths. Paths pinned at a commit keep their files, so they are
+/// left out.
+fn detect_moves(
+    repo_path: &Path,
+    metadata: &RepositoryMetadata,
+    remote_ref: &str,
+) -> Result<Vec<PatternMove>> {
+    let before = commands::list_blob_ids(repo_path, "HEAD").context("Failed to list HEAD")?;
+    let after = commands::list_blob_ids(repo_path, remote_ref)
+        .with_context(|| format!("Failed to list {}", remote_ref))?;
+    let patterns: Vec<String> = metadata
+        .checked_out_paths
+        .iter()
+        .filter(|path| !metadata.pinned_revisions.contains_key(*path))
+        .cloned()
+        .collect();
+    Ok(incoming::detect_moves(&before, &after, &patterns))
+}
+
+/// Report the sparse paths upstream moved, and replace them with their new
+/// location when `follow` is set or the user agrees. Paths the
+/// organization's policy refuses are left unchanged. Messages are added to
+/// `output`.
+async fn follow_moves(
+    repo_path: &Path,
+    metadata: &mut RepositoryMetadata,
+    moves: &[PatternMove],
+    ask: bool,
+    follow: bool,
+    output: &mut String,
+) -> Result<()> {
+    let mut summary = String::from("Upstream moved sparse paths outside the checkout:\n");
+    for pattern_move in moves {
+        summary.push_str(&format!(
+            "  {} -> {} ({} file(s))\n",
+            pattern_move.pattern, pattern_move.relocated, pattern_move.files
+        ));
+    }
+    if !follow && !ask {
+        output.push_str(&summary);
+        output.push_str("Run `git-partial smart-pull --follow-renames` next time, or update them with 'git-partial add-paths' and 'remove-paths'.\n");
+        return Ok(());
+    }
+    summary.push_str("The sparse paths will be updated to follow them.");
+    if !prompt::confirm(&summary, follow)? {
+        output.push_str("Sparse paths left unchanged.\n");
+        return Ok(());
+    }
+
+    let mut patterns = metadata.checked_out_paths.clone();
+    for pattern_move in moves {
+        patterns.remove(&pattern_move.pattern);
+        patterns.insert(pattern_move.relocated.clone());
+    }
+    let patterns_vec: Vec<String> = patterns.iter().cloned().collect();
+    if let Err(e) = policy::enforce(repo_path, &metadata.remote_url, &patterns_vec, false).await {
+        output.push_str(&format!("Sparse paths left unchanged: {}\n", e));
+        return Ok(());
+    }
+    commands::set_sparse_checkout(repo_path, &patterns_vec)
+        .context("Failed to update sparse checkout paths")?;
+    metadata.checked_out_paths = patterns;
+    for pattern_move in moves {
+        if metadata.pinned_paths.remove(&pattern_move.pattern) {
+            metadata.pinned_paths.insert(pattern_move.relocated.clone());
+        }
+    }
+
+    let added: Vec<String> = moves.iter().map(|m| m.relocated.clone()).collect();
+    let removed: Vec<String> = moves.iter().map(|m| m.pattern.clone()).collect();
+    history::record(repo_path, &added, &removed);
+    for pattern_move in moves {
+        output.push_str(&format!(
+            "Now following {} at {}\n",
+            pattern_move.pattern, pattern_move.relocated
+        ));
+    }
     Ok(())
 }
+
+/// Abbreviates a commit id for messages
+fn short(commit: &str) -> &str {
+    &commit[..commit.len().min(7)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::fake::FakeBackend;
+
+    fn pull_with(
+        backend: &FakeBackend,
+        upstream_override: Option<&str>,
+        from_remote: Option<&str>,
+    ) -> Result<String> {
+        let (branch, remote_ref) = fetch_upstream(backend, upstream_override, from_remote)?;
+        fast_forward(backend, &branch, &remote_ref)
+    }
+
+    fn fork() -> FakeBackend {
+        FakeBackend::new("main")
+            .commit("base", &[])
+            .commit("local", &["base"])
+            .commit("origin-tip", &["base"])
+            .commit("upstream-tip", &["origin-tip"])
+            .reference("main", "base")
+            .remote_branch("origin", "main", "origin-tip")
+            .remote_branch("upstream", "main", "upstream-tip")
+    }
+
+    #[test]
+    fn test_fast_forwards_to_upstream() {
+        let backend = fork();
+
+        assert_eq!(pull_with(&backend, None, None).unwrap(), "origin-tip");
+        assert_eq!(backend.fetched(), vec!["origin"]);
+    }
+
+    #[test]
+    fn test_from_remote() {
+        let backend = fork();
+
+        assert_eq!(
+            pull_with(&backend, None, Some("upstream")).unwrap(),
+            "upstream-tip"
+        );
+        assert_eq!(backend.fetched(), vec!["upstream"]);
+    }
+
+    #[test]
+    fn test_configured_upstream() {
+        let backend = fork()
+            .config("branch.main.remote", "upstream")
+            .config("branch.main.merge", "refs/heads/main");
+
+        assert_eq!(pull_with(&backend, None, None).unwrap(), "upstream-tip");
+    }
+
+    #[test]
+    fn test_already_up_to_date() {
+        let backend = fork().reference("main", "origin-tip");
+
+        assert_eq!(pull_with(&backend, None, None).unwrap(), "origin-tip");
+    }
+
+    #[test]
+    fn test_diverged() {
+        let backend = fork().reference("main", "local");
+
+        let err = pull_with(&backend, None, None).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<PartialError>(),
+            Some(&PartialError::Diverged {
+                branch: "main".to_string(),
+                upstream: "origin/main".to_string(),
+            })
+        );
+        assert_eq!(backend.head(), "local");
+    }
+
+    #[test]
+    fn test_fetch_failure() {
+        let backend = fork().unreachable("origin");
+
+        let err = pull_with(&backend, None, None).unwrap_err();
+        assert!(err.to_string().contains("Failed to fetch changes"));
+        assert_eq!(backend.head(), "base");
+    }
+
+    #[test]
+    fn test_branch_fetch_only() {
+        let backend = FakeBackend::new("feature")
+            .commit("base", &[])
+            .commit("origin-tip", &["base"])
+            .reference("feature", "base")
+            .reference("main", "base")
+            .remote_branch("origin", "main", "origin-tip");
+
+        assert_eq!(
+            pull_branch_with(&backend, "main", None, None, false).unwrap(),
+            BranchUpdate::Fetched {
+                upstream: "origin/main".to_string()
+            }
+        );
+        assert_eq!(backend.rev_parse("origin/main").unwrap(), "origin-tip");
+        assert_eq!(backend.rev_parse("main").unwrap(), "base");
+    }
+
+    #[test]
+    fn test_branch_fast_forward_leaves_current_branch() {
+        let backend = FakeBackend::new("feature")
+            .commit("base", &[])
+            .commit("origin-tip", &["base"])
+            .commit("feature-tip", &["base"])
+            .reference("feature", "feature-tip")
+            .reference("main", "base")
+            .remote_branch("origin", "main", "origin-tip");
+
+        let update = pull_branch_with(&backend, "main", None, None, true).unwrap();
+        assert_eq!(
+            update,
+            BranchUpdate::FastForwarded {
+                branch: "main".to_string(),
+                from: "base".to_string(),
+                to: "origin-tip".to_string(),
+            }
+        );
+        assert_eq!(backend.rev_parse("main").unwrap(), "origin-tip");
+        assert_eq!(backend.head(), "feature-tip");
+        assert_eq!(
+            pull_branch_with(&backend, "main", None, None, true).unwrap(),
+            BranchUpdate::UpToDate {
+                branch: "main".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_branch_diverged() {
+        let backend = fork().reference("main", "local");
+
+        let err = pull_branch_with(&backend, "main", None, None, true).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<PartialError>(),
+            Some(&PartialError::Diverged {
+                branch: "main".to_string(),
+                upstream: "origin/main".to_string(),
+            })
+        );
+        assert_eq!(backend.rev_parse("main").unwrap(), "local");
+    }
+}
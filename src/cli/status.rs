@@ -1,107 +1,826 @@
 use anyhow::{Context, Result};
-use log::info;
+use serde::Serialize;
+use std::collections::HashMap;
 use std::env;
+use std::fs;
+use std::path::Path;
+use tracing::info;
 
+use crate::core::config::{self, OutputFormat};
 use crate::core::metadata::RepositoryMetadata;
+use crate::core::path_selector;
+use crate::core::tree::{self, EntryKind};
+use crate::core::url_rewrite;
+use crate::git::backend::{CommandBackend, GitBackend};
 use crate::git::commands;
 use crate::git::sparse;
+use crate::git::upstream;
+use crate::utils::disk;
 
-/// Display status information about the partial checkout
-pub async fn show_status() -> Result<String> {
+/// Number of incoming commits listed by `status`
+const INCOMING_COMMITS: usize = 5;
+
+/// Snapshot of the partial checkout state, shared by `status` and `ui`
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusReport {
+    /// The currently checked out branch
+    pub branch: String,
+
+    /// The branch compared against, e.g. `origin/main`
+    pub upstream: String,
+
+    /// Human readable relation to the remote branch
+    pub remote_status: String,
+
+    /// Relation to the remote branch, as reported by `status --check`
+    pub sync: SyncState,
+
+    /// Commits ahead of and behind the remote branch, if known
+    pub ahead_behind: Option<(usize, usize)>,
+
+    /// Latest commits on the remote branch touching the sparse paths, as
+    /// `<short id> <subject>`, newest first
+    pub incoming: Vec<String>,
+
+    /// Commits ahead of and behind the current branch on every remote that has it
+    pub remote_divergence: Vec<(String, (usize, usize))>,
+
+    /// The last synced commit recorded in metadata
+    pub last_commit: String,
+
+    /// The commit HEAD is at, when it differs from `last_commit` because
+    /// the branch was moved outside git-partial, e.g. by a raw `git pull`
+    pub head_drift: Option<String>,
+
+    /// The original repository URL
+    pub remote_url: String,
+
+    /// URL `origin` actually fetches from, when URL rewrites or `clone
+    /// --rewrite` point it elsewhere
+    pub fetch_url: Option<String>,
+
+    /// URL of `origin` when it names another repository than `remote_url`
+    pub origin_mismatch: Option<String>,
+
+    /// Whether the server ignored the partial clone filter at clone time
+    pub filter_ignored: bool,
+
+    /// Sparse checkout paths, sorted
+    pub paths: Vec<String>,
+
+    /// Tracked files each path matches at HEAD, in path order
+    pub path_usage: Vec<PathUsage>,
+
+    /// Objects reachable from HEAD that are stored locally and that are
+    /// still missing, if they could be counted
+    pub objects: Option<ObjectCounts>,
+
+    /// Paths that match no file at HEAD, such as a directory renamed upstream
+    pub stale_paths: Vec<StalePath>,
+
+    /// Lines of `git status --short`, excluding deletions outside the sparse paths
+    pub local_changes: Vec<String>,
+
+    /// Tracked files reported as deleted only because they lost their
+    /// skip-worktree bit while outside the sparse paths
+    pub outside_sparse_deletions: Vec<String>,
+}
+
+/// Files a sparse path matches and their size in the working tree
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PathUsage {
+    pub pattern: String,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// A sparse path matching nothing, and where its files seem to have moved
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StalePath {
+    pub pattern: String,
+    pub moved_to: Option<String>,
+}
+
+/// Objects of a partial clone's history, as counted by
+/// `git rev-list --objects --missing=print`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ObjectCounts {
+    pub present: usize,
+
+    /// Objects promised by the remote, fetched only when needed
+    pub missing: usize,
+}
+
+impl ObjectCounts {
+    /// Parses `rev-list` output, where missing objects are prefixed with `?`
+    pub fn parse(output: &str) -> Self {
+        let mut counts = ObjectCounts::default();
+        for line in output.lines().filter(|line| !line.is_empty()) {
+            if line.starts_with('?') {
+                counts.missing += 1;
+            } else {
+                counts.present += 1;
+            }
+        }
+        counts
+    }
+}
+
+/// Relation of the last synced commit to the remote branch
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncState {
+    UpToDate,
+    Behind,
+    Diverged,
+    Unknown,
+}
+
+/// Exit code of `status --check`: 0 up-to-date, 3 behind, 4 diverged, and
+/// 5 when the directory is not a git-partial repository (`None`).
+/// An undeterminable remote state is an ordinary failure.
+pub fn check_exit_code(state: Option<SyncState>) -> i32 {
+    match state {
+        Some(SyncState::UpToDate) => 0,
+        Some(SyncState::Behind) => 3,
+        Some(SyncState::Diverged) => 4,
+        Some(SyncState::Unknown) => 1,
+        None => 5,
+    }
+}
+
+/// Display status information about the partial checkout.
+/// Compares against the branch's configured upstream unless `upstream_override` is given.
+/// Fetching and the output format follow the `status` settings unless given as flags.
+/// With `fix`, a HEAD that drifted from the metadata is recorded as the last synced commit first.
+/// With `objects`, the present and missing objects of HEAD's history are counted too.
+/// Also returns the sync state, or `None` outside a git-partial repository.
+pub async fn show_status(
+    upstream_override: Option<&str>,
+    no_fetch: bool,
+    format: Option<OutputFormat>,
+    fix: bool,
+    objects: bool,
+) -> Result<(String, Option<SyncState>)> {
     info!("Checking partial checkout status");
     let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let format = config::output_format(format, &current_dir)?;
+    let heading = match format {
+        OutputFormat::Text => "Status:\n",
+        OutputFormat::Json => "",
+    };
 
     // Load metadata first to check if it's a git-partial repo
-    let metadata = match RepositoryMetadata::load(&current_dir) {
+    let mut metadata = match RepositoryMetadata::load(&current_dir) {
         Ok(meta) => meta,
         Err(_) => {
-            return Ok(
-                "Current directory is not a git-partial repository (metadata not found)."
-                    .to_string(),
+            let message = format!(
+                "{}Current directory is not a git-partial repository (metadata not found).",
+                heading
             );
+            return Ok((message, None));
         }
     };
 
     // Check if repo is using sparse checkout (redundant if metadata loaded, but good sanity check)
     if !sparse::is_sparse_checkout()? {
-        return Ok(
-            "Warning: Repository metadata found, but sparse checkout is not enabled.".to_string(),
+        let message = format!(
+            "{}Warning: Repository metadata found, but sparse checkout is not enabled.",
+            heading
         );
+        return Ok((message, None));
     }
 
-    // Fetch latest changes quietly
-    info!("Fetching remote changes for status check...");
-    commands::run_git_command_in_dir(&current_dir, &["fetch", "origin", "--quiet"])
-        .context("Failed to fetch remote changes")?;
+    let mut fixed = String::new();
+    if fix {
+        let head = commands::get_head_commit(&current_dir).context("Failed to resolve HEAD")?;
+        if metadata.last_commit.as_deref() != Some(head.as_str()) {
+            info!("Recording HEAD {} as the last synced commit", head);
+            metadata.set_last_commit(&head);
+            metadata
+                .save(&current_dir)
+                .context("Failed to save metadata")?;
+            fixed = format!(
+                "Recorded HEAD {} as the last synced commit.\n\n",
+                &head[..7]
+            );
+        }
+    }
+
+    let fetch = !config::no_fetch_on_status(no_fetch, &current_dir)?;
+    let mut report = collect_status(&current_dir, &metadata, upstream_override, fetch)?;
+    if objects {
+        report.objects = object_counts(&current_dir);
+    }
+
+    info!("Status check completed");
+    let output = match format {
+        OutputFormat::Text => format!("{}{}{}", heading, fixed, format_status(&report)),
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(&report).context("Failed to serialize status")?
+        }
+    };
+    Ok((output, Some(report.sync)))
+}
+
+/// Gather the status of the partial checkout, fetching from the remotes
+/// first unless `fetch` is false
+pub fn collect_status(
+    repo_path: &Path,
+    metadata: &RepositoryMetadata,
+    upstream_override: Option<&str>,
+    fetch: bool,
+) -> Result<StatusReport> {
+    let mut report = collect_status_with(
+        &CommandBackend::new(repo_path),
+        metadata,
+        upstream_override,
+        fetch,
+    )?;
+
+    // Rewrites (url.<base>.insteadOf) change where origin fetches from, not
+    // which repository it is
+    if let Some(origin) = commands::config_value(repo_path, "remote.origin.url") {
+        let rules = commands::url_rewrites(Some(repo_path));
+        let fetch_url = url_rewrite::apply(&origin, &rules);
+        if fetch_url != metadata.remote_url {
+            report.fetch_url = Some(fetch_url);
+        }
+        let expected = metadata
+            .mirror_url
+            .as_deref()
+            .unwrap_or(&metadata.remote_url);
+        if !url_rewrite::same_repository(&origin, expected, &rules) {
+            report.origin_mismatch = Some(origin);
+        }
+    }
+    if report.ahead_behind.is_some_and(|(_, behind)| behind > 0) {
+        report.incoming = incoming_commits(repo_path, &report.upstream, &report.paths);
+    }
+    report.path_usage = path_usage(repo_path, &report.paths);
+    let search_rev = commands::run_git_command_in_dir(
+        repo_path,
+        &["rev-parse", "--verify", "--quiet", &report.upstream],
+    )
+    .unwrap_or_else(|_| "HEAD".to_string());
+    report.stale_paths = report
+        .path_usage
+        .iter()
+        .filter(|usage| usage.files == 0)
+        .map(|usage| StalePath {
+            pattern: usage.pattern.clone(),
+            moved_to: find_move(repo_path, &usage.pattern, &search_rev),
+        })
+        .collect();
+    Ok(report)
+}
+
+/// Count the present and missing objects in HEAD's history. This walks
+/// the whole history, which takes long on large repositories, so it is
+/// only done on request. Listing missing objects does not fetch them.
+pub fn object_counts(repo_path: &Path) -> Option<ObjectCounts> {
+    commands::run_git_command_in_dir(
+        repo_path,
+        &["rev-list", "--objects", "--missing=print", "HEAD"],
+    )
+    .ok()
+    .map(|output| ObjectCounts::parse(&output))
+}
+
+/// Lists the latest commits on `upstream` but not HEAD that touch `patterns`
+pub fn incoming_commits(
+    repo_path: &Path,
+    upstream: &str,
+    patterns: &[String],
+) -> Vec<String> {
+    if patterns.is_empty() {
+        return Vec::new();
+    }
+    let max_count = format!("--max-count={}", INCOMING_COMMITS);
+    let range = format!("HEAD..{}", upstream);
+    let pathspecs: Vec<String> = patterns
+        .iter()
+        .map(|pattern| format!(":(glob){}", pattern.trim_start_matches('/')))
+        .collect();
+    let mut args = vec!["log", "--format=%h %s", &max_count, &range, "--"];
+    args.extend(pathspecs.iter().map(String::as_str));
+    commands::run_git_command_in_dir(repo_path, &args)
+        .map(|output| output.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Looks for the files a stale pattern last matched in the tree of `rev`:
+/// by content first, then by file name when the name is unique there.
+/// Returns the pattern rewritten for their new location.
+fn find_move(
+    repo_path: &Path,
+    pattern: &str,
+    rev: &str,
+) -> Option<String> {
+    // The commit that moved or deleted the last files under the pattern
+    let pathspec = format!(":(glob){}", pattern.trim_start_matches('/'));
+    let commit = commands::run_git_command_in_dir(
+        repo_path,
+        &["log", "-1", "--format=%H", "HEAD", "--", &pathspec],
+    )
+    .ok()
+    .filter(|commit| !commit.is_empty())?;
+    let compiled = path_selector::compile_sparse(pattern);
+    let old_files: Vec<(String, String)> =
+        commands::list_blob_ids(repo_path, &format!("{}^", commit))
+            .ok()?
+            .into_iter()
+            .filter(|(_, path)| path_selector::pattern_covers(&compiled, path))
+            .collect();
+    let new_files = commands::list_blob_ids(repo_path, rev).ok()?;
+
+    let by_id: HashMap<&str, &str> = new_files
+        .iter()
+        .map(|(id, path)| (id.as_str(), path.as_str()))
+        .collect();
+    let mut by_name: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (_, path) in &new_files {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        by_name.entry(name).or_default().push(path);
+    }
+    let moves: Vec<(String, String)> = old_files
+        .iter()
+        .filter_map(|(id, old)| {
+            let name = old.rsplit('/').next().unwrap_or(old);
+            let new = by_id.get(id.as_str()).copied().or_else(|| {
+                match by_name.get(name).map(Vec::as_slice) {
+                    Some([only]) => Some(*only),
+                    _ => None,
+                }
+            })?;
+            Some((old.clone(), new.to_string()))
+        })
+        .collect();
+    path_selector::relocate_pattern(pattern, &moves)
+}
+
+/// Counts the files at HEAD each pattern matches and sums their sizes on
+/// disk. Only trees are read, so no blob is fetched; files that are not
+/// checked out count as empty.
+fn path_usage(
+    repo_path: &Path,
+    patterns: &[String],
+) -> Vec<PathUsage> {
+    let Ok(mut entries) = commands::list_tree_structure(repo_path, "HEAD") else {
+        return Vec::new();
+    };
+    for entry in entries.iter_mut().filter(|e| e.kind == EntryKind::Blob) {
+        entry.size = Some(
+            fs::symlink_metadata(repo_path.join(&entry.path))
+                .map(|m| m.len())
+                .unwrap_or(0),
+        );
+    }
+    patterns
+        .iter()
+        .map(|pattern| {
+            let estimate = tree::estimate(&entries, std::slice::from_ref(pattern));
+            PathUsage {
+                pattern: pattern.clone(),
+                files: estimate.files,
+                bytes: estimate.bytes,
+            }
+        })
+        .collect()
+}
 
+fn collect_status_with<B: GitBackend>(
+    backend: &B,
+    metadata: &RepositoryMetadata,
+    upstream_override: Option<&str>,
+    fetch: bool,
+) -> Result<StatusReport> {
     // Get local and remote HEAD commit SHAs
     let local_commit = metadata
         .last_commit
         .clone()
         .unwrap_or_else(|| "<unknown>".to_string());
-    let current_branch =
-        commands::run_git_command_in_dir(&current_dir, &["branch", "--show-current"])
-            .context("Failed to get current branch")?
-            .trim()
-            .to_string();
-
-    let remote_commit_res = commands::run_git_command_in_dir(
-        &current_dir,
-        &["rev-parse", &format!("origin/{}", current_branch)],
-    );
-
-    let remote_status = match remote_commit_res {
-        Ok(remote_commit) if remote_commit == local_commit => "Up-to-date".to_string(),
+    let current_branch = backend
+        .current_branch()
+        .context("Failed to get current branch")?;
+    let upstream = upstream::resolve(backend, &current_branch, upstream_override)?;
+    let remote_ref = upstream.rev();
+
+    // Fetch latest changes quietly
+    if fetch {
+        info!("Fetching remote changes for status check...");
+        upstream::fetch(backend, &upstream).context("Failed to fetch remote changes")?;
+    }
+
+    let ahead_behind = backend.ahead_behind("HEAD", &remote_ref).ok();
+    let (sync, remote_status) = match backend.rev_parse(&remote_ref) {
+        Ok(remote_commit) if remote_commit == local_commit => {
+            (SyncState::UpToDate, "Up-to-date".to_string())
+        }
         Ok(remote_commit) => {
             // Check if local commit is an ancestor of remote commit
-            match commands::run_git_command_in_dir(
-                &current_dir,
-                &["merge-base", "--is-ancestor", &local_commit, &remote_commit],
-            ) {
-                Ok(_) => format!(
-                    "Behind remote ({} -> {})",
+            if backend
+                .is_ancestor(&local_commit, &remote_commit)
+                .unwrap_or(false)
+            {
+                let count = match ahead_behind {
+                    Some((_, behind)) => format!(" by {} commit(s)", behind),
+                    None => String::new(),
+                };
+                let message = format!(
+                    "Behind remote{} ({} -> {})",
+                    count,
                     &local_commit[..7],
                     &remote_commit[..7]
-                ),
-                Err(_) => format!(
-                    "Diverged from remote (local: {}, remote: {})",
+                );
+                (SyncState::Behind, message)
+            } else {
+                let count = match ahead_behind {
+                    Some((ahead, behind)) => format!(", {} ahead and {} behind", ahead, behind),
+                    None => String::new(),
+                };
+                let message = format!(
+                    "Diverged from remote{} (local: {}, remote: {})",
+                    count,
                     &local_commit[..7],
                     &remote_commit[..7]
-                ),
+                );
+                (SyncState::Diverged, message)
             }
         }
-        Err(_) => format!(
-            "Could not determine remote status for branch '{}'",
-            current_branch
-        ),
+        Err(_) => {
+            let message = format!(
+                "Could not determine remote status for branch '{}' (upstream {})",
+                current_branch, remote_ref
+            );
+            (SyncState::Unknown, message)
+        }
     };
 
+    let head_drift = backend
+        .rev_parse("HEAD")
+        .ok()
+        .filter(|head| metadata.last_commit.as_deref() != Some(head.as_str()));
+
+    // Compare against the same branch on every remote, e.g. a fork's origin and upstream
+    let mut remote_divergence = Vec::new();
+    for remote in backend.remotes().unwrap_or_default() {
+        if fetch && remote != upstream.remote && backend.fetch(&remote).is_err() {
+            continue;
+        }
+        let remote_branch = format!("{}/{}", remote, current_branch);
+        if let Ok(counts) = backend.ahead_behind("HEAD", &remote_branch) {
+            remote_divergence.push((remote_branch, counts));
+        }
+    }
+
     // Get git status --short
-    let git_status = commands::run_git_command_in_dir(&current_dir, &["status", "--short"])
-        .context("Failed to get git status")?;
+    let git_status = backend.status_short().context("Failed to get git status")?;
 
-    // Format output
+    // Files outside the sparse paths are never checked out, so a deletion
+    // there is a lost skip-worktree bit rather than a real local deletion
+    let deletions = backend
+        .unstaged_deletions()
+        .context("Failed to list deleted files")?;
+    let outside_sparse_deletions =
+        path_selector::outside_patterns(&deletions, &metadata.checked_out_paths);
+    let local_changes = git_status
+        .into_iter()
+        .filter(|line| {
+            !line
+                .strip_prefix(" D ")
+                .is_some_and(|path| outside_sparse_deletions.iter().any(|p| p == path))
+        })
+        .collect();
+
+    let mut paths: Vec<String> = metadata.checked_out_paths.iter().cloned().collect();
+    paths.sort();
+
+    Ok(StatusReport {
+        branch: current_branch,
+        upstream: remote_ref,
+        remote_status,
+        sync,
+        ahead_behind,
+        incoming: Vec::new(),
+        remote_divergence,
+        last_commit: local_commit,
+        head_drift,
+        remote_url: metadata.remote_url.clone(),
+        fetch_url: None,
+        origin_mismatch: None,
+        filter_ignored: metadata.filter_ignored,
+        paths,
+        path_usage: Vec::new(),
+        stale_paths: Vec::new(),
+        objects: None,
+        local_changes,
+        outside_sparse_deletions,
+    })
+}
+
+/// Render a status report as the text printed by `status`
+fn format_status(report: &StatusReport) -> String {
     let mut output = String::new();
     output.push_str("Git Partial Status\n");
     output.push_str("=================\n\n");
-    output.push_str(&format!("Branch: {} ({})\n", current_branch, remote_status));
-    output.push_str(&format!("Last Synced Commit: {}\n", local_commit));
-    output.push_str(&format!("Remote URL: {}\n\n", metadata.remote_url));
+    output.push_str(&format!(
+        "Branch: {} ({})\n",
+        report.branch, report.remote_status
+    ));
+    output.push_str(&format!("Upstream: {}\n", report.upstream));
+    output.push_str(&format!("Last Synced Commit: {}\n", report.last_commit));
+    if let Some(head) = &report.head_drift {
+        output.push_str(&format!(
+            "Warning: HEAD is at {}, which git-partial did not sync (e.g. a raw git pull); \
+             run 'git-partial status --fix' to record it\n",
+            head
+        ));
+    }
+    output.push_str(&format!("Remote URL: {}\n", report.remote_url));
+    if let Some(fetch_url) = &report.fetch_url {
+        output.push_str(&format!("Fetching from: {}\n", fetch_url));
+    }
+    if let Some(origin) = &report.origin_mismatch {
+        output.push_str(&format!(
+            "Warning: origin points to {}, not the cloned repository\n",
+            origin
+        ));
+    }
+    if report.filter_ignored {
+        output.push_str(
+            "Warning: the server ignored the partial clone filter; every object is local\n",
+        );
+    }
+    output.push('\n');
+
+    if !report.incoming.is_empty() {
+        output.push_str("Incoming commits touching the sparse paths:\n");
+        for commit in &report.incoming {
+            output.push_str(&format!("  {}\n", commit));
+        }
+        output.push('\n');
+    }
+
+    if report.remote_divergence.len() > 1 {
+        output.push_str("Remotes:\n");
+        for (remote_branch, (ahead, behind)) in &report.remote_divergence {
+            output.push_str(&format!(
+                "  {}: ahead {}, behind {}\n",
+                remote_branch, ahead, behind
+            ));
+        }
+        output.push('\n');
+    }
 
     output.push_str("Sparse checkout paths:\n");
-    for path in &metadata.checked_out_paths {
-        output.push_str(&format!("  - {}\n", path));
+    let width = report.paths.iter().map(|p| p.len()).max().unwrap_or(0);
+    for path in &report.paths {
+        match report
+            .path_usage
+            .iter()
+            .find(|usage| &usage.pattern == path)
+        {
+            Some(usage) => output.push_str(&format!(
+                "  - {:width$}  {} file(s), {}\n",
+                path,
+                usage.files,
+                disk::format_size(usage.bytes),
+                width = width
+            )),
+            None => output.push_str(&format!("  - {}\n", path)),
+        }
+    }
+
+    if !report.stale_paths.is_empty() {
+        output.push_str("\nStale paths (matching no file at HEAD):\n");
+        for stale in &report.stale_paths {
+            match &stale.moved_to {
+                Some(moved_to) => {
+                    output.push_str(&format!("  - {} (moved to {}?)\n", stale.pattern, moved_to))
+                }
+                None => output.push_str(&format!("  - {}\n", stale.pattern)),
+            }
+        }
+        output.push_str("  Update them with 'git-partial add-paths' and 'remove-paths'.\n");
+    }
+
+    if let Some(objects) = &report.objects {
+        let total = objects.present + objects.missing;
+        output.push_str(&format!(
+            "\nObjects in HEAD's history: {} present, {} missing",
+            objects.present, objects.missing
+        ));
+        if let Some(percent) = (objects.present * 100).checked_div(total) {
+            output.push_str(&format!(" ({}% available offline)", percent));
+        }
+        output.push('\n');
     }
 
     output.push_str("\nLocal changes:\n");
-    if git_status.trim().is_empty() {
+    if report.local_changes.is_empty() {
         output.push_str("  No changes\n");
     } else {
-        for line in git_status.lines() {
+        for line in &report.local_changes {
             output.push_str(&format!("  {}\n", line));
         }
     }
 
-    info!("Status check completed");
-    Ok(output)
+    if !report.outside_sparse_deletions.is_empty() {
+        output.push_str("\nDeleted outside the sparse paths (not real deletions):\n");
+        for path in &report.outside_sparse_deletions {
+            output.push_str(&format!("  {}\n", path));
+        }
+        output.push_str("  Run 'git-partial repair' to hide them from git again.\n");
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::fake::FakeBackend;
+
+    const BASE: &str = "1111111aaaaaaa";
+    const LOCAL: &str = "2222222bbbbbbb";
+    const REMOTE: &str = "3333333ccccccc";
+
+    fn metadata_at(commit: &str) -> RepositoryMetadata {
+        let mut metadata = RepositoryMetadata::new("https://example.com/repo.git".to_string());
+        metadata.add_paths(&["src/**".to_string()]);
+        metadata.set_last_commit(commit);
+        metadata
+    }
+
+    fn repo(
+        local: &str,
+        remote: &str,
+    ) -> FakeBackend {
+        FakeBackend::new("main")
+            .commit(BASE, &[])
+            .commit(LOCAL, &[BASE])
+            .commit(REMOTE, &[BASE])
+            .reference("main", local)
+            .reference("origin/main", BASE)
+            .remote_branch("origin", "main", remote)
+    }
+
+    #[test]
+    fn test_up_to_date() {
+        let backend = repo(BASE, BASE);
+        let report = collect_status_with(&backend, &metadata_at(BASE), None, true).unwrap();
+
+        assert_eq!(report.remote_status, "Up-to-date");
+        assert_eq!(report.sync, SyncState::UpToDate);
+        assert_eq!(report.ahead_behind, Some((0, 0)));
+        assert_eq!(report.upstream, "origin/main");
+    }
+
+    #[test]
+    fn test_behind_after_fetch() {
+        let backend = repo(BASE, REMOTE);
+        let report = collect_status_with(&backend, &metadata_at(BASE), None, true).unwrap();
+
+        assert_eq!(
+            report.remote_status,
+            "Behind remote by 1 commit(s) (1111111 -> 3333333)"
+        );
+        assert_eq!(report.sync, SyncState::Behind);
+        assert_eq!(report.ahead_behind, Some((0, 1)));
+        assert_eq!(backend.fetched(), vec!["origin"]);
+    }
+
+    #[test]
+    fn test_no_fetch_uses_last_fetched_state() {
+        let backend = repo(BASE, REMOTE);
+        let report = collect_status_with(&backend, &metadata_at(BASE), None, false).unwrap();
+
+        assert_eq!(report.remote_status, "Up-to-date");
+        assert!(backend.fetched().is_empty());
+    }
+
+    #[test]
+    fn test_diverged() {
+        let backend = repo(LOCAL, REMOTE);
+        let report = collect_status_with(&backend, &metadata_at(LOCAL), None, true).unwrap();
+
+        assert_eq!(
+            report.remote_status,
+            "Diverged from remote, 1 ahead and 1 behind (local: 2222222, remote: 3333333)"
+        );
+        assert_eq!(report.sync, SyncState::Diverged);
+        assert_eq!(report.ahead_behind, Some((1, 1)));
+    }
+
+    #[test]
+    fn test_unknown_upstream() {
+        let backend = repo(BASE, BASE);
+        let report =
+            collect_status_with(&backend, &metadata_at(BASE), Some("origin/missing"), true)
+                .unwrap();
+
+        assert_eq!(
+            report.remote_status,
+            "Could not determine remote status for branch 'main' (upstream origin/missing)"
+        );
+        assert_eq!(report.sync, SyncState::Unknown);
+        assert_eq!(report.ahead_behind, None);
+    }
+
+    #[test]
+    fn test_unreachable_remote_is_left_out() {
+        let backend = repo(LOCAL, BASE)
+            .remote_branch("upstream", "main", REMOTE)
+            .remote_branch("mirror", "main", BASE)
+            .unreachable("mirror");
+        let report = collect_status_with(&backend, &metadata_at(LOCAL), None, true).unwrap();
+
+        assert_eq!(
+            report.remote_divergence,
+            vec![
+                ("origin/main".to_string(), (1, 0)),
+                ("upstream/main".to_string(), (1, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_path_usage() {
+        let backend = repo(BASE, BASE);
+        let mut report = collect_status_with(&backend, &metadata_at(BASE), None, true).unwrap();
+        report.path_usage = vec![PathUsage {
+            pattern: "src/**".to_string(),
+            files: 3,
+            bytes: 2048,
+        }];
+
+        assert!(format_status(&report).contains("  - src/**  3 file(s), 2.0 KiB\n"));
+    }
+
+    #[test]
+    fn test_head_drift() {
+        let backend = repo(LOCAL, BASE);
+        let report = collect_status_with(&backend, &metadata_at(BASE), None, true).unwrap();
+        assert_eq!(report.head_drift, Some(LOCAL.to_string()));
+        assert!(format_status(&report)
+            .contains("Warning: HEAD is at 2222222bbbbbbb, which git-partial did not sync"));
+
+        let report = collect_status_with(&backend, &metadata_at(LOCAL), None, true).unwrap();
+        assert_eq!(report.head_drift, None);
+    }
+
+    #[test]
+    fn test_format_stale_paths() {
+        let backend = repo(BASE, BASE);
+        let mut report = collect_status_with(&backend, &metadata_at(BASE), None, true).unwrap();
+        report.stale_paths = vec![
+            StalePath {
+                pattern: "services/api/**".to_string(),
+                moved_to: Some("apps/api/**".to_string()),
+            },
+            StalePath {
+                pattern: "gone.txt".to_string(),
+                moved_to: None,
+            },
+        ];
+        let output = format_status(&report);
+
+        assert!(output.contains("  - services/api/** (moved to apps/api/**?)\n  - gone.txt\n"));
+    }
+
+    #[test]
+    fn test_object_counts() {
+        let counts = ObjectCounts::parse("4b825dc\n?8ab686e\n?e69de29\nd670460 src/lib.rs\n");
+
+        assert_eq!(
+            counts,
+            ObjectCounts {
+                present: 2,
+                missing: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_check_exit_codes() {
+        assert_eq!(check_exit_code(Some(SyncState::UpToDate)), 0);
+        assert_eq!(check_exit_code(Some(SyncState::Behind)), 3);
+        assert_eq!(check_exit_code(Some(SyncState::Diverged)), 4);
+        assert_eq!(check_exit_code(None), 5);
+    }
+
+    #[test]
+    fn test_deletions_outside_sparse_paths() {
+        let backend = repo(BASE, BASE)
+            .status_line(" M src/lib.rs")
+            .status_line(" D src/old.rs")
+            .status_line(" D docs/guide.md")
+            .deletion("src/old.rs")
+            .deletion("docs/guide.md");
+        let report = collect_status_with(&backend, &metadata_at(BASE), None, true).unwrap();
+
+        assert_eq!(report.local_changes, vec![" M src/lib.rs", " D src/old.rs"]);
+        assert_eq!(report.outside_sparse_deletions, vec!["docs/guide.md"]);
+    }
 }
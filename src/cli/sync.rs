@@ -0,0 +1,31 @@
+use anyhow::{Context, Result};
+use std::env;
+use tracing::info;
+
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::pending::PendingFetches;
+use crate::git::commands;
+
+/// Fetch and check out the paths queued by `add-paths` while offline
+pub async fn sync() -> Result<()> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+
+    let pending = PendingFetches::load(&current_dir)?;
+    if pending.paths.is_empty() {
+        println!("Nothing to sync.");
+        return Ok(());
+    }
+    info!("Syncing queued paths: {:?}", pending.paths);
+
+    // Reapplying the full set fetches every blob the patterns now select
+    let paths: Vec<String> = metadata.checked_out_paths.iter().cloned().collect();
+    commands::set_sparse_checkout(&current_dir, &paths)
+        .context("Failed to fetch queued paths; is the remote reachable?")?;
+    PendingFetches::clear(&current_dir)?;
+
+    for path in &pending.paths {
+        println!("Checked out {}", path);
+    }
+    Ok(())
+}
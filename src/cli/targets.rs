@@ -0,0 +1,88 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::env;
+use std::path::Path;
+use std::process::Command;
+use tracing::{debug, info, warn};
+
+use crate::core::targets::{self, Label};
+use crate::git::commands;
+
+/// Resolve Bazel/Buck2 target labels to the sparse checkout patterns they need.
+/// Uses `bazel query`/`buck2 uquery` when available, and otherwise follows the
+/// BUILD files at HEAD.
+pub fn target_paths(labels: &[String]) -> Result<Vec<String>> {
+    if labels.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let parsed = labels
+        .iter()
+        .map(|label| {
+            Label::parse(label, "").with_context(|| {
+                format!("Invalid target label '{}' (expected //package:name)", label)
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let packages = match query_packages(&current_dir, labels) {
+        Some(packages) => packages,
+        None => {
+            info!("Resolving targets from BUILD files");
+            targets::resolve_packages(&parsed, |path| {
+                commands::show_file(&current_dir, "HEAD", path).ok()
+            })?
+        }
+    };
+
+    Ok(packages
+        .iter()
+        .flat_map(|package| targets::package_patterns(package))
+        .collect())
+}
+
+/// Asks the build tool for the packages in the targets' dependency closure.
+/// Returns `None` if no tool is configured or available, or the query fails.
+fn query_packages(
+    repo_path: &Path,
+    labels: &[String],
+) -> Option<BTreeSet<String>> {
+    let root_files = commands::list_tree_files(repo_path, "HEAD").ok()?;
+    let has = |name: &str| root_files.iter().any(|file| file == name);
+
+    let expression = format!("deps({})", labels.join(" + "));
+    let (program, args) = if has("MODULE.bazel") || has("WORKSPACE") || has("WORKSPACE.bazel") {
+        ("bazel", vec!["query", expression.as_str()])
+    } else if has(".buckconfig") {
+        ("buck2", vec!["uquery", expression.as_str()])
+    } else {
+        return None;
+    };
+
+    info!("Running {} {}", program, args.join(" "));
+    let output = match Command::new(program)
+        .current_dir(repo_path)
+        .args(&args)
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) => {
+            debug!("{} is not available: {}", program, e);
+            return None;
+        }
+    };
+
+    if !output.status.success() {
+        warn!(
+            "{} query failed, falling back to BUILD files: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return None;
+    }
+
+    Some(targets::packages_from_query_output(
+        &String::from_utf8_lossy(&output.stdout),
+    ))
+}
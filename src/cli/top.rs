@@ -0,0 +1,106 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use crate::core::local_usage::{self, LocalUsage};
+use crate::git::commands;
+use crate::utils::disk;
+
+/// List the `count` files and directories (grouped `depth` levels deep)
+/// taking the most space on this machine: their checked-out copy plus
+/// every version of their blobs in the local object store. Only local
+/// objects are looked at, so nothing is fetched.
+pub async fn top(
+    count: usize,
+    depth: usize,
+) -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let worktree = worktree_sizes(&current_dir)?;
+    let format = format!("--batch-check={}", local_usage::OBJECT_FORMAT);
+    let blob_sizes = local_usage::parse_blob_sizes(
+        &commands::run_git_command_in_dir(
+            &current_dir,
+            &["cat-file", "--batch-all-objects", &format],
+        )
+        .context("Failed to list local objects")?,
+    );
+    // Missing objects are skipped rather than fetched
+    let objects = commands::run_git_command_in_dir(
+        &current_dir,
+        &["rev-list", "--objects", "--all", "--missing=allow-any"],
+    )
+    .context("Failed to list the objects of the history")?;
+
+    let files = local_usage::by_file(&worktree, &objects, &blob_sizes);
+    let directories = local_usage::by_directory(&files, depth.max(1));
+    let worktree_bytes: u64 = files.iter().map(|file| file.worktree).sum();
+    let object_bytes: u64 = files.iter().map(|file| file.objects).sum();
+
+    let mut output = String::from("Largest files:\n");
+    output.push_str(&format_rows(local_usage::largest(files, count)));
+    output.push_str("Largest directories:\n");
+    output.push_str(&format_rows(local_usage::largest(directories, count)));
+    output.push_str(&format!(
+        "Total: {} checked out, {} in local objects\n",
+        disk::format_size(worktree_bytes),
+        disk::format_size(object_bytes)
+    ));
+    output.push_str(
+        "Free space with 'git-partial remove-paths' (checked-out copies) or 'git-partial dematerialize' (local objects).\n",
+    );
+    Ok(output)
+}
+
+/// Sizes of the tracked files checked out in the working tree
+fn worktree_sizes(repo_path: &Path) -> Result<Vec<(String, u64)>> {
+    let entries =
+        commands::skip_worktree_entries(repo_path).context("Failed to list tracked files")?;
+    Ok(entries
+        .into_iter()
+        .filter(|(_, skipped)| !skipped)
+        .filter_map(|(path, _)| {
+            let bytes = fs::symlink_metadata(repo_path.join(&path)).ok()?.len();
+            Some((path, bytes))
+        })
+        .collect())
+}
+
+fn format_rows(rows: Vec<LocalUsage>) -> String {
+    if rows.is_empty() {
+        return "  (none)\n".to_string();
+    }
+    let cells: Vec<[String; 3]> = rows
+        .iter()
+        .map(|row| {
+            [
+                disk::format_size(row.total()),
+                disk::format_size(row.worktree),
+                disk::format_size(row.objects),
+            ]
+        })
+        .collect();
+    let width = |column: usize| {
+        cells
+            .iter()
+            .map(|cell| cell[column].len())
+            .max()
+            .unwrap_or(0)
+    };
+    let (total_width, worktree_width, objects_width) = (width(0), width(1), width(2));
+    rows.iter()
+        .zip(&cells)
+        .map(|(row, [total, worktree, objects])| {
+            format!(
+                "  {:>total_width$}  {:>worktree_width$} checked out  {:>objects_width$} objects  {}\n",
+                total,
+                worktree,
+                objects,
+                row.path,
+                total_width = total_width,
+                worktree_width = worktree_width,
+                objects_width = objects_width
+            )
+        })
+        .collect()
+}
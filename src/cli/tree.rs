@@ -0,0 +1,152 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::path::Path;
+use tracing::{debug, info};
+
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::tree::{self, TreeEntry};
+use crate::core::tree_cache::TreeCache;
+use crate::git::backend::{CommandBackend, GitBackend};
+use crate::git::commands;
+use crate::git::upstream;
+use crate::remote::{self, capabilities, Provider};
+
+/// List repository directories, from the local clone or from the remote before cloning
+pub async fn show_tree(
+    pre_clone: Option<&str>,
+    reference: Option<&str>,
+    depth: usize,
+) -> Result<String> {
+    let (entries, _) = load_tree_entries(pre_clone, reference).await?;
+
+    let mut output = String::new();
+    for dir in tree::directories(&entries, depth) {
+        output.push_str(&format!("{}/\n", dir));
+    }
+    Ok(output)
+}
+
+/// List the upstream paths completing `prefix`, one per line, for shell
+/// completion scripts
+pub fn complete_paths(prefix: &str) -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let (_, entries) = upstream_tree(&current_dir)?;
+
+    let mut output = String::new();
+    for path in tree::completions(&entries, prefix) {
+        output.push_str(&path);
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// Load tree entries from the remote API when `pre_clone` is given,
+/// otherwise from HEAD (or `reference`) of the repository in the current
+/// directory. Returns whether the entries carry blob sizes: listing them
+/// locally would download every blob of a partial clone, so local sizes
+/// come from the remote, and are unknown when it cannot be read.
+pub async fn load_tree_entries(
+    pre_clone: Option<&str>,
+    reference: Option<&str>,
+) -> Result<(Vec<TreeEntry>, bool)> {
+    match pre_clone {
+        Some(repo_url) => {
+            let provider = Provider::from_url(repo_url).with_context(|| {
+                format!(
+                    "Browsing before clone is not supported for '{}' (GitHub, GitLab and Bitbucket only)",
+                    repo_url
+                )
+            })?;
+            let reference = match reference {
+                Some(reference) => reference.to_string(),
+                // Ask the server itself first, which needs no API token
+                None => match capabilities::default_branch(repo_url) {
+                    Ok(branch) => branch,
+                    Err(e) => {
+                        info!("Falling back to the API for the default branch: {:#}", e);
+                        provider.default_branch().await?
+                    }
+                },
+            };
+            info!("Listing remote tree of {} at {}", repo_url, reference);
+            let entries = provider.list_tree(&reference).await?;
+            let sizes_known = tree::has_sizes(&entries);
+            Ok((entries, sizes_known))
+        }
+        None => {
+            let current_dir = env::current_dir().context("Failed to get current directory")?;
+            let rev = reference.unwrap_or("HEAD");
+            let commit = CommandBackend::new(&current_dir)
+                .rev_parse(rev)
+                .with_context(|| format!("Failed to resolve {}", rev))?;
+            if let Ok(metadata) = RepositoryMetadata::load(&current_dir) {
+                if let Some(entries) = remote_tree_entries(&metadata.remote_url, &commit).await {
+                    return Ok((entries, true));
+                }
+            }
+            info!("Blob sizes are unavailable, listing files only");
+            let entries = commands::list_tree_structure(&current_dir, &commit)
+                .context("Failed to list repository tree")?;
+            Ok((entries, false))
+        }
+    }
+}
+
+/// Load tree entries with blob sizes from the remote a partial clone was made from.
+/// Listing sizes locally would download the missing blobs, so the remote is
+/// read directly when it is on this machine, or through its web API.
+/// Returns `None` when neither is possible, or when the listing carries no
+/// sizes.
+pub async fn remote_tree_entries(
+    remote_url: &str,
+    rev: &str,
+) -> Option<Vec<TreeEntry>> {
+    let result = match (
+        remote::local_path(remote_url),
+        Provider::from_url(remote_url),
+    ) {
+        (Some(remote_path), _) => commands::list_tree_entries(&remote_path, rev),
+        (None, Some(provider)) => provider.list_tree(rev).await,
+        (None, None) => return None,
+    };
+    let entries = result
+        .inspect_err(|e| info!("Could not list remote tree of {}: {:#}", remote_url, e))
+        .ok()?;
+    if !tree::has_sizes(&entries) {
+        info!("The remote tree of {} lists no blob sizes", remote_url);
+        return None;
+    }
+    Some(entries)
+}
+
+/// List the tree of the current branch's upstream, or of HEAD when the
+/// upstream has not been fetched. The listing is cached in
+/// `.gitpartial/tree-cache` until a fetch moves the upstream.
+pub fn upstream_tree(repo_path: &Path) -> Result<(String, Vec<TreeEntry>)> {
+    let backend = CommandBackend::new(repo_path);
+    let upstream = backend
+        .current_branch()
+        .and_then(|branch| upstream::resolve(&backend, &branch, None))
+        .map(|upstream| upstream.rev());
+    let (commit, rev) = match upstream.and_then(|rev| Ok((backend.rev_parse(&rev)?, rev))) {
+        Ok(resolved) => resolved,
+        Err(_) => (
+            backend
+                .rev_parse("HEAD")
+                .context("Failed to resolve HEAD")?,
+            "HEAD".to_string(),
+        ),
+    };
+
+    if let Some(cache) = TreeCache::load(repo_path, &commit) {
+        debug!("Using cached tree of {} ({})", rev, commit);
+        return Ok((rev, cache.entries));
+    }
+    let entries = commands::list_tree_structure(repo_path, &commit)
+        .with_context(|| format!("Failed to list the tree of {}", rev))?;
+    let cache = TreeCache { commit, entries };
+    if let Err(e) = cache.save(repo_path) {
+        debug!("Could not cache the tree of {}: {:#}", rev, e);
+    }
+    Ok((rev, cache.entries))
+}
@@ -0,0 +1,666 @@
+use anyhow::{Context, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::BTreeSet;
+use std::env;
+use std::path::Path;
+use tracing::info;
+
+use crate::cli::status::{self, StatusReport};
+use crate::cli::{add_paths, remove_paths, smart_pull, tree};
+use crate::core::config;
+use crate::core::error::PartialError;
+use crate::core::lock::RepoLock;
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::tree::{self as core_tree, PathUsage, TreeEntry};
+use crate::git::sparse;
+use crate::utils::disk;
+
+/// Most lines of a message the footer shows before cutting it off
+const MAX_FOOTER_LINES: usize = 10;
+
+/// What the dashboard should do after a key press
+#[derive(Debug, PartialEq)]
+enum Action {
+    None,
+    Quit,
+    Refresh,
+    Browse,
+    AddPaths(Vec<String>),
+    RemovePath(String),
+    SmartPull,
+}
+
+/// Input mode of the dashboard
+#[derive(Debug, PartialEq)]
+enum Mode {
+    Normal,
+    AddingPath(String),
+    PickingPaths(Picker),
+    ConfirmRemove(String),
+}
+
+/// Browses the tree of HEAD one directory at a time, with the files and
+/// size under each entry, to pick paths to add
+#[derive(Debug, PartialEq)]
+struct Picker {
+    entries: Vec<TreeEntry>,
+    sizes_known: bool,
+
+    /// Directory listed; empty for the repository root
+    root: String,
+
+    /// Entries of `root`, largest first
+    rows: Vec<PathUsage>,
+    selected: usize,
+
+    /// Patterns picked so far, possibly in other directories
+    chosen: BTreeSet<String>,
+}
+
+impl Picker {
+    fn new(
+        entries: Vec<TreeEntry>,
+        sizes_known: bool,
+    ) -> Result<Self> {
+        let mut picker = Picker {
+            entries,
+            sizes_known,
+            root: String::new(),
+            rows: Vec::new(),
+            selected: 0,
+            chosen: BTreeSet::new(),
+        };
+        picker.open("")?;
+        Ok(picker)
+    }
+
+    /// List the entries of the directory `root`
+    fn open(
+        &mut self,
+        root: &str,
+    ) -> Result<()> {
+        self.rows = core_tree::disk_usage(&self.entries, root, 1)?;
+        self.root = root.to_string();
+        self.selected = 0;
+        Ok(())
+    }
+
+    /// The pattern adding a row: everything under a directory, or the file
+    fn pattern(row: &PathUsage) -> String {
+        match row.path.strip_suffix('/') {
+            Some(dir) => format!("{}/**", dir),
+            None => row.path.clone(),
+        }
+    }
+
+    /// Update the picker for a key press. Returns the paths to add once
+    /// picking is done, or `None` while it goes on.
+    fn handle_key(
+        &mut self,
+        key: KeyCode,
+    ) -> Option<Vec<String>> {
+        match key {
+            KeyCode::Down | KeyCode::Char('j') if self.selected + 1 < self.rows.len() => {
+                self.selected += 1;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = self.selected.saturating_sub(1);
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                if let Some(row) = self.rows.get(self.selected) {
+                    if row.path.ends_with('/') {
+                        let dir = row.path.clone();
+                        // The row was listed, so the directory is not empty
+                        let _ = self.open(&dir);
+                    }
+                }
+            }
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::Backspace if !self.root.is_empty() => {
+                let parent = match self.root.trim_end_matches('/').rsplit_once('/') {
+                    Some((parent, _)) => format!("{}/", parent),
+                    None => String::new(),
+                };
+                let _ = self.open(&parent);
+            }
+            KeyCode::Char(' ') => {
+                if let Some(row) = self.rows.get(self.selected) {
+                    let pattern = Self::pattern(row);
+                    if !self.chosen.remove(&pattern) {
+                        self.chosen.insert(pattern);
+                    }
+                }
+            }
+            KeyCode::Enter => {
+                // Without a selection, the highlighted entry is added
+                if self.chosen.is_empty() {
+                    return Some(
+                        self.rows
+                            .get(self.selected)
+                            .map(Self::pattern)
+                            .into_iter()
+                            .collect(),
+                    );
+                }
+                return Some(self.chosen.iter().cloned().collect());
+            }
+            KeyCode::Esc => return Some(Vec::new()),
+            _ => {}
+        }
+        None
+    }
+}
+
+/// Dashboard state
+struct App {
+    report: Option<StatusReport>,
+    worktree_size: u64,
+    git_dir_size: u64,
+    selected: usize,
+    mode: Mode,
+    message: String,
+}
+
+impl App {
+    fn new() -> Self {
+        App {
+            report: None,
+            worktree_size: 0,
+            git_dir_size: 0,
+            selected: 0,
+            mode: Mode::Normal,
+            message: String::new(),
+        }
+    }
+
+    fn paths(&self) -> &[String] {
+        self.report
+            .as_ref()
+            .map(|report| report.paths.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Reload status and disk usage from the repository
+    fn refresh(
+        &mut self,
+        repo_path: &Path,
+    ) -> Result<()> {
+        let metadata = RepositoryMetadata::load(repo_path).context("Failed to load metadata")?;
+        let fetch = !config::no_fetch_on_status(false, repo_path)?;
+        let report = status::collect_status(repo_path, &metadata, None, fetch)?;
+
+        self.git_dir_size = disk::dir_size(&repo_path.join(".git"));
+        self.worktree_size = disk::dir_size(repo_path).saturating_sub(self.git_dir_size);
+        self.selected = self.selected.min(report.paths.len().saturating_sub(1));
+        self.report = Some(report);
+        Ok(())
+    }
+
+    /// Update the state for a key press and return the resulting action
+    fn handle_key(
+        &mut self,
+        key: KeyCode,
+    ) -> Action {
+        match &mut self.mode {
+            Mode::Normal => match key {
+                KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+                KeyCode::Char('r') => Action::Refresh,
+                KeyCode::Char('p') => Action::SmartPull,
+                KeyCode::Char('a') => {
+                    self.mode = Mode::AddingPath(String::new());
+                    Action::None
+                }
+                KeyCode::Char('b') => Action::Browse,
+                KeyCode::Char('d') => {
+                    if let Some(path) = self.paths().get(self.selected).cloned() {
+                        self.mode = Mode::ConfirmRemove(path);
+                    }
+                    Action::None
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    if self.selected + 1 < self.paths().len() {
+                        self.selected += 1;
+                    }
+                    Action::None
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.selected = self.selected.saturating_sub(1);
+                    Action::None
+                }
+                _ => Action::None,
+            },
+            Mode::AddingPath(input) => match key {
+                KeyCode::Char(c) => {
+                    input.push(c);
+                    Action::None
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                    Action::None
+                }
+                KeyCode::Enter => {
+                    let paths: Vec<String> = input.split_whitespace().map(String::from).collect();
+                    self.mode = Mode::Normal;
+                    if paths.is_empty() {
+                        Action::None
+                    } else {
+                        Action::AddPaths(paths)
+                    }
+                }
+                KeyCode::Esc => {
+                    self.mode = Mode::Normal;
+                    Action::None
+                }
+                _ => Action::None,
+            },
+            Mode::PickingPaths(picker) => match picker.handle_key(key) {
+                None => Action::None,
+                Some(paths) => {
+                    self.mode = Mode::Normal;
+                    if paths.is_empty() {
+                        Action::None
+                    } else {
+                        Action::AddPaths(paths)
+                    }
+                }
+            },
+            Mode::ConfirmRemove(path) => {
+                let action = match key {
+                    KeyCode::Char('y') | KeyCode::Char('Y') => Action::RemovePath(path.clone()),
+                    _ => Action::None,
+                };
+                self.mode = Mode::Normal;
+                action
+            }
+        }
+    }
+}
+
+/// Run the interactive status dashboard
+pub async fn run_dashboard() -> Result<()> {
+    info!("Starting status dashboard");
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+
+    // Fail before taking over the terminal if this is not a git-partial repo
+    RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+    if !sparse::is_sparse_checkout()? {
+        return Err(PartialError::SparseCheckoutDisabled.into());
+    }
+
+    let mut app = App::new();
+    app.refresh(&current_dir)?;
+
+    let mut terminal = ratatui::init();
+    let result = run_loop(&mut terminal, &mut app, &current_dir).await;
+    ratatui::restore();
+    result
+}
+
+async fn run_loop(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    repo_path: &Path,
+) -> Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, app))?;
+
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key.code,
+            _ => continue,
+        };
+
+        let outcome = match app.handle_key(key) {
+            Action::None => continue,
+            Action::Quit => return Ok(()),
+            Action::Refresh => Ok("Refreshed".to_string()),
+            Action::Browse => {
+                // Sizes come from the remote, as for `estimate`
+                let picker = tree::load_tree_entries(None, None)
+                    .await
+                    .and_then(|(entries, sizes_known)| Picker::new(entries, sizes_known));
+                match picker {
+                    Ok(picker) => app.mode = Mode::PickingPaths(picker),
+                    Err(e) => app.message = format!("Error: {:#}", e),
+                }
+                continue;
+            }
+            // Waiting would freeze the dashboard, so a held lock is reported instead
+            action => match RepoLock::acquire(repo_path, false) {
+                Ok(_lock) => perform(action).await,
+                Err(e) => Err(e),
+            },
+        };
+
+        app.message = match outcome.and_then(|message| app.refresh(repo_path).map(|_| message)) {
+            Ok(message) => message,
+            Err(e) => format!("Error: {:#}", e),
+        };
+    }
+}
+
+/// Run an action that changes the repository and describe the result,
+/// after the messages the action returned. Printing them would garble the
+/// dashboard.
+async fn perform(action: Action) -> Result<String> {
+    match action {
+        Action::AddPaths(paths) => add_paths::add_new_paths(&paths, true, false, false)
+            .await
+            .map(|output| output + &format!("Added {}", paths.join(" "))),
+        Action::RemovePath(path) => {
+            remove_paths::remove_paths(std::slice::from_ref(&path), true, false, false)
+                .await
+                .map(|output| output + &format!("Removed {}", path))
+        }
+        // Without an integration, diverged histories fail instead of stopping
+        // at conflicts, whose walk-through needs the terminal
+        Action::SmartPull => {
+            smart_pull::perform_smart_pull(None, None, None, false, false, false, None)
+                .await
+                .map(|output| output + "Smart pull completed")
+        }
+        Action::None | Action::Quit | Action::Refresh | Action::Browse => Ok(String::new()),
+    }
+}
+
+/// Height of the footer showing `text`, growing with the lines of a
+/// message up to `MAX_FOOTER_LINES`
+fn footer_height(text: &str) -> u16 {
+    text.lines().count().clamp(1, MAX_FOOTER_LINES) as u16 + 2
+}
+
+fn draw(
+    frame: &mut Frame,
+    app: &App,
+) {
+    let footer = match &app.mode {
+        Mode::Normal if app.message.is_empty() => {
+            "[a] add path  [b] browse paths  [d] remove path  [p] smart-pull  [r] refresh  [q] quit"
+                .to_string()
+        }
+        Mode::Normal => app.message.clone(),
+        Mode::AddingPath(input) => format!("Add paths (Enter to apply, Esc to cancel): {}", input),
+        Mode::PickingPaths(picker) => format!(
+            "[space] select  [\u{2192}] open  [\u{2190}] up  [Enter] add {} selected  [Esc] cancel",
+            picker.chosen.len()
+        ),
+        Mode::ConfirmRemove(path) => format!("Remove '{}' from the checkout? [y/N]", path),
+    };
+    let [header_area, body_area, footer_area] = Layout::vertical([
+        Constraint::Length(4),
+        Constraint::Min(3),
+        Constraint::Length(footer_height(&footer)),
+    ])
+    .areas(frame.area());
+    let [paths_area, changes_area] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .areas(body_area);
+
+    let header = match &app.report {
+        Some(report) => {
+            let counts = match report.ahead_behind {
+                Some((ahead, behind)) => format!("ahead {}, behind {}", ahead, behind),
+                None => "ahead/behind unknown".to_string(),
+            };
+            format!(
+                "Branch: {} ({}) | {} of {}\nWorktree: {} | .git: {}",
+                report.branch,
+                report.remote_status,
+                counts,
+                report.upstream,
+                disk::format_size(app.worktree_size),
+                disk::format_size(app.git_dir_size)
+            )
+        }
+        None => "Loading...".to_string(),
+    };
+    frame.render_widget(
+        Paragraph::new(header).block(Block::default().borders(Borders::ALL).title("Git Partial")),
+        header_area,
+    );
+    if let Mode::PickingPaths(picker) = &app.mode {
+        draw_picker(frame, picker, body_area);
+        frame.render_widget(
+            Paragraph::new(footer).block(Block::default().borders(Borders::ALL)),
+            footer_area,
+        );
+        return;
+    }
+
+    let path_items: Vec<ListItem> = app
+        .paths()
+        .iter()
+        .map(|path| ListItem::new(path.as_str()))
+        .collect();
+    let mut path_state = ListState::default().with_selected(Some(app.selected));
+    frame.render_stateful_widget(
+        List::new(path_items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Sparse checkout paths"),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+        paths_area,
+        &mut path_state,
+    );
+
+    let change_items: Vec<ListItem> = match &app.report {
+        Some(report) if !report.local_changes.is_empty() => report
+            .local_changes
+            .iter()
+            .map(|line| ListItem::new(line.as_str()))
+            .collect(),
+        _ => vec![ListItem::new("No changes")],
+    };
+    frame.render_widget(
+        List::new(change_items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Local changes"),
+        ),
+        changes_area,
+    );
+
+    frame.render_widget(
+        Paragraph::new(footer).block(Block::default().borders(Borders::ALL)),
+        footer_area,
+    );
+}
+
+fn draw_picker(
+    frame: &mut Frame,
+    picker: &Picker,
+    area: Rect,
+) {
+    let sizes: Vec<String> = picker
+        .rows
+        .iter()
+        .map(|row| {
+            if picker.sizes_known {
+                disk::format_size(row.bytes)
+            } else {
+                "?".to_string()
+            }
+        })
+        .collect();
+    let size_width = sizes.iter().map(String::len).max().unwrap_or(0);
+    let items: Vec<ListItem> = picker
+        .rows
+        .iter()
+        .zip(&sizes)
+        .map(|(row, size)| {
+            let mark = if picker.chosen.contains(&Picker::pattern(row)) {
+                "[x]"
+            } else {
+                "[ ]"
+            };
+            ListItem::new(format!(
+                "{} {:>size_width$}  {:>5} file(s)  {}",
+                mark,
+                size,
+                row.files,
+                row.path,
+                size_width = size_width
+            ))
+        })
+        .collect();
+    let title = match picker.root.as_str() {
+        "" => "Pick paths in the repository".to_string(),
+        root => format!("Pick paths in {}", root),
+    };
+    let mut state = ListState::default().with_selected(Some(picker.selected));
+    frame.render_stateful_widget(
+        List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+        area,
+        &mut state,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::status::SyncState;
+
+    fn app_with_paths(paths: &[&str]) -> App {
+        let mut app = App::new();
+        app.report = Some(StatusReport {
+            branch: "main".to_string(),
+            upstream: "origin/main".to_string(),
+            remote_status: "Up-to-date".to_string(),
+            sync: SyncState::UpToDate,
+            ahead_behind: Some((0, 0)),
+            incoming: Vec::new(),
+            remote_divergence: Vec::new(),
+            last_commit: "abc1234".to_string(),
+            head_drift: None,
+            remote_url: "https://github.com/user/repo.git".to_string(),
+            fetch_url: None,
+            origin_mismatch: None,
+            filter_ignored: false,
+            paths: paths.iter().map(|p| p.to_string()).collect(),
+            path_usage: Vec::new(),
+            stale_paths: Vec::new(),
+            objects: None,
+            local_changes: Vec::new(),
+            outside_sparse_deletions: Vec::new(),
+        });
+        app
+    }
+
+    #[test]
+    fn test_navigation_stays_in_bounds() {
+        let mut app = app_with_paths(&["README.md", "src/**"]);
+
+        app.handle_key(KeyCode::Up);
+        assert_eq!(app.selected, 0);
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Down);
+        assert_eq!(app.selected, 1);
+    }
+
+    #[test]
+    fn test_add_path_input() {
+        let mut app = app_with_paths(&["README.md"]);
+
+        assert_eq!(app.handle_key(KeyCode::Char('a')), Action::None);
+        for c in "docs/** x".chars() {
+            app.handle_key(KeyCode::Char(c));
+        }
+        app.handle_key(KeyCode::Backspace);
+
+        assert_eq!(
+            app.handle_key(KeyCode::Enter),
+            Action::AddPaths(vec!["docs/**".to_string()])
+        );
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn test_picker_adds_the_chosen_paths() {
+        let blob = |path: &str, size: u64| TreeEntry {
+            path: path.to_string(),
+            kind: core_tree::EntryKind::Blob,
+            size: Some(size),
+        };
+        let entries = vec![
+            blob("README.md", 10),
+            blob("src/frontend/app.js", 500),
+            blob("src/backend/server.js", 30),
+            blob("docs/guide.md", 20),
+        ];
+        let mut app = app_with_paths(&["README.md"]);
+        app.mode = Mode::PickingPaths(Picker::new(entries, true).unwrap());
+
+        // Largest first: src/, docs/, README.md
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Char(' '));
+        app.handle_key(KeyCode::Up);
+        app.handle_key(KeyCode::Right);
+        let Mode::PickingPaths(picker) = &app.mode else {
+            panic!("picker closed");
+        };
+        assert_eq!(picker.root, "src/");
+        assert_eq!(picker.rows[0].path, "src/frontend/");
+        assert_eq!(picker.rows[0].bytes, 500);
+
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Char(' '));
+        app.handle_key(KeyCode::Left);
+        assert_eq!(
+            app.handle_key(KeyCode::Enter),
+            Action::AddPaths(vec!["docs/**".to_string(), "src/backend/**".to_string()])
+        );
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn test_picker_adds_the_highlighted_path_or_cancels() {
+        let entries = vec![TreeEntry {
+            path: "LICENSE".to_string(),
+            kind: core_tree::EntryKind::Blob,
+            size: None,
+        }];
+        let mut app = app_with_paths(&[]);
+
+        app.mode = Mode::PickingPaths(Picker::new(entries.clone(), false).unwrap());
+        assert_eq!(
+            app.handle_key(KeyCode::Enter),
+            Action::AddPaths(vec!["LICENSE".to_string()])
+        );
+
+        app.mode = Mode::PickingPaths(Picker::new(entries, false).unwrap());
+        assert_eq!(app.handle_key(KeyCode::Esc), Action::None);
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn test_remove_requires_confirmation() {
+        let mut app = app_with_paths(&["README.md", "src/**"]);
+        app.handle_key(KeyCode::Down);
+
+        app.handle_key(KeyCode::Char('d'));
+        assert_eq!(app.handle_key(KeyCode::Char('n')), Action::None);
+
+        app.handle_key(KeyCode::Char('d'));
+        assert_eq!(
+            app.handle_key(KeyCode::Char('y')),
+            Action::RemovePath("src/**".to_string())
+        );
+    }
+
+    #[test]
+    fn test_footer_grows_with_the_message() {
+        assert_eq!(footer_height(""), 3);
+        assert_eq!(footer_height("Added src/**"), 3);
+        assert_eq!(
+            footer_height("Now following a at b\nSmart pull completed"),
+            4
+        );
+        assert_eq!(footer_height(&"line\n".repeat(50)), 12);
+    }
+}
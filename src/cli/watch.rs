@@ -0,0 +1,473 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde_json::json;
+use std::env;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::cli::{dematerialize, smart_pull};
+use crate::core::config::{self, SettingValue};
+use crate::core::digest::{self, Digest, DigestState};
+use crate::core::incoming::{self, IncomingSummary};
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::metrics;
+use crate::core::schedule::{self, WatchAction, WatchSchedule};
+use crate::git::backend::{CommandBackend, GitBackend};
+use crate::git::commands;
+use crate::git::upstream;
+use crate::remote;
+use crate::utils::{disk, http_server, notify, smtp};
+
+/// Git config key enabling desktop notifications for a repository
+const DESKTOP_KEY: &str = "gitpartial.notify.desktop";
+
+/// Git config key with a URL to post notifications to
+const WEBHOOK_KEY: &str = "gitpartial.notify.webhook";
+
+/// Multi-valued git config key with the cron schedules of `watch`
+const SCHEDULE_KEY: &str = "gitpartial.watch.schedule";
+
+/// Git config key with the Slack incoming webhook receiving digests
+const DIGEST_SLACK_KEY: &str = "gitpartial.digest.slack";
+
+/// Git config key with the SMTP relay (`host[:port]`) sending digests
+const DIGEST_SMTP_KEY: &str = "gitpartial.digest.smtp";
+
+/// Multi-valued git config key with the digest's email recipients
+const DIGEST_TO_KEY: &str = "gitpartial.digest.to";
+
+/// Git config key with the digest's sender address
+const DIGEST_FROM_KEY: &str = "gitpartial.digest.from";
+
+/// New upstream commits touching the sparse paths
+struct Incoming {
+    upstream: String,
+    from: String,
+    to: String,
+    commits: usize,
+    summary: IncomingSummary,
+}
+
+impl Incoming {
+    fn title(&self) -> String {
+        format!(
+            "{} new commit(s) on {} touch the sparse paths",
+            self.commits, self.upstream
+        )
+    }
+
+    /// The webhook body
+    fn payload(
+        &self,
+        remote_url: &str,
+    ) -> serde_json::Value {
+        let changes: Vec<_> = self
+            .summary
+            .groups
+            .iter()
+            .map(|group| {
+                json!({
+                    "pattern": group.pattern,
+                    "added": group.added,
+                    "modified": group.modified,
+                    "deleted": group.deleted,
+                })
+            })
+            .collect();
+        json!({
+            "repository": remote_url,
+            "upstream": self.upstream,
+            "from": self.from,
+            "to": self.to,
+            "commits": self.commits,
+            "changes": changes,
+            "outside": self.summary.outside,
+        })
+    }
+}
+
+/// Fetch the upstream of the current branch and notify when new commits
+/// touch the sparse paths. Runs on the cron schedules configured in
+/// `gitpartial.watch.schedule`, or every `interval` seconds without any,
+/// or a single time with `once`. Notifications go to stdout, and to the
+/// desktop and a webhook when configured with `gitpartial.notify.desktop`
+/// and `gitpartial.notify.webhook`.
+/// When running periodically, failed checks are logged and retried.
+/// With `metrics_listen`, Prometheus metrics of the checks are served there.
+/// Each check also prunes the objects of removed paths once
+/// `gitpartial.pruneAfter` has passed. `digest` schedules, or `digest` with
+/// `once`, also send the commits since the previous digest to Slack and by
+/// email.
+pub async fn watch(
+    interval: u64,
+    once: bool,
+    prefetch: bool,
+    digest: bool,
+    metrics_listen: Option<&str>,
+) -> Result<()> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let backend = CommandBackend::new(&current_dir);
+    let schedules = load_schedules(&current_dir)?;
+    if let Some(listen) = metrics_listen {
+        http_server::serve_metrics(listen).await?;
+    }
+    let mut last_seen = None;
+    loop {
+        let action = match schedule::next_run(&schedules, &Local::now()) {
+            Some((time, action)) if !once => {
+                info!("Next {} at {}", action, format_time(&time));
+                let delay = (time - Local::now()).to_std().unwrap_or_default();
+                tokio::time::sleep(delay).await;
+                action
+            }
+            _ if prefetch => WatchAction::Prefetch,
+            _ if digest => WatchAction::Digest,
+            _ => WatchAction::Check,
+        };
+
+        let result = run_action(&current_dir, &backend, last_seen.as_deref(), action).await;
+        match result {
+            Ok(seen) => last_seen = Some(seen),
+            Err(e) if !once => warn!("{:#}", e),
+            Err(e) => return Err(e),
+        }
+
+        if once {
+            return Ok(());
+        }
+        if schedules.is_empty() {
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+        }
+    }
+}
+
+/// List the configured schedules and when each runs next
+pub fn status() -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let schedules = load_schedules(&current_dir)?;
+    if schedules.is_empty() {
+        return Ok(format!(
+            "No schedules configured; watch checks every --interval seconds.
+Add one with: git config --add {} \"0 8 * * Mon-Fri prefetch\"\n",
+            SCHEDULE_KEY
+        ));
+    }
+
+    let now = Local::now();
+    let width = schedules
+        .iter()
+        .map(|schedule| schedule.expression.len())
+        .max()
+        .unwrap_or(0);
+    let mut output = String::new();
+    for schedule in &schedules {
+        let next = schedule
+            .next_after(&now)
+            .map(|time| format_time(&time))
+            .unwrap_or_else(|| "never".to_string());
+        output.push_str(&format!(
+            "{:width$}  {:8}  next: {}\n",
+            schedule.expression,
+            schedule.action.to_string(),
+            next,
+            width = width
+        ));
+    }
+    if let Some((time, action)) = schedule::next_run(&schedules, &now) {
+        output.push_str(&format!("Next run: {} at {}\n", action, format_time(&time)));
+    }
+    Ok(output)
+}
+
+/// Read the schedules from git config, failing on an invalid entry
+fn load_schedules(repo_path: &Path) -> Result<Vec<WatchSchedule>> {
+    commands::config_values(repo_path, SCHEDULE_KEY)
+        .iter()
+        .map(|entry| WatchSchedule::parse(entry).with_context(|| format!("In {}", SCHEDULE_KEY)))
+        .collect()
+}
+
+fn format_time(time: &DateTime<Local>) -> String {
+    time.format("%Y-%m-%d %H:%M %Z").to_string()
+}
+
+/// Check for new commits, notify, and with `Prefetch` download the blobs
+/// the next `smart-pull` needs, or with `Digest` send the digest. Returns
+/// the upstream commit seen.
+async fn run_action(
+    repo_path: &Path,
+    backend: &CommandBackend,
+    last_seen: Option<&str>,
+    action: WatchAction,
+) -> Result<String> {
+    let (seen, incoming) = check(repo_path, backend, last_seen)?;
+    record_state(repo_path, backend, &seen);
+    if let Some(incoming) = incoming {
+        notify_all(repo_path, &incoming).await;
+    }
+    match action {
+        WatchAction::Prefetch => prefetch(repo_path, &seen)?,
+        WatchAction::Digest => send_digest(repo_path, backend, &seen).await?,
+        WatchAction::Check => {}
+    }
+    prune(repo_path);
+    Ok(seen)
+}
+
+/// Drop the objects of paths removed longer than `gitpartial.pruneAfter`
+/// ago. Failures are logged, as they do not affect the check.
+fn prune(repo_path: &Path) {
+    let pruned = config::prune_after(repo_path).and_then(|retention| match retention {
+        Some(retention) => dematerialize::prune_removed(repo_path, retention),
+        None => Ok(None),
+    });
+    match pruned {
+        Ok(Some(message)) => info!("{}", message.trim_end()),
+        Ok(None) => {}
+        Err(e) => warn!("Failed to prune removed paths: {:#}", e),
+    }
+}
+
+/// Download the blobs in the sparse paths that merging `upstream` into
+/// HEAD would check out
+fn prefetch(
+    repo_path: &Path,
+    upstream: &str,
+) -> Result<()> {
+    let metadata = RepositoryMetadata::load(repo_path).context("Failed to load metadata")?;
+    let patterns: Vec<String> = metadata.checked_out_paths.iter().cloned().collect();
+    let changes = commands::diff_name_status(repo_path, "HEAD", upstream)
+        .context("Failed to list incoming changes")?;
+    let paths = incoming::downloads(&changes, &patterns);
+    if paths.is_empty() {
+        info!("Nothing to prefetch");
+        return Ok(());
+    }
+    info!("Prefetching {} file(s)", paths.len());
+    commands::prefetch_blobs(repo_path, "HEAD", upstream, &paths)
+        .context("Failed to prefetch incoming files")
+}
+
+/// Send the upstream commits up to `current` touching the sparse paths
+/// since the previous digest, or since HEAD before the first one, to the
+/// configured Slack webhook and SMTP relay. Nothing is sent when there are
+/// none; after a failed delivery, the next digest covers them again.
+async fn send_digest(
+    repo_path: &Path,
+    backend: &CommandBackend,
+    current: &str,
+) -> Result<()> {
+    let slack = commands::config_value(repo_path, DIGEST_SLACK_KEY);
+    let smtp_server = commands::config_value(repo_path, DIGEST_SMTP_KEY);
+    if slack.is_none() && smtp_server.is_none() {
+        anyhow::bail!(
+            "No digest target configured; set {} or {}",
+            DIGEST_SLACK_KEY,
+            DIGEST_SMTP_KEY
+        );
+    }
+    let recipients = commands::config_values(repo_path, DIGEST_TO_KEY);
+    if smtp_server.is_some() && recipients.is_empty() {
+        anyhow::bail!("{} is set but {} is not", DIGEST_SMTP_KEY, DIGEST_TO_KEY);
+    }
+
+    let since = match DigestState::load(repo_path)? {
+        Some(state) => state.last_sent,
+        None => backend.rev_parse("HEAD")?,
+    };
+    let metadata = RepositoryMetadata::load(repo_path).context("Failed to load metadata")?;
+    let patterns: Vec<String> = metadata.checked_out_paths.iter().cloned().collect();
+    let format = format!("--format={}", digest::LOG_FORMAT);
+    let range = format!("{}..{}", since, current);
+    let pathspecs: Vec<String> = patterns
+        .iter()
+        .map(|pattern| format!(":(glob){}", pattern.trim_start_matches('/')))
+        .collect();
+    let mut args = vec!["log", &format, &range, "--"];
+    args.extend(pathspecs.iter().map(String::as_str));
+    let commits = digest::parse_log(
+        &commands::run_git_command_in_dir(repo_path, &args)
+            .context("Failed to list upstream commits")?,
+    );
+
+    if commits.is_empty() {
+        info!("No upstream commits touch the sparse paths since the last digest");
+    } else {
+        let branch = backend
+            .current_branch()
+            .context("Failed to get current branch")?;
+        let changes = commands::diff_name_status(repo_path, &since, current)
+            .context("Failed to list incoming changes")?;
+        let digest = Digest {
+            repository: metadata.remote_url.clone(),
+            upstream: upstream::resolve(backend, &branch, None)?.rev(),
+            commits,
+            summary: incoming::summarize(&changes, &patterns, None),
+        };
+        let title = digest.title();
+        let text = digest.text();
+        println!("{}\n{}", title, text.trim_end());
+
+        if let Some(url) = &slack {
+            remote::post_json(url, &digest.slack_payload())
+                .await
+                .context("Failed to send the digest to Slack")?;
+        }
+        if let Some(server) = &smtp_server {
+            let from = commands::config_value(repo_path, DIGEST_FROM_KEY)
+                .unwrap_or_else(|| "git-partial@localhost".to_string());
+            smtp::send(server, &from, &recipients, &title, &text)
+                .await
+                .context("Failed to email the digest")?;
+        }
+    }
+
+    DigestState {
+        last_sent: current.to_string(),
+    }
+    .save(repo_path)
+}
+
+/// Fetch the upstream and report what arrived since `last_seen`, or since
+/// the remote-tracking ref's previous position on the first check.
+/// Returns the upstream commit to compare the next check against.
+fn check(
+    repo_path: &Path,
+    backend: &CommandBackend,
+    last_seen: Option<&str>,
+) -> Result<(String, Option<Incoming>)> {
+    let branch = backend
+        .current_branch()
+        .context("Failed to get current branch")?;
+    let previous = match last_seen {
+        Some(commit) => Some(commit.to_string()),
+        None => upstream::resolve(backend, &branch, None)
+            .and_then(|upstream| backend.rev_parse(&upstream.rev()))
+            .ok(),
+    };
+
+    let started = Instant::now();
+    let (_, upstream) = smart_pull::fetch_upstream(backend, None, None)?;
+    metrics::observe_fetch(
+        &repository_label(repo_path),
+        started.elapsed().as_secs_f64(),
+    );
+    let current = backend
+        .rev_parse(&upstream)
+        .with_context(|| format!("Failed to resolve {}", upstream))?;
+    let Some(previous) = previous.filter(|previous| *previous != current) else {
+        info!("No new commits on {}", upstream);
+        return Ok((current, None));
+    };
+
+    let metadata = RepositoryMetadata::load(repo_path).context("Failed to load metadata")?;
+    let patterns: Vec<String> = metadata.checked_out_paths.iter().cloned().collect();
+    let changes = commands::diff_name_status(repo_path, &previous, &current)
+        .context("Failed to list incoming changes")?;
+    let summary = incoming::summarize(&changes, &patterns, None);
+    if summary.is_empty() {
+        info!(
+            "New commits on {} leave the sparse paths unchanged",
+            upstream
+        );
+        return Ok((current, None));
+    }
+
+    let (_, commits) = backend.ahead_behind(&previous, &current)?;
+    let incoming = Incoming {
+        upstream,
+        from: previous,
+        to: current.clone(),
+        commits,
+        summary,
+    };
+    Ok((current, Some(incoming)))
+}
+
+/// Names the repository in metrics by its remote URL
+fn repository_label(repo_path: &Path) -> String {
+    RepositoryMetadata::load(repo_path)
+        .map(|metadata| metadata.remote_url)
+        .unwrap_or_else(|_| repo_path.to_string_lossy().into_owned())
+}
+
+/// Record how far HEAD is behind `upstream` and the checkout size in metrics
+fn record_state(
+    repo_path: &Path,
+    backend: &CommandBackend,
+    upstream: &str,
+) {
+    let repository = repository_label(repo_path);
+    match backend.ahead_behind("HEAD", upstream) {
+        Ok((_, behind)) => metrics::set_behind(&repository, behind),
+        Err(e) => warn!("Failed to count commits behind {}: {:#}", upstream, e),
+    }
+    let git_dir_bytes = disk::dir_size(&repo_path.join(".git"));
+    metrics::set_checkout_bytes(
+        &repository,
+        disk::dir_size(repo_path).saturating_sub(git_dir_bytes),
+    );
+}
+
+/// Send a notification everywhere it is configured; failures are logged
+async fn notify_all(
+    repo_path: &Path,
+    incoming: &Incoming,
+) {
+    let title = incoming.title();
+    let body = incoming.summary.to_string();
+    println!("{}\n{}", title, body.trim_end());
+
+    let desktop = commands::config_value(repo_path, DESKTOP_KEY)
+        .and_then(|value| bool::parse_setting(&value))
+        .unwrap_or(false);
+    if desktop {
+        if let Err(e) = notify::desktop(&title, &body) {
+            warn!("Desktop notification failed: {:#}", e);
+        }
+    }
+
+    if let Some(url) = commands::config_value(repo_path, WEBHOOK_KEY) {
+        let remote_url = RepositoryMetadata::load(repo_path)
+            .map(|metadata| metadata.remote_url)
+            .unwrap_or_default();
+        if let Err(e) = remote::post_json(&url, &incoming.payload(&remote_url)).await {
+            warn!("Webhook notification failed: {:#}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload() {
+        let changes = incoming::parse_name_status("M\0src/app.rs\0A\0docs/new.md");
+        let incoming = Incoming {
+            upstream: "origin/main".to_string(),
+            from: "abc".to_string(),
+            to: "def".to_string(),
+            commits: 2,
+            summary: incoming::summarize(&changes, &["src/**".to_string()], None),
+        };
+
+        assert_eq!(
+            incoming.title(),
+            "2 new commit(s) on origin/main touch the sparse paths"
+        );
+        assert_eq!(
+            incoming.payload("https://example.com/repo.git"),
+            json!({
+                "repository": "https://example.com/repo.git",
+                "upstream": "origin/main",
+                "from": "abc",
+                "to": "def",
+                "commits": 2,
+                "changes": [{"pattern": "src/**", "added": 0, "modified": 1, "deleted": 0}],
+                "outside": 1,
+            })
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+use crate::core::changelog::{self, COMMIT_MARKER};
+use crate::core::history;
+use crate::core::incoming::ChangeKind;
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::sync_points::{self, SyncPoint};
+use crate::git::commands;
+
+/// Record `commit` as a sync point for `what-changed`. A failure is only
+/// logged, as for the history.
+pub fn record(
+    repo_path: &Path,
+    commit: &str,
+) {
+    let point = SyncPoint {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default(),
+        commit: commit.to_string(),
+    };
+    if let Err(e) = sync_points::append(repo_path, &point) {
+        warn!("Failed to record sync point: {:#}", e);
+    }
+}
+
+/// List the recorded sync points, numbered for `what-changed`
+pub async fn list_sync_points() -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+
+    let points = sync_points::load(&current_dir)?;
+    if points.is_empty() {
+        return Ok("No sync points recorded.\n".to_string());
+    }
+    let mut output = String::new();
+    for (index, point) in points.iter().enumerate() {
+        output.push_str(&format!(
+            "#{:<3} {}  {}\n",
+            index + 1,
+            history::format_timestamp(point.timestamp),
+            &point.commit[..point.commit.len().min(7)]
+        ));
+    }
+    Ok(output)
+}
+
+/// List the files within the sparse paths that changed between two sync
+/// points, grouped by directory and then by author. `from` and `to` are
+/// `#N` sync points, dates or git revisions, and default to the two latest
+/// sync points.
+pub async fn what_changed(
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<String> {
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+    let points = sync_points::load(&current_dir)?;
+
+    let from = match from {
+        Some(spec) => resolve(&current_dir, &points, spec)?,
+        None => points
+            .len()
+            .checked_sub(2)
+            .map(|index| points[index].commit.clone())
+            .context(
+                "Fewer than two sync points are recorded; give the commits or dates to compare",
+            )?,
+    };
+    let to = match to {
+        Some(spec) => resolve(&current_dir, &points, spec)?,
+        None => points
+            .last()
+            .map(|point| point.commit.clone())
+            .context("No sync points are recorded; give the commits or dates to compare")?,
+    };
+    info!("Listing changes between {} and {}", from, to);
+
+    let range = format!("{}..{}", from, to);
+    let format = format!("--format={}%an", COMMIT_MARKER);
+    let log = commands::run_git_command_in_dir(
+        &current_dir,
+        &[
+            "log",
+            "--no-renames",
+            "--name-status",
+            "-z",
+            &format,
+            &range,
+        ],
+    )
+    .with_context(|| format!("Failed to read the history of {}", range))?;
+    let net = commands::diff_name_status(&current_dir, &from, &to)
+        .with_context(|| format!("Failed to diff {}", range))?;
+    let mut patterns: Vec<String> = metadata.checked_out_paths.iter().cloned().collect();
+    patterns.sort();
+    let directories = changelog::group(&changelog::parse_log(&log), &net, &patterns);
+
+    let mut output = format!(
+        "Changes in the sparse paths from {} to {}:\n",
+        &from[..from.len().min(7)],
+        &to[..to.len().min(7)]
+    );
+    if directories.is_empty() {
+        output.push_str("  (none)\n");
+        return Ok(output);
+    }
+    for directory in directories {
+        let name = if directory.directory.is_empty() {
+            "/"
+        } else {
+            directory.directory.as_str()
+        };
+        output.push_str(&format!("\n{}\n", name));
+        for author in directory.authors {
+            output.push_str(&format!(
+                "  {} ({} commit{})\n",
+                author.author,
+                author.commits,
+                if author.commits == 1 { "" } else { "s" }
+            ));
+            for file in author.files {
+                let status = match file.kind {
+                    ChangeKind::Added => 'A',
+                    ChangeKind::Modified => 'M',
+                    ChangeKind::Deleted => 'D',
+                };
+                output.push_str(&format!("    {} {}\n", status, file.path));
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// The commit `spec` names, as a sync point or a git revision
+fn resolve(
+    repo_path: &Path,
+    points: &[SyncPoint],
+    spec: &str,
+) -> Result<String> {
+    if let Some(point) = sync_points::resolve(points, spec)? {
+        return Ok(point.commit.clone());
+    }
+    commands::run_git_command_in_dir(
+        repo_path,
+        &["rev-parse", "--verify", &format!("{}^{{commit}}", spec)],
+    )
+    .map(|commit| commit.trim().to_string())
+    .with_context(|| {
+        format!(
+            "'{}' is neither a sync point (#N), a date (YYYY-MM-DD) nor a revision",
+            spec
+        )
+    })
+}
@@ -0,0 +1,208 @@
+use anyhow::{Context, Result};
+use std::env;
+use tracing::info;
+
+use crate::cli::add_paths;
+use crate::core::metadata::RepositoryMetadata;
+use crate::core::path_selector;
+use crate::core::tree::{EntryKind, TreeEntry};
+use crate::git::backend::{CommandBackend, GitBackend};
+use crate::git::commands;
+use crate::git::upstream;
+use crate::utils::prompt;
+
+/// Maximum number of similarly named files suggested for unknown paths
+const MAX_SUGGESTIONS: usize = 5;
+
+/// How a path relates to the sparse patterns at a revision
+#[derive(Debug, PartialEq)]
+pub enum Diagnosis {
+    /// The path is selected by a sparse pattern
+    Covered { pattern: String },
+
+    /// The path exists but no pattern selects it
+    Excluded { suggested_pattern: String },
+
+    /// The path does not exist at the revision
+    NotFound,
+}
+
+/// Explain why a path is missing from the working tree and offer to add it
+pub async fn why_missing(
+    path: &str,
+    assume_yes: bool,
+) -> Result<()> {
+    let path = path.trim_start_matches("./").trim_end_matches('/');
+    info!("Diagnosing missing path {}", path);
+
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+    let mut patterns: Vec<String> = metadata.checked_out_paths.iter().cloned().collect();
+    patterns.sort();
+
+    let entries =
+        commands::list_tree_structure(&current_dir, "HEAD").context("Failed to list HEAD tree")?;
+
+    match diagnose(path, &entries, &patterns) {
+        Diagnosis::Covered { pattern } => {
+            println!(
+                "'{}' exists at HEAD and is included by the pattern '{}'.",
+                path, pattern
+            );
+            println!(
+                "If it is missing from disk, restore it with: git checkout HEAD -- {}",
+                path
+            );
+        }
+        Diagnosis::Excluded { suggested_pattern } => {
+            println!(
+                "'{}' exists at HEAD but is excluded by the sparse patterns ({}).",
+                path,
+                patterns.join(", ")
+            );
+            let summary = format!(
+                "Adding the pattern '{}' would check it out.",
+                suggested_pattern
+            );
+            if prompt::confirm(&summary, assume_yes)? {
+                print!(
+                    "{}",
+                    add_paths::add_new_paths(&[suggested_pattern], true, false, false).await?
+                );
+                println!("Added. '{}' is now checked out.", path);
+            } else {
+                println!(
+                    "Not added. Run: git-partial add-paths '{}'",
+                    suggested_pattern
+                );
+            }
+        }
+        Diagnosis::NotFound => {
+            let backend = CommandBackend::new(&current_dir);
+            let branch = backend.current_branch().unwrap_or_default();
+            let upstream = upstream::resolve(&backend, &branch, None)?.rev();
+            let on_remote = !branch.is_empty()
+                && commands::list_tree_structure(&current_dir, &upstream)
+                    .map(|remote_entries| remote_entries.iter().any(|entry| entry.path == path))
+                    .unwrap_or(false);
+
+            if on_remote {
+                println!(
+                    "'{}' does not exist at HEAD but exists on {}. Run: git-partial smart-pull",
+                    path, upstream
+                );
+            } else {
+                println!("'{}' does not exist at HEAD.", path);
+                let similar = similar_paths(path, &entries);
+                if !similar.is_empty() {
+                    println!("Files with the same name:");
+                    for candidate in similar {
+                        println!("  {}", candidate);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decides whether a path is covered, excluded, or absent at the revision
+pub fn diagnose(
+    path: &str,
+    entries: &[TreeEntry],
+    patterns: &[String],
+) -> Diagnosis {
+    let Some(entry) = entries.iter().find(|entry| entry.path == path) else {
+        return Diagnosis::NotFound;
+    };
+
+    let covering = patterns.iter().find(|raw| {
+        let pattern = path_selector::compile_sparse(raw);
+        match entry.kind {
+            EntryKind::Blob => path_selector::pattern_covers(&pattern, path),
+            // A directory counts as covered when any file below it is
+            EntryKind::Tree => entries.iter().any(|other| {
+                other.kind == EntryKind::Blob
+                    && other.path.starts_with(&format!("{}/", path))
+                    && path_selector::pattern_covers(&pattern, &other.path)
+            }),
+        }
+    });
+
+    match covering {
+        Some(pattern) => Diagnosis::Covered {
+            pattern: pattern.clone(),
+        },
+        None => Diagnosis::Excluded {
+            suggested_pattern: match entry.kind {
+                EntryKind::Blob => path.to_string(),
+                EntryKind::Tree => format!("{}/**", path),
+            },
+        },
+    }
+}
+
+/// Returns files with the same file name as the path, for typos in directories
+fn similar_paths<'a>(
+    path: &str,
+    entries: &'a [TreeEntry],
+) -> Vec<&'a str> {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    entries
+        .iter()
+        .filter(|entry| entry.kind == EntryKind::Blob)
+        .map(|entry| entry.path.as_str())
+        .filter(|candidate| candidate.rsplit('/').next() == Some(file_name))
+        .take(MAX_SUGGESTIONS)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        path: &str,
+        kind: EntryKind,
+    ) -> TreeEntry {
+        TreeEntry {
+            path: path.to_string(),
+            kind,
+            size: None,
+        }
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let entries = vec![
+            entry("src", EntryKind::Tree),
+            entry("src/main.rs", EntryKind::Blob),
+            entry("docs", EntryKind::Tree),
+            entry("docs/guide.md", EntryKind::Blob),
+        ];
+        let patterns = vec!["src/**".to_string()];
+
+        assert_eq!(
+            diagnose("src/main.rs", &entries, &patterns),
+            Diagnosis::Covered {
+                pattern: "src/**".to_string()
+            }
+        );
+        assert_eq!(
+            diagnose("docs", &entries, &patterns),
+            Diagnosis::Excluded {
+                suggested_pattern: "docs/**".to_string()
+            }
+        );
+        assert_eq!(
+            diagnose("docs/guide.md", &entries, &patterns),
+            Diagnosis::Excluded {
+                suggested_pattern: "docs/guide.md".to_string()
+            }
+        );
+        assert_eq!(
+            diagnose("missing.txt", &entries, &patterns),
+            Diagnosis::NotFound
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::fs;
+use tracing::{debug, info};
+
+use crate::cli::history;
+use crate::core::metadata::RepositoryMetadata;
+use crate::git::commands;
+
+/// Create a linked worktree with its own sparse patterns and metadata.
+/// Objects are shared with the current clone. Without paths, the current
+/// sparse patterns are reused.
+pub async fn add_worktree(
+    path: &str,
+    branch: &str,
+    paths: &[String],
+) -> Result<()> {
+    info!("Adding worktree {} for branch {}", path, branch);
+
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    let metadata = RepositoryMetadata::load(&current_dir).context("Failed to load metadata")?;
+
+    let paths: Vec<String> = if paths.is_empty() {
+        let mut current: Vec<String> = metadata.checked_out_paths.iter().cloned().collect();
+        current.sort();
+        current
+    } else {
+        paths.to_vec()
+    };
+    debug!("Worktree paths: {:?}", paths);
+
+    let worktree_path = current_dir.join(path);
+    if worktree_path.exists() && fs::read_dir(&worktree_path)?.next().is_some() {
+        anyhow::bail!("Destination directory '{}' exists and is not empty.", path);
+    }
+
+    commands::add_worktree(&current_dir, &worktree_path, branch)
+        .with_context(|| format!("Failed to create worktree at {}", path))?;
+
+    // Sparse patterns are stored per worktree, so this leaves the current checkout untouched
+    commands::set_sparse_checkout(&worktree_path, &paths)
+        .context("Failed to set sparse checkout paths")?;
+
+    let mut worktree_metadata = RepositoryMetadata::new(metadata.remote_url.clone());
+    worktree_metadata.add_paths(&paths);
+    let head_commit =
+        commands::get_head_commit(&worktree_path).context("Failed to get HEAD commit")?;
+    worktree_metadata.set_last_commit(&head_commit);
+    worktree_metadata
+        .save(&worktree_path)
+        .context("Failed to save metadata")?;
+    history::record(&worktree_path, &paths, &[]);
+
+    info!("Worktree created at {}", worktree_path.display());
+    Ok(())
+}
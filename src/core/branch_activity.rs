@@ -0,0 +1,111 @@
+use crate::core::path_selector;
+
+/// `git for-each-ref` format read by [`parse_refs`]
+pub const REF_FORMAT: &str = "%(refname:short)%09%(committerdate:unix)%09%(authorname)";
+
+/// A remote-tracking branch and its tip commit
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteBranch {
+    /// Short name, e.g. `origin/feature/x`
+    pub name: String,
+
+    /// Committer date of the tip, in Unix seconds
+    pub timestamp: u64,
+
+    /// Author of the tip
+    pub author: String,
+}
+
+/// Work on a branch since it diverged from the base branch
+#[derive(Debug, PartialEq)]
+pub struct BranchActivity {
+    pub branch: RemoteBranch,
+
+    /// Commits on the branch since the merge base
+    pub commits: usize,
+
+    /// Files changed since the merge base that the sparse paths cover
+    pub files: Vec<String>,
+}
+
+/// Parses `git for-each-ref --format=<REF_FORMAT>`, leaving out symbolic
+/// `HEAD` refs, which git shortens to the remote name
+pub fn parse_refs(output: &str) -> Vec<RemoteBranch> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let name = fields.next()?;
+            let timestamp = fields.next()?.parse().ok()?;
+            let author = fields.next().unwrap_or_default();
+            (name.contains('/') && !name.ends_with("/HEAD")).then(|| RemoteBranch {
+                name: name.to_string(),
+                timestamp,
+                author: author.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The files of `changed` the sparse `patterns` cover
+pub fn relevant_files(
+    changed: &[String],
+    patterns: &[String],
+) -> Vec<String> {
+    let compiled: Vec<_> = patterns
+        .iter()
+        .map(|raw| path_selector::compile_sparse(raw))
+        .collect();
+    changed
+        .iter()
+        .filter(|path| {
+            compiled
+                .iter()
+                .any(|pattern| path_selector::pattern_covers(pattern, path))
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_refs() {
+        let output = "origin\t1760000000\tAlice\n\
+                      origin/feature/x\t1760000100\tBob Smith\n\
+                      origin/main\t1760000200\t\n\
+                      bad line\n";
+
+        assert_eq!(
+            parse_refs(output),
+            vec![
+                RemoteBranch {
+                    name: "origin/feature/x".to_string(),
+                    timestamp: 1_760_000_100,
+                    author: "Bob Smith".to_string(),
+                },
+                RemoteBranch {
+                    name: "origin/main".to_string(),
+                    timestamp: 1_760_000_200,
+                    author: String::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_relevant_files() {
+        let changed: Vec<String> = ["src/a.rs", "docs/b.md", "README.md"]
+            .iter()
+            .map(|path| path.to_string())
+            .collect();
+        let patterns = vec!["src/**".to_string(), "/README.md".to_string()];
+
+        assert_eq!(
+            relevant_files(&changed, &patterns),
+            vec!["src/a.rs".to_string(), "README.md".to_string()]
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt;
+
+const V2_SIGNATURE: &str = "# v2 git bundle";
+const V3_SIGNATURE: &str = "# v3 git bundle";
+
+/// The text header of a git bundle, up to the blank line before the pack
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BundleHeader {
+    /// Filter the pack was made with, for bundles missing some objects
+    pub filter: Option<String>,
+
+    /// Commits the receiving repository must already have
+    pub prerequisites: Vec<String>,
+
+    /// Object id and name of each ref, in bundle order
+    pub refs: Vec<(String, String)>,
+}
+
+impl BundleHeader {
+    /// Parses the header at the start of a bundle.
+    /// Returns `None` when the data is not a v2 or v3 bundle.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let end = data.windows(2).position(|w| w == b"\n\n")?;
+        let text = std::str::from_utf8(&data[..end]).ok()?;
+        let mut lines = text.lines();
+        let signature = lines.next()?;
+        if signature != V2_SIGNATURE && signature != V3_SIGNATURE {
+            return None;
+        }
+
+        let mut header = BundleHeader::default();
+        for line in lines {
+            if let Some(capability) = line.strip_prefix('@') {
+                if let Some(filter) = capability.strip_prefix("filter=") {
+                    header.filter = Some(filter.to_string());
+                }
+            } else if let Some(prerequisite) = line.strip_prefix('-') {
+                let id = prerequisite.split(' ').next().unwrap_or(prerequisite);
+                header.prerequisites.push(id.to_string());
+            } else {
+                let (id, name) = line.split_once(' ')?;
+                header.refs.push((id.to_string(), name.to_string()));
+            }
+        }
+        Some(header)
+    }
+
+    /// Branches in the bundle, in bundle order
+    pub fn branches(&self) -> Vec<(&str, &str)> {
+        self.refs
+            .iter()
+            .filter_map(|(id, name)| Some((name.strip_prefix("refs/heads/")?, id.as_str())))
+            .collect()
+    }
+}
+
+impl fmt::Display for BundleHeader {
+    /// Writes the header, blank line included. Filtered bundles need v3.
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match &self.filter {
+            Some(filter) => {
+                writeln!(f, "{}", V3_SIGNATURE)?;
+                writeln!(f, "@object-format=sha1")?;
+                writeln!(f, "@filter={}", filter)?;
+            }
+            None => writeln!(f, "{}", V2_SIGNATURE)?,
+        }
+        for prerequisite in &self.prerequisites {
+            writeln!(f, "-{}", prerequisite)?;
+        }
+        for (id, name) in &self.refs {
+            writeln!(f, "{} {}", id, name)?;
+        }
+        writeln!(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let header = BundleHeader {
+            filter: Some("sparse:oid=8eba6c8".to_string()),
+            prerequisites: Vec::new(),
+            refs: vec![
+                ("47e2c0e".to_string(), "refs/heads/main".to_string()),
+                ("5d1f2a9".to_string(), "refs/tags/v1".to_string()),
+            ],
+        };
+        let mut data = header.to_string().into_bytes();
+        data.extend_from_slice(b"PACK\0\0");
+
+        assert!(header.to_string().starts_with("# v3 git bundle\n"));
+        assert_eq!(BundleHeader::parse(&data), Some(header.clone()));
+        assert_eq!(
+            BundleHeader::parse(&data).unwrap().branches(),
+            vec![("main", "47e2c0e")]
+        );
+    }
+
+    #[test]
+    fn test_parse_git_bundle() {
+        let data = b"# v2 git bundle\n-1a2b3c4 Old commit\n47e2c0e refs/heads/main\n\nPACK";
+        let header = BundleHeader::parse(data).unwrap();
+
+        assert_eq!(header.filter, None);
+        assert_eq!(header.prerequisites, vec!["1a2b3c4"]);
+        assert!(BundleHeader::parse(b"ref: refs/heads/main\n\n").is_none());
+    }
+}
@@ -0,0 +1,281 @@
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use toml::Value;
+use tracing::debug;
+
+use crate::core::path_selector::join_dir;
+use crate::core::workspace::ProjectSelection;
+
+/// Dependency tables of a manifest; all must resolve for cargo to load it
+const DEPENDENCY_TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Files at the workspace root that configure how cargo builds
+const ROOT_FILES: [&str; 5] = [
+    "Cargo.toml",
+    "Cargo.lock",
+    "rust-toolchain",
+    "rust-toolchain.toml",
+    ".cargo/config.toml",
+];
+
+/// Resolves the crate in `crate_dir` to the directories of its transitive
+/// path dependencies, and the files cargo reads from the workspace `members`
+/// and their own path dependencies. Cargo loads every member, and every path
+/// dependency (development ones included), before building anything.
+/// `read_file` returns the content of a file at the inspected revision.
+pub fn select_crate<F>(
+    crate_dir: &str,
+    members: &[String],
+    read_file: F,
+) -> Result<ProjectSelection>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let read_manifest = |dir: &str| {
+        read_file(&in_dir(dir, "Cargo.toml")).and_then(|content| parse_manifest(&content).ok())
+    };
+    let workspace_deps = read_manifest("")
+        .map(|root| workspace_dependencies(&root))
+        .unwrap_or_default();
+
+    let dirs = closure(&[crate_dir.to_string()], &read_manifest, &workspace_deps);
+    if !dirs.contains_key(crate_dir) {
+        anyhow::bail!("No Cargo.toml found in '{}'", crate_dir);
+    }
+
+    let mut selection = ProjectSelection {
+        dirs: dirs.keys().cloned().collect(),
+        files: ROOT_FILES.iter().map(|file| file.to_string()).collect(),
+    };
+    for (dir, manifest) in closure(members, &read_manifest, &workspace_deps) {
+        if selection.dirs.contains(&dir) {
+            continue;
+        }
+        selection.files.insert(in_dir(&dir, "Cargo.toml"));
+        selection.files.extend(
+            target_roots(&manifest)
+                .iter()
+                .map(|file| in_dir(&dir, file)),
+        );
+    }
+    // `/**` would be everything; the root crate's sources are enough
+    if selection.dirs.remove("") {
+        selection.dirs.insert("src".to_string());
+        if let Some(manifest) = read_manifest("") {
+            selection.files.extend(target_roots(&manifest));
+        }
+    }
+    debug!("Selected crate: {:?}", selection);
+    Ok(selection)
+}
+
+/// The crates reachable from `start` through path dependencies, with their
+/// manifests. Directories without a readable manifest are left out.
+fn closure<F>(
+    start: &[String],
+    read_manifest: &F,
+    workspace_deps: &BTreeMap<String, String>,
+) -> BTreeMap<String, Value>
+where
+    F: Fn(&str) -> Option<Value>,
+{
+    let mut crates = BTreeMap::new();
+    let mut visited = BTreeSet::new();
+    let mut queue: VecDeque<String> = start.iter().cloned().collect();
+    while let Some(dir) = queue.pop_front() {
+        if !visited.insert(dir.clone()) {
+            continue;
+        }
+        let Some(manifest) = read_manifest(&dir) else {
+            debug!("No manifest in '{}'", dir);
+            continue;
+        };
+        queue.extend(path_dependencies(&manifest, &dir, workspace_deps));
+        crates.insert(dir, manifest);
+    }
+    crates
+}
+
+/// The directories of the path dependencies of the crate in `crate_dir`, of
+/// every kind and target. `workspace_deps` resolves `{ workspace = true }`.
+pub fn path_dependencies(
+    manifest: &Value,
+    crate_dir: &str,
+    workspace_deps: &BTreeMap<String, String>,
+) -> Vec<String> {
+    let mut tables: Vec<&Value> = DEPENDENCY_TABLES
+        .iter()
+        .filter_map(|name| manifest.get(name))
+        .collect();
+    if let Some(targets) = manifest.get("target").and_then(Value::as_table) {
+        for target in targets.values() {
+            tables.extend(DEPENDENCY_TABLES.iter().filter_map(|name| target.get(name)));
+        }
+    }
+
+    let mut dirs = Vec::new();
+    for (name, spec) in tables.iter().filter_map(|table| table.as_table()).flatten() {
+        if let Some(path) = spec.get("path").and_then(Value::as_str) {
+            dirs.extend(join_dir(crate_dir, path));
+        } else if spec.get("workspace").and_then(Value::as_bool) == Some(true) {
+            dirs.extend(workspace_deps.get(name).cloned());
+        }
+    }
+    dirs
+}
+
+/// The directories of the path dependencies in `[workspace.dependencies]`, by name
+pub fn workspace_dependencies(root: &Value) -> BTreeMap<String, String> {
+    root.get("workspace")
+        .and_then(|workspace| workspace.get("dependencies"))
+        .and_then(Value::as_table)
+        .into_iter()
+        .flatten()
+        .filter_map(|(name, spec)| {
+            let path = spec.get("path")?.as_str()?;
+            Some((name.clone(), join_dir("", path)?))
+        })
+        .collect()
+}
+
+/// Files cargo looks for to find a crate's targets, relative to the crate
+fn target_roots(manifest: &Value) -> Vec<String> {
+    let mut roots = vec![
+        "src/lib.rs".to_string(),
+        "src/main.rs".to_string(),
+        "build.rs".to_string(),
+    ];
+    let declared = manifest
+        .get("lib")
+        .into_iter()
+        .chain(
+            manifest
+                .get("bin")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten(),
+        )
+        .filter_map(|target| target.get("path")?.as_str());
+    roots.extend(declared.map(str::to_string));
+    if let Some(build) = manifest
+        .get("package")
+        .and_then(|package| package.get("build"))
+        .and_then(Value::as_str)
+    {
+        roots.push(build.to_string());
+    }
+    roots
+}
+
+fn in_dir(
+    dir: &str,
+    file: &str,
+) -> String {
+    if dir.is_empty() {
+        file.to_string()
+    } else {
+        format!("{}/{}", dir, file)
+    }
+}
+
+/// Parses a `Cargo.toml`
+pub fn parse_manifest(content: &str) -> Result<Value> {
+    Ok(Value::Table(
+        content
+            .parse::<toml::Table>()
+            .context("Failed to parse Cargo.toml")?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_path_dependencies() {
+        let manifest = parse_manifest(
+            r#"
+[dependencies]
+serde = "1"
+core = { path = "../core" }
+shared = { workspace = true }
+
+[dev-dependencies]
+fixtures = { path = "../../tests/fixtures" }
+
+[target.'cfg(unix)'.dependencies]
+unix = { path = "../unix" }
+"#,
+        )
+        .unwrap();
+        let workspace_deps = BTreeMap::from([("shared".to_string(), "libs/shared".to_string())]);
+
+        let mut dirs = path_dependencies(&manifest, "crates/app", &workspace_deps);
+        dirs.sort();
+        assert_eq!(
+            dirs,
+            vec![
+                "crates/core",
+                "crates/unix",
+                "libs/shared",
+                "tests/fixtures"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_crate() {
+        let files = HashMap::from([
+            (
+                "Cargo.toml",
+                "[workspace]\nmembers = [\"crates/*\"]\n[workspace.dependencies]\nutil = { path = \"libs/util\" }\n",
+            ),
+            (
+                "crates/app/Cargo.toml",
+                "[package]\nname = \"app\"\n[dependencies]\ncore = { path = \"../core\" }\n",
+            ),
+            (
+                "crates/core/Cargo.toml",
+                "[package]\nname = \"core\"\n[dependencies]\nutil = { workspace = true }\n",
+            ),
+            ("libs/util/Cargo.toml", "[package]\nname = \"util\"\n"),
+            (
+                "crates/web/Cargo.toml",
+                "[package]\nname = \"web\"\n[[bin]]\nname = \"web\"\npath = \"bin/web.rs\"\n[dev-dependencies]\nmock = { path = \"../../libs/mock\" }\n",
+            ),
+            ("libs/mock/Cargo.toml", "[package]\nname = \"mock\"\n"),
+        ]);
+        let members = vec![
+            "crates/app".to_string(),
+            "crates/core".to_string(),
+            "crates/web".to_string(),
+        ];
+
+        let selection = select_crate("crates/app", &members, |path| {
+            files.get(path).map(|content| content.to_string())
+        })
+        .unwrap();
+
+        let dirs: Vec<&str> = selection.dirs.iter().map(String::as_str).collect();
+        assert_eq!(dirs, vec!["crates/app", "crates/core", "libs/util"]);
+        assert!(selection.files.contains("crates/web/Cargo.toml"));
+        assert!(selection.files.contains("crates/web/bin/web.rs"));
+        assert!(selection.files.contains("libs/mock/Cargo.toml"));
+        assert!(selection.files.contains("libs/mock/src/lib.rs"));
+        assert!(!selection.files.contains("crates/core/Cargo.toml"));
+
+        let patterns = selection.patterns(|file| files.contains_key(file));
+        assert_eq!(
+            patterns,
+            vec![
+                "crates/app/**",
+                "crates/core/**",
+                "libs/util/**",
+                "Cargo.toml",
+                "crates/web/Cargo.toml",
+                "libs/mock/Cargo.toml",
+            ]
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::core::incoming::{self, FileChange};
+use crate::core::path_selector;
+
+/// Starts each commit in the output of `git log --format`
+pub const COMMIT_MARKER: &str = "commit\t";
+
+/// A commit and the files it changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogCommit {
+    pub author: String,
+    pub changes: Vec<FileChange>,
+}
+
+/// What one author changed in a directory
+#[derive(Debug, PartialEq)]
+pub struct AuthorChanges {
+    pub author: String,
+
+    /// Commits of the author touching the directory
+    pub commits: usize,
+
+    /// Files with their change over the whole range, by path
+    pub files: Vec<FileChange>,
+}
+
+/// The changes to the files directly in one directory
+#[derive(Debug, PartialEq)]
+pub struct DirectoryChanges {
+    /// Directory with a trailing slash; empty for the repository root
+    pub directory: String,
+
+    /// Authors with the most commits first
+    pub authors: Vec<AuthorChanges>,
+}
+
+/// An author's commits in a directory and the files they touched
+type AuthorFiles<'a> = (usize, BTreeMap<&'a str, &'a FileChange>);
+
+/// Parses `git log --no-renames --name-status -z --format=commit%x09%an`
+pub fn parse_log(output: &str) -> Vec<LogCommit> {
+    let mut commits: Vec<LogCommit> = Vec::new();
+    let mut changes = String::new();
+    for field in output.split('\0').filter(|field| !field.is_empty()) {
+        if let Some(author) = field.trim_start().strip_prefix(COMMIT_MARKER) {
+            if let Some(last) = commits.last_mut() {
+                last.changes = incoming::parse_name_status(&changes);
+            }
+            changes.clear();
+            commits.push(LogCommit {
+                author: author.to_string(),
+                changes: Vec::new(),
+            });
+        } else {
+            changes.push_str(field);
+            changes.push('\0');
+        }
+    }
+    if let Some(last) = commits.last_mut() {
+        last.changes = incoming::parse_name_status(&changes);
+    }
+    commits
+}
+
+/// Groups the files of `net` covered by `patterns` by directory, then by
+/// the authors of `commits` who touched them. A file changed by several
+/// authors is listed under each. `net` holds the change of each file over
+/// the whole range, so files changed and changed back are left out.
+pub fn group(
+    commits: &[LogCommit],
+    net: &[FileChange],
+    patterns: &[String],
+) -> Vec<DirectoryChanges> {
+    let compiled: Vec<_> = patterns
+        .iter()
+        .map(|raw| path_selector::compile_sparse(raw))
+        .collect();
+    let net: HashMap<&str, &FileChange> = net
+        .iter()
+        .filter(|change| {
+            compiled
+                .iter()
+                .any(|pattern| path_selector::pattern_covers(pattern, &change.path))
+        })
+        .map(|change| (change.path.as_str(), change))
+        .collect();
+
+    let mut grouped: BTreeMap<String, BTreeMap<&str, AuthorFiles>> = BTreeMap::new();
+    for commit in commits {
+        let mut touched: Vec<&str> = Vec::new();
+        for change in &commit.changes {
+            let Some(file) = net.get(change.path.as_str()) else {
+                continue;
+            };
+            let directory = directory_of(&file.path);
+            let (_, files) = grouped
+                .entry(directory.to_string())
+                .or_default()
+                .entry(&commit.author)
+                .or_default();
+            files.insert(&file.path, file);
+            if !touched.contains(&directory) {
+                touched.push(directory);
+            }
+        }
+        for directory in touched {
+            if let Some((count, _)) = grouped
+                .get_mut(directory)
+                .and_then(|authors| authors.get_mut(commit.author.as_str()))
+            {
+                *count += 1;
+            }
+        }
+    }
+
+    grouped
+        .into_iter()
+        .map(|(directory, authors)| {
+            let mut authors: Vec<AuthorChanges> = authors
+                .into_iter()
+                .map(|(author, (commits, files))| AuthorChanges {
+                    author: author.to_string(),
+                    commits,
+                    files: files.into_values().cloned().collect(),
+                })
+                .collect();
+            authors.sort_by(|a, b| b.commits.cmp(&a.commits).then(a.author.cmp(&b.author)));
+            DirectoryChanges { directory, authors }
+        })
+        .collect()
+}
+
+/// The directory of `path` with a trailing slash, or `""` at the root
+fn directory_of(path: &str) -> &str {
+    path.rfind('/').map_or("", |index| &path[..=index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::incoming::ChangeKind;
+
+    fn change(
+        kind: ChangeKind,
+        path: &str,
+    ) -> FileChange {
+        FileChange {
+            kind,
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_log() {
+        let output = "commit\tAlice\0\nM\0src/a.rs\0A\0src/b.rs\0commit\tBob Smith\0commit\tAlice\0\nD\0docs/c\u{e9}.md\0";
+
+        assert_eq!(
+            parse_log(output),
+            vec![
+                LogCommit {
+                    author: "Alice".to_string(),
+                    changes: vec![
+                        change(ChangeKind::Modified, "src/a.rs"),
+                        change(ChangeKind::Added, "src/b.rs")
+                    ],
+                },
+                LogCommit {
+                    author: "Bob Smith".to_string(),
+                    changes: Vec::new(),
+                },
+                LogCommit {
+                    author: "Alice".to_string(),
+                    changes: vec![change(ChangeKind::Deleted, "docs/c\u{e9}.md")],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group() {
+        let commits = parse_log(
+            "commit\tBob\0\nM\0src/a.rs\0A\0src/tmp.rs\0\
+             commit\tAlice\0\nM\0src/a.rs\0A\0src/b.rs\0M\0lib/x.rs\0\
+             commit\tAlice\0\nM\0src/a.rs\0D\0src/tmp.rs\0M\0README.md\0",
+        );
+        // src/tmp.rs came and went, lib/ is outside the sparse paths
+        let net = vec![
+            change(ChangeKind::Modified, "src/a.rs"),
+            change(ChangeKind::Added, "src/b.rs"),
+            change(ChangeKind::Modified, "lib/x.rs"),
+            change(ChangeKind::Modified, "README.md"),
+        ];
+        let patterns = vec!["src/**".to_string(), "/README.md".to_string()];
+
+        assert_eq!(
+            group(&commits, &net, &patterns),
+            vec![
+                DirectoryChanges {
+                    directory: String::new(),
+                    authors: vec![AuthorChanges {
+                        author: "Alice".to_string(),
+                        commits: 1,
+                        files: vec![change(ChangeKind::Modified, "README.md")],
+                    }],
+                },
+                DirectoryChanges {
+                    directory: "src/".to_string(),
+                    authors: vec![
+                        AuthorChanges {
+                            author: "Alice".to_string(),
+                            commits: 2,
+                            files: vec![
+                                change(ChangeKind::Modified, "src/a.rs"),
+                                change(ChangeKind::Added, "src/b.rs")
+                            ],
+                        },
+                        AuthorChanges {
+                            author: "Bob".to_string(),
+                            commits: 1,
+                            files: vec![change(ChangeKind::Modified, "src/a.rs")],
+                        },
+                    ],
+                },
+            ]
+        );
+    }
+}
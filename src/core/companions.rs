@@ -0,0 +1,161 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+
+use crate::core::path_selector;
+
+/// File at the repository root that holds the companion rules
+pub const COMPANIONS_FILE: &str = ".gitpartial-companions.toml";
+
+/// Paths `add-paths` adds along with others, encoding the conventions of a
+/// monorepo, such as the protos and configs that go with a service
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CompanionRules {
+    #[serde(default, rename = "companion")]
+    pub rules: Vec<CompanionRule>,
+}
+
+/// One `[[companion]]` rule
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CompanionRule {
+    /// Pattern that triggers the rule, e.g. `services/${X}/**`, where
+    /// `${X}` stands for one path segment
+    pub when: String,
+
+    /// Patterns to add with it, e.g. `proto/${X}/**`
+    pub add: Vec<String>,
+}
+
+impl CompanionRule {
+    /// The values of the `${NAME}` segments of `when` if `pattern` matches
+    /// it. The rest of `when` is compared literally.
+    fn captures(
+        &self,
+        pattern: &str,
+    ) -> Option<HashMap<String, String>> {
+        let mut regex = String::from("^");
+        let mut names = Vec::new();
+        let mut rest = self.when.as_str();
+        while let Some(start) = rest.find("${") {
+            let Some(len) = rest[start + 2..].find('}') else {
+                break;
+            };
+            regex.push_str(&regex::escape(&rest[..start]));
+            regex.push_str("([^/]+)");
+            names.push(&rest[start + 2..start + 2 + len]);
+            rest = &rest[start + 3 + len..];
+        }
+        regex.push_str(&regex::escape(rest));
+        regex.push('$');
+
+        let captures = Regex::new(&regex).ok()?.captures(pattern)?;
+        let mut values = HashMap::new();
+        for (name, value) in names.into_iter().zip(captures.iter().skip(1)) {
+            let value = value?.as_str();
+            // A name repeated in `when` must match the same segment
+            if values
+                .insert(name.to_string(), value.to_string())
+                .is_some_and(|v| v != value)
+            {
+                return None;
+            }
+        }
+        Some(values)
+    }
+}
+
+impl CompanionRules {
+    pub fn parse(content: &str) -> Result<Self> {
+        toml::from_str(content).context("Failed to parse companion rules")
+    }
+
+    /// The companions of `patterns`, and in turn theirs, in the order the
+    /// rules produce them, leaving out `patterns` themselves. Variables of
+    /// `add` that `when` does not bind are kept for the caller to resolve.
+    pub fn companions(
+        &self,
+        patterns: &[String],
+    ) -> Vec<String> {
+        let mut companions: Vec<String> = Vec::new();
+        let mut queue: VecDeque<String> = patterns.iter().cloned().collect();
+        while let Some(pattern) = queue.pop_front() {
+            for rule in &self.rules {
+                let Some(values) = rule.captures(&pattern) else {
+                    continue;
+                };
+                for add in &rule.add {
+                    let companion = path_selector::substitute_variables(add, |name| {
+                        Some(
+                            values
+                                .get(name)
+                                .cloned()
+                                .unwrap_or_else(|| format!("${{{}}}", name)),
+                        )
+                    })
+                    .unwrap_or_else(|_| add.clone());
+                    if !patterns.contains(&companion) && !companions.contains(&companion) {
+                        companions.push(companion.clone());
+                        queue.push_back(companion);
+                    }
+                }
+            }
+        }
+        companions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_companions() {
+        let rules = CompanionRules::parse(
+            r#"
+[[companion]]
+when = "services/${X}/**"
+add = ["proto/${X}/**", "configs/${X}.yaml", "tools/${OS}/**"]
+
+[[companion]]
+when = "proto/${X}/**"
+add = ["proto/common/**"]
+
+[[companion]]
+when = "pairs/${X}/${X}/**"
+add = ["same/${X}"]
+"#,
+        )
+        .unwrap();
+        let patterns =
+            |patterns: &[&str]| -> Vec<String> { patterns.iter().map(|p| p.to_string()).collect() };
+
+        assert_eq!(
+            rules.companions(&patterns(&["services/payments/**", "proto/common/**"])),
+            vec![
+                "proto/payments/**",
+                "configs/payments.yaml",
+                "tools/${OS}/**"
+            ]
+        );
+        assert_eq!(
+            rules.companions(&patterns(&["services/search/**"])),
+            vec![
+                "proto/search/**",
+                "configs/search.yaml",
+                "tools/${OS}/**",
+                "proto/common/**"
+            ]
+        );
+        assert!(rules
+            .companions(&patterns(&["services/payments/api/**", "services/**"]))
+            .is_empty());
+        assert_eq!(
+            rules.companions(&patterns(&["pairs/a/a/**", "pairs/a/b/**"])),
+            vec!["same/a"]
+        );
+        assert!(CompanionRules::parse("[[companion]]\nwhen = \"x\"\n").is_err());
+    }
+}
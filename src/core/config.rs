@@ -0,0 +1,791 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::core::error::PartialError;
+use crate::core::path_selector;
+use crate::git::commands;
+
+/// Partial clone filter used unless the global config sets another
+pub const DEFAULT_FILTER: &str = "blob:none";
+
+/// Machine-wide defaults, read from `~/.config/git-partial/config.toml`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GlobalConfig {
+    /// Partial clone filter for `clone`, e.g. `blob:limit=1m`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+
+    /// Use cone mode when every sparse path is a whole directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cone: Option<bool>,
+
+    /// When log output is colored
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<ColorChoice>,
+
+    /// Proxy for git and the provider APIs, e.g. `http://proxy:3128`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+
+    /// Object cache for `clone --ci` and `serve-cache`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_dir: Option<String>,
+
+    /// Default for `--jobs`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jobs: Option<usize>,
+
+    /// Default for `status --format`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<OutputFormat>,
+
+    /// Default for `status --no-fetch`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_fetch_on_status: Option<bool>,
+
+    /// Default for `clone --narrow-fetch`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub narrow_fetch: Option<bool>,
+
+    /// How long the objects of removed paths are kept, e.g. `14d`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prune_after: Option<String>,
+
+    /// Largest checkout `clone` and `add-paths` accept without `--force`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_checkout_size: Option<ByteSize>,
+
+    /// Most files `clone` and `add-paths` check out without `--force`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_files: Option<usize>,
+
+    /// Values of the `${NAME}` variables of path patterns
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub vars: BTreeMap<String, String>,
+
+    /// Editor command for `open`, e.g. `code --wait`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+
+    /// Identity profile new clones are seeded with, from `identities`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+
+    /// Git identities and signing settings for new clones, by profile name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub identities: BTreeMap<String, IdentityProfile>,
+
+    /// Client certificate for HTTPS servers requiring mutual TLS
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_cert: Option<String>,
+
+    /// Private key of `tls_cert`, when not in the certificate file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_key: Option<String>,
+}
+
+/// Repository-local git config a new clone is seeded with, so it is ready
+/// to commit on a CI runner or in a container
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IdentityProfile {
+    /// `user.name`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// `user.email`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+
+    /// `user.signingkey`: a GPG key ID, or an SSH key file with `gpg_format = "ssh"`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signing_key: Option<String>,
+
+    /// `gpg.format`: `openpgp`, `x509` or `ssh`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gpg_format: Option<String>,
+
+    /// `commit.gpgsign`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sign_commits: Option<bool>,
+
+    /// `tag.gpgsign`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sign_tags: Option<bool>,
+}
+
+impl IdentityProfile {
+    /// The git config entries the profile sets, in a fixed order
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        let mut entries = Vec::new();
+        let mut push = |key: &'static str, value: Option<String>| {
+            if let Some(value) = value {
+                entries.push((key, value));
+            }
+        };
+        push("user.name", self.name.clone());
+        push("user.email", self.email.clone());
+        push("user.signingkey", self.signing_key.clone());
+        push("gpg.format", self.gpg_format.clone());
+        push(
+            "commit.gpgsign",
+            self.sign_commits.map(|sign| sign.to_string()),
+        );
+        push("tag.gpgsign", self.sign_tags.map(|sign| sign.to_string()));
+        entries
+    }
+}
+
+/// The global config of this process, loaded once
+static GLOBAL: OnceLock<GlobalConfig> = OnceLock::new();
+
+impl GlobalConfig {
+    /// Location of the global config file, honoring `XDG_CONFIG_HOME`
+    pub fn path() -> Option<PathBuf> {
+        let base = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(base.join("git-partial").join("config.toml"))
+    }
+
+    /// Load the global config; a missing file means no defaults
+    pub fn load() -> Result<Self> {
+        match Self::path() {
+            Some(path) if path.exists() => Self::load_from(&path),
+            _ => Ok(GlobalConfig::default()),
+        }
+    }
+
+    fn load_from(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Write the global config, creating its directory
+    pub fn save(&self) -> Result<PathBuf> {
+        let path = Self::path().context("Cannot locate the config directory (HOME is not set)")?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let content = toml::to_string_pretty(self).context("Failed to serialize config")?;
+        fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// The partial clone filter for new clones
+    pub fn filter(&self) -> &str {
+        self.filter.as_deref().unwrap_or(DEFAULT_FILTER)
+    }
+}
+
+/// Partial clone filter given to `clone --filter`, overriding the global config
+static CLONE_FILTER: OnceLock<String> = OnceLock::new();
+
+/// Make new clones, and the remotes added to them, use `filter`
+pub fn set_clone_filter(filter: String) {
+    CLONE_FILTER.set(filter).ok();
+}
+
+/// The partial clone filter for new clones: `clone --filter`, else the
+/// global config's, else `blob:none`
+pub fn clone_filter() -> &'static str {
+    match CLONE_FILTER.get() {
+        Some(filter) => filter,
+        None => global().filter(),
+    }
+}
+
+/// Checks a partial clone filter given on the command line: `blob:none`,
+/// `blob:limit=<n>` with an optional `k`, `m` or `g` suffix, or `tree:<depth>`
+pub fn parse_filter(value: &str) -> Result<String, String> {
+    let valid = value == "blob:none"
+        || value
+            .strip_prefix("blob:limit=")
+            .is_some_and(|limit| parse_size(limit).is_some())
+        || value
+            .strip_prefix("tree:")
+            .is_some_and(|depth| depth.parse::<u64>().is_ok());
+    if valid {
+        Ok(value.to_string())
+    } else {
+        Err("expected blob:none, blob:limit=<size> (e.g. 1m) or tree:<depth>".to_string())
+    }
+}
+
+/// The size limit of a `blob:limit=<n>` filter, in bytes
+pub fn blob_limit(filter: &str) -> Option<u64> {
+    parse_size(filter.strip_prefix("blob:limit=")?)
+}
+
+/// Parses a size in git's notation: bytes, or `k`, `m` or `g` for powers of 1024
+fn parse_size(value: &str) -> Option<u64> {
+    let lower = value.to_ascii_lowercase();
+    let (number, unit) = match lower.strip_suffix(['k', 'm', 'g']) {
+        Some(number) => (number, &lower[number.len()..]),
+        None => (lower.as_str(), ""),
+    };
+    let factor = match unit {
+        "k" => 1 << 10,
+        "m" => 1 << 20,
+        "g" => 1 << 30,
+        _ => 1,
+    };
+    number.parse::<u64>().ok()?.checked_mul(factor)
+}
+
+/// Load the global config for this process, failing on a malformed file
+pub fn init_global() -> Result<&'static GlobalConfig> {
+    if let Some(config) = GLOBAL.get() {
+        return Ok(config);
+    }
+    let config = GlobalConfig::load()?;
+    Ok(GLOBAL.get_or_init(|| config))
+}
+
+/// The global config of this process; empty if it could not be loaded
+pub fn global() -> &'static GlobalConfig {
+    GLOBAL.get_or_init(|| GlobalConfig::load().unwrap_or_default())
+}
+
+/// A setting that can be given as a command line flag, a `GIT_PARTIAL_*`
+/// environment variable, a `gitpartial.*` git config key, or in the global
+/// config file, in that order of precedence. Git itself lets the
+/// repository's config override the global `~/.gitconfig`.
+pub struct Setting<T: 'static> {
+    /// Environment variable, e.g. `GIT_PARTIAL_JOBS`
+    pub env: &'static str,
+
+    /// Git config key, e.g. `gitpartial.jobs`
+    pub git_key: &'static str,
+
+    /// Reads the setting from the global config file
+    pub global: fn(&GlobalConfig) -> Option<T>,
+}
+
+/// Skip fetching in `status` and compare against the last fetched state
+pub const NO_FETCH_ON_STATUS: Setting<bool> = Setting {
+    env: "GIT_PARTIAL_NO_FETCH_ON_STATUS",
+    git_key: "gitpartial.noFetchOnStatus",
+    global: |config| config.no_fetch_on_status,
+};
+
+/// Narrow the fetch refspec of new clones to the current branch
+pub const NARROW_FETCH: Setting<bool> = Setting {
+    env: "GIT_PARTIAL_NARROW_FETCH",
+    git_key: "gitpartial.narrowFetch",
+    global: |config| config.narrow_fetch,
+};
+
+/// Number of parallel workers git uses to check out files
+pub const JOBS: Setting<usize> = Setting {
+    env: "GIT_PARTIAL_JOBS",
+    git_key: "gitpartial.jobs",
+    global: |config| config.jobs,
+};
+
+/// Output format of commands that support machine-readable output
+pub const FORMAT: Setting<OutputFormat> = Setting {
+    env: "GIT_PARTIAL_FORMAT",
+    git_key: "gitpartial.format",
+    global: |config| config.format,
+};
+
+/// How long the local objects of paths removed from the checkout are kept
+/// before `maintenance` or `watch` drops them
+pub const PRUNE_AFTER: Setting<Duration> = Setting {
+    env: "GIT_PARTIAL_PRUNE_AFTER",
+    git_key: "gitpartial.pruneAfter",
+    global: |config| {
+        config
+            .prune_after
+            .as_deref()
+            .and_then(Duration::parse_setting)
+    },
+};
+
+/// Largest checkout `clone` and `add-paths` accept without `--force`
+pub const MAX_CHECKOUT_SIZE: Setting<ByteSize> = Setting {
+    env: "GIT_PARTIAL_MAX_CHECKOUT_SIZE",
+    git_key: "gitpartial.maxCheckoutSize",
+    global: |config| config.max_checkout_size,
+};
+
+/// Most files `clone` and `add-paths` check out without `--force`
+pub const MAX_FILES: Setting<usize> = Setting {
+    env: "GIT_PARTIAL_MAX_FILES",
+    git_key: "gitpartial.maxFiles",
+    global: |config| config.max_files,
+};
+
+/// Editor command `open` launches
+pub const EDITOR: Setting<String> = Setting {
+    env: "GIT_PARTIAL_EDITOR",
+    git_key: "gitpartial.editor",
+    global: |config| config.editor.clone(),
+};
+
+/// Identity profile `clone` seeds the repository's git config from
+pub const IDENTITY: Setting<String> = Setting {
+    env: "GIT_PARTIAL_IDENTITY",
+    git_key: "gitpartial.identity",
+    global: |config| config.identity.clone(),
+};
+
+/// Client certificate `clone` and `smart-pull` present to HTTPS servers
+pub const TLS_CERT: Setting<String> = Setting {
+    env: "GIT_PARTIAL_TLS_CERT",
+    git_key: "gitpartial.tlsCert",
+    global: |config| config.tls_cert.clone(),
+};
+
+/// Private key of the client certificate
+pub const TLS_KEY: Setting<String> = Setting {
+    env: "GIT_PARTIAL_TLS_KEY",
+    git_key: "gitpartial.tlsKey",
+    global: |config| config.tls_key.clone(),
+};
+
+/// A size in bytes, written as a number or with git's `k`, `m` or `g`
+/// suffix, e.g. `2g`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "toml::Value", into = "toml::Value")]
+pub struct ByteSize(pub u64);
+
+impl TryFrom<toml::Value> for ByteSize {
+    type Error = String;
+
+    fn try_from(value: toml::Value) -> Result<Self, Self::Error> {
+        let parsed = match &value {
+            toml::Value::Integer(bytes) => u64::try_from(*bytes).ok(),
+            toml::Value::String(size) => parse_size(size),
+            _ => None,
+        };
+        parsed
+            .map(ByteSize)
+            .ok_or_else(|| format!("invalid size {}, expected e.g. 2g", value))
+    }
+}
+
+impl From<ByteSize> for toml::Value {
+    fn from(size: ByteSize) -> Self {
+        toml::Value::Integer(size.0 as i64)
+    }
+}
+
+/// When log output is colored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorChoice {
+    /// Only when writing to a terminal
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Output format of commands such as `status`
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Human readable text
+    #[default]
+    Text,
+
+    /// JSON, for scripts
+    Json,
+}
+
+/// A value a setting can hold, parsed from an environment variable or git config
+pub trait SettingValue: Sized {
+    fn parse_setting(value: &str) -> Option<Self>;
+}
+
+impl SettingValue for bool {
+    /// Accepts the boolean spellings git accepts
+    fn parse_setting(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Some(true),
+            "false" | "no" | "off" | "0" => Some(false),
+            _ => None,
+        }
+    }
+}
+
+impl SettingValue for String {
+    fn parse_setting(value: &str) -> Option<Self> {
+        Some(value.to_string()).filter(|value| !value.is_empty())
+    }
+}
+
+impl SettingValue for usize {
+    /// Accepts positive numbers only
+    fn parse_setting(value: &str) -> Option<Self> {
+        value.parse().ok().filter(|&n| n > 0)
+    }
+}
+
+impl SettingValue for ByteSize {
+    fn parse_setting(value: &str) -> Option<Self> {
+        parse_size(value).map(ByteSize)
+    }
+}
+
+impl SettingValue for OutputFormat {
+    fn parse_setting(value: &str) -> Option<Self> {
+        OutputFormat::from_str(value, true).ok()
+    }
+}
+
+impl SettingValue for Duration {
+    /// Accepts a number of seconds, minutes, hours, days or weeks, such as
+    /// `90m` or `14d`
+    fn parse_setting(value: &str) -> Option<Self> {
+        let unit = value.chars().last()?;
+        let seconds = match unit {
+            's' => 1,
+            'm' => 60,
+            'h' => 3_600,
+            'd' => 86_400,
+            'w' => 604_800,
+            _ => return None,
+        };
+        let number: u64 = value[..value.len() - 1].trim().parse().ok()?;
+        Some(Duration::from_secs(number.checked_mul(seconds)?))
+    }
+}
+
+/// Resolve a setting for the repository at `repo_path`; outside a
+/// repository only the global git config is read
+pub fn resolve<T: SettingValue>(
+    flag: Option<T>,
+    setting: &Setting<T>,
+    repo_path: &Path,
+) -> Result<Option<T>> {
+    if flag.is_some() {
+        return Ok(flag);
+    }
+    resolve_from(
+        None,
+        env::var(setting.env).ok(),
+        commands::config_value(repo_path, setting.git_key),
+        global(),
+        setting,
+    )
+}
+
+/// Pick the first value given, in order of precedence, and parse it
+fn resolve_from<T: SettingValue>(
+    flag: Option<T>,
+    env_value: Option<String>,
+    git_value: Option<String>,
+    global: &GlobalConfig,
+    setting: &Setting<T>,
+) -> Result<Option<T>> {
+    if flag.is_some() {
+        return Ok(flag);
+    }
+    let (value, source) = match (env_value.filter(|v| !v.is_empty()), git_value) {
+        (Some(value), _) => (value, setting.env),
+        (None, Some(value)) => (value, setting.git_key),
+        (None, None) => return Ok((setting.global)(global)),
+    };
+    match T::parse_setting(value.trim()) {
+        Some(parsed) => Ok(Some(parsed)),
+        None => anyhow::bail!("Invalid value '{}' for {}", value, source),
+    }
+}
+
+/// Whether `status` should skip fetching; `--no-fetch` forces it
+pub fn no_fetch_on_status(
+    flag: bool,
+    repo_path: &Path,
+) -> Result<bool> {
+    Ok(resolve(flag.then_some(true), &NO_FETCH_ON_STATUS, repo_path)?.unwrap_or(false))
+}
+
+/// Whether a new clone only fetches its current branch; `--narrow-fetch` forces it
+pub fn narrow_fetch(
+    flag: bool,
+    repo_path: &Path,
+) -> Result<bool> {
+    Ok(resolve(flag.then_some(true), &NARROW_FETCH, repo_path)?.unwrap_or(false))
+}
+
+/// Parallel checkout workers, or `None` to leave git's default
+pub fn jobs(
+    flag: Option<usize>,
+    repo_path: &Path,
+) -> Result<Option<usize>> {
+    resolve(flag, &JOBS, repo_path)
+}
+
+/// Output format, text unless configured otherwise
+pub fn output_format(
+    flag: Option<OutputFormat>,
+    repo_path: &Path,
+) -> Result<OutputFormat> {
+    Ok(resolve(flag, &FORMAT, repo_path)?.unwrap_or_default())
+}
+
+/// Retention period of removed paths' objects, or `None` to keep them
+pub fn prune_after(repo_path: &Path) -> Result<Option<Duration>> {
+    resolve(None, &PRUNE_AFTER, repo_path)
+}
+
+/// Largest checkout allowed without `--force`, in bytes
+pub fn max_checkout_size(repo_path: &Path) -> Result<Option<u64>> {
+    Ok(resolve(None, &MAX_CHECKOUT_SIZE, repo_path)?.map(|size| size.0))
+}
+
+/// Most files allowed in a checkout without `--force`
+pub fn max_files(repo_path: &Path) -> Result<Option<usize>> {
+    resolve(None, &MAX_FILES, repo_path)
+}
+
+/// The editor command for `open`: `flag`, else the `EDITOR` setting, else
+/// `$VISUAL` or `$EDITOR`, else VS Code's `code`
+pub fn editor(
+    flag: Option<String>,
+    repo_path: &Path,
+) -> Result<String> {
+    Ok(resolve(flag, &EDITOR, repo_path)?
+        .or_else(|| env::var("VISUAL").ok().filter(|editor| !editor.is_empty()))
+        .or_else(|| env::var("EDITOR").ok().filter(|editor| !editor.is_empty()))
+        .unwrap_or_else(|| "code".to_string()))
+}
+
+/// The identity profile to seed a new clone with, by name: `flag`, else
+/// the `IDENTITY` setting. Fails for a name missing from `[identities]`.
+pub fn identity_profile(
+    flag: Option<String>,
+    repo_path: &Path,
+) -> Result<Option<(String, IdentityProfile)>> {
+    let Some(name) = resolve(flag, &IDENTITY, repo_path)? else {
+        return Ok(None);
+    };
+    match global().identities.get(&name) {
+        Some(profile) => Ok(Some((name, profile.clone()))),
+        None => {
+            let defined: Vec<&str> = global().identities.keys().map(String::as_str).collect();
+            anyhow::bail!(
+                "No identity profile '{}' in the global config (defined: {})",
+                name,
+                if defined.is_empty() {
+                    "none".to_string()
+                } else {
+                    defined.join(", ")
+                }
+            )
+        }
+    }
+}
+
+/// The client certificate and key to present to HTTPS git servers: the
+/// flags, else the `TLS_CERT` and `TLS_KEY` settings
+pub fn tls_files(
+    cert: Option<String>,
+    key: Option<String>,
+    repo_path: &Path,
+) -> Result<(Option<String>, Option<String>)> {
+    Ok((
+        resolve(cert, &TLS_CERT, repo_path)?,
+        resolve(key, &TLS_KEY, repo_path)?,
+    ))
+}
+
+/// Git config key holding `NAME=value` entries for pattern variables
+const VARS_GIT_KEY: &str = "gitpartial.var";
+
+/// Replaces the `${NAME}` variables of `patterns`, e.g. `services/${TEAM}/**`.
+/// A variable is read from the environment variable `NAME`, else a
+/// `NAME=value` entry of the `gitpartial.var` git config key (the last one
+/// wins), else the global config's `[vars]`. `OS` and `ARCH` default to the
+/// platform's, such as `linux` and `x86_64`.
+pub fn substitute_variables(
+    patterns: &[String],
+    repo_path: &Path,
+) -> Result<Vec<String>> {
+    if !patterns.iter().any(|pattern| pattern.contains("${")) {
+        return Ok(patterns.to_vec());
+    }
+    let git_vars: BTreeMap<String, String> = commands::config_values(repo_path, VARS_GIT_KEY)
+        .iter()
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+    let lookup = |name: &str| {
+        env::var(name)
+            .ok()
+            .filter(|value| !value.is_empty())
+            .or_else(|| git_vars.get(name).cloned())
+            .or_else(|| global().vars.get(name).cloned())
+            .or_else(|| match name {
+                "OS" => Some(env::consts::OS.to_string()),
+                "ARCH" => Some(env::consts::ARCH.to_string()),
+                _ => None,
+            })
+    };
+
+    let mut substituted = Vec::new();
+    let mut undefined: Vec<String> = Vec::new();
+    for pattern in patterns {
+        match path_selector::substitute_variables(pattern, lookup) {
+            Ok(pattern) => substituted.push(pattern),
+            Err(names) => {
+                for name in names {
+                    if !undefined.contains(&name) {
+                        undefined.push(name);
+                    }
+                }
+            }
+        }
+    }
+    if !undefined.is_empty() {
+        return Err(PartialError::UndefinedVariables(undefined).into());
+    }
+    Ok(substituted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precedence() {
+        let env = || Some("4".to_string());
+        let git = || Some("2".to_string());
+        let global = GlobalConfig {
+            jobs: Some(1),
+            ..Default::default()
+        };
+        let empty = GlobalConfig::default();
+        let resolve = |flag, env, git, global| resolve_from(flag, env, git, global, &JOBS).unwrap();
+
+        assert_eq!(resolve(Some(8), env(), git(), &global), Some(8));
+        assert_eq!(resolve(None, env(), git(), &global), Some(4));
+        assert_eq!(resolve(None, None, git(), &global), Some(2));
+        assert_eq!(resolve(None, None, None, &global), Some(1));
+        assert_eq!(resolve(None, None, None, &empty), None);
+        // An empty variable counts as unset
+        assert_eq!(resolve(None, Some(String::new()), git(), &global), Some(2));
+    }
+
+    #[test]
+    fn test_invalid_value_names_its_source() {
+        let err = resolve_from(
+            None,
+            Some("many".to_string()),
+            None,
+            &GlobalConfig::default(),
+            &JOBS,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Invalid value 'many' for GIT_PARTIAL_JOBS");
+
+        let err = resolve_from(
+            None,
+            None,
+            Some("maybe".to_string()),
+            &GlobalConfig::default(),
+            &NO_FETCH_ON_STATUS,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid value 'maybe' for gitpartial.noFetchOnStatus"
+        );
+    }
+
+    #[test]
+    fn test_parse_values() {
+        assert_eq!(bool::parse_setting("Yes"), Some(true));
+        assert_eq!(bool::parse_setting("0"), Some(false));
+        assert_eq!(usize::parse_setting("0"), None);
+        assert_eq!(
+            OutputFormat::parse_setting("JSON"),
+            Some(OutputFormat::Json)
+        );
+        assert_eq!(OutputFormat::parse_setting("xml"), None);
+        assert_eq!(
+            Duration::parse_setting("14d"),
+            Some(Duration::from_secs(14 * 86_400))
+        );
+        assert_eq!(Duration::parse_setting("0h"), Some(Duration::from_secs(0)));
+        assert_eq!(Duration::parse_setting("14"), None);
+        assert_eq!(Duration::parse_setting("d"), None);
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        assert!(parse_filter("blob:none").is_ok());
+        assert!(parse_filter("blob:limit=512k").is_ok());
+        assert!(parse_filter("tree:0").is_ok());
+        assert!(parse_filter("blob:limit=big").is_err());
+        assert!(parse_filter("sparse:oid=abc").is_err());
+
+        assert_eq!(blob_limit("blob:limit=1m"), Some(1024 * 1024));
+        assert_eq!(blob_limit("blob:limit=2G"), Some(2 << 30));
+        assert_eq!(blob_limit("blob:limit=100"), Some(100));
+        assert_eq!(blob_limit("blob:none"), None);
+    }
+
+    #[test]
+    fn test_global_config_file() {
+        let config: GlobalConfig = toml::from_str(
+            r#"
+filter = "blob:limit=1m"
+cone = true
+color = "never"
+jobs = 8
+format = "json"
+max_checkout_size = "2g"
+max_files = 50000
+identity = "ci"
+tls_cert = "~/certs/me.pem"
+
+[identities.ci]
+name = "CI Bot"
+email = "ci@example.com"
+signing_key = "~/.ssh/ci.pub"
+gpg_format = "ssh"
+sign_commits = true
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.max_checkout_size, Some(ByteSize(2 << 30)));
+        assert_eq!(config.max_files, Some(50_000));
+        assert_eq!(config.identity.as_deref(), Some("ci"));
+        assert_eq!(config.tls_cert.as_deref(), Some("~/certs/me.pem"));
+        assert_eq!(config.tls_key, None);
+        assert_eq!(
+            config.identities["ci"].entries(),
+            vec![
+                ("user.name", "CI Bot".to_string()),
+                ("user.email", "ci@example.com".to_string()),
+                ("user.signingkey", "~/.ssh/ci.pub".to_string()),
+                ("gpg.format", "ssh".to_string()),
+                ("commit.gpgsign", "true".to_string()),
+            ]
+        );
+        let sized: GlobalConfig = toml::from_str("max_checkout_size = 1024").unwrap();
+        assert_eq!(sized.max_checkout_size, Some(ByteSize(1024)));
+        assert!(toml::from_str::<GlobalConfig>("max_checkout_size = \"huge\"").is_err());
+        assert_eq!(config.filter(), "blob:limit=1m");
+        assert_eq!(config.color, Some(ColorChoice::Never));
+        assert_eq!(config.format, Some(OutputFormat::Json));
+        assert_eq!(GlobalConfig::default().filter(), DEFAULT_FILTER);
+
+        assert!(toml::from_str::<GlobalConfig>("colour = \"never\"").is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::incoming::IncomingSummary;
+
+/// `git log` format read by [`parse_log`]
+pub const LOG_FORMAT: &str = "%h%x09%an%x09%s";
+
+/// An upstream commit touching the sparse paths
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigestCommit {
+    /// Abbreviated commit id
+    pub id: String,
+    pub author: String,
+    pub subject: String,
+}
+
+/// Upstream commits touching the sparse paths since the previous digest
+#[derive(Debug)]
+pub struct Digest {
+    /// The repository's remote URL
+    pub repository: String,
+
+    /// The upstream branch, e.g. `origin/main`
+    pub upstream: String,
+
+    /// Newest first, as `git log` lists them
+    pub commits: Vec<DigestCommit>,
+
+    pub summary: IncomingSummary,
+}
+
+impl Digest {
+    /// The email subject and the first line of the Slack message
+    pub fn title(&self) -> String {
+        format!(
+            "git-partial digest: {} commit(s) on {} touch the sparse paths",
+            self.commits.len(),
+            self.upstream
+        )
+    }
+
+    /// The plain-text report: the commits, then the changes per pattern
+    pub fn text(&self) -> String {
+        let mut text = format!("Repository: {}\n\n", self.repository);
+        for commit in &self.commits {
+            text.push_str(&format!(
+                "  {} {} ({})\n",
+                commit.id, commit.subject, commit.author
+            ));
+        }
+        text.push('\n');
+        text.push_str(&self.summary.to_string());
+        text
+    }
+
+    /// The body of a Slack incoming webhook
+    pub fn slack_payload(&self) -> serde_json::Value {
+        json!({
+            "text": format!("*{}*\n```\n{}```", self.title(), self.text()),
+        })
+    }
+}
+
+/// Parses `git log --format=<LOG_FORMAT>`
+pub fn parse_log(output: &str) -> Vec<DigestCommit> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            Some(DigestCommit {
+                id: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                subject: fields.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Where the previous digest stopped, stored in `.gitpartial/digest.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DigestState {
+    /// The upstream commit the previous digest covered up to
+    pub last_sent: String,
+}
+
+impl DigestState {
+    /// Loads the state, `None` before the first digest
+    pub fn load<P: AsRef<Path>>(repo_path: P) -> Result<Option<Self>> {
+        let path = Self::state_path(&repo_path);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read digest state from {:?}", path))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .context("Failed to deserialize digest state")
+    }
+
+    /// Saves the state next to the metadata
+    pub fn save<P: AsRef<Path>>(
+        &self,
+        repo_path: P,
+    ) -> Result<()> {
+        let path = Self::state_path(&repo_path);
+        let serialized =
+            serde_json::to_string_pretty(self).context("Failed to serialize digest state")?;
+        fs::write(&path, serialized)
+            .with_context(|| format!("Failed to write digest state to {:?}", path))
+    }
+
+    /// Returns the path to the state file
+    fn state_path<P: AsRef<Path>>(repo_path: P) -> PathBuf {
+        repo_path.as_ref().join(".gitpartial").join("digest.json")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::incoming;
+
+    #[test]
+    fn test_digest() {
+        let commits = parse_log("def5678\tBob\tFix the parser\nabc1234\tAlice Smith\tAdd\ta tab\n");
+        assert_eq!(
+            commits[1],
+            DigestCommit {
+                id: "abc1234".to_string(),
+                author: "Alice Smith".to_string(),
+                subject: "Add\ta tab".to_string(),
+            }
+        );
+
+        let changes = incoming::parse_name_status("M\0src/app.rs\0A\0docs/new.md");
+        let digest = Digest {
+            repository: "https://example.com/repo.git".to_string(),
+            upstream: "origin/main".to_string(),
+            commits,
+            summary: incoming::summarize(&changes, &["src/**".to_string()], None),
+        };
+        assert_eq!(
+            digest.title(),
+            "git-partial digest: 2 commit(s) on origin/main touch the sparse paths"
+        );
+        assert_eq!(
+            digest.text(),
+            "Repository: https://example.com/repo.git\n\n  \
+             def5678 Fix the parser (Bob)\n  \
+             abc1234 Add\ta tab (Alice Smith)\n\n\
+             Incoming changes in the sparse paths:\n  \
+             src/**  0 added, 1 modified, 0 deleted\n\
+             1 changed file(s) outside the sparse paths are not checked out.\n"
+        );
+    }
+
+    #[test]
+    fn test_digest_state_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".gitpartial")).unwrap();
+        assert_eq!(DigestState::load(dir.path()).unwrap(), None);
+
+        let state = DigestState {
+            last_sent: "abc1234".to_string(),
+        };
+        state.save(dir.path()).unwrap();
+        assert_eq!(DigestState::load(dir.path()).unwrap(), Some(state));
+    }
+}
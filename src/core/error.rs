@@ -0,0 +1,257 @@
+use std::fmt;
+
+/// Common failure modes with a short cause and a remediation hint
+#[derive(Debug, Clone, PartialEq)]
+pub enum PartialError {
+    /// `.gitpartial/metadata.json` does not exist
+    MetadataNotFound,
+
+    /// The repository is not configured for sparse checkout
+    SparseCheckoutDisabled,
+
+    /// Patterns that do not match any path at HEAD
+    PatternMatchedNothing(Vec<String>),
+
+    /// The local branch cannot be fast-forwarded to its remote
+    Diverged { branch: String, upstream: String },
+
+    /// Staged changes to paths outside the sparse patterns
+    StagedOutsideSparse(Vec<String>),
+
+    /// Pinned paths that an operation would drop
+    PinnedPaths(Vec<String>),
+
+    /// Files of a path pinned at a commit were edited since
+    PinnedPathChanged { path: String, commit: String },
+
+    /// Staged changes to paths pinned at a commit
+    StagedPinned(Vec<String>),
+
+    /// Rules of the organization's policy that the sparse paths break
+    PolicyViolations(Vec<String>),
+
+    /// Configured checkout size or file count limits the sparse paths exceed
+    CheckoutTooLarge(Vec<String>),
+
+    /// Another git-partial process holds `.gitpartial/lock`
+    Locked { pid: Option<u32> },
+
+    /// The server would ignore `--filter` and send every blob
+    FilterUnsupported { url: String },
+
+    /// `${NAME}` variables of path patterns that have no value
+    UndefinedVariables(Vec<String>),
+
+    /// An operation recorded in `.gitpartial/journal.json` did not complete
+    IncompleteOperation { operation: String },
+}
+
+impl PartialError {
+    /// Returns a command the user can try to fix the problem
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            PartialError::MetadataNotFound => {
+                Some("git-partial clone <repo_url> <destination> --paths <path>...".to_string())
+            }
+            PartialError::SparseCheckoutDisabled => Some(
+                "git-partial clone <repo_url> <destination> --paths <path>... (to start over with sparse checkout)"
+                    .to_string(),
+            ),
+            PartialError::PatternMatchedNothing(_) => {
+                Some("git ls-tree -r --name-only HEAD (to list available paths)".to_string())
+            }
+            PartialError::Diverged { .. } => {
+                Some("git-partial smart-pull --rebase (or --merge)".to_string())
+            }
+            PartialError::StagedOutsideSparse(_) => Some(
+                "git restore --staged <path>, or git commit --no-verify if the change is intended"
+                    .to_string(),
+            ),
+            PartialError::PinnedPaths(paths) => Some(format!(
+                "git-partial unpin {}, or repeat the command with --force",
+                paths.join(" ")
+            )),
+            PartialError::PinnedPathChanged { path, commit } => Some(format!(
+                "git restore --source={} --worktree -- '{}' (to discard the edits), or git-partial unpin {}",
+                commit, path, path
+            )),
+            PartialError::StagedPinned(_) => Some(
+                "git restore --staged <path>, or git-partial unpin <pinned path> first".to_string(),
+            ),
+            PartialError::PolicyViolations(_) => Some(
+                "change the paths, or repeat the command with --override-policy if your organization allows it"
+                    .to_string(),
+            ),
+            PartialError::CheckoutTooLarge(_) => Some(
+                "narrow the paths, or repeat the command with --force".to_string(),
+            ),
+            PartialError::Locked { .. } => Some(
+                "repeat the command without --no-wait to wait, or remove .gitpartial/lock if no git-partial is running"
+                    .to_string(),
+            ),
+            PartialError::FilterUnsupported { .. } => Some(
+                "git config uploadpack.allowFilter true (on the server), or clone with plain git"
+                    .to_string(),
+            ),
+            PartialError::UndefinedVariables(names) => Some(format!(
+                "export {}=<value>, or git config --add gitpartial.var {}=<value>",
+                names[0], names[0]
+            )),
+            PartialError::IncompleteOperation { .. } => Some(
+                "git-partial recover (to finish it), or git-partial recover --rollback (to undo it)"
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for PartialError {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            PartialError::MetadataNotFound => {
+                write!(
+                    f,
+                    "Not a git-partial repository (.gitpartial/metadata.json not found)"
+                )
+            }
+            PartialError::SparseCheckoutDisabled => {
+                write!(f, "This repository is not using sparse checkout")
+            }
+            PartialError::PatternMatchedNothing(patterns) => {
+                write!(
+                    f,
+                    "Pattern matched no files in the repository: {}",
+                    patterns.join(", ")
+                )
+            }
+            PartialError::Diverged { branch, upstream } => {
+                write!(
+                    f,
+                    "Branch '{}' has diverged from {} and cannot be fast-forwarded",
+                    branch, upstream
+                )
+            }
+            PartialError::StagedOutsideSparse(paths) => {
+                write!(
+                    f,
+                    "{} staged path(s) fall outside the sparse checkout: {}",
+                    paths.len(),
+                    paths.iter().take(5).cloned().collect::<Vec<_>>().join(", ")
+                )?;
+                if paths.len() > 5 {
+                    write!(f, ", ...")?;
+                }
+                Ok(())
+            }
+            PartialError::PinnedPaths(paths) => {
+                write!(f, "Refusing to drop pinned path(s): {}", paths.join(", "))
+            }
+            PartialError::PinnedPathChanged { path, commit } => {
+                write!(
+                    f,
+                    "Files of {} changed since it was pinned at {}",
+                    path,
+                    &commit[..commit.len().min(7)]
+                )
+            }
+            PartialError::StagedPinned(paths) => {
+                write!(
+                    f,
+                    "{} staged path(s) are pinned at a commit, and committing them would move the branch to the pinned versions: {}",
+                    paths.len(),
+                    paths.iter().take(5).cloned().collect::<Vec<_>>().join(", ")
+                )?;
+                if paths.len() > 5 {
+                    write!(f, ", ...")?;
+                }
+                Ok(())
+            }
+            PartialError::PolicyViolations(violations) => {
+                write!(f, "The paths violate the repository policy:")?;
+                for violation in violations {
+                    write!(f, "\n  - {}", violation)?;
+                }
+                Ok(())
+            }
+            PartialError::CheckoutTooLarge(limits) => {
+                write!(f, "The paths exceed the checkout limits:")?;
+                for limit in limits {
+                    write!(f, "\n  - {}", limit)?;
+                }
+                Ok(())
+            }
+            PartialError::Locked { pid: Some(pid) } => {
+                write!(f, "The repository is locked by git-partial (pid {})", pid)
+            }
+            PartialError::Locked { pid: None } => {
+                write!(f, "The repository is locked by another git-partial")
+            }
+            PartialError::FilterUnsupported { url } => {
+                write!(
+                    f,
+                    "{} does not support partial clone filters; the clone would download every file",
+                    url
+                )
+            }
+            PartialError::UndefinedVariables(names) => {
+                write!(
+                    f,
+                    "Undefined variable(s) in the path patterns: {}",
+                    names.join(", ")
+                )
+            }
+            PartialError::IncompleteOperation { operation } => {
+                write!(f, "An earlier {} did not complete", operation)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PartialError {}
+
+/// Prints an error for the user, with a hint when the cause is known
+pub fn report(err: &anyhow::Error) {
+    match err.downcast_ref::<PartialError>() {
+        Some(partial_err) => {
+            eprintln!("error: {}", partial_err);
+            if let Some(hint) = partial_err.hint() {
+                eprintln!("  try: {}", hint);
+            }
+        }
+        None => eprintln!("error: {:#}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_display_and_hint() {
+        let err = PartialError::Diverged {
+            branch: "main".to_string(),
+            upstream: "origin/main".to_string(),
+        };
+
+        assert!(err.to_string().contains("'main' has diverged"));
+        assert_eq!(
+            err.hint(),
+            Some("git-partial smart-pull --rebase (or --merge)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_downcast_through_context() {
+        let result: anyhow::Result<()> = Err(PartialError::MetadataNotFound.into());
+        let err = result.context("Failed to load metadata").unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<PartialError>(),
+            Some(&PartialError::MetadataNotFound)
+        );
+    }
+}
@@ -0,0 +1,87 @@
+/// Refspec of a clone that fetches every branch of `origin`
+pub const WIDE: &str = "+refs/heads/*:refs/remotes/origin/*";
+
+/// Where `origin`'s branches are tracked
+const TRACKING_PREFIX: &str = "refs/remotes/origin/";
+
+/// The refspec fetching only `branch` from `origin`
+pub fn branch_refspec(branch: &str) -> String {
+    format!("+refs/heads/{}:{}{}", branch, TRACKING_PREFIX, branch)
+}
+
+/// The branches a narrowed refspec fetches: the `current` one first, then
+/// the `requested` ones, sorted and without repeats
+pub fn branches(
+    current: Option<&str>,
+    requested: &[String],
+) -> Vec<String> {
+    let mut others: Vec<String> = requested
+        .iter()
+        .filter(|branch| Some(branch.as_str()) != current)
+        .cloned()
+        .collect();
+    others.sort();
+    others.dedup();
+    current
+        .map(str::to_string)
+        .into_iter()
+        .chain(others)
+        .collect()
+}
+
+/// The remote-tracking refs among `tracking` that a refspec fetching only
+/// `branches` no longer updates. `origin/HEAD` is kept.
+pub fn stale_refs<'a>(
+    tracking: &'a [String],
+    branches: &[String],
+) -> Vec<&'a str> {
+    tracking
+        .iter()
+        .filter(|name| {
+            name.strip_prefix(TRACKING_PREFIX)
+                .is_some_and(|branch| branch != "HEAD" && !branches.iter().any(|b| b == branch))
+        })
+        .map(String::as_str)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_branches() {
+        let requested = strings(&["release/2", "main", "feature/x", "release/2"]);
+        assert_eq!(
+            branches(Some("main"), &requested),
+            strings(&["main", "feature/x", "release/2"])
+        );
+        assert_eq!(
+            branches(None, &requested),
+            strings(&["feature/x", "main", "release/2"])
+        );
+        assert_eq!(
+            branch_refspec("feature/x"),
+            "+refs/heads/feature/x:refs/remotes/origin/feature/x"
+        );
+    }
+
+    #[test]
+    fn test_stale_refs() {
+        let tracking = strings(&[
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/main",
+            "refs/remotes/origin/feature/x",
+            "refs/remotes/origin/old",
+            "refs/remotes/upstream/old",
+        ]);
+        assert_eq!(
+            stale_refs(&tracking, &strings(&["main", "feature/x"])),
+            vec!["refs/remotes/origin/old"]
+        );
+    }
+}
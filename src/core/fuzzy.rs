@@ -0,0 +1,109 @@
+/// Bonus for a query character matching at the start of a path segment or word
+const BOUNDARY_BONUS: i64 = 16;
+
+/// Bonus for a query character matching right after the previous one
+const CONSECUTIVE_BONUS: i64 = 8;
+
+/// Bonus for a query character matching in the last path segment
+const BASENAME_BONUS: i64 = 4;
+
+/// Scores how well `candidate` matches `query`, or `None` when the query
+/// characters do not all appear in order (case-insensitively).
+/// Higher is better: matches at word starts, in runs and in the file name
+/// score more, and skipped characters cost a little.
+pub fn score(
+    query: &str,
+    candidate: &str,
+) -> Option<i64> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let chars: Vec<char> = candidate.chars().collect();
+    let basename_start = chars.iter().rposition(|&c| c == '/').map_or(0, |i| i + 1);
+
+    let mut total = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query {
+        let position = (next..chars.len()).find(|&i| chars[i].to_lowercase().eq([wanted]))?;
+        total += 1;
+        let at_boundary = position == 0
+            || matches!(chars[position - 1], '/' | '_' | '-' | '.' | ' ')
+            || (chars[position].is_uppercase() && chars[position - 1].is_lowercase());
+        if at_boundary {
+            total += BOUNDARY_BONUS;
+        }
+        if previous.is_some_and(|previous| previous + 1 == position) {
+            total += CONSECUTIVE_BONUS;
+        }
+        if position >= basename_start {
+            total += BASENAME_BONUS;
+        }
+        total -= (position - next) as i64;
+        previous = Some(position);
+        next = position + 1;
+    }
+    Some(total)
+}
+
+/// Returns the candidates matching the query, best first. Ties go to the
+/// shorter path, then to alphabetical order.
+pub fn rank<'a, I>(
+    query: &str,
+    candidates: I,
+) -> Vec<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut scored: Vec<(i64, &str)> = candidates
+        .into_iter()
+        .filter_map(|candidate| score(query, candidate).map(|score| (score, candidate)))
+        .collect();
+    scored.sort_by(|(score_a, a), (score_b, b)| {
+        score_b
+            .cmp(score_a)
+            .then(a.len().cmp(&b.len()))
+            .then(a.cmp(b))
+    });
+    scored.into_iter().map(|(_, candidate)| candidate).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_requires_ordered_characters() {
+        assert!(score("btn", "src/components/Button.tsx").is_some());
+        assert!(score("BTN", "src/components/button.tsx").is_some());
+        assert!(score("tsxbtn", "src/components/Button.tsx").is_none());
+        assert!(score("xyz", "src/main.rs").is_none());
+    }
+
+    #[test]
+    fn test_rank_prefers_file_names_and_word_starts() {
+        let candidates = [
+            "docs/payment-guide.md",
+            "services/payments/api.rs",
+            "lib/unpaid/amounts.rs",
+        ];
+        assert_eq!(
+            rank("payments", candidates),
+            vec!["services/payments/api.rs"]
+        );
+        assert_eq!(
+            rank("pay", candidates),
+            vec!["docs/payment-guide.md", "services/payments/api.rs"]
+        );
+    }
+
+    #[test]
+    fn test_rank_breaks_ties_by_length() {
+        assert_eq!(
+            rank("readme", ["docs/README.md", "README.md"]),
+            vec!["README.md", "docs/README.md"]
+        );
+    }
+}
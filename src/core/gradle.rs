@@ -0,0 +1,316 @@
+use anyhow::Result;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::sync::OnceLock;
+use tracing::debug;
+
+use crate::core::path_selector::join_dir;
+use crate::core::workspace::{Project, ProjectSelection};
+
+/// Settings scripts, Groovy or Kotlin
+pub const SETTINGS_FILES: [&str; 2] = ["settings.gradle", "settings.gradle.kts"];
+
+/// Build scripts of a project, Groovy or Kotlin
+const BUILD_FILES: [&str; 2] = ["build.gradle", "build.gradle.kts"];
+
+/// Files at the root that configure the build and the wrapper
+const ROOT_FILES: [&str; 10] = [
+    "settings.gradle",
+    "settings.gradle.kts",
+    "build.gradle",
+    "build.gradle.kts",
+    "gradle.properties",
+    "gradlew",
+    "gradlew.bat",
+    "gradle/wrapper/gradle-wrapper.jar",
+    "gradle/wrapper/gradle-wrapper.properties",
+    "gradle/libs.versions.toml",
+];
+
+fn regex(
+    cell: &'static OnceLock<Regex>,
+    pattern: &str,
+) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("valid regex"))
+}
+
+/// The projects a settings script includes, as `(path, directory)` pairs
+/// such as `(":payments:core", "payments/core")`. Directories set with
+/// `project(":x").projectDir = file("...")` override the default one.
+pub fn parse_settings(content: &str) -> Vec<(String, String)> {
+    static INCLUDE: OnceLock<Regex> = OnceLock::new();
+    static STRING: OnceLock<Regex> = OnceLock::new();
+    static PROJECT_DIR: OnceLock<Regex> = OnceLock::new();
+
+    let content = strip_comments(content);
+    let string = regex(&STRING, r#"["']([^"']+)["']"#);
+    let mut projects: Vec<(String, String)> = Vec::new();
+    for captures in regex(&INCLUDE, r"(?m)\binclude\b\s*\(?([^)\n]*)").captures_iter(&content) {
+        for name in string.captures_iter(&captures[1]) {
+            let path = format!(":{}", name[1].trim_start_matches(':'));
+            if projects.iter().all(|(existing, _)| *existing != path) {
+                let dir = path.trim_start_matches(':').replace(':', "/");
+                projects.push((path, dir));
+            }
+        }
+    }
+
+    let project_dir = regex(
+        &PROJECT_DIR,
+        r#"project\(\s*["'](:?[^"']+)["']\s*\)\.projectDir\s*=\s*(?:file\(|new\s+File\(\s*(?:rootDir|settingsDir)\s*,)\s*["']([^"']+)["']"#,
+    );
+    for captures in project_dir.captures_iter(&content) {
+        let path = format!(":{}", captures[1].trim_start_matches(':'));
+        let Some(dir) = join_dir("", &captures[2]) else {
+            continue;
+        };
+        if let Some(project) = projects.iter_mut().find(|(existing, _)| *existing == path) {
+            project.1 = dir;
+        }
+    }
+    projects
+}
+
+/// The builds a settings script includes with `includeBuild`, such as a
+/// `build-logic` directory of convention plugins
+fn included_builds(settings: &str) -> Vec<String> {
+    static INCLUDE_BUILD: OnceLock<Regex> = OnceLock::new();
+    regex(&INCLUDE_BUILD, r#"\bincludeBuild\s*\(?\s*["']([^"']+)["']"#)
+        .captures_iter(&strip_comments(settings))
+        .filter_map(|captures| join_dir("", &captures[1]))
+        .collect()
+}
+
+/// Resolves the project in `module_dir` to the directories of the projects
+/// it depends on, transitively, with `project(":x")` or a type-safe
+/// `projects.x` accessor. Gradle configures every project the settings
+/// include, so the other `modules`' build scripts are added as files, along
+/// with the root scripts, the wrapper, and the directories of `buildSrc`
+/// and included builds. `read_file` returns the content of a file at the
+/// inspected revision.
+pub fn select_module<F>(
+    module_dir: &str,
+    modules: &[Project],
+    read_file: F,
+) -> Result<ProjectSelection>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let read_build_script = |dir: &str| {
+        BUILD_FILES
+            .iter()
+            .filter_map(|file| join_dir(dir, file))
+            .find_map(|path| read_file(&path))
+    };
+    let dirs_by_reference: BTreeMap<String, &str> = modules
+        .iter()
+        .flat_map(|module| {
+            [
+                (module.name.clone(), module.dir.as_str()),
+                (accessor(&module.name), module.dir.as_str()),
+            ]
+        })
+        .collect();
+
+    let mut dirs = BTreeSet::new();
+    let mut queue = VecDeque::from([module_dir.to_string()]);
+    while let Some(dir) = queue.pop_front() {
+        if dirs.contains(&dir) {
+            continue;
+        }
+        // A project without a build script is valid, it just has no dependencies
+        if let Some(script) = read_build_script(&dir) {
+            queue.extend(
+                project_references(&script)
+                    .iter()
+                    .filter_map(|reference| dirs_by_reference.get(reference))
+                    .map(|dir| dir.to_string()),
+            );
+        }
+        dirs.insert(dir);
+    }
+
+    let mut selection = ProjectSelection {
+        files: ROOT_FILES.iter().map(|file| file.to_string()).collect(),
+        ..ProjectSelection::default()
+    };
+    for module in modules.iter().filter(|module| !dirs.contains(&module.dir)) {
+        // Gradle also configures the intermediate projects of a nested path
+        let mut dir = module.dir.as_str();
+        loop {
+            selection
+                .files
+                .extend(BUILD_FILES.iter().filter_map(|file| join_dir(dir, file)));
+            match dir.rsplit_once('/') {
+                Some((parent, _)) => dir = parent,
+                None => break,
+            }
+        }
+    }
+    if read_build_script("buildSrc").is_some() {
+        dirs.insert("buildSrc".to_string());
+    }
+    for settings in SETTINGS_FILES.iter().filter_map(|file| read_file(file)) {
+        dirs.extend(included_builds(&settings));
+    }
+    dirs.remove("");
+    selection.dirs = dirs;
+    debug!("Selected Gradle project: {:?}", selection);
+    Ok(selection)
+}
+
+/// The projects a build script depends on, as `:a:b` paths or
+/// `projects.a.b` accessors
+fn project_references(script: &str) -> Vec<String> {
+    static PROJECT: OnceLock<Regex> = OnceLock::new();
+    static ACCESSOR: OnceLock<Regex> = OnceLock::new();
+
+    let script = strip_comments(script);
+    let mut references: Vec<String> = regex(
+        &PROJECT,
+        r#"\bproject\s*\(\s*(?:path\s*[:=]\s*)?["'](:[^"']*)["']"#,
+    )
+    .captures_iter(&script)
+    .map(|captures| captures[1].to_string())
+    .collect();
+    references.extend(
+        regex(&ACCESSOR, r"\bprojects((?:\.[A-Za-z_][A-Za-z0-9_]*)+)")
+            .captures_iter(&script)
+            .map(|captures| format!("projects{}", &captures[1])),
+    );
+    references
+}
+
+/// The type-safe accessor of a project path: `:payments:card-core` is
+/// `projects.payments.cardCore`
+fn accessor(path: &str) -> String {
+    let mut accessor = "projects".to_string();
+    for segment in path.split(':').filter(|segment| !segment.is_empty()) {
+        accessor.push('.');
+        let mut upper = false;
+        for c in segment.chars() {
+            if c == '-' || c == '_' {
+                upper = true;
+            } else if upper {
+                accessor.extend(c.to_uppercase());
+                upper = false;
+            } else {
+                accessor.push(c);
+            }
+        }
+    }
+    accessor
+}
+
+/// Drops `//` and `/* */` comments, keeping `//` inside strings such as URLs
+fn strip_comments(content: &str) -> String {
+    static COMMENT: OnceLock<Regex> = OnceLock::new();
+    regex(
+        &COMMENT,
+        r#"(?s)/\*.*?\*/|//[^\n]*|("(?:[^"\\\n]|\\.)*"|'(?:[^'\\\n]|\\.)*')"#,
+    )
+    .replace_all(content, |captures: &regex::Captures| {
+        captures
+            .get(1)
+            .map_or(String::new(), |string| string.as_str().to_string())
+    })
+    .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::workspace::WorkspaceKind;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_settings() {
+        let settings = r#"
+rootProject.name = "shop"
+include ':app', ":payments:core"
+include("payments:api")
+// include ':legacy'
+project(':payments:api').projectDir = file('services/payments-api')
+"#;
+        assert_eq!(
+            parse_settings(settings),
+            vec![
+                (":app".to_string(), "app".to_string()),
+                (":payments:core".to_string(), "payments/core".to_string()),
+                (
+                    ":payments:api".to_string(),
+                    "services/payments-api".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_project_references() {
+        let script = r#"
+dependencies {
+    implementation(project(":payments:core"))
+    testImplementation project(path: ':testing', configuration: 'default')
+    api(projects.cardCore)
+    // implementation(project(":legacy"))
+}
+"#;
+        assert_eq!(
+            project_references(script),
+            vec![":payments:core", ":testing", "projects.cardCore"]
+        );
+        assert_eq!(
+            accessor(":payments:card-core"),
+            "projects.payments.cardCore"
+        );
+    }
+
+    #[test]
+    fn test_select_module() {
+        let files = HashMap::from([
+            ("settings.gradle.kts", r#"includeBuild("build-logic")"#),
+            (
+                "app/build.gradle.kts",
+                r#"dependencies { implementation(projects.payments.core) }"#,
+            ),
+            (
+                "payments/core/build.gradle.kts",
+                r#"dependencies { implementation(project(":common")) }"#,
+            ),
+            ("common/build.gradle.kts", ""),
+            ("admin/build.gradle.kts", ""),
+            ("gradlew", ""),
+        ]);
+        let modules: Vec<Project> = [
+            (":app", "app"),
+            (":payments:core", "payments/core"),
+            (":common", "common"),
+            (":admin", "admin"),
+        ]
+        .iter()
+        .map(|(name, dir)| Project {
+            name: name.to_string(),
+            dir: dir.to_string(),
+            kind: WorkspaceKind::Gradle,
+        })
+        .collect();
+
+        let selection = select_module("app", &modules, |path| {
+            files.get(path).map(|content| content.to_string())
+        })
+        .unwrap();
+
+        assert_eq!(
+            selection.patterns(|file| files.contains_key(file)),
+            vec![
+                "app/**",
+                "build-logic/**",
+                "common/**",
+                "payments/core/**",
+                "admin/build.gradle.kts",
+                "gradlew",
+                "settings.gradle.kts",
+            ]
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::core::path_selector;
+
+/// A change to the sparse paths, one line of `.gitpartial/history.jsonl`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+
+    /// The git-partial command line that made the change
+    pub command: String,
+
+    /// Patterns added to the checkout
+    #[serde(default)]
+    pub added: Vec<String>,
+
+    /// Patterns removed from the checkout
+    #[serde(default)]
+    pub removed: Vec<String>,
+
+    /// HEAD after the change
+    pub commit: Option<String>,
+}
+
+impl HistoryEntry {
+    /// Checks if the entry added or removed `path`, either as the pattern
+    /// itself or as a pattern that covers the file or directory
+    pub fn touches(
+        &self,
+        path: &str,
+    ) -> bool {
+        self.added.iter().chain(&self.removed).any(|pattern| {
+            pattern == path
+                || path_selector::pattern_covers(&path_selector::compile_sparse(pattern), path)
+        })
+    }
+
+    /// The subcommand name, e.g. `add-paths`
+    pub fn subcommand(&self) -> &str {
+        self.command.split_whitespace().next().unwrap_or("")
+    }
+}
+
+/// Appends an entry to the history file
+pub fn append<P: AsRef<Path>>(
+    repo_path: P,
+    entry: &HistoryEntry,
+) -> Result<()> {
+    let history_path = history_path(&repo_path);
+    let mut line = serde_json::to_string(entry).context("Failed to serialize history entry")?;
+    line.push('\n');
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&history_path)
+        .with_context(|| format!("Failed to open {:?}", history_path))?;
+    file.write_all(line.as_bytes())
+        .with_context(|| format!("Failed to write {:?}", history_path))
+}
+
+/// Loads every entry, oldest first. Unreadable lines are skipped.
+pub fn load<P: AsRef<Path>>(repo_path: P) -> Result<Vec<HistoryEntry>> {
+    let history_path = history_path(&repo_path);
+    if !history_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&history_path)
+        .with_context(|| format!("Failed to read {:?}", history_path))?;
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            serde_json::from_str(line)
+                .inspect_err(|e| warn!("Skipping unreadable history line: {}", e))
+                .ok()
+        })
+        .collect())
+}
+
+/// The patterns removed at least `retention` seconds before `now` and not
+/// added back since, leaving out those still in the `current` sparse paths
+pub fn expired_removals(
+    entries: &[HistoryEntry],
+    current: &[String],
+    now: u64,
+    retention: u64,
+) -> Vec<String> {
+    let mut removed_at: BTreeMap<&str, u64> = BTreeMap::new();
+    for entry in entries {
+        for pattern in &entry.added {
+            removed_at.remove(pattern.as_str());
+        }
+        for pattern in &entry.removed {
+            removed_at.insert(pattern, entry.timestamp);
+        }
+    }
+    removed_at
+        .into_iter()
+        .filter(|(pattern, timestamp)| {
+            timestamp.saturating_add(retention) <= now && !current.iter().any(|p| p == pattern)
+        })
+        .map(|(pattern, _)| pattern.to_string())
+        .collect()
+}
+
+/// Formats Unix seconds as `YYYY-MM-DD HH:MM:SS UTC`
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let seconds = timestamp % 86_400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
+
+/// Returns the path to the history file
+fn history_path<P: AsRef<Path>>(repo_path: P) -> PathBuf {
+    repo_path.as_ref().join(".gitpartial").join("history.jsonl")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        command: &str,
+        added: &[&str],
+    ) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: 0,
+            command: command.to_string(),
+            added: added.iter().map(|p| p.to_string()).collect(),
+            removed: Vec::new(),
+            commit: None,
+        }
+    }
+
+    #[test]
+    fn test_touches() {
+        let entry = entry("add-paths data/** README.md", &["data/**", "README.md"]);
+
+        assert!(entry.touches("data/**"));
+        assert!(entry.touches("data/raw/input.csv"));
+        assert!(entry.touches("README.md"));
+        assert!(!entry.touches("src/main.rs"));
+        assert_eq!(entry.subcommand(), "add-paths");
+    }
+
+    #[test]
+    fn test_append_and_load() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join(".gitpartial")).unwrap();
+        assert!(load(temp_dir.path()).unwrap().is_empty());
+
+        append(temp_dir.path(), &entry("clone", &["src/**"])).unwrap();
+        append(temp_dir.path(), &entry("add-paths docs/**", &["docs/**"])).unwrap();
+
+        let entries = load(temp_dir.path()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].added, vec!["docs/**"]);
+    }
+
+    #[test]
+    fn test_expired_removals() {
+        let mut removed = entry("remove-paths docs/** assets/**", &[]);
+        removed.timestamp = 100;
+        removed.removed = vec!["docs/**".to_string(), "assets/**".to_string()];
+        let mut readded = entry("add-paths assets/**", &["assets/**"]);
+        readded.timestamp = 200;
+        let entries = vec![entry("clone", &["src/**", "docs/**"]), removed, readded];
+
+        assert_eq!(
+            expired_removals(&entries, &["src/**".to_string()], 300, 200),
+            vec!["docs/**"]
+        );
+        assert!(expired_removals(&entries, &["src/**".to_string()], 299, 200).is_empty());
+        assert!(expired_removals(&entries, &["docs/**".to_string()], 300, 0).is_empty());
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_timestamp(1_792_152_245), "2026-10-16 12:04:05 UTC");
+    }
+}
@@ -0,0 +1,400 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::core::path_selector;
+use crate::core::tree::{EntryKind, TreeEntry};
+use crate::utils::disk;
+
+/// How a file changes between two commits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+}
+
+/// A file changed between two commits
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileChange {
+    pub kind: ChangeKind,
+    pub path: String,
+}
+
+/// Incoming changes to the files under one sparse pattern
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct PatternChanges {
+    pub pattern: String,
+    pub added: usize,
+    pub modified: usize,
+    pub deleted: usize,
+
+    /// Size of the added and modified files, which the merge downloads
+    pub bytes: u64,
+}
+
+/// Incoming changes grouped by the sparse pattern selecting them
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct IncomingSummary {
+    /// Patterns with changes, in pattern order
+    pub groups: Vec<PatternChanges>,
+
+    /// Changed files outside the sparse paths, which are not checked out
+    pub outside: usize,
+
+    /// Whether blob sizes were available
+    pub sizes_known: bool,
+}
+
+impl IncomingSummary {
+    /// Checks if no checked out file changes
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+}
+
+impl fmt::Display for IncomingSummary {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        if self.groups.is_empty() {
+            writeln!(f, "No incoming changes in the sparse paths.")?;
+        } else {
+            writeln!(f, "Incoming changes in the sparse paths:")?;
+            let width = self
+                .groups
+                .iter()
+                .map(|g| g.pattern.len())
+                .max()
+                .unwrap_or(0);
+            for group in &self.groups {
+                write!(
+                    f,
+                    "  {:width$}  {} added, {} modified, {} deleted",
+                    group.pattern,
+                    group.added,
+                    group.modified,
+                    group.deleted,
+                    width = width
+                )?;
+                if self.sizes_known {
+                    write!(f, ", {}", disk::format_size(group.bytes))?;
+                }
+                writeln!(f)?;
+            }
+        }
+        if self.outside > 0 {
+            writeln!(
+                f,
+                "{} changed file(s) outside the sparse paths are not checked out.",
+                self.outside
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses `git diff --name-status --no-renames -z` output, where statuses
+/// and paths alternate. Type changes count as modifications; other statuses
+/// cannot occur without rename detection.
+pub fn parse_name_status(output: &str) -> Vec<FileChange> {
+    let mut fields = output.split('\0');
+    let mut changes = Vec::new();
+    while let (Some(status), Some(path)) = (fields.next(), fields.next()) {
+        // `git log -z` starts each commit's list on a new line
+        let kind = match status.trim_start().chars().next() {
+            Some('A') => ChangeKind::Added,
+            Some('M' | 'T') => ChangeKind::Modified,
+            Some('D') => ChangeKind::Deleted,
+            _ => continue,
+        };
+        changes.push(FileChange {
+            kind,
+            path: path.to_string(),
+        });
+    }
+    changes
+}
+
+/// Returns the added and modified files covered by `patterns`, whose new
+/// content a merge has to download
+pub fn downloads<'a>(
+    changes: &'a [FileChange],
+    patterns: &[String],
+) -> Vec<&'a str> {
+    let compiled: Vec<_> = patterns
+        .iter()
+        .map(|raw| path_selector::compile_sparse(raw))
+        .collect();
+    changes
+        .iter()
+        .filter(|change| change.kind != ChangeKind::Deleted)
+        .filter(|change| {
+            compiled
+                .iter()
+                .any(|pattern| path_selector::pattern_covers(pattern, &change.path))
+        })
+        .map(|change| change.path.as_str())
+        .collect()
+}
+
+/// Groups changes by the first of the sorted `patterns` covering them.
+/// `entries` lists the incoming tree with blob sizes, when known.
+pub fn summarize(
+    changes: &[FileChange],
+    patterns: &[String],
+    entries: Option<&[TreeEntry]>,
+) -> IncomingSummary {
+    let mut patterns = patterns.to_vec();
+    patterns.sort();
+    let compiled: Vec<_> = patterns
+        .iter()
+        .map(|raw| path_selector::compile_sparse(raw))
+        .collect();
+    let sizes: HashMap<&str, u64> = entries
+        .unwrap_or_default()
+        .iter()
+        .filter(|entry| entry.kind == EntryKind::Blob)
+        .filter_map(|entry| Some((entry.path.as_str(), entry.size?)))
+        .collect();
+
+    let mut groups: Vec<PatternChanges> = Vec::new();
+    let mut outside = 0;
+    for change in changes {
+        let Some(index) = compiled
+            .iter()
+            .position(|pattern| path_selector::pattern_covers(pattern, &change.path))
+        else {
+            outside += 1;
+            continue;
+        };
+        let pattern = &patterns[index];
+        let group = match groups.iter().position(|g| &g.pattern == pattern) {
+            Some(position) => &mut groups[position],
+            None => {
+                groups.push(PatternChanges {
+                    pattern: pattern.clone(),
+                    ..Default::default()
+                });
+                groups.last_mut().unwrap()
+            }
+        };
+        match change.kind {
+            ChangeKind::Added => group.added += 1,
+            ChangeKind::Modified => group.modified += 1,
+            ChangeKind::Deleted => group.deleted += 1,
+        }
+        if change.kind != ChangeKind::Deleted {
+            group.bytes += sizes.get(change.path.as_str()).copied().unwrap_or(0);
+        }
+    }
+    groups.sort_by(|a, b| a.pattern.cmp(&b.pattern));
+
+    IncomingSummary {
+        groups,
+        outside,
+        sizes_known: entries.is_some(),
+    }
+}
+
+/// A sparse pattern whose files upstream moved outside every pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternMove {
+    pub pattern: String,
+
+    /// The pattern rewritten for the new location
+    pub relocated: String,
+
+    /// Files matched to their new path
+    pub files: usize,
+}
+
+/// Finds the `patterns` that match files in `before` but none in `after`,
+/// because the files moved outside the sparse boundary, e.g. when upstream
+/// renames `src/frontend` to `apps/web`. Both trees list `(blob id, path)`
+/// pairs; a file is matched to its new path by content, then by file name
+/// when the name is unique among the new files, so no blob is downloaded.
+pub fn detect_moves(
+    before: &[(String, String)],
+    after: &[(String, String)],
+    patterns: &[String],
+) -> Vec<PatternMove> {
+    let compiled: Vec<_> = patterns
+        .iter()
+        .map(|raw| path_selector::compile_sparse(raw))
+        .collect();
+    let covered = |path: &str| {
+        compiled
+            .iter()
+            .any(|pattern| path_selector::pattern_covers(pattern, path))
+    };
+    let old_paths: HashSet<&str> = before.iter().map(|(_, path)| path.as_str()).collect();
+    // Only files new upstream and outside the checkout can be a moved file
+    let arrived: Vec<(&str, &str)> = after
+        .iter()
+        .filter(|(_, path)| !old_paths.contains(path.as_str()) && !covered(path))
+        .map(|(id, path)| (id.as_str(), path.as_str()))
+        .collect();
+    let by_id: HashMap<&str, &str> = arrived.iter().copied().collect();
+    let mut by_name: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (_, path) in &arrived {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        by_name.entry(name).or_default().push(path);
+    }
+
+    let mut moves = Vec::new();
+    for (pattern, compiled) in patterns.iter().zip(&compiled) {
+        if after
+            .iter()
+            .any(|(_, path)| path_selector::pattern_covers(compiled, path))
+        {
+            continue;
+        }
+        let files: Vec<(String, String)> = before
+            .iter()
+            .filter(|(_, path)| path_selector::pattern_covers(compiled, path))
+            .filter_map(|(id, old)| {
+                let name = old.rsplit('/').next().unwrap_or(old);
+                let new = by_id.get(id.as_str()).copied().or_else(|| {
+                    match by_name.get(name).map(Vec::as_slice) {
+                        Some([only]) => Some(*only),
+                        _ => None,
+                    }
+                })?;
+                Some((old.clone(), new.to_string()))
+            })
+            .collect();
+        let Some(relocated) = path_selector::relocate_pattern(pattern, &files) else {
+            continue;
+        };
+        moves.push(PatternMove {
+            pattern: pattern.clone(),
+            relocated,
+            files: files.len(),
+        });
+    }
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_name_status() {
+        let output = "A\0src/new.rs\0M\0README.md\0D\0old.txt\0T\0link";
+        let changes = parse_name_status(output);
+
+        assert_eq!(
+            changes.iter().map(|c| c.kind).collect::<Vec<_>>(),
+            vec![
+                ChangeKind::Added,
+                ChangeKind::Modified,
+                ChangeKind::Deleted,
+                ChangeKind::Modified
+            ]
+        );
+        assert_eq!(changes[0].path, "src/new.rs");
+    }
+
+    #[test]
+    fn test_summarize_by_pattern() {
+        let changes = parse_name_status(
+            "A\0src/frontend/new.js\0M\0src/frontend/app.js\0D\0src/backend/old.js\0M\0docs/guide.md",
+        );
+        let entries = vec![
+            TreeEntry {
+                path: "src/frontend/new.js".to_string(),
+                kind: EntryKind::Blob,
+                size: Some(100),
+            },
+            TreeEntry {
+                path: "src/frontend/app.js".to_string(),
+                kind: EntryKind::Blob,
+                size: Some(20),
+            },
+        ];
+        let summary = summarize(
+            &changes,
+            &["src/frontend/**".to_string(), "src/backend/**".to_string()],
+            Some(&entries),
+        );
+
+        assert_eq!(
+            summary.groups,
+            vec![
+                PatternChanges {
+                    pattern: "src/backend/**".to_string(),
+                    deleted: 1,
+                    ..Default::default()
+                },
+                PatternChanges {
+                    pattern: "src/frontend/**".to_string(),
+                    added: 1,
+                    modified: 1,
+                    bytes: 120,
+                    ..Default::default()
+                },
+            ]
+        );
+        assert_eq!(summary.outside, 1);
+        assert!(summary
+            .to_string()
+            .contains("1 added, 1 modified, 0 deleted, 120 B"));
+    }
+
+    #[test]
+    fn test_downloads() {
+        let changes = parse_name_status("A\0src/new.rs\0D\0src/old.rs\0M\0docs/guide.md");
+
+        assert_eq!(
+            downloads(&changes, &["src/**".to_string()]),
+            vec!["src/new.rs"]
+        );
+    }
+
+    #[test]
+    fn test_summarize_without_sizes() {
+        let changes = parse_name_status("M\0README.md");
+        let summary = summarize(&changes, &["README.md".to_string()], None);
+
+        assert!(!summary.is_empty());
+        assert!(!summary.sizes_known);
+        assert!(summary.to_string().ends_with("0 deleted\n"));
+    }
+
+    #[test]
+    fn test_detect_moves() {
+        let tree = |files: &[(&str, &str)]| -> Vec<(String, String)> {
+            files
+                .iter()
+                .map(|(id, path)| (id.to_string(), path.to_string()))
+                .collect()
+        };
+        let before = tree(&[
+            ("a1", "src/frontend/app.js"),
+            ("a2", "src/frontend/lib/util.js"),
+            ("b1", "src/backend/main.rs"),
+            ("c1", "docs/guide.md"),
+        ]);
+        let after = tree(&[
+            ("a1", "apps/web/app.js"),
+            ("a3", "apps/web/lib/util.js"),
+            ("b2", "src/backend/main.rs"),
+            ("c1", "docs/guide.md"),
+        ]);
+        let patterns = vec!["src/frontend/**".to_string(), "src/backend/**".to_string()];
+
+        assert_eq!(
+            detect_moves(&before, &after, &patterns),
+            vec![PatternMove {
+                pattern: "src/frontend/**".to_string(),
+                relocated: "apps/web/**".to_string(),
+                files: 2,
+            }]
+        );
+        // Files deleted without a new home are not a move
+        assert!(detect_moves(&before, &tree(&[]), &patterns).is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::error::PartialError;
+use crate::core::metadata::RepositoryMetadata;
+
+/// A step of a compound operation, recorded once done
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Step {
+    /// `git switch` to the target branch
+    Checkout,
+
+    /// The sparse patterns and the working tree
+    SparseCheckout,
+
+    /// `.gitpartial/metadata.json` and the history
+    Metadata,
+}
+
+impl Step {
+    /// Name shown by `recover`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Step::Checkout => "checkout",
+            Step::SparseCheckout => "sparse-checkout",
+            Step::Metadata => "metadata",
+        }
+    }
+}
+
+/// The branch and metadata at one end of an operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalState {
+    /// Branch checked out; None leaves the branch alone
+    pub branch: Option<String>,
+
+    pub metadata: RepositoryMetadata,
+}
+
+/// A compound operation in progress, such as `switch` or `remove-paths`,
+/// stored in `.gitpartial/journal.json` until it completes. A journal left
+/// behind by a failure or a crash lets `recover` finish the operation or
+/// roll it back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Journal {
+    /// Command that started the operation
+    pub operation: String,
+
+    /// State before the operation; None for a clone
+    pub before: Option<JournalState>,
+
+    /// State the operation leads to
+    pub after: JournalState,
+
+    /// Steps done so far, in order
+    #[serde(default)]
+    pub done: Vec<Step>,
+}
+
+impl Journal {
+    /// Records the start of an operation
+    pub fn begin<P: AsRef<Path>>(
+        repo_path: P,
+        operation: &str,
+        before: Option<JournalState>,
+        after: JournalState,
+    ) -> Result<Self> {
+        let journal = Journal {
+            operation: operation.to_string(),
+            before,
+            after,
+            done: Vec::new(),
+        };
+        journal.save(&repo_path)?;
+        Ok(journal)
+    }
+
+    /// Records a step as done
+    pub fn step<P: AsRef<Path>>(
+        &mut self,
+        repo_path: P,
+        step: Step,
+    ) -> Result<()> {
+        self.done.push(step);
+        self.save(repo_path)
+    }
+
+    /// Removes the journal once the operation is complete or undone
+    pub fn finish<P: AsRef<Path>>(repo_path: P) -> Result<()> {
+        let path = Self::journal_path(&repo_path);
+        if path.exists() {
+            fs::remove_file(&path).with_context(|| format!("Failed to remove {:?}", path))?;
+        }
+        Ok(())
+    }
+
+    /// The journal of an unfinished operation, if any
+    pub fn load<P: AsRef<Path>>(repo_path: P) -> Result<Option<Self>> {
+        let path = Self::journal_path(&repo_path);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read journal from {:?}", path))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .context("Failed to deserialize journal")
+    }
+
+    /// Fails when an earlier operation was left unfinished, so another one
+    /// does not build on a half-applied state
+    pub fn ensure_none<P: AsRef<Path>>(repo_path: P) -> Result<()> {
+        match Self::load(repo_path)? {
+            Some(journal) => Err(PartialError::IncompleteOperation {
+                operation: journal.operation,
+            }
+            .into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes the journal through a temporary file, so a crash never leaves
+    /// a truncated one
+    fn save<P: AsRef<Path>>(
+        &self,
+        repo_path: P,
+    ) -> Result<()> {
+        let path = Self::journal_path(&repo_path);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+        }
+        let serialized =
+            serde_json::to_string_pretty(self).context("Failed to serialize journal")?;
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, serialized)
+            .with_context(|| format!("Failed to write journal to {:?}", temp_path))?;
+        fs::rename(&temp_path, &path)
+            .with_context(|| format!("Failed to write journal to {:?}", path))
+    }
+
+    fn journal_path<P: AsRef<Path>>(repo_path: P) -> PathBuf {
+        repo_path.as_ref().join(".gitpartial").join("journal.json")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_roundtrip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert!(Journal::load(temp_dir.path()).unwrap().is_none());
+        assert!(Journal::ensure_none(temp_dir.path()).is_ok());
+
+        let mut metadata = RepositoryMetadata::new("https://example.com/repo.git".to_string());
+        metadata.add_paths(&["src/**".to_string()]);
+        let after = JournalState {
+            branch: Some("main".to_string()),
+            metadata,
+        };
+        let mut journal = Journal::begin(temp_dir.path(), "switch", None, after).unwrap();
+        journal.step(temp_dir.path(), Step::Checkout).unwrap();
+
+        let loaded = Journal::load(temp_dir.path()).unwrap().unwrap();
+        assert_eq!(loaded.operation, "switch");
+        assert_eq!(loaded.done, vec![Step::Checkout]);
+        assert!(loaded.after.metadata.checked_out_paths.contains("src/**"));
+        assert!(Journal::ensure_none(temp_dir.path()).is_err());
+
+        Journal::finish(temp_dir.path()).unwrap();
+        assert!(Journal::load(temp_dir.path()).unwrap().is_none());
+    }
+}
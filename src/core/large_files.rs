@@ -0,0 +1,88 @@
+use std::collections::HashSet;
+
+/// A version of a file that the clone filter left on the remote
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedBlob {
+    pub path: String,
+    pub id: String,
+}
+
+/// Reads the blobs a `git log --raw --no-abbrev --format=` listing shows
+/// being written, newest first, as `(id, path)`. Deletions are skipped.
+pub fn parse_raw_log(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (info, path) = line.strip_prefix(':')?.split_once('\t')?;
+            let fields: Vec<&str> = info.split(' ').collect();
+            let (id, status) = (fields.get(3)?, fields.get(4)?);
+            (!status.starts_with('D')).then(|| (id.to_string(), path.to_string()))
+        })
+        .collect()
+}
+
+/// Picks the blobs of `history` that are missing locally, once per version,
+/// keeping its order. `missing` is the output of
+/// `git rev-list --objects --missing=print`.
+pub fn skipped_blobs(
+    history: &[(String, String)],
+    missing: &str,
+) -> Vec<SkippedBlob> {
+    let missing: HashSet<&str> = missing
+        .lines()
+        .filter_map(|line| line.strip_prefix('?'))
+        .collect();
+    let mut seen = HashSet::new();
+    history
+        .iter()
+        .filter(|(id, path)| missing.contains(id.as_str()) && seen.insert((id, path)))
+        .map(|(id, path)| SkippedBlob {
+            path: path.clone(),
+            id: id.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "\
+:100644 100644 1111111111111111111111111111111111111111 2222222222222222222222222222222222222222 M\tassets/video.mp4
+:100644 000000 3333333333333333333333333333333333333333 0000000000000000000000000000000000000000 D\tassets/old.bin
+:000000 100644 0000000000000000000000000000000000000000 1111111111111111111111111111111111111111 A\tassets/video.mp4
+:000000 100644 0000000000000000000000000000000000000000 3333333333333333333333333333333333333333 A\tassets/old.bin";
+
+    #[test]
+    fn test_parse_raw_log() {
+        let history = parse_raw_log(LOG);
+        assert_eq!(history.len(), 3);
+        assert_eq!(
+            history[0],
+            (
+                "2222222222222222222222222222222222222222".to_string(),
+                "assets/video.mp4".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_skipped_blobs() {
+        let history = parse_raw_log(LOG);
+        let missing = "abc\n?1111111111111111111111111111111111111111\n?3333333333333333333333333333333333333333\n";
+        let skipped = skipped_blobs(&history, missing);
+        assert_eq!(
+            skipped,
+            vec![
+                SkippedBlob {
+                    path: "assets/video.mp4".to_string(),
+                    id: "1111111111111111111111111111111111111111".to_string(),
+                },
+                SkippedBlob {
+                    path: "assets/old.bin".to_string(),
+                    id: "3333333333333333333333333333333333333333".to_string(),
+                },
+            ]
+        );
+    }
+}
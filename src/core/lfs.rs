@@ -0,0 +1,79 @@
+use glob::{MatchOptions, Pattern};
+
+/// Extracts the patterns tracked by Git LFS (`filter=lfs`) from a .gitattributes file
+pub fn tracked_patterns(gitattributes: &str) -> Vec<String> {
+    gitattributes
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pattern = fields.next()?;
+            fields
+                .any(|attribute| attribute == "filter=lfs")
+                .then(|| pattern.to_string())
+        })
+        .collect()
+}
+
+/// Checks if a path is tracked by one of the LFS patterns.
+/// Patterns without a slash match the file name at any depth, as in .gitattributes.
+pub fn is_tracked(
+    patterns: &[String],
+    path: &str,
+) -> bool {
+    let options = MatchOptions {
+        require_literal_separator: true,
+        ..MatchOptions::new()
+    };
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+
+    patterns.iter().any(|raw| {
+        let anchored = raw.contains('/');
+        let Ok(pattern) = Pattern::new(raw.trim_start_matches('/')) else {
+            return false;
+        };
+        if anchored {
+            pattern.matches_with(path, options)
+        } else {
+            pattern.matches_with(file_name, options)
+        }
+    })
+}
+
+/// Builds the `lfs.fetchinclude` value for a set of sparse patterns
+pub fn fetch_include(paths: &[String]) -> String {
+    let mut patterns: Vec<&str> = paths.iter().map(|p| p.trim_start_matches('/')).collect();
+    patterns.sort();
+    patterns.dedup();
+    patterns.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracked_patterns() {
+        let content = "# Binary assets\n*.psd filter=lfs diff=lfs merge=lfs -text\nassets/** filter=lfs\n*.txt text\n";
+        assert_eq!(tracked_patterns(content), vec!["*.psd", "assets/**"]);
+    }
+
+    #[test]
+    fn test_is_tracked() {
+        let patterns = vec!["*.psd".to_string(), "assets/**".to_string()];
+        assert!(is_tracked(&patterns, "design/deep/logo.psd"));
+        assert!(is_tracked(&patterns, "assets/video/intro.mp4"));
+        assert!(!is_tracked(&patterns, "src/assets/readme.md"));
+    }
+
+    #[test]
+    fn test_fetch_include() {
+        let paths = vec![
+            "src/**".to_string(),
+            "/README.md".to_string(),
+            "README.md".to_string(),
+        ];
+        assert_eq!(fetch_include(&paths), "README.md,src/**");
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// `git cat-file --batch-check` format read by [`parse_blob_sizes`]
+pub const OBJECT_FORMAT: &str = "%(objectname) %(objecttype) %(objectsize:disk)";
+
+/// Space a path takes on this machine
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LocalUsage {
+    /// A file, or a directory ending in `/`
+    pub path: String,
+
+    /// Bytes of the checked-out copy in the working tree
+    pub worktree: u64,
+
+    /// Bytes of the path's blobs, in every version, in the object store
+    pub objects: u64,
+}
+
+impl LocalUsage {
+    pub fn total(&self) -> u64 {
+        self.worktree + self.objects
+    }
+}
+
+/// Parses `git cat-file --batch-all-objects --batch-check=<OBJECT_FORMAT>`
+/// into the on-disk size of each local blob
+pub fn parse_blob_sizes(output: &str) -> HashMap<String, u64> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(' ');
+            let id = fields.next()?;
+            let size = match (fields.next()?, fields.next()?) {
+                ("blob", size) => size.parse().ok()?,
+                _ => return None,
+            };
+            Some((id.to_string(), size))
+        })
+        .collect()
+}
+
+/// Combines the working tree sizes of checked-out files with the sizes of
+/// the local blobs, attributed to the path `git rev-list --objects` names
+/// them by. Blobs shared by several paths count once, for the first.
+pub fn by_file(
+    worktree: &[(String, u64)],
+    rev_list_objects: &str,
+    blob_sizes: &HashMap<String, u64>,
+) -> Vec<LocalUsage> {
+    let mut usage: BTreeMap<String, LocalUsage> = BTreeMap::new();
+    for (path, bytes) in worktree {
+        usage.entry(path.clone()).or_default().worktree += bytes;
+    }
+    let mut counted = HashSet::new();
+    for line in rev_list_objects.lines() {
+        let Some((id, path)) = line.split_once(' ') else {
+            continue;
+        };
+        let Some(bytes) = blob_sizes.get(id) else {
+            continue;
+        };
+        if counted.insert(id) {
+            usage.entry(path.to_string()).or_default().objects += bytes;
+        }
+    }
+    usage
+        .into_iter()
+        .map(|(path, usage)| LocalUsage { path, ..usage })
+        .filter(|usage| usage.total() > 0)
+        .collect()
+}
+
+/// Sums the files into their directories, cut `depth` levels deep. Files
+/// at the root are left out.
+pub fn by_directory(
+    files: &[LocalUsage],
+    depth: usize,
+) -> Vec<LocalUsage> {
+    let mut usage: BTreeMap<String, LocalUsage> = BTreeMap::new();
+    for file in files {
+        let Some((parent, _)) = file.path.rsplit_once('/') else {
+            continue;
+        };
+        let components: Vec<&str> = parent.split('/').take(depth).collect();
+        let directory = format!("{}/", components.join("/"));
+        let row = usage.entry(directory).or_default();
+        row.worktree += file.worktree;
+        row.objects += file.objects;
+    }
+    usage
+        .into_iter()
+        .map(|(path, usage)| LocalUsage { path, ..usage })
+        .collect()
+}
+
+/// The `count` largest entries, largest first
+pub fn largest(
+    mut usage: Vec<LocalUsage>,
+    count: usize,
+) -> Vec<LocalUsage> {
+    usage.sort_by(|a, b| b.total().cmp(&a.total()).then(a.path.cmp(&b.path)));
+    usage.truncate(count);
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(
+        path: &str,
+        worktree: u64,
+        objects: u64,
+    ) -> LocalUsage {
+        LocalUsage {
+            path: path.to_string(),
+            worktree,
+            objects,
+        }
+    }
+
+    #[test]
+    fn test_by_file() {
+        let sizes = parse_blob_sizes("aaa blob 100\nbbb blob 40\nccc tree 30\nddd blob 7\n");
+        assert_eq!(sizes.len(), 3);
+
+        // ccc is a tree, eee was never downloaded, and aaa is counted once
+        let objects =
+            "c0ffee\naaa src/app.bin\nccc src\nbbb docs/old.md\neee lib/a.rs\naaa src/copy.bin\n";
+        let worktree = vec![
+            ("src/app.bin".to_string(), 300),
+            ("README.md".to_string(), 0),
+        ];
+        assert_eq!(
+            by_file(&worktree, objects, &sizes),
+            vec![usage("docs/old.md", 0, 40), usage("src/app.bin", 300, 100)]
+        );
+    }
+
+    #[test]
+    fn test_by_directory_and_largest() {
+        let files = vec![
+            usage("README.md", 10, 5),
+            usage("src/app/main.rs", 100, 20),
+            usage("src/app/lib.rs", 50, 0),
+            usage("src/util.rs", 5, 5),
+            usage("docs/guide.md", 0, 500),
+        ];
+
+        assert_eq!(
+            by_directory(&files, 1),
+            vec![usage("docs/", 0, 500), usage("src/", 155, 25)]
+        );
+        assert_eq!(
+            by_directory(&files, 2),
+            vec![
+                usage("docs/", 0, 500),
+                usage("src/", 5, 5),
+                usage("src/app/", 150, 20)
+            ]
+        );
+        assert_eq!(
+            largest(files, 2),
+            vec![
+                usage("docs/guide.md", 0, 500),
+                usage("src/app/main.rs", 100, 20)
+            ]
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use anyhow::{Context, Result};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::core::error::PartialError;
+use crate::utils::interrupt::RemoveOnInterrupt;
+
+/// How often a waiting command checks whether the lock was released
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Age after which a takeover guard is assumed to be left by a process
+/// that died while taking over a stale lock
+const STALE_GUARD_AGE: Duration = Duration::from_secs(10);
+
+/// Exclusive lock on a repository, held by commands that change the sparse
+/// paths, the metadata or the checkout. Stored in `.gitpartial/lock` with
+/// the holder's process id, and released when dropped.
+pub struct RepoLock {
+    path: PathBuf,
+    _interrupt: RemoveOnInterrupt,
+}
+
+impl RepoLock {
+    /// Takes the lock, waiting for another git-partial process to release it
+    /// unless `wait` is false. A lock left by a process that no longer runs
+    /// is taken over.
+    pub fn acquire<P: AsRef<Path>>(
+        repo_path: P,
+        wait: bool,
+    ) -> Result<Self> {
+        let path = Self::lock_path(&repo_path);
+        let mut announced = false;
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    let interrupt = RemoveOnInterrupt::new(&path);
+                    write!(file, "{}", std::process::id())
+                        .with_context(|| format!("Failed to write lock file {:?}", path))?;
+                    debug!("Acquired {:?}", path);
+                    return Ok(RepoLock {
+                        path,
+                        _interrupt: interrupt,
+                    });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to create lock file {:?}", path))
+                }
+            }
+
+            let holder = Self::holder(&path);
+            if let Some(pid) = holder.filter(|pid| !process_exists(*pid)) {
+                Self::take_over(&path, pid)?;
+                continue;
+            }
+            if !wait {
+                return Err(PartialError::Locked { pid: holder }.into());
+            }
+            if !announced {
+                match holder {
+                    Some(pid) => eprintln!("Waiting for git-partial (pid {}) to finish...", pid),
+                    None => eprintln!("Waiting for another git-partial to finish..."),
+                }
+                announced = true;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Remove the lock left by the dead process `pid`. Waiters take turns
+    /// through a guard file and check the pid again while holding it, so a
+    /// waiter that read the dead pid late cannot remove the lock another
+    /// waiter just took over.
+    fn take_over(
+        path: &Path,
+        pid: u32,
+    ) -> Result<()> {
+        let guard = path.with_extension("takeover");
+        match OpenOptions::new().write(true).create_new(true).open(&guard) {
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                let age = fs::metadata(&guard)
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+                    .and_then(|modified| modified.elapsed().ok());
+                if age.is_some_and(|age| age > STALE_GUARD_AGE) {
+                    fs::remove_file(&guard).ok();
+                } else {
+                    thread::sleep(POLL_INTERVAL);
+                }
+                return Ok(());
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to create {:?}", guard));
+            }
+        }
+        let _interrupt = RemoveOnInterrupt::new(&guard);
+        if Self::holder(path) == Some(pid) {
+            warn!("Removing stale lock of process {}", pid);
+            fs::remove_file(path).ok();
+        }
+        fs::remove_file(&guard).ok();
+        Ok(())
+    }
+
+    /// The process id recorded in the lock file. `None` while the holder
+    /// has created the file but not yet written to it.
+    fn holder(path: &Path) -> Option<u32> {
+        fs::read_to_string(path).ok()?.trim().parse().ok()
+    }
+
+    fn lock_path<P: AsRef<Path>>(repo_path: P) -> PathBuf {
+        repo_path.as_ref().join(".gitpartial").join("lock")
+    }
+}
+
+impl Drop for RepoLock {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            warn!("Failed to release {:?}: {}", self.path, e);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn process_exists(pid: u32) -> bool {
+    // SAFETY: signal 0 only checks that the process exists
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+}
+
+#[cfg(not(unix))]
+fn process_exists(_pid: u32) -> bool {
+    // Without a cheap check, never treat a lock as stale
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn create_temp_repo() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join(".gitpartial")).unwrap();
+        temp_dir
+    }
+
+    #[test]
+    fn test_acquire_and_release() {
+        let temp_dir = create_temp_repo();
+        let lock_path = RepoLock::lock_path(temp_dir.path());
+
+        let lock = RepoLock::acquire(temp_dir.path(), false).unwrap();
+        assert_eq!(RepoLock::holder(&lock_path), Some(std::process::id()));
+
+        drop(lock);
+        assert!(!lock_path.exists());
+    }
+
+    #[test]
+    fn test_held_lock_without_wait() {
+        let temp_dir = create_temp_repo();
+        let _lock = RepoLock::acquire(temp_dir.path(), false).unwrap();
+
+        let err = RepoLock::acquire(temp_dir.path(), false).err().unwrap();
+        assert_eq!(
+            err.downcast_ref::<PartialError>(),
+            Some(&PartialError::Locked {
+                pid: Some(std::process::id())
+            })
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stale_lock_is_taken_over() {
+        let temp_dir = create_temp_repo();
+        let lock_path = RepoLock::lock_path(temp_dir.path());
+        // Far above any real pid_max
+        fs::write(&lock_path, "2000000000").unwrap();
+
+        let _lock = RepoLock::acquire(temp_dir.path(), false).unwrap();
+        assert_eq!(RepoLock::holder(&lock_path), Some(std::process::id()));
+        assert!(!lock_path.with_extension("takeover").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_late_takeover_keeps_the_new_lock() {
+        let temp_dir = create_temp_repo();
+        let lock_path = RepoLock::lock_path(temp_dir.path());
+        let _lock = RepoLock::acquire(temp_dir.path(), false).unwrap();
+
+        // A waiter that read a dead pid before this process took over
+        RepoLock::take_over(&lock_path, 2000000000).unwrap();
+        assert_eq!(RepoLock::holder(&lock_path), Some(std::process::id()));
+    }
+}
@@ -0,0 +1,128 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path};
+
+/// Copy of the manifest kept at the root of a workspace, which `sync` reads
+pub const WORKSPACE_FILE: &str = ".gitpartial-manifest.toml";
+
+/// Several repositories cloned side by side into one workspace, like a
+/// `repo` or `west` manifest
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(rename = "repository")]
+    pub repositories: Vec<ManifestRepository>,
+}
+
+/// One `[[repository]]` of a manifest
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestRepository {
+    /// Repository URL to clone
+    pub url: String,
+
+    /// Directory in the workspace [default: the repository's name]
+    #[serde(default)]
+    pub path: Option<String>,
+
+    /// Paths to check out
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+impl ManifestRepository {
+    /// Directory of the clone in the workspace: `path`, or the last segment
+    /// of the URL without `.git`
+    pub fn dir(&self) -> String {
+        if let Some(path) = &self.path {
+            return path.trim_end_matches('/').to_string();
+        }
+        let url = self.url.trim_end_matches('/');
+        let name = url.rsplit(['/', ':']).next().unwrap_or(url);
+        name.strip_suffix(".git").unwrap_or(name).to_string()
+    }
+}
+
+impl Manifest {
+    /// Parses a manifest, refusing directories that leave the workspace or
+    /// are shared by two repositories
+    pub fn parse(content: &str) -> Result<Self> {
+        let manifest: Manifest = toml::from_str(content).context("Invalid manifest")?;
+        if manifest.repositories.is_empty() {
+            anyhow::bail!("The manifest lists no [[repository]]");
+        }
+        let mut dirs = HashSet::new();
+        for repository in &manifest.repositories {
+            let dir = repository.dir();
+            let inside = !dir.is_empty()
+                && Path::new(&dir)
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_)));
+            if !inside {
+                anyhow::bail!(
+                    "Invalid directory '{}' for {}: it must be a relative path inside the workspace",
+                    dir,
+                    repository.url
+                );
+            }
+            if !dirs.insert(dir.clone()) {
+                anyhow::bail!("Two repositories are cloned into '{}'", dir);
+            }
+        }
+        Ok(manifest)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read manifest {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// The manifest of the workspace rooted at `dir`, if it is one
+    pub fn load_workspace<P: AsRef<Path>>(dir: P) -> Result<Option<Self>> {
+        let path = dir.as_ref().join(WORKSPACE_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Self::load(path).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = Manifest::parse(
+            r#"
+[[repository]]
+url = "https://github.com/acme/app.git"
+paths = ["src/**", "docs/**"]
+
+[[repository]]
+url = "git@github.com:acme/tools"
+path = "vendor/tools/"
+"#,
+        )
+        .unwrap();
+
+        let dirs: Vec<String> = manifest.repositories.iter().map(|r| r.dir()).collect();
+        assert_eq!(dirs, vec!["app", "vendor/tools"]);
+        assert_eq!(manifest.repositories[0].paths, vec!["src/**", "docs/**"]);
+        assert!(manifest.repositories[1].paths.is_empty());
+
+        assert!(Manifest::parse("").is_err());
+        assert!(Manifest::parse(
+            "[[repository]]\nurl = \"https://example.com/app.git\"\npath = \"../app\"\n"
+        )
+        .is_err());
+        assert!(Manifest::parse(
+            "[[repository]]\nurl = \"https://a.example.com/app.git\"\n\n[[repository]]\nurl = \"https://b.example.com/app\"\n"
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,230 @@
+use anyhow::Result;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::sync::OnceLock;
+use tracing::debug;
+
+use crate::core::path_selector::join_dir;
+use crate::core::workspace::{Project, ProjectSelection};
+
+/// Files at the root that configure the reactor and the wrapper
+const ROOT_FILES: [&str; 7] = [
+    "pom.xml",
+    "mvnw",
+    "mvnw.cmd",
+    ".mvn/maven.config",
+    ".mvn/jvm.config",
+    ".mvn/extensions.xml",
+    ".mvn/wrapper/maven-wrapper.properties",
+];
+
+fn regex(
+    cell: &'static OnceLock<Regex>,
+    pattern: &str,
+) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("valid regex"))
+}
+
+/// Drops XML comments
+fn strip_comments(content: &str) -> String {
+    static COMMENT: OnceLock<Regex> = OnceLock::new();
+    regex(&COMMENT, r"(?s)<!--.*?-->")
+        .replace_all(content, "")
+        .into_owned()
+}
+
+/// Drops the elements named `tag`, with their content
+fn strip_elements(
+    content: &str,
+    tag: &str,
+) -> String {
+    let element = Regex::new(&format!(r"(?s)<{tag}\b[^>]*>.*?</{tag}>")).expect("valid regex");
+    element.replace_all(content, "").into_owned()
+}
+
+/// The `<module>` paths of an aggregator pom, profiles' included
+pub fn parse_modules(pom: &str) -> Vec<String> {
+    static MODULE: OnceLock<Regex> = OnceLock::new();
+    regex(&MODULE, r"<module>\s*([^<]+?)\s*</module>")
+        .captures_iter(&strip_comments(pom))
+        .map(|captures| captures[1].trim_end_matches("/pom.xml").to_string())
+        .collect()
+}
+
+/// The `artifactId` of the project a pom describes
+pub fn parse_artifact_id(pom: &str) -> Option<String> {
+    static ARTIFACT_ID: OnceLock<Regex> = OnceLock::new();
+    let mut pom = strip_comments(pom);
+    for tag in [
+        "parent",
+        "dependencyManagement",
+        "dependencies",
+        "build",
+        "profiles",
+    ] {
+        pom = strip_elements(&pom, tag);
+    }
+    regex(&ARTIFACT_ID, r"<artifactId>\s*([^<]+?)\s*</artifactId>")
+        .captures(&pom)
+        .map(|captures| captures[1].to_string())
+}
+
+/// The `artifactId`s a pom depends on, plugins and the parent included
+fn dependency_artifact_ids(pom: &str) -> Vec<String> {
+    static DEPENDENCY: OnceLock<Regex> = OnceLock::new();
+    static ARTIFACT_ID: OnceLock<Regex> = OnceLock::new();
+    // Managed versions are not dependencies of this project
+    let pom = strip_elements(&strip_comments(pom), "dependencyManagement");
+    regex(
+        &DEPENDENCY,
+        r"(?s)<(?:dependency|plugin|parent)>(.*?)</(?:dependency|plugin|parent)>",
+    )
+    .captures_iter(&pom)
+    .filter_map(|captures| {
+        regex(&ARTIFACT_ID, r"<artifactId>\s*([^<]+?)\s*</artifactId>")
+            .captures(&captures[1])
+            .map(|artifact| artifact[1].to_string())
+    })
+    .collect()
+}
+
+/// Resolves the module in `module_dir` to the directories of the reactor
+/// modules it depends on, transitively, matched by `artifactId`. Maven
+/// reads every module of the reactor before building any, so the other
+/// `modules`' poms are added as files, along with the root pom and the
+/// `.mvn` configuration. `read_file` returns the content of a file at the
+/// inspected revision.
+pub fn select_module<F>(
+    module_dir: &str,
+    modules: &[Project],
+    read_file: F,
+) -> Result<ProjectSelection>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let dirs_by_artifact: BTreeMap<&str, &str> = modules
+        .iter()
+        .map(|module| (module.name.as_str(), module.dir.as_str()))
+        .collect();
+
+    let mut dirs = BTreeSet::new();
+    let mut queue = VecDeque::from([module_dir.to_string()]);
+    while let Some(dir) = queue.pop_front() {
+        if dirs.contains(&dir) {
+            continue;
+        }
+        let Some(pom) = join_dir(&dir, "pom.xml").and_then(|path| read_file(&path)) else {
+            if dir == module_dir {
+                anyhow::bail!("No pom.xml found in '{}'", module_dir);
+            }
+            debug!("No pom.xml in '{}'", dir);
+            continue;
+        };
+        queue.extend(
+            dependency_artifact_ids(&pom)
+                .iter()
+                .filter_map(|artifact| dirs_by_artifact.get(artifact.as_str()))
+                .map(|dir| dir.to_string()),
+        );
+        dirs.insert(dir);
+    }
+
+    let mut selection = ProjectSelection {
+        files: ROOT_FILES.iter().map(|file| file.to_string()).collect(),
+        ..ProjectSelection::default()
+    };
+    selection.files.extend(
+        modules
+            .iter()
+            .filter(|module| !dirs.contains(&module.dir))
+            .filter_map(|module| join_dir(&module.dir, "pom.xml")),
+    );
+    dirs.remove("");
+    selection.dirs = dirs;
+    debug!("Selected Maven module: {:?}", selection);
+    Ok(selection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::workspace::WorkspaceKind;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_pom() {
+        let pom = r#"
+<project>
+  <parent>
+    <groupId>com.acme</groupId>
+    <artifactId>shop-parent</artifactId>
+  </parent>
+  <artifactId>payments-core</artifactId>
+  <modules>
+    <module>api</module>
+    <!-- <module>legacy</module> -->
+  </modules>
+  <dependencyManagement>
+    <dependencies>
+      <dependency><artifactId>managed</artifactId></dependency>
+    </dependencies>
+  </dependencyManagement>
+  <dependencies>
+    <dependency>
+      <groupId>com.acme</groupId>
+      <artifactId>common</artifactId>
+    </dependency>
+  </dependencies>
+</project>
+"#;
+        assert_eq!(parse_artifact_id(pom).as_deref(), Some("payments-core"));
+        assert_eq!(parse_modules(pom), vec!["api"]);
+        assert_eq!(dependency_artifact_ids(pom), vec!["shop-parent", "common"]);
+    }
+
+    #[test]
+    fn test_select_module() {
+        let files = HashMap::from([
+            ("pom.xml", "<project><artifactId>shop</artifactId></project>"),
+            (
+                "app/pom.xml",
+                "<project><dependencies><dependency><artifactId>payments-core</artifactId></dependency></dependencies></project>",
+            ),
+            (
+                "payments/core/pom.xml",
+                "<project><dependencies><dependency><artifactId>common</artifactId></dependency></dependencies></project>",
+            ),
+            ("common/pom.xml", "<project></project>"),
+            ("admin/pom.xml", "<project></project>"),
+        ]);
+        let modules: Vec<Project> = [
+            ("app", "app"),
+            ("payments-core", "payments/core"),
+            ("common", "common"),
+            ("admin", "admin"),
+        ]
+        .iter()
+        .map(|(name, dir)| Project {
+            name: name.to_string(),
+            dir: dir.to_string(),
+            kind: WorkspaceKind::Maven,
+        })
+        .collect();
+
+        let selection = select_module("app", &modules, |path| {
+            files.get(path).map(|content| content.to_string())
+        })
+        .unwrap();
+
+        assert_eq!(
+            selection.patterns(|file| files.contains_key(file)),
+            vec![
+                "app/**",
+                "common/**",
+                "payments/core/**",
+                "admin/pom.xml",
+                "pom.xml",
+            ]
+        );
+    }
+}
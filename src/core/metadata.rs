@@ -1,20 +1,72 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::core::error::PartialError;
+use crate::core::path_selector;
+use crate::core::profiles::BranchProfile;
+use crate::core::root_mount::RootMount;
+use crate::core::savings::CloneStats;
+
 /// Metadata for a GitPartial repository
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RepositoryMetadata {
     /// The original repository URL
     pub remote_url: String,
 
+    /// The canonical repository URL when the clone is of a fork
+    #[serde(default)]
+    pub upstream_url: Option<String>,
+
+    /// The mirror objects are fetched through, set by `clone --via`
+    #[serde(default)]
+    pub mirror_url: Option<String>,
+
     /// The set of paths that have been checked out
     pub checked_out_paths: HashSet<String>,
 
     /// The last known commit SHA
     pub last_commit: Option<String>,
+
+    /// Paths that must not be removed without `--force`
+    #[serde(default)]
+    pub pinned_paths: HashSet<String>,
+
+    /// Commits pinned paths are checked out at, set by `pin --at`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pinned_revisions: BTreeMap<String, String>,
+
+    /// Paths applied automatically by `switch`, matched by branch
+    #[serde(default)]
+    pub branch_profiles: Vec<BranchProfile>,
+
+    /// Partial clone filter the clone was made with, e.g. `blob:limit=1m`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+
+    /// Whether the server ignored `filter` and sent every object, so
+    /// nothing is fetched on demand
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub filter_ignored: bool,
+
+    /// Whether the clone is a bare repository without a working tree
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bare: bool,
+
+    /// Branches fetched besides the current one once `narrow-fetch` narrowed
+    /// the fetch refspec, sorted; `None` fetches every branch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetch_branches: Option<Vec<String>>,
+
+    /// Subdirectory shown as the top level, set by `clone --root`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root_mount: Option<RootMount>,
+
+    /// Full clone size last measured by `savings`, reused when the remote is unreachable
+    #[serde(default)]
+    pub full_clone_stats: Option<CloneStats>,
 }
 
 impl RepositoryMetadata {
@@ -22,18 +74,29 @@ impl RepositoryMetadata {
     pub fn new(remote_url: String) -> Self {
         RepositoryMetadata {
             remote_url,
+            upstream_url: None,
+            mirror_url: None,
             checked_out_paths: HashSet::new(),
             last_commit: None,
+            pinned_paths: HashSet::new(),
+            pinned_revisions: BTreeMap::new(),
+            branch_profiles: Vec::new(),
+            filter: None,
+            filter_ignored: false,
+            bare: false,
+            fetch_branches: None,
+            root_mount: None,
+            full_clone_stats: None,
         }
     }
 
-    /// Adds paths to the checked out paths set
+    /// Adds paths to the checked out paths set, with brace alternatives expanded
     pub fn add_paths(
         &mut self,
         paths: &[String],
     ) {
-        for path in paths {
-            self.checked_out_paths.insert(path.clone());
+        for path in path_selector::expand_patterns(paths) {
+            self.checked_out_paths.insert(path);
         }
     }
 
@@ -45,6 +108,17 @@ impl RepositoryMetadata {
         self.last_commit = Some(commit_sha.to_string());
     }
 
+    /// Returns the given paths that are pinned, in order
+    pub fn pinned<'a>(
+        &self,
+        paths: &'a [String],
+    ) -> Vec<&'a String> {
+        paths
+            .iter()
+            .filter(|path| self.pinned_paths.contains(*path))
+            .collect()
+    }
+
     /// Saves metadata to the specified repository path
     pub fn save<P: AsRef<Path>>(
         &self,
@@ -69,6 +143,9 @@ impl RepositoryMetadata {
     /// Loads metadata from the specified repository path
     pub fn load<P: AsRef<Path>>(repo_path: P) -> Result<Self> {
         let metadata_path = Self::metadata_path(&repo_path);
+        if !metadata_path.exists() {
+            return Err(PartialError::MetadataNotFound.into());
+        }
 
         let content = fs::read_to_string(&metadata_path)
             .with_context(|| format!("Failed to read metadata from {:?}", metadata_path))?;
@@ -78,8 +155,20 @@ impl RepositoryMetadata {
         Ok(metadata)
     }
 
+    /// Checks if the repository already has git-partial metadata
+    pub fn exists<P: AsRef<Path>>(repo_path: P) -> bool {
+        Self::metadata_path(repo_path).exists()
+    }
+
+    /// The closest directory at or above `dir` with git-partial metadata
+    pub fn find_root(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .find(|ancestor| Self::exists(ancestor))
+            .map(Path::to_path_buf)
+    }
+
     /// Returns the path to the metadata file
-    fn metadata_path<P: AsRef<Path>>(repo_path: P) -> PathBuf {
+    pub fn metadata_path<P: AsRef<Path>>(repo_path: P) -> PathBuf {
         repo_path.as_ref().join(".gitpartial").join("metadata.json")
     }
 }
@@ -113,6 +202,33 @@ mod tests {
         assert!(metadata.checked_out_paths.contains("*.md"));
     }
 
+    #[test]
+    fn test_add_paths_expands_braces() {
+        let mut metadata = RepositoryMetadata::new("https://github.com/user/repo.git".to_string());
+
+        metadata.add_paths(&["src/{frontend,shared}/**".to_string()]);
+
+        assert_eq!(metadata.checked_out_paths.len(), 2);
+        assert!(metadata.checked_out_paths.contains("src/frontend/**"));
+        assert!(metadata.checked_out_paths.contains("src/shared/**"));
+    }
+
+    #[test]
+    fn test_find_root_from_subdirectory() {
+        let temp_dir = create_temp_repo();
+        let nested = temp_dir.path().join("src").join("lib");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(RepositoryMetadata::find_root(&nested), None);
+
+        RepositoryMetadata::new("https://example.com/repo.git".to_string())
+            .save(temp_dir.path())
+            .unwrap();
+        assert_eq!(
+            RepositoryMetadata::find_root(&nested),
+            Some(temp_dir.path().to_path_buf())
+        );
+    }
+
     #[test]
     fn test_set_last_commit() {
         let mut metadata = RepositoryMetadata::new("https://github.com/user/repo.git".to_string());
@@ -122,6 +238,21 @@ mod tests {
         assert_eq!(metadata.last_commit, Some("abc123".to_string()));
     }
 
+    #[test]
+    fn test_load_without_pinned_paths() {
+        let temp_dir = create_temp_repo();
+        let repo_path = temp_dir.path();
+        fs::create_dir_all(repo_path.join(".gitpartial")).unwrap();
+        fs::write(
+            RepositoryMetadata::metadata_path(repo_path),
+            r#"{"remote_url": "url", "checked_out_paths": ["src/**"], "last_commit": null}"#,
+        )
+        .unwrap();
+
+        let loaded = RepositoryMetadata::load(repo_path).expect("Failed to load metadata");
+        assert!(loaded.pinned_paths.is_empty());
+    }
+
     #[test]
     fn test_save_and_load() {
         let temp_dir = create_temp_repo();
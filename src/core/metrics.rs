@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+/// A metric family: its name, Prometheus type and help text
+struct Family {
+    name: &'static str,
+    kind: &'static str,
+    help: &'static str,
+}
+
+const FETCH_DURATION: Family = Family {
+    name: "git_partial_fetch_duration_seconds",
+    kind: "summary",
+    help: "Time spent fetching from the remote",
+};
+
+const BEHIND: Family = Family {
+    name: "git_partial_behind_commits",
+    kind: "gauge",
+    help: "Commits on the upstream branch not merged into HEAD",
+};
+
+const CHECKOUT_SIZE: Family = Family {
+    name: "git_partial_checkout_bytes",
+    kind: "gauge",
+    help: "Size of the checked out files",
+};
+
+const CACHE_REQUESTS: Family = Family {
+    name: "git_partial_cache_requests_total",
+    kind: "counter",
+    help: "Requests to serve-cache by result: hit, miss, refresh or stale",
+};
+
+/// Families in the order they are rendered
+const FAMILIES: [&Family; 4] = [&FETCH_DURATION, &BEHIND, &CHECKOUT_SIZE, &CACHE_REQUESTS];
+
+/// Samples by family name, then by sample name and label set
+type Samples = BTreeMap<&'static str, BTreeMap<(String, String), f64>>;
+
+/// Metrics recorded by the daemons of this process
+static SAMPLES: OnceLock<Mutex<Samples>> = OnceLock::new();
+
+fn update(
+    family: &Family,
+    suffix: &str,
+    labels: &[(&str, &str)],
+    apply: impl FnOnce(&mut f64),
+) {
+    let mut samples = SAMPLES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    let key = (format!("{}{}", family.name, suffix), format_labels(labels));
+    apply(
+        samples
+            .entry(family.name)
+            .or_default()
+            .entry(key)
+            .or_insert(0.0),
+    );
+}
+
+/// Formats a label set as `{name="value",...}`, escaping values
+fn format_labels(labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let pairs: Vec<String> = labels
+        .iter()
+        .map(|(name, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", name, value)
+        })
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+/// Records a fetch from `repository` that took `seconds`
+pub fn observe_fetch(
+    repository: &str,
+    seconds: f64,
+) {
+    let labels = [("repository", repository)];
+    update(&FETCH_DURATION, "_sum", &labels, |sum| *sum += seconds);
+    update(&FETCH_DURATION, "_count", &labels, |count| *count += 1.0);
+}
+
+/// Records how far HEAD of `repository` is behind its upstream
+pub fn set_behind(
+    repository: &str,
+    commits: usize,
+) {
+    update(&BEHIND, "", &[("repository", repository)], |value| {
+        *value = commits as f64
+    });
+}
+
+/// Records the size of the checked out files of `repository`
+pub fn set_checkout_bytes(
+    repository: &str,
+    bytes: u64,
+) {
+    update(&CHECKOUT_SIZE, "", &[("repository", repository)], |value| {
+        *value = bytes as f64
+    });
+}
+
+/// Counts a `serve-cache` request by how the cache answered it
+pub fn count_cache_request(result: &str) {
+    update(&CACHE_REQUESTS, "", &[("result", result)], |count| {
+        *count += 1.0
+    });
+}
+
+/// Renders the recorded metrics in the Prometheus text format. Families
+/// without samples are left out.
+pub fn render() -> String {
+    let samples = SAMPLES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    let mut output = String::new();
+    for family in FAMILIES {
+        let Some(family_samples) = samples.get(family.name) else {
+            continue;
+        };
+        output.push_str(&format!("# HELP {} {}\n", family.name, family.help));
+        output.push_str(&format!("# TYPE {} {}\n", family.name, family.kind));
+        for ((name, labels), value) in family_samples {
+            output.push_str(&format!("{}{} {}\n", name, labels, value));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_labels_escapes_values() {
+        assert_eq!(format_labels(&[]), "");
+        assert_eq!(
+            format_labels(&[("repository", "a\"b\\c")]),
+            r#"{repository="a\"b\\c"}"#
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        observe_fetch("https://example.com/render.git", 1.5);
+        observe_fetch("https://example.com/render.git", 0.5);
+        set_behind("https://example.com/render.git", 3);
+        count_cache_request("hit");
+
+        let output = render();
+        assert!(output.contains(
+            "# TYPE git_partial_fetch_duration_seconds summary\n\
+             git_partial_fetch_duration_seconds_count{repository=\"https://example.com/render.git\"} 2\n\
+             git_partial_fetch_duration_seconds_sum{repository=\"https://example.com/render.git\"} 2\n"
+        ), "{}", output);
+        assert!(output.contains(
+            "git_partial_behind_commits{repository=\"https://example.com/render.git\"} 3\n"
+        ));
+        assert!(output.contains("git_partial_cache_requests_total{result=\"hit\"} "));
+    }
+}
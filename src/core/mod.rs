@@ -1,5 +1,43 @@
 // Core functionality will be implemented here
 
+pub mod branch_activity;
+pub mod bundle;
+pub mod cargo;
+pub mod changelog;
+pub mod companions;
+pub mod config;
+pub mod digest;
+pub mod error;
+pub mod fetch_refspec;
+pub mod fuzzy;
+pub mod gradle;
+pub mod history;
+pub mod incoming;
+pub mod journal;
+pub mod large_files;
+pub mod lfs;
+pub mod local_usage;
+pub mod lock;
+pub mod manifest;
+pub mod maven;
 pub mod metadata;
+pub mod metrics;
+pub mod npm;
 pub mod path_selector;
+pub mod pending;
+pub mod policy;
+pub mod profiles;
+pub mod project_graph;
+pub mod pull_state;
+pub mod registry;
 pub mod repository;
+pub mod root_mount;
+pub mod savings;
+pub mod schedule;
+pub mod symlinks;
+pub mod sync_points;
+pub mod targets;
+pub mod tree;
+pub mod tree_cache;
+pub mod url_rewrite;
+pub mod workspace;
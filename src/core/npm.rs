@@ -0,0 +1,195 @@
+use anyhow::Result;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use tracing::debug;
+
+use crate::core::path_selector::join_dir;
+use crate::core::workspace::{Project, ProjectSelection};
+
+/// Dependency fields of a package.json; the package manager links the
+/// workspace packages named in any of them
+const DEPENDENCY_FIELDS: [&str; 4] = [
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+/// Files at the workspace root that the package managers and build
+/// orchestrators read
+const ROOT_FILES: [&str; 10] = [
+    "package.json",
+    "pnpm-workspace.yaml",
+    "pnpm-lock.yaml",
+    "package-lock.json",
+    "yarn.lock",
+    ".npmrc",
+    ".yarnrc.yml",
+    "tsconfig.json",
+    "turbo.json",
+    "nx.json",
+];
+
+/// Resolves the package in `package_dir` to the directories of the workspace
+/// packages it depends on, transitively, plus the root configuration and
+/// the other `packages`' manifests, which the lockfile refers to.
+/// A dependency is internal when it names a workspace package, whatever its
+/// range (`workspace:*`, `*` or a version), or points at a directory with
+/// `link:` or `file:`. `read_file` returns the content of a file at the
+/// inspected revision.
+pub fn select_package<F>(
+    package_dir: &str,
+    packages: &[Project],
+    read_file: F,
+) -> Result<ProjectSelection>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let dirs_by_name: BTreeMap<&str, &str> = packages
+        .iter()
+        .map(|package| (package.name.as_str(), package.dir.as_str()))
+        .collect();
+
+    let mut dirs = BTreeSet::new();
+    let mut queue = VecDeque::from([package_dir.to_string()]);
+    while let Some(dir) = queue.pop_front() {
+        if dirs.contains(&dir) {
+            continue;
+        }
+        let manifest = join_dir(&dir, "package.json")
+            .and_then(|path| read_file(&path))
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok());
+        let Some(manifest) = manifest else {
+            if dir == package_dir {
+                anyhow::bail!("No package.json found in '{}'", package_dir);
+            }
+            debug!("No package.json in '{}'", dir);
+            continue;
+        };
+        queue.extend(internal_dependencies(&manifest, &dir, &dirs_by_name));
+        dirs.insert(dir);
+    }
+
+    let mut selection = ProjectSelection {
+        files: ROOT_FILES.iter().map(|file| file.to_string()).collect(),
+        ..ProjectSelection::default()
+    };
+    selection.files.extend(
+        packages
+            .iter()
+            .filter(|package| !dirs.contains(&package.dir))
+            .filter_map(|package| join_dir(&package.dir, "package.json")),
+    );
+    // `/**` would be everything; the root package is covered by the root files
+    dirs.remove("");
+    selection.dirs = dirs;
+    debug!("Selected package: {:?}", selection);
+    Ok(selection)
+}
+
+/// The directories of the workspace packages a package.json depends on
+pub fn internal_dependencies(
+    manifest: &Value,
+    package_dir: &str,
+    dirs_by_name: &BTreeMap<&str, &str>,
+) -> Vec<String> {
+    let mut dirs = Vec::new();
+    for field in DEPENDENCY_FIELDS {
+        let Some(dependencies) = manifest[field].as_object() else {
+            continue;
+        };
+        for (name, spec) in dependencies {
+            let spec = spec.as_str().unwrap_or_default();
+            let local = spec
+                .strip_prefix("link:")
+                .or_else(|| spec.strip_prefix("file:"))
+                .filter(|path| !path.ends_with(".tgz"));
+            if let Some(path) = local {
+                dirs.extend(join_dir(package_dir, path));
+            } else if let Some(dir) = dirs_by_name.get(name.as_str()) {
+                dirs.push(dir.to_string());
+            }
+        }
+    }
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::workspace::WorkspaceKind;
+    use std::collections::HashMap;
+
+    fn package(
+        name: &str,
+        dir: &str,
+    ) -> Project {
+        Project {
+            name: name.to_string(),
+            dir: dir.to_string(),
+            kind: WorkspaceKind::Pnpm,
+        }
+    }
+
+    #[test]
+    fn test_internal_dependencies() {
+        let manifest: Value = serde_json::from_str(
+            r#"{
+                "dependencies": {"@acme/ui": "workspace:*", "react": "^18.0.0"},
+                "devDependencies": {"@acme/config": "*", "fixtures": "link:../../fixtures"},
+                "optionalDependencies": {"vendored": "file:../vendored.tgz"}
+            }"#,
+        )
+        .unwrap();
+        let dirs_by_name = BTreeMap::from([
+            ("@acme/ui", "packages/ui"),
+            ("@acme/config", "packages/config"),
+        ]);
+
+        let mut dirs = internal_dependencies(&manifest, "apps/web", &dirs_by_name);
+        dirs.sort();
+        assert_eq!(dirs, vec!["fixtures", "packages/config", "packages/ui"]);
+    }
+
+    #[test]
+    fn test_select_package() {
+        let files = HashMap::from([
+            (
+                "apps/web/package.json",
+                r#"{"name": "web", "dependencies": {"@acme/ui": "workspace:^"}}"#,
+            ),
+            (
+                "packages/ui/package.json",
+                r#"{"name": "@acme/ui", "dependencies": {"@acme/tokens": "workspace:*"}}"#,
+            ),
+            (
+                "packages/tokens/package.json",
+                r#"{"name": "@acme/tokens"}"#,
+            ),
+            ("apps/admin/package.json", r#"{"name": "admin"}"#),
+            ("pnpm-lock.yaml", ""),
+        ]);
+        let packages = vec![
+            package("web", "apps/web"),
+            package("admin", "apps/admin"),
+            package("@acme/ui", "packages/ui"),
+            package("@acme/tokens", "packages/tokens"),
+        ];
+
+        let selection = select_package("apps/web", &packages, |path| {
+            files.get(path).map(|content| content.to_string())
+        })
+        .unwrap();
+
+        assert_eq!(
+            selection.patterns(|file| files.contains_key(file)),
+            vec![
+                "apps/web/**",
+                "packages/tokens/**",
+                "packages/ui/**",
+                "apps/admin/package.json",
+                "pnpm-lock.yaml",
+            ]
+        );
+    }
+}
@@ -1,5 +1,7 @@
+use anyhow::Result;
 use glob::Pattern;
 use std::path::Path;
+use unicode_normalization::UnicodeNormalization;
 
 /// Represents a set of glob patterns for selecting paths.
 /// TODO: This struct and its methods are not yet integrated into the main commands.
@@ -10,12 +12,14 @@ pub struct PathSelector {
 }
 
 impl PathSelector {
-    /// Creates a new PathSelector with the given glob patterns
+    /// Creates a new PathSelector with the given glob patterns.
+    /// Brace alternatives such as `src/{app,lib}/**` are expanded first.
     #[allow(dead_code)] // TODO: Not yet integrated
     pub fn new(patterns: Vec<&str>) -> Self {
         let compiled_patterns = patterns
             .into_iter()
-            .map(|p| Pattern::new(p).expect("Invalid glob pattern"))
+            .flat_map(expand_braces)
+            .map(|p| Pattern::new(&p).expect("Invalid glob pattern"))
             .collect();
 
         PathSelector {
@@ -43,10 +47,637 @@ impl PathSelector {
     }
 }
 
+/// Expands brace alternatives like a shell: `src/{app,lib}/**` becomes
+/// `src/app/**` and `src/lib/**`, in order. Groups may be nested or repeated.
+/// Braces without a comma or without a partner are kept literally.
+pub fn expand_braces(pattern: &str) -> Vec<String> {
+    for (open, c) in pattern.char_indices() {
+        if c != '{' {
+            continue;
+        }
+
+        let mut depth = 0;
+        let mut bounds = vec![open];
+        let mut close = None;
+        for (offset, c) in pattern[open..].char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        close = Some(open + offset);
+                        break;
+                    }
+                }
+                ',' if depth == 1 => bounds.push(open + offset),
+                _ => {}
+            }
+        }
+        let Some(close) = close else { continue };
+        if bounds.len() == 1 {
+            continue;
+        }
+        bounds.push(close);
+
+        let (prefix, suffix) = (&pattern[..open], &pattern[close + 1..]);
+        return bounds
+            .windows(2)
+            .flat_map(|window| {
+                let alternative = &pattern[window[0] + 1..window[1]];
+                expand_braces(&format!("{}{}{}", prefix, alternative, suffix))
+            })
+            .collect();
+    }
+    vec![pattern.to_string()]
+}
+
+/// Expands the braces of every pattern, dropping repeated results.
+/// Accented names are normalized for the platform (see [`normalize_unicode`]).
+pub fn expand_patterns(patterns: &[String]) -> Vec<String> {
+    let mut expanded: Vec<String> = Vec::new();
+    for pattern in patterns.iter().flat_map(|pattern| expand_braces(pattern)) {
+        let pattern = normalize_unicode(&pattern);
+        if !expanded.contains(&pattern) {
+            expanded.push(pattern);
+        }
+    }
+    expanded
+}
+
+/// Replaces the `${NAME}` variables of a pattern, such as
+/// `services/${TEAM}/**`, with their values from `lookup`. Fails with the
+/// names `lookup` has no value for. A `$` not starting a variable is kept.
+pub fn substitute_variables<F>(
+    pattern: &str,
+    lookup: F,
+) -> Result<String, Vec<String>>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut substituted = String::new();
+    let mut undefined = Vec::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find("${") {
+        let name_len = rest[start + 2..].find('}').filter(|&len| {
+            let name = &rest[start + 2..start + 2 + len];
+            name.chars().next().is_some_and(|c| !c.is_ascii_digit())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+        let Some(name_len) = name_len else {
+            substituted.push_str(&rest[..start + 2]);
+            rest = &rest[start + 2..];
+            continue;
+        };
+        let name = &rest[start + 2..start + 2 + name_len];
+        substituted.push_str(&rest[..start]);
+        match lookup(name) {
+            Some(value) => substituted.push_str(&value),
+            None if !undefined.iter().any(|undefined| undefined == name) => {
+                undefined.push(name.to_string())
+            }
+            None => {}
+        }
+        rest = &rest[start + 3 + name_len..];
+    }
+    substituted.push_str(rest);
+    if undefined.is_empty() {
+        Ok(substituted)
+    } else {
+        Err(undefined)
+    }
+}
+
+/// Composes accented characters (NFC) on macOS. Names completed by the
+/// shell there come decomposed (NFD), while git stores them composed
+/// (`core.precomposeUnicode`). Other platforms keep the pattern as given.
+pub fn normalize_unicode(pattern: &str) -> String {
+    if cfg!(target_os = "macos") {
+        pattern.nfc().collect()
+    } else {
+        pattern.to_string()
+    }
+}
+
+/// Returns the composed (NFC) and decomposed (NFD) forms of a pattern that
+/// differ from it, for trees whose accented names use the other form
+pub fn unicode_variants(pattern: &str) -> Vec<String> {
+    let mut variants: Vec<String> = Vec::new();
+    for variant in [pattern.nfc().collect::<String>(), pattern.nfd().collect()] {
+        if variant != pattern && !variants.contains(&variant) {
+            variants.push(variant);
+        }
+    }
+    variants
+}
+
+/// Resolves a pattern given from the subdirectory `prefix` (e.g. `src/app`)
+/// into one relative to the repository root, like git pathspecs.
+/// `./` and `../` are normalized; patterns starting with `:/` or `/` are
+/// already root-relative. Fails for patterns that leave the repository.
+pub fn resolve_relative(
+    pattern: &str,
+    prefix: &str,
+) -> Result<String> {
+    if let Some(rooted) = pattern.strip_prefix(":/") {
+        return Ok(rooted.to_string());
+    }
+    if pattern.starts_with('/') || prefix.is_empty() {
+        return Ok(pattern.to_string());
+    }
+
+    let mut parts: Vec<&str> = prefix.split('/').filter(|part| !part.is_empty()).collect();
+    for part in pattern.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                if parts.pop().is_none() {
+                    anyhow::bail!("Path '{}' is outside the repository", pattern);
+                }
+            }
+            _ => parts.push(part),
+        }
+    }
+    if parts.is_empty() {
+        anyhow::bail!("Path '{}' selects the whole repository", pattern);
+    }
+    // A pattern naming the current directory or a parent selects all of it
+    let names_directory = pattern
+        .split('/')
+        .rfind(|part| !part.is_empty())
+        .is_none_or(|last| last == "." || last == "..");
+    let mut resolved = parts.join("/");
+    if names_directory {
+        resolved.push_str("/**");
+    }
+    Ok(resolved)
+}
+
+/// Joins a relative path to a directory of the repository, resolving `..`.
+/// Returns `None` for paths leaving the repository.
+pub fn join_dir(
+    dir: &str,
+    relative: &str,
+) -> Option<String> {
+    let mut parts: Vec<&str> = dir.split('/').filter(|part| !part.is_empty()).collect();
+    for part in relative.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// Returns the patterns that do not cover any of the given repository paths.
+pub fn unmatched_patterns(
+    patterns: &[String],
+    paths: &[String],
+) -> Vec<String> {
+    patterns
+        .iter()
+        .filter(|raw| {
+            let pattern = compile_sparse(raw);
+            !paths.iter().any(|path| pattern_covers(&pattern, path))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Rewrites a pattern for files that moved, given `(old, new)` path pairs.
+/// Each move replaces the leading directories that differ, keeping the
+/// longest common tail (`services/api/x.rs` -> `apps/api/x.rs` maps
+/// `services` to `apps`); the rewrite most moves agree on wins. Returns
+/// `None` when no move applies to the pattern.
+pub fn relocate_pattern(
+    pattern: &str,
+    moves: &[(String, String)],
+) -> Option<String> {
+    let trimmed = pattern.trim_start_matches('/');
+    let mut candidates: Vec<(String, usize)> = Vec::new();
+    for (old, new) in moves {
+        let old_parts: Vec<&str> = old.split('/').collect();
+        let new_parts: Vec<&str> = new.split('/').collect();
+        let common = old_parts
+            .iter()
+            .rev()
+            .zip(new_parts.iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let old_prefix = old_parts[..old_parts.len() - common].join("/");
+        let new_prefix = new_parts[..new_parts.len() - common].join("/");
+        let relocated = if old_prefix.is_empty() {
+            format!("{}/{}", new_prefix, trimmed)
+        } else if trimmed == old_prefix {
+            new_prefix
+        } else if let Some(rest) = trimmed.strip_prefix(&format!("{}/", old_prefix)) {
+            if new_prefix.is_empty() {
+                rest.to_string()
+            } else {
+                format!("{}/{}", new_prefix, rest)
+            }
+        } else {
+            continue;
+        };
+        match candidates.iter_mut().find(|(c, _)| *c == relocated) {
+            Some((_, count)) => *count += 1,
+            None => candidates.push((relocated, 1)),
+        }
+    }
+    // The first of the most agreed on, for a stable answer
+    let best = candidates.iter().map(|(_, count)| *count).max()?;
+    candidates
+        .into_iter()
+        .find(|(_, count)| *count == best)
+        .map(|(relocated, _)| relocated)
+}
+
+/// Compiles a sparse checkout pattern into a glob relative to the repository root.
+/// Patterns that are not valid globs are matched literally.
+pub fn compile_sparse(raw: &str) -> Pattern {
+    let trimmed = raw.trim_start_matches('/');
+    Pattern::new(trimmed).unwrap_or_else(|_| Pattern::new(&Pattern::escape(trimmed)).unwrap())
+}
+
+/// Checks a single pattern against a path and its parent directories,
+/// mirroring how sparse checkout patterns select whole directories
+pub fn pattern_covers(
+    pattern: &Pattern,
+    path: &str,
+) -> bool {
+    let mut candidate = path;
+    loop {
+        if pattern.matches(candidate) {
+            return true;
+        }
+        match candidate.rfind('/') {
+            Some(index) => candidate = &candidate[..index],
+            None => return false,
+        }
+    }
+}
+
+/// Returns the paths not covered by any of the sparse patterns
+pub fn outside_patterns<'a, I>(
+    paths: &[String],
+    patterns: I,
+) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    let compiled: Vec<_> = patterns
+        .into_iter()
+        .map(|raw| compile_sparse(raw))
+        .collect();
+    paths
+        .iter()
+        .filter(|path| !compiled.iter().any(|pattern| pattern_covers(pattern, path)))
+        .cloned()
+        .collect()
+}
+
+/// Returns the paths that one of `patterns` covers
+pub fn covered_paths<'a, I>(
+    paths: &[String],
+    patterns: I,
+) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    let compiled: Vec<_> = patterns
+        .into_iter()
+        .map(|raw| compile_sparse(raw))
+        .collect();
+    paths
+        .iter()
+        .filter(|path| compiled.iter().any(|pattern| pattern_covers(pattern, path)))
+        .cloned()
+        .collect()
+}
+
+/// Converts cone mode directories into equivalent git-partial patterns.
+/// Cone mode always includes the files at the repository root, so those are listed explicitly.
+pub fn cone_to_patterns(
+    dirs: &[String],
+    root_files: &[String],
+) -> Vec<String> {
+    let mut patterns: Vec<String> = root_files.to_vec();
+    patterns.extend(
+        dirs.iter()
+            .map(|dir| format!("{}/**", dir.trim_matches('/'))),
+    );
+    patterns
+}
+
+/// Converts git-partial patterns into cone mode directories, the inverse of
+/// `cone_to_patterns`. Returns `None` unless every pattern selects whole
+/// directories. Root-level names are kept: cone mode includes root files
+/// anyway, and a root directory of that name is included whole.
+pub fn patterns_to_cone(patterns: &[String]) -> Option<Vec<String>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            let pattern = pattern.trim_start_matches('/');
+            let dir = match pattern.strip_suffix("/**") {
+                Some(dir) => dir,
+                None if !pattern.contains('/') => pattern,
+                None => return None,
+            };
+            let is_glob = dir.contains(['*', '?', '[']);
+            (!dir.is_empty() && !is_glob).then(|| dir.to_string())
+        })
+        .collect()
+}
+
+/// Extracts the directories selected by a cone mode sparse checkout file.
+/// Parent directories appear as `/a/` followed by `!/a/*/`; only the
+/// directories that are included recursively are returned.
+pub fn parse_cone_file(content: &str) -> Vec<String> {
+    let lines: Vec<&str> = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+
+    lines
+        .iter()
+        .filter(|line| !line.starts_with('!') && **line != "/*")
+        .filter_map(|line| {
+            let dir = line.trim_matches('/');
+            let parent_only = format!("!/{}/*/", dir);
+            (!dir.is_empty() && !lines.contains(&parent_only.as_str())).then(|| dir.to_string())
+        })
+        .collect()
+}
+
+/// Writes the lines of a cone mode sparse checkout file for `dirs`, the
+/// inverse of `parse_cone_file`: root files, then each parent directory's
+/// own files, then the directories themselves
+pub fn cone_file(dirs: &[String]) -> Vec<String> {
+    let mut dirs: Vec<&str> = dirs.iter().map(|dir| dir.trim_matches('/')).collect();
+    dirs.sort();
+    dirs.dedup();
+
+    let mut lines = vec!["/*".to_string(), "!/*/".to_string()];
+    let mut parents: Vec<&str> = Vec::new();
+    for dir in &dirs {
+        for (index, _) in dir.match_indices('/') {
+            let parent = &dir[..index];
+            if !parents.contains(&parent) && !dirs.contains(&parent) {
+                parents.push(parent);
+            }
+        }
+    }
+    parents.sort();
+    for parent in parents {
+        lines.push(format!("/{}/", parent));
+        lines.push(format!("!/{}/*/", parent));
+    }
+    lines.extend(dirs.iter().map(|dir| format!("/{}/", dir)));
+    lines
+}
+
+/// Checks whether the sparse checkout selects the file `path`, the way git
+/// sets skip-worktree bits. In cone mode `patterns` are the `dir/**` ones
+/// `applied_sparse_patterns` returns, and the files at the root and directly
+/// in a parent of a cone directory are selected too. Otherwise the last
+/// pattern covering the path decides, and a `!` pattern excludes it.
+pub fn sparse_includes(
+    cone: bool,
+    patterns: &[String],
+    path: &str,
+) -> bool {
+    if cone {
+        let Some((parent, _)) = path.rsplit_once('/') else {
+            return true;
+        };
+        return patterns
+            .iter()
+            .map(|pattern| pattern.trim_end_matches("/**").trim_matches('/'))
+            .any(|dir| {
+                parent == dir
+                    || parent.starts_with(&format!("{}/", dir))
+                    || dir.starts_with(&format!("{}/", parent))
+            });
+    }
+    patterns.iter().fold(false, |included, pattern| {
+        let (negated, pattern) = match pattern.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern.as_str()),
+        };
+        if pattern_covers(&compile_sparse(pattern.trim_end_matches('/')), path) {
+            !negated
+        } else {
+            included
+        }
+    })
+}
+
+/// Extracts the patterns of a non-cone sparse checkout file
+pub fn parse_pattern_file(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.to_string())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_outside_patterns() {
+        let patterns = vec!["src/**".to_string(), "README.md".to_string()];
+        let paths = vec![
+            "src/main.rs".to_string(),
+            "README.md".to_string(),
+            "docs/guide.md".to_string(),
+        ];
+
+        assert_eq!(outside_patterns(&paths, &patterns), vec!["docs/guide.md"]);
+        assert_eq!(
+            covered_paths(&paths, &patterns),
+            vec!["src/main.rs", "README.md"]
+        );
+    }
+
+    #[test]
+    fn test_expand_braces() {
+        assert_eq!(
+            expand_braces("src/{frontend,shared}/**"),
+            vec!["src/frontend/**", "src/shared/**"]
+        );
+        assert_eq!(
+            expand_braces("{a,b}/{x,y}.md"),
+            vec!["a/x.md", "a/y.md", "b/x.md", "b/y.md"]
+        );
+        assert_eq!(
+            expand_braces("docs/{api,guide/{en,ja}}/**"),
+            vec!["docs/api/**", "docs/guide/en/**", "docs/guide/ja/**"]
+        );
+        assert_eq!(expand_braces("lib{,s}/**"), vec!["lib/**", "libs/**"]);
+    }
+
+    #[test]
+    fn test_expand_braces_literal() {
+        assert_eq!(expand_braces("src/**"), vec!["src/**"]);
+        assert_eq!(expand_braces("{single}/**"), vec!["{single}/**"]);
+        assert_eq!(expand_braces("{open/**"), vec!["{open/**"]);
+        assert_eq!(expand_braces("{x{a,b}"), vec!["{xa", "{xb"]);
+    }
+
+    #[test]
+    fn test_expand_patterns_dedups() {
+        let patterns = vec!["src/{a,b}/**".to_string(), "src/{b,c}/**".to_string()];
+        assert_eq!(
+            expand_patterns(&patterns),
+            vec!["src/a/**", "src/b/**", "src/c/**"]
+        );
+    }
+
+    #[test]
+    fn test_substitute_variables() {
+        let lookup = |name: &str| match name {
+            "TEAM" => Some("payments".to_string()),
+            "OS" => Some("linux".to_string()),
+            _ => None,
+        };
+
+        assert_eq!(
+            substitute_variables("services/${TEAM}/**", lookup),
+            Ok("services/payments/**".to_string())
+        );
+        assert_eq!(
+            substitute_variables("${OS}-toolchain/${TEAM}.md", lookup),
+            Ok("linux-toolchain/payments.md".to_string())
+        );
+        assert_eq!(
+            substitute_variables("cost$/${1X}/{a,b}", lookup),
+            Ok("cost$/${1X}/{a,b}".to_string())
+        );
+        assert_eq!(
+            substitute_variables("${SQUAD}/${REGION}/${SQUAD}", lookup),
+            Err(vec!["SQUAD".to_string(), "REGION".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_resolve_relative() {
+        let resolve = |pattern| resolve_relative(pattern, "src/frontend");
+        assert_eq!(
+            resolve("./components/**").unwrap(),
+            "src/frontend/components/**"
+        );
+        assert_eq!(resolve("*.ts").unwrap(), "src/frontend/*.ts");
+        assert_eq!(resolve("../shared/**").unwrap(), "src/shared/**");
+        assert_eq!(resolve(".").unwrap(), "src/frontend/**");
+        assert_eq!(resolve("..").unwrap(), "src/**");
+        assert_eq!(resolve(":/docs/**").unwrap(), "docs/**");
+        assert_eq!(resolve("/README.md").unwrap(), "/README.md");
+        assert!(resolve("../../..").is_err());
+        assert!(resolve("../..").is_err());
+    }
+
+    #[test]
+    fn test_join_dir() {
+        assert_eq!(
+            join_dir("crates/app", "../core"),
+            Some("crates/core".to_string())
+        );
+        assert_eq!(
+            join_dir("", "./crates/core/"),
+            Some("crates/core".to_string())
+        );
+        assert_eq!(join_dir("app", "../.."), None);
+    }
+
+    #[test]
+    fn test_resolve_relative_at_root() {
+        assert_eq!(resolve_relative("./docs/**", "").unwrap(), "./docs/**");
+        assert_eq!(resolve_relative(":/docs/**", "").unwrap(), "docs/**");
+    }
+
+    #[test]
+    fn test_parse_cone_file() {
+        let content = "/*\n!/*/\n/src/\n!/src/*/\n/src/app/\n/docs/\n";
+        assert_eq!(parse_cone_file(content), vec!["src/app", "docs"]);
+    }
+
+    #[test]
+    fn test_cone_file() {
+        let dirs = vec!["src/app".to_string(), "docs".to_string()];
+        let lines = cone_file(&dirs);
+
+        assert_eq!(
+            lines,
+            vec!["/*", "!/*/", "/src/", "!/src/*/", "/docs/", "/src/app/"]
+        );
+        assert_eq!(parse_cone_file(&lines.join("\n")), vec!["docs", "src/app"]);
+    }
+
+    #[test]
+    fn test_sparse_includes() {
+        let cone = vec!["src/app/**".to_string()];
+        assert!(sparse_includes(true, &cone, "README.md"));
+        assert!(sparse_includes(true, &cone, "src/build.rs"));
+        assert!(sparse_includes(true, &cone, "src/app/lib/mod.rs"));
+        assert!(!sparse_includes(true, &cone, "src/web/main.rs"));
+        assert!(!sparse_includes(true, &cone, "docs/index.md"));
+
+        let patterns = vec![
+            "/README.md".to_string(),
+            "src/**".to_string(),
+            "!src/tmp/".to_string(),
+        ];
+        assert!(sparse_includes(false, &patterns, "README.md"));
+        assert!(sparse_includes(false, &patterns, "src/main.rs"));
+        assert!(!sparse_includes(false, &patterns, "src/tmp/cache.bin"));
+        assert!(!sparse_includes(false, &patterns, "docs/index.md"));
+    }
+
+    #[test]
+    fn test_parse_pattern_file() {
+        let content = "# comment\n/README.md\n\nsrc/**\n!src/tmp/\n";
+        assert_eq!(
+            parse_pattern_file(content),
+            vec!["/README.md", "src/**", "!src/tmp/"]
+        );
+    }
+
+    #[test]
+    fn test_cone_to_patterns() {
+        let dirs = vec!["src/app".to_string(), "/docs/".to_string()];
+        let root_files = vec!["README.md".to_string()];
+        assert_eq!(
+            cone_to_patterns(&dirs, &root_files),
+            vec!["README.md", "src/app/**", "docs/**"]
+        );
+    }
+
+    #[test]
+    fn test_patterns_to_cone() {
+        let patterns = vec![
+            "README.md".to_string(),
+            "src/app/**".to_string(),
+            "/docs/**".to_string(),
+        ];
+        assert_eq!(
+            patterns_to_cone(&patterns),
+            Some(vec![
+                "README.md".to_string(),
+                "src/app".to_string(),
+                "docs".to_string()
+            ])
+        );
+        assert_eq!(patterns_to_cone(&["src/main.rs".to_string()]), None);
+        assert_eq!(patterns_to_cone(&["*.md".to_string()]), None);
+        assert_eq!(patterns_to_cone(&["src/*/lib/**".to_string()]), None);
+    }
+
     #[test]
     fn test_path_matching_basic() {
         let selector = PathSelector::new(vec!["src/frontend/**", "*.md"]);
@@ -72,6 +703,66 @@ mod tests {
         assert!(!selector.matches("path/to/exact.txt"));
     }
 
+    #[test]
+    fn test_unmatched_patterns() {
+        let paths = vec!["README.md".to_string(), "src/core.rs".to_string()];
+        let patterns = vec![
+            "/README.md".to_string(),
+            "src/**".to_string(),
+            "docs/**".to_string(),
+            "src".to_string(),
+            "srcs".to_string(),
+            "[invalid".to_string(),
+        ];
+
+        assert_eq!(
+            unmatched_patterns(&patterns, &paths),
+            vec![
+                "docs/**".to_string(),
+                "srcs".to_string(),
+                "[invalid".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unicode_variants() {
+        let composed = "docs/r\u{e9}sum\u{e9}/**";
+        let decomposed = "docs/re\u{301}sume\u{301}/**";
+
+        assert_eq!(unicode_variants(composed), vec![decomposed.to_string()]);
+        assert_eq!(unicode_variants(decomposed), vec![composed.to_string()]);
+        assert!(unicode_variants("src/**").is_empty());
+    }
+
+    #[test]
+    fn test_relocate_pattern() {
+        let moves = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs
+                .iter()
+                .map(|(old, new)| (old.to_string(), new.to_string()))
+                .collect()
+        };
+
+        let renamed = moves(&[
+            ("services/api/src/main.rs", "apps/api/src/main.rs"),
+            ("services/api/Cargo.toml", "apps/api/Cargo.toml"),
+        ]);
+        assert_eq!(
+            relocate_pattern("services/api/**", &renamed),
+            Some("apps/api/**".to_string())
+        );
+        assert_eq!(
+            relocate_pattern("README.md", &moves(&[("README.md", "docs/README.md")])),
+            Some("docs/README.md".to_string())
+        );
+        assert_eq!(
+            relocate_pattern("old.txt", &moves(&[("old.txt", "new.txt")])),
+            Some("new.txt".to_string())
+        );
+        assert_eq!(relocate_pattern("**/*.proto", &renamed), None);
+    }
+
     #[test]
     fn test_path_matching_complex() {
         // Use simpler pattern matching for tests
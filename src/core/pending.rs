@@ -0,0 +1,94 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Paths added while offline whose blobs still have to be fetched.
+/// Stored in `.gitpartial/pending.json` and drained by `sync`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PendingFetches {
+    /// Sparse paths waiting for their files to be checked out
+    pub paths: Vec<String>,
+}
+
+impl PendingFetches {
+    /// Queues paths, skipping ones already queued
+    pub fn push(
+        &mut self,
+        paths: &[String],
+    ) {
+        for path in paths {
+            if !self.paths.contains(path) {
+                self.paths.push(path.clone());
+            }
+        }
+    }
+
+    /// Loads the queue, which is empty when the file does not exist
+    pub fn load<P: AsRef<Path>>(repo_path: P) -> Result<Self> {
+        let pending_path = Self::pending_path(&repo_path);
+        if !pending_path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&pending_path)
+            .with_context(|| format!("Failed to read pending fetches from {:?}", pending_path))?;
+        serde_json::from_str(&content).context("Failed to deserialize pending fetches")
+    }
+
+    /// Saves the queue next to the metadata
+    pub fn save<P: AsRef<Path>>(
+        &self,
+        repo_path: P,
+    ) -> Result<()> {
+        let pending_path = Self::pending_path(&repo_path);
+        let serialized =
+            serde_json::to_string_pretty(self).context("Failed to serialize pending fetches")?;
+        fs::write(&pending_path, serialized)
+            .with_context(|| format!("Failed to write pending fetches to {:?}", pending_path))
+    }
+
+    /// Removes the queue once everything has been fetched
+    pub fn clear<P: AsRef<Path>>(repo_path: P) -> Result<()> {
+        let pending_path = Self::pending_path(&repo_path);
+        if pending_path.exists() {
+            fs::remove_file(&pending_path)
+                .with_context(|| format!("Failed to remove {:?}", pending_path))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the path to the queue file
+    fn pending_path<P: AsRef<Path>>(repo_path: P) -> PathBuf {
+        repo_path.as_ref().join(".gitpartial").join("pending.json")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pending_roundtrip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join(".gitpartial")).unwrap();
+        assert!(PendingFetches::load(temp_dir.path())
+            .unwrap()
+            .paths
+            .is_empty());
+
+        let mut pending = PendingFetches::default();
+        pending.push(&["docs/**".to_string(), "docs/**".to_string()]);
+        pending.save(temp_dir.path()).unwrap();
+        assert_eq!(
+            PendingFetches::load(temp_dir.path()).unwrap().paths,
+            vec!["docs/**"]
+        );
+
+        PendingFetches::clear(temp_dir.path()).unwrap();
+        assert!(PendingFetches::load(temp_dir.path())
+            .unwrap()
+            .paths
+            .is_empty());
+    }
+}
@@ -0,0 +1,248 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::core::path_selector;
+use crate::core::tree::{self, EntryKind, TreeEntry};
+use crate::utils::disk;
+
+/// File at the repository root that holds the organization's policy
+pub const POLICY_FILE: &str = ".gitpartial-policy.toml";
+
+/// Rules the sparse paths of every clone must follow
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    /// Largest checkout allowed, in bytes
+    pub max_checkout_bytes: Option<u64>,
+
+    /// Patterns whose files must never be checked out, e.g. `secrets/**`
+    #[serde(default)]
+    pub forbidden: Vec<String>,
+
+    /// Patterns every checkout must include, e.g. `tools/lint/**`
+    #[serde(default)]
+    pub mandatory: Vec<String>,
+}
+
+impl Policy {
+    /// Parses a policy file
+    pub fn parse(content: &str) -> Result<Self> {
+        toml::from_str(content).context("Failed to parse policy file")
+    }
+
+    /// Returns a message for every rule the patterns break.
+    /// The size limit is only checked when `entries` carry blob sizes.
+    pub fn violations(
+        &self,
+        entries: &[TreeEntry],
+        patterns: &[String],
+    ) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        let selected = tree::select_blobs(entries, patterns);
+        for forbidden in &self.forbidden {
+            let pattern = path_selector::compile_sparse(forbidden);
+            if let Some(entry) = selected
+                .iter()
+                .find(|entry| path_selector::pattern_covers(&pattern, &entry.path))
+            {
+                violations.push(format!(
+                    "'{}' is forbidden, but the paths check out {}",
+                    forbidden, entry.path
+                ));
+            }
+        }
+
+        for mandatory in &self.mandatory {
+            if !includes(entries, patterns, mandatory) {
+                violations.push(format!("'{}' is mandatory but not included", mandatory));
+            }
+        }
+
+        if let Some(max_bytes) = self.max_checkout_bytes {
+            let bytes: u64 = selected.iter().filter_map(|entry| entry.size).sum();
+            if bytes > max_bytes {
+                violations.push(format!(
+                    "the checkout is {}, over the limit of {}",
+                    disk::format_size(bytes),
+                    disk::format_size(max_bytes)
+                ));
+            }
+        }
+
+        violations
+    }
+}
+
+/// Whether the patterns check out every file of `mandatory`, so a broader
+/// pattern such as `tools/**` satisfies `tools/lint/**`. A mandatory
+/// pattern that matches no file is satisfied when a pattern covers it.
+fn includes(
+    entries: &[TreeEntry],
+    patterns: &[String],
+    mandatory: &str,
+) -> bool {
+    let required = path_selector::compile_sparse(mandatory);
+    let files: Vec<String> = entries
+        .iter()
+        .filter(|entry| entry.kind == EntryKind::Blob)
+        .filter(|entry| path_selector::pattern_covers(&required, &entry.path))
+        .map(|entry| entry.path.clone())
+        .collect();
+    if files.is_empty() {
+        let mandatory = mandatory.trim_start_matches('/');
+        return patterns.iter().any(|raw| {
+            path_selector::pattern_covers(&path_selector::compile_sparse(raw), mandatory)
+        });
+    }
+    path_selector::outside_patterns(&files, patterns).is_empty()
+}
+
+/// Size and file count a checkout may reach before `clone` and `add-paths`
+/// refuse it, set per user in `max_checkout_size` and `max_files` rather
+/// than by the organization
+#[derive(Debug, Default, PartialEq)]
+pub struct CheckoutLimits {
+    pub max_bytes: Option<u64>,
+    pub max_files: Option<usize>,
+}
+
+impl CheckoutLimits {
+    /// Returns a message for every limit the checkout of `patterns`
+    /// exceeds. The size limit is only checked when `entries` carry blob
+    /// sizes.
+    pub fn exceeded(
+        &self,
+        entries: &[TreeEntry],
+        patterns: &[String],
+    ) -> Vec<String> {
+        let selected = tree::select_blobs(entries, patterns);
+        let mut exceeded = Vec::new();
+        if let Some(max_bytes) = self.max_bytes {
+            let bytes: u64 = selected.iter().filter_map(|entry| entry.size).sum();
+            if bytes > max_bytes {
+                exceeded.push(format!(
+                    "the checkout is {}, over max_checkout_size ({})",
+                    disk::format_size(bytes),
+                    disk::format_size(max_bytes)
+                ));
+            }
+        }
+        if let Some(max_files) = self.max_files {
+            if selected.len() > max_files {
+                exceeded.push(format!(
+                    "the checkout has {} files, over max_files ({})",
+                    selected.len(),
+                    max_files
+                ));
+            }
+        }
+        exceeded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blob(
+        path: &str,
+        size: u64,
+    ) -> TreeEntry {
+        TreeEntry {
+            path: path.to_string(),
+            kind: EntryKind::Blob,
+            size: Some(size),
+        }
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        let policy = Policy::parse(
+            r#"
+max_checkout_bytes = 1048576
+forbidden = ["secrets/**"]
+mandatory = ["tools/lint/**"]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(policy.max_checkout_bytes, Some(1_048_576));
+        assert_eq!(policy.forbidden, vec!["secrets/**"]);
+        assert!(Policy::parse("unknown = 1").is_err());
+    }
+
+    #[test]
+    fn test_violations() {
+        let entries = vec![
+            blob("src/main.rs", 600),
+            blob("secrets/prod.key", 10),
+            blob("tools/lint/run.sh", 10),
+        ];
+        let policy = Policy {
+            max_checkout_bytes: Some(500),
+            forbidden: vec!["secrets/**".to_string()],
+            mandatory: vec!["tools/lint/**".to_string()],
+        };
+
+        let compliant = vec!["tools/lint/**".to_string()];
+        assert!(policy.violations(&entries, &compliant).is_empty());
+        let broader = vec!["tools/**".to_string()];
+        assert!(policy.violations(&entries, &broader).is_empty());
+
+        let violations = policy.violations(&entries, &["**".to_string()]);
+        assert_eq!(
+            violations,
+            vec![
+                "'secrets/**' is forbidden, but the paths check out secrets/prod.key",
+                "the checkout is 620 B, over the limit of 500 B",
+            ]
+        );
+
+        let violations = policy.violations(&entries, &["src/**".to_string()]);
+        assert_eq!(
+            violations,
+            vec![
+                "'tools/lint/**' is mandatory but not included",
+                "the checkout is 600 B, over the limit of 500 B",
+            ]
+        );
+
+        let policy = Policy {
+            mandatory: vec!["docs/**".to_string()],
+            ..Policy::default()
+        };
+        assert!(policy
+            .violations(&entries, &["docs/**".to_string()])
+            .is_empty());
+        assert!(policy.violations(&entries, &["**".to_string()]).is_empty());
+        assert_eq!(policy.violations(&entries, &broader).len(), 1);
+    }
+
+    #[test]
+    fn test_checkout_limits() {
+        let entries = vec![
+            blob("src/main.rs", 100),
+            blob("datasets/a.csv", 4096),
+            blob("datasets/b.csv", 4096),
+        ];
+        let limits = CheckoutLimits {
+            max_bytes: Some(1024),
+            max_files: Some(2),
+        };
+
+        assert!(limits
+            .exceeded(&entries, &["src/**".to_string()])
+            .is_empty());
+        assert_eq!(
+            limits.exceeded(&entries, &["**".to_string()]),
+            vec![
+                "the checkout is 8.1 KiB, over max_checkout_size (1.0 KiB)",
+                "the checkout has 3 files, over max_files (2)",
+            ]
+        );
+        assert!(CheckoutLimits::default()
+            .exceeded(&entries, &["**".to_string()])
+            .is_empty());
+    }
+}
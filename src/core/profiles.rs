@@ -0,0 +1,76 @@
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+
+/// Sparse paths to apply when switching to branches matching a glob
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BranchProfile {
+    /// Branch name or glob, e.g. `feature/payments/*`
+    pub branch: String,
+
+    /// Paths to check out on matching branches
+    pub paths: Vec<String>,
+}
+
+impl BranchProfile {
+    /// Checks if the profile applies to a branch.
+    /// `*` does not cross `/`; use `**` to match nested branch names.
+    pub fn matches(
+        &self,
+        branch: &str,
+    ) -> bool {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        match Pattern::new(&self.branch) {
+            Ok(pattern) => pattern.matches_with(branch, options),
+            Err(_) => self.branch == branch,
+        }
+    }
+}
+
+/// Returns the first profile matching the branch, in the order they were defined
+pub fn matching_profile<'a>(
+    profiles: &'a [BranchProfile],
+    branch: &str,
+) -> Option<&'a BranchProfile> {
+    profiles.iter().find(|profile| profile.matches(branch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(
+        branch: &str,
+        paths: &[&str],
+    ) -> BranchProfile {
+        BranchProfile {
+            branch: branch.to_string(),
+            paths: paths.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_matching_profile() {
+        let profiles = vec![
+            profile("feature/payments/*", &["payments/**"]),
+            profile("feature/**", &["shared/**"]),
+            profile("main", &["README.md"]),
+        ];
+
+        assert_eq!(
+            matching_profile(&profiles, "feature/payments/refunds").map(|p| &p.paths[0]),
+            Some(&"payments/**".to_string())
+        );
+        assert_eq!(
+            matching_profile(&profiles, "feature/payments/a/b").map(|p| &p.paths[0]),
+            Some(&"shared/**".to_string())
+        );
+        assert_eq!(
+            matching_profile(&profiles, "main").map(|p| p.branch.as_str()),
+            Some("main")
+        );
+        assert_eq!(matching_profile(&profiles, "release/1.0"), None);
+    }
+}
@@ -0,0 +1,261 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use tracing::debug;
+
+use crate::core::npm;
+use crate::core::path_selector::join_dir;
+use crate::core::workspace::{Project, ProjectSelection};
+
+/// Files at the workspace root that Nx and Turborepo read to build the graph
+const ROOT_FILES: [&str; 10] = [
+    "nx.json",
+    "turbo.json",
+    "package.json",
+    "pnpm-workspace.yaml",
+    "pnpm-lock.yaml",
+    "package-lock.json",
+    "yarn.lock",
+    ".npmrc",
+    "tsconfig.base.json",
+    "tsconfig.json",
+];
+
+/// Dependencies between the projects of an Nx or Turborepo workspace,
+/// by project directory
+#[derive(Debug, Default, PartialEq)]
+pub struct ProjectGraph {
+    dependencies: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl ProjectGraph {
+    /// Reads the output of `nx graph --file=stdout`
+    pub fn parse_nx_graph(content: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(content).context("Invalid Nx graph")?;
+        let graph = &value["graph"];
+        let nodes = graph["nodes"]
+            .as_object()
+            .context("Nx graph has no nodes")?;
+        // The root project's root is `.`
+        let dirs: BTreeMap<&str, String> = nodes
+            .iter()
+            .filter_map(|(name, node)| {
+                Some((name.as_str(), join_dir("", node["data"]["root"].as_str()?)?))
+            })
+            .collect();
+
+        let mut dependencies: BTreeMap<String, BTreeSet<String>> = dirs
+            .values()
+            .map(|dir| (dir.clone(), BTreeSet::new()))
+            .collect();
+        for (source, targets) in graph["dependencies"].as_object().into_iter().flatten() {
+            let Some(source_dir) = dirs.get(source.as_str()) else {
+                continue;
+            };
+            // Targets outside `nodes` are npm packages
+            let target_dirs = targets
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|target| dirs.get(target["target"].as_str()?))
+                .cloned();
+            dependencies
+                .entry(source_dir.clone())
+                .or_default()
+                .extend(target_dirs);
+        }
+        Ok(ProjectGraph { dependencies })
+    }
+
+    /// Builds the graph from the projects' manifests, the way Nx does
+    /// without plugins: the workspace packages a `package.json` depends on
+    /// and the `implicitDependencies` of a `project.json`. Turborepo's graph
+    /// is the package graph alone. `read_file` returns the content of a file
+    /// at the inspected revision.
+    pub fn from_manifests<F>(
+        projects: &[Project],
+        read_file: F,
+    ) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let dirs_by_name: BTreeMap<&str, &str> = projects
+            .iter()
+            .map(|project| (project.name.as_str(), project.dir.as_str()))
+            .collect();
+        let read_json = |dir: &str, file: &str| {
+            join_dir(dir, file)
+                .and_then(|path| read_file(&path))
+                .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        };
+
+        let mut dependencies = BTreeMap::new();
+        for project in projects {
+            let mut dirs = BTreeSet::new();
+            if let Some(manifest) = read_json(&project.dir, "package.json") {
+                dirs.extend(npm::internal_dependencies(
+                    &manifest,
+                    &project.dir,
+                    &dirs_by_name,
+                ));
+            }
+            if let Some(config) = read_json(&project.dir, "project.json") {
+                dirs.extend(implicit_dependencies(&config, &dirs_by_name));
+            }
+            dirs.remove(&project.dir);
+            dependencies.insert(project.dir.clone(), dirs);
+        }
+        ProjectGraph { dependencies }
+    }
+
+    /// The project in `dir` and those it depends on, transitively
+    pub fn closure(
+        &self,
+        dir: &str,
+    ) -> BTreeSet<String> {
+        let mut dirs = BTreeSet::new();
+        let mut queue = VecDeque::from([dir.to_string()]);
+        while let Some(dir) = queue.pop_front() {
+            if let Some(dependencies) = self.dependencies.get(&dir) {
+                queue.extend(
+                    dependencies
+                        .iter()
+                        .filter(|dep| !dirs.contains(*dep))
+                        .cloned(),
+                );
+            }
+            dirs.insert(dir);
+        }
+        dirs
+    }
+}
+
+/// The directories of the projects a `project.json` names in
+/// `implicitDependencies`. Negated (`!name`) entries remove a dependency
+/// inferred elsewhere, so they add nothing here.
+fn implicit_dependencies(
+    config: &Value,
+    dirs_by_name: &BTreeMap<&str, &str>,
+) -> Vec<String> {
+    config["implicitDependencies"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .filter_map(|name| dirs_by_name.get(name))
+        .map(|dir| dir.to_string())
+        .collect()
+}
+
+/// Resolves the project in `project_dir` to its directory and those of the
+/// projects it depends on in `graph`, plus the root configuration and the
+/// other `projects`' manifests, which Nx and Turborepo read to build the
+/// graph before running anything.
+pub fn select_project(
+    project_dir: &str,
+    graph: &ProjectGraph,
+    projects: &[Project],
+) -> ProjectSelection {
+    let mut dirs = graph.closure(project_dir);
+    let mut selection = ProjectSelection {
+        files: ROOT_FILES.iter().map(|file| file.to_string()).collect(),
+        ..ProjectSelection::default()
+    };
+    for project in projects
+        .iter()
+        .filter(|project| !dirs.contains(&project.dir))
+    {
+        selection.files.extend(
+            ["package.json", "project.json"]
+                .iter()
+                .filter_map(|file| join_dir(&project.dir, file)),
+        );
+    }
+    dirs.remove("");
+    selection.dirs = dirs;
+    debug!("Selected project: {:?}", selection);
+    selection
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::workspace::WorkspaceKind;
+    use std::collections::HashMap;
+
+    fn project(
+        name: &str,
+        dir: &str,
+        kind: WorkspaceKind,
+    ) -> Project {
+        Project {
+            name: name.to_string(),
+            dir: dir.to_string(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_parse_nx_graph() {
+        let graph = ProjectGraph::parse_nx_graph(
+            r#"{"graph": {
+                "nodes": {
+                    "web": {"name": "web", "type": "app", "data": {"root": "apps/web"}},
+                    "ui": {"name": "ui", "type": "lib", "data": {"root": "libs/ui"}}
+                },
+                "dependencies": {
+                    "web": [
+                        {"source": "web", "target": "ui", "type": "static"},
+                        {"source": "web", "target": "npm:react", "type": "static"}
+                    ],
+                    "ui": []
+                }
+            }}"#,
+        )
+        .unwrap();
+
+        let closure: Vec<String> = graph.closure("apps/web").into_iter().collect();
+        assert_eq!(closure, vec!["apps/web", "libs/ui"]);
+        assert!(ProjectGraph::parse_nx_graph("{}").is_err());
+    }
+
+    #[test]
+    fn test_select_project_from_manifests() {
+        let files = HashMap::from([
+            (
+                "apps/web/project.json",
+                r#"{"name": "web", "implicitDependencies": ["e2e-utils", "!ui"]}"#,
+            ),
+            (
+                "apps/web/package.json",
+                r#"{"name": "web", "dependencies": {"@acme/ui": "*"}}"#,
+            ),
+            ("libs/ui/package.json", r#"{"name": "@acme/ui"}"#),
+            ("libs/e2e-utils/project.json", r#"{"name": "e2e-utils"}"#),
+            ("apps/admin/project.json", r#"{"name": "admin"}"#),
+            ("nx.json", "{}"),
+        ]);
+        let projects = vec![
+            project("web", "apps/web", WorkspaceKind::Nx),
+            project("@acme/ui", "libs/ui", WorkspaceKind::Npm),
+            project("e2e-utils", "libs/e2e-utils", WorkspaceKind::Nx),
+            project("admin", "apps/admin", WorkspaceKind::Nx),
+        ];
+
+        let graph = ProjectGraph::from_manifests(&projects, |path| {
+            files.get(path).map(|content| content.to_string())
+        });
+        let selection = select_project("apps/web", &graph, &projects);
+
+        assert_eq!(
+            selection.patterns(|file| files.contains_key(file)),
+            vec![
+                "apps/web/**",
+                "libs/e2e-utils/**",
+                "libs/ui/**",
+                "apps/admin/project.json",
+                "nx.json",
+            ]
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How `smart-pull` joins a branch that diverged from its upstream
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Integration {
+    /// Replay the local commits on the upstream (`git rebase`)
+    Rebase,
+
+    /// Create a merge commit (`git merge`)
+    Merge,
+}
+
+impl Integration {
+    /// The git command doing the integration
+    pub fn command(self) -> &'static str {
+        match self {
+            Integration::Rebase => "rebase",
+            Integration::Merge => "merge",
+        }
+    }
+}
+
+/// A `smart-pull` that stopped at conflicts, stored in
+/// `.gitpartial/pull.json` until `smart-pull --continue` or `--abort`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PullInProgress {
+    pub integration: Integration,
+
+    /// The upstream revision being integrated, e.g. `origin/main`
+    pub upstream: String,
+
+    /// HEAD before the pull, where `--abort` returns to
+    pub original_head: String,
+}
+
+impl PullInProgress {
+    /// Loads the stopped pull, if any
+    pub fn load<P: AsRef<Path>>(repo_path: P) -> Result<Option<Self>> {
+        let path = Self::state_path(&repo_path);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read pull state from {:?}", path))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .context("Failed to deserialize pull state")
+    }
+
+    /// Saves the state next to the metadata
+    pub fn save<P: AsRef<Path>>(
+        &self,
+        repo_path: P,
+    ) -> Result<()> {
+        let path = Self::state_path(&repo_path);
+        let serialized =
+            serde_json::to_string_pretty(self).context("Failed to serialize pull state")?;
+        fs::write(&path, serialized)
+            .with_context(|| format!("Failed to write pull state to {:?}", path))
+    }
+
+    /// Removes the state once the pull is finished or aborted
+    pub fn clear<P: AsRef<Path>>(repo_path: P) -> Result<()> {
+        let path = Self::state_path(&repo_path);
+        if path.exists() {
+            fs::remove_file(&path).with_context(|| format!("Failed to remove {:?}", path))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the path to the state file
+    fn state_path<P: AsRef<Path>>(repo_path: P) -> PathBuf {
+        repo_path.as_ref().join(".gitpartial").join("pull.json")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pull_state_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".gitpartial")).unwrap();
+        assert_eq!(PullInProgress::load(dir.path()).unwrap(), None);
+
+        let state = PullInProgress {
+            integration: Integration::Rebase,
+            upstream: "origin/main".to_string(),
+            original_head: "abc1234".to_string(),
+        };
+        state.save(dir.path()).unwrap();
+        assert_eq!(PullInProgress::load(dir.path()).unwrap(), Some(state));
+
+        PullInProgress::clear(dir.path()).unwrap();
+        assert_eq!(PullInProgress::load(dir.path()).unwrap(), None);
+    }
+}
@@ -0,0 +1,71 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::config::GlobalConfig;
+
+/// The clones made on this machine, one absolute path per line, in
+/// `repositories` next to the global config. Read by `serve-http`.
+pub fn registry_path() -> Option<PathBuf> {
+    Some(GlobalConfig::path()?.with_file_name("repositories"))
+}
+
+/// Reads the registry's paths in order, without blank lines or duplicates
+pub fn parse(content: &str) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for line in content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        let path = PathBuf::from(line);
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// The registered clones; a missing registry lists none
+pub fn load() -> Vec<PathBuf> {
+    registry_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|content| parse(&content))
+        .unwrap_or_default()
+}
+
+/// Add a clone to the registry, unless it is already listed
+pub fn register(repo_path: &Path) -> Result<()> {
+    let path = registry_path().context("Cannot locate the config directory (HOME is not set)")?;
+    let repo_path = fs::canonicalize(repo_path)
+        .with_context(|| format!("Failed to resolve {}", repo_path.display()))?;
+    let mut paths = load();
+    if paths.contains(&repo_path) {
+        return Ok(());
+    }
+    paths.push(repo_path);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create directory: {:?}", dir))?;
+    }
+    let mut content = String::new();
+    for path in &paths {
+        content.push_str(&path.to_string_lossy());
+        content.push('\n');
+    }
+    fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_skips_blanks_and_duplicates() {
+        assert_eq!(
+            parse("/work/app\n\n/work/web\n/work/app\n"),
+            vec![PathBuf::from("/work/app"), PathBuf::from("/work/web")]
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Directory in the root subdirectory the other selected paths are
+/// mounted under, unless `clone --mount-prefix` names another
+pub const DEFAULT_PREFIX: &str = ".monorepo";
+
+/// A clone made with `clone --root`: the destination is a link to the
+/// `root` subdirectory of the clone, and the other selected paths are
+/// linked under `prefix` inside it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RootMount {
+    /// Subdirectory shown as the top level, e.g. `services/api`
+    pub root: String,
+
+    /// Directory in `root` holding the links to the other paths
+    pub prefix: String,
+}
+
+impl RootMount {
+    /// Validates `root` as a subdirectory of the repository and `prefix` as
+    /// a single directory name
+    pub fn new(
+        root: &str,
+        prefix: &str,
+    ) -> Result<Self> {
+        let root = root.trim_matches('/');
+        if root.is_empty()
+            || root.contains(['*', '?', '['])
+            || root.split('/').any(|part| matches!(part, "" | "." | ".."))
+        {
+            anyhow::bail!(
+                "--root must name a subdirectory of the repository, e.g. services/api, not '{}'",
+                root
+            );
+        }
+        if prefix.is_empty() || prefix.contains('/') || matches!(prefix, "." | "..") {
+            anyhow::bail!(
+                "--mount-prefix must be a single directory name, not '{}'",
+                prefix
+            );
+        }
+        Ok(RootMount {
+            root: root.to_string(),
+            prefix: prefix.to_string(),
+        })
+    }
+
+    /// The sparse pattern selecting the root subdirectory
+    pub fn root_pattern(&self) -> String {
+        format!("{}/**", self.root)
+    }
+
+    /// The directory of the links, from the repository root
+    pub fn prefix_dir(&self) -> String {
+        format!("{}/{}", self.root, self.prefix)
+    }
+
+    /// The links to create under the prefix for the sparse `patterns`, as
+    /// `(name, target)`: one per top-level entry the patterns select
+    /// besides the root, with a target relative to the prefix directory
+    pub fn links<'a>(
+        &self,
+        patterns: impl IntoIterator<Item = &'a String>,
+    ) -> Vec<(String, String)> {
+        let root_pattern = self.root_pattern();
+        let up = "../".repeat(self.root.split('/').count() + 1);
+        let mut names: Vec<&str> = patterns
+            .into_iter()
+            .filter(|pattern| **pattern != root_pattern)
+            .filter_map(|pattern| pattern.trim_start_matches('/').split('/').next())
+            .filter(|name| !name.is_empty() && !name.contains(['*', '?', '[']))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+            .into_iter()
+            .map(|name| (name.to_string(), format!("{}{}", up, name)))
+            .collect()
+    }
+}
+
+/// Where the clone behind the destination `destination` of `clone --root`
+/// lives: a hidden directory next to it
+pub fn store_dir(destination: &Path) -> PathBuf {
+    let name = destination
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "clone".to_string());
+    destination.with_file_name(format!(".{}.git-partial", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_mount() {
+        let mount = RootMount::new("/services/api/", DEFAULT_PREFIX).unwrap();
+        assert_eq!(mount.root, "services/api");
+        assert_eq!(mount.root_pattern(), "services/api/**");
+        assert_eq!(mount.prefix_dir(), "services/api/.monorepo");
+        assert!(RootMount::new("services/../api", DEFAULT_PREFIX).is_err());
+        assert!(RootMount::new("services/*", DEFAULT_PREFIX).is_err());
+        assert!(RootMount::new("", DEFAULT_PREFIX).is_err());
+        assert!(RootMount::new("services/api", "a/b").is_err());
+
+        let patterns: Vec<String> = [
+            "services/api/**",
+            "libs/common/**",
+            "/libs/log/**",
+            "README.md",
+            "*.toml",
+        ]
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+        assert_eq!(
+            mount.links(&patterns),
+            vec![
+                ("README.md".to_string(), "../../../README.md".to_string()),
+                ("libs".to_string(), "../../../libs".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_store_dir() {
+        assert_eq!(
+            store_dir(Path::new("/work/api")),
+            PathBuf::from("/work/.api.git-partial")
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::disk;
+
+/// Object count and on-disk size of a clone
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CloneStats {
+    /// Number of objects in the object store
+    pub objects: u64,
+
+    /// Bytes used by the object store
+    pub git_dir_bytes: u64,
+
+    /// Bytes of the checked out files
+    pub checkout_bytes: u64,
+}
+
+impl CloneStats {
+    /// Total bytes on disk
+    pub fn total_bytes(&self) -> u64 {
+        self.git_dir_bytes + self.checkout_bytes
+    }
+}
+
+/// Percentage of `full` avoided by `partial`, clamped at zero
+pub fn saved_percent(
+    partial: u64,
+    full: u64,
+) -> f64 {
+    if full == 0 {
+        return 0.0;
+    }
+    full.saturating_sub(partial) as f64 * 100.0 / full as f64
+}
+
+/// Renders the comparison printed by `savings`
+pub fn format_savings(
+    partial: &CloneStats,
+    full: &CloneStats,
+    cached: bool,
+) -> String {
+    let mut output = format!(
+        "{:<14}{:>14}{:>14}{:>10}\n",
+        "", "Partial", "Full clone", "Saved"
+    );
+    output.push_str(&format!(
+        "{:<14}{:>14}{:>14}{:>9.1}%\n",
+        "Objects",
+        partial.objects,
+        full.objects,
+        saved_percent(partial.objects, full.objects)
+    ));
+    let rows = [
+        ("Object store", partial.git_dir_bytes, full.git_dir_bytes),
+        ("Checkout", partial.checkout_bytes, full.checkout_bytes),
+        ("Total", partial.total_bytes(), full.total_bytes()),
+    ];
+    for (label, partial_bytes, full_bytes) in rows {
+        output.push_str(&format!(
+            "{:<14}{:>14}{:>14}{:>9.1}%\n",
+            label,
+            disk::format_size(partial_bytes),
+            disk::format_size(full_bytes),
+            saved_percent(partial_bytes, full_bytes)
+        ));
+    }
+    if cached {
+        output.push_str("\nFull clone figures are cached from an earlier run.\n");
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved_percent() {
+        assert_eq!(saved_percent(25, 100), 75.0);
+        assert_eq!(saved_percent(0, 0), 0.0);
+        // A partial clone can carry more than the estimate, e.g. loose objects
+        assert_eq!(saved_percent(120, 100), 0.0);
+    }
+
+    #[test]
+    fn test_format_savings() {
+        let partial = CloneStats {
+            objects: 10,
+            git_dir_bytes: 1024,
+            checkout_bytes: 0,
+        };
+        let full = CloneStats {
+            objects: 40,
+            git_dir_bytes: 2048,
+            checkout_bytes: 2048,
+        };
+
+        let output = format_savings(&partial, &full, false);
+        assert!(output.contains("Objects"));
+        assert!(output.contains("75.0%"));
+        assert!(output.contains("1.0 KiB"));
+        assert!(!output.contains("cached"));
+    }
+}
@@ -0,0 +1,156 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone};
+use cron::Schedule;
+use std::fmt;
+use std::str::FromStr;
+
+/// What `watch` does when a schedule fires
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WatchAction {
+    /// Fetch the upstream and notify about changes to the sparse paths
+    Check,
+
+    /// Check, then download the blobs the next `smart-pull` needs
+    Prefetch,
+
+    /// Check, then send the digest of the commits since the previous one
+    Digest,
+}
+
+impl fmt::Display for WatchAction {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            WatchAction::Check => write!(f, "check"),
+            WatchAction::Prefetch => write!(f, "prefetch"),
+            WatchAction::Digest => write!(f, "digest"),
+        }
+    }
+}
+
+/// A cron expression and the action it triggers, configured with
+/// `git config --add gitpartial.watch.schedule "<cron> [check|prefetch|digest]"`
+#[derive(Debug, Clone)]
+pub struct WatchSchedule {
+    /// The expression as configured
+    pub expression: String,
+    pub action: WatchAction,
+    schedule: Schedule,
+}
+
+impl WatchSchedule {
+    /// Parses a crontab-style entry: five fields (minute, hour, day of
+    /// month, month, day of week) or six with leading seconds, followed by
+    /// an optional action, `check` by default
+    pub fn parse(entry: &str) -> Result<Self> {
+        let mut fields: Vec<&str> = entry.split_whitespace().collect();
+        let action = match fields.last() {
+            Some(&"check") => Some(WatchAction::Check),
+            Some(&"prefetch") => Some(WatchAction::Prefetch),
+            Some(&"digest") => Some(WatchAction::Digest),
+            _ => None,
+        };
+        if action.is_some() {
+            fields.pop();
+        }
+        let expression = fields.join(" ");
+        let cron = match fields.len() {
+            5 => format!("0 {}", expression),
+            6 => expression.clone(),
+            _ => anyhow::bail!(
+                "Invalid schedule '{}' (expected 5 cron fields and an optional action)",
+                entry
+            ),
+        };
+        let schedule = Schedule::from_str(&cron)
+            .with_context(|| format!("Invalid cron expression '{}'", expression))?;
+        Ok(WatchSchedule {
+            expression,
+            action: action.unwrap_or(WatchAction::Check),
+            schedule,
+        })
+    }
+
+    /// The first time after `after` the schedule fires
+    pub fn next_after<Z: TimeZone>(
+        &self,
+        after: &DateTime<Z>,
+    ) -> Option<DateTime<Z>> {
+        self.schedule.after(after).next()
+    }
+}
+
+/// The next time any schedule fires after `after`, with the action to run.
+/// When several fire at once the most thorough action wins.
+pub fn next_run<Z: TimeZone>(
+    schedules: &[WatchSchedule],
+    after: &DateTime<Z>,
+) -> Option<(DateTime<Z>, WatchAction)> {
+    schedules
+        .iter()
+        .filter_map(|schedule| Some((schedule.next_after(after)?, schedule.action)))
+        .min_by(|(time_a, action_a), (time_b, action_b)| {
+            time_a.cmp(time_b).then(action_b.cmp(action_a))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn at(
+        hour: u32,
+        minute: u32,
+    ) -> DateTime<Utc> {
+        // 2025-06-02 is a Monday
+        Utc.with_ymd_and_hms(2025, 6, 2, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let schedule = WatchSchedule::parse("0 8 * * Mon-Fri prefetch").unwrap();
+        assert_eq!(schedule.expression, "0 8 * * Mon-Fri");
+        assert_eq!(schedule.action, WatchAction::Prefetch);
+
+        let schedule = WatchSchedule::parse("30 * * * *").unwrap();
+        assert_eq!(schedule.action, WatchAction::Check);
+        assert_eq!(schedule.next_after(&at(9, 10)), Some(at(9, 30)));
+
+        let schedule = WatchSchedule::parse("0 17 * * Fri digest").unwrap();
+        assert_eq!(schedule.action, WatchAction::Digest);
+
+        assert!(WatchSchedule::parse("0 8 * *").is_err());
+        assert!(WatchSchedule::parse("0 25 * * * check").is_err());
+    }
+
+    #[test]
+    fn test_next_run() {
+        let schedules = vec![
+            WatchSchedule::parse("0 9-18 * * Mon-Fri check").unwrap(),
+            WatchSchedule::parse("0 8 * * Mon-Fri prefetch").unwrap(),
+        ];
+
+        assert_eq!(
+            next_run(&schedules, &at(7, 0)),
+            Some((at(8, 0), WatchAction::Prefetch))
+        );
+        assert_eq!(
+            next_run(&schedules, &at(8, 0)),
+            Some((at(9, 0), WatchAction::Check))
+        );
+
+        // Schedules firing together run the more thorough action
+        let schedules = vec![
+            WatchSchedule::parse("0 * * * * check").unwrap(),
+            WatchSchedule::parse("0 8 * * * prefetch").unwrap(),
+        ];
+        assert_eq!(
+            next_run(&schedules, &at(7, 30)),
+            Some((at(8, 0), WatchAction::Prefetch))
+        );
+        assert_eq!(next_run(&[], &at(7, 30)), None);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::core::path_selector;
+use crate::core::tree::{EntryKind, TreeEntry};
+
+/// A checked out symlink whose target is not in the sparse paths
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutsideLink {
+    /// Path of the link
+    pub link: String,
+
+    /// Target as stored in the link
+    pub target: String,
+
+    /// Sparse pattern selecting the target: `dir/**` or the file itself
+    pub pattern: String,
+}
+
+/// Resolves a link's target to a path from the repository root.
+/// Returns `None` for absolute targets and ones leaving the repository.
+pub fn resolve_target(
+    link: &str,
+    target: &str,
+) -> Option<String> {
+    if target.starts_with('/') || target.contains(':') {
+        return None;
+    }
+    let mut parts: Vec<&str> = link.split('/').collect();
+    parts.pop();
+    for part in target.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            _ => parts.push(part),
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+/// Returns the links, given as `(path, target)`, whose targets exist in
+/// `entries` but are not fully covered by `patterns`
+pub fn outside_targets(
+    links: &[(String, String)],
+    entries: &[TreeEntry],
+    patterns: &[String],
+) -> Vec<OutsideLink> {
+    let compiled: Vec<_> = patterns
+        .iter()
+        .map(|raw| path_selector::compile_sparse(raw))
+        .collect();
+    let covered = |path: &str| {
+        compiled
+            .iter()
+            .any(|pattern| path_selector::pattern_covers(pattern, path))
+    };
+
+    links
+        .iter()
+        .filter_map(|(link, target)| {
+            let resolved = resolve_target(link, target)?;
+            let entry = entries.iter().find(|entry| entry.path == resolved)?;
+            let (pattern, outside) = match entry.kind {
+                EntryKind::Blob => (resolved.clone(), !covered(&resolved)),
+                EntryKind::Tree => {
+                    let prefix = format!("{}/", resolved);
+                    let outside = entries
+                        .iter()
+                        .filter(|e| e.kind == EntryKind::Blob && e.path.starts_with(&prefix))
+                        .any(|e| !covered(&e.path));
+                    (format!("{}**", prefix), outside)
+                }
+            };
+            outside.then(|| OutsideLink {
+                link: link.clone(),
+                target: target.clone(),
+                pattern,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        path: &str,
+        kind: EntryKind,
+    ) -> TreeEntry {
+        TreeEntry {
+            path: path.to_string(),
+            kind,
+            size: None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_target() {
+        assert_eq!(
+            resolve_target("src/config", "../shared/config.toml"),
+            Some("shared/config.toml".to_string())
+        );
+        assert_eq!(
+            resolve_target("src/lib", "./vendor/lib"),
+            Some("src/vendor/lib".to_string())
+        );
+        assert_eq!(resolve_target("link", "../outside"), None);
+        assert_eq!(resolve_target("link", "/etc/hosts"), None);
+    }
+
+    #[test]
+    fn test_outside_targets() {
+        let entries = vec![
+            entry("shared", EntryKind::Tree),
+            entry("shared/a.txt", EntryKind::Blob),
+            entry("src/b.txt", EntryKind::Blob),
+            entry("docs/c.md", EntryKind::Blob),
+        ];
+        let links = vec![
+            ("src/shared".to_string(), "../shared".to_string()),
+            ("src/c".to_string(), "../docs/c.md".to_string()),
+            ("src/b".to_string(), "b.txt".to_string()),
+            ("src/dangling".to_string(), "missing".to_string()),
+        ];
+        let outside = outside_targets(&links, &entries, &["src/**".to_string()]);
+
+        assert_eq!(
+            outside
+                .iter()
+                .map(|link| link.pattern.as_str())
+                .collect::<Vec<_>>(),
+            vec!["shared/**", "docs/c.md"]
+        );
+        assert!(outside_targets(&links, &entries, &["**".to_string()]).is_empty());
+    }
+}
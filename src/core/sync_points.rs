@@ -0,0 +1,149 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// A commit the checkout was brought to by `clone` or `smart-pull`, one
+/// line of `.gitpartial/syncs.jsonl`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncPoint {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+
+    /// HEAD after the sync
+    pub commit: String,
+}
+
+/// Appends a sync point to the log
+pub fn append<P: AsRef<Path>>(
+    repo_path: P,
+    point: &SyncPoint,
+) -> Result<()> {
+    let log_path = log_path(&repo_path);
+    let mut line = serde_json::to_string(point).context("Failed to serialize sync point")?;
+    line.push('\n');
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .with_context(|| format!("Failed to open {:?}", log_path))?;
+    file.write_all(line.as_bytes())
+        .with_context(|| format!("Failed to write {:?}", log_path))
+}
+
+/// Loads every sync point, oldest first. Unreadable lines are skipped.
+pub fn load<P: AsRef<Path>>(repo_path: P) -> Result<Vec<SyncPoint>> {
+    let log_path = log_path(&repo_path);
+    if !log_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content =
+        fs::read_to_string(&log_path).with_context(|| format!("Failed to read {:?}", log_path))?;
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            serde_json::from_str(line)
+                .inspect_err(|e| warn!("Skipping unreadable sync point: {}", e))
+                .ok()
+        })
+        .collect())
+}
+
+/// Resolves `spec` against the sync points: `#N` is the Nth point, counted
+/// from 1 for the oldest, and a `YYYY-MM-DD` date the last point synced on
+/// or before that day (UTC). Anything else is left for git to resolve as a
+/// revision, so `None` is returned.
+pub fn resolve<'a>(
+    points: &'a [SyncPoint],
+    spec: &str,
+) -> Result<Option<&'a SyncPoint>> {
+    if let Some(number) = spec.strip_prefix('#') {
+        let number: usize = number
+            .parse()
+            .with_context(|| format!("Invalid sync point '{}'", spec))?;
+        return number
+            .checked_sub(1)
+            .and_then(|index| points.get(index))
+            .map(Some)
+            .with_context(|| format!("No sync point {}; {} are recorded", spec, points.len()));
+    }
+
+    let Ok(date) = NaiveDate::parse_from_str(spec, "%Y-%m-%d") else {
+        return Ok(None);
+    };
+    let end_of_day = date
+        .succ_opt()
+        .and_then(|next| next.and_hms_opt(0, 0, 0))
+        .map(|next| next.and_utc().timestamp().max(0) as u64)
+        .with_context(|| format!("Invalid date '{}'", spec))?;
+    points
+        .iter()
+        .rev()
+        .find(|point| point.timestamp < end_of_day)
+        .map(Some)
+        .with_context(|| format!("No sync point on or before {}", spec))
+}
+
+/// Returns the path to the sync log
+fn log_path<P: AsRef<Path>>(repo_path: P) -> PathBuf {
+    repo_path.as_ref().join(".gitpartial").join("syncs.jsonl")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(
+        timestamp: u64,
+        commit: &str,
+    ) -> SyncPoint {
+        SyncPoint {
+            timestamp,
+            commit: commit.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_append_and_load() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join(".gitpartial")).unwrap();
+        assert!(load(temp_dir.path()).unwrap().is_empty());
+
+        append(temp_dir.path(), &point(1, "aaa")).unwrap();
+        append(temp_dir.path(), &point(2, "bbb")).unwrap();
+
+        assert_eq!(
+            load(temp_dir.path()).unwrap(),
+            vec![point(1, "aaa"), point(2, "bbb")]
+        );
+    }
+
+    #[test]
+    fn test_resolve() {
+        // 2026-10-01 10:00, 2026-10-01 18:00 and 2026-10-03 09:00 UTC
+        let points = vec![
+            point(1_790_848_800, "aaa"),
+            point(1_790_877_600, "bbb"),
+            point(1_791_018_000, "ccc"),
+        ];
+
+        assert_eq!(resolve(&points, "#1").unwrap(), Some(&points[0]));
+        assert_eq!(resolve(&points, "#3").unwrap(), Some(&points[2]));
+        assert!(resolve(&points, "#0").is_err());
+        assert!(resolve(&points, "#4").is_err());
+        assert!(resolve(&points, "#x").is_err());
+
+        assert_eq!(resolve(&points, "2026-10-01").unwrap(), Some(&points[1]));
+        assert_eq!(resolve(&points, "2026-10-02").unwrap(), Some(&points[1]));
+        assert_eq!(resolve(&points, "2026-10-03").unwrap(), Some(&points[2]));
+        assert!(resolve(&points, "2026-09-30").is_err());
+
+        assert_eq!(resolve(&points, "main~2").unwrap(), None);
+    }
+}
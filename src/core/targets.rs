@@ -0,0 +1,378 @@
+use anyhow::Result;
+use std::collections::{BTreeSet, VecDeque};
+use tracing::debug;
+
+/// File names that define a Bazel or Buck2 package, in lookup order
+pub const BUILD_FILE_NAMES: [&str; 3] = ["BUILD.bazel", "BUILD", "BUCK"];
+
+/// Rule attributes whose values may reference other targets
+const DEPENDENCY_ATTRIBUTES: [&str; 6] =
+    ["deps", "srcs", "data", "runtime_deps", "exports", "hdrs"];
+
+/// A target label such as `//services/api:server`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Label {
+    /// Package directory relative to the repository root (empty for the root package)
+    pub package: String,
+
+    /// Target name within the package
+    pub name: String,
+}
+
+impl Label {
+    /// Parses an absolute (`//pkg:name`, `//pkg`) or package relative (`:name`) label.
+    /// Labels of external repositories (`@repo//...`) and other cells return `None`,
+    /// as do plain file names.
+    pub fn parse(
+        label: &str,
+        current_package: &str,
+    ) -> Option<Self> {
+        let label = label.trim();
+        if let Some(name) = label.strip_prefix(':') {
+            return Some(Label {
+                package: current_package.to_string(),
+                name: name.to_string(),
+            });
+        }
+
+        let (cell, rest) = label.split_once("//")?;
+        // Buck2 names the main cell `root`; anything else lives outside this repository
+        if !(cell.is_empty() || cell == "root") {
+            return None;
+        }
+
+        let (package, name) = match rest.split_once(':') {
+            Some((package, name)) => (package, name.to_string()),
+            None => (rest, rest.rsplit('/').next().unwrap_or(rest).to_string()),
+        };
+        Some(Label {
+            package: package.trim_end_matches('/').to_string(),
+            name,
+        })
+    }
+}
+
+/// A rule declared in a BUILD file with the labels it references
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub name: String,
+    pub references: Vec<String>,
+}
+
+/// Extracts the rules of a BUILD file.
+/// This is a lightweight reading of Starlark: every top-level call with a
+/// `name` argument is a rule, and string literals in dependency attributes
+/// are collected as references. Macros and computed values are not evaluated.
+pub fn parse_build_file(content: &str) -> Vec<Rule> {
+    let mut rules = Vec::new();
+    for call in top_level_calls(content) {
+        let mut name = None;
+        let mut references = Vec::new();
+        for argument in split_arguments(&call) {
+            let Some((key, value)) = argument.split_once('=') else {
+                continue;
+            };
+            let key = key.trim();
+            let strings = string_literals(value);
+            if key == "name" {
+                name = strings.into_iter().next();
+            } else if DEPENDENCY_ATTRIBUTES.contains(&key) {
+                references.extend(strings);
+            }
+        }
+        if let Some(name) = name {
+            rules.push(Rule { name, references });
+        }
+    }
+    rules
+}
+
+/// Computes the package directories needed by the given targets by following
+/// their references through the BUILD files. `read_file` returns the content
+/// of a file at the inspected revision.
+pub fn resolve_packages<F>(
+    labels: &[Label],
+    read_file: F,
+) -> Result<BTreeSet<String>>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut packages = BTreeSet::new();
+    let mut visited = BTreeSet::new();
+    let mut queue: VecDeque<(Label, bool)> = labels.iter().cloned().map(|l| (l, true)).collect();
+
+    while let Some((label, requested)) = queue.pop_front() {
+        if !visited.insert(label.clone()) {
+            continue;
+        }
+        packages.insert(label.package.clone());
+
+        let Some(rules) = read_package(&label.package, &read_file) else {
+            if requested {
+                anyhow::bail!("No BUILD file found for package '//{}'", label.package);
+            }
+            continue;
+        };
+        let Some(rule) = rules.iter().find(|rule| rule.name == label.name) else {
+            if requested {
+                anyhow::bail!("Target '//{}:{}' not found", label.package, label.name);
+            }
+            // Source files are referenced like targets but have no rule
+            continue;
+        };
+
+        for reference in &rule.references {
+            if let Some(dependency) = Label::parse(reference, &label.package) {
+                queue.push_back((dependency, false));
+            }
+        }
+    }
+
+    debug!("Resolved packages: {:?}", packages);
+    Ok(packages)
+}
+
+/// Extracts the packages of the labels printed by `bazel query` or `buck2 uquery`
+pub fn packages_from_query_output(output: &str) -> BTreeSet<String> {
+    output
+        .lines()
+        .filter_map(|line| Label::parse(line, ""))
+        .map(|label| label.package)
+        .collect()
+}
+
+/// Returns the sparse checkout pattern covering a package.
+/// The root package only contributes its BUILD files, since `/**` would be everything.
+pub fn package_patterns(package: &str) -> Vec<String> {
+    if package.is_empty() {
+        BUILD_FILE_NAMES
+            .iter()
+            .map(|name| format!("/{}", name))
+            .collect()
+    } else {
+        vec![format!("{}/**", package)]
+    }
+}
+
+fn read_package<F>(
+    package: &str,
+    read_file: &F,
+) -> Option<Vec<Rule>>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    BUILD_FILE_NAMES.iter().find_map(|name| {
+        let path = if package.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", package, name)
+        };
+        read_file(&path).map(|content| parse_build_file(&content))
+    })
+}
+
+/// Returns the argument text of every call made at the top level of the file
+fn top_level_calls(content: &str) -> Vec<String> {
+    let mut calls = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '#' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+                continue;
+            }
+            '"' | '\'' => {
+                let literal = read_string(c, &mut chars);
+                if depth > 0 {
+                    current.push(c);
+                    current.push_str(&literal);
+                    current.push(c);
+                }
+                continue;
+            }
+            '(' | '[' | '{' => {
+                depth += 1;
+                if depth == 1 {
+                    current.clear();
+                    continue;
+                }
+            }
+            ')' | ']' | '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    calls.push(std::mem::take(&mut current));
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        if depth > 0 {
+            current.push(c);
+        }
+    }
+    calls
+}
+
+/// Splits call arguments on commas that are not nested in brackets or strings
+fn split_arguments(call: &str) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+    let mut chars = call.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                let literal = read_string(c, &mut chars);
+                current.push(c);
+                current.push_str(&literal);
+                current.push(c);
+                continue;
+            }
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                arguments.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        arguments.push(current);
+    }
+    arguments
+}
+
+/// Returns the string literals in an expression
+fn string_literals(expression: &str) -> Vec<String> {
+    let mut literals = Vec::new();
+    let mut chars = expression.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '"' || c == '\'' {
+            literals.push(read_string(c, &mut chars));
+        }
+    }
+    literals
+}
+
+/// Reads a string literal after its opening quote, consuming the closing quote
+fn read_string(
+    quote: char,
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+) -> String {
+    let mut literal = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    literal.push(escaped);
+                }
+            }
+            c if c == quote => break,
+            c => literal.push(c),
+        }
+    }
+    literal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_label() {
+        assert_eq!(
+            Label::parse("//services/api:server", ""),
+            Some(Label {
+                package: "services/api".to_string(),
+                name: "server".to_string()
+            })
+        );
+        assert_eq!(
+            Label::parse("//libs/log", "").map(|l| l.name),
+            Some("log".to_string())
+        );
+        assert_eq!(
+            Label::parse(":util", "libs/core").map(|l| l.package),
+            Some("libs/core".to_string())
+        );
+        assert_eq!(
+            Label::parse("root//libs/log:log", "").map(|l| l.package),
+            Some("libs/log".to_string())
+        );
+        assert_eq!(Label::parse("@maven//:guava", ""), None);
+        assert_eq!(Label::parse("main.go", ""), None);
+    }
+
+    #[test]
+    fn test_parse_build_file() {
+        let content = r#"
+load("@rules_go//go:def.bzl", "go_binary")
+
+# The API server
+go_binary(
+    name = "server",
+    srcs = ["main.go"],
+    deps = [
+        "//libs/log",  # logging
+        ":config",
+    ] + select({"//conditions:default": []}),
+    visibility = ["//visibility:public"],
+)
+"#;
+
+        let rules = parse_build_file(content);
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].name, "server");
+        assert_eq!(
+            rules[0].references,
+            vec!["main.go", "//libs/log", ":config", "//conditions:default"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_packages() {
+        let files: HashMap<&str, &str> = HashMap::from([
+            (
+                "services/api/BUILD.bazel",
+                r#"go_binary(name = "server", deps = ["//libs/log", "@maven//:guava"])"#,
+            ),
+            (
+                "libs/log/BUILD",
+                r#"go_library(name = "log", deps = ["//libs/format:format"])"#,
+            ),
+        ]);
+        let read_file = |path: &str| files.get(path).map(|s| s.to_string());
+
+        let labels = vec![Label::parse("//services/api:server", "").unwrap()];
+        let packages = resolve_packages(&labels, read_file).unwrap();
+        assert_eq!(
+            packages.into_iter().collect::<Vec<_>>(),
+            vec!["libs/format", "libs/log", "services/api"]
+        );
+
+        let missing = vec![Label::parse("//services/api:worker", "").unwrap()];
+        assert!(resolve_packages(&missing, read_file).is_err());
+    }
+
+    #[test]
+    fn test_packages_from_query_output() {
+        let output = "//services/api:server\n//libs/log:log\n@maven//:guava\n";
+        assert_eq!(
+            packages_from_query_output(output)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["libs/log", "services/api"]
+        );
+    }
+}
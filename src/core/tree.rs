@@ -0,0 +1,492 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::core::path_selector;
+
+/// Kind of an entry in a repository tree
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryKind {
+    Blob,
+    Tree,
+}
+
+/// A file or directory in a repository tree, local or remote
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeEntry {
+    /// Path relative to the repository root
+    pub path: String,
+
+    /// Whether this is a file or a directory
+    pub kind: EntryKind,
+
+    /// Blob size in bytes, if known
+    pub size: Option<u64>,
+}
+
+/// Number of files and total blob size selected by a set of patterns
+#[derive(Debug, Default, PartialEq)]
+pub struct SizeEstimate {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Files and blob size under one path of a tree
+#[derive(Debug, PartialEq)]
+pub struct PathUsage {
+    /// Directories end in `/`
+    pub path: String,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Parses the output of `git ls-tree -r -t -l -z`.
+/// Each record looks like `<mode> <type> <object> <size>\t<path>`; without
+/// `-l` the size is missing and left unknown.
+pub fn parse_ls_tree(output: &str) -> Vec<TreeEntry> {
+    output
+        .split('\0')
+        .filter_map(|record| {
+            let (info, path) = record.split_once('\t')?;
+            let mut fields = info.split_whitespace();
+            let kind = match fields.nth(1)? {
+                "blob" => EntryKind::Blob,
+                "tree" => EntryKind::Tree,
+                // Submodule commits have no content in this repository
+                _ => return None,
+            };
+            let size = fields.nth(1).and_then(|size| size.parse().ok());
+            Some(TreeEntry {
+                path: path.to_string(),
+                kind,
+                size,
+            })
+        })
+        .collect()
+}
+
+/// Whether any file of the tree has a known size. GitLab's tree API lists
+/// none, which must read as unknown rather than as empty files.
+pub fn has_sizes(entries: &[TreeEntry]) -> bool {
+    entries
+        .iter()
+        .any(|entry| entry.kind == EntryKind::Blob && entry.size.is_some())
+}
+
+/// Returns the directories of a tree up to the given depth, sorted.
+/// Directories are derived from blob paths so trees without explicit
+/// directory entries work too.
+pub fn directories(
+    entries: &[TreeEntry],
+    max_depth: usize,
+) -> Vec<String> {
+    let mut dirs = BTreeSet::new();
+    for entry in entries {
+        let components: Vec<&str> = entry.path.split('/').collect();
+        let dir_components = match entry.kind {
+            EntryKind::Tree => components.len(),
+            EntryKind::Blob => components.len() - 1,
+        };
+        for depth in 1..=dir_components.min(max_depth) {
+            dirs.insert(components[..depth].join("/"));
+        }
+    }
+    dirs.into_iter().collect()
+}
+
+/// Returns the entries completing `prefix` one path segment at a time:
+/// those starting with it and with no further `/` after its last one.
+/// Directories end in `/` so a shell can keep completing inside them.
+pub fn completions(
+    entries: &[TreeEntry],
+    prefix: &str,
+) -> Vec<String> {
+    let dir_len = prefix.rfind('/').map_or(0, |index| index + 1);
+    entries
+        .iter()
+        .filter(|entry| entry.path.starts_with(prefix) && !entry.path[dir_len..].contains('/'))
+        .map(|entry| match entry.kind {
+            EntryKind::Tree => format!("{}/", entry.path),
+            EntryKind::Blob => entry.path.clone(),
+        })
+        .collect()
+}
+
+/// Returns the blobs selected by the given sparse patterns
+pub fn select_blobs<'a>(
+    entries: &'a [TreeEntry],
+    patterns: &[String],
+) -> Vec<&'a TreeEntry> {
+    let compiled: Vec<_> = patterns
+        .iter()
+        .map(|p| path_selector::compile_sparse(p))
+        .collect();
+
+    entries
+        .iter()
+        .filter(|entry| entry.kind == EntryKind::Blob)
+        .filter(|entry| {
+            compiled
+                .iter()
+                .any(|pattern| path_selector::pattern_covers(pattern, &entry.path))
+        })
+        .collect()
+}
+
+/// Sums the number of files and bytes selected by the given sparse patterns
+pub fn estimate(
+    entries: &[TreeEntry],
+    patterns: &[String],
+) -> SizeEstimate {
+    let selected = select_blobs(entries, patterns);
+    SizeEstimate {
+        files: selected.len(),
+        bytes: selected.iter().filter_map(|entry| entry.size).sum(),
+    }
+}
+
+/// Sums the files and bytes selected by `added` that `existing` does not select yet
+pub fn estimate_added(
+    entries: &[TreeEntry],
+    existing: &[String],
+    added: &[String],
+) -> SizeEstimate {
+    let present: BTreeSet<&str> = select_blobs(entries, existing)
+        .into_iter()
+        .map(|entry| entry.path.as_str())
+        .collect();
+    let selected: Vec<&TreeEntry> = select_blobs(entries, added)
+        .into_iter()
+        .filter(|entry| !present.contains(entry.path.as_str()))
+        .collect();
+    SizeEstimate {
+        files: selected.len(),
+        bytes: selected.iter().filter_map(|entry| entry.size).sum(),
+    }
+}
+
+/// Sums the files and blob sizes under `root` (the whole tree when empty),
+/// grouped by their path down to `depth` levels below it, largest first.
+/// Files above that depth are listed on their own. Fails when nothing
+/// exists under `root`.
+pub fn disk_usage(
+    entries: &[TreeEntry],
+    root: &str,
+    depth: usize,
+) -> Result<Vec<PathUsage>> {
+    let root = root.trim_matches('/');
+    let mut usage: BTreeMap<String, PathUsage> = BTreeMap::new();
+    for entry in entries.iter().filter(|entry| entry.kind == EntryKind::Blob) {
+        let relative = if root.is_empty() {
+            entry.path.as_str()
+        } else if entry.path == root {
+            ""
+        } else if let Some(rest) = entry.path.strip_prefix(&format!("{}/", root)) {
+            rest
+        } else {
+            continue;
+        };
+        let components: Vec<&str> = relative.split('/').collect();
+        let path = if relative.is_empty() {
+            root.to_string()
+        } else if components.len() > depth {
+            let group = components[..depth].join("/");
+            match root {
+                "" => format!("{}/", group),
+                root => format!("{}/{}/", root, group),
+            }
+        } else {
+            entry.path.clone()
+        };
+        let row = usage.entry(path.clone()).or_insert(PathUsage {
+            path,
+            files: 0,
+            bytes: 0,
+        });
+        row.files += 1;
+        row.bytes += entry.size.unwrap_or(0);
+    }
+    if usage.is_empty() {
+        anyhow::bail!("Nothing found at '{}'", root);
+    }
+
+    let mut usage: Vec<PathUsage> = usage.into_values().collect();
+    usage.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(b.files.cmp(&a.files)));
+    Ok(usage)
+}
+
+/// Translates regular expressions into concrete sparse patterns for the
+/// blobs whose whole path matches one of them. A directory whose files all
+/// match becomes `dir/**`; other matches are listed file by file.
+pub fn regex_selection(
+    entries: &[TreeEntry],
+    regexes: &[Regex],
+) -> Vec<String> {
+    let blobs: Vec<&str> = entries
+        .iter()
+        .filter(|entry| entry.kind == EntryKind::Blob)
+        .map(|entry| entry.path.as_str())
+        .collect();
+
+    // Files and matching files under each directory
+    let mut counts: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    let mut matched = Vec::new();
+    for path in &blobs {
+        let is_match = regexes.iter().any(|regex| regex.is_match(path));
+        if is_match {
+            matched.push(*path);
+        }
+        for (index, _) in path.match_indices('/') {
+            let count = counts.entry(&path[..index]).or_default();
+            count.0 += 1;
+            if is_match {
+                count.1 += 1;
+            }
+        }
+    }
+
+    let mut patterns = BTreeSet::new();
+    for path in matched {
+        let whole_dir = path
+            .match_indices('/')
+            .map(|(index, _)| &path[..index])
+            .find(|dir| counts.get(dir).is_some_and(|(files, hits)| files == hits));
+        match whole_dir {
+            Some(dir) => patterns.insert(format!("{}/**", dir)),
+            None => patterns.insert(path.to_string()),
+        };
+    }
+    patterns.into_iter().collect()
+}
+
+/// Compiles regular expressions that must match a whole path
+pub fn compile_regexes(sources: &[String]) -> Result<Vec<Regex>> {
+    sources
+        .iter()
+        .map(|source| {
+            Regex::new(&format!("^(?:{})$", source))
+                .with_context(|| format!("Invalid regex '{}'", source))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blob(
+        path: &str,
+        size: u64,
+    ) -> TreeEntry {
+        TreeEntry {
+            path: path.to_string(),
+            kind: EntryKind::Blob,
+            size: Some(size),
+        }
+    }
+
+    #[test]
+    fn test_parse_ls_tree() {
+        let output = "100644 blob 3b18e512dba79e4c8300dd08aeb37f8e728b8dad      12\tREADME.md\0\
+                      040000 tree 9bfa0a3f1e6e4b1c1f0e3c9d9a8a8a8a8a8a8a8a       -\tsrc\0\
+                      100644 blob 2222222222222222222222222222222222222222       7\tsrc/caf\u{e9}.rs\0\
+                      160000 commit 1111111111111111111111111111111111111111       -\tvendor/lib\0";
+
+        assert_eq!(
+            parse_ls_tree(output),
+            vec![
+                blob("README.md", 12),
+                TreeEntry {
+                    path: "src".to_string(),
+                    kind: EntryKind::Tree,
+                    size: None,
+                },
+                blob("src/caf\u{e9}.rs", 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_has_sizes() {
+        let unsized_blob = TreeEntry {
+            path: "src/main.rs".to_string(),
+            kind: EntryKind::Blob,
+            size: None,
+        };
+        let sized_tree = TreeEntry {
+            path: "src".to_string(),
+            kind: EntryKind::Tree,
+            size: Some(0),
+        };
+
+        assert!(has_sizes(&[unsized_blob.clone(), blob("README.md", 12)]));
+        assert!(!has_sizes(&[unsized_blob, sized_tree]));
+        assert!(!has_sizes(&[]));
+    }
+
+    #[test]
+    fn test_directories() {
+        let entries = vec![
+            blob("README.md", 10),
+            blob("src/frontend/app.js", 20),
+            blob("src/backend/server.js", 30),
+            TreeEntry {
+                path: "empty".to_string(),
+                kind: EntryKind::Tree,
+                size: None,
+            },
+        ];
+
+        assert_eq!(directories(&entries, 1), vec!["empty", "src"]);
+        assert_eq!(
+            directories(&entries, 2),
+            vec!["empty", "src", "src/backend", "src/frontend"]
+        );
+    }
+
+    #[test]
+    fn test_estimate() {
+        let entries = vec![
+            blob("README.md", 10),
+            blob("src/frontend/app.js", 20),
+            blob("src/frontend/button.js", 5),
+            blob("src/backend/server.js", 30),
+        ];
+
+        let estimate = estimate(
+            &entries,
+            &["src/frontend/**".to_string(), "README.md".to_string()],
+        );
+
+        assert_eq!(
+            estimate,
+            SizeEstimate {
+                files: 3,
+                bytes: 35
+            }
+        );
+    }
+
+    #[test]
+    fn test_estimate_added() {
+        let entries = vec![
+            blob("README.md", 10),
+            blob("src/frontend/app.js", 20),
+            blob("src/backend/server.js", 30),
+        ];
+
+        let estimate = estimate_added(
+            &entries,
+            &["src/frontend/**".to_string()],
+            &["src/**".to_string()],
+        );
+
+        assert_eq!(
+            estimate,
+            SizeEstimate {
+                files: 1,
+                bytes: 30
+            }
+        );
+    }
+
+    #[test]
+    fn test_regex_selection() {
+        let entries = vec![
+            blob("README.md", 10),
+            blob("api/unit_tests/a.rs", 1),
+            blob("api/unit_tests/b.rs", 1),
+            blob("api/src/lib.rs", 1),
+            blob("web/integration_tests/flow.ts", 1),
+            blob("web/integration_tests/fixtures.json", 1),
+        ];
+        let regexes =
+            compile_regexes(&[r".*/(unit|integration)_tests/.*\.(rs|ts)".to_string()]).unwrap();
+
+        assert_eq!(
+            regex_selection(&entries, &regexes),
+            vec!["api/unit_tests/**", "web/integration_tests/flow.ts"]
+        );
+    }
+
+    #[test]
+    fn test_regex_selection_matches_whole_paths() {
+        let entries = vec![blob("src/main.rs", 1), blob("src/main.rs.bak", 1)];
+        let regexes = compile_regexes(&[r"src/.*\.rs".to_string()]).unwrap();
+
+        assert_eq!(regex_selection(&entries, &regexes), vec!["src/main.rs"]);
+        assert!(compile_regexes(&["(".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_completions() {
+        let dir = |path: &str| TreeEntry {
+            path: path.to_string(),
+            kind: EntryKind::Tree,
+            size: None,
+        };
+        let entries = vec![
+            blob("README.md", 1),
+            dir("src"),
+            dir("src/frontend"),
+            blob("src/frontend/app.js", 1),
+            blob("src/main.rs", 1),
+            dir("scripts"),
+        ];
+
+        assert_eq!(
+            completions(&entries, ""),
+            vec!["README.md", "src/", "scripts/"]
+        );
+        assert_eq!(completions(&entries, "s"), vec!["src/", "scripts/"]);
+        assert_eq!(
+            completions(&entries, "src/"),
+            vec!["src/frontend/", "src/main.rs"]
+        );
+        assert_eq!(completions(&entries, "src/f"), vec!["src/frontend/"]);
+    }
+
+    #[test]
+    fn test_disk_usage() {
+        let entries = vec![
+            blob("README.md", 10),
+            blob("src/frontend/app.js", 20),
+            blob("src/frontend/logo.png", 500),
+            blob("src/backend/server.js", 30),
+            blob("src/main.rs", 5),
+        ];
+
+        let top = disk_usage(&entries, "", 1).unwrap();
+        assert_eq!(
+            top,
+            vec![
+                PathUsage {
+                    path: "src/".to_string(),
+                    files: 4,
+                    bytes: 555,
+                },
+                PathUsage {
+                    path: "README.md".to_string(),
+                    files: 1,
+                    bytes: 10,
+                },
+            ]
+        );
+
+        let src: Vec<(String, u64)> = disk_usage(&entries, "/src/", 1)
+            .unwrap()
+            .into_iter()
+            .map(|row| (row.path, row.bytes))
+            .collect();
+        assert_eq!(
+            src,
+            vec![
+                ("src/frontend/".to_string(), 520),
+                ("src/backend/".to_string(), 30),
+                ("src/main.rs".to_string(), 5),
+            ]
+        );
+        assert_eq!(disk_usage(&entries, "README.md", 1).unwrap().len(), 1);
+        assert!(disk_usage(&entries, "docs", 1).is_err());
+    }
+}
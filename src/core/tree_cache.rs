@@ -0,0 +1,121 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::tree::{EntryKind, TreeEntry};
+
+/// The listing of the upstream tree, stored in `.gitpartial/tree-cache` so
+/// completion, pattern validation and `find` need not list large trees on
+/// every run. It is keyed by commit: once a fetch moves the upstream, the
+/// cache no longer matches and is rebuilt.
+#[derive(Debug, PartialEq)]
+pub struct TreeCache {
+    /// The commit the tree was listed from
+    pub commit: String,
+
+    /// Files and directories of the tree, without sizes
+    pub entries: Vec<TreeEntry>,
+}
+
+impl TreeCache {
+    /// Loads the cache when it exists and lists `commit`
+    pub fn load<P: AsRef<Path>>(
+        repo_path: P,
+        commit: &str,
+    ) -> Option<Self> {
+        let content = fs::read_to_string(Self::cache_path(&repo_path)).ok()?;
+        Self::parse(&content).filter(|cache| cache.commit == commit)
+    }
+
+    /// Saves the cache next to the metadata. Commands that only read the
+    /// repository save it too, so it is replaced in one rename rather than
+    /// rewritten under a concurrent reader.
+    pub fn save<P: AsRef<Path>>(
+        &self,
+        repo_path: P,
+    ) -> Result<()> {
+        let cache_path = Self::cache_path(&repo_path);
+        let staged_path = cache_path.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&staged_path, self.serialize())
+            .with_context(|| format!("Failed to write tree cache to {:?}", staged_path))?;
+        fs::rename(&staged_path, &cache_path)
+            .with_context(|| format!("Failed to replace {:?}", cache_path))
+    }
+
+    /// The commit on the first line, then one `<blob|tree>\t<path>` line per entry
+    fn serialize(&self) -> String {
+        let mut output = format!("{}\n", self.commit);
+        for entry in &self.entries {
+            let kind = match entry.kind {
+                EntryKind::Blob => "blob",
+                EntryKind::Tree => "tree",
+            };
+            output.push_str(&format!("{}\t{}\n", kind, entry.path));
+        }
+        output
+    }
+
+    fn parse(content: &str) -> Option<Self> {
+        let mut lines = content.lines();
+        let commit = lines.next()?.trim().to_string();
+        let entries = lines
+            .map(|line| {
+                let (kind, path) = line.split_once('\t')?;
+                let kind = match kind {
+                    "blob" => EntryKind::Blob,
+                    "tree" => EntryKind::Tree,
+                    _ => return None,
+                };
+                Some(TreeEntry {
+                    path: path.to_string(),
+                    kind,
+                    size: None,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(TreeCache { commit, entries })
+    }
+
+    /// Returns the path to the cache file
+    fn cache_path<P: AsRef<Path>>(repo_path: P) -> PathBuf {
+        repo_path.as_ref().join(".gitpartial").join("tree-cache")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_save_and_load_by_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join(".gitpartial")).unwrap();
+        let cache = TreeCache {
+            commit: "abc123".to_string(),
+            entries: vec![
+                TreeEntry {
+                    path: "src".to_string(),
+                    kind: EntryKind::Tree,
+                    size: None,
+                },
+                TreeEntry {
+                    path: "src/main.rs".to_string(),
+                    kind: EntryKind::Blob,
+                    size: None,
+                },
+            ],
+        };
+        cache.save(temp_dir.path()).unwrap();
+
+        assert_eq!(TreeCache::load(temp_dir.path(), "abc123"), Some(cache));
+        // A fetch that moved the upstream leaves the cache stale
+        assert_eq!(TreeCache::load(temp_dir.path(), "def456"), None);
+    }
+
+    #[test]
+    fn test_parse_rejects_corrupt_cache() {
+        assert_eq!(TreeCache::parse(""), None);
+        assert_eq!(TreeCache::parse("abc123\nsrc/main.rs\n"), None);
+    }
+}
@@ -0,0 +1,127 @@
+/// A `url.<base>.insteadOf <prefix>` rule: URLs starting with `prefix` are
+/// fetched from `base` instead
+#[derive(Debug, Clone, PartialEq)]
+pub struct UrlRewrite {
+    pub base: String,
+    pub prefix: String,
+}
+
+/// Parses `git config --get-regexp '^url\..*\.insteadof$'` output.
+/// Keys are lowercased by git, but the base keeps its case.
+pub fn parse_rules(output: &str) -> Vec<UrlRewrite> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (key, prefix) = line.split_once(' ')?;
+            let base = key.strip_prefix("url.")?;
+            let base = &base[..base.len().checked_sub(".insteadof".len())?];
+            Some(UrlRewrite {
+                base: base.to_string(),
+                prefix: prefix.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Returns the URL git fetches from: the longest matching prefix is
+/// replaced by its base, as git does
+pub fn apply(
+    url: &str,
+    rules: &[UrlRewrite],
+) -> String {
+    rules
+        .iter()
+        .filter(|rule| url.starts_with(&rule.prefix))
+        .max_by_key(|rule| rule.prefix.len())
+        .map(|rule| format!("{}{}", rule.base, &url[rule.prefix.len()..]))
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// Returns the URL as written before rewriting: a URL under a rule's base,
+/// such as a mirror's, is mapped back to the prefix it replaces. URLs no
+/// rule produces are returned unchanged.
+pub fn canonical(
+    url: &str,
+    rules: &[UrlRewrite],
+) -> String {
+    rules
+        .iter()
+        .filter(|rule| url.starts_with(&rule.base))
+        .max_by_key(|rule| rule.base.len())
+        .map(|rule| format!("{}{}", rule.prefix, &url[rule.base.len()..]))
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// Checks if two URLs name the same repository once rewrites are undone,
+/// ignoring a `.git` suffix and trailing slashes
+pub fn same_repository(
+    a: &str,
+    b: &str,
+    rules: &[UrlRewrite],
+) -> bool {
+    let normalize = |url: &str| {
+        let url = canonical(url, rules);
+        let url = url.trim_end_matches('/');
+        url.strip_suffix(".git").unwrap_or(url).to_string()
+    };
+    normalize(a) == normalize(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> Vec<UrlRewrite> {
+        parse_rules(
+            "url.https://mirror.example.com/.insteadof https://github.com/\n\
+             url.https://cdn.example.com/org/big.insteadof https://github.com/org/big",
+        )
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        assert_eq!(
+            rules()[0],
+            UrlRewrite {
+                base: "https://mirror.example.com/".to_string(),
+                prefix: "https://github.com/".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_apply_longest_prefix() {
+        let rules = rules();
+        assert_eq!(
+            apply("https://github.com/org/repo.git", &rules),
+            "https://mirror.example.com/org/repo.git"
+        );
+        assert_eq!(
+            apply("https://github.com/org/big.git", &rules),
+            "https://cdn.example.com/org/big.git"
+        );
+        assert_eq!(
+            apply("git@gitlab.com:a/b.git", &rules),
+            "git@gitlab.com:a/b.git"
+        );
+    }
+
+    #[test]
+    fn test_canonical_and_same_repository() {
+        let rules = rules();
+        assert_eq!(
+            canonical("https://mirror.example.com/org/repo.git", &rules),
+            "https://github.com/org/repo.git"
+        );
+        assert!(same_repository(
+            "https://mirror.example.com/org/repo",
+            "https://github.com/org/repo.git/",
+            &rules
+        ));
+        assert!(!same_repository(
+            "https://github.com/org/repo",
+            "https://github.com/org/other",
+            &rules
+        ));
+    }
+}
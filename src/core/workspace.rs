@@ -0,0 +1,606 @@
+use glob::{MatchOptions, Pattern};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::fmt;
+use tracing::debug;
+
+use crate::core::path_selector::join_dir;
+use crate::core::tree::{EntryKind, TreeEntry};
+use crate::core::{gradle, maven};
+
+/// Monorepo tooling that defines projects
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WorkspaceKind {
+    Pnpm,
+    Npm,
+    Cargo,
+    Nx,
+    Turborepo,
+    Gradle,
+    Maven,
+}
+
+impl fmt::Display for WorkspaceKind {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        let name = match self {
+            WorkspaceKind::Pnpm => "pnpm",
+            WorkspaceKind::Npm => "npm/yarn",
+            WorkspaceKind::Cargo => "cargo",
+            WorkspaceKind::Nx => "nx",
+            WorkspaceKind::Turborepo => "turborepo",
+            WorkspaceKind::Gradle => "gradle",
+            WorkspaceKind::Maven => "maven",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A logical project of a monorepo and the directory it lives in
+#[derive(Debug, Clone, PartialEq)]
+pub struct Project {
+    /// Package, crate or project name
+    pub name: String,
+
+    /// Directory relative to the repository root
+    pub dir: String,
+
+    /// The tooling that declared the project
+    pub kind: WorkspaceKind,
+}
+
+impl Project {
+    /// Checks if a user supplied name refers to this project,
+    /// either by its declared name or by its directory name.
+    /// Gradle project paths may omit the leading `:`.
+    pub fn matches_name(
+        &self,
+        name: &str,
+    ) -> bool {
+        self.name == name
+            || self.name.strip_prefix(':') == Some(name)
+            || self.dir.rsplit('/').next() == Some(name)
+    }
+
+    /// Returns the sparse checkout pattern covering the project
+    pub fn pattern(&self) -> String {
+        format!("{}/**", self.dir)
+    }
+}
+
+/// What the sparse checkout needs to build one project of a workspace
+#[derive(Debug, Default, PartialEq)]
+pub struct ProjectSelection {
+    /// Directories checked out whole: the project and those it depends on
+    pub dirs: BTreeSet<String>,
+
+    /// Single files the build tool reads to load the rest of the workspace,
+    /// such as the root configuration and the other projects' manifests.
+    /// Some may not exist.
+    pub files: BTreeSet<String>,
+}
+
+impl ProjectSelection {
+    /// Sparse checkout patterns covering the selection, keeping the files
+    /// for which `exists` holds
+    pub fn patterns<F>(
+        &self,
+        exists: F,
+    ) -> Vec<String>
+    where
+        F: Fn(&str) -> bool,
+    {
+        let mut patterns: Vec<String> = self.dirs.iter().map(|dir| format!("{}/**", dir)).collect();
+        patterns.extend(self.files.iter().filter(|file| exists(file)).cloned());
+        patterns
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoManifest {
+    package: Option<CargoPackage>,
+    workspace: Option<CargoWorkspace>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoPackage {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoWorkspace {
+    #[serde(default)]
+    members: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+/// Detects the monorepo tools configured at the repository root
+pub fn detect_kinds(entries: &[TreeEntry]) -> Vec<WorkspaceKind> {
+    let has = |path: &str| {
+        entries
+            .iter()
+            .any(|entry| entry.kind == EntryKind::Blob && entry.path == path)
+    };
+
+    let mut kinds = Vec::new();
+    if has("pnpm-workspace.yaml") {
+        kinds.push(WorkspaceKind::Pnpm);
+    }
+    if has("package.json") {
+        kinds.push(WorkspaceKind::Npm);
+    }
+    if has("Cargo.toml") {
+        kinds.push(WorkspaceKind::Cargo);
+    }
+    if has("nx.json") {
+        kinds.push(WorkspaceKind::Nx);
+    }
+    if has("turbo.json") {
+        kinds.push(WorkspaceKind::Turborepo);
+    }
+    if gradle::SETTINGS_FILES.iter().any(|file| has(file)) {
+        kinds.push(WorkspaceKind::Gradle);
+    }
+    if has("pom.xml") {
+        kinds.push(WorkspaceKind::Maven);
+    }
+    kinds
+}
+
+/// Lists the projects declared by the workspace configuration at the repository root.
+/// `read_file` returns the content of a file at the inspected revision.
+pub fn detect_projects<F>(
+    entries: &[TreeEntry],
+    read_file: F,
+) -> Vec<Project>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let kinds = detect_kinds(entries);
+    let dirs: Vec<&str> = entries
+        .iter()
+        .filter(|entry| entry.kind == EntryKind::Tree)
+        .map(|entry| entry.path.as_str())
+        .collect();
+    let has_file = |path: &str| {
+        entries
+            .iter()
+            .any(|entry| entry.kind == EntryKind::Blob && entry.path == path)
+    };
+
+    let mut projects = Vec::new();
+
+    // JavaScript workspaces; Turborepo builds on top of them
+    let js_kind = if kinds.contains(&WorkspaceKind::Turborepo) {
+        WorkspaceKind::Turborepo
+    } else if kinds.contains(&WorkspaceKind::Pnpm) {
+        WorkspaceKind::Pnpm
+    } else {
+        WorkspaceKind::Npm
+    };
+    let mut js_globs = Vec::new();
+    if kinds.contains(&WorkspaceKind::Pnpm) {
+        if let Some(content) = read_file("pnpm-workspace.yaml") {
+            js_globs.extend(parse_pnpm_workspace(&content));
+        }
+    }
+    if kinds.contains(&WorkspaceKind::Npm) {
+        if let Some(content) = read_file("package.json") {
+            js_globs.extend(parse_package_workspaces(&content));
+        }
+    }
+    for dir in expand_globs(&dirs, &js_globs) {
+        let manifest = format!("{}/package.json", dir);
+        if !has_file(&manifest) {
+            continue;
+        }
+        let name = read_file(&manifest)
+            .and_then(|content| json_string_field(&content, "name"))
+            .unwrap_or_else(|| dir_name(&dir));
+        projects.push(Project {
+            name,
+            dir,
+            kind: js_kind,
+        });
+    }
+
+    // Cargo workspace members
+    if kinds.contains(&WorkspaceKind::Cargo) {
+        let workspace = read_file("Cargo.toml")
+            .and_then(|content| toml::from_str::<CargoManifest>(&content).ok())
+            .and_then(|manifest| manifest.workspace);
+        if let Some(workspace) = workspace {
+            let excluded = expand_globs(&dirs, &workspace.exclude);
+            for dir in expand_globs(&dirs, &workspace.members) {
+                let manifest = format!("{}/Cargo.toml", dir);
+                if excluded.contains(&dir) || !has_file(&manifest) {
+                    continue;
+                }
+                let name = read_file(&manifest)
+                    .and_then(|content| toml::from_str::<CargoManifest>(&content).ok())
+                    .and_then(|manifest| manifest.package)
+                    .map(|package| package.name)
+                    .unwrap_or_else(|| dir_name(&dir));
+                projects.push(Project {
+                    name,
+                    dir,
+                    kind: WorkspaceKind::Cargo,
+                });
+            }
+        }
+    }
+
+    // Nx projects are any directory with a project.json
+    if kinds.contains(&WorkspaceKind::Nx) {
+        for entry in entries {
+            let Some(dir) = entry.path.strip_suffix("/project.json") else {
+                continue;
+            };
+            if entry.kind != EntryKind::Blob || projects.iter().any(|p| p.dir == dir) {
+                continue;
+            }
+            let name = read_file(&entry.path)
+                .and_then(|content| json_string_field(&content, "name"))
+                .unwrap_or_else(|| dir_name(dir));
+            projects.push(Project {
+                name,
+                dir: dir.to_string(),
+                kind: WorkspaceKind::Nx,
+            });
+        }
+    }
+
+    // Gradle projects included by the settings script
+    if kinds.contains(&WorkspaceKind::Gradle) {
+        let settings = gradle::SETTINGS_FILES
+            .iter()
+            .find_map(|file| read_file(file))
+            .unwrap_or_default();
+        for (name, dir) in gradle::parse_settings(&settings) {
+            if !dirs.contains(&dir.as_str()) {
+                continue;
+            }
+            projects.push(Project {
+                name,
+                dir,
+                kind: WorkspaceKind::Gradle,
+            });
+        }
+    }
+
+    // Maven reactor modules, aggregators nested in aggregators included
+    if kinds.contains(&WorkspaceKind::Maven) {
+        let mut aggregators = vec![String::new()];
+        while let Some(aggregator) = aggregators.pop() {
+            let pom = join_dir(&aggregator, "pom.xml").and_then(|path| read_file(&path));
+            for module in maven::parse_modules(&pom.unwrap_or_default()) {
+                let Some(dir) = join_dir(&aggregator, &module) else {
+                    continue;
+                };
+                let manifest = format!("{}/pom.xml", dir);
+                if dir.is_empty() || !has_file(&manifest) || projects.iter().any(|p| p.dir == dir) {
+                    continue;
+                }
+                let name = read_file(&manifest)
+                    .and_then(|content| maven::parse_artifact_id(&content))
+                    .unwrap_or_else(|| dir_name(&dir));
+                aggregators.push(dir.clone());
+                projects.push(Project {
+                    name,
+                    dir,
+                    kind: WorkspaceKind::Maven,
+                });
+            }
+        }
+    }
+
+    debug!("Detected projects: {:?}", projects);
+    projects
+}
+
+/// Extracts the `packages` globs from a pnpm-workspace.yaml file
+fn parse_pnpm_workspace(content: &str) -> Vec<String> {
+    let mut globs = Vec::new();
+    let mut in_packages = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(' ') && !line.starts_with('-') {
+            in_packages = trimmed == "packages:";
+            continue;
+        }
+        if in_packages {
+            if let Some(item) = trimmed.strip_prefix('-') {
+                globs.push(
+                    item.trim()
+                        .trim_matches(|c| c == '\'' || c == '"')
+                        .to_string(),
+                );
+            }
+        }
+    }
+    globs
+}
+
+/// Extracts the `workspaces` globs from a package.json file.
+/// Both the array form and yarn's `{ "packages": [...] }` form are supported.
+fn parse_package_workspaces(content: &str) -> Vec<String> {
+    let value: serde_json::Value = match serde_json::from_str(content) {
+        Ok(value) => value,
+        Err(_) => return Vec::new(),
+    };
+    let workspaces = match &value["workspaces"] {
+        serde_json::Value::Array(items) => items.clone(),
+        serde_json::Value::Object(map) => match map.get("packages") {
+            Some(serde_json::Value::Array(items)) => items.clone(),
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+    workspaces
+        .iter()
+        .filter_map(|item| item.as_str().map(|s| s.to_string()))
+        .collect()
+}
+
+/// Reads a top level string field from a JSON document
+fn json_string_field(
+    content: &str,
+    field: &str,
+) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(content).ok()?;
+    value[field].as_str().map(|s| s.to_string())
+}
+
+/// Returns the directories matching workspace globs, honoring `!` exclusions
+fn expand_globs(
+    dirs: &[&str],
+    globs: &[String],
+) -> Vec<String> {
+    let options = MatchOptions {
+        require_literal_separator: true,
+        ..MatchOptions::default()
+    };
+    let compile =
+        |glob: &str| Pattern::new(glob.trim_start_matches("./").trim_end_matches('/')).ok();
+    let includes: Vec<Pattern> = globs
+        .iter()
+        .filter(|g| !g.starts_with('!'))
+        .filter_map(|g| compile(g))
+        .collect();
+    let excludes: Vec<Pattern> = globs
+        .iter()
+        .filter_map(|g| g.strip_prefix('!'))
+        .filter_map(compile)
+        .collect();
+
+    let matched: BTreeSet<String> = dirs
+        .iter()
+        .filter(|dir| includes.iter().any(|p| p.matches_with(dir, options)))
+        .filter(|dir| !excludes.iter().any(|p| p.matches_with(dir, options)))
+        .map(|dir| dir.to_string())
+        .collect();
+    matched.into_iter().collect()
+}
+
+fn dir_name(dir: &str) -> String {
+    dir.rsplit('/').next().unwrap_or(dir).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn entries(
+        dirs: &[&str],
+        files: &[&str],
+    ) -> Vec<TreeEntry> {
+        let mut entries: Vec<TreeEntry> = dirs
+            .iter()
+            .map(|dir| TreeEntry {
+                path: dir.to_string(),
+                kind: EntryKind::Tree,
+                size: None,
+            })
+            .collect();
+        entries.extend(files.iter().map(|file| TreeEntry {
+            path: file.to_string(),
+            kind: EntryKind::Blob,
+            size: Some(1),
+        }));
+        entries
+    }
+
+    fn reader(files: HashMap<&'static str, &'static str>) -> impl Fn(&str) -> Option<String> {
+        move |path| files.get(path).map(|content| content.to_string())
+    }
+
+    #[test]
+    fn test_pnpm_workspace() {
+        let entries = entries(
+            &[
+                "apps",
+                "apps/web",
+                "apps/web/src",
+                "packages",
+                "packages/ui",
+                "packages/legacy",
+            ],
+            &[
+                "pnpm-workspace.yaml",
+                "apps/web/package.json",
+                "packages/ui/package.json",
+                "packages/legacy/package.json",
+            ],
+        );
+        let files = HashMap::from([
+            (
+                "pnpm-workspace.yaml",
+                "packages:\n  - 'apps/*'\n  - \"packages/*\"\n  - '!packages/legacy'\n",
+            ),
+            ("apps/web/package.json", r#"{"name": "web-app"}"#),
+            ("packages/ui/package.json", r#"{"name": "@acme/ui"}"#),
+        ]);
+
+        let projects = detect_projects(&entries, reader(files));
+
+        assert_eq!(projects.len(), 2);
+        assert_eq!(projects[0].name, "web-app");
+        assert_eq!(projects[0].dir, "apps/web");
+        assert_eq!(projects[0].kind, WorkspaceKind::Pnpm);
+        assert_eq!(projects[1].name, "@acme/ui");
+        assert!(projects[1].matches_name("ui"));
+        assert_eq!(projects[1].pattern(), "packages/ui/**");
+    }
+
+    #[test]
+    fn test_yarn_workspaces_with_turborepo() {
+        let entries = entries(
+            &["packages", "packages/api"],
+            &["package.json", "turbo.json", "packages/api/package.json"],
+        );
+        let files = HashMap::from([
+            (
+                "package.json",
+                r#"{"workspaces": {"packages": ["packages/*"]}}"#,
+            ),
+            ("packages/api/package.json", r#"{"name": "api"}"#),
+        ]);
+
+        let projects = detect_projects(&entries, reader(files));
+
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].kind, WorkspaceKind::Turborepo);
+    }
+
+    #[test]
+    fn test_cargo_workspace() {
+        let entries = entries(
+            &["crates", "crates/core", "crates/cli", "crates/old"],
+            &[
+                "Cargo.toml",
+                "crates/core/Cargo.toml",
+                "crates/cli/Cargo.toml",
+                "crates/old/Cargo.toml",
+            ],
+        );
+        let files = HashMap::from([
+            (
+                "Cargo.toml",
+                "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/old\"]\n",
+            ),
+            (
+                "crates/core/Cargo.toml",
+                "[package]\nname = \"acme-core\"\n",
+            ),
+        ]);
+
+        let projects = detect_projects(&entries, reader(files));
+
+        let names: Vec<&str> = projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["cli", "acme-core"]);
+    }
+
+    #[test]
+    fn test_nx_projects() {
+        let entries = entries(
+            &["libs", "libs/auth"],
+            &["nx.json", "libs/auth/project.json"],
+        );
+        let files = HashMap::from([("libs/auth/project.json", r#"{"name": "auth-lib"}"#)]);
+
+        let projects = detect_projects(&entries, reader(files));
+
+        assert_eq!(
+            projects,
+            vec![Project {
+                name: "auth-lib".to_string(),
+                dir: "libs/auth".to_string(),
+                kind: WorkspaceKind::Nx,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_gradle_projects() {
+        let entries = entries(
+            &[
+                "app",
+                "payments",
+                "payments/core",
+                "services",
+                "services/api",
+            ],
+            &["settings.gradle.kts", "app/build.gradle.kts"],
+        );
+        let files = HashMap::from([(
+            "settings.gradle.kts",
+            "include(\":app\", \":payments:core\", \":payments:api\", \":missing\")\nproject(\":payments:api\").projectDir = file(\"services/api\")\n",
+        )]);
+
+        let projects = detect_projects(&entries, reader(files));
+
+        let dirs: Vec<(&str, &str)> = projects
+            .iter()
+            .map(|p| (p.name.as_str(), p.dir.as_str()))
+            .collect();
+        assert_eq!(
+            dirs,
+            vec![
+                (":app", "app"),
+                (":payments:core", "payments/core"),
+                (":payments:api", "services/api"),
+            ]
+        );
+        assert!(projects[1].matches_name("payments:core"));
+        assert_eq!(projects[1].kind, WorkspaceKind::Gradle);
+    }
+
+    #[test]
+    fn test_maven_modules() {
+        let entries = entries(
+            &["payments", "payments/core", "common"],
+            &[
+                "pom.xml",
+                "payments/pom.xml",
+                "payments/core/pom.xml",
+                "common/pom.xml",
+            ],
+        );
+        let files = HashMap::from([
+            (
+                "pom.xml",
+                "<project><modules><module>payments</module><module>common</module></modules></project>",
+            ),
+            (
+                "payments/pom.xml",
+                "<project><artifactId>payments</artifactId><modules><module>core</module></modules></project>",
+            ),
+            (
+                "payments/core/pom.xml",
+                "<project><parent><artifactId>payments</artifactId></parent><artifactId>payments-core</artifactId></project>",
+            ),
+        ]);
+
+        let projects = detect_projects(&entries, reader(files));
+
+        let names: Vec<(&str, &str)> = projects
+            .iter()
+            .map(|p| (p.name.as_str(), p.dir.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("payments", "payments"),
+                ("common", "common"),
+                ("payments-core", "payments/core"),
+            ]
+        );
+    }
+}
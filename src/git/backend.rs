@@ -0,0 +1,214 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use crate::git::commands;
+
+/// The git operations behind the decision logic of commands like `status`
+/// and `smart-pull`, so that logic can run against an in-memory repository
+pub trait GitBackend {
+    /// Returns the checked out branch, empty when HEAD is detached
+    fn current_branch(&self) -> Result<String>;
+
+    /// Reads a configuration value, returning `None` when it is not set
+    fn config_value(
+        &self,
+        key: &str,
+    ) -> Option<String>;
+
+    /// Lists the configured remotes
+    fn remotes(&self) -> Result<Vec<String>>;
+
+    /// Fetches from a remote
+    fn fetch(
+        &self,
+        remote: &str,
+    ) -> Result<()>;
+
+    /// Resolves a revision to a commit id
+    fn rev_parse(
+        &self,
+        rev: &str,
+    ) -> Result<String>;
+
+    /// Checks if `ancestor` is reachable from `descendant`
+    fn is_ancestor(
+        &self,
+        ancestor: &str,
+        descendant: &str,
+    ) -> Result<bool>;
+
+    /// Counts the commits only on `local` and only on `other`
+    fn ahead_behind(
+        &self,
+        local: &str,
+        other: &str,
+    ) -> Result<(usize, usize)>;
+
+    /// Returns the lines of `git status --short`
+    fn status_short(&self) -> Result<Vec<String>>;
+
+    /// Lists tracked files deleted from the worktree but not staged
+    fn unstaged_deletions(&self) -> Result<Vec<String>>;
+
+    /// Fast-forwards the current branch to `rev`
+    fn merge_ff_only(
+        &self,
+        rev: &str,
+    ) -> Result<()>;
+
+    /// Points a branch other than the current one at `rev`, provided it
+    /// still points at `old`
+    fn update_branch(
+        &self,
+        branch: &str,
+        rev: &str,
+        old: &str,
+    ) -> Result<()>;
+}
+
+/// Runs the git executable in a repository
+pub struct CommandBackend {
+    repo_path: PathBuf,
+}
+
+impl CommandBackend {
+    pub fn new<P: AsRef<Path>>(repo_path: P) -> Self {
+        CommandBackend {
+            repo_path: repo_path.as_ref().to_path_buf(),
+        }
+    }
+
+    fn run(
+        &self,
+        args: &[&str],
+    ) -> Result<String> {
+        commands::run_git_command_in_dir(&self.repo_path, args)
+    }
+}
+
+impl GitBackend for CommandBackend {
+    fn current_branch(&self) -> Result<String> {
+        self.run(&["branch", "--show-current"])
+    }
+
+    fn config_value(
+        &self,
+        key: &str,
+    ) -> Option<String> {
+        commands::config_value(&self.repo_path, key)
+    }
+
+    fn remotes(&self) -> Result<Vec<String>> {
+        let output = self.run(&["remote"])?;
+        Ok(output.lines().map(|line| line.trim().to_string()).collect())
+    }
+
+    fn fetch(
+        &self,
+        remote: &str,
+    ) -> Result<()> {
+        self.run(&["fetch", remote, "--quiet"])?;
+        Ok(())
+    }
+
+    fn rev_parse(
+        &self,
+        rev: &str,
+    ) -> Result<String> {
+        self.run(&["rev-parse", rev])
+    }
+
+    fn is_ancestor(
+        &self,
+        ancestor: &str,
+        descendant: &str,
+    ) -> Result<bool> {
+        // Exit status 1 means "not an ancestor", anything else is an error
+        let output = commands::git_output(
+            Some(&self.repo_path),
+            &["merge-base", "--is-ancestor", ancestor, descendant],
+        )?;
+        match output.status.code() {
+            Some(0) => Ok(true),
+            Some(1) => Ok(false),
+            _ => anyhow::bail!(
+                "Git command failed: merge-base --is-ancestor {} {}",
+                ancestor,
+                descendant
+            ),
+        }
+    }
+
+    fn ahead_behind(
+        &self,
+        local: &str,
+        other: &str,
+    ) -> Result<(usize, usize)> {
+        let output = self.run(&[
+            "rev-list",
+            "--left-right",
+            "--count",
+            &format!("{}...{}", local, other),
+        ])?;
+        parse_left_right_counts(&output)
+            .ok_or_else(|| anyhow::anyhow!("Unexpected rev-list output: {}", output))
+    }
+
+    fn status_short(&self) -> Result<Vec<String>> {
+        // Not trimmed as a whole, the leading space of a line is significant.
+        // Without optional locks, the index is not refreshed, so an IDE's git
+        // running at the same time never finds it locked.
+        let output = commands::git_output(
+            Some(&self.repo_path),
+            &["--no-optional-locks", "status", "--short"],
+        )?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Git command failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect())
+    }
+
+    fn unstaged_deletions(&self) -> Result<Vec<String>> {
+        commands::unstaged_deletions(&self.repo_path)
+    }
+
+    fn merge_ff_only(
+        &self,
+        rev: &str,
+    ) -> Result<()> {
+        self.run(&["merge", "--ff-only", rev])?;
+        Ok(())
+    }
+
+    fn update_branch(
+        &self,
+        branch: &str,
+        rev: &str,
+        old: &str,
+    ) -> Result<()> {
+        self.run(&[
+            "update-ref",
+            "-m",
+            "git-partial: fast-forward",
+            &format!("refs/heads/{}", branch),
+            rev,
+            old,
+        ])?;
+        Ok(())
+    }
+}
+
+/// Parse the `<ahead>\t<behind>` output of `rev-list --left-right --count`
+fn parse_left_right_counts(output: &str) -> Option<(usize, usize)> {
+    let mut parts = output.split_whitespace();
+    let ahead = parts.next()?.parse().ok()?;
+    let behind = parts.next()?.parse().ok()?;
+    Some((ahead, behind))
+}
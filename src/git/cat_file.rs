@@ -0,0 +1,165 @@
+use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Stdio};
+
+use super::commands;
+
+/// What a `cat-file` process answers with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BatchMode {
+    /// `--batch`: the header and the content of each object
+    Contents,
+    /// `--batch-check`: the header only, without reading the content
+    Check,
+}
+
+/// The header git prints for an object: `<id> <type> <size>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectInfo {
+    pub id: String,
+    pub kind: String,
+    pub size: u64,
+}
+
+impl ObjectInfo {
+    /// Parses a header line. `<spec> missing` and `<spec> ambiguous` give `None`.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split(' ');
+        let id = fields.next()?;
+        let kind = fields.next()?;
+        let size = fields.next()?.parse().ok()?;
+        Some(ObjectInfo {
+            id: id.to_string(),
+            kind: kind.to_string(),
+            size,
+        })
+    }
+}
+
+/// A long-lived `git cat-file --batch` (or `--batch-check`) process. Each
+/// query is a line written to its stdin, so reading many objects costs one
+/// process instead of one per object. In a partial clone, git fetches a
+/// missing blob on demand, as `git cat-file blob` does.
+pub struct CatFile {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    mode: BatchMode,
+}
+
+impl CatFile {
+    pub fn spawn(
+        repo_path: &Path,
+        mode: BatchMode,
+    ) -> Result<Self> {
+        let flag = match mode {
+            BatchMode::Contents => "--batch",
+            BatchMode::Check => "--batch-check",
+        };
+        let mut child = commands::git_command()
+            .args(["cat-file", flag])
+            .current_dir(repo_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("Failed to execute git command")?;
+        let stdin = child.stdin.take().context("Failed to open git stdin")?;
+        let stdout = BufReader::new(child.stdout.take().context("Failed to open git stdout")?);
+        Ok(CatFile {
+            child,
+            stdin,
+            stdout,
+            mode,
+        })
+    }
+
+    /// Looks up an object by id or by a `rev:path` spec. Returns `None`
+    /// when it does not exist, with the content in `--batch` mode.
+    pub fn query(
+        &mut self,
+        spec: &str,
+    ) -> Result<Option<(ObjectInfo, Vec<u8>)>> {
+        if spec.contains('\n') {
+            anyhow::bail!("Object name {:?} contains a newline", spec);
+        }
+        writeln!(self.stdin, "{}", spec)?;
+        self.stdin.flush()?;
+
+        let mut header = String::new();
+        if self.stdout.read_line(&mut header)? == 0 {
+            anyhow::bail!("git cat-file exited unexpectedly");
+        }
+        let Some(info) = ObjectInfo::parse(header.trim_end_matches('\n')) else {
+            return Ok(None);
+        };
+        let mut content = Vec::new();
+        if self.mode == BatchMode::Contents {
+            // The content is followed by a newline
+            content.resize(info.size as usize + 1, 0);
+            self.stdout.read_exact(&mut content)?;
+            content.pop();
+        }
+        Ok(Some((info, content)))
+    }
+}
+
+impl Drop for CatFile {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_object_info() {
+        assert_eq!(
+            ObjectInfo::parse("e69de29bb2d1d6434b8b29ae775ad8c2e48c5391 blob 12"),
+            Some(ObjectInfo {
+                id: "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391".to_string(),
+                kind: "blob".to_string(),
+                size: 12,
+            })
+        );
+        assert_eq!(ObjectInfo::parse("HEAD:nope missing"), None);
+        assert_eq!(ObjectInfo::parse("abc ambiguous"), None);
+    }
+
+    #[test]
+    fn test_query_reuses_process() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let git = |args: &[&str]| commands::run_git_command_in_dir(dir.path(), args);
+        git(&["init", "--quiet"])?;
+        std::fs::write(dir.path().join("a.txt"), "first\n")?;
+        std::fs::write(dir.path().join("b.txt"), "second")?;
+        git(&["add", "."])?;
+        git(&[
+            "-c",
+            "user.name=Test",
+            "-c",
+            "user.email=test@example.com",
+            "commit",
+            "--quiet",
+            "-m",
+            "Initial commit",
+        ])?;
+
+        let mut batch = CatFile::spawn(dir.path(), BatchMode::Contents)?;
+        let (info, content) = batch.query("HEAD:a.txt")?.unwrap();
+        assert_eq!((info.kind.as_str(), info.size), ("blob", 6));
+        assert_eq!(content, b"first\n");
+        assert!(batch.query("HEAD:missing.txt")?.is_none());
+        assert_eq!(batch.query("HEAD:b.txt")?.unwrap().1, b"second");
+
+        let mut check = CatFile::spawn(dir.path(), BatchMode::Check)?;
+        let (info, content) = check.query("HEAD:b.txt")?.unwrap();
+        assert_eq!(info.size, 6);
+        assert!(content.is_empty());
+        Ok(())
+    }
+}
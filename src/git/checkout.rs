@@ -0,0 +1,156 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use std::path::Path;
+
+use crate::git::commands;
+
+/// Line ending conversion on checkout and commit (`core.autocrlf`)
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum AutoCrlf {
+    /// Check out CRLF, commit LF (the Git for Windows installer default)
+    True,
+    /// Keep the line endings stored in the repository
+    False,
+    /// Check out as stored, convert CRLF to LF on commit
+    Input,
+}
+
+impl AutoCrlf {
+    fn as_str(self) -> &'static str {
+        match self {
+            AutoCrlf::True => "true",
+            AutoCrlf::False => "false",
+            AutoCrlf::Input => "input",
+        }
+    }
+}
+
+/// Settings a clone needs before its first checkout. On Windows, paths
+/// longer than 260 characters fail to check out without `core.longpaths`,
+/// leaving a half-populated working tree.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CheckoutSettings {
+    pub windows: bool,
+    pub autocrlf: Option<AutoCrlf>,
+}
+
+impl CheckoutSettings {
+    /// Settings for this platform and the line ending choice, if any
+    pub fn for_platform(autocrlf: Option<AutoCrlf>) -> Self {
+        CheckoutSettings {
+            windows: cfg!(windows),
+            autocrlf,
+        }
+    }
+
+    /// Configuration entries the clone is given
+    pub fn config(&self) -> Vec<(&'static str, &'static str)> {
+        let mut config = Vec::new();
+        if self.windows {
+            config.push(("core.longpaths", "true"));
+        }
+        if let Some(autocrlf) = self.autocrlf {
+            config.push(("core.autocrlf", autocrlf.as_str()));
+        }
+        config
+    }
+
+    /// Applies the settings to every git command of this run, so the
+    /// clone's own checkout uses them
+    pub fn apply(&self) {
+        commands::set_config_overrides(
+            self.config()
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        );
+    }
+
+    /// Advice to choose line endings on Windows, where the choice changes
+    /// every checked out file, when neither `--autocrlf` nor a global
+    /// `core.autocrlf` made it. `configured` is the global value, if any.
+    pub fn autocrlf_advice(
+        &self,
+        configured: Option<&str>,
+    ) -> Option<String> {
+        if !self.windows || self.autocrlf.is_some() || configured.is_some() {
+            return None;
+        }
+        Some(
+            "core.autocrlf is not set, so files are checked out with the line endings \
+             stored in the repository. Pass --autocrlf true to check out CRLF, or \
+             --autocrlf input to only convert CRLF to LF on commit."
+                .to_string(),
+        )
+    }
+}
+
+/// Store the settings in the repository's config, so later checkouts by
+/// git-partial or plain git behave the same
+pub fn configure(
+    repo_path: &Path,
+    settings: &CheckoutSettings,
+) -> Result<()> {
+    for (key, value) in settings.config() {
+        commands::run_git_command_in_dir(repo_path, &["config", key, value])?;
+    }
+    Ok(())
+}
+
+/// Make `git status` fast in a large working tree: the untracked cache
+/// remembers which directories did not change, and the built-in file system
+/// monitor, where git has one (macOS and Windows), tells git which files did.
+/// Returns whether the monitor was enabled.
+pub fn enable_fsmonitor(repo_path: &Path) -> Result<bool> {
+    commands::run_git_command_in_dir(repo_path, &["config", "core.untrackedCache", "true"])?;
+    if !cfg!(any(target_os = "macos", windows)) {
+        return Ok(false);
+    }
+    commands::run_git_command_in_dir(repo_path, &["config", "core.fsmonitor", "true"])?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config() {
+        let settings = CheckoutSettings {
+            windows: true,
+            autocrlf: Some(AutoCrlf::Input),
+        };
+        assert_eq!(
+            settings.config(),
+            vec![("core.longpaths", "true"), ("core.autocrlf", "input")]
+        );
+
+        let settings = CheckoutSettings {
+            windows: false,
+            autocrlf: None,
+        };
+        assert!(settings.config().is_empty());
+    }
+
+    #[test]
+    fn test_autocrlf_advice() {
+        let windows = CheckoutSettings {
+            windows: true,
+            autocrlf: None,
+        };
+        assert!(windows.autocrlf_advice(None).is_some());
+        assert_eq!(windows.autocrlf_advice(Some("true")), None);
+
+        let chosen = CheckoutSettings {
+            autocrlf: Some(AutoCrlf::False),
+            ..windows
+        };
+        assert_eq!(chosen.autocrlf_advice(None), None);
+
+        let unix = CheckoutSettings {
+            windows: false,
+            autocrlf: None,
+        };
+        assert_eq!(unix.autocrlf_advice(None), None);
+    }
+}
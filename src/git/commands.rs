@@ -1,21 +1,122 @@
 use anyhow::{Context, Result};
-use std::path::Path;
-use std::process::Command;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::time::Instant;
+use tracing::{debug_span, field};
 
-/// Run a git command and return the output
-pub fn run_git_command(args: &[&str]) -> Result<String> {
-    let output = Command::new("git")
-        .args(args)
-        .output()
-        .context("Failed to execute git command")?;
+use crate::core::config;
+use crate::core::incoming::{self, FileChange};
+use crate::core::path_selector;
+use crate::core::tree::{self, TreeEntry};
+use crate::core::url_rewrite::{self, UrlRewrite};
+use crate::git::cat_file::{BatchMode, CatFile, ObjectInfo};
+use crate::git::lfs;
+use crate::git::trace;
+use crate::utils::interrupt;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Git command failed: {}", stderr);
+/// Parallel checkout workers passed to every git invocation, if configured
+static CHECKOUT_WORKERS: OnceLock<usize> = OnceLock::new();
+
+/// Make git check out files with `workers` parallel workers (`checkout.workers`)
+pub fn set_checkout_workers(workers: usize) {
+    CHECKOUT_WORKERS.set(workers).ok();
+}
+
+/// SSH command passed to every git invocation, if given on the command line
+static SSH_COMMAND: OnceLock<String> = OnceLock::new();
+
+/// Make git connect to SSH servers with `command` (`core.sshCommand`)
+pub fn set_ssh_command(command: String) {
+    SSH_COMMAND.set(command).ok();
+}
+
+/// Configuration passed to every git invocation, such as settings a clone
+/// needs for its first checkout
+static CONFIG_OVERRIDES: OnceLock<Vec<(String, String)>> = OnceLock::new();
+
+/// Make every git command run with `config` (`git -c key=value`)
+pub fn set_config_overrides(config: Vec<(String, String)>) {
+    CONFIG_OVERRIDES.set(config).ok();
+}
+
+/// Client certificate settings (`http.sslCert`, `http.sslKey`) for HTTPS
+/// servers requiring mutual TLS
+static TLS_CONFIG: OnceLock<Vec<(String, String)>> = OnceLock::new();
+
+/// Make git present a client certificate to HTTPS servers
+pub fn set_tls_config(config: Vec<(String, String)>) {
+    TLS_CONFIG.set(config).ok();
+}
+
+/// HTTP header git sends to a URL prefix, such as an `Authorization` header
+/// for the remote's host
+static HTTP_HEADER: OnceLock<(String, String)> = OnceLock::new();
+
+/// Make git send `header` with every request to URLs under `url`
+/// (`http.<url>.extraHeader`). It is passed through the environment so
+/// the secret does not show up in process listings.
+pub fn set_http_header(
+    url: String,
+    header: String,
+) {
+    HTTP_HEADER.set((url, header)).ok();
+}
+
+/// `cat-file` processes kept running for the rest of the run, per
+/// repository and mode
+static CAT_FILES: OnceLock<Mutex<HashMap<(PathBuf, BatchMode), CatFile>>> = OnceLock::new();
+
+/// Set in the environment of the git commands git-partial runs, so the hooks
+/// they trigger know git-partial is already taking care of its metadata
+pub const INTERNAL_ENV: &str = "GIT_PARTIAL_INTERNAL";
+
+/// A `git` command with the options of this run: checkout workers, the
+/// SSH command, the client certificate, and the HTTP header
+pub fn git_command() -> Command {
+    let mut command = Command::new("git");
+    command.env(INTERNAL_ENV, "1");
+    if let Some(workers) = CHECKOUT_WORKERS.get() {
+        command
+            .arg("-c")
+            .arg(format!("checkout.workers={}", workers));
+    }
+    if let Some(ssh_command) = SSH_COMMAND.get() {
+        command
+            .arg("-c")
+            .arg(format!("core.sshCommand={}", ssh_command));
+    }
+    for (key, value) in CONFIG_OVERRIDES
+        .get()
+        .into_iter()
+        .chain(TLS_CONFIG.get())
+        .flatten()
+    {
+        command.arg("-c").arg(format!("{}={}", key, value));
+    }
+    if let Some((url, header)) = HTTP_HEADER.get() {
+        // Append to any configuration already passed this way
+        let index: usize = std::env::var("GIT_CONFIG_COUNT")
+            .ok()
+            .and_then(|count| count.parse().ok())
+            .unwrap_or(0);
+        command
+            .env(
+                format!("GIT_CONFIG_KEY_{}", index),
+                format!("http.{}.extraHeader", url),
+            )
+            .env(format!("GIT_CONFIG_VALUE_{}", index), header)
+            .env("GIT_CONFIG_COUNT", (index + 1).to_string());
     }
+    command
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    Ok(stdout.trim().to_string())
+/// Run a git command and return the output
+pub fn run_git_command(args: &[&str]) -> Result<String> {
+    stdout_of(git_output(None, args)?)
 }
 
 /// Run a git command in a specific directory and return the output
@@ -23,12 +124,62 @@ pub fn run_git_command_in_dir<P: AsRef<Path>>(
     dir: P,
     args: &[&str],
 ) -> Result<String> {
-    let output = Command::new("git")
-        .current_dir(dir.as_ref())
+    stdout_of(git_output(Some(dir.as_ref()), args)?)
+}
+
+/// Run git and wait for it, inside a `git` span that records the
+/// duration and exit status of the invocation
+pub fn git_output(
+    dir: Option<&Path>,
+    args: &[&str],
+) -> Result<Output> {
+    let span = debug_span!(
+        "git",
+        args = %args.join(" "),
+        duration_ms = field::Empty,
+        exit_code = field::Empty
+    );
+    let _entered = span.enter();
+
+    let mut command = git_command();
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    let started = Instant::now();
+    // Spawned rather than `output()` so an interrupt can terminate it
+    let child = command
         .args(args)
-        .output()
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
         .context("Failed to execute git command")?;
+    let pid = child.id();
+    interrupt::track_child(pid);
+    let output = child.wait_with_output();
+    interrupt::untrack_child(pid);
+    let output = output.context("Failed to execute git command")?;
+    trace::record(&command, started, &output);
+
+    span.record("duration_ms", started.elapsed().as_millis() as u64);
+    if let Some(code) = output.status.code() {
+        span.record("exit_code", code);
+    }
+    Ok(output)
+}
+
+/// Split the NUL-terminated paths git prints with `-z`, which are not
+/// quoted the way non-ASCII paths are in line-based output
+pub fn nul_separated(output: &str) -> Vec<String> {
+    output
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(str::to_string)
+        .collect()
+}
 
+/// Return the trimmed stdout of a successful invocation, or its stderr as the error
+fn stdout_of(output: Output) -> Result<String> {
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         anyhow::bail!("Git command failed: {}", stderr);
@@ -38,31 +189,40 @@ pub fn run_git_command_in_dir<P: AsRef<Path>>(
     Ok(stdout.trim().to_string())
 }
 
-/// Clone a repository using sparse checkout
+/// Read the `url.<base>.insteadOf` rules that apply in `dir`, or outside
+/// any repository when `None`
+pub fn url_rewrites(dir: Option<&Path>) -> Vec<UrlRewrite> {
+    let args = ["config", "--get-regexp", r"^url\..*\.insteadof$"];
+    let output = match dir {
+        Some(dir) => run_git_command_in_dir(dir, &args),
+        None => run_git_command(&args),
+    };
+    // git exits with 1 when no key matches
+    url_rewrite::parse_rules(&output.unwrap_or_default())
+}
+
+/// Clone a repository using sparse checkout, passing extra options to `git clone`
 pub fn clone_sparse(
     repo_url: &str,
     destination: &str,
+    extra_args: &[&str],
 ) -> Result<()> {
     // Use git clone with sparse checkout options again
-    run_git_command(&[
-        "clone",
-        "--filter=blob:none",
-        "--sparse",
-        repo_url,
-        destination,
-    ])?;
+    let filter = format!("--filter={}", config::clone_filter());
+    let mut args = vec!["clone", &filter, "--sparse"];
+    args.extend_from_slice(extra_args);
+    args.extend_from_slice(&["--", repo_url, destination]);
+    run_git_command(&args)?;
 
     Ok(())
 }
 
-/// Set sparse checkout paths
-pub fn set_sparse_checkout(
-    repo_path: &Path,
-    paths: &[String],
-) -> Result<()> {
+/// Convert git-partial paths into the patterns written to the sparse checkout.
+/// Git has no brace expansion, so alternatives are written out one per line.
+pub fn sparse_patterns(paths: &[String]) -> Vec<String> {
     // Prepend '/' to root-level files/dirs to avoid matching nested ones.
     // We only do this for paths without '/' or glob characters.
-    let processed_paths: Vec<String> = paths
+    path_selector::expand_patterns(paths)
         .iter()
         .map(|p| {
             if !p.contains('/') && !p.contains('*') && !p.contains('?') && !p.contains('[') {
@@ -71,25 +231,548 @@ pub fn set_sparse_checkout(
                 p.clone()
             }
         })
-        .collect();
+        .collect()
+}
 
-    let paths_str: Vec<&str> = processed_paths.iter().map(|s| s.as_str()).collect();
+/// Set sparse checkout paths
+pub fn set_sparse_checkout(
+    repo_path: &Path,
+    paths: &[String],
+) -> Result<()> {
+    // Cone mode is faster but only selects whole directories. Root-level
+    // names may be files, which cone mode includes anyway, so skip the check.
+    let cone_dirs = match config::global().cone {
+        Some(true) => path_selector::patterns_to_cone(paths),
+        _ => None,
+    };
+    let (mode_args, processed_paths) = match cone_dirs {
+        Some(dirs) => (vec!["--cone", "--skip-checks"], dirs),
+        None => (vec!["--no-cone"], sparse_patterns(paths)),
+    };
 
-    // Run sparse-checkout command in the repository directory
-    let mut args = vec!["sparse-checkout", "set", "--no-cone", "--"];
-    args.extend(paths_str);
-    run_git_command_in_dir(repo_path, &args)?;
+    // LFS objects are downloaded during checkout, so scope them first
+    lfs::configure_fetch_include(repo_path, paths)?;
+
+    let (cone, wanted) = wanted_sparse_patterns(paths);
+    let index = run_git_command_in_dir(repo_path, &["rev-parse", "--git-path", "index"])?;
+    let checked_out = repo_path.join(index).exists();
+    let previous = applied_sparse_patterns(repo_path);
 
-    // After setting paths, update the working directory using checkout
-    // This seems to correctly remove files/dirs not matching the new patterns.
-    run_git_command_in_dir(repo_path, &["checkout", "HEAD", "--force"])?;
-    // run_git_command_in_dir(repo_path, &["rm", "-r", "--cached", "."])?;
-    // run_git_command_in_dir(repo_path, &["reset", "--hard", "HEAD"])?;
+    if checked_out && previous.as_ref() == Some(&(cone, wanted.clone())) {
+        run_git_command_in_dir(repo_path, &["sparse-checkout", "reapply"])?;
+    } else {
+        // `set` writes the patterns and reapplies them, updating only the
+        // files whose inclusion changed
+        let mut args = vec!["sparse-checkout", "set"];
+        args.extend(mode_args);
+        args.push("--");
+        args.extend(processed_paths.iter().map(|s| s.as_str()));
+        run_git_command_in_dir(repo_path, &args)?;
+    }
 
+    if !checked_out {
+        // Nothing was checked out yet (a worktree or a clone from a bundle),
+        // so populate the index and the working tree once
+        run_git_command_in_dir(repo_path, &["checkout", "HEAD", "--force"])?;
+        return Ok(());
+    }
+    let previous = previous.map(|(_, patterns)| patterns).unwrap_or_default();
+    restore_newly_matched(repo_path, &previous, &wanted)
+}
+
+/// Whether `set_sparse_checkout` uses cone mode for `paths`, and the
+/// patterns it applies, in the form `applied_sparse_patterns` reads back
+pub fn wanted_sparse_patterns(paths: &[String]) -> (bool, Vec<String>) {
+    match config::global().cone {
+        Some(true) => match path_selector::patterns_to_cone(paths) {
+            Some(dirs) => (true, path_selector::cone_to_patterns(&dirs, &[])),
+            None => (false, sparse_patterns(paths)),
+        },
+        _ => (false, sparse_patterns(paths)),
+    }
+}
+
+/// Read the applied sparse checkout patterns and whether they are in cone
+/// mode, with cone directories as `dir/**`. `None` when there are none.
+pub fn applied_sparse_patterns(repo_path: &Path) -> Option<(bool, Vec<String>)> {
+    if config_value(repo_path, "core.sparseCheckout").as_deref() != Some("true") {
+        return None;
+    }
+    let sparse_file = run_git_command_in_dir(
+        repo_path,
+        &["rev-parse", "--git-path", "info/sparse-checkout"],
+    )
+    .ok()?;
+    let content = std::fs::read_to_string(repo_path.join(sparse_file)).ok()?;
+    let cone = config_value(repo_path, "core.sparseCheckoutCone").as_deref() == Some("true");
+    let patterns = if cone {
+        path_selector::cone_to_patterns(&path_selector::parse_cone_file(&content), &[])
+    } else {
+        path_selector::parse_pattern_file(&content)
+    };
+    Some((cone, patterns))
+}
+
+/// Check out the files the new patterns match but the previous ones did
+/// not, when they are missing without being marked skip-worktree (e.g. after
+/// the bit was lost). Sparse checkout only brings back skip-worktree files.
+/// Files within the previous patterns are left as they are, with any local
+/// changes or deletions.
+fn restore_newly_matched(
+    repo_path: &Path,
+    previous: &[String],
+    current: &[String],
+) -> Result<()> {
+    let newly_matched = path_selector::outside_patterns(&unstaged_deletions(repo_path)?, previous);
+    let excluded = path_selector::outside_patterns(&newly_matched, current);
+    let missing: Vec<&str> = newly_matched
+        .iter()
+        .filter(|path| !excluded.contains(path))
+        .map(String::as_str)
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    let mut args = vec!["checkout", "HEAD", "--"];
+    args.extend(missing);
+    run_git_command_in_dir(repo_path, &args)?;
     Ok(())
 }
 
+/// Write the sparse checkout patterns without updating the working tree.
+/// Used offline, when the blobs for a checkout cannot be fetched.
+pub fn write_sparse_patterns(
+    repo_path: &Path,
+    paths: &[String],
+) -> Result<()> {
+    let sparse_file = run_git_command_in_dir(
+        repo_path,
+        &["rev-parse", "--git-path", "info/sparse-checkout"],
+    )?;
+    let mut content = sparse_patterns(paths).join("\n");
+    content.push('\n');
+    std::fs::write(repo_path.join(sparse_file), content)
+        .context("Failed to write sparse checkout patterns")?;
+    // The patterns are not cone directories, even if the last `set` used cone mode
+    run_git_command_in_dir(repo_path, &["config", "core.sparseCheckoutCone", "false"])?;
+    lfs::configure_fetch_include(repo_path, paths)
+}
+
+/// Checks if a git failure was caused by the promisor remote being unreachable
+pub fn is_promisor_fetch_failure(error: &anyhow::Error) -> bool {
+    format!("{:#}", error).contains("from promisor remote")
+}
+
 /// Get the current HEAD commit SHA
 pub fn get_head_commit<P: AsRef<Path>>(repo_path: P) -> Result<String> {
     run_git_command_in_dir(repo_path, &["rev-parse", "HEAD"])
 }
+
+/// The branch checked out, or None on a detached HEAD
+pub fn current_branch<P: AsRef<Path>>(repo_path: P) -> Option<String> {
+    run_git_command_in_dir(repo_path, &["symbolic-ref", "--quiet", "--short", "HEAD"]).ok()
+}
+
+/// List all file paths in the tree of the given revision
+pub fn list_tree_files<P: AsRef<Path>>(
+    repo_path: P,
+    rev: &str,
+) -> Result<Vec<String>> {
+    let output = run_git_command_in_dir(repo_path, &["ls-tree", "-r", "-z", "--name-only", rev])?;
+    Ok(nul_separated(&output))
+}
+
+/// List all files and directories, with blob sizes, in the tree of the given revision
+pub fn list_tree_entries<P: AsRef<Path>>(
+    repo_path: P,
+    rev: &str,
+) -> Result<Vec<TreeEntry>> {
+    let output = run_git_command_in_dir(repo_path, &["ls-tree", "-r", "-t", "-l", "-z", rev])?;
+    Ok(tree::parse_ls_tree(&output))
+}
+
+/// List all files and directories in the tree of the given revision, without
+/// sizes. Only tree objects are read, so no blobs are fetched in a partial clone.
+pub fn list_tree_structure<P: AsRef<Path>>(
+    repo_path: P,
+    rev: &str,
+) -> Result<Vec<TreeEntry>> {
+    let output = run_git_command_in_dir(repo_path, &["ls-tree", "-r", "-t", "-z", rev])?;
+    Ok(tree::parse_ls_tree(&output))
+}
+
+/// List the object id and path of every file in the tree of the given
+/// revision. Only tree objects are read.
+pub fn list_blob_ids<P: AsRef<Path>>(
+    repo_path: P,
+    rev: &str,
+) -> Result<Vec<(String, String)>> {
+    let output = run_git_command_in_dir(repo_path, &["ls-tree", "-r", "-z", rev])?;
+    Ok(nul_separated(&output)
+        .into_iter()
+        .filter_map(|record| {
+            let (info, path) = record.split_once('\t')?;
+            let mut fields = info.split_whitespace();
+            let kind = fields.nth(1)?;
+            let id = fields.next()?;
+            (kind == "blob").then(|| (id.to_string(), path.to_string()))
+        })
+        .collect())
+}
+
+/// List the symlinks in the tree of the given revision. Only tree objects are read.
+pub fn list_symlinks<P: AsRef<Path>>(
+    repo_path: P,
+    rev: &str,
+) -> Result<Vec<String>> {
+    let output = run_git_command_in_dir(repo_path, &["ls-tree", "-r", "-z", rev])?;
+    Ok(nul_separated(&output)
+        .into_iter()
+        .filter(|record| record.starts_with("120000 "))
+        .filter_map(|record| Some(record.split_once('\t')?.1.to_string()))
+        .collect())
+}
+
+/// Read the content of a file at the given revision, without checking it out
+pub fn show_file<P: AsRef<Path>>(
+    repo_path: P,
+    rev: &str,
+    path: &str,
+) -> Result<String> {
+    let spec = format!("{}:{}", rev, path);
+    let content =
+        read_object(repo_path, &spec)?.with_context(|| format!("{} does not exist", spec))?;
+    Ok(String::from_utf8_lossy(&content).trim().to_string())
+}
+
+/// Query the pooled `cat-file` process of a repository, starting it on
+/// first use. A process that fails is dropped and restarted next time.
+fn query_cat_file(
+    repo_path: &Path,
+    spec: &str,
+    mode: BatchMode,
+) -> Result<Option<(ObjectInfo, Vec<u8>)>> {
+    let key = (
+        fs::canonicalize(repo_path).unwrap_or_else(|_| repo_path.to_path_buf()),
+        mode,
+    );
+    let mut pool = CAT_FILES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if !pool.contains_key(&key) {
+        pool.insert(key.clone(), CatFile::spawn(repo_path, mode)?);
+    }
+    let result = pool
+        .get_mut(&key)
+        .context("Failed to start git cat-file")?
+        .query(spec);
+    if result.is_err() {
+        pool.remove(&key);
+    }
+    result
+}
+
+/// Read an object by id or `rev:path` spec through a long-lived
+/// `git cat-file --batch`. Returns `None` when it does not exist.
+pub fn read_object<P: AsRef<Path>>(
+    repo_path: P,
+    spec: &str,
+) -> Result<Option<Vec<u8>>> {
+    Ok(query_cat_file(repo_path.as_ref(), spec, BatchMode::Contents)?.map(|(_, content)| content))
+}
+
+/// Look up the type and size of an object through a long-lived
+/// `git cat-file --batch-check`. Returns `None` when it does not exist.
+pub fn object_info<P: AsRef<Path>>(
+    repo_path: P,
+    spec: &str,
+) -> Result<Option<ObjectInfo>> {
+    Ok(query_cat_file(repo_path.as_ref(), spec, BatchMode::Check)?.map(|(info, _)| info))
+}
+
+/// List the paths staged for the next commit, including deletions
+pub fn staged_paths<P: AsRef<Path>>(repo_path: P) -> Result<Vec<String>> {
+    let output = run_git_command_in_dir(
+        repo_path,
+        &["diff", "--cached", "-z", "--name-only", "--no-renames"],
+    )?;
+    Ok(nul_separated(&output))
+}
+
+/// List the files changed between two commits. Only trees are compared,
+/// so no blobs are fetched in a partial clone.
+pub fn diff_name_status<P: AsRef<Path>>(
+    repo_path: P,
+    from: &str,
+    to: &str,
+) -> Result<Vec<FileChange>> {
+    let output = run_git_command_in_dir(
+        repo_path,
+        &["diff", "--name-status", "--no-renames", "-z", from, to],
+    )?;
+    Ok(incoming::parse_name_status(&output))
+}
+
+/// Download the blobs of `paths` that differ between two commits. Diffing
+/// their content makes git fetch every missing blob in one batch from the
+/// promisor remote, instead of one at a time during a later checkout.
+pub fn prefetch_blobs<P: AsRef<Path>>(
+    repo_path: P,
+    from: &str,
+    to: &str,
+    paths: &[&str],
+) -> Result<()> {
+    // Chunked to stay below the command line length limit
+    for chunk in paths.chunks(1000) {
+        let pathspecs: Vec<String> = chunk
+            .iter()
+            .map(|path| format!(":(literal){}", path))
+            .collect();
+        let mut args = vec!["diff", "--numstat", "--no-renames", from, to, "--"];
+        args.extend(pathspecs.iter().map(String::as_str));
+        run_git_command_in_dir(&repo_path, &args)?;
+    }
+    Ok(())
+}
+
+/// Download missing objects by id from the promisor remote, in batches, the
+/// way git's own lazy fetch does
+pub fn fetch_objects<P: AsRef<Path>>(
+    repo_path: P,
+    ids: &[String],
+) -> Result<()> {
+    let remote =
+        config_value(&repo_path, "extensions.partialclone").unwrap_or_else(|| "origin".to_string());
+    // Chunked to stay below the command line length limit
+    for chunk in ids.chunks(1000) {
+        let mut args = vec![
+            "-c",
+            "fetch.negotiationAlgorithm=noop",
+            "fetch",
+            remote.as_str(),
+            "--no-tags",
+            "--no-write-fetch-head",
+            "--recurse-submodules=no",
+            "--filter=blob:none",
+        ];
+        args.extend(chunk.iter().map(String::as_str));
+        run_git_command_in_dir(&repo_path, &args)?;
+    }
+    Ok(())
+}
+
+/// List the tracked paths deleted from the working tree but not staged
+pub fn unstaged_deletions<P: AsRef<Path>>(repo_path: P) -> Result<Vec<String>> {
+    let output = run_git_command_in_dir(
+        repo_path,
+        &[
+            "--no-optional-locks",
+            "diff",
+            "-z",
+            "--name-only",
+            "--no-renames",
+            "--diff-filter=D",
+        ],
+    )?;
+    Ok(nul_separated(&output))
+}
+
+/// Mark paths as skip-worktree so git stops reporting them as deleted
+pub fn set_skip_worktree<P: AsRef<Path>>(
+    repo_path: P,
+    paths: &[String],
+) -> Result<()> {
+    let mut args = vec!["update-index", "--skip-worktree", "--"];
+    args.extend(paths.iter().map(|path| path.as_str()));
+    run_git_command_in_dir(repo_path, &args)?;
+    Ok(())
+}
+
+/// Clear the skip-worktree bit of paths so git tracks them in the working tree again
+pub fn clear_skip_worktree<P: AsRef<Path>>(
+    repo_path: P,
+    paths: &[String],
+) -> Result<()> {
+    let mut args = vec!["update-index", "--no-skip-worktree", "--"];
+    args.extend(paths.iter().map(|path| path.as_str()));
+    run_git_command_in_dir(repo_path, &args)?;
+    Ok(())
+}
+
+/// List the files in the index with whether each is marked skip-worktree.
+/// Unmerged entries are left out.
+pub fn skip_worktree_entries<P: AsRef<Path>>(repo_path: P) -> Result<Vec<(String, bool)>> {
+    let output = run_git_command_in_dir(repo_path, &["ls-files", "-t", "-z"])?;
+    Ok(output
+        .split('\0')
+        .filter_map(|entry| match entry.split_once(' ')? {
+            ("H", path) => Some((path.to_string(), false)),
+            ("S", path) => Some((path.to_string(), true)),
+            _ => None,
+        })
+        .collect())
+}
+
+/// List the tracked files whose working tree copy differs from the index,
+/// deletions included
+pub fn unstaged_changes<P: AsRef<Path>>(repo_path: P) -> Result<Vec<String>> {
+    let output = run_git_command_in_dir(
+        repo_path,
+        &[
+            "--no-optional-locks",
+            "diff",
+            "-z",
+            "--name-only",
+            "--no-renames",
+        ],
+    )?;
+    Ok(nul_separated(&output))
+}
+
+/// Count the objects in the local object store, loose and packed
+pub fn count_objects<P: AsRef<Path>>(repo_path: P) -> Result<u64> {
+    let output = run_git_command_in_dir(repo_path, &["count-objects", "-v"])?;
+    Ok(output
+        .lines()
+        .filter_map(|line| line.split_once(": "))
+        .filter(|(key, _)| *key == "count" || *key == "in-pack")
+        .filter_map(|(_, value)| value.trim().parse::<u64>().ok())
+        .sum())
+}
+
+/// Count the blobs in the local object store. Nothing is fetched from the
+/// promisor remote.
+pub fn count_local_blobs<P: AsRef<Path>>(repo_path: P) -> Result<usize> {
+    let output = run_git_command_in_dir(
+        repo_path,
+        &[
+            "cat-file",
+            "--batch-all-objects",
+            "--batch-check=%(objecttype)",
+        ],
+    )?;
+    Ok(output.lines().filter(|line| *line == "blob").count())
+}
+
+/// Delete `refs` in one transaction, which stays fast for thousands of refs
+pub fn delete_refs<P: AsRef<Path>>(
+    repo_path: P,
+    refs: &[&str],
+) -> Result<()> {
+    let mut command = git_command();
+    command
+        .args(["update-ref", "--stdin"])
+        .current_dir(repo_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let started = Instant::now();
+    let mut child = command.spawn().context("Failed to execute git command")?;
+    let mut stdin = child.stdin.take().context("Failed to open git stdin")?;
+    for name in refs {
+        writeln!(stdin, "delete {}", name)?;
+    }
+    drop(stdin);
+    let output = child.wait_with_output()?;
+    trace::record(&command, started, &output);
+    stdout_of(output).map(|_| ())
+}
+
+/// Get the number of objects reachable from all refs and their size on disk
+pub fn reachable_objects<P: AsRef<Path>>(repo_path: P) -> Result<(u64, u64)> {
+    let repo_path = repo_path.as_ref();
+    let objects = run_git_command_in_dir(repo_path, &["rev-list", "--objects", "--all"])?
+        .lines()
+        .count() as u64;
+    let bytes = run_git_command_in_dir(
+        repo_path,
+        &["rev-list", "--disk-usage", "--objects", "--all"],
+    )?
+    .trim()
+    .parse()
+    .context("Failed to parse disk usage")?;
+    Ok((objects, bytes))
+}
+
+/// Get the directory git runs hooks from, honoring `core.hooksPath`
+pub fn hooks_dir<P: AsRef<Path>>(repo_path: P) -> Result<PathBuf> {
+    let repo_path = repo_path.as_ref();
+    let output = run_git_command_in_dir(repo_path, &["rev-parse", "--git-path", "hooks"])?;
+    Ok(repo_path.join(output))
+}
+
+/// Create a linked worktree for a branch without checking out any files yet
+pub fn add_worktree<P: AsRef<Path>>(
+    repo_path: P,
+    worktree_path: &Path,
+    branch: &str,
+) -> Result<()> {
+    let worktree_path = worktree_path.to_string_lossy();
+    run_git_command_in_dir(
+        repo_path,
+        &[
+            "worktree",
+            "add",
+            "--no-checkout",
+            "--",
+            &worktree_path,
+            branch,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Read a git configuration value, returning `None` when it is not set
+pub fn config_value<P: AsRef<Path>>(
+    repo_path: P,
+    key: &str,
+) -> Option<String> {
+    run_git_command_in_dir(repo_path, &["config", "--get", key])
+        .ok()
+        .filter(|value| !value.is_empty())
+}
+
+/// Read every value of a multi-valued git config key, in config order
+pub fn config_values<P: AsRef<Path>>(
+    repo_path: P,
+    key: &str,
+) -> Vec<String> {
+    run_git_command_in_dir(repo_path, &["config", "--get-all", key])
+        .map(|output| output.lines().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// Read a git config integer, expanding `k`, `m` and `g` suffixes
+pub fn config_int<P: AsRef<Path>>(
+    repo_path: P,
+    key: &str,
+) -> Option<u64> {
+    run_git_command_in_dir(repo_path, &["config", "--type=int", "--get", key])
+        .ok()
+        .and_then(|value| value.parse().ok())
+}
+
+/// List the sparse checkout patterns (directories in cone mode)
+pub fn sparse_checkout_list<P: AsRef<Path>>(repo_path: P) -> Result<Vec<String>> {
+    let output = run_git_command_in_dir(repo_path, &["sparse-checkout", "list"])?;
+    Ok(output
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect())
+}
+
+/// Read the raw content of a blob such as `HEAD:path/to/file`.
+/// In a partial clone, git fetches a missing blob from the promisor remote.
+pub fn read_blob<P: AsRef<Path>>(
+    repo_path: P,
+    spec: &str,
+) -> Result<Vec<u8>> {
+    match object_info(&repo_path, spec)? {
+        Some(info) if info.kind == "blob" => {}
+        Some(info) => anyhow::bail!("{} is a {}, not a file", spec, info.kind),
+        None => anyhow::bail!("{} does not exist", spec),
+    }
+    read_object(repo_path, spec)?.with_context(|| format!("{} does not exist", spec))
+}
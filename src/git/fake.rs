@@ -0,0 +1,269 @@
+use anyhow::{Context, Result};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use crate::git::backend::GitBackend;
+
+/// In-memory repository for unit tests of logic built on `GitBackend`.
+/// Holds a commit graph, local refs, and the branches each remote would
+/// deliver on fetch; remote-tracking refs only move when fetched.
+#[derive(Debug, Default)]
+pub struct FakeBackend {
+    branch: String,
+    parents: HashMap<String, Vec<String>>,
+    refs: RefCell<HashMap<String, String>>,
+    remotes: Vec<String>,
+    remote_branches: HashMap<String, HashMap<String, String>>,
+    unreachable: HashSet<String>,
+    config: HashMap<String, String>,
+    status: Vec<String>,
+    deletions: Vec<String>,
+    fetched: RefCell<Vec<String>>,
+}
+
+impl FakeBackend {
+    /// Creates an empty repository with `branch` checked out
+    pub fn new(branch: &str) -> Self {
+        FakeBackend {
+            branch: branch.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds a commit with the given parents
+    pub fn commit(
+        mut self,
+        id: &str,
+        parents: &[&str],
+    ) -> Self {
+        self.parents.insert(
+            id.to_string(),
+            parents.iter().map(|p| p.to_string()).collect(),
+        );
+        self
+    }
+
+    /// Points a local ref, such as the checked out branch, at a commit
+    pub fn reference(
+        self,
+        name: &str,
+        commit: &str,
+    ) -> Self {
+        self.refs
+            .borrow_mut()
+            .insert(name.to_string(), commit.to_string());
+        self
+    }
+
+    /// Adds a remote whose `branch` is at `commit` on the server
+    pub fn remote_branch(
+        mut self,
+        remote: &str,
+        branch: &str,
+        commit: &str,
+    ) -> Self {
+        if !self.remotes.iter().any(|r| r == remote) {
+            self.remotes.push(remote.to_string());
+        }
+        self.remote_branches
+            .entry(remote.to_string())
+            .or_default()
+            .insert(branch.to_string(), commit.to_string());
+        self
+    }
+
+    /// Makes fetches from a remote fail
+    pub fn unreachable(
+        mut self,
+        remote: &str,
+    ) -> Self {
+        self.unreachable.insert(remote.to_string());
+        self
+    }
+
+    /// Sets a configuration value
+    pub fn config(
+        mut self,
+        key: &str,
+        value: &str,
+    ) -> Self {
+        self.config.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Adds a line reported by `status_short`
+    pub fn status_line(
+        mut self,
+        line: &str,
+    ) -> Self {
+        self.status.push(line.to_string());
+        self
+    }
+
+    /// Adds an unstaged deletion
+    pub fn deletion(
+        mut self,
+        path: &str,
+    ) -> Self {
+        self.deletions.push(path.to_string());
+        self
+    }
+
+    /// Remotes fetched so far, in order
+    pub fn fetched(&self) -> Vec<String> {
+        self.fetched.borrow().clone()
+    }
+
+    /// Commit of the checked out branch
+    pub fn head(&self) -> String {
+        self.rev_parse("HEAD").unwrap()
+    }
+
+    fn reachable(
+        &self,
+        commit: &str,
+    ) -> HashSet<String> {
+        let mut seen = HashSet::new();
+        let mut stack = vec![commit.to_string()];
+        while let Some(id) = stack.pop() {
+            if let Some(parents) = self.parents.get(&id) {
+                stack.extend(parents.iter().cloned());
+            }
+            seen.insert(id);
+        }
+        seen
+    }
+}
+
+impl GitBackend for FakeBackend {
+    fn current_branch(&self) -> Result<String> {
+        Ok(self.branch.clone())
+    }
+
+    fn config_value(
+        &self,
+        key: &str,
+    ) -> Option<String> {
+        self.config.get(key).cloned()
+    }
+
+    fn remotes(&self) -> Result<Vec<String>> {
+        Ok(self.remotes.clone())
+    }
+
+    fn fetch(
+        &self,
+        remote: &str,
+    ) -> Result<()> {
+        if self.unreachable.contains(remote) {
+            anyhow::bail!(
+                "Git command failed: could not read from remote '{}'",
+                remote
+            );
+        }
+        let branches = self
+            .remote_branches
+            .get(remote)
+            .with_context(|| format!("Git command failed: no such remote '{}'", remote))?;
+        let mut refs = self.refs.borrow_mut();
+        for (branch, commit) in branches {
+            refs.insert(format!("{}/{}", remote, branch), commit.clone());
+        }
+        self.fetched.borrow_mut().push(remote.to_string());
+        Ok(())
+    }
+
+    fn rev_parse(
+        &self,
+        rev: &str,
+    ) -> Result<String> {
+        let name = if rev == "HEAD" { &self.branch } else { rev };
+        if let Some(commit) = self.refs.borrow().get(name) {
+            return Ok(commit.clone());
+        }
+        if self.parents.contains_key(rev) {
+            return Ok(rev.to_string());
+        }
+        anyhow::bail!("Git command failed: unknown revision '{}'", rev)
+    }
+
+    fn is_ancestor(
+        &self,
+        ancestor: &str,
+        descendant: &str,
+    ) -> Result<bool> {
+        let ancestor = self.rev_parse(ancestor)?;
+        let descendant = self.rev_parse(descendant)?;
+        Ok(self.reachable(&descendant).contains(&ancestor))
+    }
+
+    fn ahead_behind(
+        &self,
+        local: &str,
+        other: &str,
+    ) -> Result<(usize, usize)> {
+        let local = self.reachable(&self.rev_parse(local)?);
+        let other = self.reachable(&self.rev_parse(other)?);
+        Ok((
+            local.difference(&other).count(),
+            other.difference(&local).count(),
+        ))
+    }
+
+    fn status_short(&self) -> Result<Vec<String>> {
+        Ok(self.status.clone())
+    }
+
+    fn unstaged_deletions(&self) -> Result<Vec<String>> {
+        Ok(self.deletions.clone())
+    }
+
+    fn merge_ff_only(
+        &self,
+        rev: &str,
+    ) -> Result<()> {
+        let target = self.rev_parse(rev)?;
+        if !self.is_ancestor("HEAD", &target)? {
+            anyhow::bail!("Git command failed: Not possible to fast-forward, aborting.");
+        }
+        self.refs.borrow_mut().insert(self.branch.clone(), target);
+        Ok(())
+    }
+
+    fn update_branch(
+        &self,
+        branch: &str,
+        rev: &str,
+        old: &str,
+    ) -> Result<()> {
+        let target = self.rev_parse(rev)?;
+        if self.rev_parse(branch)? != old {
+            anyhow::bail!(
+                "Git command failed: cannot lock ref 'refs/heads/{}'",
+                branch
+            );
+        }
+        self.refs.borrow_mut().insert(branch.to_string(), target);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fetch_moves_remote_tracking_refs() {
+        let backend = FakeBackend::new("main")
+            .commit("a", &[])
+            .commit("b", &["a"])
+            .reference("main", "a")
+            .reference("origin/main", "a")
+            .remote_branch("origin", "main", "b");
+
+        assert_eq!(backend.ahead_behind("HEAD", "origin/main").unwrap(), (0, 0));
+        backend.fetch("origin").unwrap();
+        assert_eq!(backend.ahead_behind("HEAD", "origin/main").unwrap(), (0, 1));
+        assert_eq!(backend.fetched(), vec!["origin"]);
+    }
+}
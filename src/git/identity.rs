@@ -0,0 +1,30 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use crate::core::config::IdentityProfile;
+use crate::git::commands;
+
+/// Seed the repository's local git config with the profile's identity and
+/// signing settings. A key already set in the repository is kept. Unless
+/// the profile was `requested` by name, keys git already resolves from the
+/// user's or the system's config are kept as well, so an existing identity
+/// wins over the default profile. Returns the keys set.
+pub fn configure(
+    repo_path: &Path,
+    profile: &IdentityProfile,
+    requested: bool,
+) -> Result<Vec<&'static str>> {
+    let mut set = Vec::new();
+    for (key, value) in profile.entries() {
+        let local =
+            commands::run_git_command_in_dir(repo_path, &["config", "--local", "--get", key])
+                .is_ok_and(|value| !value.is_empty());
+        if local || (!requested && commands::config_value(repo_path, key).is_some()) {
+            continue;
+        }
+        commands::run_git_command_in_dir(repo_path, &["config", "--local", key, &value])
+            .with_context(|| format!("Failed to set {}", key))?;
+        set.push(key);
+    }
+    Ok(set)
+}
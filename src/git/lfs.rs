@@ -0,0 +1,34 @@
+use anyhow::Result;
+use std::path::Path;
+use tracing::{debug, info};
+
+use crate::core::lfs;
+use crate::git::commands;
+
+/// Returns the LFS patterns from the .gitattributes file at HEAD,
+/// or an empty list if the repository does not use LFS
+pub fn tracked_patterns<P: AsRef<Path>>(repo_path: P) -> Vec<String> {
+    commands::show_file(repo_path, "HEAD", ".gitattributes")
+        .map(|content| lfs::tracked_patterns(&content))
+        .unwrap_or_default()
+}
+
+/// Limits LFS downloads to the sparse patterns by setting `lfs.fetchinclude`.
+/// The setting is removed from repositories that do not use LFS.
+pub fn configure_fetch_include(
+    repo_path: &Path,
+    paths: &[String],
+) -> Result<()> {
+    if tracked_patterns(repo_path).is_empty() {
+        debug!("Repository does not use Git LFS");
+        // `git config --unset` fails when the key is already absent
+        let _ =
+            commands::run_git_command_in_dir(repo_path, &["config", "--unset", "lfs.fetchinclude"]);
+        return Ok(());
+    }
+
+    let fetch_include = lfs::fetch_include(paths);
+    info!("Setting lfs.fetchinclude to {}", fetch_include);
+    commands::run_git_command_in_dir(repo_path, &["config", "lfs.fetchinclude", &fetch_include])?;
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use anyhow::Result;
+use std::path::Path;
+use tracing::info;
+
+use crate::git::commands;
+
+/// Tasks `git-partial maintenance` runs: refresh the remote refs in the
+/// background, extend the commit-graph, and pack loose objects
+pub const TASKS: &[&str] = &["prefetch", "commit-graph", "loose-objects"];
+
+/// Write a commit-graph of every reachable commit and have each fetch
+/// extend it, so ancestry checks such as ahead/behind counts in `status`
+/// stay fast as history grows
+pub fn write_commit_graph(repo_path: &Path) -> Result<()> {
+    info!("Writing commit-graph");
+    commands::run_git_command_in_dir(repo_path, &["config", "fetch.writeCommitGraph", "true"])?;
+    commands::run_git_command_in_dir(
+        repo_path,
+        &["commit-graph", "write", "--reachable", "--changed-paths"],
+    )?;
+    Ok(())
+}
+
+/// Run the maintenance tasks now
+pub fn run(repo_path: &Path) -> Result<()> {
+    let mut args = vec!["maintenance", "run"];
+    let tasks: Vec<String> = TASKS
+        .iter()
+        .map(|task| format!("--task={}", task))
+        .collect();
+    args.extend(tasks.iter().map(String::as_str));
+    commands::run_git_command_in_dir(repo_path, &args)?;
+    Ok(())
+}
+
+/// Add the repository to the ones `git maintenance` keeps up in the
+/// background. The incremental strategy runs the tasks hourly (prefetch,
+/// commit-graph) or daily (loose objects, incremental repack).
+pub fn register(repo_path: &Path) -> Result<()> {
+    commands::run_git_command_in_dir(
+        repo_path,
+        &["config", "maintenance.strategy", "incremental"],
+    )?;
+    commands::run_git_command_in_dir(repo_path, &["maintenance", "register"])?;
+    Ok(())
+}
+
+/// Remove the repository from background maintenance
+pub fn unregister(repo_path: &Path) -> Result<()> {
+    commands::run_git_command_in_dir(repo_path, &["maintenance", "unregister"])?;
+    Ok(())
+}
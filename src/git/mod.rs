@@ -1,2 +1,16 @@
+pub mod backend;
+pub mod cat_file;
+pub mod checkout;
 pub mod commands;
+#[cfg(test)]
+pub mod fake;
+pub mod identity;
+pub mod lfs;
+pub mod maintenance;
+pub mod packs;
+pub mod pins;
 pub mod sparse;
+pub mod ssh;
+pub mod tls;
+pub mod trace;
+pub mod upstream;
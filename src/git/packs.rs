@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Instant;
+use tracing::info;
+
+use super::commands;
+use super::trace;
+
+/// Files git may keep next to a pack, by extension
+const PACK_FILES: [&str; 6] = ["pack", "idx", "rev", "bitmap", "mtimes", "promisor"];
+
+/// A pack fetched from the promisor remote, marked by a `.promisor` file.
+/// Git may fetch any object such a pack refers to again on demand.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromisorPack {
+    /// Path of the pack without extension, e.g. `.git/objects/pack/pack-<hash>`
+    pub base: PathBuf,
+}
+
+impl PromisorPack {
+    fn file(
+        &self,
+        extension: &str,
+    ) -> PathBuf {
+        self.base.with_extension(extension)
+    }
+
+    /// Total size of the pack's files on disk
+    pub fn size(&self) -> u64 {
+        PACK_FILES
+            .iter()
+            .filter_map(|extension| fs::metadata(self.file(extension)).ok())
+            .map(|metadata| metadata.len())
+            .sum()
+    }
+}
+
+/// The directory holding the repository's packs
+fn pack_dir(repo_path: &Path) -> Result<PathBuf> {
+    let output =
+        commands::run_git_command_in_dir(repo_path, &["rev-parse", "--git-path", "objects/pack"])?;
+    Ok(repo_path.join(output))
+}
+
+/// List the promisor packs of a repository
+pub fn promisor_packs(repo_path: &Path) -> Result<Vec<PromisorPack>> {
+    let dir = pack_dir(repo_path)?;
+    let mut packs = Vec::new();
+    for entry in fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "promisor")
+        {
+            packs.push(PromisorPack {
+                base: path.with_extension(""),
+            });
+        }
+    }
+    packs.sort_by(|a, b| a.base.cmp(&b.base));
+    Ok(packs)
+}
+
+/// Parses `git show-index` output (`<offset> <id> (<crc>)`) into object ids
+pub fn parse_show_index(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| Some(line.split(' ').nth(1)?.to_string()))
+        .collect()
+}
+
+/// List the ids of the objects in a pack, from its index
+pub fn pack_objects(
+    repo_path: &Path,
+    pack: &PromisorPack,
+) -> Result<Vec<String>> {
+    let index = File::open(pack.file("idx"))
+        .with_context(|| format!("Failed to open {}", pack.file("idx").display()))?;
+    let mut command = commands::git_command();
+    command
+        .arg("show-index")
+        .current_dir(repo_path)
+        .stdin(Stdio::from(index));
+    let started = Instant::now();
+    let output = command.output().context("Failed to execute git command")?;
+    trace::record(&command, started, &output);
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git command failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(parse_show_index(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Replace `packs` by one promisor pack holding `objects`, which may leave
+/// some of theirs out. The old packs are deleted once the new one is written,
+/// so every object stays readable, locally or from the remote, at all times.
+pub fn replace(
+    repo_path: &Path,
+    packs: &[PromisorPack],
+    objects: &[String],
+) -> Result<PromisorPack> {
+    let dir = pack_dir(repo_path)?;
+    let mut command = commands::git_command();
+    command
+        .args(["pack-objects", "--quiet"])
+        .arg(dir.join("pack"))
+        .current_dir(repo_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let started = Instant::now();
+    let mut child = command.spawn().context("Failed to execute git command")?;
+    let mut stdin = child.stdin.take().context("Failed to open git stdin")?;
+    for id in objects {
+        writeln!(stdin, "{}", id)?;
+    }
+    drop(stdin);
+    let output = child.wait_with_output()?;
+    trace::record(&command, started, &output);
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git command failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let hash = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let written = PromisorPack {
+        base: dir.join(format!("pack-{}", hash)),
+    };
+    File::create(written.file("promisor")).context("Failed to mark the new pack as promisor")?;
+    info!(
+        "Wrote {} with {} object(s)",
+        written.base.display(),
+        objects.len()
+    );
+
+    for pack in packs.iter().filter(|pack| **pack != written) {
+        // The index goes first: git ignores a pack without one
+        fs::remove_file(pack.file("idx"))
+            .with_context(|| format!("Failed to remove {}", pack.base.display()))?;
+        for extension in PACK_FILES.iter().filter(|extension| **extension != "idx") {
+            fs::remove_file(pack.file(extension)).ok();
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_show_index() {
+        let output = "12 0c8f0e3fcc8d7d8d37b1e1f1b2a4c5f3b8e1c7d2 (1a2b3c4d)\n\
+                      345 e69de29bb2d1d6434b8b29ae775ad8c2e48c5391 (5e6f7a8b)\n";
+        assert_eq!(
+            parse_show_index(output),
+            vec![
+                "0c8f0e3fcc8d7d8d37b1e1f1b2a4c5f3b8e1c7d2",
+                "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391"
+            ]
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+use super::commands;
+
+/// Pathspec matching the files of a sparse path
+fn pathspec(pattern: &str) -> String {
+    format!(":(glob){}", pattern.trim_start_matches('/'))
+}
+
+/// Check the files matching `pattern` out as of `commit`. Only the working
+/// tree changes: the index keeps HEAD's versions, so the branch can move on.
+/// The tracked files are marked assume-unchanged, so `git status` does not
+/// report them and `git add .` or `git commit -a` do not commit the pinned
+/// versions back onto the branch.
+pub fn apply(
+    repo_path: &Path,
+    pattern: &str,
+    commit: &str,
+) -> Result<()> {
+    let source = format!("--source={}", commit);
+    commands::run_git_command_in_dir(
+        repo_path,
+        &["restore", &source, "--worktree", "--", &pathspec(pattern)],
+    )
+    .with_context(|| format!("Failed to check {} out at {}", pattern, commit))?;
+    mark_unchanged(repo_path, pattern, true)
+}
+
+/// Set or clear the assume-unchanged bit of the tracked files matching
+/// `pattern`
+fn mark_unchanged(
+    repo_path: &Path,
+    pattern: &str,
+    unchanged: bool,
+) -> Result<()> {
+    let files =
+        commands::run_git_command_in_dir(repo_path, &["ls-files", "-z", "--", &pathspec(pattern)])
+            .with_context(|| format!("Failed to list the files of {}", pattern))?;
+    let files: Vec<&str> = files.split('\0').filter(|file| !file.is_empty()).collect();
+    if files.is_empty() {
+        return Ok(());
+    }
+    let flag = if unchanged {
+        "--assume-unchanged"
+    } else {
+        "--no-assume-unchanged"
+    };
+    let mut args = vec!["update-index", flag, "--"];
+    args.extend(files);
+    commands::run_git_command_in_dir(repo_path, &args)
+        .with_context(|| format!("Failed to update the index entries of {}", pattern))?;
+    Ok(())
+}
+
+/// Put the files matching `pattern`, pinned at `commit`, back as of HEAD,
+/// deleting those only `commit` has
+pub fn release(
+    repo_path: &Path,
+    pattern: &str,
+    commit: &str,
+) -> Result<()> {
+    mark_unchanged(repo_path, pattern, false)?;
+    let pathspec = pathspec(pattern);
+    let only_pinned = commands::run_git_command_in_dir(
+        repo_path,
+        &[
+            "diff",
+            "-z",
+            "--name-only",
+            "--no-renames",
+            "--diff-filter=A",
+            "HEAD",
+            commit,
+            "--",
+            &pathspec,
+        ],
+    )
+    .with_context(|| format!("Failed to compare {} with HEAD", commit))?;
+    for file in commands::nul_separated(&only_pinned) {
+        let path = repo_path.join(&file);
+        if path.exists() {
+            fs::remove_file(&path).with_context(|| format!("Failed to remove {}", file))?;
+        }
+    }
+    commands::run_git_command_in_dir(
+        repo_path,
+        &["restore", "--source=HEAD", "--worktree", "--", &pathspec],
+    )
+    .with_context(|| format!("Failed to check {} out at HEAD", pattern))?;
+    Ok(())
+}
+
+/// Whether tracked files matching `pattern` were edited since they were
+/// checked out at `commit`. The assume-unchanged bits hide edits from
+/// `git diff`, so they are cleared for the comparison.
+pub fn has_local_changes(
+    repo_path: &Path,
+    pattern: &str,
+    commit: &str,
+) -> Result<bool> {
+    mark_unchanged(repo_path, pattern, false)?;
+    let output = commands::git_output(
+        Some(repo_path),
+        &["diff", "--quiet", commit, "--", &pathspec(pattern)],
+    )?;
+    mark_unchanged(repo_path, pattern, true)?;
+    match output.status.code() {
+        Some(0) => Ok(false),
+        Some(1) => Ok(true),
+        _ => anyhow::bail!(
+            "Git command failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ),
+    }
+}
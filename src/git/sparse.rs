@@ -1,7 +1,7 @@
 use anyhow::{Context, Result};
-use log::{debug, info};
 use std::env;
 use std::path::Path;
+use tracing::{debug, info};
 
 use crate::git::commands;
 
@@ -81,6 +81,9 @@ pub fn is_sparse_checkout() -> Result<bool> {
         return Ok(false);
     }
 
-    let output = commands::run_git_command(&["config", "core.sparseCheckout"])?;
-    Ok(output.trim() == "true")
+    // `git config` exits non-zero when the key is unset
+    match commands::run_git_command(&["config", "core.sparseCheckout"]) {
+        Ok(output) => Ok(output.trim() == "true"),
+        Err(_) => Ok(false),
+    }
 }
@@ -0,0 +1,96 @@
+use anyhow::Result;
+use std::path::Path;
+
+use crate::git::commands;
+use crate::utils::shell;
+
+/// Connection options for git servers reached over SSH, such as internal
+/// servers behind a bastion host
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SshOptions {
+    /// Port of the git server, when not 22
+    pub port: Option<u16>,
+
+    /// Private key to authenticate with
+    pub identity: Option<String>,
+
+    /// Jump host (`[user@]host[:port]`) to connect through
+    pub jump: Option<String>,
+}
+
+impl SshOptions {
+    /// Returns the `core.sshCommand` for the options, or `None` without any.
+    /// A relative identity file is made absolute, since git runs ssh from
+    /// the repository; `~` is left for ssh to expand.
+    pub fn command(&self) -> Option<String> {
+        if self.port.is_none() && self.identity.is_none() && self.jump.is_none() {
+            return None;
+        }
+        let mut command = vec!["ssh".to_string()];
+        if let Some(port) = self.port {
+            command.push(format!("-p {}", port));
+        }
+        if let Some(identity) = &self.identity {
+            let path = Path::new(identity);
+            let identity = if path.is_relative() && !identity.starts_with('~') {
+                std::path::absolute(path)
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .unwrap_or_else(|_| identity.clone())
+            } else {
+                identity.clone()
+            };
+            // Offer only this key, not every key loaded in the agent
+            command.push(format!(
+                "-i {} -o IdentitiesOnly=yes",
+                shell::quote(&identity)
+            ));
+        }
+        if let Some(jump) = &self.jump {
+            command.push(format!("-J {}", shell::quote(jump)));
+        }
+        Some(command.join(" "))
+    }
+}
+
+/// Store the options in the repository's `core.sshCommand`, so later
+/// fetches and pulls connect the same way
+pub fn configure(
+    repo_path: &Path,
+    options: &SshOptions,
+) -> Result<()> {
+    if let Some(command) = options.command() {
+        commands::run_git_command_in_dir(repo_path, &["config", "core.sshCommand", &command])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command() {
+        assert_eq!(SshOptions::default().command(), None);
+
+        let options = SshOptions {
+            port: Some(2222),
+            identity: Some("~/.ssh/id_internal".to_string()),
+            jump: Some("me@bastion.example.com".to_string()),
+        };
+        assert_eq!(
+            options.command().unwrap(),
+            "ssh -p 2222 -i '~/.ssh/id_internal' -o IdentitiesOnly=yes -J 'me@bastion.example.com'"
+        );
+    }
+
+    #[test]
+    fn test_relative_identity_is_made_absolute() {
+        let options = SshOptions {
+            identity: Some("keys/deploy".to_string()),
+            ..Default::default()
+        };
+        let command = options.command().unwrap();
+        let cwd = std::env::current_dir().unwrap();
+        assert!(command.contains(&cwd.join("keys/deploy").to_string_lossy().into_owned()));
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::Result;
+use std::path::Path;
+
+use crate::git::commands;
+
+/// Client certificate for HTTPS git servers that require mutual TLS, such
+/// as enterprise servers behind a corporate PKI
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TlsOptions {
+    /// Certificate file in PEM format (`http.sslCert`)
+    pub cert: Option<String>,
+
+    /// Private key file, when not in the certificate file (`http.sslKey`)
+    pub key: Option<String>,
+}
+
+impl TlsOptions {
+    /// Returns the git config for the options. Relative files are made
+    /// absolute, since git reads them from the repository; `~` is left for
+    /// git to expand.
+    pub fn config(&self) -> Vec<(String, String)> {
+        [("http.sslCert", &self.cert), ("http.sslKey", &self.key)]
+            .into_iter()
+            .filter_map(|(key, file)| Some((key.to_string(), absolute(file.as_deref()?))))
+            .collect()
+    }
+
+    /// Fails when a file is missing, which git would only report as a
+    /// failed TLS handshake
+    pub fn check(&self) -> Result<()> {
+        for (what, file) in [("certificate", &self.cert), ("key", &self.key)] {
+            if let Some(file) = file.as_deref().filter(|file| !file.starts_with('~')) {
+                if !Path::new(file).is_file() {
+                    anyhow::bail!("TLS client {} '{}' not found", what, file);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Store the options in the repository's `http.sslCert` and `http.sslKey`,
+/// so later fetches, including those of `status` and `watch`, present the
+/// same certificate
+pub fn configure(
+    repo_path: &Path,
+    options: &TlsOptions,
+) -> Result<()> {
+    for (key, value) in options.config() {
+        commands::run_git_command_in_dir(repo_path, &["config", &key, &value])?;
+    }
+    Ok(())
+}
+
+fn absolute(file: &str) -> String {
+    let path = Path::new(file);
+    if path.is_relative() && !file.starts_with('~') {
+        std::path::absolute(path)
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| file.to_string())
+    } else {
+        file.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config() {
+        assert!(TlsOptions::default().config().is_empty());
+
+        let options = TlsOptions {
+            cert: Some("~/certs/me.pem".to_string()),
+            key: Some("keys/me.key".to_string()),
+        };
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(
+            options.config(),
+            vec![
+                ("http.sslCert".to_string(), "~/certs/me.pem".to_string()),
+                (
+                    "http.sslKey".to_string(),
+                    cwd.join("keys/me.key").to_string_lossy().into_owned()
+                ),
+            ]
+        );
+        assert!(options
+            .check()
+            .unwrap_err()
+            .to_string()
+            .contains("TLS client key 'keys/me.key' not found"));
+    }
+}
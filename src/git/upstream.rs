@@ -0,0 +1,151 @@
+use anyhow::Result;
+use std::fmt;
+use tracing::debug;
+
+use crate::git::backend::GitBackend;
+
+/// The remote branch a local branch pulls from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Upstream {
+    /// Remote name, or `.` for a local branch
+    pub remote: String,
+
+    /// Branch name on the remote
+    pub branch: String,
+}
+
+impl Upstream {
+    /// Parses a `<remote>/<branch>` override; the branch may contain slashes
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.split_once('/') {
+            Some((remote, branch)) if !remote.is_empty() && !branch.is_empty() => Ok(Upstream {
+                remote: remote.to_string(),
+                branch: branch.to_string(),
+            }),
+            _ => anyhow::bail!("Invalid upstream '{}' (expected <remote>/<branch>)", value),
+        }
+    }
+
+    /// Builds the upstream from `branch.<name>.remote` and `branch.<name>.merge` values
+    pub fn from_config(
+        remote: &str,
+        merge: &str,
+    ) -> Self {
+        Upstream {
+            remote: remote.to_string(),
+            branch: merge
+                .strip_prefix("refs/heads/")
+                .unwrap_or(merge)
+                .to_string(),
+        }
+    }
+
+    /// Returns the revision naming the upstream, e.g. `origin/main`
+    pub fn rev(&self) -> String {
+        if self.is_local() {
+            self.branch.clone()
+        } else {
+            format!("{}/{}", self.remote, self.branch)
+        }
+    }
+
+    /// Checks if the upstream is a branch of this repository, which needs no fetch
+    pub fn is_local(&self) -> bool {
+        self.remote == "."
+    }
+}
+
+impl fmt::Display for Upstream {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(f, "{}", self.rev())
+    }
+}
+
+/// Determines the upstream of a branch: the override if given, then the
+/// branch's configured upstream, and finally `origin/<branch>`
+pub fn resolve<B: GitBackend>(
+    backend: &B,
+    branch: &str,
+    override_upstream: Option<&str>,
+) -> Result<Upstream> {
+    if let Some(value) = override_upstream {
+        return Upstream::parse(value);
+    }
+
+    let remote = backend.config_value(&format!("branch.{}.remote", branch));
+    let merge = backend.config_value(&format!("branch.{}.merge", branch));
+    let upstream = match (remote, merge) {
+        (Some(remote), Some(merge)) => Upstream::from_config(&remote, &merge),
+        _ => Upstream {
+            remote: "origin".to_string(),
+            branch: branch.to_string(),
+        },
+    };
+    debug!("Upstream of {}: {}", branch, upstream);
+    Ok(upstream)
+}
+
+/// Fetches the upstream's remote, unless the upstream is local
+pub fn fetch<B: GitBackend>(
+    backend: &B,
+    upstream: &Upstream,
+) -> Result<()> {
+    if !upstream.is_local() {
+        backend.fetch(&upstream.remote)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::fake::FakeBackend;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Upstream::parse("upstream/release/1.0").unwrap(),
+            Upstream {
+                remote: "upstream".to_string(),
+                branch: "release/1.0".to_string()
+            }
+        );
+        assert!(Upstream::parse("main").is_err());
+        assert!(Upstream::parse("origin/").is_err());
+    }
+
+    #[test]
+    fn test_from_config() {
+        let upstream = Upstream::from_config("fork", "refs/heads/feature/x");
+        assert_eq!(upstream.rev(), "fork/feature/x");
+
+        let local = Upstream::from_config(".", "refs/heads/main");
+        assert!(local.is_local());
+        assert_eq!(local.rev(), "main");
+    }
+
+    #[test]
+    fn test_resolve() {
+        let backend = FakeBackend::new("topic")
+            .config("branch.topic.remote", "fork")
+            .config("branch.topic.merge", "refs/heads/feature/topic");
+
+        assert_eq!(
+            resolve(&backend, "topic", None).unwrap().rev(),
+            "fork/feature/topic"
+        );
+        assert_eq!(
+            resolve(&backend, "main", None).unwrap().rev(),
+            "origin/main"
+        );
+        assert_eq!(
+            resolve(&backend, "topic", Some("upstream/main"))
+                .unwrap()
+                .rev(),
+            "upstream/main"
+        );
+    }
+}
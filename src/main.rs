@@ -1,6 +1,9 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use log::info;
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use tracing::{info, info_span, Instrument};
+
+use crate::core::config::OutputFormat;
+use crate::utils::logging::LogFormat;
 
 mod cli;
 mod core;
@@ -12,6 +15,24 @@ mod utils;
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Cli {
+    /// Parallel workers for checking out files
+    /// [env: GIT_PARTIAL_JOBS, git config: gitpartial.jobs]
+    #[clap(long, global = true, value_name = "N")]
+    jobs: Option<usize>,
+
+    /// Format of the log lines written to stderr (verbosity is set with RUST_LOG)
+    #[clap(long, value_enum, global = true, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// Fail instead of waiting when another git-partial is changing the repository
+    #[clap(long, global = true)]
+    no_wait: bool,
+
+    /// Record every git command run, with its directory, duration, exit
+    /// code and output, to FILE as JSON lines, for `replay`
+    #[clap(long, global = true, value_name = "FILE")]
+    trace_git: Option<String>,
+
     #[clap(subcommand)]
     command: Commands,
 }
@@ -20,62 +41,1966 @@ struct Cli {
 enum Commands {
     /// Clone only part of a repository
     Clone {
-        /// Repository URL to clone
-        repo_url: String,
+        /// Repository URL to clone; with --manifest, the workspace directory
+        #[clap(required_unless_present = "manifest")]
+        repo_url: Option<String>,
 
         /// Destination directory for the clone
-        destination: String,
+        #[clap(required_unless_present = "manifest", conflicts_with = "manifest")]
+        destination: Option<String>,
 
         /// Paths to include in the partial clone
         #[clap(long, value_parser, num_args = 1.., value_delimiter = ' ')]
         paths: Vec<String>,
+
+        /// Clone every repository a TOML manifest lists, each with its own
+        /// paths, into the workspace directory; `sync` there updates them all
+        #[clap(
+            long,
+            value_name = "FILE",
+            conflicts_with_all = ["paths", "ci", "bare_partial", "upstream", "via", "rewrite", "follow_symlinks", "maintenance", "fsmonitor"]
+        )]
+        manifest: Option<String>,
+
+        /// Optimize for ephemeral CI runners (shallow, single branch, no tags)
+        /// and print a JSON report of timings and sizes
+        #[clap(long)]
+        ci: bool,
+
+        /// Runner-local repository to borrow objects from in CI mode
+        /// [global config: cache_dir]
+        #[clap(long, value_name = "DIR", requires = "ci")]
+        cache_dir: Option<String>,
+
+        /// Make a bare repository without a working tree, for services that
+        /// only read the paths with `tree` and `show`
+        #[clap(
+            long,
+            conflicts_with_all = ["ci", "upstream", "via", "rewrite", "follow_symlinks", "fsmonitor"]
+        )]
+        bare_partial: bool,
+
+        /// Make this subdirectory the top level of DESTINATION, for tooling
+        /// that expects the project at the repository root
+        #[clap(long, value_name = "SUBDIR", conflicts_with_all = ["manifest", "ci", "bare_partial"])]
+        root: Option<String>,
+
+        /// Directory in the --root subdirectory that the other paths are
+        /// linked under [default: .monorepo]
+        #[clap(long, value_name = "NAME", requires = "root")]
+        mount_prefix: Option<String>,
+
+        /// Canonical repository of a fork, registered as the `upstream` remote
+        #[clap(long, value_name = "URL")]
+        upstream: Option<String>,
+
+        /// Fetch through a mirror created by `mirror create`, e.g. on the LAN
+        #[clap(long, value_name = "MIRROR_URL")]
+        via: Option<String>,
+
+        /// Fetch from this copy of the repository (a mirror or CDN) while
+        /// recording REPO_URL, via a `url.<URL>.insteadOf` rule in the clone
+        #[clap(long, value_name = "URL", conflicts_with = "via")]
+        rewrite: Option<String>,
+
+        /// Proceed despite policy violations (requires gitpartial.allowPolicyOverride)
+        #[clap(long)]
+        override_policy: bool,
+
+        /// Proceed past max_checkout_size and max_files, with a warning
+        #[clap(long)]
+        force: bool,
+
+        /// Also check out the targets of symlinks pointing outside the paths
+        #[clap(long)]
+        follow_symlinks: bool,
+
+        /// Partial clone filter, e.g. `blob:limit=1m` to also download the
+        /// blobs below a size for every path and commit [global config: filter]
+        #[clap(long, value_parser = core::config::parse_filter)]
+        filter: Option<String>,
+
+        /// Register the clone with `git maintenance` (prefetch, commit-graph,
+        /// loose objects)
+        #[clap(long)]
+        maintenance: bool,
+
+        /// Fetch only the current branch from then on, following `switch`;
+        /// see `narrow-fetch` [env: GIT_PARTIAL_NARROW_FETCH, git config:
+        /// gitpartial.narrowFetch]
+        #[clap(long, conflicts_with = "bare_partial")]
+        narrow_fetch: bool,
+
+        /// Keep `git status` fast with the untracked cache and, on macOS and
+        /// Windows, git's built-in file system monitor
+        #[clap(long)]
+        fsmonitor: bool,
+
+        /// Line ending conversion for the checkout (`core.autocrlf`)
+        #[clap(long, value_enum, value_name = "MODE")]
+        autocrlf: Option<git::checkout::AutoCrlf>,
+
+        /// Seed the clone's git identity and signing settings from this
+        /// profile of the global config [global config: identity]
+        #[clap(long, value_name = "NAME")]
+        identity: Option<String>,
+
+        // Boxed to keep the variant, the largest, small
+        #[clap(flatten)]
+        ssh: Box<SshArgs>,
+
+        #[clap(flatten)]
+        tls: Box<TlsArgs>,
     },
 
     /// Add new paths to the partial checkout
     AddPaths {
         /// New paths to include in the checkout
+        #[clap(value_parser, num_args = 1.., value_delimiter = ' ', required_unless_present_any = ["project", "target", "regex"])]
+        paths: Vec<String>,
+
+        /// Workspace projects to include (see `projects`)
+        #[clap(long)]
+        project: Vec<String>,
+
+        /// Regular expression over whole file paths, e.g. '.*/(unit|integration)_tests/.*';
+        /// the matching files are added as concrete paths
+        #[clap(long, value_name = "REGEX")]
+        regex: Vec<String>,
+
+        /// Bazel/Buck2 targets whose source packages to include, e.g. //services/api:server
+        #[clap(long)]
+        target: Vec<String>,
+
+        /// Skip the download size estimate and confirmation
+        #[clap(short, long)]
+        yes: bool,
+
+        /// Proceed despite policy violations (requires gitpartial.allowPolicyOverride)
+        #[clap(long)]
+        override_policy: bool,
+
+        /// Proceed past max_checkout_size and max_files, with a warning
+        #[clap(long)]
+        force: bool,
+
+        /// Also add the targets of symlinks pointing outside the sparse paths
+        #[clap(long)]
+        follow_symlinks: bool,
+
+        /// Skip the companion paths the repository's rules add
+        #[clap(long)]
+        no_companions: bool,
+    },
+
+    /// Add a crate of the Cargo workspace with its path dependencies, so
+    /// `cargo build -p <NAME>` works
+    AddCrate {
+        /// Crate name, or the name of its directory
+        name: String,
+
+        /// Skip the download size estimate and confirmation
+        #[clap(short, long)]
+        yes: bool,
+
+        /// Proceed despite policy violations (requires gitpartial.allowPolicyOverride)
+        #[clap(long)]
+        override_policy: bool,
+
+        /// Proceed past max_checkout_size and max_files, with a warning
+        #[clap(long)]
+        force: bool,
+    },
+
+    /// Add a package of the JavaScript workspace with the workspace packages
+    /// it depends on, so installing and building it works
+    AddPackage {
+        /// Package name, or the name of its directory
+        name: String,
+
+        /// Skip the download size estimate and confirmation
+        #[clap(short, long)]
+        yes: bool,
+
+        /// Proceed despite policy violations (requires gitpartial.allowPolicyOverride)
+        #[clap(long)]
+        override_policy: bool,
+
+        /// Proceed past max_checkout_size and max_files, with a warning
+        #[clap(long)]
+        force: bool,
+    },
+
+    /// Add a module of a Gradle or Maven build with the modules it depends
+    /// on, so building it works
+    AddModule {
+        /// Gradle project path such as `:payments:core`, Maven artifactId,
+        /// or the name of its directory
+        name: String,
+
+        /// Skip the download size estimate and confirmation
+        #[clap(short, long)]
+        yes: bool,
+
+        /// Proceed despite policy violations (requires gitpartial.allowPolicyOverride)
+        #[clap(long)]
+        override_policy: bool,
+
+        /// Proceed past max_checkout_size and max_files, with a warning
+        #[clap(long)]
+        force: bool,
+    },
+
+    /// Add a project of an Nx or Turborepo workspace, with the projects it
+    /// depends on when `--with-deps` is given
+    AddProject {
+        /// Project name, or the name of its directory
+        name: String,
+
+        /// Include the projects it depends on, following the project graph
+        #[clap(long)]
+        with_deps: bool,
+
+        /// Skip the download size estimate and confirmation
+        #[clap(short, long)]
+        yes: bool,
+
+        /// Proceed despite policy violations (requires gitpartial.allowPolicyOverride)
+        #[clap(long)]
+        override_policy: bool,
+
+        /// Proceed past max_checkout_size and max_files, with a warning
+        #[clap(long)]
+        force: bool,
+    },
+
+    /// Remove paths from the partial checkout
+    RemovePaths {
+        /// Paths to remove from the checkout
+        #[clap(value_parser, num_args = 1.., value_delimiter = ' ')]
+        paths: Vec<String>,
+
+        /// Skip the confirmation prompt
+        #[clap(short, long)]
+        yes: bool,
+
+        /// Proceed despite policy violations (requires gitpartial.allowPolicyOverride)
+        #[clap(long)]
+        override_policy: bool,
+
+        /// Remove pinned paths too
+        #[clap(long)]
+        force: bool,
+    },
+
+    /// List the sparse paths, marking pinned ones
+    ListPaths,
+
+    /// Pin paths so they are not removed without --force
+    Pin {
+        /// Paths to pin (must be in the checkout)
         #[clap(value_parser, num_args = 1.., value_delimiter = ' ')]
         paths: Vec<String>,
+
+        /// Also check the paths out at this commit, where smart-pull leaves them
+        #[clap(long, value_name = "COMMIT")]
+        at: Option<String>,
+    },
+
+    /// Unpin previously pinned paths
+    Unpin {
+        /// Paths to unpin
+        #[clap(value_parser, num_args = 1.., value_delimiter = ' ')]
+        paths: Vec<String>,
+    },
+
+    /// Start managing an existing partial clone (e.g. from Scalar) without recloning
+    Adopt,
+
+    /// Start managing a sparse checkout configured with plain git
+    Import,
+
+    /// Manage sparse path profiles applied per branch by `switch`
+    Profile {
+        #[clap(subcommand)]
+        command: ProfileCommands,
+    },
+
+    /// Switch branches and apply the matching branch profile
+    Switch {
+        /// Branch to switch to
+        branch: String,
+
+        /// Proceed despite policy violations (requires gitpartial.allowPolicyOverride)
+        #[clap(long)]
+        override_policy: bool,
+
+        /// Drop pinned paths not in the profile
+        #[clap(long)]
+        force: bool,
+    },
+
+    /// Explain why a path is missing from the checkout and offer to add it
+    WhyMissing {
+        /// Path relative to the repository root
+        path: String,
+
+        /// Add the suggested pattern without asking
+        #[clap(short, long)]
+        yes: bool,
+    },
+
+    /// Open the repository or a path in it in the editor, adding the path
+    /// to the checkout first if needed
+    Open {
+        /// File or directory to open [default: the repository]
+        path: Option<String>,
+
+        /// Editor command, e.g. 'code --wait' [default: $VISUAL, $EDITOR or code]
+        #[clap(long, value_name = "COMMAND")]
+        editor: Option<String>,
+
+        /// Add a path that is not checked out without asking
+        #[clap(short, long)]
+        yes: bool,
     },
 
     /// Show status of the partial checkout
-    Status,
+    Status {
+        /// Compare against this branch instead of the configured upstream
+        #[clap(long, value_name = "REMOTE/BRANCH")]
+        upstream: Option<String>,
+
+        /// Compare against the last fetched state instead of fetching
+        /// [env: GIT_PARTIAL_NO_FETCH_ON_STATUS, git config: gitpartial.noFetchOnStatus]
+        #[clap(long)]
+        no_fetch: bool,
+
+        /// Output format [env: GIT_PARTIAL_FORMAT, git config: gitpartial.format]
+        #[clap(long, value_enum)]
+        format: Option<OutputFormat>,
+
+        /// Exit with 0 when up-to-date, 3 when behind, 4 when diverged,
+        /// and 5 outside a git-partial repository
+        #[clap(long)]
+        check: bool,
+
+        /// Record HEAD as the last synced commit when it was moved outside git-partial
+        #[clap(long)]
+        fix: bool,
+
+        /// Count the present and missing objects in HEAD's history, which
+        /// walks the whole history
+        #[clap(long)]
+        objects: bool,
+    },
+
+    /// Validate the partial clone quickly, for pre-commit hooks; exits with
+    /// 0 when healthy, 5 outside a git-partial repository, 6 on inconsistent
+    /// metadata, 7 on staged paths outside the sparse paths, 8 when too far
+    /// behind the upstream, and 9 on staged paths pinned at a commit
+    Check {
+        /// Fetch the upstream before counting the commits behind it
+        #[clap(long)]
+        fetch: bool,
+
+        /// Commits HEAD may be behind its upstream
+        #[clap(long, value_name = "N", default_value_t = 100)]
+        max_behind: usize,
+    },
 
     /// Pull only changes relevant to the checked-out paths
-    SmartPull,
+    SmartPull {
+        /// Pull from this branch instead of the configured upstream
+        #[clap(long, value_name = "REMOTE/BRANCH", conflicts_with = "from")]
+        upstream: Option<String>,
+
+        /// Pull the current branch from this remote (e.g. `upstream` for forks)
+        #[clap(long, value_name = "REMOTE")]
+        from: Option<String>,
+
+        /// Fetch this branch's upstream instead, without switching to it
+        #[clap(long, value_name = "NAME")]
+        branch: Option<String>,
+
+        /// With --branch, also fast-forward the local branch
+        #[clap(long, requires = "branch")]
+        ff: bool,
+
+        /// Ask before merging, after showing the incoming changes
+        #[clap(long)]
+        confirm: bool,
+
+        /// Update sparse paths whose files upstream moved elsewhere to follow them
+        #[clap(long)]
+        follow_renames: bool,
+
+        /// Rebase local commits onto the upstream when the histories diverged
+        #[clap(long, conflicts_with_all = ["merge", "branch"])]
+        rebase: bool,
+
+        /// Merge the upstream when the histories diverged
+        #[clap(long, conflicts_with = "branch")]
+        merge: bool,
+
+        /// Finish a pull that stopped at conflicts, once they are resolved and staged
+        #[clap(
+            long = "continue",
+            conflicts_with_all = ["upstream", "from", "branch", "confirm", "follow_renames", "rebase", "merge", "abort"]
+        )]
+        continue_pull: bool,
+
+        /// Undo a pull that stopped at conflicts
+        #[clap(
+            long,
+            conflicts_with_all = ["upstream", "from", "branch", "confirm", "follow_renames", "rebase", "merge"]
+        )]
+        abort: bool,
+
+        #[clap(flatten)]
+        ssh: SshArgs,
+
+        #[clap(flatten)]
+        tls: TlsArgs,
+    },
+
+    /// Open an interactive status dashboard
+    Ui,
+
+    /// Fetch periodically and notify when new upstream commits touch the sparse paths
+    #[clap(args_conflicts_with_subcommands = true)]
+    Watch {
+        #[clap(subcommand)]
+        command: Option<WatchCommands>,
+
+        /// Seconds between checks when no schedule is configured
+        #[clap(long, value_name = "SECONDS", default_value_t = 300)]
+        interval: u64,
+
+        /// Check once and exit
+        #[clap(long)]
+        once: bool,
+
+        /// With --once, also download the files the next smart-pull checks out
+        #[clap(long, requires = "once")]
+        prefetch: bool,
+
+        /// With --once, also send the digest of upstream changes to Slack and by email
+        #[clap(long, requires = "once", conflicts_with = "prefetch")]
+        digest: bool,
+
+        /// Serve Prometheus metrics on `http://<ADDR>/metrics`
+        #[clap(long, value_name = "ADDR", conflicts_with = "once")]
+        metrics_listen: Option<String>,
+    },
+
+    /// List repository directories
+    Tree {
+        /// List the tree of a remote repository without cloning it
+        #[clap(long, value_name = "REPO_URL")]
+        pre_clone: Option<String>,
+
+        /// Branch, tag or commit to list (defaults to HEAD or the remote default branch)
+        #[clap(long = "ref", value_name = "REF")]
+        reference: Option<String>,
+
+        /// Maximum directory depth to show
+        #[clap(long, default_value_t = 2)]
+        depth: usize,
+    },
+
+    /// List the upstream paths starting with a prefix, for shell completion
+    CompletePaths {
+        /// Path prefix from the repository root
+        #[clap(default_value = "")]
+        prefix: String,
+    },
+
+    /// Fuzzy-search file and directory names in the upstream tree
+    Find {
+        /// Characters to look for, in order, e.g. `paybtn`
+        query: String,
+
+        /// Branch, tag or commit to search (defaults to the upstream, or HEAD)
+        #[clap(long = "ref", value_name = "REF")]
+        reference: Option<String>,
+
+        /// Show at most N matches
+        #[clap(short = 'n', long, value_name = "N", default_value_t = 20)]
+        limit: usize,
+
+        /// Offer to add the directory containing the best match
+        #[clap(long)]
+        add: bool,
+
+        /// Add without asking
+        #[clap(short, long, requires = "add")]
+        yes: bool,
+    },
+
+    /// Print a file outside the checkout without changing the sparse paths
+    Show {
+        /// File to show, as <ref>:<path> (or <path> for HEAD)
+        #[clap(value_name = "REF:PATH")]
+        spec: String,
+
+        /// Save the file instead of printing it
+        #[clap(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Stream the files at a revision matching the paths read from stdin,
+    /// without a working tree
+    CatTree {
+        /// Revision to read the files from
+        #[clap(default_value = "HEAD")]
+        rev: String,
+    },
+
+    /// Fetch and check out paths queued by `add-paths` while offline; at the
+    /// root of a `clone --manifest` workspace, sync each of its repositories
+    Sync,
+
+    /// List the branches of origin with work in the sparse paths since they
+    /// diverged from the default branch, most recently updated first
+    Branches {
+        /// Branch the others are compared against; defaults to origin/HEAD
+        #[clap(long, value_name = "BRANCH")]
+        base: Option<String>,
+
+        /// Compare the last fetched state instead of fetching first
+        #[clap(long)]
+        no_fetch: bool,
+
+        /// List the changed files of each branch
+        #[clap(long)]
+        files: bool,
+    },
+
+    /// Show when paths were added to or removed from the checkout, newest first
+    History {
+        /// Only changes to this pattern, or to patterns covering this path
+        #[clap(long)]
+        path: Option<String>,
+
+        /// Only changes made by this command, e.g. add-paths
+        #[clap(long)]
+        command: Option<String>,
+
+        /// Show at most N entries
+        #[clap(short = 'n', long, value_name = "N")]
+        limit: Option<usize>,
+    },
+
+    /// List the files in the sparse paths changed between two syncs, grouped by
+    /// directory and author
+    WhatChanged {
+        /// Start of the range: a sync point (#N), a date (YYYY-MM-DD) or a
+        /// revision; defaults to the sync before the latest
+        from: Option<String>,
+
+        /// End of the range, as for FROM; defaults to the latest sync
+        to: Option<String>,
+
+        /// List the recorded sync points instead
+        #[clap(long, conflicts_with_all = ["from", "to"])]
+        list: bool,
+    },
+
+    /// Restore skip-worktree on files reported as deleted outside the sparse paths
+    Repair {
+        /// Recompute the skip-worktree bit of every file in the index from the
+        /// sparse checkout patterns
+        #[clap(long)]
+        index: bool,
+    },
+
+    /// Finish, or roll back, an operation an error or a crash left half-applied
+    Recover {
+        /// Undo the operation instead of finishing it
+        #[clap(long)]
+        rollback: bool,
+    },
+
+    /// Fetch only the current branch and the named ones from origin, which
+    /// keeps `fetch` fast in repositories with many refs
+    NarrowFetch {
+        /// Branches to fetch besides the current one
+        branches: Vec<String>,
+
+        /// Stop fetching these branches
+        #[clap(long, value_name = "BRANCH", num_args = 1..)]
+        remove: Vec<String>,
+
+        /// Fetch every branch again
+        #[clap(long, conflicts_with_all = ["branches", "remove"])]
+        all: bool,
+    },
+
+    /// Write the commit-graph and pack objects, now or in the background
+    Maintenance {
+        /// Register the repository with `git maintenance` instead
+        #[clap(long)]
+        register: bool,
+
+        /// Stop background maintenance of the repository
+        #[clap(long, conflicts_with = "register")]
+        unregister: bool,
+    },
+
+    /// Compare the size of this partial clone against a full clone
+    Savings,
+
+    /// Time clone, add-paths, smart-pull and status against a repository and
+    /// print the results as JSON
+    Bench {
+        /// URL of the repository to clone
+        repo_url: String,
+
+        /// Paths the clone starts with
+        #[clap(long, value_parser, num_args = 1.., value_delimiter = ' ', required = true)]
+        paths: Vec<String>,
+
+        /// Paths to time add-paths with; the step is skipped without any
+        #[clap(long = "add", value_parser, num_args = 1.., value_delimiter = ' ')]
+        add_paths: Vec<String>,
+
+        /// Number of fresh clones to time
+        #[clap(long, default_value_t = 3)]
+        runs: usize,
+
+        /// Clone in cone mode
+        #[clap(long)]
+        cone: bool,
+
+        /// Use a sparse index (`index.sparse`); needs --cone
+        #[clap(long, requires = "cone")]
+        sparse_index: bool,
+    },
+
+    /// Run the git commands of a `--trace-git` file again in a scratch
+    /// directory and report those exiting differently
+    Replay {
+        /// Trace written by `--trace-git`
+        trace: String,
+
+        /// Directory to replay in, standing for the directory of the first
+        /// traced command [default: a new temporary directory]
+        #[clap(long, value_name = "DIR")]
+        repo: Option<String>,
+    },
+
+    /// Estimate the number of files and bytes that paths would check out
+    Estimate {
+        /// Paths to estimate
+        #[clap(long, value_parser, num_args = 1.., value_delimiter = ' ')]
+        paths: Vec<String>,
+
+        /// Regular expression over whole file paths to estimate
+        #[clap(long, value_name = "REGEX")]
+        regex: Vec<String>,
+
+        /// Estimate against a remote repository without cloning it
+        #[clap(long, value_name = "REPO_URL")]
+        pre_clone: Option<String>,
+
+        /// Branch, tag or commit to estimate (defaults to HEAD or the remote default branch)
+        #[clap(long = "ref", value_name = "REF")]
+        reference: Option<String>,
+    },
+
+    /// Show the files and size of remote directories, largest first
+    Du {
+        /// Directory to break down (defaults to the repository root)
+        path: Option<String>,
+
+        /// Directory levels below the path to group by
+        #[clap(long, default_value_t = 1)]
+        depth: usize,
+
+        /// Report on a remote repository without cloning it
+        #[clap(long, value_name = "REPO_URL")]
+        pre_clone: Option<String>,
+
+        /// Branch, tag or commit to report on (defaults to HEAD or the remote default branch)
+        #[clap(long = "ref", value_name = "REF")]
+        reference: Option<String>,
+    },
+
+    /// List the files and directories taking the most space locally
+    Top {
+        /// Entries to list of each
+        #[clap(short = 'n', long, default_value_t = 10)]
+        count: usize,
+
+        /// Directory levels to group directories by
+        #[clap(long, default_value_t = 2)]
+        depth: usize,
+    },
+
+    /// Show which Git LFS objects are fetched or skipped by the sparse paths
+    LfsStatus,
+
+    /// List file versions under the sparse paths the clone filter left on the remote
+    LargeFiles {
+        /// Download the skipped versions of these files (paths or patterns)
+        #[clap(long, value_name = "PATH", num_args = 1..)]
+        fetch: Vec<String>,
+    },
+
+    /// Run git blame on a file after fetching its missing past versions in one batch
+    Blame {
+        /// File to blame
+        file: String,
+
+        /// Options passed to git blame (after `--`)
+        #[clap(last = true)]
+        options: Vec<String>,
+    },
+
+    /// Download the file contents of paths for offline history (log -p, blame)
+    Materialize {
+        /// Paths or patterns to download (defaults to the sparse paths)
+        patterns: Vec<String>,
+
+        /// Only download the files as of HEAD instead of every past version
+        #[clap(long)]
+        head: bool,
+    },
+
+    /// Delete the local file contents of paths to free disk space
+    Dematerialize {
+        /// Paths or patterns whose contents to drop; git downloads them again when needed
+        #[clap(required = true)]
+        patterns: Vec<String>,
+    },
+
+    /// Export this partial clone for teammates using plain git
+    Export {
+        /// Print a shell script that reproduces the clone with stock git commands
+        #[clap(
+            long,
+            required_unless_present = "patterns",
+            conflicts_with = "patterns"
+        )]
+        script: bool,
+
+        /// Print the sparse checkout patterns (for `git sparse-checkout set --no-cone --stdin`)
+        #[clap(long)]
+        patterns: bool,
+    },
+
+    /// List the projects of a monorepo workspace
+    Projects,
+
+    /// Install a pre-commit hook guarding against commits outside the sparse patterns
+    InstallHooks {
+        /// Reject such commits instead of only warning
+        #[clap(long)]
+        block: bool,
+
+        /// Replace existing hooks not installed by git-partial
+        #[clap(long)]
+        force: bool,
+
+        /// Also install post-checkout and post-merge hooks that update the
+        /// metadata when plain git moves HEAD or changes the sparse patterns
+        #[clap(long)]
+        sync: bool,
+    },
+
+    /// Check staged paths against the sparse patterns (run by the pre-commit hook)
+    #[clap(hide = true)]
+    CheckStaged {
+        /// Fail instead of warning
+        #[clap(long)]
+        block: bool,
+    },
+
+    /// Update the metadata after plain git commands (run by the sync hooks)
+    #[clap(hide = true)]
+    SyncState,
+
+    /// Manage linked worktrees with their own sparse patterns
+    Worktree {
+        #[clap(subcommand)]
+        command: WorktreeCommands,
+    },
+
+    /// Show or set up the credentials used for GitHub, GitLab and Azure DevOps
+    Auth {
+        #[clap(subcommand)]
+        command: AuthCommands,
+    },
+
+    /// Pack branches into a bundle for machines without access to the remote
+    Bundle {
+        #[clap(subcommand)]
+        command: BundleCommands,
+    },
+
+    /// Manage a blobless mirror that clones can fetch through
+    Mirror {
+        #[clap(subcommand)]
+        command: MirrorCommands,
+    },
+
+    /// Serve repositories over git:// from a local cache of origin
+    ServeCache {
+        /// Address to listen on, e.g. `:9418` or `127.0.0.1:9418`
+        #[clap(long, default_value = ":9418")]
+        listen: String,
+
+        /// Base URL of the origin; `/org/repo.git` is fetched from `<ORIGIN>/org/repo.git`
+        #[clap(long, value_name = "URL")]
+        origin: String,
+
+        /// Directory for the cached repositories
+        /// [global config: cache_dir, default: git-partial-cache]
+        #[clap(long, value_name = "DIR")]
+        cache_dir: Option<String>,
+
+        /// Seconds a cached repository is served before refreshing it from origin
+        #[clap(long, value_name = "SECONDS", default_value_t = 60)]
+        refresh_after: u64,
+
+        /// Serve Prometheus metrics on `http://<ADDR>/metrics`
+        #[clap(long, value_name = "ADDR")]
+        metrics_listen: Option<String>,
+    },
+
+    /// Serve read-only JSON about the clones on this machine, for dashboards
+    ServeHttp {
+        /// Address to listen on
+        #[clap(long, default_value = "127.0.0.1:7777")]
+        listen: String,
+
+        /// Clones to serve besides those registered by clone, adopt and import
+        #[clap(long = "repo", value_name = "DIR")]
+        repos: Vec<String>,
+    },
+
+    /// List the files changed by a pull request (merge request on GitLab)
+    ChangedFiles {
+        /// Pull request or merge request number
+        number: u64,
+
+        /// Look up a remote repository without cloning it
+        #[clap(long, value_name = "REPO_URL")]
+        pre_clone: Option<String>,
+    },
+
+    /// Replace this executable with the latest release
+    SelfUpdate {
+        /// Only report whether a newer release exists (exits with 3 if so)
+        #[clap(long)]
+        check: bool,
+    },
+
+    /// Read or change machine-wide defaults in ~/.config/git-partial/config.toml
+    Config {
+        /// Use the global config file (repository settings live in `git config gitpartial.*`)
+        #[clap(long)]
+        global: bool,
+
+        /// List every value that is set
+        #[clap(long, conflicts_with_all = ["unset", "key"])]
+        list: bool,
+
+        /// Remove a key
+        #[clap(long, value_name = "KEY", conflicts_with = "key")]
+        unset: Option<String>,
+
+        /// Key to read, or to set when a value is given
+        key: Option<String>,
+
+        /// New value for the key
+        value: Option<String>,
+    },
+
+    /// Run `git-partial-<name>` from PATH
+    #[clap(external_subcommand)]
+    External(Vec<String>),
+}
+
+impl Commands {
+    /// Whether the command changes the sparse paths, the metadata or the
+    /// checkout of the current repository, and so must hold its lock
+    fn mutates_repository(&self) -> bool {
+        match self {
+            Commands::AddPaths { .. }
+            | Commands::AddCrate { .. }
+            | Commands::AddPackage { .. }
+            | Commands::AddModule { .. }
+            | Commands::AddProject { .. }
+            | Commands::RemovePaths { .. }
+            | Commands::Pin { .. }
+            | Commands::Unpin { .. }
+            | Commands::Switch { .. }
+            | Commands::WhyMissing { .. }
+            | Commands::SmartPull { .. }
+            | Commands::Sync
+            | Commands::Repair { .. }
+            | Commands::Recover { .. }
+            | Commands::SyncState
+            | Commands::Adopt
+            | Commands::Import
+            | Commands::Savings
+            | Commands::NarrowFetch { .. }
+            | Commands::Dematerialize { .. }
+            | Commands::Worktree { .. } => true,
+            Commands::Find { add, .. } => *add,
+            Commands::Status { fix, .. } => *fix,
+            Commands::Profile { command } => !matches!(command, ProfileCommands::List),
+            _ => false,
+        }
+    }
+
+    /// Substitute `${NAME}` variables and expand brace alternatives such as
+    /// `src/{app,lib}/**` in the path arguments, so commands only see
+    /// concrete patterns
+    fn expand_path_patterns(
+        &mut self,
+        current_dir: &std::path::Path,
+    ) -> Result<()> {
+        let paths = match self {
+            Commands::Clone { paths, .. }
+            | Commands::AddPaths { paths, .. }
+            | Commands::RemovePaths { paths, .. }
+            | Commands::Pin { paths, .. }
+            | Commands::Unpin { paths }
+            | Commands::Estimate { paths, .. } => paths,
+            Commands::Materialize { patterns, .. } | Commands::Dematerialize { patterns } => {
+                patterns
+            }
+            // Variables are kept, to be resolved when the profile is applied
+            Commands::Profile {
+                command: ProfileCommands::Set { paths, .. },
+            } => {
+                *paths = core::path_selector::expand_patterns(paths);
+                return Ok(());
+            }
+            Commands::Worktree {
+                command: WorktreeCommands::Add { paths, .. },
+            } => paths,
+            Commands::Bundle {
+                command: BundleCommands::Create { paths, .. },
+            } => paths,
+            _ => return Ok(()),
+        };
+        let substituted = core::config::substitute_variables(paths, current_dir)?;
+        *paths = core::path_selector::expand_patterns(&substituted);
+        Ok(())
+    }
+
+    /// Whether the command works on the git-partial repository around the
+    /// current directory, and can therefore be run from a subdirectory
+    fn runs_in_repository(&self) -> bool {
+        match self {
+            Commands::Clone { .. }
+            | Commands::Adopt
+            | Commands::Import
+            | Commands::Mirror { .. }
+            | Commands::ServeCache { .. }
+            | Commands::ServeHttp { .. }
+            | Commands::Config { .. }
+            | Commands::SelfUpdate { .. }
+            | Commands::Bench { .. }
+            | Commands::Replay { .. }
+            | Commands::Auth {
+                command: AuthCommands::Login { .. },
+            }
+            | Commands::External(_) => false,
+            Commands::Tree { pre_clone, .. }
+            | Commands::Estimate { pre_clone, .. }
+            | Commands::Du { pre_clone, .. }
+            | Commands::ChangedFiles { pre_clone, .. } => pre_clone.is_none(),
+            _ => true,
+        }
+    }
+
+    /// Repository URL the command fetches from: the cloned or mirrored URL,
+    /// or the remote of the git-partial repository it runs in
+    fn remote_url(
+        &self,
+        current_dir: &std::path::Path,
+    ) -> Option<String> {
+        match self {
+            Commands::Clone { repo_url, .. } => repo_url.clone(),
+            Commands::Mirror {
+                command: MirrorCommands::Create { repo_url, .. },
+            } => Some(repo_url.clone()),
+            _ if current_dir.join(".gitpartial").is_dir() => {
+                core::metadata::RepositoryMetadata::load(current_dir)
+                    .ok()
+                    .map(|metadata| metadata.remote_url)
+            }
+            _ => None,
+        }
+    }
+
+    /// Rewrite path arguments given from the subdirectory `prefix` of the
+    /// repository so they work from its root: patterns become root-relative
+    /// and local file names absolute
+    fn resolve_paths_from(
+        &mut self,
+        prefix: &str,
+        current_dir: &std::path::Path,
+    ) -> Result<()> {
+        let resolve = |patterns: &mut Vec<String>| -> Result<()> {
+            for pattern in patterns.iter_mut() {
+                *pattern = core::path_selector::resolve_relative(pattern, prefix)?;
+            }
+            Ok(())
+        };
+        let absolute = |file: &mut String| {
+            *file = current_dir.join(&*file).to_string_lossy().into_owned();
+        };
+        match self {
+            Commands::AddPaths { paths, .. }
+            | Commands::RemovePaths { paths, .. }
+            | Commands::Pin { paths, .. }
+            | Commands::Unpin { paths }
+            | Commands::Estimate { paths, .. }
+            | Commands::Profile {
+                command: ProfileCommands::Set { paths, .. },
+            } => resolve(paths)?,
+            Commands::WhyMissing { path, .. }
+            | Commands::Open {
+                path: Some(path), ..
+            } => {
+                *path = core::path_selector::resolve_relative(path, prefix)?;
+            }
+            Commands::History {
+                path: Some(path), ..
+            }
+            | Commands::Du {
+                path: Some(path), ..
+            }
+            | Commands::Blame { file: path, .. } => {
+                *path = core::path_selector::resolve_relative(path, prefix)?;
+            }
+            Commands::Show {
+                output: Some(output),
+                ..
+            } => absolute(output),
+            Commands::Worktree {
+                command: WorktreeCommands::Add { path, paths, .. },
+            } => {
+                absolute(path);
+                resolve(paths)?;
+            }
+            Commands::Bundle {
+                command: BundleCommands::Create { file, paths, .. },
+            } => {
+                absolute(file);
+                resolve(paths)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// SSH connection options, stored in the repository's `core.sshCommand`
+#[derive(Args, Debug)]
+struct SshArgs {
+    /// Port of the SSH git server
+    #[clap(long, value_name = "PORT")]
+    ssh_port: Option<u16>,
+
+    /// Private key file to authenticate with
+    #[clap(long, value_name = "FILE")]
+    ssh_identity: Option<String>,
+
+    /// Jump host to connect through, e.g. `me@bastion.example.com`
+    #[clap(long, value_name = "[USER@]HOST[:PORT]")]
+    ssh_jump: Option<String>,
+}
+
+impl SshArgs {
+    /// Applies the options to every git command of this run
+    fn apply(self) -> git::ssh::SshOptions {
+        let options = git::ssh::SshOptions {
+            port: self.ssh_port,
+            identity: self.ssh_identity,
+            jump: self.ssh_jump,
+        };
+        if let Some(command) = options.command() {
+            git::commands::set_ssh_command(command);
+        }
+        options
+    }
+}
+
+/// Client certificate for HTTPS servers requiring mutual TLS, stored in the
+/// repository's `http.sslCert` and `http.sslKey`
+#[derive(Args, Debug)]
+struct TlsArgs {
+    /// Client certificate file (PEM) [global config: tls_cert]
+    #[clap(long, value_name = "FILE")]
+    tls_cert: Option<String>,
+
+    /// Private key file of the client certificate [global config: tls_key]
+    #[clap(long, value_name = "FILE")]
+    tls_key: Option<String>,
+}
+
+impl TlsArgs {
+    /// Applies the options to every git command of this run
+    fn apply(
+        self,
+        repo_path: &std::path::Path,
+    ) -> Result<git::tls::TlsOptions> {
+        let (cert, key) = core::config::tls_files(self.tls_cert, self.tls_key, repo_path)?;
+        let options = git::tls::TlsOptions { cert, key };
+        options.check()?;
+        git::commands::set_tls_config(options.config());
+        Ok(options)
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum WatchCommands {
+    /// List the configured schedules and their next run times
+    Status,
+}
+
+#[derive(Subcommand, Debug)]
+enum ProfileCommands {
+    /// Set the paths for branches matching a name or glob (e.g. 'feature/payments/*')
+    Set {
+        /// Branch name or glob
+        branch: String,
+
+        /// Paths to check out on matching branches
+        #[clap(value_parser, num_args = 1.., value_delimiter = ' ')]
+        paths: Vec<String>,
+    },
+
+    /// Remove the profile for a branch name or glob
+    Remove {
+        /// Branch name or glob
+        branch: String,
+    },
+
+    /// List profiles in matching order
+    List,
+}
+
+#[derive(Subcommand, Debug)]
+enum AuthCommands {
+    /// Show where the token for a repository comes from
+    Status {
+        /// Repository URL [default: the remote of this clone]
+        url: Option<String>,
+    },
+
+    /// Log in to GitHub in the browser and store the token in git's credential helper
+    Login {
+        /// GitHub host to log in to
+        #[clap(long, default_value = "github.com")]
+        host: String,
+
+        /// Client ID of the OAuth app to log in with [env: GIT_PARTIAL_GITHUB_CLIENT_ID]
+        #[clap(long, value_name = "ID")]
+        client_id: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum BundleCommands {
+    /// Create a bundle with full history but only the blobs of the given paths
+    Create {
+        /// Bundle file to write
+        file: String,
+
+        /// Paths whose files to include [default: the sparse paths]
+        #[clap(long, value_parser, num_args = 1.., value_delimiter = ' ')]
+        paths: Vec<String>,
+
+        /// Branch to include; repeat for several [default: the current branch]
+        #[clap(long, value_name = "NAME")]
+        branch: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum MirrorCommands {
+    /// Create a bare blobless mirror of a repository
+    Create {
+        /// Repository URL to mirror
+        repo_url: String,
+
+        /// Directory for the mirror
+        dir: String,
+    },
+
+    /// Fetch new history into a mirror
+    Update {
+        /// Directory of the mirror
+        dir: String,
+
+        /// Keep running and update every SECONDS
+        #[clap(long, value_name = "SECONDS")]
+        interval: Option<u64>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum WorktreeCommands {
+    /// Create a worktree for a branch, sharing objects with this clone
+    Add {
+        /// Directory for the new worktree
+        path: String,
+
+        /// Branch to check out in the worktree
+        branch: String,
+
+        /// Paths to include in the worktree (defaults to the current paths)
+        #[clap(long, value_parser, num_args = 1.., value_delimiter = ' ')]
+        paths: Vec<String>,
+    },
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    env_logger::init();
-    let cli = Cli::parse();
+async fn main() {
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let global = match core::config::init_global() {
+        Ok(global) => global,
+        Err(e) => {
+            core::error::report(&e);
+            std::process::exit(1);
+        }
+    };
+    utils::logging::init(cli.log_format, global.color.unwrap_or_default());
+    utils::interrupt::install();
 
     info!("GitPartial starting...");
 
+    // Everything a command logs, including its git invocations, is tagged with its name
+    let span = info_span!(
+        "command",
+        subcommand = matches.subcommand_name().unwrap_or_default()
+    );
+    if let Err(e) = run(cli).instrument(span).await {
+        core::error::report(&e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(mut cli: Cli) -> Result<()> {
+    if let Some(path) = &cli.trace_git {
+        git::trace::start(std::path::Path::new(path))?;
+    }
+    let mut current_dir = std::env::current_dir()?;
+    cli.command.expand_path_patterns(&current_dir)?;
+    // Run from the repository root, like git, when started in a subdirectory
+    if cli.command.runs_in_repository() {
+        if let Some(root) = core::metadata::RepositoryMetadata::find_root(&current_dir) {
+            if root != current_dir {
+                let prefix = current_dir
+                    .strip_prefix(&root)?
+                    .components()
+                    .map(|part| part.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                cli.command.resolve_paths_from(&prefix, &current_dir)?;
+                std::env::set_current_dir(&root)?;
+                current_dir = root;
+            }
+        }
+    }
+    if let Some(jobs) = core::config::jobs(cli.jobs, &current_dir)? {
+        git::commands::set_checkout_workers(jobs);
+    }
+    if let Some(url) = cli.command.remote_url(&current_dir) {
+        remote::auth::apply_to_git(&url);
+    }
+    // Released when the command returns; outside a git-partial repository
+    // the command reports that itself
+    let _lock = if cli.command.mutates_repository() && current_dir.join(".gitpartial").is_dir() {
+        Some(core::lock::RepoLock::acquire(&current_dir, !cli.no_wait)?)
+    } else {
+        None
+    };
+    // Nothing builds on a half-applied operation until it is recovered
+    if cli.command.mutates_repository() && !matches!(cli.command, Commands::Recover { .. }) {
+        core::journal::Journal::ensure_none(&current_dir)?;
+    }
+
     match cli.command {
         Commands::Clone {
             repo_url,
             destination,
             paths,
+            manifest,
+            ci,
+            cache_dir,
+            bare_partial,
+            root,
+            mount_prefix,
+            upstream,
+            via,
+            rewrite,
+            override_policy,
+            force,
+            follow_symlinks,
+            filter,
+            maintenance,
+            narrow_fetch,
+            fsmonitor,
+            autocrlf,
+            identity,
+            ssh,
+            tls,
         } => {
-            println!(
-                "Cloning repository: {} to {} with paths: {:?}",
-                repo_url, destination, paths
-            );
-            cli::clone::clone_repository(&repo_url, &destination, &paths).await?;
+            let ssh = ssh.apply();
+            let tls = tls.apply(&current_dir)?;
+            // Checked before cloning, so a misspelled profile fails early
+            let requested = identity.is_some();
+            let identity = core::config::identity_profile(identity, &current_dir)?;
+            let seed_identity = |dir: &std::path::Path| -> Result<()> {
+                if let Some((name, profile)) = &identity {
+                    let set = git::identity::configure(dir, profile, requested)?;
+                    if !set.is_empty() {
+                        println!("Set {} from identity '{}'", set.join(", "), name);
+                    }
+                }
+                Ok(())
+            };
+            let narrow_fetch = core::config::narrow_fetch(narrow_fetch, &current_dir)?;
+            if let Some(filter) = filter {
+                core::config::set_clone_filter(filter);
+            }
+            let checkout = git::checkout::CheckoutSettings::for_platform(autocrlf);
+            checkout.apply();
+            let configured = git::commands::config_value(&current_dir, "core.autocrlf");
+            if let Some(advice) = checkout.autocrlf_advice(configured.as_deref()) {
+                eprintln!("Note: {}", advice);
+            }
+            let overrides = cli::policy::Overrides {
+                policy: override_policy,
+                limits: force,
+            };
+            if let Some(manifest) = manifest {
+                // The only positional argument is the workspace
+                let Some(workspace) = repo_url else {
+                    anyhow::bail!("clone --manifest needs the workspace directory");
+                };
+                for dir in cli::manifest::clone_manifest(&manifest, &workspace, overrides).await? {
+                    git::ssh::configure(&dir, &ssh)?;
+                    git::tls::configure(&dir, &tls)?;
+                    git::checkout::configure(&dir, &checkout)?;
+                    seed_identity(&dir)?;
+                    if narrow_fetch {
+                        print!("{}", cli::narrow_fetch::narrow(&dir, &[], &[], false)?);
+                    }
+                }
+                return Ok(());
+            }
+            let (Some(repo_url), Some(mut destination)) = (repo_url, destination) else {
+                anyhow::bail!("clone needs a repository URL and a destination");
+            };
+            let root = root
+                .map(|root| {
+                    core::root_mount::RootMount::new(
+                        &root,
+                        mount_prefix
+                            .as_deref()
+                            .unwrap_or(core::root_mount::DEFAULT_PREFIX),
+                    )
+                })
+                .transpose()?;
+            if bare_partial {
+                println!(
+                    "Cloning repository: {} to {} (bare) with paths: {:?}",
+                    repo_url, destination, paths
+                );
+                cli::clone::clone_bare(&repo_url, &destination, &paths, override_policy).await?;
+            } else if ci {
+                // Keep stdout machine-readable
+                let report = cli::clone::clone_for_ci(
+                    &repo_url,
+                    &destination,
+                    &paths,
+                    cache_dir.as_deref(),
+                    via.as_deref(),
+                    rewrite.as_deref(),
+                    overrides,
+                )
+                .await?;
+                print!("{}", report);
+            } else if let Some(root) = root {
+                println!(
+                    "Cloning repository: {} to {} (root: {}) with paths: {:?}",
+                    repo_url, destination, root.root, paths
+                );
+                let store = cli::root_mount::clone_with_root(
+                    &repo_url,
+                    &destination,
+                    &paths,
+                    &root,
+                    via.as_deref(),
+                    rewrite.as_deref(),
+                    overrides,
+                )
+                .await?;
+                // The rest configures the clone behind the link
+                destination = store.to_string_lossy().into_owned();
+            } else {
+                println!(
+                    "Cloning repository: {} to {} with paths: {:?}",
+                    repo_url, destination, paths
+                );
+                cli::clone::clone_repository(
+                    &repo_url,
+                    &destination,
+                    &paths,
+                    via.as_deref(),
+                    rewrite.as_deref(),
+                    overrides,
+                )
+                .await?;
+            }
+            git::ssh::configure(std::path::Path::new(&destination), &ssh)?;
+            git::tls::configure(std::path::Path::new(&destination), &tls)?;
+            git::checkout::configure(std::path::Path::new(&destination), &checkout)?;
+            seed_identity(std::path::Path::new(&destination))?;
+            if narrow_fetch && !bare_partial {
+                print!(
+                    "{}",
+                    cli::narrow_fetch::narrow(std::path::Path::new(&destination), &[], &[], false)?
+                );
+            }
+            if fsmonitor && !git::checkout::enable_fsmonitor(std::path::Path::new(&destination))? {
+                eprintln!(
+                    "Note: git has no built-in fsmonitor on this platform; only the untracked cache was enabled."
+                );
+            }
+            if let Some(upstream_url) = upstream {
+                cli::clone::add_upstream_remote(&destination, &upstream_url)?;
+            }
+            if maintenance {
+                git::maintenance::register(std::path::Path::new(&destination))?;
+                eprintln!(
+                    "Note: registered for background maintenance; run 'git maintenance start' once on this machine to schedule it."
+                );
+            }
+            if !bare_partial {
+                std::env::set_current_dir(&destination)?;
+                cli::add_paths::check_symlinks(follow_symlinks, true, override_policy, force)
+                    .await?;
+            }
         }
-        Commands::AddPaths { paths } => {
+        Commands::AddPaths {
+            mut paths,
+            project,
+            target,
+            regex,
+            yes,
+            override_policy,
+            force,
+            follow_symlinks,
+            no_companions,
+        } => {
+            paths.extend(cli::projects::project_paths(&project)?);
+            paths.extend(cli::targets::target_paths(&target)?);
+            paths.extend(cli::add_paths::regex_paths(&regex)?);
             println!("Adding paths: {:?}", paths);
-            cli::add_paths::add_new_paths(&paths).await?;
+            if !no_companions {
+                let companions = cli::add_paths::companion_paths(&paths)?;
+                if !companions.is_empty() {
+                    println!("Adding companion paths: {:?}", companions);
+                    paths.extend(companions);
+                }
+            }
+            print!(
+                "{}",
+                cli::add_paths::add_new_paths(&paths, yes, override_policy, force).await?
+            );
+            cli::add_paths::check_symlinks(follow_symlinks, yes, override_policy, force).await?;
+        }
+        Commands::AddCrate {
+            name,
+            yes,
+            override_policy,
+            force,
+        } => {
+            let paths = cli::add_crate::crate_paths(&name)?;
+            println!("Adding crate {}: {:?}", name, paths);
+            print!(
+                "{}",
+                cli::add_paths::add_new_paths(&paths, yes, override_policy, force).await?
+            );
+            cli::add_paths::check_symlinks(false, yes, override_policy, force).await?;
+        }
+        Commands::AddPackage {
+            name,
+            yes,
+            override_policy,
+            force,
+        } => {
+            let paths = cli::add_package::package_paths(&name)?;
+            println!("Adding package {}: {:?}", name, paths);
+            print!(
+                "{}",
+                cli::add_paths::add_new_paths(&paths, yes, override_policy, force).await?
+            );
+            cli::add_paths::check_symlinks(false, yes, override_policy, force).await?;
+        }
+        Commands::AddModule {
+            name,
+            yes,
+            override_policy,
+            force,
+        } => {
+            let paths = cli::add_module::module_paths(&name)?;
+            println!("Adding module {}: {:?}", name, paths);
+            print!(
+                "{}",
+                cli::add_paths::add_new_paths(&paths, yes, override_policy, force).await?
+            );
+            cli::add_paths::check_symlinks(false, yes, override_policy, force).await?;
+        }
+        Commands::AddProject {
+            name,
+            with_deps,
+            yes,
+            override_policy,
+            force,
+        } => {
+            let paths = cli::add_project::project_graph_paths(&name, with_deps)?;
+            println!("Adding project {}: {:?}", name, paths);
+            print!(
+                "{}",
+                cli::add_paths::add_new_paths(&paths, yes, override_policy, force).await?
+            );
+            cli::add_paths::check_symlinks(false, yes, override_policy, force).await?;
+        }
+        Commands::RemovePaths {
+            paths,
+            yes,
+            override_policy,
+            force,
+        } => {
+            println!("Removing paths: {:?}", paths);
+            print!(
+                "{}",
+                cli::remove_paths::remove_paths(&paths, yes, override_policy, force).await?
+            );
+        }
+        Commands::ListPaths => {
+            let paths = cli::list_paths::list_paths().await?;
+            print!("{}", paths);
+        }
+        Commands::Pin { paths, at: None } => {
+            println!("Pinning paths: {:?}", paths);
+            cli::list_paths::set_pinned(&paths, true).await?;
+        }
+        Commands::Pin {
+            paths,
+            at: Some(commit),
+        } => {
+            println!("Pinning paths at {}: {:?}", commit, paths);
+            cli::list_paths::pin_at(&paths, &commit).await?;
+        }
+        Commands::Unpin { paths } => {
+            println!("Unpinning paths: {:?}", paths);
+            cli::list_paths::set_pinned(&paths, false).await?;
+        }
+        Commands::Adopt => {
+            let result = cli::adopt::adopt_repository().await?;
+            print!("{}", result);
         }
-        Commands::Status => {
-            println!("Status:");
-            let status = cli::status::show_status().await?;
+        Commands::Import => {
+            let result = cli::import::import_repository().await?;
+            print!("{}", result);
+        }
+        Commands::Profile { command } => match command {
+            ProfileCommands::Set { branch, paths } => {
+                println!("Setting profile for {}: {:?}", branch, paths);
+                cli::profile::set_profile(&branch, &paths).await?;
+            }
+            ProfileCommands::Remove { branch } => {
+                println!("Removing profile for {}", branch);
+                cli::profile::remove_profile(&branch).await?;
+            }
+            ProfileCommands::List => {
+                let profiles = cli::profile::list_profiles().await?;
+                print!("{}", profiles);
+            }
+        },
+        Commands::Switch {
+            branch,
+            override_policy,
+            force,
+        } => {
+            println!("Switching to branch: {}", branch);
+            cli::profile::switch_branch(&branch, override_policy, force).await?;
+        }
+        Commands::WhyMissing { path, yes } => {
+            cli::why_missing::why_missing(&path, yes).await?;
+        }
+        Commands::Open { path, editor, yes } => {
+            cli::open::open(path.as_deref(), editor, yes).await?;
+        }
+        Commands::Status {
+            upstream,
+            no_fetch,
+            format,
+            check,
+            fix,
+            objects,
+        } => {
+            let (status, state) =
+                cli::status::show_status(upstream.as_deref(), no_fetch, format, fix, objects)
+                    .await?;
             println!("{}", status);
+            if check {
+                std::process::exit(cli::status::check_exit_code(state));
+            }
+        }
+        Commands::Check { fetch, max_behind } => {
+            let problems = cli::check::check(fetch, max_behind).await?;
+            for problem in &problems {
+                eprintln!("git-partial check: {}", problem);
+            }
+            std::process::exit(cli::check::exit_code(&problems));
         }
-        Commands::SmartPull => {
-            println!("Smart pulling changes...");
-            cli::smart_pull::perform_smart_pull().await?;
+        Commands::SmartPull {
+            upstream,
+            from,
+            branch,
+            ff,
+            confirm,
+            follow_renames,
+            rebase,
+            merge,
+            continue_pull,
+            abort,
+            ssh,
+            tls,
+        } => {
+            git::ssh::configure(&current_dir, &ssh.apply())?;
+            git::tls::configure(&current_dir, &tls.apply(&current_dir)?)?;
+            if continue_pull {
+                print!("{}", cli::smart_pull::continue_pull().await?);
+            } else if abort {
+                print!("{}", cli::smart_pull::abort_pull().await?);
+            } else {
+                let integration = if rebase {
+                    Some(core::pull_state::Integration::Rebase)
+                } else if merge {
+                    Some(core::pull_state::Integration::Merge)
+                } else {
+                    None
+                };
+                println!("Smart pulling changes...");
+                let output = cli::smart_pull::perform_smart_pull(
+                    upstream.as_deref(),
+                    from.as_deref(),
+                    branch.as_deref(),
+                    ff,
+                    confirm,
+                    follow_renames,
+                    integration,
+                )
+                .await?;
+                print!("{}", output);
+            }
+        }
+        Commands::Ui => {
+            cli::ui::run_dashboard().await?;
+        }
+        Commands::Watch {
+            command: Some(WatchCommands::Status),
+            ..
+        } => {
+            print!("{}", cli::watch::status()?);
+        }
+        Commands::Watch {
+            command: None,
+            interval,
+            once,
+            prefetch,
+            digest,
+            metrics_listen,
+        } => {
+            cli::watch::watch(interval, once, prefetch, digest, metrics_listen.as_deref()).await?;
+        }
+        Commands::Tree {
+            pre_clone,
+            reference,
+            depth,
+        } => {
+            let tree =
+                cli::tree::show_tree(pre_clone.as_deref(), reference.as_deref(), depth).await?;
+            print!("{}", tree);
+        }
+        Commands::CompletePaths { prefix } => {
+            print!("{}", cli::tree::complete_paths(&prefix)?);
+        }
+        Commands::Find {
+            query,
+            reference,
+            limit,
+            add,
+            yes,
+        } => {
+            cli::find::find(&query, reference.as_deref(), limit, add, yes).await?;
+        }
+        Commands::Show { spec, output } => {
+            cli::show::show_file(&spec, output.as_deref()).await?;
+        }
+        Commands::CatTree { rev } => {
+            cli::cat_tree::cat_tree(&rev).await?;
+        }
+        Commands::Du {
+            path,
+            depth,
+            pre_clone,
+            reference,
+        } => {
+            let usage = cli::du::disk_usage(
+                path.as_deref(),
+                depth,
+                pre_clone.as_deref(),
+                reference.as_deref(),
+            )
+            .await?;
+            print!("{}", usage);
+        }
+        Commands::Top { count, depth } => {
+            print!("{}", cli::top::top(count, depth).await?);
+        }
+        Commands::Estimate {
+            paths,
+            regex,
+            pre_clone,
+            reference,
+        } => {
+            let estimate = cli::estimate::estimate_paths(
+                &paths,
+                &regex,
+                pre_clone.as_deref(),
+                reference.as_deref(),
+            )
+            .await?;
+            print!("{}", estimate);
+        }
+        Commands::Sync => match core::manifest::Manifest::load_workspace(&current_dir)? {
+            Some(manifest) => cli::manifest::sync_workspace(&current_dir, &manifest).await?,
+            None => cli::sync::sync().await?,
+        },
+        Commands::Branches {
+            base,
+            no_fetch,
+            files,
+        } => {
+            print!(
+                "{}",
+                cli::branches::branches(base.as_deref(), !no_fetch, files).await?
+            );
+        }
+        Commands::History {
+            path,
+            command,
+            limit,
+        } => {
+            let history =
+                cli::history::show_history(path.as_deref(), command.as_deref(), limit).await?;
+            print!("{}", history);
+        }
+        Commands::WhatChanged { from, to, list } => {
+            let output = if list {
+                cli::what_changed::list_sync_points().await?
+            } else {
+                cli::what_changed::what_changed(from.as_deref(), to.as_deref()).await?
+            };
+            print!("{}", output);
+        }
+        Commands::Repair { index } => {
+            cli::repair::repair(index).await?;
+        }
+        Commands::Recover { rollback } => {
+            print!("{}", cli::recover::recover(rollback).await?);
+        }
+        Commands::NarrowFetch {
+            branches,
+            remove,
+            all,
+        } => {
+            print!(
+                "{}",
+                cli::narrow_fetch::narrow_fetch(&branches, &remove, all).await?
+            );
+        }
+        Commands::Maintenance {
+            register,
+            unregister,
+        } => {
+            print!(
+                "{}",
+                cli::maintenance::maintain(register, unregister).await?
+            );
+        }
+        Commands::Bench {
+            repo_url,
+            paths,
+            add_paths,
+            runs,
+            cone,
+            sparse_index,
+        } => {
+            let options = cli::bench::BenchOptions {
+                repo_url,
+                paths,
+                add_paths,
+                runs,
+                cone,
+                sparse_index,
+            };
+            print!("{}", cli::bench::bench(&options).await?);
+        }
+        Commands::Replay { trace, repo } => {
+            print!("{}", cli::replay::replay(&trace, repo.as_deref()).await?);
+        }
+        Commands::Savings => {
+            let report = cli::savings::show_savings().await?;
+            print!("{}", report);
+        }
+        Commands::LfsStatus => {
+            let status = cli::lfs_status::show_lfs_status().await?;
+            print!("{}", status);
+        }
+        Commands::LargeFiles { fetch } => {
+            print!("{}", cli::large_files::large_files(&fetch).await?);
+        }
+        Commands::Blame { file, options } => {
+            print!("{}", cli::blame::blame(&file, &options).await?);
+        }
+        Commands::Materialize { patterns, head } => {
+            print!("{}", cli::materialize::materialize(&patterns, head).await?);
+        }
+        Commands::Dematerialize { patterns } => {
+            print!("{}", cli::dematerialize::dematerialize(&patterns).await?);
+        }
+        Commands::Export { script, patterns } => {
+            let format = match (script, patterns) {
+                (true, false) => cli::export::ExportFormat::Script,
+                (false, true) => cli::export::ExportFormat::Patterns,
+                _ => unreachable!("clap requires exactly one of --script and --patterns"),
+            };
+            let exported = cli::export::export(format).await?;
+            print!("{}", exported);
+        }
+        Commands::Projects => {
+            let projects = cli::projects::list_projects().await?;
+            print!("{}", projects);
+        }
+        Commands::InstallHooks { block, force, sync } => {
+            cli::hooks::install_hooks(block, force, sync).await?;
+        }
+        Commands::SyncState => {
+            cli::hooks::sync_state().await?;
+        }
+        Commands::CheckStaged { block } => {
+            cli::hooks::check_staged(block).await?;
+        }
+        Commands::Worktree { command } => match command {
+            WorktreeCommands::Add {
+                path,
+                branch,
+                paths,
+            } => {
+                println!("Adding worktree: {} for branch {}", path, branch);
+                cli::worktree::add_worktree(&path, &branch, &paths).await?;
+            }
+        },
+        Commands::Auth { command } => match command {
+            AuthCommands::Status { url } => {
+                print!("{}", cli::auth::auth_status(url.as_deref())?);
+            }
+            AuthCommands::Login { host, client_id } => {
+                cli::auth::login(&host, client_id.as_deref()).await?;
+            }
+        },
+        Commands::Bundle { command } => match command {
+            BundleCommands::Create {
+                file,
+                paths,
+                branch,
+            } => {
+                let created = cli::bundle::create_bundle(&file, &paths, &branch).await?;
+                print!("{}", created);
+            }
+        },
+        Commands::Mirror { command } => match command {
+            MirrorCommands::Create { repo_url, dir } => {
+                println!("Creating mirror of {} in {}", repo_url, dir);
+                cli::mirror::create_mirror(&repo_url, &dir).await?;
+            }
+            MirrorCommands::Update { dir, interval } => {
+                cli::mirror::update_mirror(&dir, interval).await?;
+            }
+        },
+        Commands::ServeCache {
+            listen,
+            origin,
+            cache_dir,
+            refresh_after,
+            metrics_listen,
+        } => {
+            cli::serve_cache::serve_cache(
+                &listen,
+                &origin,
+                cache_dir.as_deref(),
+                refresh_after,
+                metrics_listen.as_deref(),
+            )
+            .await?;
+        }
+        Commands::ServeHttp { listen, repos } => {
+            cli::serve_http::serve_http(&listen, &repos).await?;
+        }
+        Commands::ChangedFiles { number, pre_clone } => {
+            let files =
+                cli::changed_files::show_changed_files(number, pre_clone.as_deref()).await?;
+            print!("{}", files);
+        }
+        Commands::SelfUpdate { check } => {
+            if check {
+                let result = cli::self_update::check_for_update().await?;
+                println!("{}", result);
+                std::process::exit(result.exit_code());
+            }
+            cli::self_update::self_update().await?;
+        }
+        Commands::External(args) => {
+            std::process::exit(cli::plugins::run_plugin(&args)?);
+        }
+        Commands::Config {
+            global,
+            list,
+            unset,
+            key,
+            value,
+        } => {
+            if !global {
+                anyhow::bail!(
+                    "Only --global is supported; set repository options with 'git config gitpartial.<key>'"
+                );
+            }
+            let output = match (list, unset, key, value) {
+                (true, _, _, _) => cli::config::list_global()?,
+                (_, Some(key), _, _) => cli::config::unset_global(&key)?,
+                (_, _, Some(key), Some(value)) => cli::config::set_global(&key, &value)?,
+                (_, _, Some(key), None) => cli::config::get_global(&key)?,
+                _ => anyhow::bail!("Give a key, a key and a value, --list, or --unset <key>"),
+            };
+            print!("{}", output);
         }
     }
 
@@ -0,0 +1,241 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::env;
+use tracing::debug;
+
+use super::{encode_url_component, http_client, send_get, RemoteUrl};
+use crate::core::tree::{EntryKind, TreeEntry};
+
+/// Client for the Bitbucket Cloud REST API
+#[derive(Debug)]
+pub struct BitbucketClient {
+    api_base: String,
+    workspace: String,
+    repo: String,
+    token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepoResponse {
+    mainbranch: MainBranch,
+}
+
+#[derive(Debug, Deserialize)]
+struct MainBranch {
+    name: String,
+}
+
+/// A page of a paginated Bitbucket response
+#[derive(Debug, Deserialize)]
+struct Page<T> {
+    values: Vec<T>,
+    next: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SrcItem {
+    path: String,
+    #[serde(rename = "type")]
+    kind: String,
+    size: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DiffStat {
+    old: Option<DiffStatFile>,
+    new: Option<DiffStatFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DiffStatFile {
+    path: String,
+}
+
+impl BitbucketClient {
+    /// Creates a client if the remote is hosted on bitbucket.org
+    pub fn from_remote(remote: &RemoteUrl) -> Option<Self> {
+        if remote.host != "bitbucket.org" {
+            return None;
+        }
+
+        let (workspace, repo) = remote.path.split_once('/')?;
+        if repo.contains('/') {
+            return None;
+        }
+
+        Some(BitbucketClient {
+            api_base: "https://api.bitbucket.org/2.0".to_string(),
+            workspace: workspace.to_string(),
+            repo: repo.to_string(),
+            token: env::var("BITBUCKET_TOKEN").ok(),
+        })
+    }
+
+    /// Returns the name of the repository's main branch
+    pub async fn default_branch(&self) -> Result<String> {
+        let url = format!(
+            "{}/repositories/{}/{}",
+            self.api_base, self.workspace, self.repo
+        );
+        let body = self.get(&url).await?;
+        let repo: RepoResponse =
+            serde_json::from_str(&body).context("Failed to parse Bitbucket repository response")?;
+        Ok(repo.mainbranch.name)
+    }
+
+    /// Lists every file and directory at the given reference
+    pub async fn list_tree(
+        &self,
+        reference: &str,
+    ) -> Result<Vec<TreeEntry>> {
+        let mut entries = Vec::new();
+        let mut url = Some(format!(
+            "{}/repositories/{}/{}/src/{}/?max_depth=100&pagelen=100",
+            self.api_base,
+            self.workspace,
+            self.repo,
+            encode_url_component(reference, "/")
+        ));
+        while let Some(page_url) = url {
+            let body = self.get(&page_url).await?;
+            let (page_entries, next) = parse_src_page(&body)?;
+            entries.extend(page_entries);
+            url = next;
+        }
+        Ok(entries)
+    }
+
+    /// Lists the paths changed by a pull request
+    pub async fn changed_files(
+        &self,
+        id: u64,
+    ) -> Result<Vec<String>> {
+        let mut files = Vec::new();
+        let mut url = Some(format!(
+            "{}/repositories/{}/{}/pullrequests/{}/diffstat",
+            self.api_base, self.workspace, self.repo, id
+        ));
+        while let Some(page_url) = url {
+            let body = self.get(&page_url).await?;
+            let (page_files, next) = parse_diffstat_page(&body)?;
+            files.extend(page_files);
+            url = next;
+        }
+        Ok(files)
+    }
+
+    async fn get(
+        &self,
+        url: &str,
+    ) -> Result<String> {
+        debug!("GET {}", url);
+        let mut request = http_client().get(url);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let (body, _) = send_get(request, "Bitbucket").await?;
+        Ok(body)
+    }
+}
+
+/// Converts a page of the `src` API into tree entries and the next page URL
+fn parse_src_page(body: &str) -> Result<(Vec<TreeEntry>, Option<String>)> {
+    let page: Page<SrcItem> =
+        serde_json::from_str(body).context("Failed to parse Bitbucket source listing")?;
+
+    let entries = page
+        .values
+        .into_iter()
+        .filter_map(|item| {
+            let kind = match item.kind.as_str() {
+                "commit_file" => EntryKind::Blob,
+                "commit_directory" => EntryKind::Tree,
+                _ => return None,
+            };
+            Some(TreeEntry {
+                path: item.path,
+                kind,
+                size: item.size,
+            })
+        })
+        .collect();
+    Ok((entries, page.next))
+}
+
+/// Extracts changed paths from a page of the `diffstat` API and the next page URL.
+/// Renames report both the old and the new path.
+fn parse_diffstat_page(body: &str) -> Result<(Vec<String>, Option<String>)> {
+    let page: Page<DiffStat> =
+        serde_json::from_str(body).context("Failed to parse Bitbucket diffstat")?;
+
+    let mut files = Vec::new();
+    for stat in page.values {
+        let old = stat.old.map(|file| file.path);
+        let new = stat.new.map(|file| file.path);
+        if let Some(old) = &old {
+            if new.as_ref() != Some(old) {
+                files.push(old.clone());
+            }
+        }
+        files.extend(new);
+    }
+    Ok((files, page.next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_remote() {
+        let client = BitbucketClient::from_remote(
+            &RemoteUrl::parse("git@bitbucket.org:team/repo.git").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(client.workspace, "team");
+        assert_eq!(client.repo, "repo");
+
+        assert!(BitbucketClient::from_remote(
+            &RemoteUrl::parse("https://gitlab.com/user/repo").unwrap()
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_parse_src_page() {
+        let body = r#"{
+            "values": [
+                {"path": "src", "type": "commit_directory"},
+                {"path": "src/main.rs", "type": "commit_file", "size": 120}
+            ],
+            "next": "https://api.bitbucket.org/2.0/next-page"
+        }"#;
+
+        let (entries, next) = parse_src_page(body).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].size, Some(120));
+        assert_eq!(
+            next,
+            Some("https://api.bitbucket.org/2.0/next-page".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_diffstat_page() {
+        let body = r#"{
+            "values": [
+                {"old": {"path": "a.rs"}, "new": {"path": "a.rs"}},
+                {"old": {"path": "old.rs"}, "new": {"path": "new.rs"}},
+                {"old": null, "new": {"path": "added.rs"}},
+                {"old": {"path": "removed.rs"}, "new": null}
+            ]
+        }"#;
+
+        let (files, next) = parse_diffstat_page(body).unwrap();
+        assert_eq!(
+            files,
+            vec!["a.rs", "old.rs", "new.rs", "added.rs", "removed.rs"]
+        );
+        assert_eq!(next, None);
+    }
+}
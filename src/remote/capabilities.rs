@@ -0,0 +1,208 @@
+use anyhow::{Context, Result};
+use std::process::Stdio;
+use std::time::Instant;
+use tracing::{debug, info};
+
+use crate::core::error::PartialError;
+use crate::git::commands;
+use crate::git::trace;
+use crate::remote;
+
+/// What a git server advertises, as seen by `git ls-remote`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServerCapabilities {
+    /// Whether the server speaks protocol v2
+    pub protocol_v2: bool,
+
+    /// Whether the server accepts `--filter`, which partial clones need
+    pub filter: bool,
+
+    /// Branch the server's HEAD points to, if advertised
+    pub default_branch: Option<String>,
+}
+
+impl ServerCapabilities {
+    /// Parses the stdout of `git ls-remote --symref <url> HEAD` and the
+    /// packets traced to stderr with `GIT_TRACE_PACKET`
+    pub fn parse(
+        stdout: &str,
+        trace: &str,
+    ) -> Self {
+        let mut capabilities = ServerCapabilities {
+            default_branch: parse_symref(stdout),
+            ..Default::default()
+        };
+        for packet in trace.lines().filter_map(received_packet) {
+            if packet == "version 2" {
+                capabilities.protocol_v2 = true;
+            } else if let Some(features) = packet.strip_prefix("fetch=") {
+                // Protocol v2 lists the fetch features in the capability advertisement
+                capabilities.filter |= features.split(' ').any(|feature| feature == "filter");
+            } else if let Some((_, features)) =
+                packet.split_once("\\0").or_else(|| packet.split_once('\0'))
+            {
+                // Protocol v0 appends them to the first ref after a NUL
+                capabilities.filter |= features.split(' ').any(|feature| feature == "filter");
+            }
+        }
+        capabilities
+    }
+}
+
+/// Reads `ref: refs/heads/<branch>\tHEAD` from `ls-remote --symref` output
+fn parse_symref(stdout: &str) -> Option<String> {
+    stdout.lines().find_map(|line| {
+        let (target, name) = line.strip_prefix("ref: ")?.split_once('\t')?;
+        (name == "HEAD").then(|| {
+            target
+                .strip_prefix("refs/heads/")
+                .unwrap_or(target)
+                .to_string()
+        })
+    })
+}
+
+/// Returns the payload of a packet the client received, skipping the ones it
+/// sent and, for local transports, the ones traced by `upload-pack` itself
+fn received_packet(line: &str) -> Option<&str> {
+    let (_, packet) = line.split_once("packet:")?;
+    let (process, payload) = packet.trim_start().split_once("< ")?;
+    (process != "upload-pack").then_some(payload)
+}
+
+/// Asks the server at `url` what it supports, preferring protocol v2.
+/// Credentials are never prompted for; a server that needs them fails.
+pub fn probe(url: &str) -> Result<ServerCapabilities> {
+    debug!("Probing server capabilities of {}", url);
+    let mut command = commands::git_command();
+    command
+        .args([
+            "-c",
+            "protocol.version=2",
+            "ls-remote",
+            "--symref",
+            url,
+            "HEAD",
+        ])
+        .env("GIT_TRACE_PACKET", "1")
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null());
+    let started = Instant::now();
+    let output = command.output().context("Failed to execute git command")?;
+    trace::record(&command, started, &output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        let message: Vec<&str> = stderr
+            .lines()
+            .filter(|line| !line.contains("packet:"))
+            .collect();
+        anyhow::bail!("Failed to reach {}: {}", url, message.join("\n").trim());
+    }
+    Ok(ServerCapabilities::parse(
+        &String::from_utf8_lossy(&output.stdout),
+        &stderr,
+    ))
+}
+
+/// Returns the branch HEAD points to on the server, without a web API
+pub fn default_branch(url: &str) -> Result<String> {
+    probe(url)?
+        .default_branch
+        .with_context(|| format!("{} does not advertise a default branch", url))
+}
+
+/// Fails when the server at `url` would ignore `--filter` and send every blob.
+/// Plain local paths are skipped: git copies those directly and never filters.
+pub fn ensure_partial_clone(url: &str) -> Result<()> {
+    if !url.starts_with("file://") && remote::local_path(url).is_some() {
+        return Ok(());
+    }
+    let capabilities = probe(url)?;
+    info!(
+        "{} speaks protocol {}, filter {}",
+        url,
+        if capabilities.protocol_v2 { "v2" } else { "v0" },
+        if capabilities.filter {
+            "supported"
+        } else {
+            "unsupported"
+        }
+    );
+    if !capabilities.filter {
+        return Err(PartialError::FilterUnsupported {
+            url: url.to_string(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Whether a clone made with `filter` holds more blobs than it should, i.e.
+/// the server ignored the filter and sent every object. A blobless (or
+/// treeless) clone only has the `checked_out` blobs its initial checkout
+/// fetched. `blob:limit` is not judged: the blobs under the limit are
+/// legitimately local.
+pub fn filter_ignored(
+    filter: &str,
+    local_blobs: usize,
+    checked_out: usize,
+) -> bool {
+    (filter == "blob:none" || filter.starts_with("tree:")) && local_blobs > checked_out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V2_TRACE: &str = "\
+10:50:42.566877 pkt-line.c:80           packet:  upload-pack> version 2
+10:50:42.572359 pkt-line.c:80           packet:    ls-remote< version 2
+10:50:42.572410 pkt-line.c:80           packet:    ls-remote< agent=git/2.39.5
+10:50:42.572463 pkt-line.c:80           packet:    ls-remote< fetch=shallow wait-for-done filter
+10:50:42.572486 pkt-line.c:80           packet:    ls-remote< object-format=sha1
+10:50:42.567027 pkt-line.c:80           packet:    ls-remote> command=ls-refs";
+
+    #[test]
+    fn test_parse_protocol_v2() {
+        let stdout = "ref: refs/heads/main\tHEAD\n84975aa52d6ca6da84d3406f330a9f918f411b1e\tHEAD\n";
+        let capabilities = ServerCapabilities::parse(stdout, V2_TRACE);
+
+        assert_eq!(
+            capabilities,
+            ServerCapabilities {
+                protocol_v2: true,
+                filter: true,
+                default_branch: Some("main".to_string()),
+            }
+        );
+
+        let without_filter = V2_TRACE.replace(" filter", "");
+        assert!(!ServerCapabilities::parse(stdout, &without_filter).filter);
+    }
+
+    #[test]
+    fn test_parse_protocol_v0() {
+        let trace = "packet:          git< 84975aa52d6ca6da84d3406f330a9f918f411b1e HEAD\\0multi_ack thin-pack symref=HEAD:refs/heads/trunk filter agent=git/2.39.5\n\
+                     packet:          git< 84975aa52d6ca6da84d3406f330a9f918f411b1e refs/heads/trunk";
+        let capabilities = ServerCapabilities::parse("ref: refs/heads/trunk\tHEAD\n", trace);
+
+        assert!(!capabilities.protocol_v2);
+        assert!(capabilities.filter);
+        assert_eq!(capabilities.default_branch, Some("trunk".to_string()));
+    }
+
+    #[test]
+    fn test_parse_without_symref() {
+        let capabilities = ServerCapabilities::parse("84975aa\tHEAD\n", "");
+        assert_eq!(capabilities, ServerCapabilities::default());
+    }
+
+    #[test]
+    fn test_filter_ignored() {
+        assert!(filter_ignored("blob:none", 120, 3));
+        assert!(filter_ignored("tree:0", 4, 0));
+        assert!(!filter_ignored("blob:none", 3, 3));
+        assert!(!filter_ignored("blob:none", 0, 3));
+        assert!(!filter_ignored("blob:limit=1m", 120, 3));
+    }
+}
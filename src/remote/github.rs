@@ -0,0 +1,249 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+use super::auth::{self, AuthHost};
+use super::{encode_url_component, http_client, send_get, RemoteUrl};
+use crate::core::tree::{EntryKind, TreeEntry};
+
+/// Number of items requested per page from paginated endpoints
+const PAGE_SIZE: usize = 100;
+
+/// Client for the GitHub REST API (github.com and GitHub Enterprise)
+#[derive(Debug)]
+pub struct GitHubClient {
+    api_base: String,
+    owner: String,
+    repo: String,
+    token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepoResponse {
+    default_branch: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TreeResponse {
+    tree: Vec<TreeItem>,
+    #[serde(default)]
+    truncated: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct TreeItem {
+    path: String,
+    #[serde(rename = "type")]
+    kind: String,
+    size: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PullFile {
+    filename: String,
+}
+
+/// A published release and its downloadable files
+#[derive(Debug, Deserialize)]
+pub struct Release {
+    /// Tag of the release, e.g. `v0.2.0`
+    pub tag_name: String,
+    pub assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+impl GitHubClient {
+    /// Creates a client if the remote is hosted on GitHub.
+    /// Hosts named `github.com` or `github.*` are treated as GitHub (Enterprise).
+    pub fn from_remote(remote: &RemoteUrl) -> Option<Self> {
+        let api_base = if remote.host == "github.com" {
+            "https://api.github.com".to_string()
+        } else if remote.host.starts_with("github.") {
+            format!("https://{}/api/v3", remote.host)
+        } else {
+            return None;
+        };
+
+        let (owner, repo) = remote.path.split_once('/')?;
+        if repo.contains('/') {
+            return None;
+        }
+
+        Some(GitHubClient {
+            api_base,
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            token: auth::find_token(AuthHost::GitHub, &remote.host).map(|token| token.value),
+        })
+    }
+
+    /// Returns the name of the repository's default branch
+    pub async fn default_branch(&self) -> Result<String> {
+        let url = format!("{}/repos/{}/{}", self.api_base, self.owner, self.repo);
+        let body = self.get(&url).await?;
+        let repo: RepoResponse =
+            serde_json::from_str(&body).context("Failed to parse GitHub repository response")?;
+        Ok(repo.default_branch)
+    }
+
+    /// Lists every file and directory at the given reference
+    pub async fn list_tree(
+        &self,
+        reference: &str,
+    ) -> Result<Vec<TreeEntry>> {
+        let url = format!(
+            "{}/repos/{}/{}/git/trees/{}?recursive=1",
+            self.api_base,
+            self.owner,
+            self.repo,
+            encode_url_component(reference, "/")
+        );
+        let body = self.get(&url).await?;
+        parse_tree_response(&body)
+    }
+
+    /// Lists the paths changed by a pull request
+    pub async fn changed_files(
+        &self,
+        number: u64,
+    ) -> Result<Vec<String>> {
+        let mut files = Vec::new();
+        for page in 1.. {
+            let url = format!(
+                "{}/repos/{}/{}/pulls/{}/files?per_page={}&page={}",
+                self.api_base, self.owner, self.repo, number, PAGE_SIZE, page
+            );
+            let body = self.get(&url).await?;
+            let page_files = parse_pull_files_response(&body)?;
+            let count = page_files.len();
+            files.extend(page_files);
+            if count < PAGE_SIZE {
+                break;
+            }
+        }
+        Ok(files)
+    }
+
+    /// Returns the latest published release
+    pub async fn latest_release(&self) -> Result<Release> {
+        let url = format!(
+            "{}/repos/{}/{}/releases/latest",
+            self.api_base, self.owner, self.repo
+        );
+        let body = self.get(&url).await?;
+        serde_json::from_str(&body).context("Failed to parse GitHub release response")
+    }
+
+    async fn get(
+        &self,
+        url: &str,
+    ) -> Result<String> {
+        debug!("GET {}", url);
+        let mut request = http_client()
+            .get(url)
+            .header("Accept", "application/vnd.github+json");
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let (body, _) = send_get(request, "GitHub").await?;
+        Ok(body)
+    }
+}
+
+/// Converts a `git/trees` API response into tree entries
+fn parse_tree_response(body: &str) -> Result<Vec<TreeEntry>> {
+    let response: TreeResponse =
+        serde_json::from_str(body).context("Failed to parse GitHub tree response")?;
+
+    if response.truncated {
+        warn!("GitHub truncated the tree listing; some paths are missing");
+    }
+
+    Ok(response
+        .tree
+        .into_iter()
+        .filter_map(|item| {
+            let kind = match item.kind.as_str() {
+                "blob" => EntryKind::Blob,
+                "tree" => EntryKind::Tree,
+                _ => return None,
+            };
+            Some(TreeEntry {
+                path: item.path,
+                kind,
+                size: item.size,
+            })
+        })
+        .collect())
+}
+
+/// Extracts file names from a `pulls/{number}/files` API response
+fn parse_pull_files_response(body: &str) -> Result<Vec<String>> {
+    let files: Vec<PullFile> =
+        serde_json::from_str(body).context("Failed to parse GitHub pull request files")?;
+    Ok(files.into_iter().map(|file| file.filename).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_remote() {
+        let client =
+            GitHubClient::from_remote(&RemoteUrl::parse("https://github.com/user/repo").unwrap())
+                .unwrap();
+        assert_eq!(client.api_base, "https://api.github.com");
+        assert_eq!(client.owner, "user");
+        assert_eq!(client.repo, "repo");
+
+        let client = GitHubClient::from_remote(
+            &RemoteUrl::parse("git@github.example.com:org/repo.git").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(client.api_base, "https://github.example.com/api/v3");
+
+        assert!(GitHubClient::from_remote(
+            &RemoteUrl::parse("https://gitlab.com/user/repo").unwrap()
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_parse_tree_response() {
+        let body = r#"{
+            "sha": "abc",
+            "tree": [
+                {"path": "README.md", "type": "blob", "size": 42},
+                {"path": "src", "type": "tree"},
+                {"path": "vendor/lib", "type": "commit"}
+            ],
+            "truncated": false
+        }"#;
+
+        let entries = parse_tree_response(body).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].path, "README.md");
+        assert_eq!(entries[0].size, Some(42));
+        assert_eq!(entries[1].kind, EntryKind::Tree);
+    }
+
+    #[test]
+    fn test_parse_pull_files_response() {
+        let body = r#"[
+            {"filename": "src/main.rs", "status": "modified"},
+            {"filename": "docs/new.md", "status": "added"}
+        ]"#;
+
+        assert_eq!(
+            parse_pull_files_response(body).unwrap(),
+            vec!["src/main.rs", "docs/new.md"]
+        );
+    }
+}
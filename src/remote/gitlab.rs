@@ -0,0 +1,218 @@
+use anyhow::{Context, Result};
+use reqwest::header::HeaderMap;
+use serde::Deserialize;
+use tracing::debug;
+
+use super::auth::{self, AuthHost};
+use super::{encode_url_component, http_client, send_get, RemoteUrl};
+use crate::core::tree::{EntryKind, TreeEntry};
+
+/// Number of items requested per page from paginated endpoints
+const PAGE_SIZE: usize = 100;
+
+/// Client for the GitLab REST API (gitlab.com and self-managed instances)
+#[derive(Debug)]
+pub struct GitLabClient {
+    api_base: String,
+    project: String,
+    token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectResponse {
+    default_branch: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TreeItem {
+    path: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChangesResponse {
+    changes: Vec<Change>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Change {
+    old_path: String,
+    new_path: String,
+}
+
+impl GitLabClient {
+    /// Creates a client if the remote is hosted on GitLab.
+    /// Hosts named `gitlab.com` or `gitlab.*` are treated as GitLab.
+    pub fn from_remote(remote: &RemoteUrl) -> Option<Self> {
+        if remote.host != "gitlab.com" && !remote.host.starts_with("gitlab.") {
+            return None;
+        }
+
+        Some(GitLabClient {
+            api_base: format!("https://{}/api/v4", remote.host),
+            // Projects are addressed by their URL-encoded full path, including subgroups
+            project: encode_url_component(&remote.path, ""),
+            token: auth::find_token(AuthHost::GitLab, &remote.host).map(|token| token.value),
+        })
+    }
+
+    /// Returns the name of the project's default branch
+    pub async fn default_branch(&self) -> Result<String> {
+        let url = format!("{}/projects/{}", self.api_base, self.project);
+        let (body, _) = self.get(&url).await?;
+        let project: ProjectResponse =
+            serde_json::from_str(&body).context("Failed to parse GitLab project response")?;
+        Ok(project.default_branch)
+    }
+
+    /// Lists every file and directory at the given reference
+    pub async fn list_tree(
+        &self,
+        reference: &str,
+    ) -> Result<Vec<TreeEntry>> {
+        let mut entries = Vec::new();
+        let mut page = "1".to_string();
+        loop {
+            let url = format!(
+                "{}/projects/{}/repository/tree?recursive=true&ref={}&per_page={}&page={}",
+                self.api_base,
+                self.project,
+                encode_url_component(reference, ""),
+                PAGE_SIZE,
+                page
+            );
+            let (body, headers) = self.get(&url).await?;
+            entries.extend(parse_tree_response(&body)?);
+
+            match next_page(&headers) {
+                Some(next) => page = next,
+                None => break,
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Lists the paths changed by a merge request
+    pub async fn changed_files(
+        &self,
+        iid: u64,
+    ) -> Result<Vec<String>> {
+        let url = format!(
+            "{}/projects/{}/merge_requests/{}/changes",
+            self.api_base, self.project, iid
+        );
+        let (body, _) = self.get(&url).await?;
+        parse_changes_response(&body)
+    }
+
+    async fn get(
+        &self,
+        url: &str,
+    ) -> Result<(String, HeaderMap)> {
+        debug!("GET {}", url);
+        let mut request = http_client().get(url);
+        if let Some(token) = &self.token {
+            request = request.header("PRIVATE-TOKEN", token);
+        }
+        send_get(request, "GitLab").await
+    }
+}
+
+/// Returns the next page number from GitLab's pagination headers
+fn next_page(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("x-next-page")
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .map(|value| value.to_string())
+}
+
+/// Converts a `repository/tree` API response into tree entries.
+/// GitLab does not report blob sizes in tree listings.
+fn parse_tree_response(body: &str) -> Result<Vec<TreeEntry>> {
+    let items: Vec<TreeItem> =
+        serde_json::from_str(body).context("Failed to parse GitLab tree response")?;
+
+    Ok(items
+        .into_iter()
+        .filter_map(|item| {
+            let kind = match item.kind.as_str() {
+                "blob" => EntryKind::Blob,
+                "tree" => EntryKind::Tree,
+                _ => return None,
+            };
+            Some(TreeEntry {
+                path: item.path,
+                kind,
+                size: None,
+            })
+        })
+        .collect())
+}
+
+/// Extracts changed paths from a `merge_requests/{iid}/changes` API response.
+/// Renames report both the old and the new path.
+fn parse_changes_response(body: &str) -> Result<Vec<String>> {
+    let response: ChangesResponse =
+        serde_json::from_str(body).context("Failed to parse GitLab merge request changes")?;
+
+    let mut files = Vec::new();
+    for change in response.changes {
+        if change.old_path != change.new_path {
+            files.push(change.old_path);
+        }
+        files.push(change.new_path);
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_remote() {
+        let client = GitLabClient::from_remote(
+            &RemoteUrl::parse("git@gitlab.com:group/sub/repo.git").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(client.api_base, "https://gitlab.com/api/v4");
+        assert_eq!(client.project, "group%2Fsub%2Frepo");
+
+        assert!(GitLabClient::from_remote(
+            &RemoteUrl::parse("https://github.com/user/repo").unwrap()
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_parse_tree_response() {
+        let body = r#"[
+            {"id": "a1", "name": "src", "type": "tree", "path": "src", "mode": "040000"},
+            {"id": "b2", "name": "main.rs", "type": "blob", "path": "src/main.rs", "mode": "100644"}
+        ]"#;
+
+        let entries = parse_tree_response(body).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].kind, EntryKind::Tree);
+        assert_eq!(entries[1].path, "src/main.rs");
+        assert_eq!(entries[1].size, None);
+    }
+
+    #[test]
+    fn test_parse_changes_response() {
+        let body = r#"{
+            "iid": 7,
+            "changes": [
+                {"old_path": "src/a.rs", "new_path": "src/a.rs"},
+                {"old_path": "old/b.rs", "new_path": "new/b.rs"}
+            ]
+        }"#;
+
+        assert_eq!(
+            parse_changes_response(body).unwrap(),
+            vec!["src/a.rs", "old/b.rs", "new/b.rs"]
+        );
+    }
+}
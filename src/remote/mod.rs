@@ -1 +1,255 @@
-// Remote integration functionality will be implemented here
+pub mod auth;
+pub mod bitbucket;
+pub mod capabilities;
+pub mod github;
+pub mod gitlab;
+
+use anyhow::{Context, Result};
+use reqwest::header::HeaderMap;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::core::config;
+use crate::core::tree::TreeEntry;
+
+/// Host and repository path parsed from a clone URL
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteUrl {
+    /// Host name without user or port, e.g. `github.com`
+    pub host: String,
+
+    /// Repository path without leading slash or `.git` suffix, e.g. `owner/repo`
+    pub path: String,
+}
+
+impl RemoteUrl {
+    /// Parses HTTPS, `ssh://` and scp-like (`git@host:owner/repo.git`) URLs.
+    /// Returns `None` for local paths and unsupported forms.
+    pub fn parse(url: &str) -> Option<Self> {
+        let (host_part, path) = if let Some((_, rest)) = url.split_once("://") {
+            rest.split_once('/')?
+        } else if !url.starts_with('/') && url.contains(':') {
+            url.split_once(':')?
+        } else {
+            return None;
+        };
+
+        // Strip credentials and port
+        let host = host_part.rsplit('@').next()?;
+        let host = host.split(':').next()?.to_lowercase();
+
+        let path = path.trim_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path).to_string();
+
+        if host.is_empty() || !path.contains('/') {
+            return None;
+        }
+
+        Some(RemoteUrl { host, path })
+    }
+}
+
+/// Returns the directory of a remote that lives on this machine,
+/// given as a plain path or a `file://` URL
+pub fn local_path(url: &str) -> Option<PathBuf> {
+    let path = Path::new(url.strip_prefix("file://").unwrap_or(url));
+    path.is_dir().then(|| path.to_path_buf())
+}
+
+/// A hosting service that can be queried through its web API
+#[derive(Debug)]
+pub enum Provider {
+    GitHub(github::GitHubClient),
+    GitLab(gitlab::GitLabClient),
+    Bitbucket(bitbucket::BitbucketClient),
+}
+
+impl Provider {
+    /// Selects a provider based on the remote URL, if one is supported
+    pub fn from_url(url: &str) -> Option<Self> {
+        let remote = RemoteUrl::parse(url)?;
+        github::GitHubClient::from_remote(&remote)
+            .map(Provider::GitHub)
+            .or_else(|| gitlab::GitLabClient::from_remote(&remote).map(Provider::GitLab))
+            .or_else(|| bitbucket::BitbucketClient::from_remote(&remote).map(Provider::Bitbucket))
+    }
+
+    /// Returns the name of the repository's default branch
+    pub async fn default_branch(&self) -> Result<String> {
+        match self {
+            Provider::GitHub(client) => client.default_branch().await,
+            Provider::GitLab(client) => client.default_branch().await,
+            Provider::Bitbucket(client) => client.default_branch().await,
+        }
+    }
+
+    /// Lists every file and directory at the given reference
+    pub async fn list_tree(
+        &self,
+        reference: &str,
+    ) -> Result<Vec<TreeEntry>> {
+        match self {
+            Provider::GitHub(client) => client.list_tree(reference).await,
+            Provider::GitLab(client) => client.list_tree(reference).await,
+            Provider::Bitbucket(client) => client.list_tree(reference).await,
+        }
+    }
+
+    /// Lists the paths changed by a pull request (merge request on GitLab)
+    pub async fn changed_files(
+        &self,
+        number: u64,
+    ) -> Result<Vec<String>> {
+        match self {
+            Provider::GitHub(client) => client.changed_files(number).await,
+            Provider::GitLab(client) => client.changed_files(number).await,
+            Provider::Bitbucket(client) => client.changed_files(number).await,
+        }
+    }
+}
+
+/// Percent-encodes `value`, such as a branch name, for an API URL. Only
+/// unreserved characters and those in `keep` are left as they are.
+pub fn encode_url_component(
+    value: &str,
+    keep: &str,
+) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric()
+            || b"-._~".contains(&byte)
+            || keep.as_bytes().contains(&byte)
+        {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// HTTP client for the provider APIs, using the proxy from the global config.
+/// Without one, reqwest honors the usual `HTTPS_PROXY` variables.
+fn http_client() -> reqwest::Client {
+    let mut builder = reqwest::Client::builder();
+    if let Some(proxy) = &config::global().proxy {
+        match reqwest::Proxy::all(proxy) {
+            Ok(proxy) => builder = builder.proxy(proxy),
+            Err(e) => warn!("Ignoring invalid proxy '{}': {}", proxy, e),
+        }
+    }
+    builder.build().unwrap_or_default()
+}
+
+/// Sends a prepared GET request and returns the response body and headers.
+/// Non-success statuses are turned into errors naming the service.
+async fn send_get(
+    request: reqwest::RequestBuilder,
+    service: &str,
+) -> Result<(String, HeaderMap)> {
+    let response = request
+        .header("User-Agent", "git-partial")
+        .send()
+        .await
+        .with_context(|| format!("Failed to reach {} API", service))?;
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response
+        .text()
+        .await
+        .with_context(|| format!("Failed to read {} API response", service))?;
+
+    if !status.is_success() {
+        anyhow::bail!(
+            "{} API request failed ({}): {}",
+            service,
+            status,
+            body.trim()
+        );
+    }
+    Ok((body, headers))
+}
+
+/// Downloads a file, such as a release asset, following redirects
+pub async fn download(url: &str) -> Result<Vec<u8>> {
+    let response = http_client()
+        .get(url)
+        .header("User-Agent", "git-partial")
+        .send()
+        .await
+        .with_context(|| format!("Failed to download {}", url))?;
+    let status = response.status();
+    if !status.is_success() {
+        anyhow::bail!("Download of {} failed ({})", url, status);
+    }
+    let bytes = response
+        .bytes()
+        .await
+        .with_context(|| format!("Failed to download {}", url))?;
+    Ok(bytes.to_vec())
+}
+
+/// Posts a JSON body, such as a webhook notification
+pub async fn post_json(
+    url: &str,
+    body: &serde_json::Value,
+) -> Result<()> {
+    let response = http_client()
+        .post(url)
+        .header("User-Agent", "git-partial")
+        .json(body)
+        .send()
+        .await
+        .with_context(|| format!("Failed to post to {}", url))?;
+    let status = response.status();
+    if !status.is_success() {
+        anyhow::bail!("Post to {} failed ({})", url, status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_https_url() {
+        let remote = RemoteUrl::parse("https://github.com/user/repo.git").unwrap();
+        assert_eq!(remote.host, "github.com");
+        assert_eq!(remote.path, "user/repo");
+
+        let remote = RemoteUrl::parse("https://token@GitHub.example.com:8443/org/repo/").unwrap();
+        assert_eq!(remote.host, "github.example.com");
+        assert_eq!(remote.path, "org/repo");
+    }
+
+    #[test]
+    fn test_parse_ssh_urls() {
+        let remote = RemoteUrl::parse("git@github.com:user/repo.git").unwrap();
+        assert_eq!(remote.host, "github.com");
+        assert_eq!(remote.path, "user/repo");
+
+        let remote = RemoteUrl::parse("ssh://git@gitlab.com/group/sub/repo.git").unwrap();
+        assert_eq!(remote.host, "gitlab.com");
+        assert_eq!(remote.path, "group/sub/repo");
+    }
+
+    #[test]
+    fn test_encode_url_component() {
+        assert_eq!(
+            encode_url_component("feature/a#1?x=50% b", ""),
+            "feature%2Fa%231%3Fx%3D50%25%20b"
+        );
+        assert_eq!(
+            encode_url_component("release/v1.2_rc~1", "/"),
+            "release/v1.2_rc~1"
+        );
+        assert_eq!(encode_url_component("caf\u{e9}", "/"), "caf%C3%A9");
+    }
+
+    #[test]
+    fn test_parse_local_paths() {
+        assert_eq!(RemoteUrl::parse("/tmp/repo"), None);
+        assert_eq!(RemoteUrl::parse("./repo"), None);
+    }
+}
@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info, warn};
+
+use crate::core::metrics;
+
+/// Largest request head read before the request is rejected
+const MAX_REQUEST_LEN: usize = 8192;
+
+/// The parts of an HTTP request the daemons look at
+#[derive(Debug, PartialEq)]
+pub struct Request {
+    pub method: String,
+
+    /// The request target without its query string
+    pub path: String,
+}
+
+/// Parses the request line, e.g. `GET /metrics?x=1 HTTP/1.1`
+pub fn parse_request_line(line: &str) -> Option<Request> {
+    let mut parts = line.split(' ');
+    let method = parts.next().filter(|method| !method.is_empty())?;
+    let target = parts.next()?;
+    Some(Request {
+        method: method.to_string(),
+        path: target.split('?').next().unwrap_or(target).to_string(),
+    })
+}
+
+/// Reads a request head from `stream`. Answers and returns `None` when it
+/// is too large or malformed.
+pub async fn read_request(stream: &mut TcpStream) -> Result<Option<Request>> {
+    let mut head = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        head.extend_from_slice(&buffer[..read]);
+        if head.len() > MAX_REQUEST_LEN {
+            send(stream, 431, "text/plain", "request too large\n").await?;
+            return Ok(None);
+        }
+    }
+    let head = String::from_utf8_lossy(&head);
+    match parse_request_line(head.lines().next().unwrap_or_default()) {
+        Some(request) => Ok(Some(request)),
+        None => {
+            send(stream, 400, "text/plain", "invalid request\n").await?;
+            Ok(None)
+        }
+    }
+}
+
+/// Writes a complete response and closes the connection
+pub async fn send(
+    stream: &mut TcpStream,
+    status: u16,
+    content_type: &str,
+    body: &str,
+) -> Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error",
+    };
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Serve the process's metrics on `GET /metrics` at `listen`, in the
+/// background, for Prometheus to scrape. Fails if the address is taken.
+pub async fn serve_metrics(listen: &str) -> Result<()> {
+    let listener = TcpListener::bind(listen)
+        .await
+        .with_context(|| format!("Failed to listen on {}", listen))?;
+    info!(
+        "Serving metrics on http://{}/metrics",
+        listener.local_addr()?
+    );
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("Failed to accept a metrics connection: {}", e);
+                    continue;
+                }
+            };
+            tokio::spawn(async move {
+                let result = match read_request(&mut stream).await {
+                    Ok(Some(request)) if request.method != "GET" => {
+                        send(&mut stream, 405, "text/plain", "only GET is supported\n").await
+                    }
+                    Ok(Some(request)) if request.path == "/metrics" => {
+                        send(
+                            &mut stream,
+                            200,
+                            "text/plain; version=0.0.4",
+                            &metrics::render(),
+                        )
+                        .await
+                    }
+                    Ok(Some(_)) => send(&mut stream, 404, "text/plain", "not found\n").await,
+                    Ok(None) => Ok(()),
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    debug!("Metrics request from {} failed: {:#}", peer, e);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request_line() {
+        assert_eq!(
+            parse_request_line("GET /metrics?debug=1 HTTP/1.1"),
+            Some(Request {
+                method: "GET".to_string(),
+                path: "/metrics".to_string(),
+            })
+        );
+        assert_eq!(parse_request_line(""), None);
+        assert_eq!(parse_request_line("GET"), None);
+    }
+}
@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use tracing::warn;
+
+/// Exit code after an interrupt, as shells report for SIGINT (128 + 2)
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+/// Work to undo if the user interrupts the running command
+struct Pending {
+    /// Process ids of running git children
+    children: Vec<u32>,
+    /// Files and directories left incomplete by an interrupt
+    remove: Vec<Removal>,
+}
+
+/// A path to remove on interrupt
+struct Removal {
+    path: PathBuf,
+    /// Only empty the directory, e.g. one the user created before cloning
+    keep_dir: bool,
+}
+
+static PENDING: Mutex<Pending> = Mutex::new(Pending {
+    children: Vec::new(),
+    remove: Vec::new(),
+});
+
+fn pending() -> MutexGuard<'static, Pending> {
+    // A panic while holding the lock does not make the lists invalid
+    PENDING.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Listen for Ctrl-C in the background. On interrupt, running git children
+/// are terminated, registered paths are removed and the process exits with
+/// [`EXIT_INTERRUPTED`]. Commands run synchronously, so the listener runs
+/// on its own task rather than racing the command.
+pub fn install() {
+    tokio::spawn(async {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("Interrupted, cleaning up...");
+            cleanup();
+            std::process::exit(EXIT_INTERRUPTED);
+        }
+    });
+}
+
+fn cleanup() {
+    let mut pending = pending();
+    for pid in pending.children.drain(..) {
+        terminate(pid);
+    }
+    for removal in pending.remove.drain(..) {
+        let removed = if removal.keep_dir {
+            empty_dir(&removal.path)
+        } else if removal.path.is_dir() {
+            std::fs::remove_dir_all(&removal.path)
+        } else {
+            std::fs::remove_file(&removal.path)
+        };
+        if let Err(e) = removed {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove {}: {}", removal.path.display(), e);
+            }
+        }
+    }
+}
+
+fn empty_dir(dir: &Path) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            std::fs::remove_dir_all(&path)?;
+        } else {
+            std::fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn terminate(pid: u32) {
+    // SAFETY: kill has no memory effects; a stale pid at worst returns ESRCH
+    unsafe {
+        libc::kill(pid as libc::pid_t, libc::SIGTERM);
+    }
+}
+
+#[cfg(not(unix))]
+fn terminate(_pid: u32) {
+    // The console delivers Ctrl-C to every attached process, git included
+}
+
+/// Remember a spawned git process so an interrupt can terminate it
+pub fn track_child(pid: u32) {
+    pending().children.push(pid);
+}
+
+/// Forget a git process once it has exited
+pub fn untrack_child(pid: u32) {
+    pending().children.retain(|&child| child != pid);
+}
+
+/// Removes a path if the command is interrupted before the guard is dropped
+pub struct RemoveOnInterrupt {
+    path: PathBuf,
+    keep_dir: bool,
+}
+
+impl RemoveOnInterrupt {
+    /// Remove the file or directory at `path`
+    pub fn new(path: &Path) -> Self {
+        Self::register(path, false)
+    }
+
+    /// Remove everything inside the directory at `path`, but not the directory itself
+    pub fn contents(path: &Path) -> Self {
+        Self::register(path, true)
+    }
+
+    fn register(
+        path: &Path,
+        keep_dir: bool,
+    ) -> Self {
+        pending().remove.push(Removal {
+            path: path.to_path_buf(),
+            keep_dir,
+        });
+        RemoveOnInterrupt {
+            path: path.to_path_buf(),
+            keep_dir,
+        }
+    }
+}
+
+impl Drop for RemoveOnInterrupt {
+    fn drop(&mut self) {
+        pending()
+            .remove
+            .retain(|removal| removal.path != self.path || removal.keep_dir != self.keep_dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guard_unregisters_on_drop() {
+        let path = Path::new("/tmp/git-partial-interrupt-test");
+        {
+            let _guard = RemoveOnInterrupt::new(path);
+            assert!(pending().remove.iter().any(|r| r.path == path));
+        }
+        assert!(!pending().remove.iter().any(|r| r.path == path));
+    }
+}
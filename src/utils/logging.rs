@@ -0,0 +1,42 @@
+use clap::ValueEnum;
+use std::io::{self, IsTerminal};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+
+use crate::core::config::ColorChoice;
+
+/// How log lines are written to stderr
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum LogFormat {
+    /// Human readable lines
+    #[default]
+    Text,
+
+    /// One JSON object per line, for log aggregators
+    Json,
+}
+
+/// Install the global subscriber. Verbosity comes from `RUST_LOG` and
+/// defaults to errors only. Span closes are logged too, so `git` spans
+/// carry their duration and exit status.
+pub fn init(
+    format: LogFormat,
+    color: ColorChoice,
+) {
+    let ansi = match color {
+        ColorChoice::Auto => io::stderr().is_terminal(),
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("error"));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(io::stderr)
+        .with_ansi(ansi)
+        .with_span_events(FmtSpan::CLOSE);
+
+    match format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().with_current_span(true).init(),
+    }
+}
@@ -1 +1,8 @@
-// Utility functions will be implemented here
+pub mod disk;
+pub mod http_server;
+pub mod interrupt;
+pub mod logging;
+pub mod notify;
+pub mod prompt;
+pub mod shell;
+pub mod smtp;
@@ -0,0 +1,18 @@
+use anyhow::{Context, Result};
+use notify_rust::Notification;
+
+/// Show a desktop notification through the platform's notification
+/// service: the D-Bus notification server on Linux and the BSDs, the
+/// Notification Center on macOS, and toast notifications on Windows
+pub fn desktop(
+    title: &str,
+    body: &str,
+) -> Result<()> {
+    Notification::new()
+        .appname("git-partial")
+        .summary(title)
+        .body(body.trim_end())
+        .show()
+        .context("Failed to show a desktop notification")?;
+    Ok(())
+}
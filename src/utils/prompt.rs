@@ -0,0 +1,54 @@
+use anyhow::{Context, Result};
+use std::io::{self, BufRead, Write};
+
+/// Ask the user to confirm a destructive operation.
+///
+/// Prints the summary followed by a `[y/N]` prompt and reads one line from stdin.
+/// Returns `true` without prompting when `assume_yes` is set (`--yes`/`-y`).
+/// An empty answer or a closed stdin counts as "no".
+pub fn confirm(
+    summary: &str,
+    assume_yes: bool,
+) -> Result<bool> {
+    if assume_yes {
+        return Ok(true);
+    }
+
+    print!("{}\nContinue? [y/N] ", summary);
+    io::stdout().flush().context("Failed to flush stdout")?;
+
+    let mut answer = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .context("Failed to read confirmation from stdin")?;
+
+    Ok(is_affirmative(&answer))
+}
+
+/// Returns true if the answer means "yes"
+fn is_affirmative(answer: &str) -> bool {
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_affirmative() {
+        assert!(is_affirmative("y"));
+        assert!(is_affirmative("YES\n"));
+        assert!(is_affirmative("  Yes  "));
+
+        assert!(!is_affirmative(""));
+        assert!(!is_affirmative("\n"));
+        assert!(!is_affirmative("n"));
+        assert!(!is_affirmative("yep"));
+    }
+
+    #[test]
+    fn test_confirm_assume_yes() {
+        assert!(confirm("Delete everything", true).unwrap());
+    }
+}
@@ -0,0 +1,15 @@
+/// Quotes a value for POSIX shells
+pub fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("src/**"), "'src/**'");
+        assert_eq!(quote("it's"), "'it'\\''s'");
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::{Context, Result};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tracing::debug;
+
+/// Port used when the server is given without one
+const DEFAULT_PORT: u16 = 25;
+
+/// Send a plain-text email through the SMTP relay at `server` (`host` or
+/// `host:port`). The connection is unencrypted and unauthenticated, as to
+/// a relay on the local network or machine.
+pub async fn send(
+    server: &str,
+    from: &str,
+    to: &[String],
+    subject: &str,
+    body: &str,
+) -> Result<()> {
+    let address = if server.contains(':') {
+        server.to_string()
+    } else {
+        format!("{}:{}", server, DEFAULT_PORT)
+    };
+    let stream = TcpStream::connect(&address)
+        .await
+        .with_context(|| format!("Failed to connect to SMTP server {}", address))?;
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    expect(&mut reader, 220).await?;
+    let commands = [
+        "EHLO git-partial".to_string(),
+        format!("MAIL FROM:<{}>", single_line(from)),
+    ]
+    .into_iter()
+    .chain(
+        to.iter()
+            .map(|recipient| format!("RCPT TO:<{}>", single_line(recipient))),
+    );
+    for command in commands {
+        command_reply(&mut reader, &mut writer, &command, 250).await?;
+    }
+    command_reply(&mut reader, &mut writer, "DATA", 354).await?;
+    writer
+        .write_all(message(from, to, subject, body).as_bytes())
+        .await?;
+    command_reply(&mut reader, &mut writer, ".", 250).await?;
+    command_reply(&mut reader, &mut writer, "QUIT", 221).await
+}
+
+/// The message as sent after `DATA`: headers, the body with CRLF line
+/// endings and leading dots doubled, without the terminating `.` line
+pub fn message(
+    from: &str,
+    to: &[String],
+    subject: &str,
+    body: &str,
+) -> String {
+    let mut message = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n",
+        single_line(from),
+        single_line(&to.join(", ")),
+        single_line(subject),
+        chrono::Local::now().to_rfc2822()
+    );
+    for line in body.lines() {
+        if line.starts_with('.') {
+            message.push('.');
+        }
+        message.push_str(line);
+        message.push_str("\r\n");
+    }
+    message
+}
+
+/// Replaces line breaks, so a header value or command argument built from
+/// commit data cannot add headers or SMTP commands of its own
+fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+/// Write one command line and check the reply code
+async fn command_reply<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    command: &str,
+    code: u16,
+) -> Result<()>
+where
+    R: AsyncBufReadExt + Unpin,
+    W: AsyncWriteExt + Unpin,
+{
+    debug!("SMTP > {}", command);
+    writer
+        .write_all(format!("{}\r\n", command).as_bytes())
+        .await?;
+    expect(reader, code)
+        .await
+        .with_context(|| format!("SMTP server rejected '{}'", command))
+}
+
+/// Read a reply, following `250-` continuation lines, and fail unless it
+/// has the expected code
+async fn expect<R: AsyncBufReadExt + Unpin>(
+    reader: &mut R,
+    code: u16,
+) -> Result<()> {
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            anyhow::bail!("SMTP server closed the connection");
+        }
+        debug!("SMTP < {}", line.trim_end());
+        if line.as_bytes().get(3) == Some(&b'-') {
+            continue;
+        }
+        let received: u16 = line.get(..3).and_then(|c| c.parse().ok()).unwrap_or(0);
+        if received != code {
+            anyhow::bail!("Unexpected SMTP reply: {}", line.trim_end());
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message() {
+        let to = vec!["a@example.com".to_string(), "b@example.com".to_string()];
+        let message = message("bot@example.com", &to, "Digest", "line one\n.dot\n");
+
+        assert!(message.starts_with(
+            "From: bot@example.com\r\nTo: a@example.com, b@example.com\r\nSubject: Digest\r\n"
+        ));
+        assert!(message.ends_with("\r\n\r\nline one\r\n..dot\r\n"));
+
+        let injected = super::message("bot@example.com", &to, "Fix\r\nBcc: victim@example.com", "");
+        assert!(injected.contains("Subject: Fix  Bcc: victim@example.com\r\n"));
+        assert!(!injected.contains("\nBcc:"));
+    }
+}
@@ -0,0 +1,116 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::{file_exists, TestRepo};
+use anyhow::Result;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn manifest(
+    name: &str,
+    extra: &str,
+) -> String {
+    format!(
+        "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n{}",
+        name, extra
+    )
+}
+
+#[test]
+fn test_add_crate_adds_path_dependencies() -> Result<()> {
+    // 1. Setup: a Cargo workspace, cloned with only the README
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Workspace")?;
+    source_repo.write_file(
+        "Cargo.toml",
+        "[workspace]\nmembers = [\"crates/*\"]\nresolver = \"2\"\n\n[workspace.dependencies]\nutil = { path = \"libs/util\" }\n",
+    )?;
+    source_repo.write_file(
+        "crates/app/Cargo.toml",
+        &manifest("app", "\n[dependencies]\ncore = { path = \"../core\" }\n"),
+    )?;
+    source_repo.write_file("crates/app/src/main.rs", "fn main() { core::run() }\n")?;
+    source_repo.write_file(
+        "crates/core/Cargo.toml",
+        &manifest("core", "\n[dependencies]\nutil = { workspace = true }\n"),
+    )?;
+    source_repo.write_file("crates/core/src/lib.rs", "pub fn run() { util::help() }\n")?;
+    source_repo.write_file("libs/util/Cargo.toml", &manifest("util", ""))?;
+    source_repo.write_file("libs/util/src/lib.rs", "pub fn help() {}\n")?;
+    source_repo.write_file("crates/web/Cargo.toml", &manifest("web", ""))?;
+    source_repo.write_file("crates/web/src/lib.rs", "pub fn serve() {}\n")?;
+    source_repo.write_file("crates/web/assets/logo.svg", "<svg/>")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "README.md",
+        ],
+    )?;
+
+    // 2. Action
+    run_gitpartial(&clone_path, &["add-crate", "app", "-y"])?;
+
+    // 3. Verification: the crate and its transitive path dependencies are
+    // checked out, other members only as far as cargo reads them
+    assert!(file_exists(&clone_path, "crates/app/src/main.rs"));
+    assert!(file_exists(&clone_path, "crates/core/src/lib.rs"));
+    assert!(file_exists(&clone_path, "libs/util/src/lib.rs"));
+    assert!(file_exists(&clone_path, "Cargo.toml"));
+    assert!(file_exists(&clone_path, "crates/web/Cargo.toml"));
+    assert!(!file_exists(&clone_path, "crates/web/assets/logo.svg"));
+
+    // Cargo can load the workspace
+    let output = Command::new(env!("CARGO"))
+        .args([
+            "metadata",
+            "--no-deps",
+            "--offline",
+            "--format-version",
+            "1",
+        ])
+        .current_dir(&clone_path)
+        .output()?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_add_crate_unknown_name_fails() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Workspace")?;
+    source_repo.write_file("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n")?;
+    source_repo.write_file("crates/app/Cargo.toml", &manifest("app", ""))?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "README.md",
+        ],
+    )?;
+
+    let result = run_gitpartial(&clone_path, &["add-crate", "missing", "-y"]);
+
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("Unknown crate 'missing'"));
+    Ok(())
+}
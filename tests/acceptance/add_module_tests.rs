@@ -0,0 +1,127 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::{file_exists, TestRepo};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+fn clone_readme_only(source_repo: &TestRepo) -> Result<(tempfile::TempDir, PathBuf)> {
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        Path::new("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "README.md",
+        ],
+    )?;
+    Ok((clone_dir, clone_path))
+}
+
+#[test]
+fn test_add_module_adds_gradle_project_dependencies() -> Result<()> {
+    // 1. Setup: a Gradle multi-project build, cloned with only the README
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Shop")?;
+    source_repo.write_file(
+        "settings.gradle.kts",
+        "rootProject.name = \"shop\"\ninclude(\":app\", \":payments:core\", \":common\", \":admin\")\n",
+    )?;
+    source_repo.write_file("gradle.properties", "org.gradle.jvmargs=-Xmx2g\n")?;
+    source_repo.write_file(
+        "app/build.gradle.kts",
+        "dependencies {\n    implementation(project(\":payments:core\"))\n}\n",
+    )?;
+    source_repo.write_file("app/src/main/java/App.java", "class App {}\n")?;
+    source_repo.write_file(
+        "payments/core/build.gradle.kts",
+        "dependencies {\n    implementation(projects.common)\n}\n",
+    )?;
+    source_repo.write_file("payments/core/src/main/java/Core.java", "class Core {}\n")?;
+    source_repo.write_file("common/build.gradle.kts", "")?;
+    source_repo.write_file("common/src/main/java/Common.java", "class Common {}\n")?;
+    source_repo.write_file("admin/build.gradle.kts", "")?;
+    source_repo.write_file("admin/src/main/java/Admin.java", "class Admin {}\n")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let (_clone_dir, clone_path) = clone_readme_only(&source_repo)?;
+
+    // 2. Action
+    run_gitpartial(&clone_path, &["add-module", ":app", "-y"])?;
+
+    // 3. Verification: the project and its transitive project dependencies
+    // are checked out, other projects only as build scripts
+    assert!(file_exists(&clone_path, "app/src/main/java/App.java"));
+    assert!(file_exists(
+        &clone_path,
+        "payments/core/src/main/java/Core.java"
+    ));
+    assert!(file_exists(&clone_path, "common/src/main/java/Common.java"));
+    assert!(file_exists(&clone_path, "settings.gradle.kts"));
+    assert!(file_exists(&clone_path, "gradle.properties"));
+    assert!(file_exists(&clone_path, "admin/build.gradle.kts"));
+    assert!(!file_exists(&clone_path, "admin/src/main/java/Admin.java"));
+
+    Ok(())
+}
+
+#[test]
+fn test_add_module_adds_maven_module_dependencies() -> Result<()> {
+    // 1. Setup: a Maven reactor, cloned with only the README
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Shop")?;
+    source_repo.write_file(
+        "pom.xml",
+        "<project>\n  <artifactId>shop</artifactId>\n  <modules>\n    <module>app</module>\n    <module>common</module>\n    <module>admin</module>\n  </modules>\n</project>\n",
+    )?;
+    source_repo.write_file(
+        "app/pom.xml",
+        "<project>\n  <artifactId>shop-app</artifactId>\n  <dependencies>\n    <dependency>\n      <artifactId>shop-common</artifactId>\n    </dependency>\n  </dependencies>\n</project>\n",
+    )?;
+    source_repo.write_file("app/src/main/java/App.java", "class App {}\n")?;
+    source_repo.write_file(
+        "common/pom.xml",
+        "<project>\n  <artifactId>shop-common</artifactId>\n</project>\n",
+    )?;
+    source_repo.write_file("common/src/main/java/Common.java", "class Common {}\n")?;
+    source_repo.write_file(
+        "admin/pom.xml",
+        "<project>\n  <artifactId>shop-admin</artifactId>\n</project>\n",
+    )?;
+    source_repo.write_file("admin/src/main/java/Admin.java", "class Admin {}\n")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let (_clone_dir, clone_path) = clone_readme_only(&source_repo)?;
+
+    // 2. Action
+    run_gitpartial(&clone_path, &["add-module", "shop-app", "-y"])?;
+
+    // 3. Verification
+    assert!(file_exists(&clone_path, "app/src/main/java/App.java"));
+    assert!(file_exists(&clone_path, "common/src/main/java/Common.java"));
+    assert!(file_exists(&clone_path, "pom.xml"));
+    assert!(file_exists(&clone_path, "admin/pom.xml"));
+    assert!(!file_exists(&clone_path, "admin/src/main/java/Admin.java"));
+
+    Ok(())
+}
+
+#[test]
+fn test_add_module_unknown_name_fails() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Shop")?;
+    source_repo.write_file("settings.gradle", "include ':app'\n")?;
+    source_repo.write_file("app/build.gradle", "")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let (_clone_dir, clone_path) = clone_readme_only(&source_repo)?;
+
+    let result = run_gitpartial(&clone_path, &["add-module", ":missing", "-y"]);
+
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("Unknown module ':missing'"));
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::{file_exists, TestRepo};
+use anyhow::Result;
+use std::path::PathBuf;
+
+#[test]
+fn test_add_package_adds_workspace_dependencies() -> Result<()> {
+    // 1. Setup: a pnpm workspace, cloned with only the README
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Workspace")?;
+    source_repo.write_file("package.json", r#"{"name": "root", "private": true}"#)?;
+    source_repo.write_file(
+        "pnpm-workspace.yaml",
+        "packages:\n  - \"apps/*\"\n  - \"packages/*\"\n",
+    )?;
+    source_repo.write_file("pnpm-lock.yaml", "lockfileVersion: '9.0'\n")?;
+    source_repo.write_file(
+        "apps/web/package.json",
+        r#"{"name": "web", "dependencies": {"@acme/ui": "workspace:*", "react": "^18.0.0"}}"#,
+    )?;
+    source_repo.write_file("apps/web/src/index.ts", "import '@acme/ui';\n")?;
+    source_repo.write_file(
+        "packages/ui/package.json",
+        r#"{"name": "@acme/ui", "dependencies": {"@acme/tokens": "workspace:^"}}"#,
+    )?;
+    source_repo.write_file("packages/ui/src/index.ts", "export {};\n")?;
+    source_repo.write_file(
+        "packages/tokens/package.json",
+        r#"{"name": "@acme/tokens"}"#,
+    )?;
+    source_repo.write_file("packages/tokens/index.ts", "export {};\n")?;
+    source_repo.write_file("apps/admin/package.json", r#"{"name": "admin"}"#)?;
+    source_repo.write_file("apps/admin/src/index.ts", "export {};\n")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "README.md",
+        ],
+    )?;
+
+    // 2. Action
+    run_gitpartial(&clone_path, &["add-package", "web", "-y"])?;
+
+    // 3. Verification: the package and its transitive workspace
+    // dependencies are checked out, other packages only as manifests
+    assert!(file_exists(&clone_path, "apps/web/src/index.ts"));
+    assert!(file_exists(&clone_path, "packages/ui/src/index.ts"));
+    assert!(file_exists(&clone_path, "packages/tokens/index.ts"));
+    assert!(file_exists(&clone_path, "pnpm-lock.yaml"));
+    assert!(file_exists(&clone_path, "pnpm-workspace.yaml"));
+    assert!(file_exists(&clone_path, "apps/admin/package.json"));
+    assert!(!file_exists(&clone_path, "apps/admin/src/index.ts"));
+
+    Ok(())
+}
+
+#[test]
+fn test_add_package_unknown_name_fails() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Workspace")?;
+    source_repo.write_file(
+        "package.json",
+        r#"{"name": "root", "workspaces": ["apps/*"]}"#,
+    )?;
+    source_repo.write_file("apps/web/package.json", r#"{"name": "web"}"#)?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "README.md",
+        ],
+    )?;
+
+    let result = run_gitpartial(&clone_path, &["add-package", "missing", "-y"]);
+
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("Unknown package 'missing'"));
+    Ok(())
+}
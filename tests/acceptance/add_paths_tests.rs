@@ -1,7 +1,9 @@
+use crate::test_helpers::cli::run_gitpartial_raw;
 use crate::test_helpers::test_repo::TestRepo;
 use anyhow::{anyhow, Result};
 use git_partial::core::metadata::RepositoryMetadata; // Use crate name 'git_partial'
 use std::collections::HashSet;
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command; // Import the test helper
 
@@ -58,7 +60,13 @@ fn setup_partial_repo(initial_paths: &[&str]) -> Result<(TestRepo, tempfile::Tem
     source_repo.write_file("data/data.txt", "important data")?;
     source_repo.add_all()?;
     source_repo.commit("Initial commit")?;
-    let source_repo_url = source_repo.path_str()?;
+    TestRepo::run_git_command(
+        source_repo.path(),
+        &["config", "uploadpack.allowFilter", "true"],
+    )?;
+    // A plain local path is copied whole, which git-partial flags as an
+    // ignored filter and never asks to confirm downloads for
+    let source_repo_url = format!("file://{}", source_repo.path_str()?);
 
     // 2. Clone it partially
     let clone_dir = tempfile::tempdir()?;
@@ -116,6 +124,30 @@ fn test_add_paths() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_add_paths_keeps_local_changes() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo(&["src/**"])?;
+    fs::write(clone_path.join("src/core.rs"), "// Edited")?;
+    fs::remove_file(clone_path.join("src/utils.rs"))?;
+    // A file outside the paths that lost its skip-worktree bit
+    TestRepo::run_git_command(
+        &clone_path,
+        &["update-index", "--no-skip-worktree", "docs/guide.md"],
+    )?;
+
+    run_gitpartial(&clone_path, &["add-paths", "docs/**"])?;
+
+    // Only the new paths are checked out; the existing ones keep their changes
+    assert!(file_exists(&clone_path, "docs/guide.md"));
+    assert_eq!(
+        fs::read_to_string(clone_path.join("src/core.rs"))?,
+        "// Edited"
+    );
+    assert!(!file_exists(&clone_path, "src/utils.rs"));
+
+    Ok(())
+}
+
 #[test]
 fn test_add_duplicate_paths() -> Result<()> {
     // 1. Setup: Clone a repo with initial paths
@@ -153,3 +185,295 @@ fn test_add_duplicate_paths() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_add_paths_with_braces() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo(&["README.md"])?;
+
+    run_gitpartial(&clone_path, &["add-paths", "{docs,data}/**", "--yes"])?;
+
+    assert!(file_exists(&clone_path, "docs/guide.md"));
+    assert!(file_exists(&clone_path, "data/data.txt"));
+    assert!(!file_exists(&clone_path, "src/core.rs"));
+
+    // Metadata and the sparse checkout hold the expanded patterns
+    let metadata = RepositoryMetadata::load(&clone_path)?;
+    let expected: HashSet<String> = ["README.md", "docs/**", "data/**"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    assert_eq!(metadata.checked_out_paths, expected);
+    let sparse = std::fs::read_to_string(clone_path.join(".git/info/sparse-checkout"))?;
+    assert!(sparse.lines().any(|line| line == "docs/**"));
+    assert!(!sparse.contains('{'));
+
+    Ok(())
+}
+
+#[test]
+fn test_add_paths_with_regex() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo(&["README.md"])?;
+
+    run_gitpartial(
+        &clone_path,
+        &[
+            "add-paths",
+            "--regex",
+            r"(docs|src)/(guide|core)\.(md|rs)",
+            "--yes",
+        ],
+    )?;
+
+    assert!(file_exists(&clone_path, "docs/guide.md"));
+    assert!(file_exists(&clone_path, "src/core.rs"));
+    assert!(!file_exists(&clone_path, "src/utils.rs"));
+
+    // A directory matched whole is stored as a directory pattern, the rest file by file
+    let metadata = RepositoryMetadata::load(&clone_path)?;
+    let expected: HashSet<String> = ["README.md", "docs/**", "src/core.rs"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    assert_eq!(metadata.checked_out_paths, expected);
+
+    let output = run_gitpartial_raw(
+        &clone_path,
+        &["add-paths", "--regex", "nothing/.*", "--yes"],
+        "",
+    )?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("nothing/.*"));
+
+    Ok(())
+}
+
+#[test]
+fn test_add_paths_from_subdirectory() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo(&["src/core.rs"])?;
+
+    // Patterns are relative to the current directory, like git pathspecs
+    let src_dir = clone_path.join("src");
+    run_gitpartial(&src_dir, &["add-paths", "./utils.rs", ":/docs/**", "--yes"])?;
+
+    assert!(file_exists(&clone_path, "src/utils.rs"));
+    assert!(file_exists(&clone_path, "docs/guide.md"));
+    let metadata = RepositoryMetadata::load(&clone_path)?;
+    let expected: HashSet<String> = ["src/core.rs", "src/utils.rs", "docs/**"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    assert_eq!(metadata.checked_out_paths, expected);
+
+    let output = run_gitpartial_raw(&src_dir, &["add-paths", "../../elsewhere", "--yes"], "")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("outside the repository"));
+
+    Ok(())
+}
+
+#[test]
+fn test_add_paths_pattern_matches_nothing() -> Result<()> {
+    let initial_paths = ["README.md"];
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo(&initial_paths)?;
+
+    let output = run_gitpartial_raw(&clone_path, &["add-paths", "nonexistent/**"], "")?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("error: Pattern matched no files in the repository: nonexistent/**"));
+    assert!(stderr.contains("try: "));
+
+    // Metadata is left untouched
+    let metadata = RepositoryMetadata::load(&clone_path)?;
+    assert_eq!(metadata.checked_out_paths.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_add_paths_outside_partial_repo() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+
+    let output = run_gitpartial_raw(temp_dir.path(), &["add-paths", "docs/**"], "")?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("error: Not a git-partial repository"));
+    assert!(stderr.contains("try: git-partial clone"));
+
+    Ok(())
+}
+
+#[test]
+fn test_why_missing_offers_to_add_path() -> Result<()> {
+    // Setup
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Main Readme")?;
+    source_repo.write_file("docs/guide.md", "User guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let source_repo_url = source_repo.path_str()?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo_url,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "README.md",
+        ],
+    )?;
+
+    // Declining leaves the checkout unchanged
+    let output = run_gitpartial(clone_path, &["why-missing", "docs"])?;
+    assert!(output.contains("'docs' exists at HEAD but is excluded"));
+    assert!(output.contains("git-partial add-paths 'docs/**'"));
+    assert!(!clone_path.join("docs/guide.md").exists());
+
+    // Accepting adds the suggested pattern
+    run_gitpartial(clone_path, &["why-missing", "docs", "--yes"])?;
+    assert!(clone_path.join("docs/guide.md").exists());
+
+    let output = run_gitpartial(clone_path, &["why-missing", "nowhere/guide.md"])?;
+    assert!(output.contains("does not exist at HEAD"));
+    assert!(output.contains("  docs/guide.md"));
+
+    Ok(())
+}
+
+#[test]
+fn test_add_paths_confirms_large_download() -> Result<()> {
+    // 1. Setup: Clone a repo and lower the confirmation threshold below the new files
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo(&["README.md"])?;
+    TestRepo::run_git_command(
+        &clone_path,
+        &["config", "gitpartial.addConfirmThreshold", "10"],
+    )?;
+
+    // 2. Action: Add paths without answering the prompt
+    let output = run_gitpartial(&clone_path, &["add-paths", "docs/**", "data/**"])?;
+
+    // 3. Verification: the estimate is shown and nothing is added
+    assert!(output.contains("The new paths add 2 file(s), about 24 B to download."));
+    assert!(output.contains("Aborted."));
+    assert!(!file_exists(&clone_path, "docs/guide.md"));
+
+    // --yes skips the confirmation
+    run_gitpartial(&clone_path, &["add-paths", "--yes", "docs/**", "data/**"])?;
+    assert!(file_exists(&clone_path, "docs/guide.md"));
+    assert!(file_exists(&clone_path, "data/data.txt"));
+
+    Ok(())
+}
+
+#[test]
+fn test_add_paths_offline_queues_until_sync() -> Result<()> {
+    // 1. Setup: a blobless clone, so new paths need their blobs fetched
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Main Readme")?;
+    source_repo.write_file("docs/guide.md", "User guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    TestRepo::run_git_command(
+        source_repo.path(),
+        &["config", "uploadpack.allowFilter", "true"],
+    )?;
+    let source_repo_url = format!("file://{}", source_repo.path_str()?);
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo_url,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "README.md",
+        ],
+    )?;
+
+    // 2. Action: add a path while the remote is unreachable
+    let offline_path = source_repo.path().with_extension("offline");
+    std::fs::rename(source_repo.path(), &offline_path)?;
+    let output = run_gitpartial(&clone_path, &["add-paths", "--yes", "docs/**"]);
+    std::fs::rename(&offline_path, source_repo.path())?;
+    let output = output?;
+
+    // 3. Verification: patterns and metadata are updated, the checkout is queued
+    assert!(output.contains("Offline: queued 1 path(s)."));
+    assert!(!file_exists(&clone_path, "docs/guide.md"));
+    assert!(file_exists(&clone_path, ".gitpartial/pending.json"));
+    let metadata = RepositoryMetadata::load(&clone_path)?;
+    assert!(metadata.checked_out_paths.contains("docs/**"));
+
+    // 4. Back online, sync drains the queue
+    let output = run_gitpartial(&clone_path, &["sync"])?;
+    assert!(output.contains("Checked out docs/**"));
+    assert!(file_exists(&clone_path, "docs/guide.md"));
+    assert!(!file_exists(&clone_path, ".gitpartial/pending.json"));
+    assert!(run_gitpartial(&clone_path, &["sync"])?.contains("Nothing to sync."));
+
+    Ok(())
+}
+
+#[test]
+fn test_add_paths_includes_companions() -> Result<()> {
+    // 1. A monorepo whose services come with protos and configs
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Main Readme")?;
+    source_repo.write_file("services/payments/main.go", "package main")?;
+    source_repo.write_file("services/search/main.go", "package main")?;
+    source_repo.write_file("proto/payments/payments.proto", "syntax = \"proto3\";")?;
+    source_repo.write_file("proto/search/search.proto", "syntax = \"proto3\";")?;
+    source_repo.write_file("configs/payments.yaml", "port: 8080")?;
+    source_repo.write_file(
+        ".gitpartial-companions.toml",
+        "[[companion]]\nwhen = \"services/${X}/**\"\nadd = [\"proto/${X}/**\", \"configs/${X}.yaml\"]\n",
+    )?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "README.md",
+        ],
+    )?;
+
+    // 2. The companions come along
+    let output = run_gitpartial(&clone_path, &["add-paths", "services/payments/**"])?;
+    assert!(output
+        .contains("Adding companion paths: [\"proto/payments/**\", \"configs/payments.yaml\"]"));
+    assert!(file_exists(&clone_path, "proto/payments/payments.proto"));
+    assert!(file_exists(&clone_path, "configs/payments.yaml"));
+    let metadata = RepositoryMetadata::load(&clone_path)?;
+    assert!(metadata.checked_out_paths.contains("proto/payments/**"));
+
+    // 3. Companions that match nothing are skipped, and they can be turned off
+    run_gitpartial(
+        &clone_path,
+        &["add-paths", "services/search/**", "--no-companions"],
+    )?;
+    assert!(file_exists(&clone_path, "services/search/main.go"));
+    assert!(!file_exists(&clone_path, "proto/search/search.proto"));
+
+    run_gitpartial(
+        &clone_path,
+        &["remove-paths", "services/search/**", "--yes"],
+    )?;
+    let output = run_gitpartial(&clone_path, &["add-paths", "services/search/**"])?;
+    assert!(output.contains("Adding companion paths: [\"proto/search/**\"]"));
+    assert!(file_exists(&clone_path, "proto/search/search.proto"));
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::{file_exists, TestRepo};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// An Nx workspace where `web` depends on `ui` through its package.json and
+/// on `e2e-utils` implicitly, cloned with only the README
+fn setup_nx_workspace() -> Result<(TestRepo, tempfile::TempDir, PathBuf)> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Workspace")?;
+    source_repo.write_file("nx.json", "{}")?;
+    source_repo.write_file(
+        "apps/web/project.json",
+        r#"{"name": "web", "implicitDependencies": ["e2e-utils"]}"#,
+    )?;
+    source_repo.write_file(
+        "apps/web/package.json",
+        r#"{"name": "web", "dependencies": {"ui": "*"}}"#,
+    )?;
+    source_repo.write_file("apps/web/src/main.ts", "import 'ui';\n")?;
+    source_repo.write_file("libs/ui/project.json", r#"{"name": "ui"}"#)?;
+    source_repo.write_file("libs/ui/src/index.ts", "export {};\n")?;
+    source_repo.write_file("libs/e2e-utils/project.json", r#"{"name": "e2e-utils"}"#)?;
+    source_repo.write_file("libs/e2e-utils/src/index.ts", "export {};\n")?;
+    source_repo.write_file("apps/admin/project.json", r#"{"name": "admin"}"#)?;
+    source_repo.write_file("apps/admin/src/main.ts", "export {};\n")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        Path::new("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "README.md",
+        ],
+    )?;
+    Ok((source_repo, clone_dir, clone_path))
+}
+
+#[test]
+fn test_add_project_with_deps_follows_project_graph() -> Result<()> {
+    // 1. Setup
+    let (_source_repo, _clone_dir, clone_path) = setup_nx_workspace()?;
+
+    // 2. Action: Nx is not installed, so the graph comes from the manifests
+    run_gitpartial(&clone_path, &["add-project", "web", "--with-deps", "-y"])?;
+
+    // 3. Verification
+    assert!(file_exists(&clone_path, "apps/web/src/main.ts"));
+    assert!(file_exists(&clone_path, "libs/ui/src/index.ts"));
+    assert!(file_exists(&clone_path, "libs/e2e-utils/src/index.ts"));
+    assert!(file_exists(&clone_path, "nx.json"));
+    assert!(file_exists(&clone_path, "apps/admin/project.json"));
+    assert!(!file_exists(&clone_path, "apps/admin/src/main.ts"));
+
+    Ok(())
+}
+
+#[test]
+fn test_add_project_without_deps_adds_only_the_project() -> Result<()> {
+    // 1. Setup
+    let (_source_repo, _clone_dir, clone_path) = setup_nx_workspace()?;
+
+    // 2. Action
+    run_gitpartial(&clone_path, &["add-project", "web", "-y"])?;
+
+    // 3. Verification
+    assert!(file_exists(&clone_path, "apps/web/src/main.ts"));
+    assert!(!file_exists(&clone_path, "libs/ui/src/index.ts"));
+
+    Ok(())
+}
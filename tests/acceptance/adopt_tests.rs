@@ -0,0 +1,98 @@
+use crate::test_helpers::cli::{run_gitpartial, run_gitpartial_raw};
+use crate::test_helpers::test_repo::TestRepo;
+use anyhow::Result;
+use git_partial::core::metadata::RepositoryMetadata;
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::Command;
+
+// Runs git in a directory and fails on a non-zero exit
+fn git(
+    cwd: &Path,
+    args: &[&str],
+) -> Result<()> {
+    let output = Command::new("git").args(args).current_dir(cwd).output()?;
+    anyhow::ensure!(
+        output.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(())
+}
+
+fn setup_source_repo() -> Result<TestRepo> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Main Readme")?;
+    source_repo.write_file("src/app/main.rs", "fn main() {}")?;
+    source_repo.write_file("docs/guide.md", "User guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    // Allow blobless clones over file://
+    TestRepo::run_git_command(
+        source_repo.path(),
+        &["config", "uploadpack.allowFilter", "true"],
+    )?;
+    Ok(source_repo)
+}
+
+#[test]
+fn test_adopt_cone_mode_partial_clone() -> Result<()> {
+    let source_repo = setup_source_repo()?;
+    let source_repo_url = format!("file://{}", source_repo.path_str()?);
+
+    // A repository set up with stock git, as scalar would
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path();
+    git(
+        Path::new("."),
+        &[
+            "clone",
+            "--filter=blob:none",
+            "--sparse",
+            &source_repo_url,
+            &clone_path.to_string_lossy(),
+        ],
+    )?;
+    git(clone_path, &["sparse-checkout", "set", "--cone", "src/app"])?;
+
+    let output = run_gitpartial(clone_path, &["adopt"])?;
+    assert!(output.contains("Adopted partial clone repository with 2 path(s)"));
+
+    let metadata = RepositoryMetadata::load(clone_path)?;
+    let expected: HashSet<String> = ["README.md", "src/app/**"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    assert_eq!(metadata.checked_out_paths, expected);
+    assert_eq!(metadata.remote_url, source_repo_url);
+
+    // Adopting twice is refused
+    let output = run_gitpartial_raw(clone_path, &["adopt"], "")?;
+    assert!(!output.status.success());
+
+    Ok(())
+}
+
+#[test]
+fn test_adopt_requires_partial_clone() -> Result<()> {
+    let source_repo = setup_source_repo()?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path();
+    git(
+        Path::new("."),
+        &[
+            "clone",
+            "--sparse",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+        ],
+    )?;
+
+    let output = run_gitpartial_raw(clone_path, &["adopt"], "")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not a partial clone"));
+
+    Ok(())
+}
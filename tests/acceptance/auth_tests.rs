@@ -0,0 +1,77 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Runs git-partial with `bin_dir` in front of PATH and no token variables
+fn run_without_tokens(
+    bin_dir: &Path,
+    envs: &[(&str, &str)],
+    args: &[&str],
+) -> Result<Output> {
+    let bin_path = PathBuf::from(env!("CARGO_BIN_EXE_git-partial"));
+    let mut paths = vec![bin_dir.to_path_buf()];
+    paths.extend(std::env::split_paths(
+        &std::env::var_os("PATH").unwrap_or_default(),
+    ));
+    Ok(Command::new(bin_path)
+        .args(args)
+        .env("PATH", std::env::join_paths(paths)?)
+        .env_remove("GITHUB_TOKEN")
+        .env_remove("GH_TOKEN")
+        .env_remove("GITLAB_TOKEN")
+        .env_remove("AZURE_DEVOPS_PAT")
+        .env_remove("AZURE_DEVOPS_EXT_PAT")
+        .envs(envs.iter().copied())
+        .output()?)
+}
+
+#[cfg(unix)]
+#[test]
+fn test_auth_status_finds_cli_and_env_tokens() -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    // A fake gh that is logged in to github.com only
+    let bin_dir = tempfile::tempdir()?;
+    let gh = bin_dir.path().join("gh");
+    std::fs::write(
+        &gh,
+        "#!/bin/sh\n[ \"$*\" = \"auth token --hostname github.com\" ] && echo gho_fake && exit 0\nexit 1\n",
+    )?;
+    std::fs::set_permissions(&gh, std::fs::Permissions::from_mode(0o755))?;
+
+    let output = run_without_tokens(
+        bin_dir.path(),
+        &[],
+        &["auth", "status", "https://github.com/org/repo.git"],
+    )?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "github.com: token from gh CLI\n"
+    );
+
+    // Environment variables take precedence over the CLI
+    let output = run_without_tokens(
+        bin_dir.path(),
+        &[("GH_TOKEN", "ghp_env")],
+        &["auth", "status", "https://github.com/org/repo.git"],
+    )?;
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "github.com: token from $GH_TOKEN\n"
+    );
+
+    let output = run_without_tokens(
+        bin_dir.path(),
+        &[],
+        &[
+            "auth",
+            "status",
+            "https://dev.azure.com/org/project/_git/repo",
+        ],
+    )?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("dev.azure.com: no token found"));
+    assert!(stdout.contains("AZURE_DEVOPS_PAT"));
+    Ok(())
+}
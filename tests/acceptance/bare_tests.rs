@@ -0,0 +1,120 @@
+use crate::test_helpers::cli::{run_gitpartial, run_gitpartial_raw};
+use crate::test_helpers::test_repo::TestRepo;
+use anyhow::Result;
+use git_partial::core::metadata::RepositoryMetadata;
+use std::path::PathBuf;
+
+#[test]
+fn test_bare_partial_clone_reads_and_pulls_without_worktree() -> Result<()> {
+    // 1. Set up a source repository
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("app/settings.toml", "version = 1\n")?;
+    source_repo.write_file("src/main.rs", "fn main() {}\n")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    TestRepo::run_git_command(
+        source_repo.path(),
+        &["config", "uploadpack.allowFilter", "true"],
+    )?;
+
+    // 2. A bare clone has metadata but no working tree
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &format!("file://{}", source_repo.path_str()?),
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "app/**",
+            "--bare-partial",
+        ],
+    )?;
+    let metadata = RepositoryMetadata::load(clone_path)?;
+    assert!(metadata.bare);
+    assert!(clone_path.join("HEAD").exists());
+    assert!(!clone_path.join("app").exists());
+
+    // 3. Files are read from the object store
+    let tree = run_gitpartial(clone_path, &["tree"])?;
+    assert_eq!(tree, "app/\nsrc/\n");
+    let content = run_gitpartial(clone_path, &["show", "app/settings.toml"])?;
+    assert_eq!(content, "version = 1\n");
+
+    // 4. smart-pull moves the branch to the remote's and reports the changes
+    source_repo.write_file("app/settings.toml", "version = 2\n")?;
+    source_repo.add_all()?;
+    source_repo.commit("Bump version")?;
+    let output = run_gitpartial(clone_path, &["smart-pull"])?;
+    assert!(output.contains("app/**"), "{}", output);
+    let content = run_gitpartial(clone_path, &["show", "app/settings.toml"])?;
+    assert_eq!(content, "version = 2\n");
+    let head = TestRepo::run_git_command(source_repo.path(), &["rev-parse", "HEAD"])?;
+    let metadata = RepositoryMetadata::load(clone_path)?;
+    assert_eq!(
+        metadata.last_commit.as_deref(),
+        Some(String::from_utf8_lossy(&head.stdout).trim())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cat_tree_streams_files_from_bare_clone() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("app/settings.toml", "version = 1\n")?;
+    source_repo.write_file("data/2024/a.csv", "x,y\n1,2\n")?;
+    source_repo.write_file("data/2025/b.csv", "x,y\n3,4\n")?;
+    source_repo.write_file("src/main.rs", "fn main() {}\n")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    TestRepo::run_git_command(
+        source_repo.path(),
+        &["config", "uploadpack.allowFilter", "true"],
+    )?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &format!("file://{}", source_repo.path_str()?),
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "app/**",
+            "--bare-partial",
+        ],
+    )?;
+
+    // Directories and patterns expand to their files; each file comes once
+    let output = run_gitpartial_raw(
+        clone_path,
+        &["cat-tree"],
+        "data/**/*.csv\nsrc/main.rs\n\ndata/2024\nnope.txt\n",
+    )?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    let headers: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.len() > 40 && line.as_bytes()[40] == b' ')
+        .map(|line| &line[41..])
+        .collect();
+    assert_eq!(
+        headers,
+        vec!["8 data/2024/a.csv", "8 data/2025/b.csv", "13 src/main.rs"]
+    );
+    assert!(
+        stdout.contains(" 8 data/2024/a.csv\nx,y\n1,2\n\n"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.ends_with("fn main() {}\n\nnope.txt missing\n"),
+        "{}",
+        stdout
+    );
+
+    Ok(())
+}
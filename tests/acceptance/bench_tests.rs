@@ -0,0 +1,63 @@
+use crate::test_helpers::cli::{run_gitpartial, run_gitpartial_raw};
+use crate::test_helpers::test_repo::TestRepo;
+use anyhow::Result;
+use std::path::PathBuf;
+
+#[test]
+fn test_bench_reports_step_timings() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "// main")?;
+    source_repo.write_file("docs/guide.md", "Guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+
+    let output = run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "bench",
+            &source_repo.path_str()?,
+            "--paths",
+            "src/**",
+            "--add",
+            "docs/**",
+            "--runs",
+            "2",
+        ],
+    )?;
+
+    let report: serde_json::Value = serde_json::from_str(&output)?;
+    assert_eq!(report["runs"], 2);
+    assert_eq!(report["cone"], false);
+    let steps: Vec<&str> = report["steps"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|step| step["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(steps, ["clone", "add_paths", "smart_pull", "status"]);
+    for step in report["steps"].as_array().unwrap() {
+        assert_eq!(step["runs_ms"].as_array().unwrap().len(), 2);
+        assert!(step["min_ms"].as_u64() <= step["median_ms"].as_u64());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_bench_sparse_index_needs_cone() -> Result<()> {
+    let output = run_gitpartial_raw(
+        &PathBuf::from("."),
+        &[
+            "bench",
+            "https://example.com/repo.git",
+            "--paths",
+            "src/**",
+            "--sparse-index",
+        ],
+        "",
+    )?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--cone"));
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::TestRepo;
+use anyhow::Result;
+use git_partial::core::metadata::RepositoryMetadata;
+use std::path::PathBuf;
+
+#[test]
+fn test_bundle_round_trip_keeps_only_sparse_blobs() -> Result<()> {
+    // 1. Setup: a partial clone on a connected machine
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "fn main() {}")?;
+    source_repo.write_file("docs/guide.md", "User guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    source_repo.write_file("src/main.rs", "fn main() { println!(\"v2\"); }")?;
+    source_repo.add_all()?;
+    source_repo.commit("Update main")?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ],
+    )?;
+
+    // 2. Action: bundle the sparse paths, then clone the bundle elsewhere
+    let bundle_dir = tempfile::tempdir()?;
+    let bundle_path = bundle_dir.path().join("repo.bundle");
+    let output = run_gitpartial(
+        &clone_path,
+        &["bundle", "create", &bundle_path.to_string_lossy()],
+    )?;
+    assert!(output.contains("1 branch(es) and 1 path(s)"));
+
+    let offline_dir = tempfile::tempdir()?;
+    let offline_path = offline_dir.path().join("offline");
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &bundle_path.to_string_lossy(),
+            &offline_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ],
+    )?;
+
+    // 3. Verify: full history, the sparse files, and no blobs outside them
+    assert!(std::fs::read_to_string(offline_path.join("src/main.rs"))?.contains("v2"));
+    assert!(!offline_path.join("docs/guide.md").exists());
+    let log = TestRepo::run_git_command(&offline_path, &["log", "--oneline"])?;
+    assert_eq!(String::from_utf8_lossy(&log.stdout).lines().count(), 2);
+    let objects = TestRepo::run_git_command(
+        &offline_path,
+        &["rev-list", "--objects", "--all", "--missing=print"],
+    )?;
+    let missing = String::from_utf8_lossy(&objects.stdout)
+        .lines()
+        .filter(|line| line.starts_with('?'))
+        .count();
+    assert_eq!(missing, 1);
+    let status = TestRepo::run_git_command(
+        &offline_path,
+        &["status", "--porcelain", "--untracked-files=no"],
+    )?;
+    assert!(status.stdout.is_empty());
+
+    let metadata = RepositoryMetadata::load(&offline_path)?;
+    assert_eq!(
+        PathBuf::from(&metadata.remote_url),
+        bundle_path.canonicalize()?
+    );
+
+    Ok(())
+}
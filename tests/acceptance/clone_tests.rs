@@ -1,5 +1,6 @@
 use crate::test_helpers::test_repo::TestRepo;
 use anyhow::{anyhow, Result};
+use git_partial::core::metadata::RepositoryMetadata;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 // Import the test helper
@@ -189,3 +190,651 @@ fn test_partial_clone_with_glob_pattern() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_partial_clone_ci_mode() -> Result<()> {
+    // 1. Set up a source Git repository with some history
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "fn main() {}")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    source_repo.write_file("docs/guide.md", "User guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Add docs")?;
+    TestRepo::run_git_command(
+        source_repo.path(),
+        &["config", "uploadpack.allowFilter", "true"],
+    )?;
+    // Shallow options only apply to file:// URLs, not plain local paths
+    let source_repo_url = format!("file://{}", source_repo.path_str()?);
+
+    // 2. Clone in CI mode
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path();
+    let output = run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo_url,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+            "--ci",
+        ],
+    )?;
+
+    // 3. Verify the report and the shallow checkout
+    let report: serde_json::Value = serde_json::from_str(output.trim())?;
+    assert!(report["total_ms"].is_u64());
+    assert!(report["git_dir_bytes"].as_u64().unwrap() > 0);
+    assert_eq!(report["cache_used"], false);
+
+    assert!(file_exists(clone_path, "src/main.rs"));
+    assert!(!file_exists(clone_path, "docs/guide.md"));
+    let shallow = Command::new("git")
+        .args(["rev-parse", "--is-shallow-repository"])
+        .current_dir(clone_path)
+        .output()?;
+    assert_eq!(String::from_utf8_lossy(&shallow.stdout).trim(), "true");
+
+    Ok(())
+}
+
+#[test]
+fn test_clone_via_mirror() -> Result<()> {
+    // 1. Set up a source repository and a blobless mirror of it
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "fn main() {}")?;
+    source_repo.write_file("docs/guide.md", "User guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    TestRepo::run_git_command(
+        source_repo.path(),
+        &["config", "uploadpack.allowFilter", "true"],
+    )?;
+    let source_repo_url = format!("file://{}", source_repo.path_str()?);
+
+    let mirror_dir = tempfile::tempdir()?;
+    let mirror_path = mirror_dir.path().join("mirror.git");
+    let mirror_url = format!("file://{}", mirror_path.to_string_lossy());
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "mirror",
+            "create",
+            &source_repo_url,
+            &mirror_path.to_string_lossy(),
+        ],
+    )?;
+
+    // 2. Clone through the mirror
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo_url,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+            "--via",
+            &mirror_url,
+        ],
+    )?;
+
+    // 3. Blobs missing from the mirror come from the repository, pushes go there too
+    assert!(file_exists(clone_path, "src/main.rs"));
+    assert!(!file_exists(clone_path, "docs/guide.md"));
+    let git_config = |key: &str| -> Result<String> {
+        let output = Command::new("git")
+            .args(["config", key])
+            .current_dir(clone_path)
+            .output()?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    assert_eq!(git_config("remote.origin.url")?, mirror_url);
+    assert_eq!(git_config("remote.origin.pushurl")?, source_repo_url);
+    let metadata = RepositoryMetadata::load(clone_path)?;
+    assert_eq!(metadata.remote_url, source_repo_url);
+    assert_eq!(metadata.mirror_url, Some(mirror_url));
+
+    // 4. New history reaches the clone once the mirror is updated
+    source_repo.write_file("src/main.rs", "fn main() { println!(\"v2\"); }")?;
+    source_repo.add_all()?;
+    source_repo.commit("Update main")?;
+    run_gitpartial(
+        &PathBuf::from("."),
+        &["mirror", "update", &mirror_path.to_string_lossy()],
+    )?;
+    run_gitpartial(clone_path, &["smart-pull"])?;
+    assert!(std::fs::read_to_string(clone_path.join("src/main.rs"))?.contains("v2"));
+
+    Ok(())
+}
+
+#[test]
+fn test_clone_fails_early_without_filter_support() -> Result<()> {
+    // 1. A source served over file:// that does not allow filters
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "fn main() {}")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let source_repo_url = format!("file://{}", source_repo.path_str()?);
+
+    // 2. The clone is refused before anything is downloaded
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().join("clone");
+    let output = Command::new(env!("CARGO_BIN_EXE_git-partial"))
+        .args([
+            "clone",
+            &source_repo_url,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ])
+        .output()?;
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("does not support partial clone filters"));
+    assert!(stderr.contains("uploadpack.allowFilter"));
+    assert!(!clone_path.exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_clone_detects_ignored_filter() -> Result<()> {
+    // 1. A source with files in subdirectories and history
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "Readme")?;
+    source_repo.write_file("src/main.rs", "fn main() {}")?;
+    source_repo.write_file("docs/guide.md", "User guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    source_repo.write_file("src/main.rs", "fn main() { run(); }")?;
+    source_repo.add_all()?;
+    source_repo.commit("Update main")?;
+    TestRepo::run_git_command(
+        source_repo.path(),
+        &["config", "uploadpack.allowFilter", "true"],
+    )?;
+
+    // 2. A plain local path is copied whole, like a server ignoring --filter
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().join("clone");
+    let output = Command::new(env!("CARGO_BIN_EXE_git-partial"))
+        .args([
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ])
+        .output()?;
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("ignored the partial clone filter (blob:none)"));
+    assert!(RepositoryMetadata::load(&clone_path)?.filter_ignored);
+
+    // 3. Later commands know everything is local
+    let large_files = run_gitpartial(&clone_path, &["large-files"])?;
+    assert!(large_files.contains("every version of every file is local"));
+    let status = run_gitpartial(&clone_path, &["status"])?;
+    assert!(status.contains("Warning: the server ignored the partial clone filter"));
+    let added = run_gitpartial(&clone_path, &["add-paths", "docs/**"])?;
+    assert!(added.contains("already local"));
+    assert!(file_exists(&clone_path, "docs/guide.md"));
+
+    // 4. A server honoring the filter is not flagged
+    let filtered_path = clone_dir.path().join("filtered");
+    let output = Command::new(env!("CARGO_BIN_EXE_git-partial"))
+        .args([
+            "clone",
+            &format!("file://{}", source_repo.path_str()?),
+            &filtered_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ])
+        .output()?;
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("ignored the partial clone filter"));
+    assert!(!RepositoryMetadata::load(&filtered_path)?.filter_ignored);
+
+    Ok(())
+}
+
+#[test]
+fn test_clone_stores_ssh_options() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "fn main() {}")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+
+    // Clone with SSH options; the local source does not use them
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+            "--ssh-port",
+            "2222",
+            "--ssh-identity",
+            "/keys/id_internal",
+            "--ssh-jump",
+            "me@bastion.example.com",
+        ],
+    )?;
+
+    assert!(file_exists(clone_path, "src/main.rs"));
+    let output = Command::new("git")
+        .args(["config", "core.sshCommand"])
+        .current_dir(clone_path)
+        .output()?;
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "ssh -p 2222 -i '/keys/id_internal' -o IdentitiesOnly=yes -J 'me@bastion.example.com'"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_clone_stores_tls_client_certificate() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "fn main() {}")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+
+    // Only the local files are checked; the local source does not use them
+    let certs_dir = tempfile::tempdir()?;
+    std::fs::write(certs_dir.path().join("me.pem"), "certificate")?;
+    std::fs::write(certs_dir.path().join("me.key"), "key")?;
+
+    let missing_dir = tempfile::tempdir()?;
+    let output = Command::new(env!("CARGO_BIN_EXE_git-partial"))
+        .args([
+            "clone",
+            &source_repo.path_str()?,
+            &missing_dir.path().join("clone").to_string_lossy(),
+            "--paths",
+            "src/**",
+            "--tls-cert",
+            "missing.pem",
+        ])
+        .current_dir(certs_dir.path())
+        .output()?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("TLS client certificate 'missing.pem' not found"));
+    assert!(!missing_dir.path().join("clone").exists());
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path();
+    run_gitpartial(
+        certs_dir.path(),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+            "--tls-cert",
+            "me.pem",
+            "--tls-key",
+            "me.key",
+        ],
+    )?;
+
+    assert!(file_exists(clone_path, "src/main.rs"));
+    for (key, file) in [("http.sslCert", "me.pem"), ("http.sslKey", "me.key")] {
+        let output = Command::new("git")
+            .args(["config", key])
+            .current_dir(clone_path)
+            .output()?;
+        assert_eq!(
+            PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()),
+            std::path::absolute(certs_dir.path().join(file))?
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_clone_with_autocrlf_converts_checkout() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/notes.txt", "one\ntwo\n")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+            "--autocrlf",
+            "true",
+        ],
+    )?;
+
+    // The first checkout already converted, and the setting is kept
+    assert!(file_content_matches(
+        clone_path,
+        "src/notes.txt",
+        "one\r\ntwo\r\n"
+    )?);
+    let output = Command::new("git")
+        .args(["config", "core.autocrlf"])
+        .current_dir(clone_path)
+        .output()?;
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "true");
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_clone_with_symlink_outside_paths() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("shared/config.txt", "shared config")?;
+    source_repo.write_file("src/main.rs", "// main")?;
+    std::os::unix::fs::symlink(
+        "../shared/config.txt",
+        source_repo.path().join("src/config"),
+    )?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+
+    // Without --follow-symlinks the dangling link is reported
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().join("plain");
+    let output = Command::new(env!("CARGO_BIN_EXE_git-partial"))
+        .args([
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ])
+        .output()?;
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("src/config -> ../shared/config.txt"),
+        "stderr: {}",
+        stderr
+    );
+    assert!(!file_exists(&clone_path, "shared/config.txt"));
+
+    // With it, the target is checked out too
+    let followed_path = clone_dir.path().join("followed");
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &followed_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+            "--follow-symlinks",
+        ],
+    )?;
+    assert!(file_content_matches(
+        &followed_path,
+        "src/config",
+        "shared config"
+    )?);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_clone_reports_symlink_with_accented_name() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("shared/config.txt", "shared config")?;
+    source_repo.write_file("src/main.rs", "// main")?;
+    // Precomposed and decomposed accents, which git would quote without -z
+    std::os::unix::fs::symlink(
+        "../shared/config.txt",
+        source_repo.path().join("src/r\u{e9}glages"),
+    )?;
+    std::os::unix::fs::symlink(
+        "../shared/config.txt",
+        source_repo.path().join("src/pre\u{301}fe\u{301}rences"),
+    )?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().join("accented");
+    let output = Command::new(env!("CARGO_BIN_EXE_git-partial"))
+        .args([
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ])
+        .output()?;
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    for link in ["src/r\u{e9}glages", "src/pre\u{301}fe\u{301}rences"] {
+        assert!(
+            stderr.contains(&format!("{} -> ../shared/config.txt", link)),
+            "stderr: {}",
+            stderr
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_clone_with_fsmonitor_enables_untracked_cache() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "// main")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+            "--fsmonitor",
+        ],
+    )?;
+
+    let config = |key: &str| -> Result<String> {
+        let output = Command::new("git")
+            .args(["config", key])
+            .current_dir(clone_path)
+            .output()?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    assert_eq!(config("core.untrackedCache")?, "true");
+    let expected = if cfg!(any(target_os = "macos", windows)) {
+        "true"
+    } else {
+        ""
+    };
+    assert_eq!(config("core.fsmonitor")?, expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_clone_with_rewrite_records_original_url() -> Result<()> {
+    // 1. The repository, and a copy of it to fetch from instead
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "// origin")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let copy_repo = TestRepo::new()?;
+    copy_repo.write_file("src/main.rs", "// copy")?;
+    copy_repo.add_all()?;
+    copy_repo.commit("Initial commit")?;
+
+    // 2. Clone the repository through the copy
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+            "--rewrite",
+            &copy_repo.path_str()?,
+        ],
+    )?;
+
+    // 3. Objects came from the copy, but the repository is still the original
+    assert!(file_content_matches(clone_path, "src/main.rs", "// copy")?);
+    let metadata = RepositoryMetadata::load(clone_path)?;
+    assert_eq!(metadata.remote_url, source_repo.path_str()?);
+
+    let status = run_gitpartial(clone_path, &["status", "--no-fetch"])?;
+    assert!(status.contains(&format!("Fetching from: {}", copy_repo.path_str()?)));
+    assert!(!status.contains("Warning: origin"));
+
+    Ok(())
+}
+
+#[test]
+fn test_clone_manifest_and_sync_workspace() -> Result<()> {
+    // 1. Two repositories, and a manifest giving each its own paths
+    let app_repo = TestRepo::new()?;
+    app_repo.write_file("README.md", "# App")?;
+    app_repo.write_file("src/main.rs", "fn main() {}")?;
+    app_repo.write_file("assets/logo.png", "png")?;
+    app_repo.add_all()?;
+    app_repo.commit("Initial commit")?;
+    let tools_repo = TestRepo::new()?;
+    tools_repo.write_file("docs/guide.md", "# Guide")?;
+    tools_repo.write_file("bin/tool.sh", "echo tool")?;
+    tools_repo.add_all()?;
+    tools_repo.commit("Initial commit")?;
+
+    let manifest_dir = tempfile::tempdir()?;
+    let manifest_path = manifest_dir.path().join("manifest.toml");
+    let manifest = format!(
+        "[[repository]]\nurl = \"{}\"\npath = \"app\"\npaths = [\"src/**\"]\n\n\
+         [[repository]]\nurl = \"{}\"\npath = \"vendor/tools\"\npaths = [\"docs/**\"]\n",
+        app_repo.path_str()?,
+        tools_repo.path_str()?
+    );
+    std::fs::write(&manifest_path, &manifest)?;
+
+    // 2. Clone them all into a workspace
+    let workspace_dir = tempfile::tempdir()?;
+    let workspace = workspace_dir.path().join("workspace");
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            "--manifest",
+            &manifest_path.to_string_lossy(),
+            &workspace.to_string_lossy(),
+        ],
+    )?;
+
+    assert!(file_exists(&workspace, "app/src/main.rs"));
+    assert!(!file_exists(&workspace, "app/assets/logo.png"));
+    assert!(file_exists(&workspace, "vendor/tools/docs/guide.md"));
+    assert!(!file_exists(&workspace, "vendor/tools/bin/tool.sh"));
+    assert!(file_exists(&workspace, ".gitpartial-manifest.toml"));
+
+    // 3. A path and a repository added to the manifest arrive with sync
+    let lib_repo = TestRepo::new()?;
+    lib_repo.write_file("lib.rs", "pub fn lib() {}")?;
+    lib_repo.add_all()?;
+    lib_repo.commit("Initial commit")?;
+    let manifest = manifest.replace("[\"src/**\"]", "[\"src/**\", \"README.md\"]")
+        + &format!(
+            "\n[[repository]]\nurl = \"{}\"\npath = \"lib\"\n",
+            lib_repo.path_str()?
+        );
+    std::fs::write(workspace.join(".gitpartial-manifest.toml"), manifest)?;
+
+    let output = run_gitpartial(&workspace, &["sync"])?;
+    assert!(output.contains("app:"));
+    assert!(file_exists(&workspace, "app/README.md"));
+    assert!(!file_exists(&workspace, "app/assets/logo.png"));
+    assert!(file_exists(&workspace, "lib/lib.rs"));
+    let metadata = RepositoryMetadata::load(workspace.join("app"))?;
+    assert!(metadata.checked_out_paths.contains("README.md"));
+
+    Ok(())
+}
+
+#[test]
+fn test_clone_with_root_subdirectory() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("services/api/Cargo.toml", "[package]")?;
+    source_repo.write_file("services/api/src/main.rs", "fn main() {}")?;
+    source_repo.write_file("services/web/index.js", "// web")?;
+    source_repo.write_file("libs/common/lib.rs", "// common")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+
+    let parent = tempfile::tempdir()?;
+    let destination = parent.path().join("api");
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &destination.to_string_lossy(),
+            "--root",
+            "services/api",
+            "--paths",
+            "libs/common/**",
+        ],
+    )?;
+
+    // The subdirectory is the top level, the other paths sit under the prefix
+    assert!(file_exists(&destination, "Cargo.toml"));
+    assert!(file_exists(&destination, "src/main.rs"));
+    assert!(file_exists(&destination, ".monorepo/libs/common/lib.rs"));
+    assert!(!file_exists(&destination, ".monorepo/services"));
+    assert!(file_exists(
+        parent.path(),
+        ".api.git-partial/services/api/Cargo.toml"
+    ));
+    assert!(!file_exists(
+        parent.path(),
+        ".api.git-partial/services/web/index.js"
+    ));
+
+    // Commands work from the mounted directory, and the links stay out of git status
+    let status = Command::new("git")
+        .args(["status", "--porcelain"])
+        .current_dir(&destination)
+        .output()?;
+    assert!(!String::from_utf8_lossy(&status.stdout).contains(".monorepo"));
+    run_gitpartial(&destination, &["add-paths", "/services/web/**", "--yes"])?;
+    assert!(file_exists(&destination, ".monorepo/services/web/index.js"));
+
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use crate::test_helpers::test_repo::TestRepo;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Runs git-partial with its global config read from `config_home`
+fn run_with_config_home(
+    cwd: &Path,
+    config_home: &Path,
+    args: &[&str],
+) -> Result<Output> {
+    let bin_path = PathBuf::from(env!("CARGO_BIN_EXE_git-partial"));
+    Ok(Command::new(bin_path)
+        .args(args)
+        .current_dir(cwd)
+        .env("XDG_CONFIG_HOME", config_home)
+        .output()?)
+}
+
+#[test]
+fn test_config_global_set_get_unset() -> Result<()> {
+    let config_home = tempfile::tempdir()?;
+    let cwd = PathBuf::from(".");
+    let run = |args: &[&str]| run_with_config_home(&cwd, config_home.path(), args);
+
+    assert!(run(&["config", "--global", "jobs", "4"])?.status.success());
+    assert!(run(&["config", "--global", "filter", "blob:limit=1m"])?
+        .status
+        .success());
+
+    let content = std::fs::read_to_string(config_home.path().join("git-partial/config.toml"))?;
+    assert!(content.contains("jobs = 4"));
+    assert!(content.contains("filter = \"blob:limit=1m\""));
+
+    let output = run(&["config", "--global", "filter"])?;
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "blob:limit=1m\n");
+
+    // Unknown keys and mistyped values are rejected
+    assert!(!run(&["config", "--global", "colour", "never"])?
+        .status
+        .success());
+    assert!(!run(&["config", "--global", "jobs", "many"])?
+        .status
+        .success());
+
+    assert!(run(&["config", "--global", "--unset", "jobs"])?
+        .status
+        .success());
+    let output = run(&["config", "--global", "--list"])?;
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "filter = \"blob:limit=1m\"\n"
+    );
+
+    // Only the global scope exists
+    assert!(!run(&["config", "jobs"])?.status.success());
+
+    Ok(())
+}
+
+#[test]
+fn test_clone_uses_global_defaults() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Readme")?;
+    source_repo.write_file("src/main.rs", "fn main() {}")?;
+    source_repo.write_file("docs/guide.md", "guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    TestRepo::run_git_command(
+        source_repo.path(),
+        &["config", "uploadpack.allowFilter", "true"],
+    )?;
+    let source_repo_url = format!("file://{}", source_repo.path_str()?);
+
+    let config_home = tempfile::tempdir()?;
+    std::fs::create_dir_all(config_home.path().join("git-partial"))?;
+    std::fs::write(
+        config_home.path().join("git-partial/config.toml"),
+        "filter = \"blob:limit=1m\"\ncone = true\n",
+    )?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    let output = run_with_config_home(
+        &PathBuf::from("."),
+        config_home.path(),
+        &[
+            "clone",
+            &source_repo_url,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ],
+    )?;
+    assert!(output.status.success(), "{:?}", output);
+
+    let filter =
+        TestRepo::run_git_command(&clone_path, &["config", "remote.origin.partialclonefilter"])?;
+    // Git stores the limit in bytes
+    assert_eq!(
+        String::from_utf8_lossy(&filter.stdout).trim(),
+        "blob:limit=1048576"
+    );
+
+    // Whole directories are checked out in cone mode, which includes root files
+    let cone = TestRepo::run_git_command(&clone_path, &["config", "core.sparseCheckoutCone"])?;
+    assert_eq!(String::from_utf8_lossy(&cone.stdout).trim(), "true");
+    assert!(clone_path.join("src/main.rs").exists());
+    assert!(clone_path.join("README.md").exists());
+    assert!(!clone_path.join("docs/guide.md").exists());
+
+    // A malformed config file fails every command
+    std::fs::write(
+        config_home.path().join("git-partial/config.toml"),
+        "cone = \"sometimes\"\n",
+    )?;
+    let output = run_with_config_home(&clone_path, config_home.path(), &["list-paths"])?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("config.toml"));
+
+    Ok(())
+}
+
+#[test]
+fn test_clone_seeds_identity_profile() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "fn main() {}")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+
+    let config_home = tempfile::tempdir()?;
+    std::fs::create_dir_all(config_home.path().join("git-partial"))?;
+    std::fs::write(
+        config_home.path().join("git-partial/config.toml"),
+        "identity = \"ci\"\n\n[identities.ci]\nname = \"CI Bot\"\nemail = \"ci@example.com\"\nsign_commits = false\n",
+    )?;
+    // The user's own git config already names them
+    let gitconfig = config_home.path().join("gitconfig");
+    std::fs::write(&gitconfig, "[user]\n\tname = Me\n")?;
+
+    let clone = |destination: &Path, args: &[&str]| -> Result<Output> {
+        let bin_path = PathBuf::from(env!("CARGO_BIN_EXE_git-partial"));
+        let mut command = Command::new(bin_path);
+        command
+            .args([
+                "clone",
+                &source_repo.path_str()?,
+                &destination.to_string_lossy(),
+                "--paths",
+                "src/**",
+            ])
+            .args(args)
+            .env("XDG_CONFIG_HOME", config_home.path())
+            .env("GIT_CONFIG_GLOBAL", &gitconfig)
+            .env("GIT_CONFIG_NOSYSTEM", "1");
+        Ok(command.output()?)
+    };
+    let local_config = |repo: &Path, key: &str| -> Result<String> {
+        let output = TestRepo::run_git_command(repo, &["config", "--local", "--get", key]);
+        Ok(output
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .unwrap_or_default())
+    };
+
+    // The default profile fills in what the user's config leaves out
+    let clones = tempfile::tempdir()?;
+    let default = clones.path().join("default");
+    assert!(clone(&default, &[])?.status.success());
+    assert_eq!(local_config(&default, "user.name")?, "");
+    assert_eq!(local_config(&default, "user.email")?, "ci@example.com");
+    assert_eq!(local_config(&default, "commit.gpgsign")?, "false");
+
+    // A profile asked for by name wins over the user's config
+    let requested = clones.path().join("requested");
+    assert!(clone(&requested, &["--identity", "ci"])?.status.success());
+    assert_eq!(local_config(&requested, "user.name")?, "CI Bot");
+
+    // Unknown profiles fail before cloning
+    let unknown = clones.path().join("unknown");
+    let output = clone(&unknown, &["--identity", "release"])?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No identity profile 'release'"));
+    assert!(!unknown.exists());
+
+    Ok(())
+}
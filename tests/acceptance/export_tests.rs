@@ -0,0 +1,59 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::{file_exists, TestRepo};
+use anyhow::Result;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[test]
+fn test_export_script_reproduces_checkout() -> Result<()> {
+    // Setup
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Main Readme")?;
+    source_repo.write_file("src/main.rs", "fn main() {}")?;
+    source_repo.write_file("docs/guide.md", "User guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let source_repo_url = source_repo.path_str()?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().join("partial");
+    let clone_path_str = clone_path.to_string_lossy().to_string();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo_url,
+            &clone_path_str,
+            "--paths",
+            "README.md",
+            "src/**",
+        ],
+    )?;
+
+    // Action
+    let patterns = run_gitpartial(&clone_path, &["export", "--patterns"])?;
+    assert_eq!(patterns, "/README.md\nsrc/**\n");
+
+    let script = run_gitpartial(&clone_path, &["export", "--script"])?;
+    let script_path = clone_dir.path().join("reproduce.sh");
+    fs::write(&script_path, &script)?;
+
+    // Verification: running the script with plain git gives the same checkout
+    let output = Command::new("sh")
+        .arg(&script_path)
+        .arg("plain")
+        .current_dir(clone_dir.path())
+        .output()?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let plain_path = clone_dir.path().join("plain");
+    assert!(file_exists(&plain_path, "README.md"));
+    assert!(file_exists(&plain_path, "src/main.rs"));
+    assert!(!file_exists(&plain_path, "docs/guide.md"));
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use crate::test_helpers::cli::{run_gitpartial, run_gitpartial_raw, run_gitpartial_with_stdin};
+use crate::test_helpers::test_repo::{file_exists, TestRepo};
+use anyhow::Result;
+use git_partial::core::metadata::RepositoryMetadata;
+use std::path::PathBuf;
+
+fn setup_partial_repo() -> Result<(TestRepo, tempfile::TempDir, PathBuf)> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Main Readme")?;
+    source_repo.write_file("docs/user-guide.md", "User guide")?;
+    source_repo.write_file("services/payments/api.rs", "// api")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "README.md",
+        ],
+    )?;
+    Ok((source_repo, clone_dir, clone_path))
+}
+
+#[test]
+fn test_find_lists_matches() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo()?;
+
+    let output = run_gitpartial(&clone_path, &["find", "guide"])?;
+    assert_eq!(output.lines().next(), Some("docs/user-guide.md"));
+
+    let output = run_gitpartial(&clone_path, &["find", "readme"])?;
+    assert_eq!(output.lines().next(), Some("README.md  (checked out)"));
+
+    let output = run_gitpartial(&clone_path, &["find", "zzz"])?;
+    assert!(output.contains("No paths"));
+    Ok(())
+}
+
+#[test]
+fn test_find_add_containing_directory() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo()?;
+
+    run_gitpartial(&clone_path, &["find", "payapi", "--add", "--yes"])?;
+
+    assert!(file_exists(&clone_path, "services/payments/api.rs"));
+    let metadata = RepositoryMetadata::load(&clone_path)?;
+    assert!(metadata.checked_out_paths.contains("services/payments/**"));
+    Ok(())
+}
+
+#[test]
+fn test_complete_paths_from_tree_cache() -> Result<()> {
+    let (source_repo, _clone_dir, clone_path) = setup_partial_repo()?;
+
+    let output = run_gitpartial(&clone_path, &["complete-paths"])?;
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines, vec!["README.md", "docs/", "services/"]);
+    let output = run_gitpartial(&clone_path, &["complete-paths", "services/"])?;
+    assert_eq!(output.trim(), "services/payments/");
+    assert!(clone_path.join(".gitpartial/tree-cache").exists());
+
+    // Fetching new upstream commits invalidates the cache
+    source_repo.write_file("tools/lint.sh", "#!/bin/sh")?;
+    source_repo.add_all()?;
+    source_repo.commit("Add tools")?;
+    run_gitpartial(&clone_path, &["smart-pull"])?;
+
+    let output = run_gitpartial(&clone_path, &["complete-paths", "t"])?;
+    assert_eq!(output.trim(), "tools/");
+    Ok(())
+}
+
+#[test]
+fn test_open_adds_missing_path_before_launching_editor() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo()?;
+
+    // Declined: nothing is added and the editor does not run
+    let output = run_gitpartial_raw(
+        &clone_path,
+        &["open", "docs/user-guide.md", "--editor", "echo opened"],
+        "n\n",
+    )?;
+    assert!(!output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("opened "));
+    assert!(!file_exists(&clone_path, "docs/user-guide.md"));
+
+    let output = run_gitpartial_with_stdin(
+        &clone_path,
+        &["open", "docs/user-guide.md", "--editor", "echo opened"],
+        "y\n",
+    )?;
+    assert!(file_exists(&clone_path, "docs/user-guide.md"));
+    assert!(output.contains(&format!(
+        "opened {}",
+        clone_path.join("docs/user-guide.md").display()
+    )));
+
+    // Checked out paths and the repository open directly
+    let output = run_gitpartial(
+        &clone_path.join("docs"),
+        &["open", "user-guide.md", "--editor", "echo"],
+    )?;
+    assert!(output.ends_with("docs/user-guide.md\n"));
+    assert!(
+        !run_gitpartial_raw(&clone_path, &["open", "nowhere.md", "--editor", "echo"], "")?
+            .status
+            .success()
+    );
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::TestRepo;
+use anyhow::Result;
+use std::path::PathBuf;
+
+#[test]
+fn test_history_records_path_changes() -> Result<()> {
+    // 1. Setup: clone, then add and remove a path
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "fn main() {}")?;
+    source_repo.write_file("data/raw.csv", "a,b")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let source_repo_url = source_repo.path_str()?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo_url,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ],
+    )?;
+    run_gitpartial(&clone_path, &["add-paths", "data/**"])?;
+    run_gitpartial(&clone_path, &["remove-paths", "--yes", "data/**"])?;
+
+    // 2. Verification: every change is logged, newest first
+    let history = std::fs::read_to_string(clone_path.join(".gitpartial/history.jsonl"))?;
+    assert_eq!(history.lines().count(), 3);
+
+    let output = run_gitpartial(&clone_path, &["history"])?;
+    let commands: Vec<&str> = output
+        .lines()
+        .filter(|line| !line.starts_with("  "))
+        .map(|line| line.split("  ").nth(2).unwrap_or(""))
+        .collect();
+    assert_eq!(
+        commands,
+        vec![
+            "remove-paths --yes data/**",
+            "add-paths data/**",
+            &format!(
+                "clone {} {} --paths src/**",
+                source_repo_url,
+                clone_path.to_string_lossy()
+            )
+        ]
+    );
+    assert!(output.contains("  + data/**\n"));
+    assert!(output.contains("  - data/**\n"));
+
+    // 3. Filtering
+    let output = run_gitpartial(&clone_path, &["history", "--path", "data/raw.csv"])?;
+    assert!(output.contains("add-paths data/**"));
+    assert!(output.contains("remove-paths"));
+    assert!(!output.contains("clone"));
+
+    let output = run_gitpartial(&clone_path, &["history", "--command", "add-paths"])?;
+    assert_eq!(output.lines().count(), 2);
+
+    let output = run_gitpartial(&clone_path, &["history", "-n", "1"])?;
+    assert!(output.contains("remove-paths"));
+    assert!(!output.contains("add-paths"));
+
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use crate::test_helpers::cli::{run_gitpartial, run_gitpartial_raw};
+use crate::test_helpers::test_repo::TestRepo;
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+// Helper function to set up a partial clone with only src checked out
+fn setup_partial_repo() -> Result<(TestRepo, tempfile::TempDir, PathBuf)> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Main Readme")?;
+    source_repo.write_file("src/core.rs", "// Core lib")?;
+    source_repo.write_file("docs/guide.md", "User guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let source_repo_url = source_repo.path_str()?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    let clone_path_str = clone_path.to_string_lossy().to_string();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo_url,
+            &clone_path_str,
+            "--paths",
+            "src/**",
+        ],
+    )?;
+
+    Ok((source_repo, clone_dir, clone_path))
+}
+
+// Runs git with a committer identity, returning the raw output
+fn git(
+    cwd: &Path,
+    args: &[&str],
+) -> Result<Output> {
+    Ok(Command::new("git")
+        .args([
+            "-c",
+            "user.name=Test User",
+            "-c",
+            "user.email=test@example.com",
+        ])
+        .args(args)
+        .current_dir(cwd)
+        .output()?)
+}
+
+#[test]
+fn test_install_hooks_blocks_commit_outside_sparse() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo()?;
+
+    run_gitpartial(&clone_path, &["install-hooks", "--block"])?;
+
+    // A change outside the sparse patterns is rejected
+    fs::create_dir_all(clone_path.join("docs"))?;
+    fs::write(clone_path.join("docs/new.md"), "New doc")?;
+    git(&clone_path, &["add", "--sparse", "docs/new.md"])?;
+    let output = git(&clone_path, &["commit", "-m", "Add doc"])?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("docs/new.md"));
+
+    // A change inside the sparse patterns is accepted
+    git(&clone_path, &["restore", "--staged", "docs/new.md"])?;
+    fs::write(clone_path.join("src/new.rs"), "// New")?;
+    git(&clone_path, &["add", "src/new.rs"])?;
+    let output = git(&clone_path, &["commit", "-m", "Add source"])?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Non-ASCII paths, which git quotes in line output, are matched as written
+    fs::write(clone_path.join("src/café.rs"), "// Café")?;
+    git(&clone_path, &["add", "src/café.rs"])?;
+    let output = git(&clone_path, &["commit", "-m", "Add café"])?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_install_hooks_warns_by_default() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo()?;
+
+    run_gitpartial(&clone_path, &["install-hooks"])?;
+
+    fs::create_dir_all(clone_path.join("docs"))?;
+    fs::write(clone_path.join("docs/new.md"), "New doc")?;
+    git(&clone_path, &["add", "--sparse", "docs/new.md"])?;
+    let output = git(&clone_path, &["commit", "-m", "Add doc"])?;
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("warning: 1 staged path(s)"));
+
+    Ok(())
+}
+
+#[test]
+fn test_install_hooks_keeps_foreign_hook() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo()?;
+    let hook_path = clone_path.join(".git/hooks/pre-commit");
+    fs::write(&hook_path, "#!/bin/sh\nexit 0\n")?;
+
+    assert!(run_gitpartial(&clone_path, &["install-hooks"]).is_err());
+    assert_eq!(fs::read_to_string(&hook_path)?, "#!/bin/sh\nexit 0\n");
+
+    run_gitpartial(&clone_path, &["install-hooks", "--force"])?;
+    assert!(fs::read_to_string(&hook_path)?.contains("check-staged"));
+
+    Ok(())
+}
+
+#[test]
+fn test_sync_hooks_follow_plain_git() -> Result<()> {
+    let (source_repo, _clone_dir, clone_path) = setup_partial_repo()?;
+    run_gitpartial(&clone_path, &["install-hooks", "--sync"])?;
+    assert!(clone_path.join(".git/hooks/post-merge").exists());
+    assert!(clone_path.join(".git/hooks/post-checkout").exists());
+
+    // A raw git pull records the new HEAD
+    source_repo.write_file("src/core.rs", "// Core lib v2")?;
+    source_repo.add_all()?;
+    source_repo.commit("Update core")?;
+    let output = git(&clone_path, &["pull", "--ff-only", "--quiet"])?;
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("git-partial: updated metadata"));
+    let status = run_gitpartial(&clone_path, &["status", "--no-fetch"])?;
+    assert!(!status.contains("did not sync"), "{}", status);
+
+    // Raw sparse-checkout changes are taken over on the next checkout
+    assert!(git(&clone_path, &["sparse-checkout", "add", "docs/**"])?
+        .status
+        .success());
+    assert!(git(&clone_path, &["checkout", "--quiet", "HEAD"])?
+        .status
+        .success());
+    let paths = run_gitpartial(&clone_path, &["list-paths"])?;
+    assert!(paths.contains("docs/**"), "{}", paths);
+    assert!(paths.contains("src/**"), "{}", paths);
+
+    Ok(())
+}
+
+#[test]
+fn test_check_exit_codes() -> Result<()> {
+    let (source_repo, _clone_dir, clone_path) = setup_partial_repo()?;
+    let check = |args: &[&str]| -> Result<(Option<i32>, String)> {
+        let mut full = vec!["check"];
+        full.extend(args);
+        let output = run_gitpartial_raw(&clone_path, &full, "")?;
+        Ok((
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ))
+    };
+
+    assert_eq!(check(&[])?.0, Some(0));
+
+    // Too far behind the upstream once fetched
+    source_repo.write_file("src/core.rs", "// Core lib v2")?;
+    source_repo.add_all()?;
+    source_repo.commit("Update core")?;
+    assert_eq!(check(&[])?.0, Some(0));
+    let (code, stderr) = check(&["--fetch", "--max-behind", "0"])?;
+    assert_eq!(code, Some(8));
+    assert!(stderr.contains("1 commit(s) behind origin/main"));
+
+    // Staged paths outside the sparse paths
+    fs::create_dir_all(clone_path.join("docs"))?;
+    fs::write(clone_path.join("docs/new.md"), "New doc")?;
+    git(&clone_path, &["add", "--sparse", "docs/new.md"])?;
+    let (code, stderr) = check(&["--max-behind", "0"])?;
+    assert_eq!(code, Some(7));
+    assert!(stderr.contains("docs/new.md"));
+    git(&clone_path, &["restore", "--staged", "docs/new.md"])?;
+
+    // Sparse patterns changed behind git-partial's back
+    git(&clone_path, &["sparse-checkout", "add", "docs/"])?;
+    let (code, stderr) = check(&[])?;
+    assert_eq!(code, Some(6));
+    assert!(stderr.contains("inconsistent metadata"));
+
+    let outside = tempfile::tempdir()?;
+    let output = run_gitpartial_raw(outside.path(), &["check"], "")?;
+    assert_eq!(output.status.code(), Some(5));
+    Ok(())
+}
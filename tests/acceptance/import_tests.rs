@@ -0,0 +1,97 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::{file_exists, TestRepo};
+use anyhow::Result;
+use git_partial::core::metadata::RepositoryMetadata;
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::Command;
+
+// Runs git in a directory and fails on a non-zero exit
+fn git(
+    cwd: &Path,
+    args: &[&str],
+) -> Result<()> {
+    let output = Command::new("git").args(args).current_dir(cwd).output()?;
+    anyhow::ensure!(
+        output.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(())
+}
+
+// Clones the source repository with plain git sparse checkout
+fn plain_sparse_clone(
+    source_repo: &TestRepo,
+    sparse_args: &[&str],
+) -> Result<tempfile::TempDir> {
+    let clone_dir = tempfile::tempdir()?;
+    git(
+        Path::new("."),
+        &[
+            "clone",
+            "--sparse",
+            &source_repo.path_str()?,
+            &clone_dir.path().to_string_lossy(),
+        ],
+    )?;
+    let mut args = vec!["sparse-checkout", "set"];
+    args.extend_from_slice(sparse_args);
+    git(clone_dir.path(), &args)?;
+    Ok(clone_dir)
+}
+
+fn setup_source_repo() -> Result<TestRepo> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Main Readme")?;
+    source_repo.write_file("src/app/main.rs", "fn main() {}")?;
+    source_repo.write_file("src/lib/lib.rs", "// Lib")?;
+    source_repo.write_file("docs/guide.md", "User guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    Ok(source_repo)
+}
+
+#[test]
+fn test_import_cone_mode() -> Result<()> {
+    let source_repo = setup_source_repo()?;
+    let clone_dir = plain_sparse_clone(&source_repo, &["--cone", "src/app", "docs"])?;
+    let clone_path = clone_dir.path();
+
+    let output = run_gitpartial(clone_path, &["import"])?;
+    assert!(output.contains("Imported 3 path(s)"));
+
+    let metadata = RepositoryMetadata::load(clone_path)?;
+    let expected: HashSet<String> = ["README.md", "src/app/**", "docs/**"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    assert_eq!(metadata.checked_out_paths, expected);
+
+    // git-partial now manages the checkout
+    run_gitpartial(clone_path, &["add-paths", "src/lib/**"])?;
+    assert!(file_exists(clone_path, "src/lib/lib.rs"));
+    assert!(file_exists(clone_path, "src/app/main.rs"));
+    assert!(file_exists(clone_path, "README.md"));
+
+    Ok(())
+}
+
+#[test]
+fn test_import_non_cone_mode() -> Result<()> {
+    let source_repo = setup_source_repo()?;
+    let clone_dir = plain_sparse_clone(&source_repo, &["--no-cone", "/README.md", "docs/**"])?;
+    let clone_path = clone_dir.path();
+
+    run_gitpartial(clone_path, &["import"])?;
+
+    let metadata = RepositoryMetadata::load(clone_path)?;
+    let expected: HashSet<String> = ["/README.md", "docs/**"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    assert_eq!(metadata.checked_out_paths, expected);
+
+    Ok(())
+}
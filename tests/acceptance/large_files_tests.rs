@@ -0,0 +1,126 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::TestRepo;
+use anyhow::Result;
+use git_partial::core::metadata::RepositoryMetadata;
+use std::path::PathBuf;
+
+#[test]
+fn test_clone_with_blob_limit_lists_large_files() -> Result<()> {
+    // 1. Two versions of a large file, and a small one
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("assets/big.bin", &"a".repeat(4096))?;
+    source_repo.write_file("assets/small.txt", "small")?;
+    source_repo.add_all()?;
+    source_repo.commit("Add assets")?;
+    source_repo.write_file("assets/big.bin", &"b".repeat(4096))?;
+    source_repo.add_all()?;
+    source_repo.commit("Update big file")?;
+    TestRepo::run_git_command(
+        source_repo.path(),
+        &["config", "uploadpack.allowFilter", "true"],
+    )?;
+
+    // 2. Clone without blobs over 1 KiB; checking out fetches the current version
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &format!("file://{}", source_repo.path_str()?),
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "assets/**",
+            "--filter",
+            "blob:limit=1k",
+        ],
+    )?;
+    let metadata = RepositoryMetadata::load(clone_path)?;
+    assert_eq!(metadata.filter.as_deref(), Some("blob:limit=1k"));
+
+    // 3. The first version of the large file was left on the remote
+    let output = run_gitpartial(clone_path, &["large-files"])?;
+    assert!(output.starts_with(
+        "Versions over 1.0 KiB under the sparse paths left on the remote (filter blob:limit=1k):\n  assets/big.bin  "
+    ), "{}", output);
+    assert!(!output.contains("small.txt"));
+
+    // 4. Fetching it makes every version local
+    let output = run_gitpartial(clone_path, &["large-files", "--fetch", "assets/big.bin"])?;
+    assert_eq!(output, "Fetched 1 version(s).\n");
+    let output = run_gitpartial(clone_path, &["large-files"])?;
+    assert_eq!(
+        output,
+        "Every version of the files under the sparse paths is local (filter blob:limit=1k).\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_clone_rejects_invalid_filter() -> Result<()> {
+    let clone_dir = tempfile::tempdir()?;
+    let result = run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            "https://example.com/repo.git",
+            &clone_dir.path().join("clone").to_string_lossy(),
+            "--paths",
+            "src/**",
+            "--filter",
+            "blob:limit=huge",
+        ],
+    );
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("blob:limit=<size>"));
+
+    Ok(())
+}
+
+#[test]
+fn test_blame_fetches_missing_versions() -> Result<()> {
+    // 1. A file outside the sparse paths, changed over two commits
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "readme")?;
+    source_repo.write_file("src/app.txt", "first\n")?;
+    source_repo.add_all()?;
+    source_repo.commit("Add app")?;
+    source_repo.write_file("src/app.txt", "first\nsecond\n")?;
+    source_repo.add_all()?;
+    source_repo.commit("Extend app")?;
+    TestRepo::run_git_command(
+        source_repo.path(),
+        &["config", "uploadpack.allowFilter", "true"],
+    )?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &format!("file://{}", source_repo.path_str()?),
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "README.md",
+        ],
+    )?;
+
+    // 2. Blame reads it at HEAD, each line from the commit that added it
+    let output = run_gitpartial(clone_path, &["blame", "src/app.txt"])?;
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 2, "{}", output);
+    assert!(lines[0].ends_with(") first"), "{}", output);
+    assert!(lines[1].ends_with(") second"), "{}", output);
+    assert_ne!(lines[0][..8], lines[1][..8]);
+
+    // 3. Options after `--` go to git blame
+    let output = run_gitpartial(clone_path, &["blame", "src/app.txt", "--", "-L", "2,2"])?;
+    assert_eq!(output.lines().count(), 1);
+    assert!(output.trim_end().ends_with(") second"), "{}", output);
+
+    Ok(())
+}
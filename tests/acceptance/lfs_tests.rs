@@ -0,0 +1,86 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::TestRepo;
+use anyhow::Result;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[test]
+fn test_lfs_fetch_include_follows_sparse_paths() -> Result<()> {
+    // Setup: a repository tracking binaries with LFS (pointer files are enough here)
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file(
+        ".gitattributes",
+        "*.bin filter=lfs diff=lfs merge=lfs -text\n",
+    )?;
+    source_repo.write_file("game/level.bin", "pointer")?;
+    source_repo.write_file("game/main.rs", "fn main() {}")?;
+    source_repo.write_file("assets/intro.bin", "pointer")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let source_repo_url = source_repo.path_str()?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    let clone_path_str = clone_path.to_string_lossy().to_string();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo_url,
+            &clone_path_str,
+            "--paths",
+            "game/**",
+        ],
+    )?;
+
+    let fetch_include = Command::new("git")
+        .args(["config", "lfs.fetchinclude"])
+        .current_dir(&clone_path)
+        .output()?;
+    assert_eq!(
+        String::from_utf8_lossy(&fetch_include.stdout).trim(),
+        "game/**"
+    );
+
+    let output = run_gitpartial(&clone_path, &["lfs-status"])?;
+    assert!(output.contains("Fetched: 1 object(s)"));
+    assert!(output.contains("Skipped: 1 object(s)"));
+    assert!(output.contains("  assets/intro.bin"));
+
+    Ok(())
+}
+
+#[test]
+fn test_lfs_status_without_lfs() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Readme")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let source_repo_url = source_repo.path_str()?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    let clone_path_str = clone_path.to_string_lossy().to_string();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo_url,
+            &clone_path_str,
+            "--paths",
+            "README.md",
+        ],
+    )?;
+
+    let output = run_gitpartial(&clone_path, &["lfs-status"])?;
+    assert!(output.contains("does not use Git LFS"));
+
+    // The provisional clone-time setting is removed again
+    let fetch_include = Command::new("git")
+        .args(["config", "lfs.fetchinclude"])
+        .current_dir(&clone_path)
+        .output()?;
+    assert!(!fetch_include.status.success());
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use crate::test_helpers::cli::{run_gitpartial, run_gitpartial_raw};
+use crate::test_helpers::test_repo::{file_exists, TestRepo};
+use anyhow::Result;
+use std::fs;
+use std::path::PathBuf;
+
+fn setup_partial_repo() -> Result<(TestRepo, tempfile::TempDir, PathBuf)> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Main Readme")?;
+    source_repo.write_file("docs/guide.md", "User guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let source_repo_url = source_repo.path_str()?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    let clone_path_str = clone_path.to_string_lossy().to_string();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo_url,
+            &clone_path_str,
+            "--paths",
+            "README.md",
+        ],
+    )?;
+
+    Ok((source_repo, clone_dir, clone_path))
+}
+
+#[test]
+fn test_no_wait_fails_while_locked() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo()?;
+    // The test process is alive, so the lock is not stale
+    let lock_path = clone_path.join(".gitpartial").join("lock");
+    fs::write(&lock_path, std::process::id().to_string())?;
+
+    let output = run_gitpartial_raw(
+        &clone_path,
+        &["add-paths", "docs/**", "--yes", "--no-wait"],
+        "",
+    )?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("locked by git-partial"),
+        "stderr: {}",
+        stderr
+    );
+    assert!(!file_exists(&clone_path, "docs/guide.md"));
+
+    // Read-only commands do not take the lock
+    run_gitpartial(&clone_path, &["list-paths"])?;
+
+    fs::remove_file(&lock_path)?;
+    run_gitpartial(&clone_path, &["add-paths", "docs/**", "--yes", "--no-wait"])?;
+    assert!(file_exists(&clone_path, "docs/guide.md"));
+    assert!(!lock_path.exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_waits_for_lock_release() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo()?;
+    let lock_path = clone_path.join(".gitpartial").join("lock");
+    fs::write(&lock_path, std::process::id().to_string())?;
+
+    let release = {
+        let lock_path = lock_path.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            fs::remove_file(lock_path)
+        })
+    };
+    let output = run_gitpartial_raw(&clone_path, &["add-paths", "docs/**", "--yes"], "")?;
+    release.join().unwrap()?;
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Waiting for git-partial"));
+    assert!(file_exists(&clone_path, "docs/guide.md"));
+
+    Ok(())
+}
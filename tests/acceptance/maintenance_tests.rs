@@ -0,0 +1,142 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::{file_exists, TestRepo};
+use anyhow::Result;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn setup_clone() -> Result<(TestRepo, tempfile::TempDir, PathBuf)> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "// main")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().join("clone");
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ],
+    )?;
+    Ok((source_repo, clone_dir, clone_path))
+}
+
+#[test]
+fn test_clone_writes_commit_graph() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_clone()?;
+
+    assert!(file_exists(&clone_path, ".git/objects/info/commit-graph"));
+    let output = TestRepo::run_git_command(&clone_path, &["config", "fetch.writeCommitGraph"])?;
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "true");
+
+    let output = run_gitpartial(&clone_path, &["maintenance"])?;
+    assert_eq!(output, "Ran prefetch, commit-graph, loose-objects.\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_maintenance_register() -> Result<()> {
+    let (_source_repo, clone_dir, clone_path) = setup_clone()?;
+    // Registering edits the global git config, so give git a scratch home
+    let home = clone_dir.path().join("home");
+    std::fs::create_dir(&home)?;
+    let gitpartial = |args: &[&str]| -> Result<()> {
+        let status = Command::new(env!("CARGO_BIN_EXE_git-partial"))
+            .args(args)
+            .current_dir(&clone_path)
+            .env("HOME", &home)
+            .env("XDG_CONFIG_HOME", home.join(".config"))
+            .status()?;
+        assert!(status.success());
+        Ok(())
+    };
+    let registered = || -> Result<String> {
+        let output = Command::new("git")
+            .args(["config", "--global", "--get-all", "maintenance.repo"])
+            .env("HOME", &home)
+            .env("XDG_CONFIG_HOME", home.join(".config"))
+            .output()?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    gitpartial(&["maintenance", "--register"])?;
+    let repo = registered()?;
+    assert!(repo.ends_with("clone"), "maintenance.repo: {}", repo);
+    let output = TestRepo::run_git_command(&clone_path, &["config", "maintenance.strategy"])?;
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "incremental"
+    );
+
+    gitpartial(&["maintenance", "--unregister"])?;
+    assert_eq!(registered()?, "");
+
+    Ok(())
+}
+
+#[test]
+fn test_maintenance_prunes_removed_paths() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "// main")?;
+    source_repo.write_file("assets/logo.svg", "<svg/>")?;
+    source_repo.write_file("assets/shared.txt", "shared")?;
+    source_repo.write_file("src/shared.txt", "shared")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    TestRepo::run_git_command(
+        source_repo.path(),
+        &["config", "uploadpack.allowFilter", "true"],
+    )?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().join("clone");
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &format!("file://{}", source_repo.path_str()?),
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+            "assets/**",
+        ],
+    )?;
+    run_gitpartial(&clone_path, &["remove-paths", "--yes", "assets/**"])?;
+    let logo = String::from_utf8(
+        TestRepo::run_git_command(&clone_path, &["rev-parse", "HEAD:assets/logo.svg"])?.stdout,
+    )?;
+
+    // 1. Within the retention period nothing is pruned
+    TestRepo::run_git_command(&clone_path, &["config", "gitpartial.pruneAfter", "14d"])?;
+    let output = run_gitpartial(&clone_path, &["maintenance"])?;
+    assert_eq!(output, "Ran prefetch, commit-graph, loose-objects.\n");
+
+    // 2. Past it, the removed paths' blobs go, except those the checkout shares
+    TestRepo::run_git_command(&clone_path, &["config", "gitpartial.pruneAfter", "0s"])?;
+    let output = run_gitpartial(&clone_path, &["maintenance"])?;
+    assert!(
+        output.contains("Pruned 1 blob(s) of removed paths assets/**, freeing "),
+        "{}",
+        output
+    );
+    assert!(file_exists(&clone_path, "src/shared.txt"));
+    let missing = String::from_utf8(
+        TestRepo::run_git_command(
+            &clone_path,
+            &["rev-list", "--objects", "--missing=print", "HEAD"],
+        )?
+        .stdout,
+    )?;
+    assert!(
+        missing.contains(&format!("?{}", logo.trim())),
+        "{}",
+        missing
+    );
+
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::TestRepo;
+use anyhow::Result;
+use std::path::PathBuf;
+
+#[test]
+fn test_materialize_makes_history_available_offline() -> Result<()> {
+    // 1. Two versions of a source file
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "fn main() {}\n")?;
+    source_repo.write_file("docs/guide.md", "guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    source_repo.write_file("src/main.rs", "fn main() { run(); }\n")?;
+    source_repo.add_all()?;
+    source_repo.commit("Call run")?;
+    TestRepo::run_git_command(
+        source_repo.path(),
+        &["config", "uploadpack.allowFilter", "true"],
+    )?;
+
+    // 2. A blobless clone only has the checked-out version
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &format!("file://{}", source_repo.path_str()?),
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ],
+    )?;
+    let output = run_gitpartial(clone_path, &["materialize", "--head"])?;
+    assert_eq!(
+        output,
+        "Every version at HEAD of the matching files is already local.\n"
+    );
+
+    // 3. Materializing the history fetches the older version only
+    let output = run_gitpartial(clone_path, &["materialize"])?;
+    assert_eq!(output, "Fetched 1 blob(s) in history for src/**.\n");
+
+    // 4. With the remote gone, the history can still be diffed
+    std::fs::remove_dir_all(source_repo.path())?;
+    let log = TestRepo::run_git_command(clone_path, &["log", "-p", "--", "src/main.rs"])?;
+    let log = String::from_utf8_lossy(&log.stdout);
+    assert!(log.contains("-fn main() {}"), "{}", log);
+
+    // 5. Other paths are left on the remote
+    let output = run_gitpartial(clone_path, &["materialize", "--head", "docs/**"]);
+    assert!(output.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_dematerialize_drops_blobs_and_refetches_them() -> Result<()> {
+    // 1. A clone with every version of src/ local
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "fn main() {}\n")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    source_repo.write_file("src/main.rs", "fn main() { run(); }\n")?;
+    source_repo.add_all()?;
+    source_repo.commit("Call run")?;
+    TestRepo::run_git_command(
+        source_repo.path(),
+        &["config", "uploadpack.allowFilter", "true"],
+    )?;
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &format!("file://{}", source_repo.path_str()?),
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ],
+    )?;
+    run_gitpartial(clone_path, &["materialize"])?;
+
+    // 2. Both versions are dropped, the checkout is untouched
+    let output = run_gitpartial(clone_path, &["dematerialize", "src/**"])?;
+    assert!(
+        output.starts_with("Dropped 2 blob(s) of src/**, freeing "),
+        "{}",
+        output
+    );
+    let missing = TestRepo::run_git_command(
+        clone_path,
+        &["rev-list", "--objects", "--missing=print", "--all"],
+    )?;
+    let missing = String::from_utf8_lossy(&missing.stdout);
+    assert_eq!(
+        missing.lines().filter(|line| line.starts_with('?')).count(),
+        2
+    );
+    assert!(clone_path.join("src/main.rs").exists());
+    let output = run_gitpartial(clone_path, &["dematerialize", "src/**"])?;
+    assert_eq!(output, "No local blobs of src/** to drop.\n");
+
+    // 3. Git fetches them again on demand
+    let log = TestRepo::run_git_command(clone_path, &["log", "-p", "--", "src/main.rs"])?;
+    let log = String::from_utf8_lossy(&log.stdout);
+    assert!(log.contains("-fn main() {}"), "{}", log);
+
+    Ok(())
+}
+
+#[test]
+fn test_top_lists_largest_local_paths() -> Result<()> {
+    // Data that does not compress, so its packed size stays large
+    let mut state: u32 = 1;
+    let noise: String = (0..40_000)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            char::from(b'a' + (state >> 16) as u8 % 26)
+        })
+        .collect();
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("assets/images/big.dat", &noise)?;
+    source_repo.write_file("src/main.rs", "fn main() {}\n")?;
+    source_repo.write_file("docs/guide.md", "guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    TestRepo::run_git_command(
+        source_repo.path(),
+        &["config", "uploadpack.allowFilter", "true"],
+    )?;
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &format!("file://{}", source_repo.path_str()?),
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+            "assets/**",
+        ],
+    )?;
+
+    let output = run_gitpartial(clone_path, &["top", "-n", "1", "--depth", "1"])?;
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[0], "Largest files:");
+    assert!(lines[1].contains("39.1 KiB checked out"), "{}", output);
+    assert!(lines[1].ends_with("  assets/images/big.dat"), "{}", output);
+    assert_eq!(lines[2], "Largest directories:");
+    assert!(lines[3].ends_with("  assets/"), "{}", output);
+    assert!(lines[4].starts_with("Total: "));
+    // Files outside the sparse paths were never downloaded
+    assert!(!output.contains("docs/"));
+    Ok(())
+}
@@ -1,6 +1,39 @@
 // Acceptance tests for GitPartial
 
+pub mod add_crate_tests;
+pub mod add_module_tests;
+pub mod add_package_tests;
 pub mod add_paths_tests;
+pub mod add_project_tests;
+pub mod adopt_tests;
+pub mod auth_tests;
+pub mod bare_tests;
+pub mod bench_tests;
+pub mod bundle_tests;
 pub mod clone_tests;
+pub mod config_tests;
+pub mod export_tests;
+pub mod find_tests;
+pub mod history_tests;
+pub mod hooks_tests;
+pub mod import_tests;
+pub mod large_files_tests;
+pub mod lfs_tests;
+pub mod lock_tests;
+pub mod maintenance_tests;
+pub mod materialize_tests;
+pub mod plugins_tests;
+pub mod policy_tests;
+pub mod profile_tests;
+pub mod projects_tests;
+pub mod remove_paths_tests;
+pub mod replay_tests;
+pub mod savings_tests;
+pub mod serve_cache_tests;
+pub mod serve_http_tests;
 pub mod smart_pull_tests;
 pub mod status_tests;
+pub mod targets_tests;
+pub mod tree_tests;
+pub mod watch_tests;
+pub mod worktree_tests;
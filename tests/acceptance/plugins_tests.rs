@@ -0,0 +1,85 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::TestRepo;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Runs git-partial with `plugin_dir` in front of PATH
+fn run_with_plugins(
+    cwd: &Path,
+    plugin_dir: &Path,
+    args: &[&str],
+) -> Result<Output> {
+    let bin_path = PathBuf::from(env!("CARGO_BIN_EXE_git-partial"));
+    let mut paths = vec![plugin_dir.to_path_buf()];
+    paths.extend(std::env::split_paths(
+        &std::env::var_os("PATH").unwrap_or_default(),
+    ));
+    Ok(Command::new(bin_path)
+        .args(args)
+        .current_dir(cwd)
+        .env("PATH", std::env::join_paths(paths)?)
+        .output()?)
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unknown_subcommand_runs_plugin() -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Main Readme")?;
+    source_repo.write_file("src/lib.rs", "// lib")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ],
+    )?;
+
+    // The plugin echoes its arguments, environment and stdin, then fails
+    let plugin_dir = tempfile::tempdir()?;
+    let plugin = plugin_dir.path().join("git-partial-hello");
+    std::fs::write(
+        &plugin,
+        "#!/bin/sh\necho \"args: $*\"\necho \"root: $GIT_PARTIAL_ROOT\"\ncat\nexit 7\n",
+    )?;
+    std::fs::set_permissions(&plugin, std::fs::Permissions::from_mode(0o755))?;
+
+    // Run from a subdirectory; the root is still found
+    let output = run_with_plugins(
+        &clone_path.join("src"),
+        plugin_dir.path(),
+        &["hello", "--loud", "world"],
+    )?;
+    assert_eq!(output.status.code(), Some(7));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("args: --loud world"), "stdout: {}", stdout);
+    assert!(stdout.contains(&format!("root: {}", clone_path.display())));
+    let state: serde_json::Value = serde_json::from_str(stdout.lines().last().unwrap())?;
+    assert_eq!(
+        state["metadata"]["checked_out_paths"],
+        serde_json::json!(["src/**"])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_unknown_subcommand_without_plugin() -> Result<()> {
+    let plugin_dir = tempfile::tempdir()?;
+    let output = run_with_plugins(&PathBuf::from("."), plugin_dir.path(), &["no-such-cmd"])?;
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("'no-such-cmd' is not a git-partial command"));
+    Ok(())
+}
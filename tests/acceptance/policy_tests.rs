@@ -0,0 +1,200 @@
+use crate::test_helpers::cli::{config_home, run_gitpartial, run_gitpartial_raw};
+use crate::test_helpers::test_repo::TestRepo;
+use anyhow::Result;
+use std::path::PathBuf;
+
+fn setup_source_repo() -> Result<TestRepo> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file(
+        ".gitpartial-policy.toml",
+        "forbidden = [\"secrets/**\"]\nmandatory = [\"tools/lint/**\"]\n",
+    )?;
+    source_repo.write_file("src/main.rs", "fn main() {}")?;
+    source_repo.write_file("tools/lint/run.sh", "echo lint")?;
+    source_repo.write_file("secrets/prod.key", "secret")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    Ok(source_repo)
+}
+
+#[test]
+fn test_clone_enforces_mandatory_paths() -> Result<()> {
+    let source_repo = setup_source_repo()?;
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().join("clone");
+    let clone_path_str = clone_path.to_string_lossy().to_string();
+
+    // Without the mandatory path the clone is rejected and cleaned up
+    let output = run_gitpartial_raw(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path_str,
+            "--paths",
+            "src/**",
+        ],
+        "",
+    )?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("The paths violate the repository policy:"));
+    assert!(stderr.contains("'tools/lint/**' is mandatory but not included"));
+    assert!(!clone_path.exists());
+
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path_str,
+            "--paths",
+            "src/**",
+            "tools/lint/**",
+        ],
+    )?;
+    assert!(clone_path.join("tools/lint/run.sh").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_broader_pattern_satisfies_mandatory_and_remove_paths_enforces() -> Result<()> {
+    let source_repo = setup_source_repo()?;
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+
+    // `tools/**` checks out every file of the mandatory `tools/lint/**`
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+            "tools/**",
+        ],
+    )?;
+    assert!(clone_path.join("tools/lint/run.sh").exists());
+
+    // Removing it would drop the mandatory path
+    let output = run_gitpartial_raw(&clone_path, &["remove-paths", "--yes", "tools/**"], "")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("'tools/lint/**' is mandatory but not included"));
+    assert!(clone_path.join("tools/lint/run.sh").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_add_paths_forbidden_pattern_and_override() -> Result<()> {
+    let source_repo = setup_source_repo()?;
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "tools/lint/**",
+        ],
+    )?;
+
+    // Forbidden paths are refused
+    let output = run_gitpartial_raw(&clone_path, &["add-paths", "secrets/**"], "")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("'secrets/**' is forbidden, but the paths check out secrets/prod.key"));
+
+    // Overriding needs to be enabled in config first
+    let output = run_gitpartial_raw(
+        &clone_path,
+        &["add-paths", "--override-policy", "secrets/**"],
+        "",
+    )?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Policy overrides are disabled"));
+    assert!(!clone_path.join("secrets/prod.key").exists());
+
+    TestRepo::run_git_command(
+        &clone_path,
+        &["config", "gitpartial.allowPolicyOverride", "true"],
+    )?;
+    run_gitpartial(
+        &clone_path,
+        &["add-paths", "--override-policy", "secrets/**"],
+    )?;
+    assert!(clone_path.join("secrets/prod.key").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_checkout_limits_refuse_without_force() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "fn main() {}")?;
+    source_repo.write_file("datasets/a.csv", &"1,2\n".repeat(1024))?;
+    source_repo.write_file("datasets/b.csv", &"3,4\n".repeat(1024))?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().join("clone");
+    let clone_path_str = clone_path.to_string_lossy().to_string();
+
+    // 1. The file count limit applies to clone, from the environment here
+    let clone = |extra: &[&str]| -> Result<std::process::Output> {
+        Ok(
+            std::process::Command::new(env!("CARGO_BIN_EXE_git-partial"))
+                .args([
+                    "clone",
+                    &source_repo.path_str()?,
+                    &clone_path_str,
+                    "--paths",
+                    "datasets/**",
+                ])
+                .args(extra)
+                .env("XDG_CONFIG_HOME", config_home())
+                .env("GIT_PARTIAL_MAX_FILES", "1")
+                .output()?,
+        )
+    };
+    let output = clone(&[])?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("The paths exceed the checkout limits:\n  - the checkout has 2 files, over max_files (1)"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("repeat the command with --force"));
+    assert!(!clone_path.exists());
+
+    let output = clone(&["--force"])?;
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("warning: forcing past checkout limit: the checkout has 2 files"));
+    assert!(clone_path.join("datasets/a.csv").exists());
+
+    // 2. The size limit applies to add-paths, from git config here
+    run_gitpartial(&clone_path, &["add-paths", "--yes", "src/**"])?;
+    run_gitpartial(&clone_path, &["remove-paths", "--yes", "datasets/**"])?;
+    TestRepo::run_git_command(&clone_path, &["config", "gitpartial.maxCheckoutSize", "6k"])?;
+    let output = run_gitpartial_raw(&clone_path, &["add-paths", "--yes", "datasets/**"], "")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("over max_checkout_size (6.0 KiB)"));
+    assert!(!clone_path.join("datasets/a.csv").exists());
+
+    let output = run_gitpartial_raw(
+        &clone_path,
+        &["add-paths", "--yes", "--force", "datasets/**"],
+        "",
+    )?;
+    assert!(output.status.success());
+    assert!(clone_path.join("datasets/a.csv").exists());
+
+    Ok(())
+}
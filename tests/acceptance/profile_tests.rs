@@ -0,0 +1,202 @@
+use crate::test_helpers::cli::{run_gitpartial, run_gitpartial_raw};
+use crate::test_helpers::test_repo::{file_exists, TestRepo};
+use anyhow::Result;
+use git_partial::core::metadata::RepositoryMetadata;
+use std::path::PathBuf;
+
+// Helper function to set up a partial clone with team branches
+fn setup_partial_repo() -> Result<(TestRepo, tempfile::TempDir, PathBuf)> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Main Readme")?;
+    source_repo.write_file("payments/api.rs", "// Payments")?;
+    source_repo.write_file("search/index.rs", "// Search")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    TestRepo::run_git_command(source_repo.path(), &["branch", "feature/payments/refunds"])?;
+    let source_repo_url = source_repo.path_str()?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    let clone_path_str = clone_path.to_string_lossy().to_string();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo_url,
+            &clone_path_str,
+            "--paths",
+            "search/**",
+        ],
+    )?;
+
+    Ok((source_repo, clone_dir, clone_path))
+}
+
+#[test]
+fn test_switch_applies_matching_profile() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo()?;
+
+    run_gitpartial(
+        &clone_path,
+        &[
+            "profile",
+            "set",
+            "feature/payments/*",
+            "payments/**",
+            "README.md",
+        ],
+    )?;
+    let output = run_gitpartial(&clone_path, &["profile", "list"])?;
+    assert_eq!(output, "feature/payments/*: payments/** README.md\n");
+
+    run_gitpartial(&clone_path, &["switch", "feature/payments/refunds"])?;
+
+    assert!(file_exists(&clone_path, "payments/api.rs"));
+    assert!(file_exists(&clone_path, "README.md"));
+    assert!(!file_exists(&clone_path, "search/index.rs"));
+    let metadata = RepositoryMetadata::load(&clone_path)?;
+    assert!(metadata.checked_out_paths.contains("payments/**"));
+    assert!(!metadata.checked_out_paths.contains("search/**"));
+
+    Ok(())
+}
+
+#[test]
+fn test_switch_refuses_to_drop_pinned_paths() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo()?;
+
+    run_gitpartial(
+        &clone_path,
+        &["profile", "set", "feature/**", "payments/**"],
+    )?;
+    run_gitpartial(&clone_path, &["pin", "search/**"])?;
+
+    let output = run_gitpartial_raw(&clone_path, &["switch", "feature/payments/refunds"], "")?;
+    assert!(!output.status.success());
+    assert!(file_exists(&clone_path, "search/index.rs"));
+
+    run_gitpartial(
+        &clone_path,
+        &["switch", "feature/payments/refunds", "--force"],
+    )?;
+    assert!(!file_exists(&clone_path, "search/index.rs"));
+
+    Ok(())
+}
+
+#[test]
+fn test_profile_variables_resolve_when_applied() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo()?;
+
+    // One shared profile, resolved per machine
+    run_gitpartial(
+        &clone_path,
+        &["profile", "set", "feature/**", "${SQUAD}/**"],
+    )?;
+    let output = run_gitpartial(&clone_path, &["profile", "list"])?;
+    assert_eq!(output, "feature/**: ${SQUAD}/**\n");
+
+    let output = run_gitpartial_raw(&clone_path, &["switch", "feature/payments/refunds"], "")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Undefined variable(s) in the path patterns: SQUAD"));
+
+    TestRepo::run_git_command(
+        &clone_path,
+        &["config", "--add", "gitpartial.var", "SQUAD=payments"],
+    )?;
+    run_gitpartial(&clone_path, &["switch", "feature/payments/refunds"])?;
+
+    assert!(file_exists(&clone_path, "payments/api.rs"));
+    assert!(!file_exists(&clone_path, "search/index.rs"));
+    let metadata = RepositoryMetadata::load(&clone_path)?;
+    assert!(metadata.checked_out_paths.contains("payments/**"));
+
+    // Command line patterns are resolved right away
+    run_gitpartial(&clone_path, &["add-paths", "{${SQUAD},search}/**"])?;
+    assert!(file_exists(&clone_path, "search/index.rs"));
+
+    Ok(())
+}
+
+#[test]
+fn test_narrow_fetch_follows_switch() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("search/index.rs", "// Search")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    for branch in ["feature/payments/refunds", "release/1", "old"] {
+        TestRepo::run_git_command(source_repo.path(), &["branch", branch])?;
+    }
+    let default_branch = String::from_utf8(
+        TestRepo::run_git_command(source_repo.path(), &["symbolic-ref", "--short", "HEAD"])?.stdout,
+    )?
+    .trim()
+    .to_string();
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    let output = run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "search/**",
+            "--narrow-fetch",
+        ],
+    )?;
+    assert!(output.contains(&format!("Fetching only: {}", default_branch)));
+    let refspecs = |path: &PathBuf| -> Result<String> {
+        Ok(String::from_utf8(
+            TestRepo::run_git_command(path, &["config", "--get-all", "remote.origin.fetch"])?
+                .stdout,
+        )?)
+    };
+    let tracking = |path: &PathBuf| -> Result<String> {
+        Ok(String::from_utf8(
+            TestRepo::run_git_command(
+                path,
+                &[
+                    "for-each-ref",
+                    "--format=%(refname)",
+                    "refs/remotes/origin/",
+                ],
+            )?
+            .stdout,
+        )?)
+    };
+    assert_eq!(
+        refspecs(&clone_path)?,
+        format!("+refs/heads/{0}:refs/remotes/origin/{0}\n", default_branch)
+    );
+    assert!(!tracking(&clone_path)?.contains("refs/remotes/origin/old"));
+
+    // Switching fetches the branch and moves the refspec along
+    run_gitpartial(&clone_path, &["switch", "feature/payments/refunds"])?;
+    assert_eq!(
+        refspecs(&clone_path)?,
+        "+refs/heads/feature/payments/refunds:refs/remotes/origin/feature/payments/refunds\n"
+    );
+
+    // The branch switched away from is no longer fetched
+    let output = run_gitpartial(&clone_path, &["narrow-fetch", "release/1"])?;
+    assert_eq!(
+        output,
+        "Fetching only: feature/payments/refunds, release/1\n\
+         Deleted 1 remote-tracking ref(s) no longer fetched.\n"
+    );
+    assert!(tracking(&clone_path)?.contains("refs/remotes/origin/release/1"));
+    let metadata = RepositoryMetadata::load(&clone_path)?;
+    assert_eq!(metadata.fetch_branches, Some(vec!["release/1".to_string()]));
+
+    run_gitpartial(&clone_path, &["narrow-fetch", "--all"])?;
+    assert_eq!(
+        refspecs(&clone_path)?,
+        "+refs/heads/*:refs/remotes/origin/*\n"
+    );
+    assert_eq!(RepositoryMetadata::load(&clone_path)?.fetch_branches, None);
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::{file_exists, TestRepo};
+use anyhow::Result;
+use std::path::PathBuf;
+
+// Helper function to set up a partial clone of a pnpm workspace
+fn setup_workspace_repo() -> Result<(TestRepo, tempfile::TempDir, PathBuf)> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Monorepo")?;
+    source_repo.write_file("package.json", r#"{ "name": "root", "private": true }"#)?;
+    source_repo.write_file(
+        "pnpm-workspace.yaml",
+        "packages:\n  - 'apps/*'\n  - 'packages/*'\n",
+    )?;
+    source_repo.write_file("apps/web/package.json", r#"{ "name": "web-app" }"#)?;
+    source_repo.write_file("apps/web/index.js", "// Web")?;
+    source_repo.write_file("packages/ui/package.json", r#"{ "name": "@acme/ui" }"#)?;
+    source_repo.write_file("packages/ui/button.js", "// Button")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let source_repo_url = source_repo.path_str()?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    let clone_path_str = clone_path.to_string_lossy().to_string();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo_url,
+            &clone_path_str,
+            "--paths",
+            "README.md",
+        ],
+    )?;
+
+    Ok((source_repo, clone_dir, clone_path))
+}
+
+#[test]
+fn test_projects_lists_workspace_packages() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_workspace_repo()?;
+
+    let output = run_gitpartial(&clone_path, &["projects"])?;
+
+    assert!(output.contains("web-app"));
+    assert!(output.contains("apps/web (pnpm)"));
+    assert!(output.contains("@acme/ui"));
+    assert!(output.contains("packages/ui (pnpm)"));
+
+    Ok(())
+}
+
+#[test]
+fn test_add_paths_by_project() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_workspace_repo()?;
+
+    run_gitpartial(&clone_path, &["add-paths", "--project", "web-app"])?;
+
+    assert!(file_exists(&clone_path, "apps/web/index.js"));
+    assert!(!file_exists(&clone_path, "packages/ui/button.js"));
+
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use crate::test_helpers::cli::{run_gitpartial, run_gitpartial_raw, run_gitpartial_with_stdin};
+use crate::test_helpers::test_repo::{file_exists, TestRepo};
+use anyhow::Result;
+use git_partial::core::journal::{Journal, JournalState};
+use git_partial::core::metadata::RepositoryMetadata;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+// Helper function to set up a basic partially cloned repo
+fn setup_partial_repo(initial_paths: &[&str]) -> Result<(TestRepo, tempfile::TempDir, PathBuf)> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Main Readme")?;
+    source_repo.write_file("src/core.rs", "// Core lib")?;
+    source_repo.write_file("docs/guide.md", "User guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let source_repo_url = source_repo.path_str()?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    let clone_path_str = clone_path.to_string_lossy().to_string();
+
+    let mut clone_args = vec!["clone", &source_repo_url, &clone_path_str, "--paths"];
+    clone_args.extend(initial_paths);
+    run_gitpartial(&PathBuf::from("."), &clone_args)?;
+
+    Ok((source_repo, clone_dir, clone_path))
+}
+
+#[test]
+fn test_remove_paths_with_yes() -> Result<()> {
+    let initial_paths = ["README.md", "src/**", "docs/**"];
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo(&initial_paths)?;
+    assert!(file_exists(&clone_path, "docs/guide.md"));
+
+    run_gitpartial(&clone_path, &["remove-paths", "docs/**", "--yes"])?;
+
+    assert!(!file_exists(&clone_path, "docs/guide.md"));
+    assert!(file_exists(&clone_path, "README.md"));
+    assert!(file_exists(&clone_path, "src/core.rs"));
+
+    let metadata = RepositoryMetadata::load(&clone_path)?;
+    let expected: HashSet<String> = ["README.md", "src/**"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    assert_eq!(metadata.checked_out_paths, expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_remove_paths_prompt_declined() -> Result<()> {
+    let initial_paths = ["README.md", "docs/**"];
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo(&initial_paths)?;
+
+    // No answer on stdin means "no"
+    let output = run_gitpartial(&clone_path, &["remove-paths", "docs/**"])?;
+    assert!(output.contains("docs/**"));
+    assert!(output.contains("Aborted."));
+    assert!(file_exists(&clone_path, "docs/guide.md"));
+    assert_eq!(
+        RepositoryMetadata::load(&clone_path)?
+            .checked_out_paths
+            .len(),
+        2
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_remove_paths_prompt_accepted() -> Result<()> {
+    let initial_paths = ["README.md", "docs/**"];
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo(&initial_paths)?;
+
+    run_gitpartial_with_stdin(&clone_path, &["remove-paths", "docs/**"], "y\n")?;
+
+    assert!(!file_exists(&clone_path, "docs/guide.md"));
+    assert_eq!(
+        RepositoryMetadata::load(&clone_path)?
+            .checked_out_paths
+            .len(),
+        1
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_remove_all_paths_is_refused() -> Result<()> {
+    let initial_paths = ["README.md"];
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo(&initial_paths)?;
+
+    let output = run_gitpartial_raw(&clone_path, &["remove-paths", "README.md", "-y"], "")?;
+
+    assert!(!output.status.success());
+    assert!(file_exists(&clone_path, "README.md"));
+
+    Ok(())
+}
+
+#[test]
+fn test_remove_pinned_path_requires_force() -> Result<()> {
+    let initial_paths = ["README.md", "docs/**"];
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo(&initial_paths)?;
+
+    run_gitpartial(&clone_path, &["pin", "docs/**"])?;
+    let output = run_gitpartial(&clone_path, &["list-paths"])?;
+    assert_eq!(output, "README.md\ndocs/** (pinned)\n");
+
+    let output = run_gitpartial_raw(&clone_path, &["remove-paths", "docs/**", "-y"], "")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("pinned"));
+    assert!(file_exists(&clone_path, "docs/guide.md"));
+
+    run_gitpartial(&clone_path, &["remove-paths", "docs/**", "-y", "--force"])?;
+    assert!(!file_exists(&clone_path, "docs/guide.md"));
+    assert!(RepositoryMetadata::load(&clone_path)?
+        .pinned_paths
+        .is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_recover_interrupted_remove_paths() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo(&["src/**", "docs/**"])?;
+    let output = run_gitpartial(&clone_path, &["recover"])?;
+    assert_eq!(output, "Nothing to recover.\n");
+
+    // 1. A remove-paths of docs/** that crashed before its first step
+    let interrupt = |path: &str| -> Result<()> {
+        let metadata = RepositoryMetadata::load(&clone_path)?;
+        let mut updated = metadata.clone();
+        updated.checked_out_paths.remove(path);
+        Journal::begin(
+            &clone_path,
+            "remove-paths",
+            Some(JournalState {
+                branch: None,
+                metadata,
+            }),
+            JournalState {
+                branch: None,
+                metadata: updated,
+            },
+        )?;
+        Ok(())
+    };
+    interrupt("docs/**")?;
+
+    let output = run_gitpartial_raw(&clone_path, &["add-paths", "README.md"], "")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("An earlier remove-paths did not complete"));
+
+    // 2. recover finishes it
+    let output = run_gitpartial(&clone_path, &["recover"])?;
+    assert!(output.contains("Found an interrupted remove-paths (done: nothing)."));
+    assert!(output.contains("Completed the remove-paths."));
+    assert!(!file_exists(&clone_path, "docs/guide.md"));
+    let metadata = RepositoryMetadata::load(&clone_path)?;
+    assert_eq!(
+        metadata.checked_out_paths,
+        HashSet::from(["src/**".to_string()])
+    );
+    let history = run_gitpartial(&clone_path, &["history"])?;
+    assert!(history.contains("docs/**"));
+
+    // 3. recover --rollback undoes one instead
+    run_gitpartial(&clone_path, &["add-paths", "docs/**"])?;
+    interrupt("src/**")?;
+    let output = run_gitpartial(&clone_path, &["recover", "--rollback"])?;
+    assert!(output.contains("Rolled back the remove-paths."));
+    assert!(file_exists(&clone_path, "src/core.rs"));
+    assert!(file_exists(&clone_path, "docs/guide.md"));
+    assert!(RepositoryMetadata::load(&clone_path)?
+        .checked_out_paths
+        .contains("src/**"));
+    assert!(Journal::load(&clone_path)?.is_none());
+
+    Ok(())
+}
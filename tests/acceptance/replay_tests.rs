@@ -0,0 +1,70 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::TestRepo;
+use anyhow::Result;
+use std::fs;
+
+#[test]
+fn test_trace_git_and_replay() -> Result<()> {
+    // 1. Setup: a source repository
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "fn main() {}")?;
+    source_repo.write_file("docs/guide.md", "User guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+
+    // 2. Action: a clone recording its git commands
+    let work_dir = tempfile::tempdir()?;
+    let trace_path = work_dir.path().join("trace.jsonl");
+    run_gitpartial(
+        work_dir.path(),
+        &[
+            "--trace-git",
+            &trace_path.to_string_lossy(),
+            "clone",
+            &source_repo.path_str()?,
+            "app",
+            "--paths",
+            "src/**",
+        ],
+    )?;
+
+    // 3. Verification: every git command is traced with its outcome
+    let trace = fs::read_to_string(&trace_path)?;
+    let entries: Vec<serde_json::Value> = trace
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    let clone = entries
+        .iter()
+        .find(|entry| entry["args"].as_array().unwrap().contains(&"clone".into()))
+        .expect("clone is traced");
+    assert_eq!(clone["exit_code"], 0);
+    assert!(clone["duration_ms"].is_u64());
+    assert!(entries
+        .iter()
+        .any(|entry| entry["cwd"].as_str().unwrap().ends_with("/app")));
+
+    // Replaying in a scratch directory makes the same clone there
+    let scratch = tempfile::tempdir()?;
+    let output = run_gitpartial(
+        work_dir.path(),
+        &[
+            "replay",
+            &trace_path.to_string_lossy(),
+            "--repo",
+            &scratch.path().to_string_lossy(),
+        ],
+    )?;
+    assert!(
+        output.contains(&format!(
+            "Replayed {} command(s), skipped 0; 0 exited differently than traced.",
+            entries.len()
+        )),
+        "{}",
+        output
+    );
+    assert!(scratch.path().join("app/src/main.rs").exists());
+    assert!(!scratch.path().join("app/docs/guide.md").exists());
+
+    Ok(())
+}
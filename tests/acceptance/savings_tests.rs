@@ -0,0 +1,51 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::TestRepo;
+use anyhow::Result;
+use git_partial::core::metadata::RepositoryMetadata;
+use std::path::PathBuf;
+
+#[test]
+fn test_savings_measures_and_caches_full_clone() -> Result<()> {
+    // Setup: a large directory left out of a blobless clone
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "fn main() {}")?;
+    source_repo.write_file("assets/video.dat", &"x".repeat(512 * 1024))?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    TestRepo::run_git_command(
+        source_repo.path(),
+        &["config", "uploadpack.allowFilter", "true"],
+    )?;
+    let source_repo_url = format!("file://{}", source_repo.path_str()?);
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo_url,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ],
+    )?;
+
+    // Action: measure against the reachable remote
+    let output = run_gitpartial(&clone_path, &["savings"])?;
+    assert!(output.contains("Objects"));
+    assert!(output.contains("Checkout"));
+    assert!(!output.contains("cached"));
+
+    let mut metadata = RepositoryMetadata::load(&clone_path)?;
+    let full = metadata.full_clone_stats.clone().expect("stats cached");
+    assert!(full.checkout_bytes >= 512 * 1024);
+
+    // Once the remote is out of reach, the cached figures are reported
+    metadata.remote_url = "https://example.invalid/repo.git".to_string();
+    metadata.save(&clone_path)?;
+    let output = run_gitpartial(&clone_path, &["savings"])?;
+    assert!(output.contains("Full clone figures are cached from an earlier run."));
+
+    Ok(())
+}
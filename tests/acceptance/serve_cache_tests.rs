@@ -0,0 +1,153 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::TestRepo;
+use anyhow::Result;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+// Stops the cache server when the test ends, even on failure
+struct Server(Child);
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn free_port() -> Result<u16> {
+    Ok(TcpListener::bind("127.0.0.1:0")?.local_addr()?.port())
+}
+
+fn start_server(
+    origin: &str,
+    cache_dir: &str,
+    extra_args: &[&str],
+) -> Result<(Server, u16)> {
+    let port = free_port()?;
+    let listen = format!("127.0.0.1:{}", port);
+    let child = Command::new(env!("CARGO_BIN_EXE_git-partial"))
+        .args([
+            "serve-cache",
+            "--listen",
+            &listen,
+            "--origin",
+            origin,
+            "--cache-dir",
+            cache_dir,
+            "--refresh-after",
+            "0",
+        ])
+        .args(extra_args)
+        .stdout(Stdio::null())
+        .spawn()?;
+    let server = Server(child);
+
+    for _ in 0..50 {
+        if TcpStream::connect(&listen).is_ok() {
+            return Ok((server, port));
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    anyhow::bail!("cache server did not start")
+}
+
+#[test]
+fn test_clone_through_cache_server() -> Result<()> {
+    // 1. Setup: an origin repository and a cache server in front of it
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "fn main() {}")?;
+    source_repo.write_file("docs/guide.md", "User guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let origin = format!(
+        "file://{}",
+        source_repo.path().parent().unwrap().to_string_lossy()
+    );
+    let repo_name = source_repo.path().file_name().unwrap().to_string_lossy();
+
+    let cache_dir = tempfile::tempdir()?;
+    let (_server, port) = start_server(&origin, &cache_dir.path().to_string_lossy(), &[])?;
+    let url = format!("git://127.0.0.1:{}/{}", port, repo_name);
+
+    // 2. Action: a partial clone through the cache (miss)
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &url,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ],
+    )?;
+
+    // 3. Verification: the clone works and origin was mirrored into the cache
+    assert!(clone_path.join("src/main.rs").exists());
+    assert!(!clone_path.join("docs/guide.md").exists());
+    assert!(cache_dir.path().join(&*repo_name).join("HEAD").exists());
+
+    // New commits on origin reach clients on the next fetch (refresh)
+    source_repo.write_file("src/main.rs", "fn main() { println!(\"v2\"); }")?;
+    source_repo.add_all()?;
+    source_repo.commit("Update main")?;
+    run_gitpartial(&clone_path, &["smart-pull"])?;
+    assert!(std::fs::read_to_string(clone_path.join("src/main.rs"))?.contains("v2"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cache_server_exposes_metrics() -> Result<()> {
+    // 1. Setup: a cache server serving metrics
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "fn main() {}")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let origin = format!(
+        "file://{}",
+        source_repo.path().parent().unwrap().to_string_lossy()
+    );
+    let repo_name = source_repo.path().file_name().unwrap().to_string_lossy();
+    let cache_dir = tempfile::tempdir()?;
+    let metrics_listen = format!("127.0.0.1:{}", free_port()?);
+    let (_server, port) = start_server(
+        &origin,
+        &cache_dir.path().to_string_lossy(),
+        &["--metrics-listen", &metrics_listen],
+    )?;
+
+    // 2. Action: a clone through the cache
+    let clone_dir = tempfile::tempdir()?;
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &format!("git://127.0.0.1:{}/{}", port, repo_name),
+            &clone_dir.path().to_string_lossy(),
+            "--paths",
+            "src/**",
+        ],
+    )?;
+
+    // 3. Verification: the miss and the fetch from origin are counted
+    let mut stream = TcpStream::connect(&metrics_listen)?;
+    write!(
+        stream,
+        "GET /metrics HTTP/1.1\r\nHost: {}\r\n\r\n",
+        metrics_listen
+    )?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    assert!(response.contains("# TYPE git_partial_cache_requests_total counter"));
+    assert!(response.contains("git_partial_cache_requests_total{result=\"miss\"} 1\n"));
+    assert!(response.contains("git_partial_fetch_duration_seconds_count{repository="));
+
+    Ok(())
+}
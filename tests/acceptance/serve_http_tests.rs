@@ -0,0 +1,91 @@
+use crate::test_helpers::cli::{config_home, run_gitpartial};
+use crate::test_helpers::test_repo::TestRepo;
+use anyhow::Result;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Sends a GET request and returns the status line and the body
+fn get(
+    address: &str,
+    path: &str,
+) -> Result<(String, serde_json::Value)> {
+    let mut stream = TcpStream::connect(address)?;
+    write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", path, address)?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.lines().next().unwrap_or_default().to_string();
+    Ok((status, serde_json::from_str(body)?))
+}
+
+#[test]
+fn test_serve_http_reports_registered_clones() -> Result<()> {
+    // 1. A clone, registered in the test config
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "fn main() {}\n")?;
+    source_repo.write_file("docs/guide.md", "guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().join("served-app");
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ],
+    )?;
+
+    // 2. An upstream commit touching the sparse paths
+    source_repo.write_file("src/lib.rs", "pub fn run() {}\n")?;
+    source_repo.add_all()?;
+    source_repo.commit("Add lib")?;
+    TestRepo::run_git_command(&clone_path, &["fetch", "--quiet"])?;
+
+    // 3. Serve on a free port
+    let mut server = Command::new(env!("CARGO_BIN_EXE_git-partial"))
+        .args(["serve-http", "--listen", "127.0.0.1:0"])
+        .env("XDG_CONFIG_HOME", config_home())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut line = String::new();
+    BufReader::new(server.stdout.take().unwrap()).read_line(&mut line)?;
+    let address = line.trim().rsplit("http://").next().unwrap().to_string();
+
+    let result = (|| -> Result<()> {
+        let (status, repos) = get(&address, "/repos")?;
+        assert!(status.contains("200"), "{}", status);
+        assert!(repos
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|repo| repo["name"] == "served-app"));
+
+        let (_, paths) = get(&address, "/repos/served-app/paths")?;
+        assert_eq!(paths["paths"], serde_json::json!(["src/**"]));
+
+        let (_, status) = get(&address, "/repos/served-app/status")?;
+        assert_eq!(status["upstream"], "origin/main");
+        assert_eq!(status["ahead_behind"], serde_json::json!([0, 1]));
+
+        let (_, incoming) = get(&address, "/repos/served-app/incoming")?;
+        assert_eq!(incoming["changes"]["groups"][0]["pattern"], "src/**");
+        assert_eq!(incoming["changes"]["groups"][0]["added"], 1);
+
+        let (_, savings) = get(&address, "/repos/served-app/savings")?;
+        assert_eq!(savings["cached"], false);
+
+        let (status, _) = get(&address, "/repos/missing/status")?;
+        assert!(status.contains("404"), "{}", status);
+        Ok(())
+    })();
+    server.kill()?;
+    server.wait()?;
+    result
+}
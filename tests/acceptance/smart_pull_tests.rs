@@ -176,3 +176,462 @@ fn test_smart_pull_ignores_nonmatching_changes() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_smart_pull_follows_configured_upstream() -> Result<()> {
+    // 1. Setup: a fork with a commit that origin does not have
+    let initial_paths = ["src/frontend/**"];
+    let (source_repo, _local_repo_dir, local_path) = setup_repos_for_pull(&initial_paths)?;
+    let fork_dir = tempfile::tempdir()?;
+    let fork_path = fork_dir.path().join("fork");
+    TestRepo::run_git_command(
+        Path::new("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &fork_path.to_string_lossy(),
+        ],
+    )?;
+    std::fs::write(fork_path.join("src/frontend/button.js"), "// Button fork")?;
+    TestRepo::run_git_command(
+        &fork_path,
+        &[
+            "-c",
+            "user.name=Test User",
+            "-c",
+            "user.email=test@example.com",
+            "commit",
+            "-am",
+            "Fork change",
+        ],
+    )?;
+
+    let branch = String::from_utf8(
+        TestRepo::run_git_command(&local_path, &["branch", "--show-current"])?.stdout,
+    )?
+    .trim()
+    .to_string();
+    TestRepo::run_git_command(
+        &local_path,
+        &["remote", "add", "fork", &fork_path.to_string_lossy()],
+    )?;
+    TestRepo::run_git_command(&local_path, &["fetch", "fork"])?;
+    TestRepo::run_git_command(
+        &local_path,
+        &["branch", "--set-upstream-to", &format!("fork/{}", branch)],
+    )?;
+
+    // 2. Action: smart-pull uses the configured upstream
+    run_gitpartial(&local_path, &["smart-pull"])?;
+    assert_eq!(
+        get_file_content(&local_path, "src/frontend/button.js")?,
+        "// Button fork"
+    );
+
+    // 3. An explicit override is reported by status
+    let status = run_gitpartial(
+        &local_path,
+        &["status", "--upstream", &format!("origin/{}", branch)],
+    )?;
+    assert!(status.contains(&format!("Upstream: origin/{}", branch)));
+
+    Ok(())
+}
+
+#[test]
+fn test_fork_clone_syncs_from_upstream() -> Result<()> {
+    // 1. Setup: a canonical repository and a fork of it
+    let canonical_repo = TestRepo::new()?;
+    canonical_repo.write_file("src/frontend/main.js", "// Frontend main v1")?;
+    canonical_repo.add_all()?;
+    canonical_repo.commit("Initial commit")?;
+    let fork_dir = tempfile::tempdir()?;
+    let fork_path = fork_dir.path().join("fork");
+    TestRepo::run_git_command(
+        Path::new("."),
+        &[
+            "clone",
+            &canonical_repo.path_str()?,
+            &fork_path.to_string_lossy(),
+        ],
+    )?;
+
+    let local_dir = tempfile::tempdir()?;
+    let local_path = local_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &fork_path.to_string_lossy(),
+            &local_path.to_string_lossy(),
+            "--paths",
+            "src/frontend/**",
+            "--upstream",
+            &canonical_repo.path_str()?,
+        ],
+    )?;
+
+    // 2. The canonical repository moves ahead of the fork
+    canonical_repo.write_file("src/frontend/main.js", "// Frontend main v2")?;
+    canonical_repo.add_all()?;
+    canonical_repo.commit("Update upstream")?;
+
+    let status = run_gitpartial(&local_path, &["status"])?;
+    assert!(status.contains("Remotes:"));
+    assert!(status.contains(": ahead 0, behind 1"));
+    assert!(status.contains(": ahead 0, behind 0"));
+
+    // 3. Action: sync from upstream
+    run_gitpartial(&local_path, &["smart-pull", "--from", "upstream"])?;
+
+    assert_eq!(
+        get_file_content(&local_path, "src/frontend/main.js")?,
+        "// Frontend main v2"
+    );
+    let metadata = RepositoryMetadata::load(&local_path)?;
+    assert_eq!(metadata.upstream_url, Some(canonical_repo.path_str()?));
+
+    Ok(())
+}
+
+#[test]
+fn test_smart_pull_other_branch_without_switching() -> Result<()> {
+    let (source_repo, _local_repo_tempdir, local_repo_path) =
+        setup_repos_for_pull(&["src/frontend/**"])?;
+    TestRepo::run_git_command(&local_repo_path, &["checkout", "-q", "-b", "feature"])?;
+    let feature_head = rev_parse(&local_repo_path, "HEAD")?;
+
+    source_repo.write_file("src/frontend/main.js", "// Frontend main v2")?;
+    source_repo.add_all()?;
+    let upstream_head = source_repo.commit("Update frontend")?;
+
+    // Without --ff only the remote-tracking ref moves
+    run_gitpartial(&local_repo_path, &["smart-pull", "--branch", "main"])?;
+    assert_eq!(rev_parse(&local_repo_path, "origin/main")?, upstream_head);
+    assert_eq!(rev_parse(&local_repo_path, "main")?, feature_head);
+
+    let output = run_gitpartial(
+        &local_repo_path,
+        &["smart-pull", "--branch", "main", "--ff"],
+    )?;
+    assert!(output.contains("Fast-forwarded main"));
+    assert_eq!(rev_parse(&local_repo_path, "main")?, upstream_head);
+
+    // The checked out branch and its files are untouched
+    assert_eq!(rev_parse(&local_repo_path, "HEAD")?, feature_head);
+    assert_eq!(
+        get_file_content(&local_repo_path, "src/frontend/main.js")?,
+        "// Frontend main v1"
+    );
+    Ok(())
+}
+
+fn rev_parse(
+    repo_path: &Path,
+    rev: &str,
+) -> Result<String> {
+    let output = TestRepo::run_git_command(repo_path, &["rev-parse", rev])?;
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+#[test]
+fn test_smart_pull_previews_incoming_changes() -> Result<()> {
+    let (source_repo, _local_repo_tempdir, local_repo_path) =
+        setup_repos_for_pull(&["src/frontend/**"])?;
+
+    source_repo.write_file("src/frontend/main.js", "// Frontend main v2")?;
+    source_repo.write_file("src/frontend/new.js", "// New")?;
+    source_repo.write_file("src/backend/server.js", "// Backend server v2")?;
+    source_repo.add_all()?;
+    source_repo.commit("Update frontend and backend")?;
+
+    // Declining the confirmation leaves the checkout as it was
+    let output = crate::test_helpers::cli::run_gitpartial_raw(
+        &local_repo_path,
+        &["smart-pull", "--confirm"],
+        "n\n",
+    )?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("src/frontend/**  1 added, 1 modified, 0 deleted"));
+    assert!(stdout.contains("1 changed file(s) outside the sparse paths"));
+    assert!(stdout.contains("Not merged"));
+    assert!(!file_exists(&local_repo_path, "src/frontend/new.js"));
+
+    let stdout = run_gitpartial(&local_repo_path, &["smart-pull"])?;
+    assert!(stdout.contains("Incoming changes in the sparse paths:"));
+    assert!(file_exists(&local_repo_path, "src/frontend/new.js"));
+    Ok(())
+}
+
+#[test]
+fn test_smart_pull_stores_ssh_options() -> Result<()> {
+    // 1. Setup
+    let (source_repo, _local_repo_dir, local_path) = setup_repos_for_pull(&["README.md"])?;
+    source_repo.write_file("README.md", "# Main Readme v2")?;
+    source_repo.add_all()?;
+    source_repo.commit("Update readme")?;
+
+    // 2. Action: pull with SSH options (the local remote does not use them)
+    run_gitpartial(
+        &local_path,
+        &["smart-pull", "--ssh-port", "2222", "--ssh-jump", "bastion"],
+    )?;
+
+    // 3. Verify: the pull worked and later fetches connect the same way
+    assert_eq!(
+        get_file_content(&local_path, "README.md")?,
+        "# Main Readme v2"
+    );
+    let output = TestRepo::run_git_command(&local_path, &["config", "core.sshCommand"])?;
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "ssh -p 2222 -J 'bastion'"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_smart_pull_skips_paths_pinned_at_a_commit() -> Result<()> {
+    // 1. Setup: the frontend pinned at the cloned commit
+    let (source_repo, _local_repo_tempdir, local_repo_path) =
+        setup_repos_for_pull(&["src/frontend/**", "src/backend/**"])?;
+    let pinned_at = rev_parse(&local_repo_path, "HEAD")?;
+    run_gitpartial(
+        &local_repo_path,
+        &["pin", "src/frontend/**", "--at", &pinned_at],
+    )?;
+
+    // 2. Action: upstream changes both paths
+    source_repo.write_file("src/frontend/main.js", "// Frontend main v2")?;
+    source_repo.write_file("src/frontend/new.js", "// New")?;
+    source_repo.write_file("src/backend/server.js", "// Backend server v2")?;
+    source_repo.add_all()?;
+    source_repo.commit("Update frontend and backend")?;
+    run_gitpartial(&local_repo_path, &["smart-pull"])?;
+
+    // 3. Verification: only the unpinned path follows the branch
+    assert_eq!(
+        get_file_content(&local_repo_path, "src/backend/server.js")?,
+        "// Backend server v2"
+    );
+    assert_eq!(
+        get_file_content(&local_repo_path, "src/frontend/main.js")?,
+        "// Frontend main v1"
+    );
+    assert!(!file_exists(&local_repo_path, "src/frontend/new.js"));
+    assert_eq!(
+        RepositoryMetadata::load(&local_repo_path)?.pinned_revisions["src/frontend/**"],
+        pinned_at
+    );
+    let paths = run_gitpartial(&local_repo_path, &["list-paths"])?;
+    assert!(paths.contains(&format!("src/frontend/** (pinned at {})", &pinned_at[..7])));
+
+    // Unpinning brings the path to the branch's version
+    run_gitpartial(&local_repo_path, &["unpin", "src/frontend/**"])?;
+    assert_eq!(
+        get_file_content(&local_repo_path, "src/frontend/main.js")?,
+        "// Frontend main v2"
+    );
+    assert!(file_exists(&local_repo_path, "src/frontend/new.js"));
+    let status =
+        TestRepo::run_git_command(&local_repo_path, &["status", "--porcelain", "--", "src"])?;
+    assert_eq!(String::from_utf8(status.stdout)?, "");
+
+    Ok(())
+}
+
+#[test]
+fn test_commit_leaves_paths_pinned_at_a_commit_alone() -> Result<()> {
+    // 1. Setup: the frontend pinned behind the branch
+    let (source_repo, _local_repo_tempdir, local_repo_path) =
+        setup_repos_for_pull(&["src/frontend/**", "src/backend/**"])?;
+    let pinned_at = rev_parse(&local_repo_path, "HEAD")?;
+    run_gitpartial(
+        &local_repo_path,
+        &["pin", "src/frontend/**", "--at", &pinned_at],
+    )?;
+    source_repo.write_file("src/frontend/main.js", "// Frontend main v2")?;
+    source_repo.write_file("src/frontend/new.js", "// New")?;
+    source_repo.add_all()?;
+    source_repo.commit("Update the frontend")?;
+    run_gitpartial(&local_repo_path, &["smart-pull"])?;
+    let head = rev_parse(&local_repo_path, "HEAD")?;
+    for (key, value) in [
+        ("user.name", "Test User"),
+        ("user.email", "test@example.com"),
+    ] {
+        TestRepo::run_git_command(&local_repo_path, &["config", key, value])?;
+    }
+
+    // 2. Action: commit everything with plain git
+    std::fs::write(
+        local_repo_path.join("src/backend/server.js"),
+        "// Backend server v2",
+    )?;
+    TestRepo::run_git_command(&local_repo_path, &["add", "src"])?;
+    TestRepo::run_git_command(
+        &local_repo_path,
+        &["commit", "-a", "-m", "Update the backend"],
+    )?;
+
+    // 3. Verification: only the backend change was committed, and the
+    //    pinned files are neither committed nor reported as modified
+    let changed =
+        TestRepo::run_git_command(&local_repo_path, &["diff", "--name-only", &head, "HEAD"])?;
+    assert_eq!(
+        String::from_utf8(changed.stdout)?,
+        "src/backend/server.js\n"
+    );
+    assert_eq!(
+        get_file_content(&local_repo_path, "src/frontend/main.js")?,
+        "// Frontend main v1"
+    );
+    let status =
+        TestRepo::run_git_command(&local_repo_path, &["status", "--porcelain", "--", "src"])?;
+    assert_eq!(String::from_utf8(status.stdout)?, "");
+
+    // Staging a file under the pinned path is rejected
+    std::fs::write(local_repo_path.join("src/frontend/extra.js"), "// Extra")?;
+    TestRepo::run_git_command(&local_repo_path, &["add", "src/frontend/extra.js"])?;
+    let output = Command::new(env!("CARGO_BIN_EXE_git-partial"))
+        .arg("check")
+        .current_dir(&local_repo_path)
+        .output()?;
+    assert_eq!(output.status.code(), Some(9));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("src/frontend/extra.js"));
+
+    Ok(())
+}
+
+#[test]
+fn test_smart_pull_follows_renamed_directory() -> Result<()> {
+    // 1. Setup
+    let (source_repo, _local_repo_tempdir, local_repo_path) =
+        setup_repos_for_pull(&["src/frontend/**", "README.md"])?;
+
+    // 2. Action: upstream moves the frontend, editing one file on the way
+    std::fs::create_dir_all(source_repo.path().join("apps"))?;
+    TestRepo::run_git_command(source_repo.path(), &["mv", "src/frontend", "apps/web"])?;
+    source_repo.write_file("apps/web/main.js", "// Frontend main v2")?;
+    source_repo.add_all()?;
+    source_repo.commit("Move the frontend")?;
+
+    // Without --follow-renames the move is only reported
+    let stdout = run_gitpartial(&local_repo_path, &["smart-pull"])?;
+    assert!(stdout.contains("src/frontend/** -> apps/web/** (2 file(s))"));
+    assert!(!file_exists(&local_repo_path, "apps/web/main.js"));
+
+    // 3. Verification: the next move is followed
+    TestRepo::run_git_command(source_repo.path(), &["mv", "apps/web", "apps/site"])?;
+    source_repo.commit("Rename the web app")?;
+    run_gitpartial(&local_repo_path, &["add-paths", "apps/web/**", "-y"])?;
+    run_gitpartial(&local_repo_path, &["smart-pull", "--follow-renames"])?;
+    assert_eq!(
+        get_file_content(&local_repo_path, "apps/site/main.js")?,
+        "// Frontend main v2"
+    );
+    assert!(file_exists(&local_repo_path, "apps/site/button.js"));
+    assert!(!file_exists(&local_repo_path, "apps/web"));
+    let metadata = RepositoryMetadata::load(&local_repo_path)?;
+    assert!(metadata.checked_out_paths.contains("apps/site/**"));
+    assert!(!metadata.checked_out_paths.contains("apps/web/**"));
+
+    Ok(())
+}
+
+#[test]
+fn test_what_changed_between_syncs() -> Result<()> {
+    let (source_repo, _local_repo_dir, local_path) =
+        setup_repos_for_pull(&["src/frontend/**", "README.md"])?;
+
+    source_repo.write_file("src/frontend/button.js", "// Button v2")?;
+    source_repo.write_file("src/frontend/menu.js", "// Menu v1")?;
+    source_repo.write_file("src/backend/server.js", "// Backend server v2")?;
+    source_repo.add_all()?;
+    source_repo.commit("Update the frontend and backend")?;
+    TestRepo::run_git_command(source_repo.path(), &["config", "user.name", "Other User"])?;
+    source_repo.write_file("README.md", "# Main Readme v2")?;
+    source_repo.add_all()?;
+    source_repo.commit("Update the readme")?;
+    run_gitpartial(&local_path, &["smart-pull"])?;
+
+    let points = run_gitpartial(&local_path, &["what-changed", "--list"])?;
+    assert!(points.contains("#1 "));
+    assert!(points.contains("#2 "));
+
+    let changes = run_gitpartial(&local_path, &["what-changed"])?;
+    assert!(changes.contains("\n/\n  Other User (1 commit)\n    M README.md\n"));
+    assert!(changes.contains(
+        "\nsrc/frontend/\n  Test User (1 commit)\n    M src/frontend/button.js\n    A src/frontend/menu.js\n"
+    ));
+    assert!(!changes.contains("server.js"));
+    assert_eq!(
+        run_gitpartial(&local_path, &["what-changed", "#1", "#2"])?,
+        changes
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_smart_pull_rebase_stops_at_conflicts() -> Result<()> {
+    let (source_repo, _local_repo_tempdir, local_repo_path) =
+        setup_repos_for_pull(&["src/frontend/**"])?;
+    source_repo.write_file("src/frontend/main.js", "// Frontend main upstream")?;
+    source_repo.add_all()?;
+    source_repo.commit("Upstream change")?;
+
+    std::fs::write(
+        local_repo_path.join("src/frontend/main.js"),
+        "// Frontend main local",
+    )?;
+    TestRepo::run_git_command(&local_repo_path, &["config", "user.name", "Test User"])?;
+    TestRepo::run_git_command(
+        &local_repo_path,
+        &["config", "user.email", "test@example.com"],
+    )?;
+    TestRepo::run_git_command(&local_repo_path, &["commit", "-am", "Local change"])?;
+    let local_head = rev_parse(&local_repo_path, "HEAD")?;
+
+    // Diverged history is refused without --rebase or --merge
+    assert!(run_gitpartial(&local_repo_path, &["smart-pull"]).is_err());
+
+    let output = run_gitpartial(&local_repo_path, &["smart-pull", "--rebase"])?;
+    assert!(output.contains("stopped at conflicts in 1 file(s):\n  src/frontend/main.js\n"));
+    assert!(output.contains("smart-pull --continue"));
+    // Another pull waits for this one
+    assert!(run_gitpartial(&local_repo_path, &["smart-pull"]).is_err());
+    // Unresolved files block --continue
+    assert!(run_gitpartial(&local_repo_path, &["smart-pull", "--continue"]).is_err());
+
+    run_gitpartial(&local_repo_path, &["smart-pull", "--abort"])?;
+    assert_eq!(rev_parse(&local_repo_path, "HEAD")?, local_head);
+    assert!(!local_repo_path.join(".gitpartial/pull.json").exists());
+
+    run_gitpartial(&local_repo_path, &["smart-pull", "--rebase"])?;
+    std::fs::write(
+        local_repo_path.join("src/frontend/main.js"),
+        "// Frontend main resolved",
+    )?;
+    TestRepo::run_git_command(&local_repo_path, &["add", "src/frontend/main.js"])?;
+    let output = run_gitpartial(&local_repo_path, &["smart-pull", "--continue"])?;
+    assert!(output.contains("Finished the rebase onto origin/main"));
+
+    let head = rev_parse(&local_repo_path, "HEAD")?;
+    assert_eq!(
+        rev_parse(&local_repo_path, "HEAD~1")?,
+        rev_parse(&local_repo_path, "origin/main")?
+    );
+    assert_eq!(
+        get_file_content(&local_repo_path, "src/frontend/main.js")?,
+        "// Frontend main resolved"
+    );
+    assert_eq!(
+        RepositoryMetadata::load(&local_repo_path)?.last_commit,
+        Some(head)
+    );
+    assert!(!local_repo_path.join(".gitpartial/pull.json").exists());
+    Ok(())
+}
@@ -75,7 +75,8 @@ fn test_status_up_to_date() -> Result<()> {
     // 3. Verification
     assert!(status_output.contains("Branch: main (Up-to-date)"));
     assert!(status_output.contains("Sparse checkout paths:"));
-    assert!(status_output.contains("  - README.md"));
+    // "# Main Readme v1" is the only file the path matches
+    assert!(status_output.contains("  - README.md  1 file(s), 16 B\n"));
     assert!(status_output.contains("Local changes:"));
     // Don't strictly assert "No changes", as fetch might cause status output
     // Just check the section exists.
@@ -96,9 +97,13 @@ fn test_status_behind() -> Result<()> {
     let status_output = run_gitpartial(&local_path, &["status"])?;
 
     // 3. Verification
-    assert!(status_output.contains(&"Branch: main (Behind remote".to_string()));
+    assert!(status_output.contains(&"Branch: main (Behind remote by 1 commit(s)".to_string()));
     // Check for partial commit hashes
     assert!(status_output.contains(&commit2[..7]));
+    assert!(status_output.contains(&format!(
+        "Incoming commits touching the sparse paths:\n  {} Update README\n",
+        &commit2[..7]
+    )));
     assert!(status_output.contains("Local changes:"));
     // Don't strictly assert "No changes" when behind, status might show branch divergence info
     // assert!(status_output.contains("  No changes"));
@@ -106,6 +111,67 @@ fn test_status_behind() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_status_settings_from_environment() -> Result<()> {
+    // 1. Setup: Clone repo, then update source
+    let initial_paths = ["README.md"];
+    let (source_repo, _local_repo_dir, local_path) = setup_repos_for_status(&initial_paths)?;
+    source_repo.write_file("README.md", "# Main Readme v2")?;
+    source_repo.add_all()?;
+    source_repo.commit("Update README")?;
+
+    // 2. Action: Run status with settings from the environment
+    let bin_path = PathBuf::from(env!("CARGO_BIN_EXE_git-partial"));
+    let run_with_env = |args: &[&str]| -> Result<String> {
+        let output = Command::new(&bin_path)
+            .args(args)
+            .current_dir(&local_path)
+            .env("GIT_PARTIAL_NO_FETCH_ON_STATUS", "1")
+            .env("GIT_PARTIAL_FORMAT", "json")
+            .output()?;
+        assert!(output.status.success(), "{:?}", output);
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    };
+    let json_output = run_with_env(&["status"])?;
+
+    // 3. Verification: nothing was fetched, and the report is JSON
+    let report: serde_json::Value = serde_json::from_str(&json_output)?;
+    assert_eq!(report["remote_status"], "Up-to-date");
+    assert_eq!(report["paths"], serde_json::json!(["README.md"]));
+
+    // A flag takes precedence over the environment
+    let text_output = run_with_env(&["status", "--format", "text"])?;
+    assert!(text_output.contains("Branch: main (Up-to-date)"));
+
+    Ok(())
+}
+
+#[test]
+fn test_status_check_exit_codes() -> Result<()> {
+    let initial_paths = ["README.md"];
+    let (source_repo, _local_repo_dir, local_path) = setup_repos_for_status(&initial_paths)?;
+    let bin_path = PathBuf::from(env!("CARGO_BIN_EXE_git-partial"));
+    let check_in = |dir: &Path| -> Result<Option<i32>> {
+        let output = Command::new(&bin_path)
+            .args(["status", "--check"])
+            .current_dir(dir)
+            .output()?;
+        Ok(output.status.code())
+    };
+
+    assert_eq!(check_in(&local_path)?, Some(0));
+
+    source_repo.write_file("README.md", "# Main Readme v2")?;
+    source_repo.add_all()?;
+    source_repo.commit("Update README")?;
+    assert_eq!(check_in(&local_path)?, Some(3));
+
+    let not_a_repo = tempfile::tempdir()?;
+    assert_eq!(check_in(not_a_repo.path())?, Some(5));
+
+    Ok(())
+}
+
 #[test]
 fn test_status_with_local_changes() -> Result<()> {
     // 1. Setup: Clone repo
@@ -145,3 +211,224 @@ fn test_status_non_partial_repo() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_status_flags_and_repairs_deletions_outside_sparse() -> Result<()> {
+    // 1. Setup: Clone repo with only README.md checked out
+    let initial_paths = ["README.md"];
+    let (_source_repo, _local_repo_dir, local_path) = setup_repos_for_status(&initial_paths)?;
+
+    // 2. A file outside the sparse paths loses its skip-worktree bit,
+    //    and a file inside them is really deleted
+    TestRepo::run_git_command(
+        &local_path,
+        &["update-index", "--no-skip-worktree", "src/lib.rs"],
+    )?;
+    std::fs::remove_file(local_path.join("README.md"))?;
+
+    let status_output = run_gitpartial(&local_path, &["status"])?;
+    assert!(status_output.contains(" D README.md"));
+    assert!(!status_output.contains(" D src/lib.rs"));
+    assert!(status_output
+        .contains("Deleted outside the sparse paths (not real deletions):\n  src/lib.rs"));
+
+    // 3. Action: Repair
+    let repair_output = run_gitpartial(&local_path, &["repair"])?;
+    assert!(repair_output.contains("Repaired src/lib.rs"));
+
+    // 4. Verification: only the real deletion remains
+    let git_status = TestRepo::run_git_command(&local_path, &["status", "--short"])?;
+    let git_status = String::from_utf8_lossy(&git_status.stdout);
+    assert!(git_status.contains(" D README.md"));
+    assert!(!git_status.contains("src/lib.rs"));
+    let status_output = run_gitpartial(&local_path, &["status"])?;
+    assert!(!status_output.contains("Deleted outside the sparse paths"));
+
+    Ok(())
+}
+
+#[test]
+fn test_repair_index_recomputes_skip_worktree() -> Result<()> {
+    let initial_paths = ["README.md"];
+    let (_source_repo, _local_repo_dir, local_path) = setup_repos_for_status(&initial_paths)?;
+    let git = |args: &[&str]| TestRepo::run_git_command(&local_path, args);
+
+    // 1. Another tool hid a selected file and exposed two others, one of
+    //    which was then edited
+    git(&["update-index", "--skip-worktree", "README.md"])?;
+    std::fs::remove_file(local_path.join("README.md"))?;
+    git(&[
+        "update-index",
+        "--no-skip-worktree",
+        "src/main.rs",
+        "src/lib.rs",
+    ])?;
+    git(&["checkout", "--", "src/main.rs", "src/lib.rs"])?;
+    std::fs::write(local_path.join("src/main.rs"), "// Main edited")?;
+
+    // 2. Repair
+    let output = run_gitpartial(&local_path, &["repair", "--index"])?;
+    assert!(output.contains("Checked out README.md (was hidden by skip-worktree)"));
+    assert!(output.contains("Marked src/lib.rs skip-worktree (outside the sparse paths)"));
+    assert!(output.contains("Kept src/main.rs: outside the sparse paths but changed locally"));
+
+    // 3. The selected file is back, the clean one is hidden, the edit survives
+    let listed = git(&["ls-files", "-t"])?;
+    let listed = String::from_utf8_lossy(&listed.stdout);
+    assert!(listed.contains("H README.md"), "{}", listed);
+    assert!(listed.contains("S src/lib.rs"), "{}", listed);
+    assert!(listed.contains("H src/main.rs"), "{}", listed);
+    assert!(local_path.join("README.md").exists());
+    assert!(!local_path.join("src/lib.rs").exists());
+    assert_eq!(
+        std::fs::read_to_string(local_path.join("src/main.rs"))?,
+        "// Main edited"
+    );
+
+    // 4. Once the edit is discarded, a second repair hides it too
+    git(&["checkout", "--", "src/main.rs"])?;
+    let output = run_gitpartial(&local_path, &["repair", "--index"])?;
+    assert_eq!(
+        output,
+        "Marked src/main.rs skip-worktree (outside the sparse paths)\n"
+    );
+    let output = run_gitpartial(&local_path, &["repair", "--index"])?;
+    assert_eq!(output, "The index matches the sparse paths.\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_status_counts_missing_objects() -> Result<()> {
+    // 1. Setup: a filtered clone, which only plain local paths skip
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Main Readme")?;
+    source_repo.write_file("src/lib.rs", "// Lib")?;
+    source_repo.write_file("docs/guide.md", "User guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    TestRepo::run_git_command(
+        source_repo.path(),
+        &["config", "uploadpack.allowFilter", "true"],
+    )?;
+    let source_repo_url = format!("file://{}", source_repo.path_str()?);
+    let clone_dir = tempfile::tempdir()?;
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo_url,
+            &clone_dir.path().to_string_lossy(),
+            "--paths",
+            "src/**",
+        ],
+    )?;
+
+    // 2. Verification: the commit, three trees, README.md (root files are
+    //    always checked out) and src/lib.rs are present; docs/guide.md was
+    //    never downloaded
+    let status_output = run_gitpartial(clone_dir.path(), &["status", "--no-fetch", "--objects"])?;
+    assert!(status_output
+        .contains("Objects in HEAD's history: 6 present, 1 missing (85% available offline)"));
+
+    // The default status does not walk the history
+    let status_output = run_gitpartial(clone_dir.path(), &["status", "--no-fetch"])?;
+    assert!(!status_output.contains("Objects in HEAD's history"));
+
+    Ok(())
+}
+
+#[test]
+fn test_status_detects_and_fixes_head_drift() -> Result<()> {
+    // 1. Setup: pull a new commit with plain git, bypassing git-partial
+    let initial_paths = ["README.md"];
+    let (source_repo, _local_repo_dir, local_path) = setup_repos_for_status(&initial_paths)?;
+    source_repo.write_file("README.md", "# Main Readme v2")?;
+    source_repo.add_all()?;
+    let commit2 = source_repo.commit("Update README")?;
+    TestRepo::run_git_command(&local_path, &["pull", "--quiet", "--ff-only"])?;
+
+    // 2. Verification: status reports the drift
+    let status_output = run_gitpartial(&local_path, &["status", "--no-fetch"])?;
+    assert!(status_output.contains(&format!("Warning: HEAD is at {}", commit2)));
+
+    // 3. Action: record HEAD
+    let fix_output = run_gitpartial(&local_path, &["status", "--no-fetch", "--fix"])?;
+    assert!(fix_output.contains(&format!(
+        "Recorded HEAD {} as the last synced commit.",
+        &commit2[..7]
+    )));
+    assert!(fix_output.contains("Branch: main (Up-to-date)"));
+    assert!(!fix_output.contains("Warning: HEAD is at"));
+
+    let metadata = std::fs::read_to_string(local_path.join(".gitpartial/metadata.json"))?;
+    assert!(metadata.contains(&commit2));
+
+    Ok(())
+}
+
+#[test]
+fn test_status_flags_stale_path_renamed_upstream() -> Result<()> {
+    // 1. Setup: clone src/**, then rename src to lib upstream and pull with git
+    let initial_paths = ["src/**"];
+    let (source_repo, _local_repo_dir, local_path) = setup_repos_for_status(&initial_paths)?;
+    TestRepo::run_git_command(source_repo.path(), &["mv", "src", "lib"])?;
+    source_repo.commit("Rename src to lib")?;
+    TestRepo::run_git_command(&local_path, &["pull", "--quiet", "--ff-only"])?;
+
+    // 2. Verification: the pattern is stale, and the new location is suggested
+    let status_output = run_gitpartial(&local_path, &["status"])?;
+    assert!(status_output
+        .contains("Stale paths (matching no file at HEAD):\n  - src/** (moved to lib/**?)"));
+
+    Ok(())
+}
+
+#[test]
+fn test_branches_lists_work_in_sparse_paths() -> Result<()> {
+    // 1. A source with one branch changing the sparse paths and one that does not
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/main.rs", "fn main() {}")?;
+    source_repo.write_file("docs/guide.md", "Guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let source = source_repo.path();
+    TestRepo::run_git_command(source, &["checkout", "-q", "-b", "feature/src"])?;
+    source_repo.write_file("src/lib.rs", "pub fn run() {}")?;
+    source_repo.add_all()?;
+    source_repo.commit("Add lib")?;
+    TestRepo::run_git_command(source, &["checkout", "-q", "-"])?;
+    TestRepo::run_git_command(source, &["checkout", "-q", "-b", "feature/docs"])?;
+    source_repo.write_file("docs/guide.md", "Better guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Improve guide")?;
+    TestRepo::run_git_command(source, &["checkout", "-q", "-"])?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ],
+    )?;
+
+    // 2. Only the branch touching src/ is listed
+    let output = run_gitpartial(&clone_path, &["branches", "--files"])?;
+    assert!(output.contains("origin/feature/src  1 commit(s), 1 file(s)  Test User"));
+    assert!(output.contains("      src/lib.rs\n"));
+    assert!(!output.contains("feature/docs"));
+
+    // 3. Against a base that already has the work, nothing is listed
+    let output = run_gitpartial(
+        &clone_path,
+        &["branches", "--no-fetch", "--base", "origin/feature/src"],
+    )?;
+    assert!(output.contains("(none)"));
+
+    Ok(())
+}
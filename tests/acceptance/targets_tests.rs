@@ -0,0 +1,53 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::{file_exists, TestRepo};
+use anyhow::Result;
+use std::path::PathBuf;
+
+#[test]
+fn test_add_paths_by_target_follows_build_files() -> Result<()> {
+    // Setup: a Bazel style repository where the server depends on a library
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Monorepo")?;
+    source_repo.write_file(
+        "services/api/BUILD.bazel",
+        "go_binary(\n    name = \"server\",\n    srcs = [\"main.go\"],\n    deps = [\"//libs/log\"],\n)\n",
+    )?;
+    source_repo.write_file("services/api/main.go", "package main")?;
+    source_repo.write_file(
+        "libs/log/BUILD",
+        "go_library(name = \"log\", srcs = [\"log.go\"])\n",
+    )?;
+    source_repo.write_file("libs/log/log.go", "package log")?;
+    source_repo.write_file("libs/unused/BUILD", "go_library(name = \"unused\")\n")?;
+    source_repo.write_file("libs/unused/unused.go", "package unused")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let source_repo_url = source_repo.path_str()?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    let clone_path_str = clone_path.to_string_lossy().to_string();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo_url,
+            &clone_path_str,
+            "--paths",
+            "README.md",
+        ],
+    )?;
+
+    // Action
+    run_gitpartial(
+        &clone_path,
+        &["add-paths", "--target", "//services/api:server"],
+    )?;
+
+    // Verification
+    assert!(file_exists(&clone_path, "services/api/main.go"));
+    assert!(file_exists(&clone_path, "libs/log/log.go"));
+    assert!(!file_exists(&clone_path, "libs/unused/unused.go"));
+
+    Ok(())
+}
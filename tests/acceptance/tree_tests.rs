@@ -0,0 +1,140 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::TestRepo;
+use anyhow::Result;
+use std::path::PathBuf;
+
+// Helper function to set up a partial clone with a nested layout
+fn setup_partial_repo() -> Result<(TestRepo, tempfile::TempDir, PathBuf)> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Main Readme")?;
+    source_repo.write_file("src/frontend/app.js", "// 12345678")?;
+    source_repo.write_file("src/backend/server.js", "// Server")?;
+    source_repo.write_file("docs/guide.md", "User guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let source_repo_url = source_repo.path_str()?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    let clone_path_str = clone_path.to_string_lossy().to_string();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo_url,
+            &clone_path_str,
+            "--paths",
+            "README.md",
+        ],
+    )?;
+
+    Ok((source_repo, clone_dir, clone_path))
+}
+
+#[test]
+fn test_tree_lists_directories_outside_checkout() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo()?;
+
+    let output = run_gitpartial(&clone_path, &["tree", "--depth", "1"])?;
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines, vec!["docs/", "src/"]);
+
+    let output = run_gitpartial(&clone_path, &["tree"])?;
+    assert!(output.contains("src/backend/"));
+    assert!(output.contains("src/frontend/"));
+
+    Ok(())
+}
+
+#[test]
+fn test_estimate_paths() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo()?;
+
+    let output = run_gitpartial(&clone_path, &["estimate", "--paths", "src/**"])?;
+
+    assert!(output.contains("Files: 2"));
+    assert!(output.contains("(20 bytes)"));
+
+    Ok(())
+}
+
+#[test]
+fn test_show_file_outside_checkout() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo()?;
+
+    let output = run_gitpartial(&clone_path, &["show", "HEAD:docs/guide.md"])?;
+    assert_eq!(output, "User guide");
+
+    let saved_path = clone_path.join("guide-copy.md");
+    run_gitpartial(
+        &clone_path,
+        &["show", "docs/guide.md", "-o", &saved_path.to_string_lossy()],
+    )?;
+    assert_eq!(std::fs::read_to_string(&saved_path)?, "User guide");
+
+    // The sparse checkout is unchanged
+    assert!(!clone_path.join("docs/guide.md").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_du_reports_remote_directory_sizes() -> Result<()> {
+    let (_source_repo, _clone_dir, clone_path) = setup_partial_repo()?;
+
+    let output = run_gitpartial(&clone_path, &["du", "src"])?;
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            "11 B  1 file(s)  src/frontend/",
+            " 9 B  1 file(s)  src/backend/",
+            "Total: 20 B in 2 file(s) under src",
+        ]
+    );
+
+    // The files are not checked out, and listing their sizes fetched none
+    assert!(!clone_path.join("src").exists());
+    let output = run_gitpartial(&clone_path, &["du"])?;
+    assert!(output.contains("Total: 43 B in 4 file(s) under the repository"));
+
+    let result = run_gitpartial(&clone_path, &["du", "missing"]);
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("Nothing found at 'missing'"));
+
+    Ok(())
+}
+
+#[test]
+fn test_tree_and_estimate_match_accented_paths() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Main Readme")?;
+    source_repo.write_file("docs/caf\u{e9}/menu.md", "Menu")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "README.md",
+        ],
+    )?;
+
+    // Git quotes non-ASCII paths in line-based output
+    let output = run_gitpartial(&clone_path, &["tree"])?;
+    assert!(output.contains("docs/caf\u{e9}/"), "output: {}", output);
+
+    let output = run_gitpartial(&clone_path, &["estimate", "--paths", "docs/caf\u{e9}/**"])?;
+    assert!(output.contains("Files: 1"), "output: {}", output);
+    assert!(output.contains("(4 bytes)"), "output: {}", output);
+
+    Ok(())
+}
@@ -0,0 +1,296 @@
+use crate::test_helpers::cli::{run_gitpartial, run_gitpartial_raw};
+use crate::test_helpers::test_repo::TestRepo;
+use anyhow::Result;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::Command;
+use std::thread;
+
+/// Accepts one HTTP request and returns its body
+fn receive_one_post(listener: TcpListener) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        String::from_utf8(body).unwrap()
+    })
+}
+
+#[test]
+fn test_watch_notifies_webhook_of_sparse_changes() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Main Readme")?;
+    source_repo.write_file("src/lib.rs", "// lib v1")?;
+    source_repo.write_file("docs/guide.md", "Guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ],
+    )?;
+
+    // Nothing new upstream: no notification
+    let output = run_gitpartial(&clone_path, &["watch", "--once"])?;
+    assert!(!output.contains("new commit"));
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}/hook", listener.local_addr()?);
+    TestRepo::run_git_command(&clone_path, &["config", "gitpartial.notify.webhook", &url])?;
+    let received = receive_one_post(listener);
+
+    source_repo.write_file("src/lib.rs", "// lib v2")?;
+    source_repo.write_file("docs/guide.md", "Guide v2")?;
+    source_repo.add_all()?;
+    source_repo.commit("Update lib and docs")?;
+
+    let output = Command::new(PathBuf::from(env!("CARGO_BIN_EXE_git-partial")))
+        .args(["watch", "--once"])
+        .current_dir(&clone_path)
+        .env("NO_PROXY", "127.0.0.1")
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("1 new commit(s) on origin/main touch the sparse paths"));
+
+    let body: serde_json::Value = serde_json::from_str(&received.join().unwrap())?;
+    assert_eq!(body["upstream"], "origin/main");
+    assert_eq!(body["commits"], 1);
+    assert_eq!(body["changes"][0]["pattern"], "src/**");
+    assert_eq!(body["changes"][0]["modified"], 1);
+    assert_eq!(body["outside"], 1);
+    Ok(())
+}
+
+#[test]
+fn test_watch_status_lists_schedules() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("README.md", "# Main Readme")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "README.md",
+        ],
+    )?;
+
+    let output = run_gitpartial(&clone_path, &["watch", "status"])?;
+    assert!(output.contains("No schedules configured"));
+
+    for entry in ["0 8 * * Mon-Fri prefetch", "0 9-18 * * Mon-Fri"] {
+        TestRepo::run_git_command(
+            &clone_path,
+            &["config", "--add", "gitpartial.watch.schedule", entry],
+        )?;
+    }
+    let output = run_gitpartial(&clone_path, &["watch", "status"])?;
+    let lines: Vec<&str> = output.lines().collect();
+    assert!(lines[0].starts_with("0 8 * * Mon-Fri     prefetch  next: "));
+    assert!(lines[1].starts_with("0 9-18 * * Mon-Fri  check     next: "));
+    assert!(lines[2].starts_with("Next run: "));
+
+    TestRepo::run_git_command(
+        &clone_path,
+        &["config", "--add", "gitpartial.watch.schedule", "0 8 * *"],
+    )?;
+    let output = run_gitpartial_raw(&clone_path, &["watch", "status"], "")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid schedule '0 8 * *'"));
+    Ok(())
+}
+
+#[test]
+fn test_watch_prefetch_downloads_incoming_files() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/lib.rs", "// lib v1")?;
+    source_repo.write_file("docs/guide.md", "Guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    // Allow blobless clones over file://, and fetching missing blobs later
+    for key in ["uploadpack.allowFilter", "uploadpack.allowAnySHA1InWant"] {
+        TestRepo::run_git_command(source_repo.path(), &["config", key, "true"])?;
+    }
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &format!("file://{}", source_repo.path_str()?),
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ],
+    )?;
+
+    source_repo.write_file("src/lib.rs", "// lib v2")?;
+    source_repo.write_file("docs/guide.md", "Guide v2")?;
+    source_repo.add_all()?;
+    source_repo.commit("Update lib and docs")?;
+
+    run_gitpartial(&clone_path, &["watch", "--once", "--prefetch"])?;
+
+    // The blob in the sparse paths is local now; the one outside is not
+    let output = TestRepo::run_git_command(
+        &clone_path,
+        &["rev-list", "--objects", "--missing=print", "origin/main"],
+    )?;
+    let missing = String::from_utf8(output.stdout)?;
+    let missing_blob = |spec: &str| -> Result<bool> {
+        let output = TestRepo::run_git_command(source_repo.path(), &["rev-parse", spec])?;
+        let oid = String::from_utf8(output.stdout)?.trim().to_string();
+        Ok(missing.lines().any(|line| line == format!("?{}", oid)))
+    };
+    assert!(!missing_blob("HEAD:src/lib.rs")?);
+    assert!(missing_blob("HEAD:docs/guide.md")?);
+    Ok(())
+}
+
+/// Answers one SMTP session and returns the message received after DATA
+fn receive_one_email(listener: TcpListener) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        stream.write_all(b"220 test\r\n").unwrap();
+        let mut message = String::new();
+        let mut in_data = false;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap() == 0 {
+                break;
+            }
+            if in_data {
+                if line == ".\r\n" {
+                    in_data = false;
+                    stream.write_all(b"250 queued\r\n").unwrap();
+                } else {
+                    message.push_str(&line);
+                }
+                continue;
+            }
+            let reply: &[u8] = match &line[..4] {
+                "DATA" => {
+                    in_data = true;
+                    b"354 go ahead\r\n"
+                }
+                "QUIT" => {
+                    stream.write_all(b"221 bye\r\n").unwrap();
+                    break;
+                }
+                _ => b"250 ok\r\n",
+            };
+            stream.write_all(reply).unwrap();
+        }
+        message
+    })
+}
+
+#[test]
+fn test_watch_digest_sends_to_slack_and_email() -> Result<()> {
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("src/lib.rs", "// lib v1")?;
+    source_repo.write_file("docs/guide.md", "Guide")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().to_path_buf();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo.path_str()?,
+            &clone_path.to_string_lossy(),
+            "--paths",
+            "src/**",
+        ],
+    )?;
+
+    // Without a target the digest is refused
+    let output = run_gitpartial_raw(&clone_path, &["watch", "--once", "--digest"], "")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No digest target configured"));
+
+    let slack = TcpListener::bind("127.0.0.1:0")?;
+    let smtp = TcpListener::bind("127.0.0.1:0")?;
+    let config = [
+        (
+            "gitpartial.digest.slack",
+            format!("http://{}/hook", slack.local_addr()?),
+        ),
+        ("gitpartial.digest.smtp", smtp.local_addr()?.to_string()),
+        ("gitpartial.digest.to", "team@example.com".to_string()),
+    ];
+    for (key, value) in &config {
+        TestRepo::run_git_command(&clone_path, &["config", key, value])?;
+    }
+    let slack_body = receive_one_post(slack);
+    let email = receive_one_email(smtp);
+
+    source_repo.write_file("src/lib.rs", "// lib v2")?;
+    source_repo.add_all()?;
+    source_repo.commit("Rework the lib")?;
+    source_repo.write_file("docs/guide.md", "Guide v2")?;
+    source_repo.add_all()?;
+    source_repo.commit("Update the docs")?;
+
+    let run_digest = || {
+        Command::new(PathBuf::from(env!("CARGO_BIN_EXE_git-partial")))
+            .args(["watch", "--once", "--digest"])
+            .current_dir(&clone_path)
+            .env("NO_PROXY", "127.0.0.1")
+            .output()
+    };
+    let output = run_digest()?;
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("git-partial digest: 1 commit(s) on origin/main touch the sparse paths")
+    );
+
+    let body: serde_json::Value = serde_json::from_str(&slack_body.join().unwrap())?;
+    let text = body["text"].as_str().unwrap();
+    assert!(text.contains("Rework the lib (Test User)"));
+    assert!(!text.contains("Update the docs"));
+    let email = email.join().unwrap();
+    assert!(email.contains("To: team@example.com\r\n"));
+    assert!(email.contains("Subject: git-partial digest: 1 commit(s)"));
+    assert!(email.contains("src/**  0 added, 1 modified, 0 deleted"));
+
+    // Nothing new since: no delivery is attempted, though nobody listens
+    let output = run_digest()?;
+    assert!(output.status.success(), "{:?}", output);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("git-partial digest"));
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use crate::test_helpers::cli::run_gitpartial;
+use crate::test_helpers::test_repo::{file_exists, TestRepo};
+use anyhow::Result;
+use git_partial::core::metadata::RepositoryMetadata;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+#[test]
+fn test_worktree_add_with_own_paths() -> Result<()> {
+    // Setup: a source repository with a second branch
+    let source_repo = TestRepo::new()?;
+    source_repo.write_file("frontend/app.js", "// App")?;
+    source_repo.write_file("backend/server.js", "// Server")?;
+    source_repo.add_all()?;
+    source_repo.commit("Initial commit")?;
+    TestRepo::run_git_command(source_repo.path(), &["branch", "feature"])?;
+    let source_repo_url = source_repo.path_str()?;
+
+    let clone_dir = tempfile::tempdir()?;
+    let clone_path = clone_dir.path().join("main");
+    let clone_path_str = clone_path.to_string_lossy().to_string();
+    run_gitpartial(
+        &PathBuf::from("."),
+        &[
+            "clone",
+            &source_repo_url,
+            &clone_path_str,
+            "--paths",
+            "frontend/**",
+        ],
+    )?;
+
+    // Action
+    run_gitpartial(
+        &clone_path,
+        &[
+            "worktree",
+            "add",
+            "../feature",
+            "feature",
+            "--paths",
+            "backend/**",
+        ],
+    )?;
+
+    // Verification: each worktree keeps its own sparse set
+    let worktree_path = clone_dir.path().join("feature");
+    assert!(file_exists(&worktree_path, "backend/server.js"));
+    assert!(!file_exists(&worktree_path, "frontend/app.js"));
+    assert!(file_exists(&clone_path, "frontend/app.js"));
+    assert!(!file_exists(&clone_path, "backend/server.js"));
+
+    let metadata = RepositoryMetadata::load(&worktree_path)?;
+    let expected: HashSet<String> = HashSet::from(["backend/**".to_string()]);
+    assert_eq!(metadata.checked_out_paths, expected);
+    assert_eq!(metadata.remote_url, source_repo_url);
+
+    // The main clone's metadata is unchanged
+    let main_metadata = RepositoryMetadata::load(&clone_path)?;
+    assert!(main_metadata.checked_out_paths.contains("frontend/**"));
+
+    Ok(())
+}
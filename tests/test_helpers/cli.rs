@@ -0,0 +1,73 @@
+use anyhow::{anyhow, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+/// Runs the git-partial binary in a specific directory and returns its stdout.
+/// Fails if the command exits with a non-zero status.
+pub fn run_gitpartial(
+    cwd: &Path,
+    args: &[&str],
+) -> Result<String> {
+    run_gitpartial_with_stdin(cwd, args, "")
+}
+
+/// Runs the git-partial binary with the given text piped to stdin.
+pub fn run_gitpartial_with_stdin(
+    cwd: &Path,
+    args: &[&str],
+    stdin: &str,
+) -> Result<String> {
+    let output = run_gitpartial_raw(cwd, args, stdin)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        return Err(anyhow!(
+            "Command failed in {}:
+Args: {:?}
+Exit Code: {:?}
+Stderr: {}
+Stdout: {}",
+            cwd.display(),
+            args,
+            output.status.code(),
+            stderr,
+            stdout
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Global config directory of the runs, so the clones tests make are not
+/// registered in the developer's own config
+pub fn config_home() -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("config-home")
+}
+
+/// Runs the git-partial binary and returns the raw process output,
+/// regardless of the exit status.
+pub fn run_gitpartial_raw(
+    cwd: &Path,
+    args: &[&str],
+    stdin: &str,
+) -> Result<Output> {
+    let bin_path = PathBuf::from(env!("CARGO_BIN_EXE_git-partial"));
+
+    let mut child = Command::new(bin_path)
+        .args(args)
+        .current_dir(cwd)
+        .env("XDG_CONFIG_HOME", config_home())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Dropping the handle closes stdin so prompts see EOF after the input
+    if let Some(mut child_stdin) = child.stdin.take() {
+        child_stdin.write_all(stdin.as_bytes())?;
+    }
+
+    Ok(child.wait_with_output()?)
+}